@@ -0,0 +1,31 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{io, process};
+
+use clarity::vm::tooling::lsp::LanguageServer;
+
+fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut server = LanguageServer::default();
+    match server.run(&mut stdin.lock(), &mut stdout.lock()) {
+        Ok(exit_code) => process::exit(exit_code),
+        Err(e) => {
+            eprintln!("clarity-lsp: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,1493 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Typed client bindings, generated from a `ContractInterface`.
+//!
+//! The generated Rust module converts between Rust structs and Clarity `Value`s, builds
+//! contract-call `TransactionPayload`s for public functions, builds and decodes
+//! `/v2/contracts/call-read` requests for read-only functions, and decodes the contract's
+//! `print` events.  The generated TypeScript module does the same on top of
+//! `@stacks/transactions` (v7).
+//!
+//! A `print` event can only be decoded if the type checker inferred a tuple type for the printed
+//! value, so `infer_print_events` needs an analysis that was run with a type map.  Events whose
+//! tuple literal has a string-literal `event`, `topic`, `type` or `action` field are told apart by
+//! that field; other events are matched by their tuple type alone.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::vm::analysis::contract_interface_builder::{
+    ContractInterface, ContractInterfaceAtomType, ContractInterfaceFunction,
+    ContractInterfaceFunctionAccess, ContractInterfaceTupleEntryType,
+};
+use crate::vm::analysis::types::ContractAnalysis;
+use crate::vm::representations::SymbolicExpressionType;
+use crate::vm::types::{CharType, QualifiedContractIdentifier, SequenceData, TypeSignature, Value};
+use crate::vm::SymbolicExpression;
+
+/// Tuple fields that can tell a contract's `print` events apart, in order of preference
+const PRINT_EVENT_TAGS: &[&str] = &["event", "topic", "type", "action"];
+
+/// A `print` event of a contract, whose tuple type was inferred by the type checker
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintEventType {
+    pub name: String,
+    /// A field of the printed tuple which always holds the given string literal
+    pub tag: Option<(String, String)>,
+    pub fields: Vec<ContractInterfaceTupleEntryType>,
+}
+
+/// Find the `print` events of a contract.  Returns nothing if the analysis has no type map.
+pub fn infer_print_events(contract_analysis: &ContractAnalysis) -> Vec<PrintEventType> {
+    let Some(type_map) = contract_analysis.type_map.as_ref() else {
+        return vec![];
+    };
+    let mut events: Vec<PrintEventType> = vec![];
+    let mut printed = vec![];
+    for expr in contract_analysis.expressions.iter() {
+        find_printed_exprs(expr, &mut printed);
+    }
+    for printed_expr in printed {
+        let Some(TypeSignature::TupleType(tuple_type)) = type_map.get_type_expected(printed_expr)
+        else {
+            continue;
+        };
+        let fields = ContractInterfaceAtomType::vec_from_tuple_type(tuple_type);
+        let tag = find_event_tag(printed_expr);
+        if events
+            .iter()
+            .any(|event| event.tag == tag && event.fields == fields)
+        {
+            continue;
+        }
+        let base_name = match tag {
+            Some((_, ref value)) => value.clone(),
+            None => "event".to_string(),
+        };
+        let mut name = base_name.clone();
+        let mut suffix = 1;
+        while events.iter().any(|event| event.name == name) {
+            suffix += 1;
+            name = format!("{base_name}-{suffix}");
+        }
+        events.push(PrintEventType { name, tag, fields });
+    }
+    events
+}
+
+fn find_printed_exprs<'a>(expr: &'a SymbolicExpression, printed: &mut Vec<&'a SymbolicExpression>) {
+    let Some(list) = expr.match_list() else {
+        return;
+    };
+    if let [function, argument] = list {
+        if function.match_atom().map(|name| name.as_str()) == Some("print") {
+            printed.push(argument);
+        }
+    }
+    for child in list.iter() {
+        find_printed_exprs(child, printed);
+    }
+}
+
+/// If `expr` is a tuple literal with a string-literal tag field, get that field and its value
+fn find_event_tag(expr: &SymbolicExpression) -> Option<(String, String)> {
+    let list = expr.match_list()?;
+    let (function, pairs) = list.split_first()?;
+    if function.match_atom()?.as_str() != "tuple" {
+        return None;
+    }
+    let literals: Vec<(String, String)> = pairs
+        .iter()
+        .filter_map(|pair| match pair.match_list()? {
+            [key, value] => {
+                let key = key.match_atom()?.to_string();
+                let SymbolicExpressionType::LiteralValue(Value::Sequence(SequenceData::String(
+                    CharType::ASCII(ref data),
+                ))) = value.expr
+                else {
+                    return None;
+                };
+                Some((key, String::from_utf8(data.data.clone()).ok()?))
+            }
+            _ => None,
+        })
+        .collect();
+    PRINT_EVENT_TAGS
+        .iter()
+        .find_map(|tag| literals.iter().find(|(key, _)| key == tag).cloned())
+}
+
+/// Split a Clarity name into its alphanumeric words
+fn name_words(name: &str) -> Vec<String> {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect()
+}
+
+fn upper_camel_case(name: &str) -> String {
+    let mut out: String = name_words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    if !out.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        out.insert(0, 'T');
+    }
+    out
+}
+
+const TYPESCRIPT_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+];
+
+fn lower_camel_case(name: &str) -> String {
+    let mut out = upper_camel_case(name);
+    let first = out.remove(0).to_ascii_lowercase();
+    out.insert(0, first);
+    if TYPESCRIPT_KEYWORDS.contains(&out.as_str()) {
+        out.push('_');
+    }
+    out
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try",
+    "type", "unsafe", "use", "where", "while", "yield",
+];
+
+fn snake_case(name: &str) -> String {
+    let mut out = name_words(name).join("_");
+    if !out.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        out.insert(0, 'x');
+    }
+    if RUST_KEYWORDS.contains(&out.as_str()) {
+        out.push('_');
+    }
+    out
+}
+
+/// Assigns distinct identifiers to distinct Clarity names
+#[derive(Default)]
+struct IdentAllocator {
+    used: HashSet<String>,
+}
+
+impl IdentAllocator {
+    fn allocate(&mut self, ident: String) -> String {
+        let mut candidate = ident.clone();
+        let mut suffix = 1;
+        while !self.used.insert(candidate.clone()) {
+            suffix += 1;
+            candidate = format!("{ident}{suffix}");
+        }
+        candidate
+    }
+}
+
+struct NamedTuple {
+    name: String,
+    fields: Vec<ContractInterfaceTupleEntryType>,
+}
+
+struct BindingsFunction<'a> {
+    function: &'a ContractInterfaceFunction,
+    /// The function's name, as a Rust or TypeScript identifier
+    ident: String,
+    /// The function's arguments, as Rust or TypeScript identifiers
+    arg_idents: Vec<String>,
+}
+
+struct BindingsEvent<'a> {
+    event: &'a PrintEventType,
+    /// The name of the event's tuple type and of its variant in the event enum/union
+    type_name: String,
+    variant: String,
+}
+
+/// Everything that the Rust and TypeScript generators share: a name for every tuple type that
+/// appears in the bindings, and identifiers for the functions and events.
+struct BindingsModel<'a> {
+    contract_identifier: &'a QualifiedContractIdentifier,
+    tuples: Vec<NamedTuple>,
+    type_names: IdentAllocator,
+    public_functions: Vec<BindingsFunction<'a>>,
+    read_only_functions: Vec<BindingsFunction<'a>>,
+    events: Vec<BindingsEvent<'a>>,
+}
+
+impl<'a> BindingsModel<'a> {
+    fn new(
+        contract_identifier: &'a QualifiedContractIdentifier,
+        interface: &'a ContractInterface,
+        print_events: &'a [PrintEventType],
+        ident_case: fn(&str) -> String,
+    ) -> BindingsModel<'a> {
+        let mut model = BindingsModel {
+            contract_identifier,
+            tuples: vec![],
+            type_names: IdentAllocator::default(),
+            public_functions: vec![],
+            read_only_functions: vec![],
+            events: vec![],
+        };
+        let mut function_idents = IdentAllocator::default();
+        for function in interface.functions.iter() {
+            let functions = match function.access {
+                ContractInterfaceFunctionAccess::public => &mut model.public_functions,
+                ContractInterfaceFunctionAccess::read_only => &mut model.read_only_functions,
+                ContractInterfaceFunctionAccess::private => continue,
+            };
+            let mut arg_idents = IdentAllocator::default();
+            functions.push(BindingsFunction {
+                function,
+                ident: function_idents.allocate(ident_case(&function.name)),
+                arg_idents: function
+                    .args
+                    .iter()
+                    .map(|arg| arg_idents.allocate(ident_case(&arg.name)))
+                    .collect(),
+            });
+        }
+        for function in interface.functions.iter() {
+            if function.access == ContractInterfaceFunctionAccess::private {
+                continue;
+            }
+            for arg in function.args.iter() {
+                model.register_tuples(&arg.type_f, &format!("{}-{}", function.name, arg.name));
+            }
+            model.register_tuples(
+                &function.outputs.type_f,
+                &format!("{}-output", function.name),
+            );
+        }
+        let mut variants = IdentAllocator::default();
+        for event in print_events.iter() {
+            let hint = format!("{}-event", event.name);
+            let type_name = model.register_tuple(&event.fields, &hint);
+            model.events.push(BindingsEvent {
+                event,
+                type_name,
+                variant: variants.allocate(upper_camel_case(&event.name)),
+            });
+        }
+        model
+    }
+
+    /// Name every tuple type within `type_f`, using `hint` to name the outermost one
+    fn register_tuples(&mut self, type_f: &ContractInterfaceAtomType, hint: &str) {
+        match type_f {
+            ContractInterfaceAtomType::tuple(fields) => {
+                self.register_tuple(fields, hint);
+            }
+            ContractInterfaceAtomType::optional(inner) => self.register_tuples(inner, hint),
+            ContractInterfaceAtomType::list { type_f, .. } => {
+                self.register_tuples(type_f, &format!("{hint}-item"))
+            }
+            ContractInterfaceAtomType::response { ok, error } => {
+                self.register_tuples(ok, hint);
+                self.register_tuples(error, &format!("{hint}-error"));
+            }
+            _ => {}
+        }
+    }
+
+    fn register_tuple(&mut self, fields: &[ContractInterfaceTupleEntryType], hint: &str) -> String {
+        if let Some(tuple) = self.tuples.iter().find(|tuple| tuple.fields == fields) {
+            return tuple.name.clone();
+        }
+        for field in fields.iter() {
+            self.register_tuples(&field.type_f, &format!("{hint}-{}", field.name));
+        }
+        let name = self.type_names.allocate(upper_camel_case(hint));
+        self.tuples.push(NamedTuple {
+            name: name.clone(),
+            fields: fields.to_vec(),
+        });
+        name
+    }
+
+    fn tuple_name(&self, fields: &[ContractInterfaceTupleEntryType]) -> &str {
+        self.tuples
+            .iter()
+            .find(|tuple| tuple.fields == fields)
+            .map(|tuple| tuple.name.as_str())
+            // all tuples are registered before any code is generated
+            .unwrap_or("UnknownTuple")
+    }
+}
+
+/// Generate a Rust module of client bindings for a contract
+pub fn generate_rust_bindings(
+    contract_identifier: &QualifiedContractIdentifier,
+    interface: &ContractInterface,
+    print_events: &[PrintEventType],
+) -> String {
+    RustBindings {
+        model: BindingsModel::new(contract_identifier, interface, print_events, snake_case),
+    }
+    .generate()
+}
+
+/// Generate a TypeScript module of client bindings for a contract
+pub fn generate_typescript_bindings(
+    contract_identifier: &QualifiedContractIdentifier,
+    interface: &ContractInterface,
+    print_events: &[PrintEventType],
+) -> String {
+    TypeScriptBindings {
+        model: BindingsModel::new(
+            contract_identifier,
+            interface,
+            print_events,
+            lower_camel_case,
+        ),
+    }
+    .generate()
+}
+
+const RUST_PRELUDE: &str = r#"#![allow(
+    dead_code,
+    clippy::needless_question_mark,
+    clippy::result_large_err,
+    clippy::too_many_arguments
+)]
+
+use std::fmt;
+
+use blockstack_lib::chainstate::stacks::{TransactionContractCall, TransactionPayload};
+use blockstack_lib::net::api::callreadonly::{CallReadOnlyRequestBody, CallReadOnlyResponse};
+use clarity::vm::types::{
+    ASCIIData, BuffData, CharType, ListData, OptionalData, PrincipalData,
+    QualifiedContractIdentifier, ResponseData, SequenceData, TupleData, UTF8Data, Value,
+};
+use clarity::vm::ClarityName;
+use stacks_common::types::chainstate::StacksAddress;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindingError {
+    /// A value does not fit its Clarity type
+    Encode(String),
+    /// A Clarity value does not have the expected type
+    Decode(String),
+    /// The node could not evaluate a read-only call
+    Call(String),
+}
+
+impl fmt::Display for BindingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BindingError::Encode(msg) => write!(f, "failed to encode value: {msg}"),
+            BindingError::Decode(msg) => write!(f, "failed to decode value: {msg}"),
+            BindingError::Call(msg) => write!(f, "read-only call failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for BindingError {}
+
+fn unexpected(expected: &str, value: &Value) -> BindingError {
+    BindingError::Decode(format!("expected {expected}, got {value}"))
+}
+
+fn encode_none(_value: ()) -> Value {
+    Value::none()
+}
+
+fn encode_buff(data: Vec<u8>, max_len: usize) -> Result<Value, BindingError> {
+    if data.len() > max_len {
+        return Err(BindingError::Encode(format!(
+            "buffer of length {} exceeds {max_len}",
+            data.len()
+        )));
+    }
+    Value::buff_from(data).map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn encode_ascii(data: String, max_len: usize) -> Result<Value, BindingError> {
+    if data.len() > max_len {
+        return Err(BindingError::Encode(format!(
+            "string of length {} exceeds {max_len}",
+            data.len()
+        )));
+    }
+    Value::string_ascii_from_bytes(data.into_bytes()).map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn encode_utf8(data: String, max_len: usize) -> Result<Value, BindingError> {
+    if data.chars().count() > max_len {
+        return Err(BindingError::Encode(format!(
+            "string of length {} exceeds {max_len}",
+            data.chars().count()
+        )));
+    }
+    Value::string_utf8_from_bytes(data.into_bytes()).map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn encode_optional(data: Option<Value>) -> Result<Value, BindingError> {
+    match data {
+        Some(value) => Value::some(value).map_err(|e| BindingError::Encode(e.to_string())),
+        None => Ok(Value::none()),
+    }
+}
+
+fn encode_response(data: Result<Value, Value>) -> Result<Value, BindingError> {
+    match data {
+        Ok(value) => Value::okay(value),
+        Err(value) => Value::error(value),
+    }
+    .map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn encode_list(items: Vec<Value>, max_len: usize) -> Result<Value, BindingError> {
+    if items.len() > max_len {
+        return Err(BindingError::Encode(format!(
+            "list of length {} exceeds {max_len}",
+            items.len()
+        )));
+    }
+    Value::cons_list_unsanitized(items).map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn encode_tuple(fields: Vec<(&str, Value)>) -> Result<Value, BindingError> {
+    let fields = fields
+        .into_iter()
+        .map(|(name, value)| {
+            ClarityName::try_from(name.to_string())
+                .map(|name| (name, value))
+                .map_err(|e| BindingError::Encode(e.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    TupleData::from_data(fields)
+        .map(Value::Tuple)
+        .map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn decode_none(value: Value) -> Result<(), BindingError> {
+    match value {
+        Value::Optional(OptionalData { data: None }) => Ok(()),
+        value => Err(unexpected("none", &value)),
+    }
+}
+
+fn decode_int(value: Value) -> Result<i128, BindingError> {
+    match value {
+        Value::Int(x) => Ok(x),
+        value => Err(unexpected("int", &value)),
+    }
+}
+
+fn decode_uint(value: Value) -> Result<u128, BindingError> {
+    match value {
+        Value::UInt(x) => Ok(x),
+        value => Err(unexpected("uint", &value)),
+    }
+}
+
+fn decode_bool(value: Value) -> Result<bool, BindingError> {
+    match value {
+        Value::Bool(x) => Ok(x),
+        value => Err(unexpected("bool", &value)),
+    }
+}
+
+fn decode_principal(value: Value) -> Result<PrincipalData, BindingError> {
+    match value {
+        Value::Principal(x) => Ok(x),
+        Value::CallableContract(x) => Ok(PrincipalData::Contract(x.contract_identifier)),
+        value => Err(unexpected("principal", &value)),
+    }
+}
+
+fn decode_contract(value: Value) -> Result<QualifiedContractIdentifier, BindingError> {
+    match value {
+        Value::Principal(PrincipalData::Contract(x)) => Ok(x),
+        Value::CallableContract(x) => Ok(x.contract_identifier),
+        value => Err(unexpected("contract principal", &value)),
+    }
+}
+
+fn decode_buff(value: Value, max_len: usize) -> Result<Vec<u8>, BindingError> {
+    match value {
+        Value::Sequence(SequenceData::Buffer(BuffData { data })) if data.len() <= max_len => {
+            Ok(data)
+        }
+        value => Err(unexpected(&format!("(buff {max_len})"), &value)),
+    }
+}
+
+fn decode_ascii(value: Value, max_len: usize) -> Result<String, BindingError> {
+    match value {
+        Value::Sequence(SequenceData::String(CharType::ASCII(ASCIIData { data })))
+            if data.len() <= max_len =>
+        {
+            String::from_utf8(data).map_err(|e| BindingError::Decode(e.to_string()))
+        }
+        value => Err(unexpected(&format!("(string-ascii {max_len})"), &value)),
+    }
+}
+
+fn decode_utf8(value: Value, max_len: usize) -> Result<String, BindingError> {
+    match value {
+        Value::Sequence(SequenceData::String(CharType::UTF8(UTF8Data { data })))
+            if data.len() <= max_len =>
+        {
+            String::from_utf8(data.concat()).map_err(|e| BindingError::Decode(e.to_string()))
+        }
+        value => Err(unexpected(&format!("(string-utf8 {max_len})"), &value)),
+    }
+}
+
+fn decode_optional(value: Value) -> Result<Option<Value>, BindingError> {
+    match value {
+        Value::Optional(OptionalData { data }) => Ok(data.map(|x| *x)),
+        value => Err(unexpected("optional", &value)),
+    }
+}
+
+fn decode_response(value: Value) -> Result<Result<Value, Value>, BindingError> {
+    match value {
+        Value::Response(ResponseData { committed: true, data }) => Ok(Ok(*data)),
+        Value::Response(ResponseData { committed: false, data }) => Ok(Err(*data)),
+        value => Err(unexpected("response", &value)),
+    }
+}
+
+fn decode_list(value: Value, max_len: usize) -> Result<Vec<Value>, BindingError> {
+    match value {
+        Value::Sequence(SequenceData::List(ListData { data, .. })) if data.len() <= max_len => {
+            Ok(data)
+        }
+        value => Err(unexpected(&format!("list of at most {max_len} items"), &value)),
+    }
+}
+
+fn decode_tuple(value: Value, num_fields: usize) -> Result<TupleData, BindingError> {
+    match value {
+        Value::Tuple(tuple) if tuple.data_map.len() == num_fields => Ok(tuple),
+        value => Err(unexpected(&format!("tuple of {num_fields} fields"), &value)),
+    }
+}
+
+fn take_field(tuple: &mut TupleData, name: &str) -> Result<Value, BindingError> {
+    tuple
+        .data_map
+        .remove(name)
+        .ok_or_else(|| BindingError::Decode(format!("missing tuple field `{name}`")))
+}
+
+fn has_tag(tuple: &TupleData, field: &str, tag: &str) -> bool {
+    match tuple.data_map.get(field) {
+        Some(Value::Sequence(SequenceData::String(CharType::ASCII(ASCIIData { data })))) => {
+            data.as_slice() == tag.as_bytes()
+        }
+        _ => false,
+    }
+}
+
+fn contract_call(
+    function_name: &str,
+    function_args: Vec<Value>,
+) -> Result<TransactionPayload, BindingError> {
+    let contract_id = contract_id();
+    Ok(TransactionPayload::ContractCall(TransactionContractCall {
+        address: StacksAddress::from(contract_id.issuer),
+        contract_name: contract_id.name,
+        function_name: ClarityName::try_from(function_name.to_string())
+            .map_err(|e| BindingError::Encode(e.to_string()))?,
+        function_args,
+    }))
+}
+
+fn read_only_request(
+    sender: &PrincipalData,
+    arguments: Vec<Value>,
+) -> Result<CallReadOnlyRequestBody, BindingError> {
+    Ok(CallReadOnlyRequestBody {
+        sender: sender.to_string(),
+        sponsor: None,
+        arguments: arguments
+            .iter()
+            .map(|value| value.serialize_to_hex())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| BindingError::Encode(format!("{e:?}")))?,
+    })
+}
+
+fn read_only_path(function_name: &str) -> String {
+    let contract_id = contract_id();
+    format!(
+        "/v2/contracts/call-read/{}/{}/{function_name}",
+        contract_id.issuer, contract_id.name
+    )
+}
+
+fn read_only_result(response: &CallReadOnlyResponse) -> Result<Value, BindingError> {
+    if !response.okay {
+        return Err(BindingError::Call(
+            response.cause.clone().unwrap_or_default(),
+        ));
+    }
+    let result = response
+        .result
+        .as_deref()
+        .ok_or_else(|| BindingError::Call("no result".into()))?;
+    Value::try_deserialize_hex_untyped(result).map_err(|e| BindingError::Decode(e.to_string()))
+}
+"#;
+
+struct RustBindings<'a> {
+    model: BindingsModel<'a>,
+}
+
+impl RustBindings<'_> {
+    fn rust_type(&self, type_f: &ContractInterfaceAtomType) -> String {
+        use ContractInterfaceAtomType::*;
+        match type_f {
+            none => "()".into(),
+            int128 => "i128".into(),
+            uint128 => "u128".into(),
+            bool => "bool".into(),
+            principal => "PrincipalData".into(),
+            trait_reference => "QualifiedContractIdentifier".into(),
+            buffer { .. } => "Vec<u8>".into(),
+            string_ascii { .. } | string_utf8 { .. } => "String".into(),
+            tuple(fields) => self.model.tuple_name(fields).into(),
+            optional(inner) => format!("Option<{}>", self.rust_type(inner)),
+            response { ok, error } => {
+                format!("Result<{}, {}>", self.rust_type(ok), self.rust_type(error))
+            }
+            list { type_f, .. } => format!("Vec<{}>", self.rust_type(type_f)),
+        }
+    }
+
+    /// A Rust expression which encodes `expr`, of type `type_f`, as a `Value`.  It may use `?`.
+    fn encode(&self, type_f: &ContractInterfaceAtomType, expr: &str, depth: usize) -> String {
+        use ContractInterfaceAtomType::*;
+        let x = format!("x{depth}");
+        match type_f {
+            none => format!("encode_none({expr})"),
+            int128 => format!("Value::Int({expr})"),
+            uint128 => format!("Value::UInt({expr})"),
+            bool => format!("Value::Bool({expr})"),
+            principal => format!("Value::Principal({expr})"),
+            trait_reference => format!("Value::Principal(PrincipalData::Contract({expr}))"),
+            buffer { length } => format!("encode_buff({expr}, {length})?"),
+            string_ascii { length } => format!("encode_ascii({expr}, {length})?"),
+            string_utf8 { length } => format!("encode_utf8({expr}, {length})?"),
+            tuple(_) => format!("{expr}.to_value()?"),
+            optional(inner) => format!(
+                "encode_optional({expr}.map(|{x}| -> Result<Value, BindingError> {{ Ok({}) }}).transpose()?)?",
+                self.encode(inner, &x, depth + 1)
+            ),
+            response { ok, error } => format!(
+                "encode_response(match {expr} {{ Ok({x}) => Ok({}), Err({x}) => Err({}) }})?",
+                self.encode(ok, &x, depth + 1),
+                self.encode(error, &x, depth + 1)
+            ),
+            list { type_f, length } => format!(
+                "encode_list({expr}.into_iter().map(|{x}| -> Result<Value, BindingError> {{ Ok({}) }}).collect::<Result<Vec<_>, _>>()?, {length})?",
+                self.encode(type_f, &x, depth + 1)
+            ),
+        }
+    }
+
+    /// A Rust expression which decodes the `Value` `expr` as `type_f`.  It may use `?`.
+    fn decode(&self, type_f: &ContractInterfaceAtomType, expr: &str, depth: usize) -> String {
+        use ContractInterfaceAtomType::*;
+        let x = format!("x{depth}");
+        match type_f {
+            none => format!("decode_none({expr})?"),
+            int128 => format!("decode_int({expr})?"),
+            uint128 => format!("decode_uint({expr})?"),
+            bool => format!("decode_bool({expr})?"),
+            principal => format!("decode_principal({expr})?"),
+            trait_reference => format!("decode_contract({expr})?"),
+            buffer { length } => format!("decode_buff({expr}, {length})?"),
+            string_ascii { length } => format!("decode_ascii({expr}, {length})?"),
+            string_utf8 { length } => format!("decode_utf8({expr}, {length})?"),
+            tuple(fields) => format!("{}::from_value({expr})?", self.model.tuple_name(fields)),
+            optional(inner) => format!(
+                "decode_optional({expr})?.map(|{x}| -> Result<_, BindingError> {{ Ok({}) }}).transpose()?",
+                self.decode(inner, &x, depth + 1)
+            ),
+            response { ok, error } => format!(
+                "match decode_response({expr})? {{ Ok({x}) => Ok({}), Err({x}) => Err({}) }}",
+                self.decode(ok, &x, depth + 1),
+                self.decode(error, &x, depth + 1)
+            ),
+            list { type_f, length } => format!(
+                "decode_list({expr}, {length})?.into_iter().map(|{x}| -> Result<_, BindingError> {{ Ok({}) }}).collect::<Result<Vec<_>, _>>()?",
+                self.decode(type_f, &x, depth + 1)
+            ),
+        }
+    }
+
+    fn generate(&self) -> String {
+        let model = &self.model;
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "//! Client bindings for the `{}` contract.\n//!\n//! Generated by `clarity-cli generate_bindings`. Do not edit.\n",
+            model.contract_identifier
+        );
+        out.push_str(RUST_PRELUDE);
+        let _ = writeln!(
+            out,
+            "\npub const CONTRACT_ID: &str = \"{}\";\n\npub fn contract_id() -> QualifiedContractIdentifier {{\n    QualifiedContractIdentifier::parse(CONTRACT_ID).expect(\"valid contract identifier\")\n}}",
+            model.contract_identifier
+        );
+
+        for tuple in model.tuples.iter() {
+            self.generate_tuple(&mut out, tuple);
+        }
+        for function in model.public_functions.iter() {
+            self.generate_public_function(&mut out, function);
+        }
+        for function in model.read_only_functions.iter() {
+            self.generate_read_only_function(&mut out, function);
+        }
+        if !model.events.is_empty() {
+            self.generate_events(&mut out);
+        }
+        out
+    }
+
+    fn generate_tuple(&self, out: &mut String, tuple: &NamedTuple) {
+        let mut field_idents = IdentAllocator::default();
+        let fields: Vec<_> = tuple
+            .fields
+            .iter()
+            .map(|field| (field, field_idents.allocate(snake_case(&field.name))))
+            .collect();
+
+        let _ = writeln!(
+            out,
+            "\n#[derive(Debug, Clone, PartialEq)]\npub struct {} {{",
+            tuple.name
+        );
+        for (field, ident) in fields.iter() {
+            let _ = writeln!(out, "    pub {ident}: {},", self.rust_type(&field.type_f));
+        }
+        let _ = writeln!(out, "}}\n\nimpl {} {{", tuple.name);
+        let _ = writeln!(
+            out,
+            "    pub fn to_value(&self) -> Result<Value, BindingError> {{\n        let this = self.clone();\n        encode_tuple(vec!["
+        );
+        for (field, ident) in fields.iter() {
+            let _ = writeln!(
+                out,
+                "            (\"{}\", {}),",
+                field.name,
+                self.encode(&field.type_f, &format!("this.{ident}"), 0)
+            );
+        }
+        let _ = writeln!(out, "        ])\n    }}\n");
+        let _ = writeln!(
+            out,
+            "    pub fn from_value(value: Value) -> Result<Self, BindingError> {{\n        let mut tuple = decode_tuple(value, {})?;\n        Ok(Self {{",
+            fields.len()
+        );
+        for (field, ident) in fields.iter() {
+            let _ = writeln!(
+                out,
+                "            {ident}: {},",
+                self.decode(
+                    &field.type_f,
+                    &format!("take_field(&mut tuple, \"{}\")?", field.name),
+                    0
+                )
+            );
+        }
+        let _ = writeln!(out, "        }})\n    }}\n}}");
+    }
+
+    fn params(&self, function: &BindingsFunction) -> String {
+        function
+            .function
+            .args
+            .iter()
+            .zip(function.arg_idents.iter())
+            .map(|(arg, ident)| format!("{ident}: {}", self.rust_type(&arg.type_f)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn encode_args(&self, function: &BindingsFunction) -> String {
+        function
+            .function
+            .args
+            .iter()
+            .zip(function.arg_idents.iter())
+            .map(|(arg, ident)| self.encode(&arg.type_f, ident, 0))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn generate_public_function(&self, out: &mut String, function: &BindingsFunction) {
+        let name = &function.function.name;
+        let ident = &function.ident;
+        let _ = writeln!(
+            out,
+            "\n/// Build the payload of a contract-call to `{name}`\npub fn {ident}_payload({}) -> Result<TransactionPayload, BindingError> {{\n    contract_call(\"{name}\", vec![{}])\n}}",
+            self.params(function),
+            self.encode_args(function)
+        );
+        let _ = writeln!(
+            out,
+            "\n/// Decode the result of a contract-call to `{name}`\npub fn decode_{ident}_result(value: Value) -> Result<{}, BindingError> {{\n    Ok({})\n}}",
+            self.rust_type(&function.function.outputs.type_f),
+            self.decode(&function.function.outputs.type_f, "value", 0)
+        );
+    }
+
+    fn generate_read_only_function(&self, out: &mut String, function: &BindingsFunction) {
+        let name = &function.function.name;
+        let ident = &function.ident;
+        let params = self.params(function);
+        let sep = if params.is_empty() { "" } else { ", " };
+        let _ = writeln!(
+            out,
+            "\n/// The RPC path of a read-only call to `{name}`\npub fn {ident}_path() -> String {{\n    read_only_path(\"{name}\")\n}}"
+        );
+        let _ = writeln!(
+            out,
+            "\n/// Build the body of a read-only call to `{name}`\npub fn {ident}_request(sender: &PrincipalData{sep}{params}) -> Result<CallReadOnlyRequestBody, BindingError> {{\n    read_only_request(sender, vec![{}])\n}}",
+            self.encode_args(function)
+        );
+        let _ = writeln!(
+            out,
+            "\n/// Decode the response to a read-only call to `{name}`\npub fn decode_{ident}_response(response: &CallReadOnlyResponse) -> Result<{}, BindingError> {{\n    let value = read_only_result(response)?;\n    Ok({})\n}}",
+            self.rust_type(&function.function.outputs.type_f),
+            self.decode(&function.function.outputs.type_f, "value", 0)
+        );
+    }
+
+    fn generate_events(&self, out: &mut String) {
+        let events = &self.model.events;
+        let _ = writeln!(
+            out,
+            "\n/// The contract's `print` events\n#[derive(Debug, Clone, PartialEq)]\npub enum PrintEvent {{"
+        );
+        for event in events.iter() {
+            let _ = writeln!(out, "    {}({}),", event.variant, event.type_name);
+        }
+        let _ = writeln!(
+            out,
+            "}}\n\n/// Decode a value printed by the contract.  Returns `None` if it is not one of its events.\npub fn decode_print_event(value: Value) -> Result<Option<PrintEvent>, BindingError> {{\n    let Value::Tuple(ref tuple) = value else {{\n        return Ok(None);\n    }};"
+        );
+        for event in events.iter() {
+            if let Some((field, tag)) = event.event.tag.as_ref() {
+                let _ = writeln!(
+                    out,
+                    "    if has_tag(tuple, \"{field}\", \"{tag}\") {{\n        return Ok(Some(PrintEvent::{}({}::from_value(value)?)));\n    }}",
+                    event.variant, event.type_name
+                );
+            }
+        }
+        for event in events.iter().filter(|event| event.event.tag.is_none()) {
+            let _ = writeln!(
+                out,
+                "    if let Ok(event) = {}::from_value(value.clone()) {{\n        return Ok(Some(PrintEvent::{}(event)));\n    }}",
+                event.type_name, event.variant
+            );
+        }
+        let _ = writeln!(
+            out,
+            "    Ok(None)\n}}\n\n/// Decode the hex-encoded `raw_value` of a contract event\npub fn decode_print_event_hex(raw_value: &str) -> Result<Option<PrintEvent>, BindingError> {{\n    decode_print_event(\n        Value::try_deserialize_hex_untyped(raw_value)\n            .map_err(|e| BindingError::Decode(e.to_string()))?,\n    )\n}}"
+        );
+    }
+}
+
+const TYPESCRIPT_PRELUDE: &str = r#"import {
+  Cl,
+  ClarityType,
+  ClarityValue,
+  ContractCallPayload,
+  createContractCallPayload,
+  cvToHex,
+  hexToCV,
+} from '@stacks/transactions';
+
+export type Response<T, E> = { ok: true; value: T } | { ok: false; error: E };
+
+function unexpected(expected: string, cv: ClarityValue): Error {
+  return new Error(`expected ${expected}, got ${cv.type}`);
+}
+
+function hexToBytes(hex: string): Uint8Array {
+  const digits = hex.startsWith('0x') ? hex.slice(2) : hex;
+  const bytes = new Uint8Array(digits.length / 2);
+  for (let i = 0; i < bytes.length; i++) {
+    bytes[i] = parseInt(digits.slice(2 * i, 2 * i + 2), 16);
+  }
+  return bytes;
+}
+
+function encodeOptional<T>(value: T | null, encode: (x: T) => ClarityValue): ClarityValue {
+  return value === null ? Cl.none() : Cl.some(encode(value));
+}
+
+function encodeResponse<T, E>(
+  value: Response<T, E>,
+  encodeOk: (x: T) => ClarityValue,
+  encodeErr: (x: E) => ClarityValue,
+): ClarityValue {
+  return value.ok ? Cl.ok(encodeOk(value.value)) : Cl.error(encodeErr(value.error));
+}
+
+function decodeNone(cv: ClarityValue): null {
+  if (cv.type !== ClarityType.OptionalNone) throw unexpected('none', cv);
+  return null;
+}
+
+function decodeInt(cv: ClarityValue): bigint {
+  if (cv.type !== ClarityType.Int) throw unexpected('int', cv);
+  return BigInt(cv.value);
+}
+
+function decodeUint(cv: ClarityValue): bigint {
+  if (cv.type !== ClarityType.UInt) throw unexpected('uint', cv);
+  return BigInt(cv.value);
+}
+
+function decodeBool(cv: ClarityValue): boolean {
+  if (cv.type === ClarityType.BoolTrue) return true;
+  if (cv.type === ClarityType.BoolFalse) return false;
+  throw unexpected('bool', cv);
+}
+
+function decodePrincipal(cv: ClarityValue): string {
+  if (cv.type !== ClarityType.PrincipalStandard && cv.type !== ClarityType.PrincipalContract) {
+    throw unexpected('principal', cv);
+  }
+  return cv.value;
+}
+
+function decodeContract(cv: ClarityValue): string {
+  if (cv.type !== ClarityType.PrincipalContract) throw unexpected('contract principal', cv);
+  return cv.value;
+}
+
+function decodeBuffer(cv: ClarityValue): Uint8Array {
+  if (cv.type !== ClarityType.Buffer) throw unexpected('buffer', cv);
+  return hexToBytes(cv.value);
+}
+
+function decodeAscii(cv: ClarityValue): string {
+  if (cv.type !== ClarityType.StringASCII) throw unexpected('string-ascii', cv);
+  return cv.value;
+}
+
+function decodeUtf8(cv: ClarityValue): string {
+  if (cv.type !== ClarityType.StringUTF8) throw unexpected('string-utf8', cv);
+  return cv.value;
+}
+
+function decodeOptional<T>(cv: ClarityValue, decode: (x: ClarityValue) => T): T | null {
+  if (cv.type === ClarityType.OptionalNone) return null;
+  if (cv.type === ClarityType.OptionalSome) return decode(cv.value);
+  throw unexpected('optional', cv);
+}
+
+function decodeResponse<T, E>(
+  cv: ClarityValue,
+  decodeOk: (x: ClarityValue) => T,
+  decodeErr: (x: ClarityValue) => E,
+): Response<T, E> {
+  if (cv.type === ClarityType.ResponseOk) return { ok: true, value: decodeOk(cv.value) };
+  if (cv.type === ClarityType.ResponseErr) return { ok: false, error: decodeErr(cv.value) };
+  throw unexpected('response', cv);
+}
+
+function decodeList<T>(cv: ClarityValue, decode: (x: ClarityValue) => T): T[] {
+  if (cv.type !== ClarityType.List) throw unexpected('list', cv);
+  return cv.value.map(decode);
+}
+
+function decodeTuple(cv: ClarityValue, numFields: number): { [key: string]: ClarityValue } {
+  if (cv.type !== ClarityType.Tuple || Object.keys(cv.value).length !== numFields) {
+    throw unexpected(`tuple of ${numFields} fields`, cv);
+  }
+  return cv.value;
+}
+
+function field(tuple: { [key: string]: ClarityValue }, name: string): ClarityValue {
+  const value = tuple[name];
+  if (value === undefined) throw new Error(`missing tuple field \`${name}\``);
+  return value;
+}
+
+function hasTag(cv: ClarityValue, name: string, tag: string): boolean {
+  if (cv.type !== ClarityType.Tuple) return false;
+  const value = cv.value[name];
+  return value !== undefined && value.type === ClarityType.StringASCII && value.value === tag;
+}
+
+async function callReadOnly(
+  node: string,
+  functionName: string,
+  sender: string,
+  args: ClarityValue[],
+): Promise<ClarityValue> {
+  const response = await fetch(
+    `${node}/v2/contracts/call-read/${CONTRACT_ADDRESS}/${CONTRACT_NAME}/${functionName}`,
+    {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ sender, arguments: args.map((arg) => cvToHex(arg)) }),
+    },
+  );
+  const body = await response.json();
+  if (!body.okay) throw new Error(`read-only call failed: ${body.cause}`);
+  return hexToCV(body.result);
+}
+"#;
+
+struct TypeScriptBindings<'a> {
+    model: BindingsModel<'a>,
+}
+
+impl TypeScriptBindings<'_> {
+    fn ts_type(&self, type_f: &ContractInterfaceAtomType) -> String {
+        use ContractInterfaceAtomType::*;
+        match type_f {
+            none => "null".into(),
+            int128 | uint128 => "bigint".into(),
+            bool => "boolean".into(),
+            principal | trait_reference | string_ascii { .. } | string_utf8 { .. } => {
+                "string".into()
+            }
+            buffer { .. } => "Uint8Array".into(),
+            tuple(fields) => self.model.tuple_name(fields).into(),
+            optional(inner) => format!("{} | null", self.ts_type(inner)),
+            response { ok, error } => {
+                format!("Response<{}, {}>", self.ts_type(ok), self.ts_type(error))
+            }
+            list { type_f, .. } => match **type_f {
+                optional(_) => format!("({})[]", self.ts_type(type_f)),
+                _ => format!("{}[]", self.ts_type(type_f)),
+            },
+        }
+    }
+
+    /// A TypeScript expression which encodes `expr`, of type `type_f`, as a `ClarityValue`
+    fn encode(&self, type_f: &ContractInterfaceAtomType, expr: &str, depth: usize) -> String {
+        use ContractInterfaceAtomType::*;
+        let x = format!("x{depth}");
+        match type_f {
+            none => "Cl.none()".into(),
+            int128 => format!("Cl.int({expr})"),
+            uint128 => format!("Cl.uint({expr})"),
+            bool => format!("Cl.bool({expr})"),
+            principal | trait_reference => format!("Cl.principal({expr})"),
+            buffer { .. } => format!("Cl.buffer({expr})"),
+            string_ascii { .. } => format!("Cl.stringAscii({expr})"),
+            string_utf8 { .. } => format!("Cl.stringUtf8({expr})"),
+            tuple(fields) => format!("encode{}({expr})", self.model.tuple_name(fields)),
+            optional(inner) => format!(
+                "encodeOptional({expr}, ({x}) => {})",
+                self.encode(inner, &x, depth + 1)
+            ),
+            response { ok, error } => format!(
+                "encodeResponse({expr}, ({x}) => {}, ({x}) => {})",
+                self.encode(ok, &x, depth + 1),
+                self.encode(error, &x, depth + 1)
+            ),
+            list { type_f, .. } => format!(
+                "Cl.list({expr}.map(({x}) => {}))",
+                self.encode(type_f, &x, depth + 1)
+            ),
+        }
+    }
+
+    /// A TypeScript expression which decodes the `ClarityValue` `expr` as `type_f`
+    fn decode(&self, type_f: &ContractInterfaceAtomType, expr: &str, depth: usize) -> String {
+        use ContractInterfaceAtomType::*;
+        let x = format!("x{depth}");
+        match type_f {
+            none => format!("decodeNone({expr})"),
+            int128 => format!("decodeInt({expr})"),
+            uint128 => format!("decodeUint({expr})"),
+            bool => format!("decodeBool({expr})"),
+            principal => format!("decodePrincipal({expr})"),
+            trait_reference => format!("decodeContract({expr})"),
+            buffer { .. } => format!("decodeBuffer({expr})"),
+            string_ascii { .. } => format!("decodeAscii({expr})"),
+            string_utf8 { .. } => format!("decodeUtf8({expr})"),
+            tuple(fields) => format!("decode{}({expr})", self.model.tuple_name(fields)),
+            optional(inner) => format!(
+                "decodeOptional({expr}, ({x}) => {})",
+                self.decode(inner, &x, depth + 1)
+            ),
+            response { ok, error } => format!(
+                "decodeResponse({expr}, ({x}) => {}, ({x}) => {})",
+                self.decode(ok, &x, depth + 1),
+                self.decode(error, &x, depth + 1)
+            ),
+            list { type_f, .. } => format!(
+                "decodeList({expr}, ({x}) => {})",
+                self.decode(type_f, &x, depth + 1)
+            ),
+        }
+    }
+
+    fn generate(&self) -> String {
+        let model = &self.model;
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "// Client bindings for the `{}` contract.\n//\n// Generated by `clarity-cli generate_bindings`. Do not edit.\n",
+            model.contract_identifier
+        );
+        out.push_str(TYPESCRIPT_PRELUDE);
+        let _ = writeln!(
+            out,
+            "\nexport const CONTRACT_ADDRESS = '{}';\nexport const CONTRACT_NAME = '{}';",
+            model.contract_identifier.issuer, model.contract_identifier.name
+        );
+        for tuple in model.tuples.iter() {
+            self.generate_tuple(&mut out, tuple);
+        }
+        for function in model.public_functions.iter() {
+            self.generate_public_function(&mut out, function);
+        }
+        for function in model.read_only_functions.iter() {
+            self.generate_read_only_function(&mut out, function);
+        }
+        if !model.events.is_empty() {
+            self.generate_events(&mut out);
+        }
+        out
+    }
+
+    fn generate_tuple(&self, out: &mut String, tuple: &NamedTuple) {
+        let mut field_idents = IdentAllocator::default();
+        let fields: Vec<_> = tuple
+            .fields
+            .iter()
+            .map(|field| (field, field_idents.allocate(lower_camel_case(&field.name))))
+            .collect();
+        let name = &tuple.name;
+
+        let _ = writeln!(out, "\nexport interface {name} {{");
+        for (field, ident) in fields.iter() {
+            let _ = writeln!(out, "  {ident}: {};", self.ts_type(&field.type_f));
+        }
+        let _ = writeln!(
+            out,
+            "}}\n\nexport function encode{name}(value: {name}): ClarityValue {{\n  return Cl.tuple({{"
+        );
+        for (field, ident) in fields.iter() {
+            let _ = writeln!(
+                out,
+                "    '{}': {},",
+                field.name,
+                self.encode(&field.type_f, &format!("value.{ident}"), 0)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "  }});\n}}\n\nexport function decode{name}(cv: ClarityValue): {name} {{\n  const tuple = decodeTuple(cv, {});\n  return {{",
+            fields.len()
+        );
+        for (field, ident) in fields.iter() {
+            let _ = writeln!(
+                out,
+                "    {ident}: {},",
+                self.decode(&field.type_f, &format!("field(tuple, '{}')", field.name), 0)
+            );
+        }
+        let _ = writeln!(out, "  }};\n}}");
+    }
+
+    fn params(&self, function: &BindingsFunction) -> String {
+        function
+            .function
+            .args
+            .iter()
+            .zip(function.arg_idents.iter())
+            .map(|(arg, ident)| format!("{ident}: {}", self.ts_type(&arg.type_f)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn encode_args(&self, function: &BindingsFunction) -> String {
+        function
+            .function
+            .args
+            .iter()
+            .zip(function.arg_idents.iter())
+            .map(|(arg, ident)| self.encode(&arg.type_f, ident, 0))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn generate_public_function(&self, out: &mut String, function: &BindingsFunction) {
+        let name = &function.function.name;
+        let ident = &function.ident;
+        let upper = upper_camel_case(name);
+        let output = &function.function.outputs.type_f;
+        let _ = writeln!(
+            out,
+            "\n/** Build the payload of a contract-call to `{name}` */\nexport function {ident}Payload({}): ContractCallPayload {{\n  return createContractCallPayload(CONTRACT_ADDRESS, CONTRACT_NAME, '{name}', [{}]);\n}}",
+            self.params(function),
+            self.encode_args(function)
+        );
+        let _ = writeln!(
+            out,
+            "\n/** Decode the result of a contract-call to `{name}` */\nexport function decode{upper}Result(cv: ClarityValue): {} {{\n  return {};\n}}",
+            self.ts_type(output),
+            self.decode(output, "cv", 0)
+        );
+    }
+
+    fn generate_read_only_function(&self, out: &mut String, function: &BindingsFunction) {
+        let name = &function.function.name;
+        let ident = &function.ident;
+        let params = self.params(function);
+        let sep = if params.is_empty() { "" } else { ", " };
+        let output = &function.function.outputs.type_f;
+        let _ = writeln!(
+            out,
+            "\n/** Call the read-only function `{name}` through the node at `node` */\nexport async function {ident}(node: string, sender: string{sep}{params}): Promise<{}> {{\n  const cv = await callReadOnly(node, '{name}', sender, [{}]);\n  return {};\n}}",
+            self.ts_type(output),
+            self.encode_args(function),
+            self.decode(output, "cv", 0)
+        );
+    }
+
+    fn generate_events(&self, out: &mut String) {
+        let events = &self.model.events;
+        let variants: Vec<_> = events
+            .iter()
+            .map(|event| {
+                format!(
+                    "  | {{ event: '{}'; data: {} }}",
+                    event.variant, event.type_name
+                )
+            })
+            .collect();
+        let _ = writeln!(
+            out,
+            "\n/** The contract's `print` events */\nexport type PrintEvent =\n{};",
+            variants.join("\n")
+        );
+        let _ = writeln!(
+            out,
+            "\n/** Decode a value printed by the contract.  Returns `null` if it is not one of its events. */\nexport function decodePrintEvent(cv: ClarityValue): PrintEvent | null {{"
+        );
+        for event in events.iter() {
+            if let Some((field, tag)) = event.event.tag.as_ref() {
+                let _ = writeln!(
+                    out,
+                    "  if (hasTag(cv, '{field}', '{tag}')) {{\n    return {{ event: '{}', data: decode{}(cv) }};\n  }}",
+                    event.variant, event.type_name
+                );
+            }
+        }
+        for event in events.iter().filter(|event| event.event.tag.is_none()) {
+            let _ = writeln!(
+                out,
+                "  try {{\n    return {{ event: '{}', data: decode{}(cv) }};\n  }} catch {{}}",
+                event.variant, event.type_name
+            );
+        }
+        let _ = writeln!(
+            out,
+            "  return null;\n}}\n\n/** Decode the hex-encoded `raw_value` of a contract event */\nexport function decodePrintEventHex(rawValue: string): PrintEvent | null {{\n  return decodePrintEvent(hexToCV(rawValue));\n}}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use stacks_common::types::StacksEpochId;
+
+    use super::*;
+    use crate::vm::analysis::contract_interface_builder::build_contract_interface;
+    use crate::vm::analysis::mem_type_check;
+    use crate::vm::ClarityVersion;
+
+    const CONTRACT: &str = r#"
+        (define-map balances principal uint)
+        (define-public (transfer (amount uint) (recipient principal) (memo (optional (buff 34))))
+            (begin
+                (print { event: "transfer", amount: amount, sender: tx-sender, recipient: recipient })
+                (ok true)))
+        (define-public (set-owner (new-owner { owner: principal, since: uint }))
+            (begin
+                (print { topic: "owner-changed", owner: (get owner new-owner) })
+                (print { owner: (get owner new-owner), since: (get since new-owner) })
+                (ok u1)))
+        (define-read-only (get-info (who principal))
+            (ok { balance: (default-to u0 (map-get? balances who)), names: (list "a" "b") }))
+        (define-private (helper) u1)
+    "#;
+
+    fn bindings_inputs() -> (ContractInterface, Vec<PrintEventType>) {
+        let (_, analysis) =
+            mem_type_check(CONTRACT, ClarityVersion::Clarity2, StacksEpochId::Epoch25).unwrap();
+        (
+            build_contract_interface(&analysis).unwrap(),
+            infer_print_events(&analysis),
+        )
+    }
+
+    #[test]
+    fn test_infer_print_events() {
+        let (_, events) = bindings_inputs();
+        assert_eq!(events.len(), 3);
+
+        assert_eq!(events[0].name, "transfer");
+        assert_eq!(
+            events[0].tag,
+            Some(("event".to_string(), "transfer".to_string()))
+        );
+        let field_names: Vec<_> = events[0].fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(field_names, vec!["amount", "event", "recipient", "sender"]);
+
+        assert_eq!(events[1].name, "owner-changed");
+        assert_eq!(
+            events[1].tag,
+            Some(("topic".to_string(), "owner-changed".to_string()))
+        );
+
+        assert_eq!(events[2].name, "event");
+        assert_eq!(events[2].tag, None);
+        // the same type as `set-owner`'s argument
+        assert_eq!(
+            ContractInterfaceAtomType::tuple(events[2].fields.clone()),
+            ContractInterfaceAtomType::tuple(vec![
+                ContractInterfaceTupleEntryType {
+                    name: "owner".into(),
+                    type_f: ContractInterfaceAtomType::principal,
+                },
+                ContractInterfaceTupleEntryType {
+                    name: "since".into(),
+                    type_f: ContractInterfaceAtomType::uint128,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_rust_bindings() {
+        let (interface, events) = bindings_inputs();
+        let contract_id = QualifiedContractIdentifier::transient();
+        let rust = generate_rust_bindings(&contract_id, &interface, &events);
+
+        // functions
+        assert!(rust.contains("pub fn transfer_payload(amount: u128, recipient: PrincipalData, memo: Option<Vec<u8>>) -> Result<TransactionPayload, BindingError>"));
+        assert!(rust.contains(
+            "pub fn decode_transfer_result(value: Value) -> Result<Result<bool, ()>, BindingError>"
+        ));
+        assert!(rust.contains("pub fn set_owner_payload(new_owner: SetOwnerNewOwner)"));
+        assert!(rust.contains("pub fn get_info_request(sender: &PrincipalData, who: PrincipalData) -> Result<CallReadOnlyRequestBody, BindingError>"));
+        assert!(rust.contains("pub fn decode_get_info_response(response: &CallReadOnlyResponse) -> Result<Result<GetInfoOutput, ()>, BindingError>"));
+        assert!(!rust.contains("helper"));
+
+        // tuples
+        assert!(rust.contains(
+            "pub struct GetInfoOutput {\n    pub balance: u128,\n    pub names: Vec<String>,\n}"
+        ));
+        assert!(rust.contains("pub struct TransferEvent {"));
+        // the untagged event reuses the argument's struct
+        assert!(!rust.contains("pub struct EventEvent"));
+
+        // events
+        assert!(rust.contains("    Transfer(TransferEvent),\n    OwnerChanged(OwnerChangedEvent),\n    Event(SetOwnerNewOwner),\n"));
+        assert!(rust.contains("if has_tag(tuple, \"event\", \"transfer\")"));
+        assert!(rust.contains("if let Ok(event) = SetOwnerNewOwner::from_value(value.clone())"));
+    }
+
+    #[test]
+    fn test_typescript_bindings() {
+        let (interface, events) = bindings_inputs();
+        let contract_id = QualifiedContractIdentifier::transient();
+        let ts = generate_typescript_bindings(&contract_id, &interface, &events);
+
+        assert!(ts.contains("export const CONTRACT_NAME = '__transient';"));
+        assert!(ts.contains("export function transferPayload(amount: bigint, recipient: string, memo: Uint8Array | null): ContractCallPayload"));
+        assert!(ts.contains(
+            "export function decodeTransferResult(cv: ClarityValue): Response<boolean, null>"
+        ));
+        assert!(ts.contains("export async function getInfo(node: string, sender: string, who: string): Promise<Response<GetInfoOutput, null>>"));
+        assert!(ts.contains(
+            "export interface GetInfoOutput {\n  balance: bigint;\n  names: string[];\n}"
+        ));
+        assert!(ts.contains("names: decodeList(field(tuple, 'names'), (x0) => decodeAscii(x0)),"));
+        assert!(ts.contains(
+            "export function decodeSetOwnerResult(cv: ClarityValue): Response<bigint, null>"
+        ));
+        assert!(ts.contains("  | { event: 'OwnerChanged'; data: OwnerChangedEvent }\n  | { event: 'Event'; data: SetOwnerNewOwner };"));
+        assert!(ts.contains("if (hasTag(cv, 'topic', 'owner-changed'))"));
+    }
+
+    #[test]
+    fn test_identifiers() {
+        assert_eq!(snake_case("get-balance?"), "get_balance");
+        assert_eq!(snake_case("type"), "type_");
+        assert_eq!(upper_camel_case("set-owner-new-owner"), "SetOwnerNewOwner");
+        assert_eq!(lower_camel_case("token-uri"), "tokenUri");
+
+        let mut idents = IdentAllocator::default();
+        assert_eq!(idents.allocate(snake_case("is-ok?")), "is_ok");
+        assert_eq!(idents.allocate(snake_case("is-ok!")), "is_ok2");
+    }
+}
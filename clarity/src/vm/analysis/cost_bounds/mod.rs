@@ -0,0 +1,856 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Static worst-case cost analysis.
+//!
+//! This pass walks the body of each public and read-only function of a type-checked contract
+//! and charges the same cost functions that the interpreter charges when evaluating it.  Every
+//! input to a cost function is replaced by its largest possible value given the types in the
+//! contract's type map (e.g. the maximum size of a value, or the maximum length of a sequence),
+//! `if` and `match` take their most expensive branch, and `map`, `filter` and `fold` are
+//! charged once per element of the longest sequence their argument's type admits.  Since the
+//! default cost functions are non-decreasing in their input, the result is an upper bound on
+//! the cost of any call to the function.
+//!
+//! The bound covers the evaluation of the function's body.  It does not include the cost of
+//! loading the contract itself, which the transaction that calls it pays, nor the costs of
+//! `contract-call?`s whose targets cannot be resolved (e.g. calls through traits): those calls
+//! are reported alongside the bound instead.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+use std::{cmp, fmt};
+
+use stacks_common::types::StacksEpochId;
+
+use crate::boot_util::boot_code_id;
+use crate::vm::analysis::types::ContractAnalysis;
+use crate::vm::callables::CallableType;
+use crate::vm::costs::cost_functions::ClarityCostFunction;
+use crate::vm::costs::{
+    ClarityCostFunctionReference, DefaultVersion, ExecutionCost, LimitedCostTracker,
+};
+use crate::vm::functions::define::DefineFunctionsParsed;
+use crate::vm::functions::{lookup_reserved_functions, NativeFunctions};
+use crate::vm::representations::SymbolicExpressionType::{
+    Atom, AtomValue, Field, List, LiteralValue, TraitReference,
+};
+use crate::vm::representations::{ClarityName, SymbolicExpression};
+use crate::vm::types::signatures::{FunctionType, SequenceSubtype, StringSubtype};
+use crate::vm::types::{
+    PrincipalData, QualifiedContractIdentifier, TypeSignature, Value, MAX_VALUE_SIZE,
+};
+use crate::vm::variables::NativeVariables;
+use crate::vm::ClarityVersion;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Error {
+    /// Clarity does not exist in the epoch, so there are no cost functions
+    NoCostFunctions(StacksEpochId),
+    /// The contract was analyzed without a type map
+    MissingTypeMap(QualifiedContractIdentifier),
+    CostComputationFailed(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoCostFunctions(epoch) => write!(f, "no cost functions in epoch {epoch}"),
+            Error::MissingTypeMap(contract) => {
+                write!(f, "contract {contract} was analyzed without a type map")
+            }
+            Error::CostComputationFailed(msg) => write!(f, "cost computation failed: {msg}"),
+        }
+    }
+}
+
+/// A deployed contract whose functions may be the target of a `contract-call?`
+pub struct KnownContract {
+    /// The contract's analysis, which must include its expressions and type map
+    pub analysis: ContractAnalysis,
+    /// The size that is charged when the contract is loaded (its source length plus its data
+    /// size)
+    pub size: u64,
+}
+
+/// The worst-case cost of calling a public or read-only function
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FunctionCostBound {
+    pub name: ClarityName,
+    pub read_only: bool,
+    pub cost: ExecutionCost,
+    /// `contract-call?`s whose targets are not known, and whose costs are thus not included in
+    /// `cost`, as `contract::function` (or `<name>::function` for calls through a trait
+    /// reference)
+    pub unbounded_calls: BTreeSet<String>,
+    pub exceeds_block_limit: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CostBound {
+    cost: ExecutionCost,
+    unbounded_calls: BTreeSet<String>,
+}
+
+fn saturating_add(cost: &mut ExecutionCost, other: &ExecutionCost) {
+    cost.runtime = cost.runtime.saturating_add(other.runtime);
+    cost.read_count = cost.read_count.saturating_add(other.read_count);
+    cost.read_length = cost.read_length.saturating_add(other.read_length);
+    cost.write_count = cost.write_count.saturating_add(other.write_count);
+    cost.write_length = cost.write_length.saturating_add(other.write_length);
+}
+
+impl CostBound {
+    fn zero() -> CostBound {
+        CostBound {
+            cost: ExecutionCost::ZERO,
+            unbounded_calls: BTreeSet::new(),
+        }
+    }
+
+    fn add(&mut self, other: &CostBound) {
+        saturating_add(&mut self.cost, &other.cost);
+        self.unbounded_calls
+            .extend(other.unbounded_calls.iter().cloned());
+    }
+
+    /// Take the maximum of each dimension, for expressions that evaluate one of `self` and
+    /// `other`.
+    fn join(&mut self, other: &CostBound) {
+        self.cost = ExecutionCost::max_cost(self.cost.clone(), other.cost.clone());
+        self.unbounded_calls
+            .extend(other.unbounded_calls.iter().cloned());
+    }
+
+    fn times(&self, n: u64) -> CostBound {
+        let cost = &self.cost;
+        CostBound {
+            cost: ExecutionCost {
+                runtime: cost.runtime.saturating_mul(n),
+                read_count: cost.read_count.saturating_mul(n),
+                read_length: cost.read_length.saturating_mul(n),
+                write_count: cost.write_count.saturating_mul(n),
+                write_length: cost.write_length.saturating_mul(n),
+            },
+            unbounded_calls: self.unbounded_calls.clone(),
+        }
+    }
+}
+
+/// Upper bound on `Value::size()` of a value of type `ty`.  Missing types are bounded by the
+/// maximum size of any value.
+fn value_size(ty: Option<&TypeSignature>) -> u64 {
+    ty.and_then(|ty| ty.size().ok())
+        .unwrap_or(MAX_VALUE_SIZE)
+        .into()
+}
+
+/// Upper bound on the length of the consensus serialization of a value of type `ty`.  Types
+/// without a serialization bound (e.g. the element type of `none`) fall back to their size.
+fn serialized_size(ty: Option<&TypeSignature>) -> u64 {
+    ty.and_then(|ty| ty.max_serialized_size().ok())
+        .map(u64::from)
+        .unwrap_or_else(|| value_size(ty))
+}
+
+/// Upper bound on the size that a data var or map operation charges on `types`: before 2.05 it
+/// is the size of the stored types, after it is the length of their serialization (which wraps
+/// the stored value in an optional).
+fn storage_size(types: &[Option<&TypeSignature>]) -> u64 {
+    let sizes = types.iter().map(|ty| value_size(*ty)).sum::<u64>();
+    let serialized = types.iter().map(|ty| serialized_size(*ty)).sum::<u64>() + 1;
+    cmp::max(sizes, serialized)
+}
+
+/// The maximum length of a sequence of type `ty`
+fn max_len(ty: Option<&TypeSignature>) -> u64 {
+    match ty {
+        Some(TypeSignature::SequenceType(SequenceSubtype::ListType(list))) => {
+            list.get_max_len().into()
+        }
+        Some(TypeSignature::SequenceType(SequenceSubtype::BufferType(len)))
+        | Some(TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::ASCII(
+            len,
+        )))) => u32::from(len).into(),
+        Some(TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::UTF8(
+            len,
+        )))) => u32::from(len).into(),
+        Some(_) => 0,
+        None => MAX_VALUE_SIZE.into(),
+    }
+}
+
+/// The type of an element of a sequence of type `ty`
+fn element_type(ty: Option<&TypeSignature>) -> Option<TypeSignature> {
+    match ty {
+        Some(TypeSignature::SequenceType(seq)) => seq.unit_type().ok(),
+        _ => None,
+    }
+}
+
+/// Looks up a contract that is the target of a `contract-call?`
+type ContractResolver<'a> =
+    Box<dyn FnMut(&QualifiedContractIdentifier) -> Option<KnownContract> + 'a>;
+
+/// Computes upper bounds of the costs of contract functions.
+pub struct CostBoundsChecker<'a> {
+    epoch: StacksEpochId,
+    cost_version: DefaultVersion,
+    cost_contract: QualifiedContractIdentifier,
+    resolver: ContractResolver<'a>,
+    known_contracts: HashMap<QualifiedContractIdentifier, Option<Rc<KnownContract>>>,
+    function_bounds: HashMap<(QualifiedContractIdentifier, ClarityName), CostBound>,
+    in_progress: HashSet<(QualifiedContractIdentifier, ClarityName)>,
+}
+
+impl<'a> CostBoundsChecker<'a> {
+    /// Make a checker that uses the default cost functions of `epoch`.  `contract-call?`
+    /// targets are unknown unless a resolver is set with `with_resolver`.
+    pub fn new(epoch: StacksEpochId, mainnet: bool) -> Result<CostBoundsChecker<'a>, Error> {
+        let cost_contract_name = LimitedCostTracker::default_cost_contract_for_epoch(epoch)
+            .map_err(|_| Error::NoCostFunctions(epoch))?;
+        let cost_contract = boot_code_id(&cost_contract_name, mainnet);
+        let cost_version = DefaultVersion::try_from(mainnet, &cost_contract)
+            .map_err(Error::CostComputationFailed)?;
+        Ok(CostBoundsChecker {
+            epoch,
+            cost_version,
+            cost_contract,
+            resolver: Box::new(|_| None),
+            known_contracts: HashMap::new(),
+            function_bounds: HashMap::new(),
+            in_progress: HashSet::new(),
+        })
+    }
+
+    /// Use `resolver` to look up the targets of `contract-call?`s.  Each contract is looked up
+    /// at most once.
+    pub fn with_resolver<F>(mut self, resolver: F) -> CostBoundsChecker<'a>
+    where
+        F: FnMut(&QualifiedContractIdentifier) -> Option<KnownContract> + 'a,
+    {
+        self.resolver = Box::new(resolver);
+        self
+    }
+
+    /// Compute the cost bound of each public and read-only function of `contract`, which must
+    /// have been analyzed with a type map, and flag those that exceed `block_limit`.
+    pub fn run(
+        &mut self,
+        contract: &ContractAnalysis,
+        block_limit: &ExecutionCost,
+    ) -> Result<Vec<FunctionCostBound>, Error> {
+        if !has_type_map(contract) {
+            return Err(Error::MissingTypeMap(contract.contract_identifier.clone()));
+        }
+        let mut bounds = vec![];
+        let functions = contract
+            .public_function_types
+            .keys()
+            .map(|name| (name, false))
+            .chain(
+                contract
+                    .read_only_function_types
+                    .keys()
+                    .map(|name| (name, true)),
+            );
+        for (name, read_only) in functions {
+            let bound = self.function_bound(contract, name)?;
+            bounds.push(FunctionCostBound {
+                name: name.clone(),
+                read_only,
+                exceeds_block_limit: bound.cost.exceeds(block_limit),
+                cost: bound.cost,
+                unbounded_calls: bound.unbounded_calls,
+            });
+        }
+        bounds.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(bounds)
+    }
+
+    fn charge(&self, f: ClarityCostFunction, n: u64) -> Result<CostBound, Error> {
+        let cost_function_ref = ClarityCostFunctionReference {
+            contract_id: self.cost_contract.clone(),
+            function_name: f.get_name(),
+        };
+        // Logarithmic cost functions are undefined at 0, where the interpreter would fail.
+        // They are non-decreasing, so evaluating them at 1 instead keeps the bound.
+        let cost = self
+            .cost_version
+            .evaluate(&cost_function_ref, &f, &[cmp::max(n, 1)])
+            .map_err(|e| Error::CostComputationFailed(format!("{e:?}")))?;
+        Ok(CostBound {
+            cost,
+            unbounded_calls: BTreeSet::new(),
+        })
+    }
+
+    fn known_contract(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+    ) -> Option<Rc<KnownContract>> {
+        if let Some(known) = self.known_contracts.get(contract_identifier) {
+            return known.clone();
+        }
+        let known = (self.resolver)(contract_identifier)
+            .filter(|known| has_type_map(&known.analysis))
+            .map(Rc::new);
+        self.known_contracts
+            .insert(contract_identifier.clone(), known.clone());
+        known
+    }
+
+    /// The cost of applying the user-defined function `name` of `contract` to evaluated
+    /// arguments: the application itself, the type checks of its arguments, and its body.
+    fn function_bound(
+        &mut self,
+        contract: &ContractAnalysis,
+        name: &ClarityName,
+    ) -> Result<CostBound, Error> {
+        let key = (contract.contract_identifier.clone(), name.clone());
+        if let Some(bound) = self.function_bounds.get(&key) {
+            return Ok(bound.clone());
+        }
+        // Clarity has no recursion, so a cycle can only come from a malformed contract
+        if !self.in_progress.insert(key.clone()) {
+            let mut bound = CostBound::zero();
+            bound
+                .unbounded_calls
+                .insert(format!("{}::{}", contract.contract_identifier, name));
+            return Ok(bound);
+        }
+
+        let function_type = contract
+            .public_function_types
+            .get(name)
+            .or_else(|| contract.read_only_function_types.get(name))
+            .or_else(|| contract.private_function_types.get(name));
+        let arg_types = match function_type {
+            Some(FunctionType::Fixed(function)) => function
+                .args
+                .iter()
+                .map(|arg| arg.signature.clone())
+                .collect(),
+            _ => vec![],
+        };
+        let body = contract.expressions.iter().find_map(|expr| {
+            match DefineFunctionsParsed::try_parse(expr) {
+                Ok(Some(DefineFunctionsParsed::PrivateFunction { signature, body }))
+                | Ok(Some(DefineFunctionsParsed::ReadOnlyFunction { signature, body }))
+                | Ok(Some(DefineFunctionsParsed::PublicFunction { signature, body }))
+                    if signature.first().and_then(|x| x.match_atom()) == Some(name) =>
+                {
+                    Some(body)
+                }
+                _ => None,
+            }
+        });
+
+        let mut bound = self.charge(
+            ClarityCostFunction::UserFunctionApplication,
+            arg_types.len() as u64,
+        )?;
+        for arg_type in arg_types.iter() {
+            bound.add(&self.charge(
+                ClarityCostFunction::InnerTypeCheckCost,
+                value_size(Some(arg_type)),
+            )?);
+        }
+        if let Some(body) = body {
+            bound.add(&self.expr_bound(contract, body, 0)?);
+        }
+
+        self.in_progress.remove(&key);
+        self.function_bounds.insert(key, bound.clone());
+        Ok(bound)
+    }
+
+    fn exprs_bound(
+        &mut self,
+        contract: &ContractAnalysis,
+        exprs: &[SymbolicExpression],
+        depth: u64,
+    ) -> Result<CostBound, Error> {
+        let mut bound = CostBound::zero();
+        for expr in exprs {
+            bound.add(&self.expr_bound(contract, expr, depth)?);
+        }
+        Ok(bound)
+    }
+
+    /// The cost of evaluating `expr` in a local context of depth `depth`
+    fn expr_bound(
+        &mut self,
+        contract: &ContractAnalysis,
+        expr: &SymbolicExpression,
+        depth: u64,
+    ) -> Result<CostBound, Error> {
+        match expr.expr {
+            AtomValue(_) | LiteralValue(_) | TraitReference(..) | Field(_) => Ok(CostBound::zero()),
+            Atom(ref name) => {
+                if let Some(variable) =
+                    NativeVariables::lookup_by_name_at_version(name, &contract.clarity_version)
+                {
+                    return match variable {
+                        NativeVariables::BlockHeight
+                        | NativeVariables::BurnBlockHeight
+                        | NativeVariables::TotalLiquidMicroSTX
+                        | NativeVariables::StacksBlockHeight
+                        | NativeVariables::TenureHeight => {
+                            self.charge(ClarityCostFunction::FetchVar, 1)
+                        }
+                        _ => Ok(CostBound::zero()),
+                    };
+                }
+                let mut bound = self.charge(ClarityCostFunction::LookupVariableDepth, depth)?;
+                bound.add(&self.charge(
+                    ClarityCostFunction::LookupVariableSize,
+                    value_size(expr_type(contract, expr)),
+                )?);
+                Ok(bound)
+            }
+            List(ref children) => {
+                let Some((function, args)) = children.split_first() else {
+                    return Ok(CostBound::zero());
+                };
+                let Some(function_name) = function.match_atom() else {
+                    return Ok(CostBound::zero());
+                };
+                let mut bound = self.charge(ClarityCostFunction::LookupFunction, 0)?;
+                if let Some(native) = NativeFunctions::lookup_by_name_at_version(
+                    function_name,
+                    &contract.clarity_version,
+                ) {
+                    bound.add(&self.native_bound(contract, expr, native, args, depth)?);
+                } else {
+                    bound.add(&self.exprs_bound(contract, args, depth)?);
+                    bound.add(&self.function_bound(contract, function_name)?);
+                }
+                Ok(bound)
+            }
+        }
+    }
+
+    /// The cost of applying the function `name` to evaluated arguments of types `arg_types`, as
+    /// `map`, `filter` and `fold` do for each element.
+    fn apply_bound(
+        &mut self,
+        contract: &ContractAnalysis,
+        name: &ClarityName,
+        arg_types: &[Option<TypeSignature>],
+    ) -> Result<CostBound, Error> {
+        if let Some(native) =
+            NativeFunctions::lookup_by_name_at_version(name, &contract.clarity_version)
+        {
+            let arg_types: Vec<_> = arg_types.iter().map(|ty| ty.as_ref()).collect();
+            self.simple_native_bound(contract, native, &arg_types)
+        } else {
+            self.function_bound(contract, name)
+        }
+    }
+
+    /// The cost that a native function charges for itself when applied to evaluated arguments
+    /// of types `arg_types`, for natives whose cost only depends on their arguments' types.
+    fn simple_native_bound(
+        &self,
+        contract: &ContractAnalysis,
+        native: NativeFunctions,
+        arg_types: &[Option<&TypeSignature>],
+    ) -> Result<CostBound, Error> {
+        use crate::vm::functions::NativeFunctions::*;
+
+        let argc = arg_types.len() as u64;
+        let sizes = || arg_types.iter().map(|ty| value_size(*ty));
+        let (f, n) = match native {
+            CmpGeq | CmpLeq | CmpLess | CmpGreater => {
+                let f = match native {
+                    CmpGeq => ClarityCostFunction::Geq,
+                    CmpLeq => ClarityCostFunction::Leq,
+                    CmpLess => ClarityCostFunction::Le,
+                    _ => ClarityCostFunction::Ge,
+                };
+                if contract.clarity_version >= ClarityVersion::Clarity2 {
+                    (f, sizes().min().unwrap_or(0))
+                } else {
+                    (f, argc)
+                }
+            }
+            And => (ClarityCostFunction::And, argc),
+            Or => (ClarityCostFunction::Or, argc),
+            ListCons => (ClarityCostFunction::ListCons, sizes().sum()),
+            Print => (ClarityCostFunction::Print, sizes().sum()),
+            Append => {
+                let entry_type = element_type(arg_types.first().copied().flatten());
+                let element_size = arg_types.get(1).map(|ty| value_size(*ty)).unwrap_or(0);
+                (
+                    ClarityCostFunction::Append,
+                    cmp::max(value_size(entry_type.as_ref()), element_size),
+                )
+            }
+            Concat => {
+                if self.epoch >= StacksEpochId::Epoch2_05 {
+                    (
+                        ClarityCostFunction::Concat,
+                        arg_types.iter().map(|ty| max_len(*ty)).sum(),
+                    )
+                } else {
+                    (ClarityCostFunction::Concat, sizes().sum())
+                }
+            }
+            Slice => (
+                ClarityCostFunction::Slice,
+                value_size(arg_types.first().copied().flatten()),
+            ),
+            ReplaceAt => (
+                ClarityCostFunction::ReplaceAt,
+                value_size(arg_types.first().copied().flatten()),
+            ),
+            TupleGet => {
+                let fields = match arg_types.last().copied().flatten() {
+                    Some(TypeSignature::TupleType(tuple)) => tuple.len(),
+                    Some(TypeSignature::OptionalType(inner)) => match inner.as_ref() {
+                        TypeSignature::TupleType(tuple) => tuple.len(),
+                        _ => 0,
+                    },
+                    _ => 0,
+                };
+                (ClarityCostFunction::TupleGet, fields)
+            }
+            FromConsensusBuff => (
+                ClarityCostFunction::FromConsensusBuff,
+                max_len(arg_types.last().copied().flatten()),
+            ),
+            IsStandard => (ClarityCostFunction::IsStandard, 0),
+            PrincipalDestruct => (ClarityCostFunction::PrincipalDestruct, 0),
+            PrincipalConstruct => (ClarityCostFunction::PrincipalConstruct, 0),
+            PrincipalOf => (ClarityCostFunction::PrincipalOf, 0),
+            Secp256k1Recover => (ClarityCostFunction::Secp256k1recover, 0),
+            Secp256k1Verify => (ClarityCostFunction::Secp256k1verify, 0),
+            Asserts => (ClarityCostFunction::Asserts, 0),
+            AsMaxLen => (ClarityCostFunction::AsMaxLen, 0),
+            AtBlock => (ClarityCostFunction::AtBlock, 0),
+            ContractOf => (ClarityCostFunction::ContractOf, 0),
+            AsContract => {
+                if self.epoch >= StacksEpochId::Epoch21 {
+                    (ClarityCostFunction::AsContract, 0)
+                } else {
+                    return Ok(CostBound::zero());
+                }
+            }
+            GetStxBalance => (ClarityCostFunction::StxBalance, 0),
+            StxTransfer | StxBurn => (ClarityCostFunction::StxTransfer, 0),
+            StxTransferMemo => (ClarityCostFunction::StxTransferMemo, 0),
+            StxGetAccount => (ClarityCostFunction::StxGetAccount, 0),
+            _ => {
+                match lookup_reserved_functions(native.get_name_str(), &contract.clarity_version) {
+                    Some(CallableType::NativeFunction(_, _, f)) => (f, argc),
+                    Some(CallableType::NativeFunction205(_, _, f, _)) => {
+                        if self.epoch >= StacksEpochId::Epoch2_05 {
+                            (f, arg_types.iter().map(|ty| serialized_size(*ty)).sum())
+                        } else {
+                            (f, argc)
+                        }
+                    }
+                    _ => {
+                        return Err(Error::CostComputationFailed(format!(
+                            "no cost bound for `{}`",
+                            native.get_name_str()
+                        )))
+                    }
+                }
+            }
+        };
+        self.charge(f, n)
+    }
+
+    /// The cost of evaluating the application `expr` of the native function `native` to `args`
+    fn native_bound(
+        &mut self,
+        contract: &ContractAnalysis,
+        expr: &SymbolicExpression,
+        native: NativeFunctions,
+        args: &[SymbolicExpression],
+        depth: u64,
+    ) -> Result<CostBound, Error> {
+        use crate::vm::functions::NativeFunctions::*;
+
+        let arg_type = |i: usize| args.get(i).and_then(|arg| expr_type(contract, arg));
+        let name_at = |i: usize| args.get(i).and_then(|arg| arg.match_atom());
+
+        let bound = match native {
+            If => {
+                let mut bound = self.charge(ClarityCostFunction::If, 0)?;
+                if let Some(condition) = args.first() {
+                    bound.add(&self.expr_bound(contract, condition, depth)?);
+                }
+                let mut branches = CostBound::zero();
+                for branch in args.iter().skip(1) {
+                    branches.join(&self.expr_bound(contract, branch, depth)?);
+                }
+                bound.add(&branches);
+                bound
+            }
+            Match => {
+                let mut bound = self.charge(ClarityCostFunction::Match, 0)?;
+                if let Some(input) = args.first() {
+                    bound.add(&self.expr_bound(contract, input, depth)?);
+                }
+                // (match opt some-name some-body none-body) or
+                // (match resp ok-name ok-body err-name err-body): both bodies may bind a name
+                let branches = if args.len() == 5 {
+                    vec![&args[2], &args[4]]
+                } else {
+                    args.iter().skip(2).collect()
+                };
+                let mut worst = CostBound::zero();
+                for branch in branches {
+                    worst.join(&self.expr_bound(contract, branch, depth + 1)?);
+                }
+                bound.add(&worst);
+                bound
+            }
+            Let => {
+                let bindings = args.first().and_then(|x| x.match_list()).unwrap_or(&[]);
+                let mut bound = self.charge(ClarityCostFunction::Let, bindings.len() as u64)?;
+                for binding in bindings {
+                    if let Some(value) = binding.match_list().and_then(|pair| pair.get(1)) {
+                        bound.add(&self.expr_bound(contract, value, depth + 1)?);
+                    }
+                }
+                bound.add(&self.exprs_bound(contract, args.get(1..).unwrap_or(&[]), depth + 1)?);
+                bound
+            }
+            TupleCons => {
+                let mut bound = self.charge(ClarityCostFunction::TupleCons, args.len() as u64)?;
+                for binding in args {
+                    if let Some(value) = binding.match_list().and_then(|pair| pair.get(1)) {
+                        bound.add(&self.expr_bound(contract, value, depth)?);
+                    }
+                }
+                bound
+            }
+            Map | Filter | Fold => {
+                let f = match native {
+                    Map => ClarityCostFunction::Map,
+                    Filter => ClarityCostFunction::Filter,
+                    _ => ClarityCostFunction::Fold,
+                };
+                let n = if native == Map { args.len() as u64 } else { 0 };
+                let mut bound = self.charge(f, n)?;
+                bound.add(&self.charge(ClarityCostFunction::LookupFunction, 0)?);
+                bound.add(&self.exprs_bound(contract, args.get(1..).unwrap_or(&[]), depth)?);
+
+                let sequences = if native == Map {
+                    args.get(1..).unwrap_or(&[])
+                } else {
+                    args.get(1..2).unwrap_or(&[])
+                };
+                let iterations = sequences
+                    .iter()
+                    .map(|seq| max_len(expr_type(contract, seq)))
+                    .min()
+                    .unwrap_or(0);
+                let mut element_types: Vec<_> = sequences
+                    .iter()
+                    .map(|seq| element_type(expr_type(contract, seq)))
+                    .collect();
+                if native == Fold {
+                    // the accumulator has the type of the fold's result
+                    element_types.push(expr_type(contract, expr).or(arg_type(2)).cloned());
+                }
+                if let Some(function_name) = name_at(0) {
+                    let per_element = self.apply_bound(contract, function_name, &element_types)?;
+                    bound.add(&per_element.times(iterations));
+                }
+                bound
+            }
+            FetchVar | SetVar => {
+                let var_type =
+                    name_at(0).and_then(|name| contract.persisted_variable_types.get(name));
+                let f = if native == FetchVar {
+                    ClarityCostFunction::FetchVar
+                } else {
+                    ClarityCostFunction::SetVar
+                };
+                let mut bound = self.charge(f, storage_size(&[var_type]))?;
+                bound.add(&self.exprs_bound(contract, args.get(1..).unwrap_or(&[]), depth)?);
+                bound
+            }
+            FetchEntry | SetEntry | InsertEntry | DeleteEntry => {
+                let (key_type, value_type) = name_at(0)
+                    .and_then(|name| contract.map_types.get(name))
+                    .map(|(key, value)| (Some(key), Some(value)))
+                    .unwrap_or((None, None));
+                let f = if native == FetchEntry {
+                    ClarityCostFunction::FetchEntry
+                } else {
+                    ClarityCostFunction::SetEntry
+                };
+                let mut bound = self.charge(f, storage_size(&[key_type, value_type]))?;
+                bound.add(&self.exprs_bound(contract, args.get(1..).unwrap_or(&[]), depth)?);
+                bound
+            }
+            MintAsset | TransferAsset | GetAssetOwner | BurnAsset => {
+                let asset_type = name_at(0).and_then(|name| contract.non_fungible_tokens.get(name));
+                let f = match native {
+                    MintAsset => ClarityCostFunction::NftMint,
+                    TransferAsset => ClarityCostFunction::NftTransfer,
+                    GetAssetOwner => ClarityCostFunction::NftOwner,
+                    _ => ClarityCostFunction::NftBurn,
+                };
+                let mut bound = self.charge(f, storage_size(&[asset_type]))?;
+                if native == BurnAsset {
+                    bound.add(&self.charge(f, 0)?);
+                }
+                bound.add(&self.exprs_bound(contract, args.get(1..).unwrap_or(&[]), depth)?);
+                bound
+            }
+            MintToken | TransferToken | GetTokenBalance | BurnToken | GetTokenSupply => {
+                let f = match native {
+                    MintToken => ClarityCostFunction::FtMint,
+                    TransferToken => ClarityCostFunction::FtTransfer,
+                    GetTokenBalance => ClarityCostFunction::FtBalance,
+                    BurnToken => ClarityCostFunction::FtBurn,
+                    _ => ClarityCostFunction::FtSupply,
+                };
+                let mut bound = self.charge(f, 0)?;
+                bound.add(&self.exprs_bound(contract, args.get(1..).unwrap_or(&[]), depth)?);
+                bound
+            }
+            GetBlockInfo | GetBurnBlockInfo | GetStacksBlockInfo | GetTenureInfo => {
+                let f = if native == GetBurnBlockInfo {
+                    ClarityCostFunction::GetBurnBlockInfo
+                } else {
+                    ClarityCostFunction::BlockInfo
+                };
+                let mut bound = self.charge(f, 0)?;
+                bound.add(&self.exprs_bound(contract, args.get(1..).unwrap_or(&[]), depth)?);
+                bound
+            }
+            AsMaxLen => {
+                let mut bound = self.charge(ClarityCostFunction::AsMaxLen, 0)?;
+                bound.add(&self.exprs_bound(contract, args.get(..1).unwrap_or(&[]), depth)?);
+                bound
+            }
+            TupleGet | FromConsensusBuff => {
+                let value_args = args.get(1..).unwrap_or(&[]);
+                let value_types: Vec<_> = value_args
+                    .iter()
+                    .map(|arg| expr_type(contract, arg))
+                    .collect();
+                let mut bound = self.simple_native_bound(contract, native, &value_types)?;
+                bound.add(&self.exprs_bound(contract, value_args, depth)?);
+                bound
+            }
+            ContractCall => self.contract_call_bound(contract, args, depth)?,
+            _ => {
+                let arg_types: Vec<_> = args.iter().map(|arg| expr_type(contract, arg)).collect();
+                let mut bound = self.simple_native_bound(contract, native, &arg_types)?;
+                bound.add(&self.exprs_bound(contract, args, depth)?);
+                bound
+            }
+        };
+        Ok(bound)
+    }
+
+    /// The cost of `(contract-call? target function args...)`, including the target function
+    /// if its contract is known.
+    fn contract_call_bound(
+        &mut self,
+        contract: &ContractAnalysis,
+        args: &[SymbolicExpression],
+        depth: u64,
+    ) -> Result<CostBound, Error> {
+        let mut bound = self.charge(ClarityCostFunction::ContractCall, 0)?;
+        bound.add(&self.exprs_bound(contract, args.get(2..).unwrap_or(&[]), depth)?);
+
+        let Some(function_name) = args.get(1).and_then(|x| x.match_atom()) else {
+            return Ok(bound);
+        };
+        let target = match args.first().map(|x| &x.expr) {
+            Some(LiteralValue(Value::Principal(PrincipalData::Contract(target)))) => {
+                Ok(target.clone())
+            }
+            Some(LiteralValue(Value::CallableContract(callable))) => {
+                Ok(callable.contract_identifier.clone())
+            }
+            Some(Atom(name)) => Err(format!("<{name}>")),
+            _ => Err("<unknown>".to_string()),
+        };
+        let known = match &target {
+            Ok(target) => self.known_contract(target),
+            Err(_) => None,
+        };
+        match known {
+            Some(known)
+                if known
+                    .analysis
+                    .public_function_types
+                    .contains_key(function_name)
+                    || known
+                        .analysis
+                        .read_only_function_types
+                        .contains_key(function_name) =>
+            {
+                bound.add(&self.charge(ClarityCostFunction::LoadContract, known.size)?);
+                bound.add(&self.function_bound(&known.analysis, function_name)?);
+            }
+            _ => {
+                let target = match target {
+                    Ok(target) => target.to_string(),
+                    Err(name) => name,
+                };
+                bound
+                    .unbounded_calls
+                    .insert(format!("{target}::{function_name}"));
+            }
+        }
+        Ok(bound)
+    }
+}
+
+fn has_type_map(contract: &ContractAnalysis) -> bool {
+    contract
+        .type_map
+        .as_ref()
+        .is_some_and(|type_map| type_map.records_types())
+}
+
+fn expr_type<'b>(
+    contract: &'b ContractAnalysis,
+    expr: &SymbolicExpression,
+) -> Option<&'b TypeSignature> {
+    contract.type_map.as_ref()?.get_type_expected(expr)
+}
+
+/// Compute the cost bound of each public and read-only function of `contract` with the default
+/// cost functions of `epoch`, without following `contract-call?`s.
+pub fn check_cost_bounds(
+    contract: &ContractAnalysis,
+    epoch: StacksEpochId,
+    mainnet: bool,
+    block_limit: &ExecutionCost,
+) -> Result<Vec<FunctionCostBound>, Error> {
+    CostBoundsChecker::new(epoch, mainnet)?.run(contract, block_limit)
+}
@@ -0,0 +1,335 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use stacks_common::types::StacksEpochId;
+
+use crate::vm::analysis::cost_bounds::{
+    check_cost_bounds, CostBoundsChecker, Error, FunctionCostBound, KnownContract,
+};
+use crate::vm::analysis::{run_analysis, ContractAnalysis};
+use crate::vm::ast::{parse, ASTRules};
+use crate::vm::costs::cost_functions::ClarityCostFunction;
+use crate::vm::costs::costs_3::Costs3;
+use crate::vm::costs::{ExecutionCost, LimitedCostTracker};
+use crate::vm::database::MemoryBackingStore;
+use crate::vm::tests::cost_tracked_env;
+use crate::vm::tooling::mem_type_check;
+use crate::vm::types::{PrincipalData, QualifiedContractIdentifier, Value};
+use crate::vm::{ClarityVersion, SymbolicExpression};
+
+const EPOCH: StacksEpochId = StacksEpochId::Epoch25;
+
+fn bounds(contract: &str) -> Vec<FunctionCostBound> {
+    let analysis = mem_type_check(contract, ClarityVersion::Clarity2, EPOCH)
+        .unwrap()
+        .1;
+    check_cost_bounds(&analysis, EPOCH, false, &ExecutionCost::max_value()).unwrap()
+}
+
+fn bound(contract: &str, name: &str) -> ExecutionCost {
+    bounds(contract)
+        .into_iter()
+        .find(|bound| bound.name.as_str() == name)
+        .unwrap()
+        .cost
+}
+
+/// Analyze `contracts` in order, saving each in the analysis database so that later contracts
+/// can call earlier ones.
+fn analyze_all(contracts: &[(&str, &str)]) -> Vec<ContractAnalysis> {
+    let mut marf = MemoryBackingStore::new();
+    let mut db = marf.as_analysis_db();
+    db.execute(|db| {
+        contracts
+            .iter()
+            .map(|(name, src)| {
+                let contract_id = QualifiedContractIdentifier::local(name).unwrap();
+                let expressions =
+                    parse(&contract_id, src, ClarityVersion::Clarity2, EPOCH).unwrap();
+                run_analysis(
+                    &contract_id,
+                    &expressions,
+                    db,
+                    true,
+                    LimitedCostTracker::new_free(),
+                    EPOCH,
+                    ClarityVersion::Clarity2,
+                    true,
+                )
+                .map_err(|(e, _)| e)
+            })
+            .collect()
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_constant_function() {
+    let cost = bound("(define-read-only (one) u1)", "one");
+    assert_eq!(
+        cost,
+        ClarityCostFunction::UserFunctionApplication
+            .eval::<Costs3>(1)
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_only_public_and_read_only_functions() {
+    let bounds = bounds(
+        "(define-private (helper) u1)
+         (define-read-only (get-one) (helper))
+         (define-public (set-one) (ok (helper)))",
+    );
+    let names: Vec<_> = bounds
+        .iter()
+        .map(|bound| (bound.name.as_str(), bound.read_only))
+        .collect();
+    assert_eq!(names, vec![("get-one", true), ("set-one", false)]);
+    // the private function's body is charged to its callers
+    assert!(bounds[0].cost.runtime > bound("(define-read-only (one) u1)", "one").runtime);
+}
+
+#[test]
+fn test_worst_branch() {
+    let contract = "(define-data-var v uint u0)
+         (define-read-only (one-read (flag bool)) (if flag (var-get v) u0))
+         (define-read-only (two-reads) (+ (var-get v) (var-get v)))
+         (define-read-only (match-read (x (optional uint)))
+           (match x value (+ value (var-get v)) u0))";
+    assert_eq!(bound(contract, "one-read").read_count, 1);
+    assert_eq!(bound(contract, "two-reads").read_count, 2);
+    assert_eq!(bound(contract, "match-read").read_count, 1);
+}
+
+#[test]
+fn test_iteration_over_bounded_sequences() {
+    let sum = |len: u32| {
+        format!(
+            "(define-map totals uint uint)
+             (define-private (add-total (key uint) (acc uint))
+               (+ acc (default-to u0 (map-get? totals key))))
+             (define-read-only (sum (keys (list {len} uint))) (fold add-total keys u0))"
+        )
+    };
+    let short = bound(&sum(10), "sum");
+    let long = bound(&sum(100), "sum");
+    assert_eq!(short.read_count, 10);
+    assert_eq!(long.read_count, 100);
+    let per_element = ClarityCostFunction::Add.eval::<Costs3>(2).unwrap().runtime;
+    assert!(long.runtime - short.runtime > 90 * per_element);
+
+    // `map` stops at its shortest sequence
+    let mapped = bound(
+        "(define-data-var v uint u0)
+         (define-private (read-one (x uint) (y uint)) (+ x y (var-get v)))
+         (define-read-only (read-all (xs (list 20 uint)) (ys (list 5 uint)))
+           (map read-one xs ys))",
+        "read-all",
+    );
+    assert_eq!(mapped.read_count, 5);
+}
+
+#[test]
+fn test_block_limit() {
+    let contract = "(define-map entries uint (buff 1024))
+         (define-private (set-entry (key uint)) (map-set entries key 0x00))
+         (define-public (set-many (keys (list 20000 uint))) (ok (map set-entry keys)))
+         (define-public (set-one (key uint)) (ok (set-entry key)))";
+    let analysis = mem_type_check(contract, ClarityVersion::Clarity2, EPOCH)
+        .unwrap()
+        .1;
+    let limit = ExecutionCost {
+        write_length: 15_000_000,
+        write_count: 15_000,
+        read_length: 100_000_000,
+        read_count: 15_000,
+        runtime: 5_000_000_000,
+    };
+    let bounds = check_cost_bounds(&analysis, EPOCH, true, &limit).unwrap();
+    assert_eq!(bounds[0].name.as_str(), "set-many");
+    assert!(bounds[0].exceeds_block_limit);
+    assert_eq!(bounds[0].cost.write_count, 20_000);
+    assert_eq!(bounds[1].name.as_str(), "set-one");
+    assert!(!bounds[1].exceeds_block_limit);
+}
+
+#[test]
+fn test_contract_calls() {
+    let analyses = analyze_all(&[
+        (
+            "callee",
+            "(define-data-var counter uint u0)
+             (define-public (incr) (ok (var-set counter (+ u1 (var-get counter)))))",
+        ),
+        (
+            "caller",
+            "(define-trait incr-trait ((incr () (response bool uint))))
+             (define-public (call-known) (contract-call? .callee incr))
+             (define-public (call-trait (target <incr-trait>)) (contract-call? target incr))",
+        ),
+    ]);
+    let [callee, caller] = &analyses[..] else {
+        panic!("expected two analyses");
+    };
+    let callee_id = callee.contract_identifier.clone();
+
+    // without a resolver, both calls are unbounded
+    let bounds = check_cost_bounds(caller, EPOCH, false, &ExecutionCost::max_value()).unwrap();
+    assert_eq!(
+        bounds[0].unbounded_calls.iter().collect::<Vec<_>>(),
+        vec![&format!("{callee_id}::incr")]
+    );
+    assert_eq!(
+        bounds[1].unbounded_calls.iter().collect::<Vec<_>>(),
+        vec!["<target>::incr"]
+    );
+
+    let mut resolved = vec![];
+    let bounds = CostBoundsChecker::new(EPOCH, false)
+        .unwrap()
+        .with_resolver(|contract_id| {
+            resolved.push(contract_id.clone());
+            (contract_id == &callee_id).then(|| KnownContract {
+                analysis: callee.clone(),
+                size: 1000,
+            })
+        })
+        .run(caller, &ExecutionCost::max_value())
+        .unwrap();
+    let callee_bound = check_cost_bounds(callee, EPOCH, false, &ExecutionCost::max_value())
+        .unwrap()
+        .remove(0)
+        .cost;
+    let call_known = &bounds[0];
+    assert!(call_known.unbounded_calls.is_empty());
+    assert_eq!(call_known.cost.write_count, callee_bound.write_count);
+    assert!(
+        call_known.cost.runtime
+            > callee_bound.runtime
+                + ClarityCostFunction::LoadContract
+                    .eval::<Costs3>(1000)
+                    .unwrap()
+                    .runtime
+    );
+    assert_eq!(
+        bounds[1].unbounded_calls.iter().collect::<Vec<_>>(),
+        vec!["<target>::incr"]
+    );
+    assert_eq!(resolved, vec![callee_id]);
+}
+
+#[test]
+fn test_missing_type_map() {
+    let contract_id = QualifiedContractIdentifier::transient();
+    let expressions = parse(
+        &contract_id,
+        "(define-read-only (one) u1)",
+        ClarityVersion::Clarity2,
+        EPOCH,
+    )
+    .unwrap();
+    let mut marf = MemoryBackingStore::new();
+    let analysis = run_analysis(
+        &contract_id,
+        &expressions,
+        &mut marf.as_analysis_db(),
+        false,
+        LimitedCostTracker::new_free(),
+        EPOCH,
+        ClarityVersion::Clarity2,
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        check_cost_bounds(&analysis, EPOCH, false, &ExecutionCost::max_value()),
+        Err(Error::MissingTypeMap(contract_id))
+    );
+    assert_eq!(
+        CostBoundsChecker::new(StacksEpochId::Epoch10, false).err(),
+        Some(Error::NoCostFunctions(StacksEpochId::Epoch10))
+    );
+}
+
+/// Compare the bound of each function with the cost of actually calling it with the given
+/// (worst-case) arguments.  Calling a function also charges for loading its contract, which is
+/// not part of the bound, so that cost is measured by calling `noop`.
+#[test]
+fn test_bounds_cover_execution() {
+    let contract = "(define-map names principal (string-ascii 64))
+         (define-data-var total uint u0)
+         (define-read-only (noop) true)
+         (define-private (register-one (name (string-ascii 64)) (count uint))
+           (begin (map-set names tx-sender name) (+ count u1)))
+         (define-public (register (names-list (list 8 (string-ascii 64))))
+           (let ((count (fold register-one names-list u0)))
+             (var-set total (+ (var-get total) count))
+             (ok (concat \"registered: \" (int-to-ascii count)))))
+         (define-read-only (lookup (who principal))
+           (match (map-get? names who) name (ok (len name)) (err u404)))";
+    let bounds = bounds(contract);
+    let bound_of = |name: &str| {
+        bounds
+            .iter()
+            .find(|bound| bound.name.as_str() == name)
+            .unwrap()
+            .cost
+            .clone()
+    };
+
+    let contract_id = QualifiedContractIdentifier::local("registry").unwrap();
+    let sender = PrincipalData::parse("S1G2081040G2081040G2081040G208105NK8PE5").unwrap();
+    let mut marf = MemoryBackingStore::new();
+    let mut owned_env = cost_tracked_env(marf.as_clarity_db(), EPOCH);
+    owned_env
+        .initialize_versioned_contract(
+            contract_id.clone(),
+            ClarityVersion::Clarity2,
+            contract,
+            None,
+            ASTRules::PrecheckSize,
+        )
+        .unwrap();
+    let mut measure = |function: &str, args: Vec<Value>| {
+        let args: Vec<_> = args
+            .into_iter()
+            .map(SymbolicExpression::atom_value)
+            .collect();
+        let before = owned_env.get_cost_total();
+        owned_env
+            .execute_transaction(sender.clone(), None, contract_id.clone(), function, &args)
+            .unwrap();
+        let mut cost = owned_env.get_cost_total();
+        cost.sub(&before).unwrap();
+        cost
+    };
+
+    let name = Value::string_ascii_from_bytes(vec![b'a'; 64]).unwrap();
+    let names = Value::cons_list_unsanitized(vec![name; 8]).unwrap();
+    let noop = measure("noop", vec![]);
+    let register = measure("register", vec![names]);
+    let lookup = measure("lookup", vec![Value::Principal(sender.clone())]);
+
+    for (name, mut measured) in [("register", register), ("lookup", lookup)] {
+        // the cost of loading the contract is at least `noop`'s measured cost, less its bound
+        measured.sub(&noop).unwrap();
+        measured.add(&bound_of("noop")).unwrap();
+        let bound = bound_of(name);
+        assert!(
+            !measured.exceeds(&bound),
+            "{name}: measured {measured} exceeds bound {bound}"
+        );
+    }
+}
@@ -17,6 +17,7 @@
 pub mod analysis_db;
 pub mod arithmetic_checker;
 pub mod contract_interface_builder;
+pub mod cost_bounds;
 pub mod errors;
 pub mod read_only_checker;
 pub mod trait_checker;
@@ -84,6 +84,12 @@ impl TypeMap {
         }
     }
 
+    /// Whether this records the type of each expression, rather than only which expressions
+    /// were visited
+    pub fn records_types(&self) -> bool {
+        matches!(self.map, TypeMapDataType::Map(_))
+    }
+
     pub fn get_type_expected(&self, expr: &SymbolicExpression) -> Option<&TypeSignature> {
         match self.map {
             TypeMapDataType::Map(ref map) => map.get(&expr.id),
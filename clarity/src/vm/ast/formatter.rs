@@ -0,0 +1,661 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A canonical pretty-printer for Clarity source code.
+//!
+//! The formatter works on the v2 lexer's tokens, so that comments and the exact spelling of
+//! literals survive.  A list is printed on one line if it fits and contains no comments or blank
+//! lines.  Otherwise, its head and first argument stay on the opening line (only the head for
+//! `begin`), and each remaining argument goes on its own line, indented by two spaces.  Lists
+//! that do not start with a name (e.g. `let` bindings) are aligned one column past their opening
+//! parenthesis, and tuples put one `key: value` entry per line.  Comments stay on their own line
+//! or at the end of the line they were on, and runs of blank lines are collapsed into one.
+//!
+//! `format_contract` only returns formatted source that parses to the same AST as the input.
+
+use std::fmt;
+
+use stacks_common::types::StacksEpochId;
+
+use crate::vm::ast::parser::v2::lexer::token::Token;
+use crate::vm::ast::parser::v2::lexer::Lexer;
+use crate::vm::ast::{build_ast_with_rules, ASTRules};
+use crate::vm::diagnostic::DiagnosableError;
+use crate::vm::representations::{Span, SymbolicExpression, SymbolicExpressionType};
+use crate::vm::types::QualifiedContractIdentifier;
+use crate::vm::ClarityVersion;
+
+/// Lines are kept within this width where possible
+pub const MAX_LINE_WIDTH: usize = 80;
+const INDENT: usize = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatError {
+    /// The source could not be parsed
+    Parse(String),
+    /// The formatted source did not parse to the same AST as the original.  This is a bug in the
+    /// formatter; the original source should be left as it is.
+    AstMismatch,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::Parse(msg) => write!(f, "failed to parse source: {}", msg),
+            FormatError::AstMismatch => {
+                write!(f, "formatting would change the program (formatter bug)")
+            }
+        }
+    }
+}
+
+/// Format Clarity source in the canonical style.  Fails if the source does not parse, or if the
+/// formatted source would not parse to an identical AST.
+pub fn format_contract(
+    source: &str,
+    clarity_version: ClarityVersion,
+    epoch: StacksEpochId,
+) -> Result<String, FormatError> {
+    let original = parse_expressions(source, clarity_version, epoch)?;
+    let items = build_tree(source)?;
+    let formatted = render_top_level(&items);
+    let reparsed = parse_expressions(&formatted, clarity_version, epoch)
+        .map_err(|_| FormatError::AstMismatch)?;
+    if !same_expressions(&original, &reparsed) {
+        return Err(FormatError::AstMismatch);
+    }
+    Ok(formatted)
+}
+
+fn parse_expressions(
+    source: &str,
+    clarity_version: ClarityVersion,
+    epoch: StacksEpochId,
+) -> Result<Vec<SymbolicExpression>, FormatError> {
+    build_ast_with_rules(
+        &QualifiedContractIdentifier::transient(),
+        source,
+        &mut (),
+        clarity_version,
+        epoch,
+        ASTRules::PrecheckSize,
+    )
+    .map(|ast| ast.expressions)
+    .map_err(|e| FormatError::Parse(e.to_string()))
+}
+
+/// Compare two ASTs, ignoring expression ids and source locations
+fn same_expressions(a: &[SymbolicExpression], b: &[SymbolicExpression]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b.iter())
+            .all(|(a, b)| match (&a.expr, &b.expr) {
+                (SymbolicExpressionType::List(a), SymbolicExpressionType::List(b)) => {
+                    same_expressions(a, b)
+                }
+                (a, b) => a == b,
+            })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GroupKind {
+    List,
+    Tuple,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// A token, or several tokens with no space between them (e.g. `.contract.trait`), as written
+    Atom(String),
+    /// A comment, as written
+    Comment(String),
+    /// A `:` or `,` in a tuple
+    Punct(char),
+    Group(GroupKind, Vec<Item>),
+    /// A `key: value` tuple entry
+    Entry(String, Box<Node>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Item {
+    node: Node,
+    /// The item was preceded by a blank line
+    blank_before: bool,
+    /// The item is a comment at the end of a line of code
+    trailing: bool,
+}
+
+/// Get the text of a token from the source, given the byte offset of each line.
+/// Span columns count characters, so they are mapped to byte offsets within their line.
+fn span_text<'a>(source: &'a str, line_starts: &[usize], span: &Span) -> &'a str {
+    // byte range of the character at `line:column`
+    let char_at = |line: u32, column: u32| {
+        let Some(start) = line_starts.get((line as usize).saturating_sub(1)) else {
+            return (source.len(), source.len());
+        };
+        source[*start..]
+            .char_indices()
+            .nth((column as usize).saturating_sub(1))
+            .map(|(i, c)| (start + i, start + i + c.len_utf8()))
+            .unwrap_or((source.len(), source.len()))
+    };
+    let (start, _) = char_at(span.start_line, span.start_column);
+    let (_, end) = char_at(span.end_line, span.end_column);
+    source.get(start..end.max(start)).unwrap_or("")
+}
+
+fn build_tree(source: &str) -> Result<Vec<Item>, FormatError> {
+    let mut line_starts = vec![0];
+    line_starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+
+    let mut lexer = Lexer::new(source, true).map_err(|e| FormatError::Parse(e.message()))?;
+    // the open groups, with the items before each one
+    let mut stack: Vec<(GroupKind, bool, Vec<Item>)> = vec![];
+    let mut items: Vec<Item> = vec![];
+    let mut last_span: Option<Span> = None;
+    let mut last_was_atom = false;
+
+    loop {
+        let placed = lexer
+            .read_token()
+            .map_err(|e| FormatError::Parse(e.message()))?;
+        let span = placed.span;
+        let blank_before = last_span
+            .as_ref()
+            .map(|last| span.start_line > last.end_line + 1)
+            .unwrap_or(false);
+        let same_line = last_span
+            .as_ref()
+            .map(|last| span.start_line == last.end_line)
+            .unwrap_or(false);
+        let adjacent = last_span
+            .as_ref()
+            .map(|last| same_line && span.start_column == last.end_column + 1)
+            .unwrap_or(false);
+
+        let mut is_atom = false;
+        match placed.token {
+            Token::Eof => break,
+            Token::Whitespace => continue,
+            Token::Lparen | Token::Lbrace => {
+                let kind = if placed.token == Token::Lparen {
+                    GroupKind::List
+                } else {
+                    GroupKind::Tuple
+                };
+                stack.push((kind, blank_before, std::mem::take(&mut items)));
+            }
+            Token::Rparen | Token::Rbrace => {
+                let (kind, group_blank_before, parent_items) = stack
+                    .pop()
+                    .ok_or_else(|| FormatError::Parse("unbalanced brackets".into()))?;
+                let children = std::mem::replace(&mut items, parent_items);
+                let children = match kind {
+                    GroupKind::List => children,
+                    GroupKind::Tuple => tuple_entries(children)?,
+                };
+                items.push(Item {
+                    node: Node::Group(kind, children),
+                    blank_before: group_blank_before,
+                    trailing: false,
+                });
+            }
+            Token::Colon | Token::Comma => {
+                let punct = if placed.token == Token::Colon {
+                    ':'
+                } else {
+                    ','
+                };
+                items.push(Item {
+                    node: Node::Punct(punct),
+                    blank_before,
+                    trailing: false,
+                });
+            }
+            Token::Comment(_) => {
+                let text = span_text(source, &line_starts, &span).trim_end();
+                items.push(Item {
+                    node: Node::Comment(text.to_string()),
+                    blank_before,
+                    trailing: same_line,
+                });
+            }
+            _ => {
+                let text = span_text(source, &line_starts, &span);
+                is_atom = true;
+                match items.last_mut() {
+                    Some(Item {
+                        node: Node::Atom(ref mut atom),
+                        ..
+                    }) if last_was_atom && adjacent => atom.push_str(text),
+                    _ => items.push(Item {
+                        node: Node::Atom(text.to_string()),
+                        blank_before,
+                        trailing: false,
+                    }),
+                }
+            }
+        }
+        last_was_atom = is_atom;
+        last_span = Some(span);
+    }
+
+    if !stack.is_empty() {
+        return Err(FormatError::Parse("unbalanced brackets".into()));
+    }
+    if items.iter().any(|item| matches!(item.node, Node::Punct(_))) {
+        return Err(FormatError::Parse("unexpected `:` or `,`".into()));
+    }
+    Ok(items)
+}
+
+/// Turn the tokens of a tuple into `key: value` entries and comments.  Comments between a key
+/// and its value are moved before the entry.
+fn tuple_entries(children: Vec<Item>) -> Result<Vec<Item>, FormatError> {
+    let mut entries = vec![];
+    let mut pending_comments = vec![];
+    let mut iter = children.into_iter().peekable();
+    while let Some(item) = iter.next() {
+        match item.node {
+            Node::Comment(_) => entries.push(item),
+            Node::Punct(',') => {}
+            Node::Atom(key) => {
+                let mut value = None;
+                let mut seen_colon = false;
+                for next in iter.by_ref() {
+                    match next.node {
+                        Node::Comment(_) => pending_comments.push(Item {
+                            trailing: false,
+                            ..next
+                        }),
+                        Node::Punct(':') if !seen_colon => seen_colon = true,
+                        Node::Punct(_) => {
+                            return Err(FormatError::Parse("malformed tuple".into()));
+                        }
+                        node => {
+                            value = Some(node);
+                            break;
+                        }
+                    }
+                }
+                let value = value.ok_or_else(|| FormatError::Parse("malformed tuple".into()))?;
+                entries.append(&mut pending_comments);
+                entries.push(Item {
+                    node: Node::Entry(key, Box::new(value)),
+                    blank_before: item.blank_before,
+                    trailing: false,
+                });
+            }
+            _ => return Err(FormatError::Parse("malformed tuple".into())),
+        }
+    }
+    Ok(entries)
+}
+
+/// Render a node on one line, if it has no comments or blank lines
+fn flat(node: &Node) -> Option<String> {
+    match node {
+        Node::Atom(text) => Some(text.clone()),
+        Node::Comment(_) | Node::Punct(_) => None,
+        Node::Entry(key, value) => Some(format!("{}: {}", key, flat(value)?)),
+        Node::Group(kind, items) => {
+            let parts = items
+                .iter()
+                .map(|item| {
+                    if item.blank_before {
+                        None
+                    } else {
+                        flat(&item.node)
+                    }
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(match kind {
+                GroupKind::List => format!("({})", parts.join(" ")),
+                GroupKind::Tuple => format!("{{ {} }}", parts.join(", ")),
+            })
+        }
+    }
+}
+
+/// How many arguments stay on the opening line of a list that is broken over several lines
+fn header_args(head: &str) -> usize {
+    match head {
+        "begin" => 0,
+        _ => 1,
+    }
+}
+
+fn pad(out: &mut String, column: usize) {
+    out.push('\n');
+    out.push_str(&" ".repeat(column));
+}
+
+/// The column after the last line of `out`, if `out` starts at column `start`
+fn end_column(out: &str, start: usize) -> usize {
+    match out.rfind('\n') {
+        Some(i) => out.len() - i - 1,
+        None => start + out.len(),
+    }
+}
+
+/// Render a node that starts at column `column`
+fn render(node: &Node, column: usize) -> String {
+    if let Some(line) = flat(node) {
+        if column + line.len() <= MAX_LINE_WIDTH {
+            return line;
+        }
+    }
+    match node {
+        Node::Atom(text) | Node::Comment(text) => text.clone(),
+        Node::Punct(punct) => punct.to_string(),
+        Node::Entry(key, value) => {
+            format!("{}: {}", key, render(value, column + key.len() + 2))
+        }
+        Node::Group(GroupKind::Tuple, items) => render_tuple(items, column),
+        Node::Group(GroupKind::List, items) => render_list(items, column),
+    }
+}
+
+fn render_list(items: &[Item], column: usize) -> String {
+    let (header, body_column) = match items.first().map(|item| &item.node) {
+        Some(Node::Atom(head)) => (header_args(head), column + INDENT),
+        // data, such as `let` bindings, is aligned past the parenthesis
+        _ => (0, column + 1),
+    };
+    render_list_with(items, column, header, body_column)
+}
+
+/// Render a list that does not fit on one line, keeping `header` arguments after its head on
+/// the opening line, and putting each other item on its own line at `body_column`
+fn render_list_with(items: &[Item], column: usize, header: usize, body_column: usize) -> String {
+    let mut out = String::from("(");
+    let Some((first, rest)) = items.split_first() else {
+        out.push(')');
+        return out;
+    };
+    let is_definition = matches!(first.node, Node::Atom(ref head) if head.starts_with("define-"));
+    out.push_str(&render(&first.node, column + 1));
+
+    let mut index = 0;
+    // whether the output ends in a comment, and so needs a new line
+    let mut open_comment = false;
+    while index < rest.len() && index < header {
+        let item = &rest[index];
+        if item.blank_before || matches!(item.node, Node::Comment(_)) {
+            break;
+        }
+        let start = end_column(&out, column) + 1;
+        let rendered = match (&item.node, flat(&item.node)) {
+            (_, Some(line)) if start + line.len() <= MAX_LINE_WIDTH => line,
+            // a tuple that does not fit hangs from this list's indentation
+            (Node::Group(GroupKind::Tuple, entries), _) => render_tuple(entries, column),
+            // a function signature puts each parameter under the function's name
+            (Node::Group(GroupKind::List, signature), _) if is_definition => {
+                render_list_with(signature, start, 0, start + 1)
+            }
+            // data, such as `let` bindings, is aligned past its parenthesis
+            (Node::Group(GroupKind::List, data), _)
+                if !matches!(data.first().map(|item| &item.node), Some(Node::Atom(_))) =>
+            {
+                render_list(data, start)
+            }
+            // anything else that does not fit goes on its own line
+            _ => break,
+        };
+        out.push(' ');
+        out.push_str(&rendered);
+        index += 1;
+    }
+
+    for item in rest[index..].iter() {
+        if item.trailing {
+            out.push(' ');
+            out.push_str(&render(&item.node, 0));
+            open_comment = true;
+            continue;
+        }
+        if item.blank_before {
+            out.push('\n');
+        }
+        pad(&mut out, body_column);
+        out.push_str(&render(&item.node, body_column));
+        open_comment = matches!(item.node, Node::Comment(_));
+    }
+
+    if open_comment {
+        pad(&mut out, column);
+    }
+    out.push(')');
+    out
+}
+
+fn render_tuple(items: &[Item], column: usize) -> String {
+    let mut out = String::from("{");
+    let body_column = column + INDENT;
+    let last_entry = items
+        .iter()
+        .rposition(|item| matches!(item.node, Node::Entry(..)));
+    for (i, item) in items.iter().enumerate() {
+        if item.trailing {
+            out.push(' ');
+            out.push_str(&render(&item.node, 0));
+            continue;
+        }
+        if item.blank_before {
+            out.push('\n');
+        }
+        pad(&mut out, body_column);
+        out.push_str(&render(&item.node, body_column));
+        if matches!(item.node, Node::Entry(..)) && Some(i) != last_entry {
+            out.push(',');
+        }
+    }
+    pad(&mut out, column);
+    out.push('}');
+    out
+}
+
+fn render_top_level(items: &[Item]) -> String {
+    let mut out = String::new();
+    for item in items.iter() {
+        if item.trailing {
+            out.push(' ');
+            out.push_str(&render(&item.node, 0));
+            continue;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+            if item.blank_before {
+                out.push('\n');
+            }
+        }
+        out.push_str(&render(&item.node, 0));
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(source: &str) -> String {
+        format_contract(source, ClarityVersion::Clarity2, StacksEpochId::Epoch25).unwrap()
+    }
+
+    #[test]
+    fn test_format_short_forms_stay_flat() {
+        assert_eq!(
+            fmt("(define-constant   ERR_UNAUTHORIZED\n  (err   u401))"),
+            "(define-constant ERR_UNAUTHORIZED (err u401))\n"
+        );
+        assert_eq!(
+            fmt("(define-map balances principal {amount:u1,since:   u2})"),
+            "(define-map balances principal { amount: u1, since: u2 })\n"
+        );
+        assert_eq!(
+            fmt("(define-read-only (get-owner) (ok (contract-call? .registry owner-of   u1)))"),
+            "(define-read-only (get-owner) (ok (contract-call? .registry owner-of u1)))\n"
+        );
+    }
+
+    #[test]
+    fn test_format_long_forms_break() {
+        let source = r#"(define-public (transfer (amount uint) (sender principal) (recipient principal)) (begin (asserts! (is-eq tx-sender sender) (err u1)) (let ((balance (get-balance sender)) (fee u10)) (print {event: "transfer", amount: amount, sender: sender, recipient: recipient, fee: fee}) (ok true))))"#;
+        let expected = r#"(define-public (transfer (amount uint) (sender principal) (recipient principal))
+  (begin
+    (asserts! (is-eq tx-sender sender) (err u1))
+    (let ((balance (get-balance sender)) (fee u10))
+      (print {
+        event: "transfer",
+        amount: amount,
+        sender: sender,
+        recipient: recipient,
+        fee: fee
+      })
+      (ok true))))
+"#;
+        let source = format!("(define-private (get-balance (who principal)) u0)\n{source}");
+        let expected = format!("(define-private (get-balance (who principal)) u0)\n{expected}");
+        assert_eq!(fmt(&source), expected);
+
+        let source = "(define-read-only (f) (let ((first-value (some-function-with-a-long-name u1 u2 u3)) (second-value (another-function u4 u5 u6))) (+ first-value second-value)))";
+        let expected = r#"(define-read-only (f)
+  (let ((first-value (some-function-with-a-long-name u1 u2 u3))
+        (second-value (another-function u4 u5 u6)))
+    (+ first-value second-value)))
+"#;
+        assert_eq!(fmt(source), expected);
+    }
+
+    #[test]
+    fn test_format_keeps_comments() {
+        let source = r#";;;; Token contract
+;; with a header
+
+(define-data-var supply uint u0) ;; total supply
+
+
+;; mint tokens
+(define-public (mint (amount uint))
+  (begin ;; start
+    ;; bump the supply
+    (var-set supply (+ (var-get supply) amount)) ;; no overflow check
+    (ok true) ;; done
+  )
+)
+"#;
+        let expected = r#";;;; Token contract
+;; with a header
+
+(define-data-var supply uint u0) ;; total supply
+
+;; mint tokens
+(define-public (mint (amount uint))
+  (begin ;; start
+    ;; bump the supply
+    (var-set supply (+ (var-get supply) amount)) ;; no overflow check
+    (ok true) ;; done
+  ))
+"#;
+        let formatted = fmt(source);
+        assert_eq!(formatted, expected);
+        // formatting is idempotent
+        assert_eq!(fmt(&formatted), formatted);
+    }
+
+    #[test]
+    fn test_format_keeps_literals_as_written() {
+        let source = "(define-constant owner 'SP000000000000000000002Q6VF78.pox-4)\n(define-constant s u\"caf\\u{e9}\")\n(define-constant b 0xDEADbeef)\n(define-constant neg -0001)\n(define-constant t (contract-call? .pox-4 foo \"a\\\"b\"))\n";
+        assert_eq!(fmt(source), source);
+    }
+
+    #[test]
+    fn test_span_text_non_ascii() {
+        let source = "(a \"\u{e9}t\u{e9}\")\n;; caf\u{e9} \u{1f44d}\n(b)";
+        let mut line_starts = vec![0];
+        line_starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+        let span = |start_line, start_column, end_line, end_column| Span {
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        };
+        assert_eq!(
+            span_text(source, &line_starts, &span(1, 4, 1, 8)),
+            "\"\u{e9}t\u{e9}\""
+        );
+        assert_eq!(span_text(source, &line_starts, &span(1, 9, 1, 9)), ")");
+        assert_eq!(
+            span_text(source, &line_starts, &span(2, 1, 2, 9)),
+            ";; caf\u{e9} \u{1f44d}"
+        );
+        assert_eq!(span_text(source, &line_starts, &span(3, 2, 3, 2)), "b");
+        // out-of-range spans don't panic
+        assert_eq!(span_text(source, &line_starts, &span(4, 1, 4, 3)), "");
+        assert_eq!(
+            span_text(source, &line_starts, &span(2, 9, 3, 2)),
+            "\u{1f44d}\n(b"
+        );
+        assert_eq!(span_text(source, &line_starts, &span(3, 2, 3, 20)), "b)");
+    }
+
+    #[test]
+    fn test_format_rejects_non_ascii_source() {
+        assert!(matches!(
+            format_contract(
+                ";; caf\u{e9}\n(define-constant a u1)",
+                ClarityVersion::Clarity2,
+                StacksEpochId::Epoch25
+            ),
+            Err(FormatError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_rejects_invalid_source() {
+        assert!(matches!(
+            format_contract(
+                "(define-public (foo)",
+                ClarityVersion::Clarity2,
+                StacksEpochId::Epoch25
+            ),
+            Err(FormatError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_sample_contracts() {
+        let sample_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../sample/contracts");
+        for entry in std::fs::read_dir(sample_dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("clar") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            let formatted =
+                format_contract(&source, ClarityVersion::Clarity2, StacksEpochId::Epoch25)
+                    .unwrap_or_else(|e| panic!("failed to format {:?}: {}", &path, e));
+            assert_eq!(
+                fmt(&formatted),
+                formatted,
+                "formatting {:?} is not idempotent",
+                &path
+            );
+        }
+    }
+}
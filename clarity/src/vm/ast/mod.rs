@@ -20,6 +20,7 @@ pub mod parser;
 pub mod traits_resolver;
 
 pub mod errors;
+pub mod formatter;
 pub mod stack_depth_checker;
 pub mod sugar_expander;
 pub mod types;
@@ -911,7 +911,7 @@ impl TrackerData {
             if !cost_contracts.contains_key(&cost_function_ref.contract_id) {
                 let contract_context = match clarity_db.get_contract(&cost_function_ref.contract_id)
                 {
-                    Ok(contract) => contract.contract_context,
+                    Ok(contract) => contract.contract_context.clone(),
                     Err(e) => {
                         error!("Failed to load intended Clarity cost contract";
                                "contract" => %cost_function_ref.contract_id,
@@ -938,7 +938,7 @@ impl TrackerData {
         for (_, circuit_target) in self.contract_call_circuits.iter() {
             if !cost_contracts.contains_key(&circuit_target.contract_id) {
                 let contract_context = match clarity_db.get_contract(&circuit_target.contract_id) {
-                    Ok(contract) => contract.contract_context,
+                    Ok(contract) => contract.contract_context.clone(),
                     Err(e) => {
                         error!("Failed to load intended Clarity cost contract";
                                "contract" => %boot_costs_id.to_string(),
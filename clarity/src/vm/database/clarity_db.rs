@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::sync::Arc;
+
 use stacks_common::consts::{
     BITCOIN_REGTEST_FIRST_BLOCK_HASH, BITCOIN_REGTEST_FIRST_BLOCK_HEIGHT,
     BITCOIN_REGTEST_FIRST_BLOCK_TIMESTAMP, FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH,
@@ -843,16 +845,30 @@ impl<'a> ClarityDatabase<'a> {
     pub fn get_contract(
         &mut self,
         contract_identifier: &QualifiedContractIdentifier,
-    ) -> Result<Contract> {
+    ) -> Result<Arc<Contract>> {
         let key = ClarityDatabase::make_metadata_key(
             StoreType::Contract,
             ContractDataVarName::Contract.as_str(),
         );
-        let mut data: Contract = self.fetch_metadata(contract_identifier, &key)?
+        let epoch = self.get_clarity_epoch_version()?;
+        let cache_entry = self
+            .store
+            .get_contract_cache_entry(contract_identifier, &key, &epoch)?;
+        if let Some((cache, cache_key)) = cache_entry.as_ref() {
+            if let Some(data) = cache.get(cache_key) {
+                return Ok(data);
+            }
+        }
+        let serialized = self.store.get_metadata(contract_identifier, &key)?
             .ok_or_else(|| InterpreterError::Expect(
                 "Failed to read non-consensus contract metadata, even though contract exists in MARF."
                 .into()))?;
-        data.canonicalize_types(&self.get_clarity_epoch_version()?);
+        let mut data = Contract::deserialize(&serialized)?;
+        data.canonicalize_types(&epoch);
+        let data = Arc::new(data);
+        if let Some((cache, cache_key)) = cache_entry {
+            cache.insert(cache_key, data.clone(), serialized.len() as u64);
+        }
         Ok(data)
     }
 
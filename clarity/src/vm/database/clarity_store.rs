@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::sync::Arc;
+
 #[cfg(feature = "canonical")]
 use rusqlite::Connection;
 use stacks_common::types::chainstate::{StacksBlockId, TrieHash};
@@ -21,6 +23,7 @@ use stacks_common::util::hash::{hex_bytes, to_hex, Sha512Trunc256Sum};
 
 use crate::vm::analysis::AnalysisDatabase;
 use crate::vm::contexts::GlobalContext;
+use crate::vm::database::contract_cache::ContractCache;
 #[cfg(feature = "canonical")]
 use crate::vm::database::{
     ClarityDatabase, ClarityDeserializable, ClaritySerializable, NULL_BURN_STATE_DB, NULL_HEADER_DB,
@@ -85,6 +88,13 @@ pub trait ClarityBackingStore {
     fn get_open_chain_tip_height(&mut self) -> u32;
     fn get_open_chain_tip(&mut self) -> StacksBlockId;
 
+    /// Is `block_height` at or above the height of the block that is currently open for
+    /// writing?  Stores that never open a block for writing (e.g. read-only stores) override
+    /// this to always return false.
+    fn is_at_or_above_open_chain_tip(&mut self, block_height: u32) -> bool {
+        block_height >= self.get_open_chain_tip_height()
+    }
+
     #[cfg(feature = "canonical")]
     fn get_side_store(&mut self) -> &Connection;
 
@@ -92,6 +102,11 @@ pub trait ClarityBackingStore {
         None
     }
 
+    /// The cache of deserialized contracts shared by connections to this store, if any
+    fn get_contract_cache(&self) -> Option<Arc<ContractCache>> {
+        None
+    }
+
     /// The contract commitment is the hash of the contract, plus the block height in
     ///   which the contract was initialized.
     fn make_contract_commitment(&mut self, contract_hash: Sha512Trunc256Sum) -> String {
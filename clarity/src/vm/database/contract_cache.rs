@@ -0,0 +1,203 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A bounded cache of deserialized contracts.
+//!
+//! Loading a contract deserializes its whole `ContractContext` from the side store and then
+//! canonicalizes its types for the current epoch.  For frequently-called contracts, this
+//! dominates the cost of a contract call, so `ClarityDatabase::get_contract` consults this
+//! cache first.
+//!
+//! Entries are keyed by the contract's identifier, the index block hash of the block that
+//! deployed it, the hash of its source, and the epoch its types were canonicalized for.  The
+//! deploying block is looked up through the MARF at the chain tip being evaluated, so a fork
+//! switch can never serve a contract that was deployed on another fork: the other fork's
+//! deployment has a different block hash, and thus a different key.  Contracts deployed in the
+//! block that is currently open for writing are never cached, since that block's hash is not
+//! final (e.g. a miner's block under construction).
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use hashbrown::HashMap;
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::types::StacksEpochId;
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+use crate::vm::contracts::Contract;
+use crate::vm::types::QualifiedContractIdentifier;
+
+/// Default bound on the total (serialized) size of the contracts in a cache
+pub const DEFAULT_CONTRACT_CACHE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Identifies one deployment of a contract, canonicalized for one epoch
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContractCacheKey {
+    pub contract_identifier: QualifiedContractIdentifier,
+    /// The index block hash of the block that deployed the contract
+    pub deploy_block: StacksBlockId,
+    /// The hash of the contract's source
+    pub contract_hash: Sha512Trunc256Sum,
+    pub epoch: StacksEpochId,
+}
+
+/// Cache statistics, for metrics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContractCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: u64,
+    /// Total serialized size of the cached contracts, which approximates their memory use
+    pub size: u64,
+    pub max_size: u64,
+}
+
+impl ContractCacheStats {
+    /// Fraction of lookups served from the cache
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits.saturating_add(self.misses);
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+struct ContractCacheEntry {
+    contract: Arc<Contract>,
+    size: u64,
+    last_used: u64,
+}
+
+struct ContractCacheInner {
+    entries: HashMap<ContractCacheKey, ContractCacheEntry>,
+    /// `last_used` ticks of the entries, in least-recently-used order
+    lru: BTreeMap<u64, ContractCacheKey>,
+    tick: u64,
+    stats: ContractCacheStats,
+}
+
+impl ContractCacheInner {
+    fn touch(&mut self, key: &ContractCacheKey) -> Option<&ContractCacheEntry> {
+        self.tick += 1;
+        let tick = self.tick;
+        let entry = self.entries.get_mut(key)?;
+        self.lru.remove(&entry.last_used);
+        self.lru.insert(tick, key.clone());
+        entry.last_used = tick;
+        Some(entry)
+    }
+
+    fn evict_lru(&mut self) -> bool {
+        let Some((_, key)) = self.lru.pop_first() else {
+            return false;
+        };
+        if let Some(entry) = self.entries.remove(&key) {
+            self.stats.size = self.stats.size.saturating_sub(entry.size);
+            self.stats.entries = self.stats.entries.saturating_sub(1);
+            self.stats.evictions += 1;
+        }
+        true
+    }
+}
+
+/// A bounded, least-recently-used cache of deserialized contracts, which can be shared between
+/// Clarity connections (and threads).
+pub struct ContractCache {
+    inner: Mutex<ContractCacheInner>,
+}
+
+impl ContractCache {
+    /// Make a cache that holds contracts with a total serialized size of at most `max_size`
+    /// bytes.
+    pub fn new(max_size: u64) -> ContractCache {
+        ContractCache {
+            inner: Mutex::new(ContractCacheInner {
+                entries: HashMap::new(),
+                lru: BTreeMap::new(),
+                tick: 0,
+                stats: ContractCacheStats {
+                    max_size,
+                    ..ContractCacheStats::default()
+                },
+            }),
+        }
+    }
+
+    /// Look up a contract, counting a hit or a miss
+    pub fn get(&self, key: &ContractCacheKey) -> Option<Arc<Contract>> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let contract = inner.touch(key).map(|entry| entry.contract.clone());
+        if contract.is_some() {
+            inner.stats.hits += 1;
+        } else {
+            inner.stats.misses += 1;
+        }
+        contract
+    }
+
+    /// Cache a contract, whose serialized size is `size` bytes, evicting least-recently-used
+    /// contracts as needed.  Contracts that are larger than the whole cache are not cached.
+    pub fn insert(&self, key: ContractCacheKey, contract: Arc<Contract>, size: u64) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if size > inner.stats.max_size || inner.entries.contains_key(&key) {
+            return;
+        }
+        while inner.stats.size.saturating_add(size) > inner.stats.max_size {
+            if !inner.evict_lru() {
+                break;
+            }
+        }
+        inner.tick += 1;
+        let last_used = inner.tick;
+        inner.lru.insert(last_used, key.clone());
+        inner.entries.insert(
+            key,
+            ContractCacheEntry {
+                contract,
+                size,
+                last_used,
+            },
+        );
+        inner.stats.size = inner.stats.size.saturating_add(size);
+        inner.stats.entries += 1;
+    }
+
+    /// Drop all cached contracts
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.entries.clear();
+        inner.lru.clear();
+        inner.stats.size = 0;
+        inner.stats.entries = 0;
+    }
+
+    /// The number of cached contracts with this identifier, across all forks and epochs
+    pub fn entries_for(&self, contract_identifier: &QualifiedContractIdentifier) -> usize {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner
+            .entries
+            .keys()
+            .filter(|key| &key.contract_identifier == contract_identifier)
+            .count()
+    }
+
+    pub fn stats(&self) -> ContractCacheStats {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.stats.clone()
+    }
+}
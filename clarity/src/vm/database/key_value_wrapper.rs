@@ -15,6 +15,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::hash::Hash;
+use std::sync::Arc;
 
 use hashbrown::HashMap;
 use stacks_common::types::chainstate::{StacksBlockId, TrieHash};
@@ -23,8 +24,9 @@ use stacks_common::util::hash::Sha512Trunc256Sum;
 
 use super::clarity_store::SpecialCaseHandler;
 use super::{ClarityBackingStore, ClarityDeserializable};
-use crate::vm::database::clarity_store::make_contract_hash_key;
-use crate::vm::errors::{InterpreterError, InterpreterResult};
+use crate::vm::database::clarity_store::{make_contract_hash_key, ContractCommitment};
+use crate::vm::database::contract_cache::{ContractCache, ContractCacheKey};
+use crate::vm::errors::{CheckErrors, InterpreterError, InterpreterResult};
 use crate::vm::types::serialization::SerializationError;
 use crate::vm::types::{QualifiedContractIdentifier, TypeSignature};
 use crate::vm::Value;
@@ -230,6 +232,59 @@ impl<'a> RollbackWrapper<'a> {
         self.store.get_cc_special_cases_handler()
     }
 
+    /// Get the backing store's contract cache, and the key of a contract's entry in it.
+    /// Returns None if there is no cache, or if the contract's metadata may not be final:
+    /// either it is still pending in this wrapper, or the contract was deployed in the block
+    /// that is open for writing.
+    pub fn get_contract_cache_entry(
+        &mut self,
+        contract: &QualifiedContractIdentifier,
+        key: &str,
+        epoch: &StacksEpochId,
+    ) -> InterpreterResult<Option<(Arc<ContractCache>, ContractCacheKey)>> {
+        let Some(cache) = self.store.get_contract_cache() else {
+            return Ok(None);
+        };
+        if self
+            .metadata_lookup_map
+            .contains_key(&(contract.clone(), key.to_string()))
+            || self
+                .lookup_map
+                .contains_key(&make_contract_hash_key(contract))
+        {
+            return Ok(None);
+        }
+        let commitment = self
+            .store
+            .get_data(&make_contract_hash_key(contract))?
+            .map(|x| ContractCommitment::deserialize(&x))
+            .transpose()?
+            .ok_or_else(|| CheckErrors::NoSuchContract(contract.to_string()))?;
+        // the commitment's height is that of the block the contract was deployed in, so this
+        // also catches a deployment in the open block whose commitment was already flushed here
+        if self
+            .store
+            .is_at_or_above_open_chain_tip(commitment.block_height)
+        {
+            return Ok(None);
+        }
+        let deploy_block = self
+            .store
+            .get_block_at_height(commitment.block_height)
+            .ok_or_else(|| {
+                InterpreterError::Expect(
+                    "Should always be able to map from height to block hash when looking up contract information.".into(),
+                )
+            })?;
+        let cache_key = ContractCacheKey {
+            contract_identifier: contract.clone(),
+            deploy_block,
+            contract_hash: commitment.hash,
+            epoch: *epoch,
+        };
+        Ok(Some((cache, cache_key)))
+    }
+
     pub fn nest(&mut self) {
         self.stack.push(RollbackContext {
             edits: Vec::new(),
@@ -31,7 +31,9 @@ pub use self::structures::{
 
 pub mod clarity_db;
 pub mod clarity_store;
+pub mod contract_cache;
 mod key_value_wrapper;
 #[cfg(feature = "canonical")]
 pub mod sqlite;
+pub mod state_proofs;
 mod structures;
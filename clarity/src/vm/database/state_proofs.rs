@@ -0,0 +1,302 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verification of Clarity state proofs, such as the batch proofs returned by the
+//! `/v2/map_entry` and `/v2/clarity/marf` RPC endpoints.
+//!
+//! Verification only needs the proof, the header of the block it was made against, and that
+//! block's signer set -- not a chainstate, sortition DB, or MARF -- so light clients and bridges
+//! can use it to check Clarity map entries and data vars reported by an untrusted node.
+
+use std::collections::HashMap;
+use std::{error, fmt};
+
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::consts::NAKAMOTO_SIGNER_BLOCK_APPROVAL_THRESHOLD;
+use stacks_common::types::chainstate::{StacksBlockId, TrieHash};
+use stacks_common::types::marf::{MARFValue, TrieMerkleMultiProof};
+use stacks_common::util::hash::{hex_bytes, Sha512Trunc256Sum};
+use stacks_common::util::secp256k1::{MessageSignature, Secp256k1PublicKey};
+
+use crate::vm::database::{ClarityDatabase, StoreType};
+use crate::vm::types::QualifiedContractIdentifier;
+use crate::vm::Value;
+
+/// Reasons a Clarity state proof can fail to verify
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateProofError {
+    /// The proof could not be decoded
+    Malformed(String),
+    /// The block header is not signed by enough of the signer set
+    BadSignatures(String),
+    /// The proof does not hash to the block header's `state_index_root`
+    BadProof,
+    /// The proof does not include this key
+    MissingEntry(TrieHash),
+    /// The proof is for a different value of this key, or for its absence
+    ValueMismatch(TrieHash),
+}
+
+impl fmt::Display for StateProofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateProofError::Malformed(msg) => write!(f, "Malformed state proof: {msg}"),
+            StateProofError::BadSignatures(msg) => {
+                write!(f, "Block header is not signed by the signer set: {msg}")
+            }
+            StateProofError::BadProof => {
+                write!(f, "State proof does not match the block's state_index_root")
+            }
+            StateProofError::MissingEntry(path) => write!(f, "State proof does not prove {path}"),
+            StateProofError::ValueMismatch(path) => {
+                write!(f, "State proof is for a different value of {path}")
+            }
+        }
+    }
+}
+
+impl error::Error for StateProofError {}
+
+/// The parts of a signed block header that a state proof is checked against
+pub trait StateRootHeader {
+    /// Root hash of the Clarity state MARF as of this block
+    fn state_index_root(&self) -> &TrieHash;
+    /// The message that the signers sign
+    fn signer_signature_hash(&self) -> Sha512Trunc256Sum;
+    /// The signers' signatures, in signer set order
+    fn signer_signatures(&self) -> &[MessageSignature];
+}
+
+/// A member of the signer set for a block
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateProofSigner {
+    /// Compressed secp256k1 public key
+    pub signing_key: [u8; 33],
+    pub weight: u32,
+}
+
+/// Get the MARF key hash for a Clarity data map entry
+pub fn map_entry_key_hash(
+    contract_identifier: &QualifiedContractIdentifier,
+    map_name: &str,
+    key: &Value,
+) -> Result<TrieHash, StateProofError> {
+    let key = ClarityDatabase::make_key_for_data_map_entry(contract_identifier, map_name, key)
+        .map_err(|e| StateProofError::Malformed(format!("Bad map key: {e:?}")))?;
+    Ok(TrieHash::from_key(&key))
+}
+
+/// Get the MARF key hash for a Clarity data var
+pub fn data_var_key_hash(
+    contract_identifier: &QualifiedContractIdentifier,
+    var_name: &str,
+) -> TrieHash {
+    let key =
+        ClarityDatabase::make_key_for_trip(contract_identifier, StoreType::Variable, var_name);
+    TrieHash::from_key(&key)
+}
+
+/// Decode a hex-encoded multi-proof, as returned by the RPC interface.  The `0x` prefix is
+/// optional.
+pub fn decode_state_proof(
+    proof_hex: &str,
+) -> Result<TrieMerkleMultiProof<StacksBlockId>, StateProofError> {
+    let proof_hex = proof_hex.strip_prefix("0x").unwrap_or(proof_hex);
+    let proof_bytes =
+        hex_bytes(proof_hex).map_err(|_| StateProofError::Malformed("Proof is not hex".into()))?;
+    TrieMerkleMultiProof::consensus_deserialize(&mut &proof_bytes[..])
+        .map_err(|e| StateProofError::Malformed(e.to_string()))
+}
+
+/// Verify that `header` is signed by at least 70% of `signers` by weight, with the signatures in
+/// signer set order.  Returns the signed weight.
+pub fn verify_signer_signatures<H: StateRootHeader>(
+    header: &H,
+    signers: &[StateProofSigner],
+) -> Result<u32, StateProofError> {
+    let message = header.signer_signature_hash();
+    let total_weight = signers
+        .iter()
+        .try_fold(0u32, |total, signer| total.checked_add(signer.weight))
+        .ok_or_else(|| StateProofError::BadSignatures("Signer weights overflow".into()))?;
+    if total_weight == 0 {
+        return Err(StateProofError::BadSignatures("No signers".into()));
+    }
+
+    let signers_by_pk: HashMap<_, _> = signers
+        .iter()
+        .enumerate()
+        .map(|(i, signer)| (&signer.signing_key, (signer, i)))
+        .collect();
+
+    let mut total_weight_signed: u32 = 0;
+    let mut last_index = None;
+    for signature in header.signer_signatures().iter() {
+        let public_key = Secp256k1PublicKey::recover_to_pubkey(message.as_bytes(), signature)
+            .map_err(|_| {
+                StateProofError::BadSignatures(format!(
+                    "Unable to recover public key from signature {}",
+                    signature.to_hex()
+                ))
+            })?;
+
+        let mut public_key_bytes = [0u8; 33];
+        public_key_bytes.copy_from_slice(&public_key.to_bytes_compressed()[..]);
+
+        let (signer, signer_index) = signers_by_pk.get(&public_key_bytes).ok_or_else(|| {
+            StateProofError::BadSignatures(format!(
+                "Public key {} not found in the signer set",
+                public_key.to_hex()
+            ))
+        })?;
+
+        if last_index.is_some_and(|index| index >= *signer_index) {
+            return Err(StateProofError::BadSignatures(
+                "Signatures are out of order".into(),
+            ));
+        }
+        last_index = Some(*signer_index);
+
+        total_weight_signed = total_weight_signed.saturating_add(signer.weight);
+    }
+
+    let threshold =
+        (u64::from(total_weight) * NAKAMOTO_SIGNER_BLOCK_APPROVAL_THRESHOLD).div_ceil(10);
+    if u64::from(total_weight_signed) < threshold {
+        return Err(StateProofError::BadSignatures(format!(
+            "Not enough signatures. Needed at least {threshold} but got {total_weight_signed} (out of {total_weight})"
+        )));
+    }
+    Ok(total_weight_signed)
+}
+
+/// Verify that `header` was signed by `signers`, that `proof` hashes to its `state_index_root`,
+/// and that `proof` proves each of the given MARF key hashes has the given Clarity side-store
+/// data (the hex-serialized Clarity value, with or without a `0x` prefix), or has no value if the
+/// data is None.
+pub fn verify_state_proof<H: StateRootHeader>(
+    header: &H,
+    signers: &[StateProofSigner],
+    proof: &TrieMerkleMultiProof<StacksBlockId>,
+    entries: &[(TrieHash, Option<&str>)],
+) -> Result<(), StateProofError> {
+    verify_signer_signatures(header, signers)?;
+
+    if !proof.verify(header.state_index_root()) {
+        return Err(StateProofError::BadProof);
+    }
+
+    for (path, data) in entries.iter() {
+        let entry = proof
+            .get_entry(path)
+            .ok_or_else(|| StateProofError::MissingEntry(path.clone()))?;
+        let expected_value =
+            data.map(|data| MARFValue::from_value(data.strip_prefix("0x").unwrap_or(data)));
+        if entry.value != expected_value {
+            return Err(StateProofError::ValueMismatch(path.clone()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use stacks_common::types::PrivateKey;
+    use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+    use super::*;
+
+    struct TestHeader {
+        state_index_root: TrieHash,
+        signer_signature: Vec<MessageSignature>,
+    }
+
+    impl StateRootHeader for TestHeader {
+        fn state_index_root(&self) -> &TrieHash {
+            &self.state_index_root
+        }
+        fn signer_signature_hash(&self) -> Sha512Trunc256Sum {
+            Sha512Trunc256Sum::from_data(self.state_index_root.as_bytes())
+        }
+        fn signer_signatures(&self) -> &[MessageSignature] {
+            &self.signer_signature
+        }
+    }
+
+    fn make_signers(weights: &[u32]) -> (Vec<Secp256k1PrivateKey>, Vec<StateProofSigner>) {
+        let privks: Vec<_> = weights
+            .iter()
+            .map(|_| Secp256k1PrivateKey::random())
+            .collect();
+        let signers = privks
+            .iter()
+            .zip(weights.iter())
+            .map(|(privk, weight)| {
+                let mut signing_key = [0u8; 33];
+                signing_key.copy_from_slice(
+                    &Secp256k1PublicKey::from_private(privk).to_bytes_compressed(),
+                );
+                StateProofSigner {
+                    signing_key,
+                    weight: *weight,
+                }
+            })
+            .collect();
+        (privks, signers)
+    }
+
+    fn sign(header: &mut TestHeader, privks: &[&Secp256k1PrivateKey]) {
+        let message = header.signer_signature_hash();
+        header.signer_signature = privks
+            .iter()
+            .map(|privk| privk.sign(message.as_bytes()).unwrap())
+            .collect();
+    }
+
+    #[test]
+    fn signer_signatures_need_threshold_weight_in_order() {
+        let (privks, signers) = make_signers(&[35, 35, 30]);
+        let mut header = TestHeader {
+            state_index_root: TrieHash([0x11; 32]),
+            signer_signature: vec![],
+        };
+
+        // exactly 70%
+        sign(&mut header, &[&privks[0], &privks[1]]);
+        assert_eq!(verify_signer_signatures(&header, &signers), Ok(70));
+
+        // 65%
+        sign(&mut header, &[&privks[0], &privks[2]]);
+        assert!(matches!(
+            verify_signer_signatures(&header, &signers),
+            Err(StateProofError::BadSignatures(_))
+        ));
+
+        // out of order
+        sign(&mut header, &[&privks[1], &privks[0]]);
+        assert!(matches!(
+            verify_signer_signatures(&header, &signers),
+            Err(StateProofError::BadSignatures(_))
+        ));
+
+        // not a signer
+        let outsider = Secp256k1PrivateKey::random();
+        sign(&mut header, &[&privks[0], &privks[1], &outsider]);
+        assert!(matches!(
+            verify_signer_signatures(&header, &signers),
+            Err(StateProofError::BadSignatures(_))
+        ));
+    }
+}
@@ -0,0 +1,775 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A step debugger for Clarity, implemented as an `EvalHook`.
+//!
+//! The debugger stops before evaluating a function application when it reaches a breakpoint
+//! (a contract and a source line), when stepping, or after a watched data-var or map is
+//! written.  At each stop, it reads commands from its input until one of them resumes
+//! evaluation.  The input is either an interactive terminal or a script of commands; once the
+//! input is exhausted, the debugger detaches and evaluation runs to completion, so a scripted
+//! session can never block.
+//!
+//! The source line of an expression in a deployed contract is found by re-parsing the
+//! contract's source, so breakpoints work with or without the `developer-mode` feature.
+//! Expressions that are not part of a deployed contract only have a source line in
+//! `developer-mode` builds.
+
+use std::io::{BufRead, Write};
+
+use hashbrown::{HashMap, HashSet};
+
+use super::EvalHook;
+use crate::vm::ast::{self, ASTRules};
+use crate::vm::contexts::{Environment, LocalContext};
+use crate::vm::errors::Error;
+use crate::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use crate::vm::{functions, ExecutionResult, SymbolicExpression, Value};
+
+const DEBUGGER_HELP: &str = "\
+commands:
+  break [<contract>:]<line>    stop before evaluating <line> (alias: b)
+  delete [<contract>:]<line>   remove a breakpoint (alias: d)
+  watch [<contract>] <name>    stop after writes to a data-var or map (alias: w)
+  step                         stop at the next expression (alias: s)
+  next                         stop at the next expression, stepping over calls (alias: n)
+  finish                       stop after returning from the current function (alias: f)
+  continue                     run until the next breakpoint or watch (alias: c)
+  locals                       print the local bindings (alias: l)
+  stack                        print the call stack (aliases: bt, backtrace)
+  print <name>                 print a local binding, constant, or data-var (alias: p)
+  quit                         detach the debugger and run to completion (alias: q)";
+
+/// A breakpoint on a contract source line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub contract: QualifiedContractIdentifier,
+    pub line: u32,
+}
+
+/// A watch on writes to a data-var or map.  If `contract` is None, then the watch applies to
+/// a data-var or map of this name in any contract.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watch {
+    pub contract: Option<QualifiedContractIdentifier>,
+    pub name: String,
+}
+
+/// How evaluation resumes after a stop
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StepMode {
+    /// Run until a breakpoint or watch
+    Continue,
+    /// Stop at the next function application
+    StepIn,
+    /// Stop at the next function application at or above this call depth
+    StepOver(usize),
+    /// Stop at the next function application above this call depth
+    StepOut(usize),
+}
+
+/// A debugger command
+#[derive(Debug, Clone, PartialEq)]
+enum DebugCommand {
+    Break(Option<QualifiedContractIdentifier>, u32),
+    Delete(Option<QualifiedContractIdentifier>, u32),
+    Watch(Watch),
+    Step,
+    Next,
+    Finish,
+    Continue,
+    Locals,
+    Stack,
+    Print(String),
+    Help,
+    Quit,
+}
+
+impl DebugCommand {
+    fn parse_location(arg: &str) -> Result<(Option<QualifiedContractIdentifier>, u32), String> {
+        let (contract, line) = match arg.rsplit_once(':') {
+            Some((contract, line)) => {
+                let contract = QualifiedContractIdentifier::parse(contract)
+                    .map_err(|e| format!("invalid contract identifier '{}': {}", contract, e))?;
+                (Some(contract), line)
+            }
+            None => (None, arg),
+        };
+        let line = line
+            .parse::<u32>()
+            .map_err(|_| format!("invalid line number '{}'", line))?;
+        Ok((contract, line))
+    }
+
+    fn parse(line: &str) -> Result<DebugCommand, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((command, args)) = words.split_first() else {
+            return Err("empty command".into());
+        };
+        let command = match (*command, args) {
+            ("break" | "b", [location]) => {
+                let (contract, line) = Self::parse_location(location)?;
+                DebugCommand::Break(contract, line)
+            }
+            ("delete" | "d", [location]) => {
+                let (contract, line) = Self::parse_location(location)?;
+                DebugCommand::Delete(contract, line)
+            }
+            ("watch" | "w", [name]) => DebugCommand::Watch(Watch {
+                contract: None,
+                name: name.to_string(),
+            }),
+            ("watch" | "w", [contract, name]) => {
+                let contract = QualifiedContractIdentifier::parse(contract)
+                    .map_err(|e| format!("invalid contract identifier '{}': {}", contract, e))?;
+                DebugCommand::Watch(Watch {
+                    contract: Some(contract),
+                    name: name.to_string(),
+                })
+            }
+            ("step" | "s", []) => DebugCommand::Step,
+            ("next" | "n", []) => DebugCommand::Next,
+            ("finish" | "f", []) => DebugCommand::Finish,
+            ("continue" | "c", []) => DebugCommand::Continue,
+            ("locals" | "l", []) => DebugCommand::Locals,
+            ("stack" | "bt" | "backtrace", []) => DebugCommand::Stack,
+            ("print" | "p", [name]) => DebugCommand::Print(name.to_string()),
+            ("help" | "h", []) => DebugCommand::Help,
+            ("quit" | "q", []) => DebugCommand::Quit,
+            _ => {
+                return Err(format!(
+                    "unrecognized command '{}' (try 'help')",
+                    line.trim()
+                ))
+            }
+        };
+        Ok(command)
+    }
+}
+
+/// An expression that is being evaluated
+struct EvalFrame {
+    expr_id: u64,
+    contract: QualifiedContractIdentifier,
+    line: Option<u32>,
+    /// If this expression calls a user-defined function, its name
+    call: Option<String>,
+    /// If this expression writes to a watched data-var or map, the write operation and the
+    /// name of the data-var or map
+    write: Option<(String, String)>,
+    /// The values of this expression's evaluated arguments, if it is a watched write
+    write_args: Vec<Value>,
+}
+
+/// The Clarity step debugger
+pub struct Debugger {
+    input: Box<dyn BufRead>,
+    output: Option<Box<dyn Write>>,
+    /// Whether or not to prompt for commands
+    interactive: bool,
+    transcript: Vec<String>,
+    breakpoints: Vec<Breakpoint>,
+    watches: Vec<Watch>,
+    mode: StepMode,
+    frames: Vec<EvalFrame>,
+    entry_contract: Option<QualifiedContractIdentifier>,
+    num_stops: u64,
+    /// The source line of each expression in each contract seen so far, if it could be found
+    contract_lines: HashMap<QualifiedContractIdentifier, Option<HashMap<u64, u32>>>,
+    /// Once detached, the debugger never stops again
+    detached: bool,
+}
+
+/// The source line of an expression from its span, if known
+#[cfg(feature = "developer-mode")]
+fn span_line(expr: &SymbolicExpression) -> Option<u32> {
+    if expr.span.start_line == 0 {
+        None
+    } else {
+        Some(expr.span.start_line)
+    }
+}
+
+#[cfg(not(feature = "developer-mode"))]
+fn span_line(_expr: &SymbolicExpression) -> Option<u32> {
+    None
+}
+
+/// A piece of Clarity source that starts an expression or ends a list
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SourceToken {
+    /// `(` or `{`
+    Open(char),
+    /// `)` or `}`
+    Close,
+    /// An atom or literal
+    Leaf,
+}
+
+/// Split Clarity source into the tokens that open or close lists and tuples, and the atoms
+/// and literals between them, along with the line each one starts on.  Comments, whitespace,
+/// and tuple separators are skipped.
+fn scan_source(source: &str) -> Vec<(SourceToken, u32)> {
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            '(' | '{' => tokens.push((SourceToken::Open(c), line)),
+            ')' | '}' => tokens.push((SourceToken::Close, line)),
+            ';' => while chars.next_if(|c| *c != '\n').is_some() {},
+            ',' | ':' => {}
+            c if c.is_whitespace() => {}
+            _ => {
+                tokens.push((SourceToken::Leaf, line));
+                // a leaf runs until the next delimiter outside of a string literal
+                let mut in_string = c == '"';
+                while let Some(&next) = chars.peek() {
+                    if !in_string && (next.is_whitespace() || "(){},:;".contains(next)) {
+                        break;
+                    }
+                    chars.next();
+                    match next {
+                        '\n' => line += 1,
+                        '"' => in_string = !in_string,
+                        '\\' if in_string => {
+                            if chars.next() == Some('\n') {
+                                line += 1;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    tokens
+}
+
+/// Record the source line of each of `exprs`, and of the expressions within them, by walking
+/// them alongside the source's tokens.  Returns None if they do not line up.
+fn assign_lines<'a>(
+    exprs: &[SymbolicExpression],
+    tokens: &mut impl Iterator<Item = &'a (SourceToken, u32)>,
+    lines: &mut HashMap<u64, u32>,
+) -> Option<()> {
+    for expr in exprs {
+        let (token, line) = *tokens.next()?;
+        lines.insert(expr.id, line);
+        match (expr.match_list(), token) {
+            (None, SourceToken::Leaf) => continue,
+            (Some(items), SourceToken::Open('(')) => assign_lines(items, tokens, lines)?,
+            (Some(items), SourceToken::Open('{')) => {
+                // `{k: v, ...}` is sugar for `(tuple (k v) ...)`, and each pair starts at its key
+                let (tuple, pairs) = items.split_first()?;
+                lines.insert(tuple.id, line);
+                for pair in pairs {
+                    let pair_items = pair.match_list()?;
+                    assign_lines(pair_items, tokens, lines)?;
+                    let key_line = *lines.get(&pair_items.first()?.id)?;
+                    lines.insert(pair.id, key_line);
+                }
+            }
+            _ => return None,
+        }
+        if tokens.next()?.0 != SourceToken::Close {
+            return None;
+        }
+    }
+    Some(())
+}
+
+/// Map the expression IDs of a contract's AST to the source lines they start on
+fn source_lines(source: &str, exprs: &[SymbolicExpression]) -> Option<HashMap<u64, u32>> {
+    let tokens = scan_source(source);
+    let mut tokens = tokens.iter();
+    let mut lines = HashMap::new();
+    assign_lines(exprs, &mut tokens, &mut lines)?;
+    if tokens.next().is_some() {
+        return None;
+    }
+    Some(lines)
+}
+
+fn format_location(contract: &QualifiedContractIdentifier, line: Option<u32>) -> String {
+    match line {
+        Some(line) => format!("{}:{}", contract, line),
+        None => format!("{}:?", contract),
+    }
+}
+
+impl Debugger {
+    /// Make a debugger that reads commands from `input`, and writes its output to `output`.
+    /// All output is also kept in the debugger's transcript.
+    pub fn new(input: Box<dyn BufRead>, output: Option<Box<dyn Write>>) -> Debugger {
+        Debugger {
+            input,
+            output,
+            interactive: false,
+            transcript: vec![],
+            breakpoints: vec![],
+            watches: vec![],
+            mode: StepMode::StepIn,
+            frames: vec![],
+            entry_contract: None,
+            num_stops: 0,
+            contract_lines: HashMap::new(),
+            detached: false,
+        }
+    }
+
+    /// Make a debugger that prompts for commands on stdin and writes to stdout
+    pub fn interactive() -> Debugger {
+        let mut debugger = Debugger::new(
+            Box::new(std::io::BufReader::new(std::io::stdin())),
+            Some(Box::new(std::io::stdout())),
+        );
+        debugger.interactive = true;
+        debugger
+    }
+
+    /// Make a debugger that runs a script of commands, one per line.  Blank lines and lines
+    /// starting with `#` are ignored.  Its output is only kept in its transcript.
+    pub fn scripted(script: &str) -> Debugger {
+        Debugger::new(Box::new(std::io::Cursor::new(script.to_string())), None)
+    }
+
+    /// Everything the debugger has output so far
+    pub fn transcript(&self) -> &[String] {
+        &self.transcript
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn watches(&self) -> &[Watch] {
+        &self.watches
+    }
+
+    fn emit(&mut self, line: String) {
+        if let Some(output) = self.output.as_mut() {
+            let _ = writeln!(output, "{}", line);
+        }
+        self.transcript.push(line);
+    }
+
+    /// Read the next command line, or None if the input is exhausted
+    fn read_command(&mut self) -> Option<String> {
+        loop {
+            if self.interactive {
+                if let Some(output) = self.output.as_mut() {
+                    let _ = write!(output, "(cdb) ");
+                    let _ = output.flush();
+                }
+            }
+            let mut line = String::new();
+            match self.input.read_line(&mut line) {
+                Ok(0) | Err(_) => return None,
+                Ok(_) => {}
+            }
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if !self.interactive {
+                self.emit(format!("> {}", line));
+            }
+            return Some(line.to_string());
+        }
+    }
+
+    /// The source line of `expr` in the contract being evaluated, if known
+    fn expr_line(&mut self, env: &mut Environment, expr: &SymbolicExpression) -> Option<u32> {
+        let contract = env.contract_context.contract_identifier.clone();
+        if !self.contract_lines.contains_key(&contract) {
+            let lines = Self::load_contract_lines(env);
+            self.contract_lines.insert(contract.clone(), lines);
+        }
+        self.contract_lines
+            .get(&contract)
+            .and_then(|lines| lines.as_ref()?.get(&expr.id).copied())
+            .or_else(|| span_line(expr))
+    }
+
+    /// Re-parse the source of the contract being evaluated, to find the source line of each
+    /// of its expressions
+    fn load_contract_lines(env: &mut Environment) -> Option<HashMap<u64, u32>> {
+        let contract = env.contract_context.contract_identifier.clone();
+        let version = *env.contract_context.get_clarity_version();
+        let epoch = *env.epoch();
+        let source = env.global_context.database.get_contract_src(&contract)?;
+        let contract_ast = ast::build_ast_with_rules(
+            &contract,
+            &source,
+            &mut (),
+            version,
+            epoch,
+            ASTRules::PrecheckSize,
+        )
+        .ok()?;
+        source_lines(&source, &contract_ast.expressions)
+    }
+
+    /// The number of user-defined function calls being evaluated
+    fn call_depth(&self) -> usize {
+        self.frames.iter().filter(|f| f.call.is_some()).count()
+    }
+
+    /// If `expr` calls a user-defined function, get the function's name
+    fn call_name(env: &Environment, expr: &SymbolicExpression) -> Option<String> {
+        let list = expr.match_list()?;
+        let name = list.first()?.match_atom()?;
+        if name.as_str() == "contract-call?" {
+            let target = list.get(1)?;
+            let function = list.get(2)?;
+            let target = match target.match_atom_value() {
+                Some(Value::Principal(PrincipalData::Contract(contract))) => contract.to_string(),
+                _ => target.to_string(),
+            };
+            return Some(format!("{}::{}", target, function));
+        }
+        if functions::lookup_reserved_functions(name, env.contract_context.get_clarity_version())
+            .is_some()
+            || !env.contract_context.functions.contains_key(name.as_str())
+        {
+            return None;
+        }
+        Some(format!(
+            "{}::{}",
+            env.contract_context.contract_identifier, name
+        ))
+    }
+
+    /// If `expr` writes to a watched data-var or map, get the write operation and the name of
+    /// the data-var or map
+    fn watched_write(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        expr: &SymbolicExpression,
+    ) -> Option<(String, String)> {
+        if self.watches.is_empty() {
+            return None;
+        }
+        let list = expr.match_list()?;
+        let op = list.first()?.match_atom()?;
+        if !matches!(
+            op.as_str(),
+            "var-set" | "map-set" | "map-insert" | "map-delete"
+        ) {
+            return None;
+        }
+        let name = list.get(1)?.match_atom()?;
+        self.watches
+            .iter()
+            .any(|watch| {
+                watch.name == name.as_str()
+                    && (watch.contract.is_none() || watch.contract.as_ref() == Some(contract))
+            })
+            .then(|| (op.to_string(), name.to_string()))
+    }
+
+    fn hit_breakpoint(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        line: Option<u32>,
+    ) -> Option<usize> {
+        let line = line?;
+        // only stop at the outermost expression on a line
+        if let Some(parent) = self.frames.last() {
+            if &parent.contract == contract && parent.line == Some(line) {
+                return None;
+            }
+        }
+        self.breakpoints
+            .iter()
+            .position(|bp| &bp.contract == contract && bp.line == line)
+    }
+
+    fn print_locals(&mut self, context: &LocalContext) {
+        let mut seen = HashSet::new();
+        let mut lines = vec![];
+        let mut ctx = Some(context);
+        while let Some(c) = ctx {
+            let mut names: Vec<_> = c.variables.keys().collect();
+            names.sort();
+            for name in names {
+                if seen.insert(name.clone()) {
+                    lines.push(format!("  {} = {}", name, c.variables[name]));
+                }
+            }
+            let mut names: Vec<_> = c.callable_contracts.keys().collect();
+            names.sort();
+            for name in names {
+                if seen.insert(name.clone()) {
+                    lines.push(format!(
+                        "  {} = {}",
+                        name, c.callable_contracts[name].contract_identifier
+                    ));
+                }
+            }
+            ctx = c.parent;
+        }
+        if lines.is_empty() {
+            lines.push("  (no local bindings)".into());
+        }
+        for line in lines {
+            self.emit(line);
+        }
+    }
+
+    fn print_stack(&mut self, contract: &QualifiedContractIdentifier, line: Option<u32>) {
+        let mut positions = vec![format_location(contract, line)];
+        let mut names = vec![];
+        for frame in self.frames.iter().rev() {
+            if let Some(call) = frame.call.as_ref() {
+                names.push(call.clone());
+                positions.push(format_location(&frame.contract, frame.line));
+            }
+        }
+        names.push(match self.entry_contract.as_ref() {
+            Some(entry) => format!("{} (top level)", entry),
+            None => "(top level)".into(),
+        });
+        for (i, (name, position)) in names.into_iter().zip(positions).enumerate() {
+            self.emit(format!("  #{} {} at {}", i, name, position));
+        }
+    }
+
+    fn print_name(&mut self, env: &mut Environment, context: &LocalContext, name: &str) {
+        let line = if let Some(value) = context.lookup_variable(name) {
+            format!("  {} = {}", name, value)
+        } else if let Some(value) = env.contract_context.lookup_variable(name) {
+            format!("  {} = {}", name, value)
+        } else if env.contract_context.meta_data_var.contains_key(name) {
+            let epoch = *env.epoch();
+            let contract = env.contract_context.contract_identifier.clone();
+            match env
+                .global_context
+                .database
+                .lookup_variable_unknown_descriptor(&contract, name, &epoch)
+            {
+                Ok(value) => format!("  {} = {}", name, value),
+                Err(e) => format!("  failed to read data-var '{}': {}", name, e),
+            }
+        } else {
+            format!("  no binding, constant, or data-var named '{}'", name)
+        };
+        self.emit(line);
+    }
+
+    /// Stop evaluation, and run commands until one of them resumes it
+    fn stop(
+        &mut self,
+        env: &mut Environment,
+        context: &LocalContext,
+        expr: &SymbolicExpression,
+        reason: &str,
+    ) {
+        self.num_stops += 1;
+        let contract = env.contract_context.contract_identifier.clone();
+        let line = self.expr_line(env, expr);
+        self.emit(format!(
+            "stopped at {} ({})",
+            format_location(&contract, line),
+            reason
+        ));
+        self.emit(format!("  {}", expr));
+        self.mode = StepMode::Continue;
+
+        loop {
+            let Some(command_line) = self.read_command() else {
+                self.emit("end of input; detaching".into());
+                self.detached = true;
+                return;
+            };
+            let command = match DebugCommand::parse(&command_line) {
+                Ok(command) => command,
+                Err(e) => {
+                    self.emit(format!("error: {}", e));
+                    continue;
+                }
+            };
+            match command {
+                DebugCommand::Break(bp_contract, bp_line) => {
+                    let breakpoint = Breakpoint {
+                        contract: bp_contract.unwrap_or_else(|| contract.clone()),
+                        line: bp_line,
+                    };
+                    if !self.breakpoints.contains(&breakpoint) {
+                        self.breakpoints.push(breakpoint.clone());
+                    }
+                    self.emit(format!(
+                        "breakpoint at {}",
+                        format_location(&breakpoint.contract, Some(breakpoint.line))
+                    ));
+                }
+                DebugCommand::Delete(bp_contract, bp_line) => {
+                    let breakpoint = Breakpoint {
+                        contract: bp_contract.unwrap_or_else(|| contract.clone()),
+                        line: bp_line,
+                    };
+                    let location = format_location(&breakpoint.contract, Some(breakpoint.line));
+                    if let Some(i) = self.breakpoints.iter().position(|bp| bp == &breakpoint) {
+                        self.breakpoints.remove(i);
+                        self.emit(format!("deleted breakpoint at {}", location));
+                    } else {
+                        self.emit(format!("no breakpoint at {}", location));
+                    }
+                }
+                DebugCommand::Watch(watch) => {
+                    let description = match watch.contract.as_ref() {
+                        Some(contract) => format!("{}::{}", contract, watch.name),
+                        None => watch.name.clone(),
+                    };
+                    if !self.watches.contains(&watch) {
+                        self.watches.push(watch);
+                    }
+                    self.emit(format!("watching writes to {}", description));
+                }
+                DebugCommand::Step => {
+                    self.mode = StepMode::StepIn;
+                    return;
+                }
+                DebugCommand::Next => {
+                    self.mode = StepMode::StepOver(self.call_depth());
+                    return;
+                }
+                DebugCommand::Finish => {
+                    self.mode = StepMode::StepOut(self.call_depth());
+                    return;
+                }
+                DebugCommand::Continue => {
+                    self.mode = StepMode::Continue;
+                    return;
+                }
+                DebugCommand::Locals => self.print_locals(context),
+                DebugCommand::Stack => self.print_stack(&contract, line),
+                DebugCommand::Print(name) => self.print_name(env, context, &name),
+                DebugCommand::Help => {
+                    for help_line in DEBUGGER_HELP.lines() {
+                        self.emit(help_line.to_string());
+                    }
+                }
+                DebugCommand::Quit => {
+                    self.emit("detaching".into());
+                    self.detached = true;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl EvalHook for Debugger {
+    fn will_begin_eval(
+        &mut self,
+        env: &mut Environment,
+        context: &LocalContext,
+        expr: &SymbolicExpression,
+    ) {
+        if self.detached {
+            return;
+        }
+        let contract = env.contract_context.contract_identifier.clone();
+        if self.entry_contract.is_none() {
+            self.entry_contract = Some(contract.clone());
+        }
+        let line = self.expr_line(env, expr);
+        let call_depth = self.call_depth();
+
+        // only stop at function applications
+        let reason = if expr.match_list().is_none() {
+            None
+        } else if let Some(i) = self.hit_breakpoint(&contract, line) {
+            Some(format!("breakpoint {}", i + 1))
+        } else {
+            let stepped = match self.mode {
+                StepMode::Continue => false,
+                StepMode::StepIn => true,
+                StepMode::StepOver(depth) => call_depth <= depth,
+                StepMode::StepOut(depth) => call_depth < depth,
+            };
+            match (stepped, self.num_stops) {
+                (false, _) => None,
+                (true, 0) => Some("entry".to_string()),
+                (true, _) => Some("step".to_string()),
+            }
+        };
+
+        // stop before this expression's frame is pushed, so that its call (if any) is not on
+        // the reported stack yet
+        if let Some(reason) = reason {
+            self.stop(env, context, expr, &reason);
+        }
+        let frame = EvalFrame {
+            expr_id: expr.id,
+            call: Self::call_name(env, expr),
+            write: self.watched_write(&contract, expr),
+            write_args: vec![],
+            contract,
+            line,
+        };
+        self.frames.push(frame);
+    }
+
+    fn did_finish_eval(
+        &mut self,
+        env: &mut Environment,
+        context: &LocalContext,
+        expr: &SymbolicExpression,
+        res: &core::result::Result<Value, Error>,
+    ) {
+        if self.detached {
+            return;
+        }
+        // expressions that fail before evaluation starts are never finished, so unwind to
+        // this expression's frame
+        let Some(pos) = self.frames.iter().rposition(|f| f.expr_id == expr.id) else {
+            return;
+        };
+        let frame = self.frames.remove(pos);
+        self.frames.truncate(pos);
+
+        if let (Some(parent), Ok(value)) = (self.frames.last_mut(), res) {
+            if parent.write.is_some() {
+                parent.write_args.push(value.clone());
+            }
+        }
+
+        let Some((op, name)) = frame.write else {
+            return;
+        };
+        let mut write = format!("({} {}", op, name);
+        for arg in frame.write_args.iter() {
+            write.push_str(&format!(" {}", arg));
+        }
+        write.push(')');
+        match res {
+            Ok(value) => self.emit(format!(
+                "watch: {}::{} {} => {}",
+                frame.contract, name, write, value
+            )),
+            Err(e) => self.emit(format!(
+                "watch: {}::{} {} failed: {}",
+                frame.contract, name, write, e
+            )),
+        }
+        self.stop(env, context, expr, "watch");
+    }
+
+    fn did_complete(&mut self, _result: core::result::Result<&mut ExecutionResult, String>) {}
+}
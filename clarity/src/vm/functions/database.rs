@@ -107,7 +107,7 @@ pub fn special_contract_call(
                         .map_err(|_e| {
                             CheckErrors::NoSuchContract(trait_data.contract_identifier.to_string())
                         })?;
-                    let contract_context_to_check = contract_to_check.contract_context;
+                    let contract_context_to_check = &contract_to_check.contract_context;
 
                     // This error case indicates a bad implementation. Only traits should be
                     // added to callable_contracts.
@@ -136,7 +136,7 @@ pub fn special_contract_call(
                                 )
                             })?;
                         let contract_context_defining_trait =
-                            contract_defining_trait.contract_context;
+                            &contract_defining_trait.contract_context;
 
                         // Retrieve the function that will be invoked
                         let function_to_check = contract_context_to_check
@@ -162,7 +162,7 @@ pub fn special_contract_call(
 
                         function_to_check.check_trait_expectations(
                             env.epoch(),
-                            &contract_context_defining_trait,
+                            contract_context_defining_trait,
                             trait_identifier,
                         )?;
 
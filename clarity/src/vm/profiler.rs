@@ -0,0 +1,434 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An execution cost profiler for Clarity, implemented as an `EvalHook`.
+//!
+//! The profiler attributes each dimension of `ExecutionCost` to the stack of function
+//! applications that incurred it: native functions by name, user-defined functions as
+//! `contract::function`, and `contract-call?`s as `target::function`.  The outermost frame of
+//! every stack is the contract whose code started the evaluation.  Costs that are not incurred
+//! by evaluating an expression (such as loading the called contract, or the cost of a
+//! transaction's payload) are not seen by the profiler.
+//!
+//! A profile can be written in the folded-stack format read by flamegraph tools, with one file
+//! per cost dimension, and summarized per frame as JSON.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::EvalHook;
+use crate::vm::contexts::{Environment, LocalContext};
+use crate::vm::costs::ExecutionCost;
+use crate::vm::errors::Error;
+use crate::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use crate::vm::{ExecutionResult, SymbolicExpression, Value};
+
+/// A dimension of `ExecutionCost`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostDimension {
+    Runtime,
+    ReadCount,
+    ReadLength,
+    WriteCount,
+    WriteLength,
+}
+
+impl CostDimension {
+    pub const ALL: [CostDimension; 5] = [
+        CostDimension::Runtime,
+        CostDimension::ReadCount,
+        CostDimension::ReadLength,
+        CostDimension::WriteCount,
+        CostDimension::WriteLength,
+    ];
+
+    /// The name of this dimension, as it is serialized in `ExecutionCost`
+    pub fn name(&self) -> &'static str {
+        match self {
+            CostDimension::Runtime => "runtime",
+            CostDimension::ReadCount => "read_count",
+            CostDimension::ReadLength => "read_length",
+            CostDimension::WriteCount => "write_count",
+            CostDimension::WriteLength => "write_length",
+        }
+    }
+
+    pub fn of(&self, cost: &ExecutionCost) -> u64 {
+        match self {
+            CostDimension::Runtime => cost.runtime,
+            CostDimension::ReadCount => cost.read_count,
+            CostDimension::ReadLength => cost.read_length,
+            CostDimension::WriteCount => cost.write_count,
+            CostDimension::WriteLength => cost.write_length,
+        }
+    }
+}
+
+/// The cost attributed to all evaluations of one frame
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrameProfile {
+    pub calls: u64,
+    /// The cost of this frame and of the frames it called.  Nested evaluations of the same
+    /// frame are only counted once.
+    pub inclusive: ExecutionCost,
+    /// The cost of this frame, not including the frames it called
+    pub exclusive: ExecutionCost,
+}
+
+/// A summary of a profile, with the cost of each frame
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProfileSummary {
+    pub total: ExecutionCost,
+    pub frames: BTreeMap<String, FrameProfile>,
+}
+
+/// A frame on the profiler's stack
+struct Frame {
+    contract: QualifiedContractIdentifier,
+    expr_id: u64,
+    name: String,
+    /// Whether this frame is the outermost frame of its stack
+    root: bool,
+    /// The total cost when this frame was entered
+    start: ExecutionCost,
+    /// The inclusive cost of the frames that this frame called
+    children: ExecutionCost,
+}
+
+fn saturating_add(total: &mut ExecutionCost, other: &ExecutionCost) {
+    total.runtime = total.runtime.saturating_add(other.runtime);
+    total.read_count = total.read_count.saturating_add(other.read_count);
+    total.read_length = total.read_length.saturating_add(other.read_length);
+    total.write_count = total.write_count.saturating_add(other.write_count);
+    total.write_length = total.write_length.saturating_add(other.write_length);
+}
+
+fn saturating_sub(total: &ExecutionCost, other: &ExecutionCost) -> ExecutionCost {
+    ExecutionCost {
+        runtime: total.runtime.saturating_sub(other.runtime),
+        read_count: total.read_count.saturating_sub(other.read_count),
+        read_length: total.read_length.saturating_sub(other.read_length),
+        write_count: total.write_count.saturating_sub(other.write_count),
+        write_length: total.write_length.saturating_sub(other.write_length),
+    }
+}
+
+/// Attributes execution costs to the stacks of function applications that incurred them.
+/// A profiler can be added to any number of evaluations, and accumulates their costs.
+pub struct Profiler {
+    frames: Vec<Frame>,
+    /// The exclusive cost of each distinct stack, keyed by its frame names joined with `;`
+    stacks: BTreeMap<String, ExecutionCost>,
+    profiles: BTreeMap<String, FrameProfile>,
+    total: ExecutionCost,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler {
+            frames: vec![],
+            stacks: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+            total: ExecutionCost::ZERO,
+        }
+    }
+
+    /// The total cost of all profiled evaluations
+    pub fn total(&self) -> &ExecutionCost {
+        &self.total
+    }
+
+    /// The exclusive cost of each distinct stack, keyed by its frame names joined with `;`
+    pub fn stacks(&self) -> &BTreeMap<String, ExecutionCost> {
+        &self.stacks
+    }
+
+    /// The profile in the folded-stack format: one line per stack with a non-zero cost in
+    /// `dimension`, followed by that cost.
+    pub fn folded(&self, dimension: CostDimension) -> String {
+        let mut folded = String::new();
+        for (stack, cost) in self.stacks.iter() {
+            let value = dimension.of(cost);
+            if value > 0 {
+                folded.push_str(&format!("{} {}\n", stack, value));
+            }
+        }
+        folded
+    }
+
+    pub fn summary(&self) -> ProfileSummary {
+        ProfileSummary {
+            total: self.total.clone(),
+            frames: self.profiles.clone(),
+        }
+    }
+
+    /// Write the profile to `<prefix>.<dimension>.folded` for each cost dimension, and its
+    /// summary to `<prefix>.json`.  Returns the paths that were written.
+    pub fn to_files(&self, prefix: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let with_suffix = |suffix: &str| {
+            let mut path = prefix.as_os_str().to_owned();
+            path.push(suffix);
+            PathBuf::from(path)
+        };
+        let mut paths = vec![];
+        for dimension in CostDimension::ALL.iter() {
+            let path = with_suffix(&format!(".{}.folded", dimension.name()));
+            fs::write(&path, self.folded(*dimension))?;
+            paths.push(path);
+        }
+        let path = with_suffix(".json");
+        let summary = serde_json::to_string_pretty(&self.summary())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        fs::write(&path, summary)?;
+        paths.push(path);
+        Ok(paths)
+    }
+
+    /// The name of the frame of `expr`, if it is a function application
+    fn frame_name(env: &Environment, expr: &SymbolicExpression) -> Option<String> {
+        let list = expr.match_list()?;
+        let name = list.first()?.match_atom()?;
+        if name.as_str() == "contract-call?" {
+            let target = list.get(1)?;
+            let function = list.get(2)?;
+            let target = match target.match_atom_value() {
+                Some(Value::Principal(PrincipalData::Contract(contract))) => contract.to_string(),
+                _ => target.to_string(),
+            };
+            return Some(format!("{}::{}", target, function));
+        }
+        if env.contract_context.functions.contains_key(name.as_str()) {
+            return Some(format!(
+                "{}::{}",
+                env.contract_context.contract_identifier, name
+            ));
+        }
+        Some(name.to_string())
+    }
+
+    fn push(&mut self, env: &Environment, expr: &SymbolicExpression, name: String, root: bool) {
+        self.frames.push(Frame {
+            contract: env.contract_context.contract_identifier.clone(),
+            expr_id: expr.id,
+            name,
+            root,
+            start: env.global_context.cost_track.get_total(),
+            children: ExecutionCost::ZERO,
+        });
+    }
+
+    /// Attribute the cost of the frame on top of the stack, which ends at `now`
+    fn pop(&mut self, now: &ExecutionCost) {
+        let Some(frame) = self.frames.pop() else {
+            return;
+        };
+        let inclusive = saturating_sub(now, &frame.start);
+        let exclusive = saturating_sub(&inclusive, &frame.children);
+        let nested = self.frames.iter().any(|f| f.name == frame.name);
+
+        let mut stack = String::new();
+        for f in self.frames.iter() {
+            stack.push_str(&f.name);
+            stack.push(';');
+        }
+        stack.push_str(&frame.name);
+        saturating_add(
+            self.stacks.entry(stack).or_insert(ExecutionCost::ZERO),
+            &exclusive,
+        );
+
+        let profile = self.profiles.entry(frame.name).or_insert(FrameProfile {
+            calls: 0,
+            inclusive: ExecutionCost::ZERO,
+            exclusive: ExecutionCost::ZERO,
+        });
+        profile.calls = profile.calls.saturating_add(1);
+        saturating_add(&mut profile.exclusive, &exclusive);
+        if !nested {
+            saturating_add(&mut profile.inclusive, &inclusive);
+        }
+
+        match self.frames.last_mut() {
+            Some(parent) => saturating_add(&mut parent.children, &inclusive),
+            None => saturating_add(&mut self.total, &inclusive),
+        }
+    }
+}
+
+impl EvalHook for Profiler {
+    fn will_begin_eval(
+        &mut self,
+        env: &mut Environment,
+        _context: &LocalContext,
+        expr: &SymbolicExpression,
+    ) {
+        if self.frames.is_empty() {
+            let name = env.contract_context.contract_identifier.to_string();
+            self.push(env, expr, name, true);
+        }
+        if let Some(name) = Self::frame_name(env, expr) {
+            self.push(env, expr, name, false);
+        }
+    }
+
+    fn did_finish_eval(
+        &mut self,
+        env: &mut Environment,
+        _context: &LocalContext,
+        expr: &SymbolicExpression,
+        _res: &core::result::Result<Value, Error>,
+    ) {
+        // expressions that fail before evaluation starts are never finished, so unwind to
+        // this expression's frame
+        let contract = &env.contract_context.contract_identifier;
+        let Some(pos) = self
+            .frames
+            .iter()
+            .rposition(|f| f.expr_id == expr.id && f.contract == *contract)
+        else {
+            return;
+        };
+        let now = env.global_context.cost_track.get_total();
+        while self.frames.len() > pos {
+            self.pop(&now);
+        }
+        // the root frame ends with the expression that started it
+        if let [root] = self.frames.as_slice() {
+            if root.root && root.expr_id == expr.id && root.contract == *contract {
+                self.pop(&now);
+            }
+        }
+    }
+
+    fn did_complete(&mut self, _result: core::result::Result<&mut ExecutionResult, String>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use stacks_common::types::StacksEpochId;
+
+    use super::*;
+    use crate::vm::ast::ASTRules;
+    use crate::vm::database::MemoryBackingStore;
+    use crate::vm::tests::cost_tracked_env;
+    use crate::vm::ClarityVersion;
+
+    const CONTRACT: &str = "
+        (define-map balances principal uint)
+        (define-private (credit (who principal) (amount uint))
+          (map-set balances who (+ amount (default-to u0 (map-get? balances who)))))
+        (define-public (airdrop (amount uint))
+          (begin
+            (credit tx-sender amount)
+            (credit 'S1G2081040G2081040G2081040G208105NK8PE5 amount)
+            (ok true)))";
+
+    fn profile_airdrop() -> (Profiler, ExecutionCost) {
+        let mut marf = MemoryBackingStore::new();
+        let mut owned_env = cost_tracked_env(marf.as_clarity_db(), StacksEpochId::Epoch21);
+        let contract_id = QualifiedContractIdentifier::local("airdrop").unwrap();
+        owned_env
+            .initialize_versioned_contract(
+                contract_id.clone(),
+                ClarityVersion::Clarity2,
+                CONTRACT,
+                None,
+                ASTRules::PrecheckSize,
+            )
+            .unwrap();
+
+        let mut profiler = Profiler::new();
+        let before = owned_env.get_cost_total();
+        owned_env.add_eval_hook(&mut profiler);
+        let sender =
+            PrincipalData::parse_standard_principal("S1G2081040G2081040G2081040G208105NK8PE5")
+                .unwrap();
+        owned_env
+            .execute_transaction(
+                sender.into(),
+                None,
+                contract_id,
+                "airdrop",
+                &[SymbolicExpression::atom_value(Value::UInt(10))],
+            )
+            .unwrap();
+        let spent = saturating_sub(&owned_env.get_cost_total(), &before);
+        drop(owned_env);
+        (profiler, spent)
+    }
+
+    #[test]
+    fn test_stacks() {
+        let (profiler, _) = profile_airdrop();
+        let credit = "S1G2081040G2081040G2081040G208105NK8PE5.airdrop::credit";
+        let root = "S1G2081040G2081040G2081040G208105NK8PE5.airdrop";
+        let map_set = format!("{};begin;{};map-set", root, credit);
+        let map_get = format!("{};+;default-to;map-get?", map_set);
+
+        // map-set checks whether the entry exists before writing it
+        let stacks = profiler.stacks();
+        assert_eq!(stacks[&map_set].write_count, 2);
+        assert_eq!(stacks[&map_set].read_count, 2);
+        assert_eq!(stacks[&map_get].read_count, 2);
+        assert_eq!(stacks[&map_get].write_count, 0);
+        assert!(stacks[&format!("{};+", map_set)].runtime > 0);
+
+        let summary = profiler.summary();
+        assert_eq!(summary.frames[credit].calls, 2);
+        assert_eq!(summary.frames["map-set"].calls, 2);
+        assert_eq!(summary.frames[credit].inclusive.read_count, 4);
+        assert_eq!(summary.frames[credit].inclusive.write_count, 2);
+        assert_eq!(summary.frames[credit].exclusive.write_count, 0);
+        assert_eq!(summary.frames[root].calls, 1);
+    }
+
+    #[test]
+    fn test_costs_add_up() {
+        let (profiler, spent) = profile_airdrop();
+        let summary = profiler.summary();
+        let mut exclusive = ExecutionCost::ZERO;
+        for cost in profiler.stacks().values() {
+            saturating_add(&mut exclusive, cost);
+        }
+        assert_eq!(exclusive, summary.total);
+        assert!(!summary.total.exceeds(&spent));
+        assert!(summary.total.runtime > 0);
+    }
+
+    #[test]
+    fn test_folded() {
+        let (profiler, _) = profile_airdrop();
+        let folded = profiler.folded(CostDimension::WriteCount);
+        assert_eq!(
+            folded,
+            "S1G2081040G2081040G2081040G208105NK8PE5.airdrop;begin;\
+             S1G2081040G2081040G2081040G208105NK8PE5.airdrop::credit;map-set 2\n"
+        );
+        let runtime = profiler.folded(CostDimension::Runtime);
+        let total: u64 = runtime
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().1.parse::<u64>().unwrap())
+            .sum();
+        assert_eq!(total, profiler.total().runtime);
+    }
+}
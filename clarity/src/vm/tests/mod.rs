@@ -21,9 +21,12 @@ use stacks_common::types::StacksEpochId;
 
 pub use super::test_util::*;
 use super::ClarityVersion;
+use crate::boot_util::boot_code_id;
+use crate::vm::ast::ASTRules;
 use crate::vm::contexts::OwnedEnvironment;
+use crate::vm::costs::LimitedCostTracker;
 pub use crate::vm::database::BurnStateDB;
-use crate::vm::database::MemoryBackingStore;
+use crate::vm::database::{ClarityDatabase, MemoryBackingStore};
 use crate::vm::errors::Error;
 use crate::vm::types::Value;
 
@@ -201,6 +204,36 @@ impl TopLevelMemoryEnvironmentGenerator {
     }
 }
 
+/// Make an environment that tracks costs with the default cost functions of `epoch`, with no
+/// limit.  Cost tracking expects the default cost contract to exist (although it evaluates the
+/// default costs natively), and reads the number of confirmed cost proposals from the cost
+/// voting contract, so stubs of both are deployed first.
+pub fn cost_tracked_env(mut db: ClarityDatabase, epoch: StacksEpochId) -> OwnedEnvironment {
+    db.begin();
+    db.set_clarity_epoch_version(epoch).unwrap();
+    db.commit().unwrap();
+    let cost_contract = LimitedCostTracker::default_cost_contract_for_epoch(epoch).unwrap();
+    let mut free_env = OwnedEnvironment::new_free(false, CHAIN_ID_TESTNET, db, epoch);
+    for (name, stub) in [
+        (cost_contract.as_str(), "(define-read-only (cost-stub) u0)"),
+        (
+            "cost-voting",
+            "(define-data-var confirmed-proposal-count uint u0)",
+        ),
+    ] {
+        free_env
+            .initialize_contract(
+                boot_code_id(name, false),
+                stub,
+                None,
+                ASTRules::PrecheckSize,
+            )
+            .unwrap();
+    }
+    let (db, _) = free_env.destruct().unwrap();
+    OwnedEnvironment::new_max_limit(db, epoch, false)
+}
+
 /// Determine whether or not to use the testnet or mainnet chain ID, given whether or not the
 /// caller expects to use mainnet or testnet.
 ///
@@ -0,0 +1,1121 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A language server for Clarity, which speaks the Language Server Protocol over stdio.
+//!
+//! Each open document is checked as a standalone contract, with the same passes and the same
+//! epoch and Clarity version rules as `run_analysis`.  Diagnostics are published when a document
+//! is opened or saved.  The server also offers hover types and documentation, go-to-definition
+//! for `define-*` names, local bindings and trait references, and completion of native
+//! functions, keywords and contract-local names.
+//!
+//! The server relies on the source spans of expressions, which are only recorded when Clarity
+//! is built with the `developer-mode` feature.  Without it, diagnostics are all reported at the
+//! start of the document, and position-based requests find nothing.
+
+use std::io::{self, BufRead, Write};
+
+use hashbrown::HashMap;
+use serde_json::{json, Value};
+use stacks_common::types::StacksEpochId;
+
+use crate::vm::analysis::{run_analysis, ContractAnalysis};
+use crate::vm::ast::build_ast_with_diagnostics;
+use crate::vm::costs::LimitedCostTracker;
+use crate::vm::database::MemoryBackingStore;
+use crate::vm::diagnostic::{Diagnostic, Level};
+use crate::vm::docs::{make_api_reference, make_define_reference, make_keyword_reference};
+use crate::vm::functions::define::DefineFunctions;
+use crate::vm::functions::NativeFunctions;
+use crate::vm::representations::{
+    Span, SymbolicExpression, SymbolicExpressionType, TraitDefinition,
+};
+use crate::vm::types::signatures::FunctionType;
+use crate::vm::types::QualifiedContractIdentifier;
+use crate::vm::variables::NativeVariables;
+use crate::vm::ClarityVersion;
+
+/// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// LSP `CompletionItemKind`s
+const COMPLETION_FUNCTION: u64 = 3;
+const COMPLETION_VARIABLE: u64 = 6;
+const COMPLETION_INTERFACE: u64 = 8;
+const COMPLETION_KEYWORD: u64 = 14;
+const COMPLETION_CONSTANT: u64 = 21;
+const COMPLETION_STRUCT: u64 = 22;
+
+/// Largest message body `read_message` will accept
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// The epochs a client may select, i.e. every `StacksEpochId` since Clarity was introduced
+fn supported_epochs() -> impl Iterator<Item = StacksEpochId> {
+    (StacksEpochId::Epoch20 as u32..=StacksEpochId::latest() as u32)
+        .filter_map(|id| StacksEpochId::try_from(id).ok())
+}
+
+/// Read one message body from an LSP stream.  Returns `None` at the end of the stream.
+pub fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = Some(value.trim().parse::<usize>().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "bad Content-Length header")
+                })?);
+            }
+        }
+    }
+    let content_length = content_length.unwrap_or(0);
+    if content_length > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Content-Length exceeds maximum message length",
+        ));
+    }
+    let mut body = vec![0; content_length];
+    input.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+/// Write one message to an LSP stream
+pub fn write_message<W: Write>(output: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+/// The result of checking a document
+pub struct DocumentAnalysis {
+    pub contract_identifier: QualifiedContractIdentifier,
+    pub expressions: Vec<SymbolicExpression>,
+    /// Only set if the document passed all checks
+    pub contract_analysis: Option<ContractAnalysis>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Check a document as a standalone contract
+pub fn analyze_document(
+    contract_identifier: &QualifiedContractIdentifier,
+    source: &str,
+    epoch: StacksEpochId,
+    clarity_version: ClarityVersion,
+) -> DocumentAnalysis {
+    let (ast, diagnostics, success) =
+        build_ast_with_diagnostics(contract_identifier, source, &mut (), clarity_version, epoch);
+    let mut analysis = DocumentAnalysis {
+        contract_identifier: contract_identifier.clone(),
+        expressions: ast.expressions,
+        contract_analysis: None,
+        diagnostics,
+    };
+    if !success {
+        return analysis;
+    }
+
+    let mut marf = MemoryBackingStore::new();
+    let mut analysis_db = marf.as_analysis_db();
+    match run_analysis(
+        contract_identifier,
+        &analysis.expressions,
+        &mut analysis_db,
+        false,
+        LimitedCostTracker::new_free(),
+        epoch,
+        clarity_version,
+        true,
+    ) {
+        Ok(contract_analysis) => analysis.contract_analysis = Some(contract_analysis),
+        Err((e, _)) => analysis.diagnostics.push(e.diagnostic),
+    }
+    analysis
+}
+
+/// A top-level `define-*` form
+struct Definition<'a> {
+    define_type: DefineFunctions,
+    name: &'a str,
+    /// The defined name
+    name_expr: &'a SymbolicExpression,
+    /// The whole `define-*` form
+    expr: &'a SymbolicExpression,
+}
+
+fn definitions(expressions: &[SymbolicExpression]) -> Vec<Definition<'_>> {
+    expressions
+        .iter()
+        .filter_map(|expr| {
+            let list = expr.match_list()?;
+            let define_type = DefineFunctions::lookup_by_name(list.first()?.match_atom()?)?;
+            let target = list.get(1)?;
+            // functions are named by the head of their signature
+            let name_expr = match target.match_list() {
+                Some(signature) => signature.first()?,
+                None => target,
+            };
+            Some(Definition {
+                define_type,
+                name: name_expr.match_atom()?.as_str(),
+                name_expr,
+                expr,
+            })
+        })
+        .collect()
+}
+
+/// Does `span` contain the 1-based `line` and `column`?
+fn span_contains(span: &Span, line: u32, column: u32) -> bool {
+    span.start_line != 0
+        && (span.start_line, span.start_column) <= (line, column)
+        && (line, column) <= (span.end_line, span.end_column)
+}
+
+/// The expressions that contain a position, from the outermost to the innermost
+fn expression_path(
+    expressions: &[SymbolicExpression],
+    line: u32,
+    column: u32,
+) -> Vec<&SymbolicExpression> {
+    let mut path = vec![];
+    let mut level = expressions;
+    while let Some(expr) = level
+        .iter()
+        .find(|expr| span_contains(expr.span(), line, column))
+    {
+        path.push(expr);
+        level = expr.match_list().unwrap_or(&[]);
+    }
+    path
+}
+
+/// The local bindings (function arguments and `let` bindings) that are in scope at the end of
+/// `path`, from the innermost to the outermost
+fn local_bindings<'a>(path: &[&'a SymbolicExpression]) -> Vec<&'a SymbolicExpression> {
+    let mut bindings = vec![];
+    for expr in path.iter().rev() {
+        let Some(list) = expr.match_list() else {
+            continue;
+        };
+        let head = list.first().and_then(|head| head.match_atom());
+        let binding_list = match head.map(|head| head.as_str()) {
+            Some("let") => list.get(1).and_then(|bindings| bindings.match_list()),
+            Some("define-public") | Some("define-private") | Some("define-read-only") => list
+                .get(1)
+                .and_then(|signature| signature.match_list())
+                .and_then(|signature| signature.get(1..)),
+            _ => None,
+        };
+        for binding in binding_list.unwrap_or(&[]) {
+            if let Some(name) = binding.match_list().and_then(|pair| pair.first()) {
+                if name.match_atom().is_some() {
+                    bindings.push(name);
+                }
+            }
+        }
+    }
+    bindings
+}
+
+fn span_range(span: &Span) -> Value {
+    json!({
+        "start": {
+            "line": span.start_line.saturating_sub(1),
+            "character": span.start_column.saturating_sub(1),
+        },
+        "end": {
+            "line": span.end_line.saturating_sub(1),
+            "character": span.end_column,
+        },
+    })
+}
+
+fn diagnostic_to_lsp(diagnostic: &Diagnostic) -> Value {
+    let severity = match diagnostic.level {
+        Level::Error => 1,
+        Level::Warning => 2,
+        Level::Note => 3,
+    };
+    let range = diagnostic
+        .spans
+        .first()
+        .map(span_range)
+        .unwrap_or_else(|| span_range(&Span::zero()));
+    let message = match diagnostic.suggestion {
+        Some(ref suggestion) => format!("{}\n{}", diagnostic.message, suggestion),
+        None => diagnostic.message.clone(),
+    };
+    json!({
+        "range": range,
+        "severity": severity,
+        "source": "clarity",
+        "message": message,
+    })
+}
+
+fn markdown(code: &str, text: &str) -> String {
+    if text.is_empty() {
+        format!("```clarity\n{}\n```", code)
+    } else {
+        format!("```clarity\n{}\n```\n\n{}", code, text)
+    }
+}
+
+/// The reference documentation of a native function, `define-*` form or keyword, as Markdown
+fn native_docs(name: &str, clarity_version: &ClarityVersion) -> Option<String> {
+    let (signature, description, example) = if let Some(define_type) =
+        DefineFunctions::lookup_by_name(name)
+    {
+        let api = make_define_reference(&define_type);
+        (api.signature, api.description, api.example)
+    } else if let Some(function) = NativeFunctions::lookup_by_name_at_version(name, clarity_version)
+    {
+        let api = make_api_reference(&function);
+        (api.signature, api.description, api.example)
+    } else {
+        let variable = NativeVariables::lookup_by_name_at_version(name, clarity_version)?;
+        let api = make_keyword_reference(&variable)?;
+        (
+            format!("{}: {}", api.name, api.output_type),
+            api.description.to_string(),
+            api.example.to_string(),
+        )
+    };
+    Some(markdown(
+        &signature,
+        &format!(
+            "{}\n\n**Example**\n```clarity\n{}\n```",
+            description, example
+        ),
+    ))
+}
+
+fn function_signature(define: &str, name: &str, function_type: &FunctionType) -> String {
+    match function_type {
+        FunctionType::Fixed(function) => {
+            let args: Vec<_> = function
+                .args
+                .iter()
+                .map(|arg| format!(" ({} {})", arg.name, arg.signature))
+                .collect();
+            format!(
+                "({} ({}{}))\n;; returns {}",
+                define,
+                name,
+                args.join(""),
+                function.returns
+            )
+        }
+        _ => format!("({} ({}))", define, name),
+    }
+}
+
+/// Describe a definition, with its types if the contract passed its checks
+fn definition_docs(definition: &Definition, analysis: Option<&ContractAnalysis>) -> String {
+    let define = definition.define_type.get_name();
+    let name = definition.name;
+    let described = analysis.and_then(|analysis| match definition.define_type {
+        DefineFunctions::PublicFunction => analysis
+            .get_public_function_type(name)
+            .map(|function_type| function_signature(&define, name, function_type)),
+        DefineFunctions::ReadOnlyFunction => analysis
+            .get_read_only_function_type(name)
+            .map(|function_type| function_signature(&define, name, function_type)),
+        DefineFunctions::PrivateFunction => analysis
+            .get_private_function(name)
+            .map(|function_type| function_signature(&define, name, function_type)),
+        DefineFunctions::Constant => analysis
+            .get_variable_type(name)
+            .map(|var_type| format!("({} {})\n;; type {}", define, name, var_type)),
+        DefineFunctions::PersistedVariable => analysis
+            .get_persisted_variable_type(name)
+            .map(|var_type| format!("({} {} {})", define, name, var_type)),
+        DefineFunctions::Map => analysis
+            .get_map_type(name)
+            .map(|(key, value)| format!("({} {} {} {})", define, name, key, value)),
+        DefineFunctions::NonFungibleToken => analysis
+            .non_fungible_tokens
+            .get(name)
+            .map(|asset_type| format!("({} {} {})", define, name, asset_type)),
+        DefineFunctions::Trait => analysis.defined_traits.get(name).map(|functions| {
+            let functions: Vec<_> = functions
+                .iter()
+                .map(|(function_name, signature)| {
+                    let args: Vec<_> = signature.args.iter().map(|arg| arg.to_string()).collect();
+                    format!(
+                        "\n  ({} ({}) {})",
+                        function_name,
+                        args.join(" "),
+                        signature.returns
+                    )
+                })
+                .collect();
+            format!("({} {} ({}))", define, name, functions.join(""))
+        }),
+        _ => None,
+    });
+    if let Some(described) = described {
+        return described;
+    }
+    match definition.define_type {
+        DefineFunctions::UseTrait | DefineFunctions::ImplTrait => {
+            let trait_id = definition
+                .expr
+                .match_list()
+                .and_then(|list| list.get(2))
+                .and_then(|trait_expr| trait_expr.match_field());
+            match trait_id {
+                Some(trait_id) => format!("({} {} {})", define, name, trait_id),
+                None => format!("({} {})", define, name),
+            }
+        }
+        _ => format!("({} {})", define, name),
+    }
+}
+
+fn completion_kind(define_type: &DefineFunctions) -> u64 {
+    match define_type {
+        DefineFunctions::PublicFunction
+        | DefineFunctions::ReadOnlyFunction
+        | DefineFunctions::PrivateFunction => COMPLETION_FUNCTION,
+        DefineFunctions::Constant => COMPLETION_CONSTANT,
+        DefineFunctions::Map => COMPLETION_STRUCT,
+        DefineFunctions::Trait | DefineFunctions::UseTrait | DefineFunctions::ImplTrait => {
+            COMPLETION_INTERFACE
+        }
+        DefineFunctions::PersistedVariable
+        | DefineFunctions::FungibleToken
+        | DefineFunctions::NonFungibleToken => COMPLETION_VARIABLE,
+    }
+}
+
+struct Document {
+    text: String,
+    /// The analysis of the latest version of the document that could be parsed
+    analysis: Option<DocumentAnalysis>,
+}
+
+/// An LSP server for Clarity contracts.  Messages are handled one at a time by
+/// `handle_message`; `run` serves a client over a pair of streams.
+pub struct LanguageServer {
+    epoch: StacksEpochId,
+    clarity_version: ClarityVersion,
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+    exit: bool,
+}
+
+impl Default for LanguageServer {
+    fn default() -> Self {
+        LanguageServer::new(StacksEpochId::latest(), None)
+    }
+}
+
+impl LanguageServer {
+    /// Make a server that checks contracts in `epoch`, with the given Clarity version or the
+    /// epoch's default version.  Clients can override both in their `initializationOptions`.
+    pub fn new(epoch: StacksEpochId, clarity_version: Option<ClarityVersion>) -> LanguageServer {
+        LanguageServer {
+            epoch,
+            clarity_version: clarity_version
+                .unwrap_or_else(|| ClarityVersion::default_for_epoch(epoch)),
+            documents: HashMap::new(),
+            shutdown: false,
+            exit: false,
+        }
+    }
+
+    /// Serve a client until it sends `exit` or closes its stream.  Returns the process exit
+    /// code: 0 if the client shut the server down before exiting, 1 otherwise.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: &mut R, output: &mut W) -> io::Result<i32> {
+        while let Some(body) = read_message(input)? {
+            let replies = match serde_json::from_slice::<Value>(&body) {
+                Ok(message) => self.handle_message(&message),
+                Err(e) => vec![error_response(
+                    Value::Null,
+                    PARSE_ERROR,
+                    &format!("invalid JSON: {}", e),
+                )],
+            };
+            for reply in replies.iter() {
+                write_message(output, reply)?;
+            }
+            if self.exit {
+                break;
+            }
+        }
+        Ok(if self.shutdown { 0 } else { 1 })
+    }
+
+    /// Handle one message from the client, returning the messages to send back
+    pub fn handle_message(&mut self, message: &Value) -> Vec<Value> {
+        let Some(method) = message.get("method").and_then(|method| method.as_str()) else {
+            // a response to a request that this server never sends
+            return vec![];
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let Some(id) = message.get("id").cloned() else {
+            return self.handle_notification(method, &params);
+        };
+
+        if self.shutdown {
+            return vec![error_response(
+                id,
+                INVALID_REQUEST,
+                "the server is shutting down",
+            )];
+        }
+        let result = match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => self.hover(&params),
+            "textDocument/definition" => self.definition(&params),
+            "textDocument/completion" => self.completion(&params),
+            _ => Err((METHOD_NOT_FOUND, format!("unsupported method: {}", method))),
+        };
+        match result {
+            Ok(result) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            Err((code, message)) => vec![error_response(id, code, &message)],
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params
+            .pointer("/textDocument/uri")
+            .and_then(|uri| uri.as_str())
+            .map(|uri| uri.to_string());
+        match (method, uri) {
+            ("exit", _) => {
+                self.exit = true;
+                vec![]
+            }
+            ("textDocument/didOpen", Some(uri)) => {
+                let text = params
+                    .pointer("/textDocument/text")
+                    .and_then(|text| text.as_str())
+                    .unwrap_or("");
+                self.update_document(&uri, text);
+                self.publish_diagnostics(&uri)
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                // the server only asks for full-document sync
+                let text = params
+                    .pointer("/contentChanges")
+                    .and_then(|changes| changes.as_array())
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(|text| text.as_str());
+                if let Some(text) = text {
+                    self.update_document(&uri, text);
+                }
+                vec![]
+            }
+            ("textDocument/didSave", Some(uri)) => {
+                if let Some(text) = params.get("text").and_then(|text| text.as_str()) {
+                    self.update_document(&uri, text);
+                }
+                self.publish_diagnostics(&uri)
+            }
+            ("textDocument/didClose", Some(uri)) => {
+                self.documents.remove(&uri);
+                vec![publish_diagnostics_notification(&uri, vec![])]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn initialize(&mut self, params: &Value) -> Value {
+        let options = params.get("initializationOptions");
+        let epoch = options
+            .and_then(|options| options.get("epoch"))
+            .and_then(|epoch| epoch.as_str())
+            .and_then(|epoch| supported_epochs().find(|e| e.to_string() == epoch));
+        if let Some(epoch) = epoch {
+            self.epoch = epoch;
+            self.clarity_version = ClarityVersion::default_for_epoch(epoch);
+        }
+        let clarity_version = options
+            .and_then(|options| options.get("clarityVersion"))
+            .and_then(|version| version.as_str())
+            .and_then(|version| version.parse::<ClarityVersion>().ok());
+        if let Some(clarity_version) = clarity_version {
+            self.clarity_version = clarity_version;
+        }
+
+        json!({
+            "capabilities": {
+                "textDocumentSync": {
+                    "openClose": true,
+                    "change": 1,
+                    "save": { "includeText": true },
+                },
+                "hoverProvider": true,
+                "definitionProvider": true,
+                "completionProvider": { "triggerCharacters": ["("] },
+            },
+            "serverInfo": {
+                "name": "clarity-lsp",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        })
+    }
+
+    fn update_document(&mut self, uri: &str, text: &str) {
+        let analysis = analyze_document(
+            &contract_identifier_for_uri(uri),
+            text,
+            self.epoch,
+            self.clarity_version,
+        );
+        let document = self
+            .documents
+            .entry(uri.to_string())
+            .or_insert_with(|| Document {
+                text: String::new(),
+                analysis: None,
+            });
+        document.text = text.to_string();
+        // keep the last analysis that found some definitions, so that completion keeps working
+        //  while a document is being edited
+        let parsed = !analysis.expressions.is_empty() || text.trim().is_empty();
+        if parsed || document.analysis.is_none() {
+            document.analysis = Some(analysis);
+        }
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str) -> Vec<Value> {
+        let Some(document) = self.documents.get(uri) else {
+            return vec![];
+        };
+        // re-check the current text, in case the last analysis is an older one
+        let analysis = analyze_document(
+            &contract_identifier_for_uri(uri),
+            &document.text,
+            self.epoch,
+            self.clarity_version,
+        );
+        let diagnostics = analysis.diagnostics.iter().map(diagnostic_to_lsp).collect();
+        vec![publish_diagnostics_notification(uri, diagnostics)]
+    }
+
+    /// Look up the analysis of the document and the 1-based line and column of a request
+    fn document_position(
+        &self,
+        params: &Value,
+    ) -> Result<(String, &DocumentAnalysis, u32, u32), (i64, String)> {
+        let uri = params
+            .pointer("/textDocument/uri")
+            .and_then(|uri| uri.as_str())
+            .ok_or_else(|| (INVALID_PARAMS, "missing textDocument.uri".to_string()))?;
+        let line = params
+            .pointer("/position/line")
+            .and_then(|line| line.as_u64())
+            .ok_or_else(|| (INVALID_PARAMS, "missing position.line".to_string()))?;
+        let character = params
+            .pointer("/position/character")
+            .and_then(|character| character.as_u64())
+            .ok_or_else(|| (INVALID_PARAMS, "missing position.character".to_string()))?;
+        let analysis = self
+            .documents
+            .get(uri)
+            .and_then(|document| document.analysis.as_ref())
+            .ok_or_else(|| (INVALID_PARAMS, format!("unknown document: {}", uri)))?;
+        let line = u32::try_from(line + 1).unwrap_or(u32::MAX);
+        let column = u32::try_from(character + 1).unwrap_or(u32::MAX);
+        Ok((uri.to_string(), analysis, line, column))
+    }
+
+    fn hover(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let (_, analysis, line, column) = self.document_position(params)?;
+        let path = expression_path(&analysis.expressions, line, column);
+        let Some(expr) = path.last() else {
+            return Ok(Value::Null);
+        };
+        let contract_analysis = analysis.contract_analysis.as_ref();
+        let expr_type = contract_analysis
+            .and_then(|contract_analysis| contract_analysis.type_map.as_ref())
+            .and_then(|type_map| type_map.get_type_expected(expr));
+
+        let contents = match &expr.expr {
+            SymbolicExpressionType::Atom(name) => {
+                let definition = definitions(&analysis.expressions)
+                    .into_iter()
+                    .find(|definition| definition.name == name.as_str());
+                let is_local = local_bindings(&path)
+                    .iter()
+                    .any(|binding| binding.match_atom() == Some(name));
+                if let (Some(definition), false) = (definition, is_local) {
+                    Some(markdown(
+                        &definition_docs(&definition, contract_analysis),
+                        "",
+                    ))
+                } else if let (Some(docs), false) =
+                    (native_docs(name, &self.clarity_version), is_local)
+                {
+                    Some(docs)
+                } else {
+                    expr_type.map(|expr_type| markdown(&format!("{}: {}", name, expr_type), ""))
+                }
+            }
+            SymbolicExpressionType::TraitReference(name, trait_definition) => {
+                let (TraitDefinition::Defined(trait_id) | TraitDefinition::Imported(trait_id)) =
+                    trait_definition;
+                Some(markdown(&format!("<{}>: {}", name, trait_id), ""))
+            }
+            SymbolicExpressionType::Field(trait_id) => {
+                Some(markdown(&format!("trait {}", trait_id), ""))
+            }
+            _ => expr_type.map(|expr_type| markdown(&expr_type.to_string(), "")),
+        };
+
+        Ok(match contents {
+            Some(contents) => json!({
+                "contents": { "kind": "markdown", "value": contents },
+                "range": span_range(expr.span()),
+            }),
+            None => Value::Null,
+        })
+    }
+
+    fn definition(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let (uri, analysis, line, column) = self.document_position(params)?;
+        let path = expression_path(&analysis.expressions, line, column);
+        let Some(expr) = path.last() else {
+            return Ok(Value::Null);
+        };
+        let name = match &expr.expr {
+            SymbolicExpressionType::Atom(name) => name.as_str(),
+            SymbolicExpressionType::TraitReference(name, _) => name.as_str(),
+            SymbolicExpressionType::Field(trait_id)
+                if trait_id.contract_identifier == analysis.contract_identifier =>
+            {
+                trait_id.name.as_str()
+            }
+            _ => return Ok(Value::Null),
+        };
+
+        let target = if let SymbolicExpressionType::Atom(_) = expr.expr {
+            local_bindings(&path)
+                .into_iter()
+                .find(|binding| binding.match_atom().map(|n| n.as_str()) == Some(name))
+        } else {
+            None
+        };
+        let target = target.or_else(|| {
+            definitions(&analysis.expressions)
+                .into_iter()
+                .find(|definition| {
+                    definition.name == name
+                        && match expr.expr {
+                            SymbolicExpressionType::TraitReference(..) => matches!(
+                                definition.define_type,
+                                DefineFunctions::Trait | DefineFunctions::UseTrait
+                            ),
+                            SymbolicExpressionType::Field(_) => {
+                                definition.define_type == DefineFunctions::Trait
+                            }
+                            _ => true,
+                        }
+                })
+                .map(|definition| definition.name_expr)
+        });
+        Ok(match target {
+            Some(target) => json!({ "uri": uri, "range": span_range(target.span()) }),
+            None => Value::Null,
+        })
+    }
+
+    fn completion(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let (_, analysis, line, column) = self.document_position(params)?;
+        let mut items = vec![];
+
+        // bindings in scope at the cursor (which is just after the character before it)
+        let path = expression_path(&analysis.expressions, line, column.saturating_sub(1));
+        for binding in local_bindings(&path) {
+            if let Some(name) = binding.match_atom() {
+                items.push(json!({ "label": name.as_str(), "kind": COMPLETION_VARIABLE }));
+            }
+        }
+
+        let contract_analysis = analysis.contract_analysis.as_ref();
+        for definition in definitions(&analysis.expressions) {
+            if definition.define_type == DefineFunctions::ImplTrait {
+                continue;
+            }
+            items.push(json!({
+                "label": definition.name,
+                "kind": completion_kind(&definition.define_type),
+                "detail": definition_docs(&definition, contract_analysis),
+            }));
+        }
+
+        for define_type in DefineFunctions::ALL.iter() {
+            let api = make_define_reference(define_type);
+            items.push(json!({
+                "label": api.name,
+                "kind": COMPLETION_KEYWORD,
+                "detail": api.signature,
+                "documentation": { "kind": "markdown", "value": api.description },
+            }));
+        }
+        for function in NativeFunctions::ALL.iter() {
+            if NativeFunctions::lookup_by_name_at_version(
+                &function.get_name(),
+                &self.clarity_version,
+            )
+            .is_none()
+            {
+                continue;
+            }
+            let api = make_api_reference(function);
+            items.push(json!({
+                "label": api.name,
+                "kind": COMPLETION_FUNCTION,
+                "detail": api.signature,
+                "documentation": { "kind": "markdown", "value": api.description },
+            }));
+        }
+        for variable in NativeVariables::ALL.iter() {
+            if NativeVariables::lookup_by_name_at_version(
+                &variable.get_name(),
+                &self.clarity_version,
+            )
+            .is_none()
+            {
+                continue;
+            }
+            if let Some(api) = make_keyword_reference(variable) {
+                items.push(json!({
+                    "label": api.name,
+                    "kind": COMPLETION_CONSTANT,
+                    "detail": api.output_type,
+                    "documentation": { "kind": "markdown", "value": api.description },
+                }));
+            }
+        }
+
+        Ok(json!({ "isIncomplete": false, "items": items }))
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn publish_diagnostics_notification(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+/// Name the contract in a document after its file, e.g. `file:///src/token.clar` is checked as
+/// the local contract `.token`
+fn contract_identifier_for_uri(uri: &str) -> QualifiedContractIdentifier {
+    let file_name = uri.rsplit('/').next().unwrap_or(uri);
+    let name = file_name.strip_suffix(".clar").unwrap_or(file_name);
+    QualifiedContractIdentifier::local(name)
+        .unwrap_or_else(|_| QualifiedContractIdentifier::transient())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///workspace/counter.clar";
+
+    const SOURCE: &str =
+        "(define-trait token-trait ((get-balance (principal) (response uint uint))))
+(define-constant OWNER tx-sender)
+(define-data-var counter uint u0)
+(define-map balances principal uint)
+(define-public (incr (by uint))
+  (let ((next (+ (var-get counter) by)))
+    (var-set counter next)
+    (ok next)))
+(define-read-only (get-balance-of (t <token-trait>) (who principal))
+  (ok (map-get? balances who)))
+";
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    fn notification(method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "method": method, "params": params })
+    }
+
+    fn position(line: u64, character: u64) -> Value {
+        json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+    }
+
+    fn open_server(text: &str) -> (LanguageServer, Vec<Value>) {
+        let mut server = LanguageServer::default();
+        server.handle_message(&request(
+            1,
+            "initialize",
+            json!({ "initializationOptions": { "epoch": "2.5" } }),
+        ));
+        let replies = server.handle_message(&notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": URI, "languageId": "clarity", "version": 1, "text": text } }),
+        ));
+        (server, replies)
+    }
+
+    #[test]
+    fn test_message_framing() {
+        let mut stream = vec![];
+        write_message(&mut stream, &json!({ "jsonrpc": "2.0", "method": "exit" })).unwrap();
+        write_message(&mut stream, &json!({ "jsonrpc": "2.0", "id": 1 })).unwrap();
+        assert!(stream.starts_with(b"Content-Length: 33\r\n\r\n"));
+
+        let mut input = io::Cursor::new(stream);
+        let first = read_message(&mut input).unwrap().unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&first).unwrap()["method"],
+            "exit"
+        );
+        let second = read_message(&mut input).unwrap().unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&second).unwrap()["id"], 1);
+        assert!(read_message(&mut input).unwrap().is_none());
+
+        // oversized bodies are rejected before anything is allocated
+        let header = format!("Content-Length: {}\r\n\r\n", MAX_MESSAGE_LEN + 1);
+        let err = read_message(&mut io::Cursor::new(header.into_bytes())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_initialize_epoch() {
+        for epoch in supported_epochs() {
+            let mut server = LanguageServer::default();
+            server.handle_message(&request(
+                1,
+                "initialize",
+                json!({ "initializationOptions": { "epoch": epoch.to_string() } }),
+            ));
+            assert_eq!(server.epoch, epoch);
+        }
+        assert_eq!(supported_epochs().last(), Some(StacksEpochId::latest()));
+    }
+
+    #[test]
+    fn test_session_lifecycle() {
+        let messages = [
+            request(1, "initialize", json!({})),
+            notification("initialized", json!({})),
+            request(2, "workspace/symbol", json!({ "query": "" })),
+            request(3, "shutdown", Value::Null),
+            request(4, "textDocument/hover", position(0, 0)),
+            notification("exit", Value::Null),
+        ];
+        let mut input = vec![];
+        for message in messages.iter() {
+            write_message(&mut input, message).unwrap();
+        }
+        input.extend_from_slice(b"Content-Length: 5\r\n\r\n{oops");
+
+        let mut output = vec![];
+        let mut server = LanguageServer::default();
+        let exit_code = server
+            .run(&mut io::Cursor::new(input), &mut output)
+            .unwrap();
+        assert_eq!(exit_code, 0);
+
+        let mut output = io::Cursor::new(output);
+        let mut replies = vec![];
+        while let Some(body) = read_message(&mut output).unwrap() {
+            replies.push(serde_json::from_slice::<Value>(&body).unwrap());
+        }
+        // the server stops reading at `exit`
+        assert_eq!(replies.len(), 4);
+        assert_eq!(replies[0]["result"]["capabilities"]["hoverProvider"], true);
+        assert_eq!(replies[1]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(replies[2]["result"], Value::Null);
+        assert_eq!(replies[3]["error"]["code"], INVALID_REQUEST);
+
+        // exiting without a shutdown is an error
+        let mut input = vec![];
+        write_message(&mut input, &notification("exit", Value::Null)).unwrap();
+        let exit_code = LanguageServer::default()
+            .run(&mut io::Cursor::new(input), &mut vec![])
+            .unwrap();
+        assert_eq!(exit_code, 1);
+    }
+
+    #[test]
+    fn test_diagnostics_on_open_and_save() {
+        let bad_source = format!("{}(define-public (oops) (ok (+ u1 1)))\n", SOURCE);
+        let (mut server, replies) = open_server(&bad_source);
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0]["method"], "textDocument/publishDiagnostics");
+        let diagnostics = replies[0]["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["severity"], 1);
+        assert!(diagnostics[0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("expecting expression of type 'uint'"));
+        if cfg!(feature = "developer-mode") {
+            assert_eq!(
+                diagnostics[0]["range"],
+                json!({ "start": { "line": 10, "character": 26 }, "end": { "line": 10, "character": 34 } })
+            );
+        }
+
+        // edits are only checked when saved
+        let replies = server.handle_message(&notification(
+            "textDocument/didChange",
+            json!({ "textDocument": { "uri": URI, "version": 2 }, "contentChanges": [{ "text": SOURCE }] }),
+        ));
+        assert!(replies.is_empty());
+        let replies = server.handle_message(&notification(
+            "textDocument/didSave",
+            json!({ "textDocument": { "uri": URI } }),
+        ));
+        assert_eq!(replies[0]["params"]["diagnostics"], json!([]));
+
+        // Clarity 2 functions are not available in epoch 2.05
+        let mut server = LanguageServer::new(StacksEpochId::Epoch2_05, None);
+        let replies = server.handle_message(&notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": URI, "text": "(define-read-only (f) (stx-account tx-sender))" } }),
+        ));
+        assert_eq!(
+            replies[0]["params"]["diagnostics"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_completion() {
+        let (mut server, _) = open_server(SOURCE);
+        let replies = server.handle_message(&request(2, "textDocument/completion", position(7, 8)));
+        let items = replies[0]["result"]["items"].as_array().unwrap();
+        let labels: Vec<_> = items
+            .iter()
+            .map(|item| item["label"].as_str().unwrap())
+            .collect();
+        for label in [
+            "token-trait",
+            "OWNER",
+            "counter",
+            "balances",
+            "incr",
+            "get-balance-of",
+            "define-public",
+            "map-get?",
+            "stx-account",
+            "tx-sender",
+        ] {
+            assert!(labels.contains(&label), "missing completion {}", label);
+        }
+        // Clarity 3 keywords are not offered in epoch 2.5
+        assert!(!labels.contains(&"stacks-block-height"));
+        if cfg!(feature = "developer-mode") {
+            assert!(labels.contains(&"next"));
+            assert!(labels.contains(&"by"));
+        }
+    }
+
+    #[cfg(feature = "developer-mode")]
+    #[test]
+    fn test_hover_and_definition() {
+        let (mut server, _) = open_server(SOURCE);
+        let mut call = |id: u64, method: &str, line: u64, character: u64| {
+            server.handle_message(&request(id, method, position(line, character)))[0]["result"]
+                .clone()
+        };
+
+        // a data var
+        let hover = call(2, "textDocument/hover", 6, 14);
+        assert_eq!(
+            hover["contents"]["value"],
+            "```clarity\n(define-data-var counter uint)\n```"
+        );
+        // a native function
+        let hover = call(3, "textDocument/hover", 6, 5);
+        assert!(hover["contents"]["value"]
+            .as_str()
+            .unwrap()
+            .contains("(var-set var-name expr1)"));
+        // a let binding, typed by the type checker
+        let hover = call(4, "textDocument/hover", 7, 9);
+        assert_eq!(hover["contents"]["value"], "```clarity\nnext: uint\n```");
+        // a public function
+        let hover = call(5, "textDocument/hover", 4, 17);
+        assert_eq!(
+            hover["contents"]["value"],
+            "```clarity\n(define-public (incr (by uint)))\n;; returns (response uint UnknownType)\n```"
+        );
+        // whitespace between definitions
+        assert_eq!(call(6, "textDocument/hover", 10, 0), Value::Null);
+
+        let location = |line: u64, start: u64, end: u64| {
+            json!({
+                "uri": URI,
+                "range": {
+                    "start": { "line": line, "character": start },
+                    "end": { "line": line, "character": end },
+                },
+            })
+        };
+        // a let binding
+        assert_eq!(call(7, "textDocument/definition", 7, 9), location(5, 9, 13));
+        // a function argument
+        assert_eq!(
+            call(8, "textDocument/definition", 5, 36),
+            location(4, 22, 24)
+        );
+        // a data var
+        assert_eq!(
+            call(9, "textDocument/definition", 6, 18),
+            location(2, 17, 24)
+        );
+        // a trait reference
+        assert_eq!(
+            call(10, "textDocument/definition", 8, 39),
+            location(0, 14, 25)
+        );
+        // a native function has no definition
+        assert_eq!(call(11, "textDocument/definition", 6, 5), Value::Null);
+    }
+}
@@ -1,3 +1,6 @@
+pub mod lsp;
+pub mod unit_test;
+
 use stacks_common::types::StacksEpochId;
 
 use super::analysis::ContractAnalysis;
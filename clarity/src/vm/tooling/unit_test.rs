@@ -0,0 +1,323 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Discovery of Clarity unit tests.
+//!
+//! A unit test is a public function of a test contract whose name starts with `test-`.  It
+//! passes if it returns `(ok ...)`, and fails if it returns `(err ...)` or aborts.  The `;;`
+//! comment lines directly above a test (with no blank line in between) can configure how it
+//! is run, with one annotation per line:
+//!
+//! ```text
+//! ;; @sender SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7
+//! ;; @advance-blocks 10
+//! ;; @balance SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7 1000000
+//! (define-public (test-transfer) ...)
+//! ```
+//!
+//! * `@sender` sets the `tx-sender` of the test.
+//! * `@advance-blocks` advances the block height by that many blocks before the test runs.
+//! * `@balance` sets the STX balance (in micro-STX) of a principal before the test runs.  It
+//!   can be given more than once.
+//!
+//! Comment lines that do not start with `@` are ignored.  Comments are found with the v2
+//! lexer, so that annotations are read the same way whether or not spans are recorded in the
+//! AST (i.e. with or without the `developer-mode` feature).
+
+use std::fmt;
+
+use crate::vm::ast::parser::v2::lexer::token::Token;
+use crate::vm::ast::parser::v2::lexer::Lexer;
+use crate::vm::diagnostic::DiagnosableError;
+use crate::vm::types::{PrincipalData, StandardPrincipalData};
+use crate::vm::ClarityName;
+
+/// The prefix of the names of public functions that are unit tests
+pub const TEST_FUNCTION_PREFIX: &str = "test-";
+
+/// How a unit test is run, as set by its annotations
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TestAnnotations {
+    /// The `tx-sender` of the test, if not the default sender
+    pub sender: Option<StandardPrincipalData>,
+    /// How many blocks to advance the chain by before running the test
+    pub advance_blocks: u32,
+    /// The STX balances to set before running the test
+    pub balances: Vec<(PrincipalData, u128)>,
+}
+
+/// A unit test found in a test contract
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestFunction {
+    pub name: ClarityName,
+    /// The line of the test's `define-public`
+    pub line: u32,
+    pub annotations: TestAnnotations,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiscoveryError {
+    /// The test contract could not be lexed
+    Parse(String),
+    /// An annotation of a test could not be read
+    Annotation { line: u32, message: String },
+}
+
+impl fmt::Display for DiscoveryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiscoveryError::Parse(msg) => write!(f, "failed to parse test contract: {}", msg),
+            DiscoveryError::Annotation { line, message } => {
+                write!(f, "invalid test annotation on line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+/// How much of the head of a top-level expression has been read, while looking for
+/// `(define-public (test-...`
+enum HeadState {
+    /// Just after the top-level `(`
+    Start,
+    /// After `(define-public`
+    Public,
+    /// After `(define-public (`
+    Signature,
+    /// The expression is not a test, or its name was already read
+    Done,
+}
+
+/// Find the unit tests of a test contract, in the order that they are defined.
+pub fn discover_tests(source: &str) -> Result<Vec<TestFunction>, DiscoveryError> {
+    let mut lexer = Lexer::new(source, true).map_err(|e| DiscoveryError::Parse(e.message()))?;
+    let mut tests = vec![];
+    let mut depth = 0usize;
+    // the end line of the last token read, to find blank lines and trailing comments
+    let mut last_line = 0;
+    // the comment lines directly above the next top-level expression
+    let mut comments: Vec<(u32, String)> = vec![];
+    // the comment lines directly above the current top-level expression
+    let mut head_comments: Vec<(u32, String)> = vec![];
+    let mut head_line = 0;
+    let mut head = HeadState::Done;
+
+    loop {
+        let placed = lexer
+            .read_token()
+            .map_err(|e| DiscoveryError::Parse(e.message()))?;
+        let span = placed.span;
+        let is_list = placed.token == Token::Lparen;
+        match placed.token {
+            Token::Eof => break,
+            Token::Whitespace => continue,
+            Token::Comment(text) => {
+                if depth == 0 {
+                    if span.start_line > last_line + 1 {
+                        comments.clear();
+                    }
+                    // a comment at the end of a line belongs to that line
+                    if span.start_line > last_line {
+                        comments.push((span.start_line, text));
+                    }
+                }
+            }
+            Token::Lparen | Token::Lbrace => {
+                if depth == 0 {
+                    if span.start_line > last_line + 1 {
+                        comments.clear();
+                    }
+                    head_comments = std::mem::take(&mut comments);
+                    head_line = span.start_line;
+                    head = if is_list {
+                        HeadState::Start
+                    } else {
+                        HeadState::Done
+                    };
+                } else {
+                    head = match head {
+                        HeadState::Public if depth == 1 && is_list => HeadState::Signature,
+                        _ => HeadState::Done,
+                    };
+                }
+                depth += 1;
+            }
+            Token::Rparen | Token::Rbrace => {
+                depth = depth.saturating_sub(1);
+                head = HeadState::Done;
+            }
+            Token::Ident(name) => {
+                head = match head {
+                    HeadState::Start if name == "define-public" => HeadState::Public,
+                    HeadState::Signature if name.starts_with(TEST_FUNCTION_PREFIX) => {
+                        let name = ClarityName::try_from(name)
+                            .map_err(|e| DiscoveryError::Parse(e.to_string()))?;
+                        tests.push(TestFunction {
+                            name,
+                            line: head_line,
+                            annotations: parse_annotations(&head_comments)?,
+                        });
+                        HeadState::Done
+                    }
+                    _ => HeadState::Done,
+                };
+            }
+            _ => {
+                head = HeadState::Done;
+            }
+        }
+        last_line = span.end_line;
+    }
+
+    Ok(tests)
+}
+
+fn parse_principal(arg: &str) -> Result<PrincipalData, String> {
+    PrincipalData::parse(arg.trim_start_matches('\''))
+        .map_err(|e| format!("invalid principal `{}`: {}", arg, e))
+}
+
+fn parse_annotations(comments: &[(u32, String)]) -> Result<TestAnnotations, DiscoveryError> {
+    let mut annotations = TestAnnotations::default();
+    for (line, text) in comments.iter() {
+        let Some(annotation) = text.strip_prefix('@') else {
+            continue;
+        };
+        let error = |message: String| DiscoveryError::Annotation {
+            line: *line,
+            message,
+        };
+        let mut words = annotation.split_whitespace();
+        let name = words.next().unwrap_or("");
+        let args: Vec<_> = words.collect();
+        match (name, args.as_slice()) {
+            ("sender", [sender]) => {
+                let sender = match parse_principal(sender).map_err(error)? {
+                    PrincipalData::Standard(sender) => sender,
+                    PrincipalData::Contract(_) => {
+                        return Err(error(format!(
+                            "@sender must be a standard principal, not `{}`",
+                            sender
+                        )));
+                    }
+                };
+                annotations.sender = Some(sender);
+            }
+            ("advance-blocks", [blocks]) => {
+                annotations.advance_blocks = blocks
+                    .parse()
+                    .map_err(|_| error(format!("invalid number of blocks `{}`", blocks)))?;
+            }
+            ("balance", [principal, amount]) => {
+                let principal = parse_principal(principal).map_err(error)?;
+                let amount = amount
+                    .parse()
+                    .map_err(|_| error(format!("invalid amount `{}`", amount)))?;
+                annotations.balances.push((principal, amount));
+            }
+            ("sender", _) => return Err(error("usage: @sender <principal>".into())),
+            ("advance-blocks", _) => return Err(error("usage: @advance-blocks <blocks>".into())),
+            ("balance", _) => return Err(error("usage: @balance <principal> <ustx>".into())),
+            _ => return Err(error(format!("unknown annotation `@{}`", name))),
+        }
+    }
+    Ok(annotations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_CONTRACT: &str = "
+(define-constant deployer tx-sender) ;; @sender is not read here
+
+;; @advance-blocks 3
+(define-read-only (test-not-public) (ok true))
+
+;; Transfers to the second wallet.
+;; @sender 'S1G2081040G2081040G2081040G208105NK8PE5
+;; @balance S1G2081040G2081040G2081040G208105NK8PE5 1000
+;; @balance S1G2081040G2081040G2081040G208105NK8PE5.names 20
+;; @advance-blocks 10
+(define-public (test-transfer)
+  (stx-transfer? u10 tx-sender 'S1G2081040G2081040G2081040G208105NK8PE5.names))
+
+;; @sender S1G2081040G2081040G2081040G208105NK8PE5
+
+(define-public (test-defaults) (ok (list (define-public (test-nested)))))
+(define-public (helper) (ok true))
+";
+
+    #[test]
+    fn discovers_annotated_tests() {
+        let tests = discover_tests(TEST_CONTRACT).unwrap();
+        assert_eq!(tests.len(), 2);
+
+        let transfer = &tests[0];
+        assert_eq!(transfer.name.as_str(), "test-transfer");
+        assert_eq!(transfer.line, 12);
+        let sender =
+            PrincipalData::parse_standard_principal("S1G2081040G2081040G2081040G208105NK8PE5")
+                .unwrap();
+        assert_eq!(transfer.annotations.sender, Some(sender.clone()));
+        assert_eq!(transfer.annotations.advance_blocks, 10);
+        assert_eq!(
+            transfer.annotations.balances,
+            vec![
+                (PrincipalData::Standard(sender), 1000),
+                (
+                    PrincipalData::parse("S1G2081040G2081040G2081040G208105NK8PE5.names").unwrap(),
+                    20
+                ),
+            ]
+        );
+
+        // annotations separated from a test by a blank line do not apply to it
+        assert_eq!(tests[1].name.as_str(), "test-defaults");
+        assert_eq!(tests[1].annotations, TestAnnotations::default());
+    }
+
+    #[test]
+    fn rejects_invalid_annotations() {
+        let err = discover_tests(";; @sender\n(define-public (test-a) (ok true))").unwrap_err();
+        assert_eq!(
+            err,
+            DiscoveryError::Annotation {
+                line: 1,
+                message: "usage: @sender <principal>".into()
+            }
+        );
+
+        let err = discover_tests(
+            "\n;; @sender S1G2081040G2081040G2081040G208105NK8PE5.names\n(define-public (test-a) (ok true))",
+        )
+        .unwrap_err();
+        assert!(matches!(err, DiscoveryError::Annotation { line: 2, .. }));
+
+        let err = discover_tests(";; @advance-blocks -1\n(define-public (test-a) (ok true))")
+            .unwrap_err();
+        assert!(matches!(err, DiscoveryError::Annotation { line: 1, .. }));
+
+        let err = discover_tests(";; @mine 1\n(define-public (test-a) (ok true))").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid test annotation on line 1: unknown annotation `@mine`"
+        );
+
+        // annotations of functions that are not tests are not read
+        assert!(discover_tests(";; @mine 1\n(define-public (a) (ok true))")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -460,6 +460,21 @@ impl MockSignature {
         sig
     }
 
+    /// Create a new mock signature from the provided proposal, signed with the given signing
+    /// function.  The signing function is given the hash to sign.
+    pub fn new_with_signer<F>(mock_proposal: MockProposal, sign_fn: F) -> Result<Self, String>
+    where
+        F: FnOnce(&Sha256Sum) -> Result<MessageSignature, String>,
+    {
+        let mut sig = Self {
+            signature: MessageSignature::empty(),
+            mock_proposal,
+            metadata: SignerMessageMetadata::default(),
+        };
+        sig.signature = sign_fn(&sig.mock_proposal.signer_signature_hash())?;
+        Ok(sig)
+    }
+
     /// Sign the mock signature and set the internal signature field
     fn sign(&mut self, private_key: &StacksPrivateKey) -> Result<(), String> {
         let signature_hash = self.mock_proposal.signer_signature_hash();
@@ -1143,6 +1158,32 @@ impl BlockRejection {
         mainnet: bool,
         timestamp: u64,
     ) -> Self {
+        Self::new_with_signer(
+            signer_signature_hash,
+            reject_reason,
+            mainnet,
+            timestamp,
+            |signature_hash| {
+                private_key
+                    .sign(signature_hash.as_bytes())
+                    .map_err(String::from)
+            },
+        )
+        .expect("Failed to sign BlockRejection")
+    }
+
+    /// Create a new BlockRejection for the provided block and reason code, signed with the given
+    /// signing function.  The signing function is given the hash to sign.
+    pub fn new_with_signer<F>(
+        signer_signature_hash: Sha512Trunc256Sum,
+        reject_reason: RejectReason,
+        mainnet: bool,
+        timestamp: u64,
+        sign_fn: F,
+    ) -> Result<Self, String>
+    where
+        F: FnOnce(&Sha256Sum) -> Result<MessageSignature, String>,
+    {
         let chain_id = if mainnet {
             CHAIN_ID_MAINNET
         } else {
@@ -1157,10 +1198,8 @@ impl BlockRejection {
             metadata: SignerMessageMetadata::default(),
             response_data: BlockResponseData::new(timestamp, reject_reason),
         };
-        rejection
-            .sign(private_key)
-            .expect("Failed to sign BlockRejection");
-        rejection
+        rejection.signature = sign_fn(&rejection.hash())?;
+        Ok(rejection)
     }
 
     /// Create a new BlockRejection from a BlockValidateRejection
@@ -1170,6 +1209,25 @@ impl BlockRejection {
         mainnet: bool,
         timestamp: u64,
     ) -> Self {
+        Self::from_validate_rejection_with_signer(reject, mainnet, timestamp, |signature_hash| {
+            private_key
+                .sign(signature_hash.as_bytes())
+                .map_err(String::from)
+        })
+        .expect("Failed to sign BlockRejection")
+    }
+
+    /// Create a new BlockRejection from a BlockValidateRejection, signed with the given signing
+    /// function.  The signing function is given the hash to sign.
+    pub fn from_validate_rejection_with_signer<F>(
+        reject: BlockValidateReject,
+        mainnet: bool,
+        timestamp: u64,
+        sign_fn: F,
+    ) -> Result<Self, String>
+    where
+        F: FnOnce(&Sha256Sum) -> Result<MessageSignature, String>,
+    {
         let chain_id = if mainnet {
             CHAIN_ID_MAINNET
         } else {
@@ -1185,10 +1243,8 @@ impl BlockRejection {
             metadata: SignerMessageMetadata::default(),
             response_data: BlockResponseData::new(timestamp, (&reject_code).into()),
         };
-        rejection
-            .sign(private_key)
-            .expect("Failed to sign BlockRejection");
-        rejection
+        rejection.signature = sign_fn(&rejection.hash())?;
+        Ok(rejection)
     }
 
     /// The signature hash for the block rejection
@@ -1198,13 +1254,6 @@ impl BlockRejection {
         structured_data_message_hash(data, domain_tuple)
     }
 
-    /// Sign the block rejection and set the internal signature field
-    fn sign(&mut self, private_key: &StacksPrivateKey) -> Result<(), String> {
-        let signature_hash = self.hash();
-        self.signature = private_key.sign(signature_hash.as_bytes())?;
-        Ok(())
-    }
-
     /// Verify the rejection's signature against the provided signer public key
     pub fn verify(&self, public_key: &StacksPublicKey) -> Result<bool, String> {
         if self.signature == MessageSignature::empty() {
@@ -169,10 +169,19 @@ impl SlotMetadata {
     /// data_hash.  Sets self.signature to the signature.
     /// Fails if the underlying crypto library fails
     pub fn sign(&mut self, privkey: &StacksPrivateKey) -> Result<(), Error> {
+        self.sign_with(|auth_digest| privkey.sign(&auth_digest.0).map_err(|se| se.to_string()))
+    }
+
+    /// Sign this slot metadata with the given signing function, which is given the digest
+    /// to sign.  This is for signers whose private key is not held in memory.
+    /// Sets self.signature to the signature.
+    /// Fails if `sign_fn` fails
+    pub fn sign_with<F>(&mut self, sign_fn: F) -> Result<(), Error>
+    where
+        F: FnOnce(&Sha512Trunc256Sum) -> Result<MessageSignature, String>,
+    {
         let auth_digest = self.auth_digest();
-        let sig = privkey
-            .sign(&auth_digest.0)
-            .map_err(|se| Error::SigningError(se.to_string()))?;
+        let sig = sign_fn(&auth_digest).map_err(Error::SigningError)?;
 
         self.signature = sig;
         Ok(())
@@ -227,6 +236,20 @@ impl StackerDBChunkData {
         Ok(())
     }
 
+    /// Sign this given chunk data message with the given signing function, which is given the
+    /// digest to sign.
+    /// Sets self.signature to the signature.
+    /// Fails if `sign_fn` fails.
+    pub fn sign_with<F>(&mut self, sign_fn: F) -> Result<(), Error>
+    where
+        F: FnOnce(&Sha512Trunc256Sum) -> Result<MessageSignature, String>,
+    {
+        let mut md = self.get_slot_metadata();
+        md.sign_with(sign_fn)?;
+        self.sig = md.signature;
+        Ok(())
+    }
+
     pub fn recover_pk(&self) -> Result<StacksPublicKey, Error> {
         let digest = self.get_slot_metadata().auth_digest();
         StacksPublicKey::recover_to_pubkey(digest.as_bytes(), &self.sig)
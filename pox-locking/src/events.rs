@@ -656,7 +656,7 @@ fn inner_synthesize_pox_event_info(
         .special_cc_handler_execute_read_only(
             sender.clone(),
             None,
-            pox_contract.contract_context,
+            pox_contract.contract_context.clone(),
             |env| {
                 let base_event_info = env
                     .eval_read_only_with_rules(contract_id, &code_snippet, ASTRules::PrecheckSize)
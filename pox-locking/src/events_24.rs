@@ -388,7 +388,7 @@ pub fn synthesize_pox_2_or_3_event_info(
         .special_cc_handler_execute_read_only(
             sender.clone(),
             None,
-            pox_2_contract.contract_context,
+            pox_2_contract.contract_context.clone(),
             |env| {
                 let base_event_info = env
                     .eval_read_only_with_rules(contract_id, &code_snippet, ASTRules::PrecheckSize)
@@ -59,6 +59,10 @@ pub mod consts {
 
     pub const STACKS_EPOCH_MAX: u64 = i64::MAX as u64;
 
+    /// The threshold of weighted votes on a block to approve it in Nakamoto.
+    /// This is out of 10, so 7 means "70%".
+    pub const NAKAMOTO_SIGNER_BLOCK_APPROVAL_THRESHOLD: u64 = 7;
+
     /// The number of StackerDB slots each signing key needs
     ///  to use to participate in DKG and block validation signing.
     pub const SIGNER_SLOTS_PER_USER: u32 = 13;
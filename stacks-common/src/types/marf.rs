@@ -0,0 +1,1497 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! MARF values, leaves, and Merkle proofs, along with proof verification and the proofs' wire
+//! format.  None of this needs the MARF's storage, so clients can decode and verify proofs
+//! without depending on stackslib.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::ops::Deref;
+
+use sha2::{Digest, Sha512_256 as TrieHasher};
+
+use crate::codec::{read_next, Error as codec_error, StacksMessageCodec};
+#[cfg(any(test, feature = "testing"))]
+use crate::types::chainstate::BlockHeaderHash;
+use crate::types::chainstate::{
+    BurnchainHeaderHash, SortitionId, StacksBlockId, TrieHash, TRIEHASH_ENCODED_SIZE,
+};
+use crate::util::hash::to_hex;
+
+#[derive(Debug)]
+pub struct TrieMerkleProof<T: ClarityMarfTrieId>(pub Vec<TrieMerkleProofType<T>>);
+
+/// Merkle proofs of inclusion or exclusion for several keys in the same trie.  Each proof node is
+/// stored once, no matter how many of the keys' proofs contain it.
+#[derive(Debug)]
+pub struct TrieMerkleMultiProof<T: ClarityMarfTrieId> {
+    /// Deduplicated proof nodes
+    pub nodes: Vec<TrieMerkleProofType<T>>,
+    /// The proven keys and values, or absent keys
+    pub entries: Vec<TrieMerkleMultiProofEntry>,
+    /// Root hashes of the tries that the proofs pass through (including the tip's), and their blocks
+    pub ancestor_roots: Vec<(TrieHash, T)>,
+}
+
+/// One key proven by a `TrieMerkleMultiProof`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrieMerkleMultiProofEntry {
+    pub path: TrieHash,
+    /// The key's value, or None if the entry proves that the key is absent
+    pub value: Option<MARFValue>,
+    /// Indexes into the multi-proof's nodes, in the order of this entry's `TrieMerkleProof`
+    pub nodes: Vec<u32>,
+}
+
+pub trait ClarityMarfTrieId:
+    PartialEq + Clone + std::fmt::Display + std::fmt::Debug + std::convert::From<[u8; 32]>
+{
+    fn as_bytes(&self) -> &[u8];
+    fn to_bytes(self) -> [u8; 32];
+    fn from_bytes(from: [u8; 32]) -> Self;
+    fn sentinel() -> Self;
+}
+
+#[derive(Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum TrieMerkleProofType<T> {
+    Node4((u8, ProofTrieNode<T>, [TrieHash; 3])),
+    Node16((u8, ProofTrieNode<T>, [TrieHash; 15])),
+    Node48((u8, ProofTrieNode<T>, [TrieHash; 47])),
+    Node256((u8, ProofTrieNode<T>, [TrieHash; 255])),
+    Leaf((u8, TrieLeaf)),
+    Shunt((i64, Vec<TrieHash>)),
+    /// The non-leaf node at which the walk of an absent path stops, with all of its children's
+    /// hashes.  This only appears as the first node of a proof of exclusion.
+    Terminal((ProofTrieNode<T>, Vec<TrieHash>)),
+}
+
+/// Merkle Proof Trie Pointers have a different structure
+///   than the runtime representation --- the proof includes
+///   the block header hash for back pointers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProofTrieNode<T> {
+    pub id: u8,
+    pub path: Vec<u8>,
+    pub ptrs: Vec<ProofTriePtr<T>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProofTriePtr<T> {
+    pub id: u8,
+    pub chr: u8,
+    pub back_block: T,
+}
+
+/// Leaf of a Trie.
+#[derive(Clone)]
+pub struct TrieLeaf {
+    pub path: Vec<u8>,   // path to be lazily expanded
+    pub data: MARFValue, // the actual data
+}
+
+pub const SENTINEL_ARRAY: [u8; 32] = [255u8; 32];
+
+macro_rules! impl_clarity_marf_trie_id {
+    ($thing:ident) => {
+        impl ClarityMarfTrieId for $thing {
+            fn as_bytes(&self) -> &[u8] {
+                self.as_ref()
+            }
+            fn to_bytes(self) -> [u8; 32] {
+                self.0
+            }
+            fn sentinel() -> Self {
+                Self(SENTINEL_ARRAY.clone())
+            }
+            fn from_bytes(bytes: [u8; 32]) -> Self {
+                Self(bytes)
+            }
+        }
+
+        impl From<MARFValue> for $thing {
+            fn from(m: MARFValue) -> Self {
+                let h = m.0;
+                let mut d = [0u8; 32];
+                for i in 0..32 {
+                    d[i] = h[i];
+                }
+                for i in 32..h.len() {
+                    if h[i] != 0 {
+                        panic!(
+                            "Failed to convert MARF value into BHH: data stored after 32nd byte"
+                        );
+                    }
+                }
+                Self(d)
+            }
+        }
+    };
+}
+
+impl_clarity_marf_trie_id!(BurnchainHeaderHash);
+impl_clarity_marf_trie_id!(StacksBlockId);
+impl_clarity_marf_trie_id!(SortitionId);
+#[cfg(any(test, feature = "testing"))]
+impl_clarity_marf_trie_id!(BlockHeaderHash);
+
+/// Structure that holds the actual data in a MARF leaf node.
+/// It only stores the hash of some value string, but we add 8 extra bytes for future extensions.
+/// If not used (the rule today), then they should all be 0.
+pub struct MARFValue(pub [u8; 40]);
+impl_array_newtype!(MARFValue, u8, 40);
+impl_array_hexstring_fmt!(MARFValue);
+impl_byte_array_newtype!(MARFValue, u8, 40);
+impl_byte_array_message_codec!(MARFValue, 40);
+pub const MARF_VALUE_ENCODED_SIZE: u32 = 40;
+
+impl From<u32> for MARFValue {
+    fn from(value: u32) -> MARFValue {
+        let h = value.to_le_bytes();
+        let mut d = [0u8; MARF_VALUE_ENCODED_SIZE as usize];
+        if h.len() > MARF_VALUE_ENCODED_SIZE as usize {
+            panic!("Cannot convert a u32 into a MARF Value.");
+        }
+        d[..h.len()].copy_from_slice(&h[..]);
+        MARFValue(d)
+    }
+}
+
+impl<T: ClarityMarfTrieId> From<T> for MARFValue {
+    fn from(bhh: T) -> MARFValue {
+        let h = bhh.to_bytes();
+        let mut d = [0u8; MARF_VALUE_ENCODED_SIZE as usize];
+        if h.len() > MARF_VALUE_ENCODED_SIZE as usize {
+            panic!("Cannot convert a BHH into a MARF Value.");
+        }
+        d[..h.len()].copy_from_slice(&h[..]);
+        MARFValue(d)
+    }
+}
+
+impl From<MARFValue> for u32 {
+    fn from(m: MARFValue) -> u32 {
+        let h = m.0;
+        let mut d = [0u8; 4];
+
+        d[..4].copy_from_slice(&h[..4]);
+        if h[4..].iter().any(|byte| *byte != 0) {
+            panic!("Failed to convert MARF value into u32: data stored after 4th byte");
+        }
+        u32::from_le_bytes(d)
+    }
+}
+
+impl MARFValue {
+    /// Construct from a TRIEHASH_ENCODED_SIZE-length slice
+    pub fn from_value_hash_bytes(h: &[u8; TRIEHASH_ENCODED_SIZE]) -> MARFValue {
+        let mut d = [0u8; MARF_VALUE_ENCODED_SIZE as usize];
+        d[..TRIEHASH_ENCODED_SIZE].copy_from_slice(&h[..TRIEHASH_ENCODED_SIZE]);
+        MARFValue(d)
+    }
+
+    /// Construct from a TrieHash
+    pub fn from_value_hash(h: &TrieHash) -> MARFValue {
+        MARFValue::from_value_hash_bytes(h.as_bytes())
+    }
+
+    /// Construct from a String that encodes a value inserted into the underlying data store
+    pub fn from_value(s: &str) -> MARFValue {
+        let mut tmp = [0u8; 32];
+
+        let mut hasher = TrieHasher::new();
+        hasher.update(s.as_bytes());
+        tmp.copy_from_slice(hasher.finalize().as_slice());
+
+        MARFValue::from_value_hash_bytes(&tmp)
+    }
+
+    /// Convert to a byte vector
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    /// Extract the value hash from the MARF value
+    pub fn to_value_hash(&self) -> TrieHash {
+        let mut h = [0u8; TRIEHASH_ENCODED_SIZE];
+        h.copy_from_slice(&self.0[0..TRIEHASH_ENCODED_SIZE]);
+        TrieHash(h)
+    }
+}
+
+// All numeric values of a Trie node when encoded.
+// They are all 7-bit numbers -- the 8th bit is used to indicate whether or not the value
+// identifies a back-pointer to be followed.
+define_u8_enum!(TrieNodeID {
+    Empty = 0,
+    Leaf = 1,
+    Node4 = 2,
+    Node16 = 3,
+    Node48 = 4,
+    Node256 = 5
+});
+
+impl PartialEq for TrieLeaf {
+    fn eq(&self, other: &TrieLeaf) -> bool {
+        self.path == other.path && self.data.as_bytes() == other.data.as_bytes()
+    }
+}
+
+impl TrieLeaf {
+    pub fn new(path: &[u8], data: &[u8]) -> TrieLeaf {
+        assert!(data.len() <= 40);
+        let mut bytes = [0u8; 40];
+        bytes.copy_from_slice(data);
+        TrieLeaf {
+            path: path.to_owned(),
+            data: MARFValue(bytes),
+        }
+    }
+
+    pub fn from_value(path: &[u8], value: MARFValue) -> TrieLeaf {
+        TrieLeaf {
+            path: path.to_owned(),
+            data: value,
+        }
+    }
+}
+
+impl fmt::Debug for TrieLeaf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "TrieLeaf(path={} data={})",
+            &to_hex(&self.path),
+            &self.data.to_hex()
+        )
+    }
+}
+
+impl StacksMessageCodec for TrieLeaf {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        self.path.consensus_serialize(fd)?;
+        self.data.consensus_serialize(fd)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<TrieLeaf, codec_error> {
+        let path = read_next(fd)?;
+        let data = read_next(fd)?;
+
+        Ok(TrieLeaf { path, data })
+    }
+}
+
+impl<T: ClarityMarfTrieId> ProofTrieNode<T> {
+    pub fn ptrs(&self) -> &[ProofTriePtr<T>] {
+        &self.ptrs
+    }
+}
+
+/// Calculate the hash of a proof node, given its childrens' hashes.  This is the same hash as the
+/// trie node it was made from.
+fn get_proof_node_hash<T: ClarityMarfTrieId>(
+    node: &ProofTrieNode<T>,
+    child_hashes: &[TrieHash],
+) -> TrieHash {
+    let mut hasher = TrieHasher::new();
+    hasher.update([node.id]);
+    for ptr in node.ptrs.iter() {
+        hasher.update([ptr.id, ptr.chr]);
+        hasher.update(ptr.back_block.as_bytes());
+    }
+    hasher.update([node.path.len() as u8]);
+    hasher.update(&node.path);
+    for child_hash in child_hashes {
+        hasher.update(child_hash.as_ref());
+    }
+
+    let mut res = [0u8; 32];
+    res.copy_from_slice(hasher.finalize().as_slice());
+
+    let ret = TrieHash(res);
+
+    trace!(
+        "get_proof_node_hash: hash {:?} = {:?} + {:?}",
+        &ret,
+        node,
+        child_hashes
+    );
+    ret
+}
+
+/// Calculate the hash of a TrieLeaf
+pub fn get_leaf_hash(node: &TrieLeaf) -> TrieHash {
+    let mut hasher = TrieHasher::new();
+    hasher.update([TrieNodeID::Leaf as u8]);
+    hasher.update([node.path.len() as u8]);
+    hasher.update(&node.path);
+    hasher.update(node.data.as_bytes());
+
+    let mut res = [0u8; 32];
+    res.copy_from_slice(hasher.finalize().as_slice());
+
+    let ret = TrieHash(res);
+
+    trace!("get_leaf_hash: hash {:?} = {:?} + []", &ret, node);
+    ret
+}
+
+define_u8_enum!( TrieMerkleProofTypeIndicator {
+    Node4 = 0, Node16 = 1, Node48 = 2, Node256 = 3, Leaf = 4, Shunt = 5, Terminal = 6
+});
+
+impl<T: ClarityMarfTrieId> PartialEq for TrieMerkleProofType<T> {
+    fn eq(&self, other: &TrieMerkleProofType<T>) -> bool {
+        match (self, other) {
+            (
+                TrieMerkleProofType::Node4((ref chr, ref node, ref hashes)),
+                TrieMerkleProofType::Node4((ref other_chr, ref other_node, ref other_hashes)),
+            ) => chr == other_chr && node == other_node && hashes == other_hashes,
+            (
+                TrieMerkleProofType::Node16((ref chr, ref node, ref hashes)),
+                TrieMerkleProofType::Node16((ref other_chr, ref other_node, ref other_hashes)),
+            ) => chr == other_chr && node == other_node && hashes == other_hashes,
+            (
+                TrieMerkleProofType::Node48((ref chr, ref node, ref hashes)),
+                TrieMerkleProofType::Node48((ref other_chr, ref other_node, ref other_hashes)),
+            ) => chr == other_chr && node == other_node && hashes == other_hashes,
+            (
+                TrieMerkleProofType::Node256((ref chr, ref node, ref hashes)),
+                TrieMerkleProofType::Node256((ref other_chr, ref other_node, ref other_hashes)),
+            ) => chr == other_chr && node == other_node && hashes == other_hashes,
+            (
+                TrieMerkleProofType::Leaf((ref chr, ref node)),
+                TrieMerkleProofType::Leaf((ref other_chr, ref other_node)),
+            ) => chr == other_chr && node == other_node,
+            (
+                TrieMerkleProofType::Shunt((ref idx_1, ref hashes_1)),
+                TrieMerkleProofType::Shunt((ref idx_2, ref hashes_2)),
+            ) => idx_1 == idx_2 && hashes_1 == hashes_2,
+            (
+                TrieMerkleProofType::Terminal((ref node, ref hashes)),
+                TrieMerkleProofType::Terminal((ref other_node, ref other_hashes)),
+            ) => node == other_node && hashes == other_hashes,
+            (_, _) => false,
+        }
+    }
+}
+
+pub fn hashes_fmt(hashes: &[TrieHash]) -> String {
+    let mut strs = vec![];
+    if hashes.len() < 48 {
+        for hash in hashes.iter() {
+            strs.push(format!("{:?}", hash));
+        }
+        strs.join(",")
+    } else {
+        for i in 0..hashes.len() / 4 {
+            strs.push(format!(
+                "{:?},{:?},{:?},{:?}",
+                hashes[4 * i],
+                hashes[4 * i + 1],
+                hashes[4 * i + 2],
+                hashes[4 * i + 3]
+            ));
+        }
+        format!("\n{}", strs.join("\n"))
+    }
+}
+
+impl<T: ClarityMarfTrieId> fmt::Debug for TrieMerkleProofType<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrieMerkleProofType::Node4((ref chr, ref node, ref hashes)) => write!(
+                f,
+                "TrieMerkleProofType::Node4(0x{:02x}, node={:?}, hashes={})",
+                chr,
+                node,
+                hashes_fmt(hashes)
+            ),
+            TrieMerkleProofType::Node16((ref chr, ref node, ref hashes)) => write!(
+                f,
+                "TrieMerkleProofType::Node16(0x{:02x}, node={:?}, hashes={})",
+                chr,
+                node,
+                hashes_fmt(hashes)
+            ),
+            TrieMerkleProofType::Node48((ref chr, ref node, ref hashes)) => write!(
+                f,
+                "TrieMerkleProofType::Node48(0x{:02x}, node={:?}, hashes={})",
+                chr,
+                node,
+                hashes_fmt(hashes)
+            ),
+            TrieMerkleProofType::Node256((ref chr, ref node, ref hashes)) => write!(
+                f,
+                "TrieMerkleProofType::Node256(0x{:02x}, node={:?}, hashes={})",
+                chr,
+                node,
+                hashes_fmt(hashes)
+            ),
+            TrieMerkleProofType::Leaf((ref chr, ref node)) => write!(
+                f,
+                "TrieMerkleProofType::Leaf(0x{:02x}, node={:?})",
+                chr, node
+            ),
+            TrieMerkleProofType::Shunt((ref idx, ref hashes)) => write!(
+                f,
+                "TrieMerkleProofType::Shunt(idx={}, hashes={:?})",
+                idx, hashes
+            ),
+            TrieMerkleProofType::Terminal((ref node, ref hashes)) => write!(
+                f,
+                "TrieMerkleProofType::Terminal(node={:?}, hashes={})",
+                node,
+                hashes_fmt(hashes)
+            ),
+        }
+    }
+}
+
+impl<T: ClarityMarfTrieId> Deref for TrieMerkleProof<T> {
+    type Target = Vec<TrieMerkleProofType<T>>;
+    fn deref(&self) -> &Vec<TrieMerkleProofType<T>> {
+        &self.0
+    }
+}
+
+fn serialize_id_hash_node<W: Write, T: ClarityMarfTrieId + StacksMessageCodec>(
+    fd: &mut W,
+    id: &u8,
+    node: &ProofTrieNode<T>,
+    hashes: &[TrieHash],
+) -> Result<(), codec_error> {
+    id.consensus_serialize(fd)?;
+    node.consensus_serialize(fd)?;
+    for hash in hashes.iter() {
+        hash.consensus_serialize(fd)?;
+    }
+    Ok(())
+}
+
+macro_rules! deserialize_id_hash_node {
+    ($fd:expr, $HashesArray:expr) => {{
+        let id = read_next($fd)?;
+        let node = read_next($fd)?;
+        let mut array = $HashesArray;
+        for i in 0..array.len() {
+            array[i] = read_next($fd)?;
+        }
+        (id, node, array)
+    }};
+}
+
+impl<T: ClarityMarfTrieId + StacksMessageCodec> StacksMessageCodec for ProofTriePtr<T> {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        self.id.consensus_serialize(fd)?;
+        self.chr.consensus_serialize(fd)?;
+        self.back_block.consensus_serialize(fd)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<ProofTriePtr<T>, codec_error> {
+        let id = read_next(fd)?;
+        let chr = read_next(fd)?;
+        let back_block = read_next(fd)?;
+
+        Ok(ProofTriePtr {
+            id,
+            chr,
+            back_block,
+        })
+    }
+}
+
+impl<T: ClarityMarfTrieId + StacksMessageCodec> StacksMessageCodec for ProofTrieNode<T> {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        self.id.consensus_serialize(fd)?;
+        self.path.consensus_serialize(fd)?;
+        self.ptrs.consensus_serialize(fd)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<ProofTrieNode<T>, codec_error> {
+        let id = read_next(fd)?;
+        let path = read_next(fd)?;
+        let ptrs = read_next(fd)?;
+
+        Ok(ProofTrieNode { id, path, ptrs })
+    }
+}
+
+impl<T: ClarityMarfTrieId + StacksMessageCodec> StacksMessageCodec for TrieMerkleProofType<T> {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        let type_byte = match self {
+            TrieMerkleProofType::Node4(_) => TrieMerkleProofTypeIndicator::Node4,
+            TrieMerkleProofType::Node16(_) => TrieMerkleProofTypeIndicator::Node16,
+            TrieMerkleProofType::Node48(_) => TrieMerkleProofTypeIndicator::Node48,
+            TrieMerkleProofType::Node256(_) => TrieMerkleProofTypeIndicator::Node256,
+            TrieMerkleProofType::Leaf(_) => TrieMerkleProofTypeIndicator::Leaf,
+            TrieMerkleProofType::Shunt(_) => TrieMerkleProofTypeIndicator::Shunt,
+            TrieMerkleProofType::Terminal(_) => TrieMerkleProofTypeIndicator::Terminal,
+        } as u8;
+
+        type_byte.consensus_serialize(fd)?;
+
+        match self {
+            TrieMerkleProofType::Node4((id, proof_node, hashes)) => {
+                serialize_id_hash_node(fd, id, proof_node, hashes)
+            }
+            TrieMerkleProofType::Node16((id, proof_node, hashes)) => {
+                serialize_id_hash_node(fd, id, proof_node, hashes)
+            }
+            TrieMerkleProofType::Node48((id, proof_node, hashes)) => {
+                serialize_id_hash_node(fd, id, proof_node, hashes)
+            }
+            TrieMerkleProofType::Node256((id, proof_node, hashes)) => {
+                serialize_id_hash_node(fd, id, proof_node, hashes)
+            }
+            TrieMerkleProofType::Leaf((id, leaf_node)) => {
+                id.consensus_serialize(fd)?;
+                leaf_node.consensus_serialize(fd)
+            }
+            TrieMerkleProofType::Shunt((id, hashes)) => {
+                id.consensus_serialize(fd)?;
+                hashes.consensus_serialize(fd)
+            }
+            TrieMerkleProofType::Terminal((proof_node, hashes)) => {
+                proof_node.consensus_serialize(fd)?;
+                hashes.consensus_serialize(fd)
+            }
+        }
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<TrieMerkleProofType<T>, codec_error> {
+        let type_byte = TrieMerkleProofTypeIndicator::from_u8(read_next(fd)?).ok_or_else(|| {
+            codec_error::DeserializeError("Bad type byte in Trie Merkle Proof".into())
+        })?;
+
+        let codec = match type_byte {
+            TrieMerkleProofTypeIndicator::Node4 => {
+                TrieMerkleProofType::Node4(deserialize_id_hash_node!(fd, [TrieHash([0; 32]); 3]))
+            }
+            TrieMerkleProofTypeIndicator::Node16 => {
+                TrieMerkleProofType::Node16(deserialize_id_hash_node!(fd, [TrieHash([0; 32]); 15]))
+            }
+            TrieMerkleProofTypeIndicator::Node48 => {
+                TrieMerkleProofType::Node48(deserialize_id_hash_node!(fd, [TrieHash([0; 32]); 47]))
+            }
+            TrieMerkleProofTypeIndicator::Node256 => TrieMerkleProofType::Node256(
+                deserialize_id_hash_node!(fd, [TrieHash([0; 32]); 255]),
+            ),
+            TrieMerkleProofTypeIndicator::Leaf => {
+                let id = read_next(fd)?;
+                let leaf_node = read_next(fd)?;
+                TrieMerkleProofType::Leaf((id, leaf_node))
+            }
+            TrieMerkleProofTypeIndicator::Shunt => {
+                let id = read_next(fd)?;
+                let hashes = read_next(fd)?;
+                TrieMerkleProofType::Shunt((id, hashes))
+            }
+            TrieMerkleProofTypeIndicator::Terminal => {
+                let proof_node = read_next(fd)?;
+                let hashes = read_next(fd)?;
+                TrieMerkleProofType::Terminal((proof_node, hashes))
+            }
+        };
+
+        Ok(codec)
+    }
+}
+
+impl<T: ClarityMarfTrieId + StacksMessageCodec> StacksMessageCodec for TrieMerkleProof<T> {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        self.0.consensus_serialize(fd)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<TrieMerkleProof<T>, codec_error> {
+        let proof_parts: Vec<TrieMerkleProofType<T>> = read_next(fd)?;
+        Ok(TrieMerkleProof(proof_parts))
+    }
+}
+
+impl StacksMessageCodec for TrieMerkleMultiProofEntry {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        self.path.consensus_serialize(fd)?;
+        match self.value.as_ref() {
+            Some(value) => {
+                1u8.consensus_serialize(fd)?;
+                value.consensus_serialize(fd)?;
+            }
+            None => {
+                0u8.consensus_serialize(fd)?;
+            }
+        }
+        self.nodes.consensus_serialize(fd)
+    }
+
+    fn consensus_deserialize<R: Read>(
+        fd: &mut R,
+    ) -> Result<TrieMerkleMultiProofEntry, codec_error> {
+        let path = read_next(fd)?;
+        let has_value: u8 = read_next(fd)?;
+        let value = match has_value {
+            0 => None,
+            1 => Some(read_next(fd)?),
+            _ => {
+                return Err(codec_error::DeserializeError(
+                    "Bad value indicator in Trie Merkle multi-proof entry".into(),
+                ));
+            }
+        };
+        let nodes = read_next(fd)?;
+
+        Ok(TrieMerkleMultiProofEntry { path, value, nodes })
+    }
+}
+
+impl<T: ClarityMarfTrieId + StacksMessageCodec> StacksMessageCodec for TrieMerkleMultiProof<T> {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        self.nodes.consensus_serialize(fd)?;
+        self.entries.consensus_serialize(fd)?;
+        let (root_hashes, blocks): (Vec<_>, Vec<_>) = self.ancestor_roots.iter().cloned().unzip();
+        root_hashes.consensus_serialize(fd)?;
+        blocks.consensus_serialize(fd)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<TrieMerkleMultiProof<T>, codec_error> {
+        let nodes = read_next(fd)?;
+        let entries = read_next(fd)?;
+        let root_hashes: Vec<TrieHash> = read_next(fd)?;
+        let blocks: Vec<T> = read_next(fd)?;
+        if root_hashes.len() != blocks.len() {
+            return Err(codec_error::DeserializeError(
+                "Mismatched ancestor root hashes and blocks in Trie Merkle multi-proof".into(),
+            ));
+        }
+
+        Ok(TrieMerkleMultiProof {
+            nodes,
+            entries,
+            ancestor_roots: root_hashes.into_iter().zip(blocks).collect(),
+        })
+    }
+}
+
+impl<T: ClarityMarfTrieId + StacksMessageCodec> TrieMerkleMultiProof<T> {
+    pub fn to_hex(&self) -> String {
+        let mut marf_proof = vec![];
+        self.consensus_serialize(&mut marf_proof)
+            .expect("Write error on memory buffer");
+        to_hex(&marf_proof)
+    }
+
+    /// Reassemble the `TrieMerkleProof` for the entry at `index`.
+    /// Returns None if there is no such entry, or if it refers to a nonexistent proof node.
+    pub fn get_proof(&self, index: usize) -> Option<TrieMerkleProof<T>> {
+        let entry = self.entries.get(index)?;
+        let proof = entry
+            .nodes
+            .iter()
+            .map(|i| self.nodes.get(usize::try_from(*i).ok()?).cloned())
+            .collect::<Option<Vec<_>>>()?;
+        Some(TrieMerkleProof(proof))
+    }
+
+    /// Verify every entry's proof of inclusion or exclusion against the trie root hash
+    /// `root_hash`.  The root-to-block map
+    /// for the ancestor tries comes from `ancestor_roots`; a bad mapping makes the proofs fail to
+    /// hash to `root_hash`.
+    pub fn verify(&self, root_hash: &TrieHash) -> bool {
+        if self.entries.is_empty() {
+            test_debug!("Invalid multi-proof -- no entries");
+            return false;
+        }
+        let root_to_block: HashMap<TrieHash, T> = self.ancestor_roots.iter().cloned().collect();
+        for (i, entry) in self.entries.iter().enumerate() {
+            let Some(proof) = self.get_proof(i) else {
+                test_debug!("Invalid multi-proof -- bad node index in entry {}", i);
+                return false;
+            };
+            let verified = match entry.value.as_ref() {
+                Some(value) => proof.verify(&entry.path, value, root_hash, &root_to_block),
+                None => proof.verify_exclusion(&entry.path, root_hash, &root_to_block),
+            };
+            if !verified {
+                test_debug!("Invalid multi-proof -- entry {} did not verify", i);
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Get the entry that proves `path`, if there is one
+    pub fn get_entry(&self, path: &TrieHash) -> Option<&TrieMerkleMultiProofEntry> {
+        self.entries.iter().find(|entry| entry.path == *path)
+    }
+}
+
+impl<T: ClarityMarfTrieId + StacksMessageCodec> TrieMerkleProof<T> {
+    pub fn to_hex(&self) -> String {
+        let mut marf_proof = vec![];
+        self.consensus_serialize(&mut marf_proof)
+            .expect("Write error on memory buffer");
+        to_hex(&marf_proof)
+    }
+
+    fn next_shunt_hash(hash: &TrieHash, idx: i64, hashes: &[TrieHash]) -> Option<TrieHash> {
+        let mut all_hashes = Vec::with_capacity(hashes.len() + 1);
+        let mut hash_idx = 0;
+        for i in 0..hashes.len() + 1 {
+            if idx == 0 {
+                trace!("Intermediate shunt proof entry must have idx > 0");
+                return None;
+            }
+
+            if idx - 1 == (i as i64) {
+                all_hashes.push(*hash);
+            } else {
+                if hash_idx >= hashes.len() {
+                    trace!(
+                        "Invalid proof: hash_idx = {}, hashes.len() = {}",
+                        hash_idx,
+                        hashes.len()
+                    );
+                    return None;
+                }
+                all_hashes.push(hashes[hash_idx]);
+                hash_idx += 1;
+            }
+        }
+        trace!("Shunt proof node: idx={}, all_hashes={:?}", idx, all_hashes);
+        let next_hash = TrieHash::from_data_array(&all_hashes);
+        Some(next_hash)
+    }
+
+    /// Verify the head of a shunt proof
+    fn verify_shunt_proof_head(
+        node_root_hash: &TrieHash,
+        shunt_proof_head: &TrieMerkleProofType<T>,
+    ) -> Option<TrieHash> {
+        // ancestor hashes are always the first item
+        let hash = match shunt_proof_head {
+            TrieMerkleProofType::Shunt((ref idx, ref hashes)) => {
+                if *idx != 0 {
+                    trace!("First shunt proof entry must have idx == 0");
+                    return None;
+                }
+
+                if hashes.is_empty() {
+                    // special case -- if this shunt proof has no hashes (i.e. this is a leaf from the first
+                    // block), then we can safely skip this step
+                    trace!(
+                        "Special case for a 0-ancestor node: hash is just the trie hash: {:?}",
+                        node_root_hash
+                    );
+                    *node_root_hash
+                } else {
+                    let mut all_hashes = Vec::with_capacity(hashes.len() + 1);
+                    all_hashes.push(*node_root_hash);
+                    for h in hashes {
+                        all_hashes.push(*h);
+                    }
+                    let ret = TrieHash::from_data_array(&all_hashes);
+                    trace!(
+                        "Shunt proof head: hash = {:?}, all_hashes = {:?}",
+                        &ret,
+                        &all_hashes
+                    );
+                    ret
+                }
+            }
+            _ => {
+                trace!("Shunt proof head is not a shunt proof node");
+                return None;
+            }
+        };
+
+        Some(hash)
+    }
+
+    /// Verify the tail of a shunt proof, given the backptr root hash.
+    /// Calculate the root hash of the next segment proof.
+    fn verify_shunt_proof_tail(
+        initial_hash: &TrieHash,
+        shunt_proof: &[TrieMerkleProofType<T>],
+    ) -> Option<TrieHash> {
+        let mut hash = *initial_hash;
+
+        // walk subsequent legs of a shunt proof, except for the last (since we need the next
+        // segment proof for that)
+        for proof_node in shunt_proof.iter() {
+            hash = match proof_node {
+                TrieMerkleProofType::Shunt((ref idx, ref hashes)) => {
+                    if *idx == 0 {
+                        trace!("Invalid shunt proof tail: idx == 0");
+                        return None;
+                    }
+
+                    match TrieMerkleProof::<T>::next_shunt_hash(&hash, *idx, hashes) {
+                        Some(h) => h,
+                        None => {
+                            return None;
+                        }
+                    }
+                }
+                _ => {
+                    trace!("Shunt proof item is not a shunt proof node");
+                    return None;
+                }
+            };
+        }
+        Some(hash)
+    }
+
+    /// Verify a shunt juncture, where a shunt proof tail and a segment proof meet.
+    /// Returns the hash of the root of the junction
+    fn verify_shunt_proof_junction(
+        node_root_hash: &TrieHash,
+        penultimate_trie_hash: &TrieHash,
+        shunt_proof_junction: &TrieMerkleProofType<T>,
+    ) -> Option<TrieHash> {
+        // at the juncture, we include the node root hash (from the subsequent segment proof) as
+        // the first hash, and include the penultimate trie hash in its idx
+        let hash = match shunt_proof_junction {
+            TrieMerkleProofType::Shunt((ref idx, ref hashes)) => {
+                if *idx == 0 {
+                    trace!("Shunt proof junction entry must not have idx == 0");
+                    return None;
+                }
+
+                let mut all_hashes = Vec::with_capacity(hashes.len() + 1);
+                let mut hash_idx = 0;
+
+                all_hashes.push(*node_root_hash);
+
+                for i in 0..hashes.len() + 1 {
+                    if *idx - 1 == (i as i64) {
+                        all_hashes.push(*penultimate_trie_hash);
+                    } else {
+                        if hash_idx >= hashes.len() {
+                            trace!(
+                                "ran out of hashes: hash_idx = {}, hashes.len() = {}",
+                                hash_idx,
+                                hashes.len()
+                            );
+                            return None;
+                        }
+
+                        all_hashes.push(hashes[hash_idx]);
+                        hash_idx += 1;
+                    }
+                }
+
+                trace!(
+                    "idx = {}, hashes = {:?}, penultimate = {:?}, node root = {:?}",
+                    *idx,
+                    hashes,
+                    penultimate_trie_hash,
+                    node_root_hash
+                );
+                trace!("Shunt proof junction: all_hashes = {:?}", &all_hashes);
+                TrieHash::from_data_array(&all_hashes)
+            }
+            _ => {
+                trace!("Shunt proof junction is not a shunt proof node");
+                return None;
+            }
+        };
+
+        Some(hash)
+    }
+
+    /// Given a node in a segment proof, find the hash
+    fn get_segment_proof_hash(
+        node: &ProofTrieNode<T>,
+        hash: &TrieHash,
+        chr: u8,
+        hashes: &[TrieHash],
+        count: usize,
+    ) -> Option<TrieHash> {
+        let mut all_hashes = vec![];
+        let mut ih = 0;
+
+        assert!(node.ptrs().len() == count);
+        assert!(count > 0 && hashes.len() == count - 1);
+
+        for child_ptr in node.ptrs() {
+            if child_ptr.id != TrieNodeID::Empty as u8 && child_ptr.chr == chr {
+                all_hashes.push(*hash);
+            } else if ih >= hashes.len() {
+                trace!("verify_get_hash: {} >= {}", ih, hashes.len());
+                return None;
+            } else {
+                all_hashes.push(hashes[ih]);
+                ih += 1;
+            }
+        }
+        if all_hashes.len() != count {
+            trace!("verify_get_hash: {} != {}", all_hashes.len(), count);
+            return None;
+        }
+
+        Some(get_proof_node_hash(node, &all_hashes))
+    }
+
+    /// Given the terminal node of a proof of exclusion, find its hash.  Unlike the other nodes in
+    /// a segment proof, it includes all of its childrens' hashes.
+    fn get_terminal_node_hash(node: &ProofTrieNode<T>, hashes: &[TrieHash]) -> Option<TrieHash> {
+        if node.ptrs().len() != hashes.len() {
+            trace!(
+                "Terminal node has {} children but {} hashes",
+                node.ptrs().len(),
+                hashes.len()
+            );
+            return None;
+        }
+        Some(get_proof_node_hash(node, hashes))
+    }
+
+    /// Given a segment proof, the deepest node's hash, and the hash of the trie root, verify that
+    /// the segment proof is well-formed.
+    /// If so, calculate the root hash of the segment and return it.
+    fn verify_segment_proof(
+        proof: &[TrieMerkleProofType<T>],
+        node_hash: &TrieHash,
+    ) -> Option<TrieHash> {
+        let mut hash = *node_hash;
+        for proof_node in proof.iter() {
+            let hash_opt = match *proof_node {
+                TrieMerkleProofType::Leaf((ref _chr, ref node)) => {
+                    // special case the leaf hash -- it doesn't
+                    //   have any child hashes to check.
+                    Some(get_leaf_hash(node))
+                }
+                TrieMerkleProofType::Terminal((ref node, ref hashes)) => {
+                    TrieMerkleProof::get_terminal_node_hash(node, hashes)
+                }
+                TrieMerkleProofType::Node4((ref chr, ref node, ref hashes)) => {
+                    TrieMerkleProof::get_segment_proof_hash(node, &hash, *chr, hashes, 4)
+                }
+                TrieMerkleProofType::Node16((ref chr, ref node, ref hashes)) => {
+                    TrieMerkleProof::get_segment_proof_hash(node, &hash, *chr, hashes, 16)
+                }
+                TrieMerkleProofType::Node48((ref chr, ref node, ref hashes)) => {
+                    TrieMerkleProof::get_segment_proof_hash(node, &hash, *chr, hashes, 48)
+                }
+                TrieMerkleProofType::Node256((ref chr, ref node, ref hashes)) => {
+                    TrieMerkleProof::get_segment_proof_hash(node, &hash, *chr, hashes, 256)
+                }
+                _ => {
+                    trace!("Invalid proof -- encountered a non-node proof type");
+                    return None;
+                }
+            };
+            hash = match hash_opt {
+                None => {
+                    return None;
+                }
+                Some(h) => h,
+            };
+        }
+
+        trace!("verify segment: calculated root hash = {:?}", hash);
+        Some(hash)
+    }
+
+    /// Given a segment proof, extract the path prefix it encodes
+    fn get_segment_proof_path_prefix(segment_proof: &[TrieMerkleProofType<T>]) -> Option<Vec<u8>> {
+        let mut path_parts = vec![];
+        for proof_node in segment_proof {
+            match proof_node {
+                TrieMerkleProofType::Leaf((ref _chr, ref node)) => {
+                    // path_parts.push(vec![*chr]);
+                    path_parts.push(node.path.clone());
+                }
+                TrieMerkleProofType::Node4((ref chr, ref node, _)) => {
+                    path_parts.push(vec![*chr]);
+                    path_parts.push(node.path.clone());
+                }
+                TrieMerkleProofType::Node16((ref chr, ref node, _)) => {
+                    path_parts.push(vec![*chr]);
+                    path_parts.push(node.path.clone());
+                }
+                TrieMerkleProofType::Node48((ref chr, ref node, _)) => {
+                    path_parts.push(vec![*chr]);
+                    path_parts.push(node.path.clone());
+                }
+                TrieMerkleProofType::Node256((ref chr, ref node, _)) => {
+                    path_parts.push(vec![*chr]);
+                    path_parts.push(node.path.clone());
+                }
+                _ => {
+                    trace!("Not a valid segment proof: got a non-node proof node");
+                    return None;
+                }
+            }
+        }
+
+        let mut path = vec![];
+        for i in 0..path_parts.len() {
+            let idx = path_parts.len() - 1 - i;
+            path.extend_from_slice(&path_parts[idx]);
+        }
+        Some(path)
+    }
+
+    /// Verify that a proof is well-formed:
+    /// * it must have the same number of segment and shunt proofs
+    /// * segment proof 0 must start with a leaf or a terminal node, and the path to that node
+    ///   must be a prefix of the expected path
+    /// * every other segment proof must encode a prefix of the expected path
+    /// * all segment proofs must end in a Node256 (a root)
+    fn is_proof_well_formed(proof: &[TrieMerkleProofType<T>], expected_path: &TrieHash) -> bool {
+        if proof.is_empty() {
+            trace!("Proof is empty");
+            return false;
+        }
+
+        match proof[0] {
+            TrieMerkleProofType::Leaf(_) | TrieMerkleProofType::Terminal(_) => {}
+            _ => {
+                trace!("First proof node is not a leaf or a terminal node");
+                return false;
+            }
+        }
+
+        // must be alternating segment and shunt proofs
+        let mut i = 0;
+
+        while i < proof.len() {
+            // next segment proof
+            let mut j = i + 1;
+            while j < proof.len() {
+                match proof[j] {
+                    TrieMerkleProofType::Shunt(_) => {
+                        break;
+                    }
+                    _ => {
+                        j += 1;
+                    }
+                }
+            }
+
+            // the first segment proof's leaf or terminal node is checked against the rest of the
+            // path when the proof is verified
+            let segment_proof = if i == 0 { &proof[1..j] } else { &proof[i..j] };
+
+            let path_bytes = match TrieMerkleProof::get_segment_proof_path_prefix(segment_proof) {
+                Some(bytes) => bytes,
+                None => {
+                    trace!("Failed to get the path prefix from the proof");
+                    return false;
+                }
+            };
+
+            if !expected_path.as_bytes().starts_with(&path_bytes) {
+                trace!(
+                    "Segment path {:?} is not a prefix of the expected path {:?}",
+                    &path_bytes,
+                    expected_path
+                );
+                return false;
+            }
+
+            // next shunt proof
+            i = j;
+            if i >= proof.len() {
+                trace!("Proof is incomplete -- must end with a shunt proof");
+                return false;
+            }
+
+            j = i + 1;
+            while j < proof.len() {
+                match proof[j] {
+                    TrieMerkleProofType::Shunt(_) => {
+                        j += 1;
+                    }
+                    _ => {
+                        break;
+                    }
+                }
+            }
+
+            // end of shunt proof
+            i = j;
+        }
+
+        true
+    }
+
+    /// Check the leaf or terminal node at the start of a well-formed proof against the part of
+    /// the path that the rest of the first segment proof does not encode.
+    /// If `value` is given, the node must be the leaf for the path, with that value.  Otherwise,
+    /// the node must show that the path is absent: either it is a leaf for a different path, or
+    /// the path diverges from its compressed path, or it has no child for the path's next byte.
+    fn is_terminal_for_path(
+        proof: &[TrieMerkleProofType<T>],
+        path: &TrieHash,
+        value: Option<&MARFValue>,
+    ) -> bool {
+        let mut j = 1;
+        while j < proof.len() {
+            match proof[j] {
+                TrieMerkleProofType::Shunt(_) => {
+                    break;
+                }
+                _ => {
+                    j += 1;
+                }
+            }
+        }
+        let Some(path_prefix) = TrieMerkleProof::get_segment_proof_path_prefix(&proof[1..j]) else {
+            return false;
+        };
+        let Some(rest) = path.as_bytes().get(path_prefix.len()..) else {
+            return false;
+        };
+
+        match (&proof[0], value) {
+            (TrieMerkleProofType::Leaf((_, ref node)), Some(value)) => {
+                if node.path != rest {
+                    trace!("Invalid proof -- leaf is for a different path");
+                    return false;
+                }
+                if node.data != *value {
+                    test_debug!(
+                        "Invalid proof -- not for value hash {:?}",
+                        value.to_value_hash()
+                    );
+                    return false;
+                }
+                true
+            }
+            (TrieMerkleProofType::Leaf((_, ref node)), None) => {
+                if node.path == rest {
+                    test_debug!("Invalid proof of exclusion -- found a leaf for the path");
+                    return false;
+                }
+                true
+            }
+            (TrieMerkleProofType::Terminal((ref node, _)), None) => {
+                if !rest.starts_with(&node.path) {
+                    // path diverges from this node
+                    return true;
+                }
+                let Some(chr) = rest.get(node.path.len()) else {
+                    trace!("Invalid proof of exclusion -- path ends at a non-leaf node");
+                    return false;
+                };
+                if node
+                    .ptrs()
+                    .iter()
+                    .any(|ptr| ptr.id != TrieNodeID::Empty as u8 && ptr.chr == *chr)
+                {
+                    test_debug!(
+                        "Invalid proof of exclusion -- terminal node has a child for 0x{:02x}",
+                        chr
+                    );
+                    return false;
+                }
+                true
+            }
+            _ => {
+                trace!("Invalid proof -- first proof node does not match the expected value");
+                false
+            }
+        }
+    }
+
+    /// Given a value and the root hash from which this proof was
+    /// (supposedly) generated go and verify whether or not it is consistent with the root hash.
+    /// For the proof validation to work, the verifier needs to know which Trie roots correspond to
+    /// which block headers.  This can be calculated and verified independently from the blockchain
+    /// headers.
+    /// NOTE: Trie root hashes are globally unique by design, even if they represent the same contents, so the root_to_block map is bijective with high probability.
+    pub fn verify_proof(
+        proof: &[TrieMerkleProofType<T>],
+        path: &TrieHash,
+        value: &MARFValue,
+        root_hash: &TrieHash,
+        root_to_block: &HashMap<TrieHash, T>,
+    ) -> bool {
+        TrieMerkleProof::verify_proof_for_value(proof, path, Some(value), root_hash, root_to_block)
+    }
+
+    /// Given the root hash from which this proof was (supposedly) generated, verify that it shows
+    /// `path` has no value.  As with `verify_proof()`, the verifier needs to know which Trie roots
+    /// correspond to which block headers.
+    pub fn verify_exclusion_proof(
+        proof: &[TrieMerkleProofType<T>],
+        path: &TrieHash,
+        root_hash: &TrieHash,
+        root_to_block: &HashMap<TrieHash, T>,
+    ) -> bool {
+        TrieMerkleProof::verify_proof_for_value(proof, path, None, root_hash, root_to_block)
+    }
+
+    /// Verify a proof of inclusion of `value` at `path`, or a proof of exclusion of `path` if
+    /// `value` is None.
+    fn verify_proof_for_value(
+        proof: &[TrieMerkleProofType<T>],
+        path: &TrieHash,
+        value: Option<&MARFValue>,
+        root_hash: &TrieHash,
+        root_to_block: &HashMap<TrieHash, T>,
+    ) -> bool {
+        if !TrieMerkleProof::is_proof_well_formed(proof, path) {
+            test_debug!("Invalid proof -- proof is not well-formed");
+            return false;
+        }
+
+        if !TrieMerkleProof::is_terminal_for_path(proof, path, value) {
+            test_debug!("Invalid proof -- first proof node does not prove the path");
+            return false;
+        }
+
+        let mut node_hash = match proof[0] {
+            TrieMerkleProofType::Leaf((_, ref node)) => get_leaf_hash(node),
+            TrieMerkleProofType::Terminal((ref node, ref hashes)) => {
+                match TrieMerkleProof::get_terminal_node_hash(node, hashes) {
+                    Some(h) => h,
+                    None => {
+                        return false;
+                    }
+                }
+            }
+            _ => unreachable!(),
+        };
+
+        let mut i = 0;
+
+        // verify the very first segment proof
+        let mut j = i + 1;
+        while j < proof.len() {
+            match proof[j] {
+                TrieMerkleProofType::Shunt(_) => {
+                    break;
+                }
+                _ => {
+                    j += 1;
+                }
+            }
+        }
+
+        trace!("verify segment proof in range {}..{}", i, j);
+        let node_root_hash = match TrieMerkleProof::verify_segment_proof(&proof[i..j], &node_hash) {
+            Some(h) => h,
+            None => {
+                test_debug!("Unable to verify segment proof in range {}...{}", i, j);
+                return false;
+            }
+        };
+
+        i = j;
+        if i >= proof.len() {
+            test_debug!(
+                "Proof is too short -- needed at least one shunt proof for the first segment"
+            );
+            return false;
+        }
+
+        // verify the very first shunt proof head.
+        trace!("verify shunt proof head at {}: {:?}", i, &proof[i]);
+        let mut trie_hash =
+            match TrieMerkleProof::verify_shunt_proof_head(&node_root_hash, &proof[i]) {
+                Some(h) => h,
+                None => {
+                    test_debug!(
+                        "Unable to verify shunt proof head at {}: {:?}",
+                        i,
+                        &proof[i]
+                    );
+                    return false;
+                }
+            };
+        trace!("shunt proof head hash: {:?}", &trie_hash);
+
+        i += 1;
+        if i >= proof.len() {
+            // done -- no further shunts
+            test_debug!("Verify proof: {:?} =?= {:?}", root_hash, &trie_hash);
+            return *root_hash == trie_hash;
+        }
+
+        // next node hash is the hash of the block from which its root came
+        node_hash = match root_to_block.get(&trie_hash) {
+            Some(bhh) => {
+                trace!("Block hash for {:?} is {:?}", &trie_hash, bhh);
+
+                // safe because block header hashes are 32 bytes long
+                TrieHash(bhh.clone().to_bytes())
+            }
+            None => {
+                test_debug!("Trie hash not found in root-to-block map: {:?}", &trie_hash);
+                trace!("root-to-block map: {:?}", &root_to_block);
+                return false;
+            }
+        };
+
+        // next proof item should be part of a segment proof
+        if let TrieMerkleProofType::Shunt(_) = proof[i] {
+            test_debug!(
+                "Malformed proof -- exepcted segment proof following first shunt proof head at {}",
+                i
+            );
+            return false;
+        }
+
+        while i < proof.len() {
+            // find the next segment proof
+            j = i + 1;
+            while j < proof.len() {
+                match proof[j] {
+                    TrieMerkleProofType::Shunt(_) => {
+                        break;
+                    }
+                    _ => {
+                        j += 1;
+                    }
+                }
+            }
+
+            trace!("verify segment proof in range {}..{}", i, j);
+            let next_node_root_hash =
+                match TrieMerkleProof::verify_segment_proof(&proof[i..j], &node_hash) {
+                    Some(h) => h,
+                    None => {
+                        test_debug!("Unable to verify segment proof in range {}..{}", i, j);
+                        return false;
+                    }
+                };
+
+            i = j;
+            if i >= proof.len() {
+                test_debug!("Proof to short -- no shunt proof tail");
+                return false;
+            }
+
+            // find the tail end
+            j = i;
+            while j < proof.len() {
+                match proof[j] {
+                    TrieMerkleProofType::Shunt((ref idx, _)) => {
+                        if *idx == 0 {
+                            break;
+                        }
+                        j += 1;
+                    }
+                    _ => {
+                        break;
+                    }
+                }
+            }
+            j -= 1;
+
+            if j < i {
+                test_debug!("Proof is malformed -- no tail or junction proof");
+                return false;
+            }
+
+            trace!(
+                "verify shunt proof tail in range {}..{} initial hash = {:?}: {:?}",
+                i,
+                j,
+                &trie_hash,
+                &proof[i..j]
+            );
+            let penultimate_trie_hash =
+                match TrieMerkleProof::verify_shunt_proof_tail(&trie_hash, &proof[i..j]) {
+                    Some(h) => h,
+                    None => {
+                        test_debug!("Unable to verify shunt proof tail");
+                        return false;
+                    }
+                };
+            trace!(
+                "verify shunt proof tail in range {}..{}: penultimate trie hash is {:?}",
+                i,
+                j,
+                &penultimate_trie_hash
+            );
+
+            i = j;
+            if i >= proof.len() {
+                test_debug!("Proof to short -- no junction proof");
+                return false;
+            }
+
+            trace!("verify shunt junction proof at {} next_node_root_hash = {:?} penultimate hash = {:?}: {:?}", i, &next_node_root_hash, &penultimate_trie_hash, &proof[i]);
+            let next_trie_hash = match TrieMerkleProof::verify_shunt_proof_junction(
+                &next_node_root_hash,
+                &penultimate_trie_hash,
+                &proof[i],
+            ) {
+                Some(h) => h,
+                None => {
+                    test_debug!("Unable to verify shunt junction proof at {} next_node_root_hash = {:?} penultimate hash = {:?}: {:?}", i, &next_node_root_hash, &penultimate_trie_hash, &proof[i]);
+                    return false;
+                }
+            };
+
+            // next node hash is the hash of the block from which its root came
+            trie_hash = next_trie_hash;
+            node_hash = match root_to_block.get(&trie_hash) {
+                Some(bhh) => {
+                    trace!("Block hash for {:?} is {:?}", &trie_hash, bhh);
+
+                    // safe because block header hashes are 32 bytes long
+                    TrieHash(bhh.clone().to_bytes())
+                }
+                None => {
+                    test_debug!("Trie hash not found in root-to-block map: {:?}", &trie_hash);
+                    test_debug!("root-to-block map: {:?}", &root_to_block);
+                    return false;
+                }
+            };
+
+            i += 1;
+
+            if trie_hash == *root_hash {
+                trace!(
+                    "Appeared to find the root hash early, with the remaining proof:\n{:?}",
+                    &proof[i..]
+                );
+                break;
+            }
+        }
+
+        test_debug!("Verify proof: {:?} =?= {:?}", root_hash, &trie_hash);
+        *root_hash == trie_hash
+    }
+
+    /// Verify this proof
+    pub fn verify(
+        &self,
+        path: &TrieHash,
+        marf_value: &MARFValue,
+        root_hash: &TrieHash,
+        root_to_block: &HashMap<TrieHash, T>,
+    ) -> bool {
+        TrieMerkleProof::<T>::verify_proof(&self.0, path, marf_value, root_hash, root_to_block)
+    }
+
+    /// Verify this proof of exclusion
+    pub fn verify_exclusion(
+        &self,
+        path: &TrieHash,
+        root_hash: &TrieHash,
+        root_to_block: &HashMap<TrieHash, T>,
+    ) -> bool {
+        TrieMerkleProof::<T>::verify_exclusion_proof(&self.0, path, root_hash, root_to_block)
+    }
+}
@@ -34,6 +34,7 @@ use crate::util::hash::Hash160;
 use crate::util::secp256k1::{MessageSignature, Secp256k1PublicKey};
 
 pub mod chainstate;
+pub mod marf;
 pub mod net;
 
 #[cfg(test)]
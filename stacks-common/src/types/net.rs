@@ -21,6 +21,7 @@ use std::str::FromStr;
 
 use serde::de::{Deserialize, Error as de_Error};
 use serde::ser::Serialize;
+use sha3::{Digest, Sha3_256};
 
 use crate::util::hash::to_bin;
 
@@ -214,11 +215,41 @@ impl PeerAddress {
                 || (self.0[12] == 192 && self.0[13] == 168)
                 || self.0[12] == 127
         } else {
-            // private address (fc00::/7) or localhost (::1)
-            self.0[0] >= 0xfc || (self.0[0..15] == [0u8; 15] && self.0[15] == 1)
+            // private address (fc00::/7) or localhost (::1).  Onion addresses and proxied
+            // hostnames are synthesized from fd87:d87e:eb43::/47, but they are routable through
+            // our SOCKS5 proxy.
+            (self.0[0] >= 0xfc && !self.is_onion() && !self.is_proxied_hostname())
+                || (self.0[0..15] == [0u8; 15] && self.0[15] == 1)
         }
     }
 
+    /// Is this a synthetic address which stands in for a Tor onion service?
+    /// See `OnionAddress::to_peer_address()`.
+    pub fn is_onion(&self) -> bool {
+        self.0[0..6] == ONION_PEER_ADDRESS_PREFIX
+    }
+
+    /// Get the synthetic address which stands in for a hostname that only our SOCKS5 proxy
+    /// resolves.  The address is derived from the (case-insensitive) hostname, so it's the same
+    /// every time the node starts.
+    pub fn from_proxied_hostname(hostname: &str) -> PeerAddress {
+        let mut hasher = Sha3_256::new();
+        hasher.update(b"proxied hostname");
+        hasher.update(hostname.to_ascii_lowercase().as_bytes());
+        let digest = hasher.finalize();
+
+        let mut addrbytes = [0u8; 16];
+        addrbytes[0..6].copy_from_slice(&PROXIED_HOSTNAME_PEER_ADDRESS_PREFIX);
+        addrbytes[6..16].copy_from_slice(&digest[0..10]);
+        PeerAddress(addrbytes)
+    }
+
+    /// Is this a synthetic address which stands in for a hostname that our SOCKS5 proxy
+    /// resolves?  See `PeerAddress::from_proxied_hostname()`.
+    pub fn is_proxied_hostname(&self) -> bool {
+        self.0[0..6] == PROXIED_HOSTNAME_PEER_ADDRESS_PREFIX
+    }
+
     /// Is this a local loopback address?
     pub fn is_loopback(&self) -> bool {
         self.to_socketaddr(0).ip().is_loopback()
@@ -233,6 +264,164 @@ impl PeerAddress {
     }
 }
 
+/// The /48 prefix (fd87:d87e:eb43::/48) used to map onion services into the `PeerAddress` space.
+/// This is the same prefix OnionCat and Bitcoin use for this purpose.
+pub const ONION_PEER_ADDRESS_PREFIX: [u8; 6] = [0xfd, 0x87, 0xd8, 0x7e, 0xeb, 0x43];
+
+/// The /48 prefix (fd87:d87e:eb42::/48) used to map hostnames into the `PeerAddress` space when
+/// our SOCKS5 proxy resolves them, so that we never resolve them ourselves.
+pub const PROXIED_HOSTNAME_PEER_ADDRESS_PREFIX: [u8; 6] = [0xfd, 0x87, 0xd8, 0x7e, 0xeb, 0x42];
+
+/// Tor v3 onion service version byte
+const ONION_V3_VERSION: u8 = 0x03;
+
+/// RFC 4648 base32 alphabet, in the lowercase form Tor uses for hostnames
+const ONION_BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// A Tor v3 onion service address, represented by the service's ed25519 public key.
+/// The `.onion` hostname is derived from the key (see rend-spec-v3, section 6).
+///
+/// An onion address does not fit into a `PeerAddress`, so the p2p network refers to it by a
+/// synthetic `PeerAddress` in fd87:d87e:eb43::/48 which carries the first 10 bytes of the key.
+/// The node must learn the full onion address separately in order to connect to it.
+pub struct OnionAddress(pub [u8; 32]);
+impl_array_newtype!(OnionAddress, u8, 32);
+impl_byte_array_message_codec!(OnionAddress, 32);
+
+impl OnionAddress {
+    /// Instantiates from the service's public key bytes
+    pub fn from_bytes(inp: &[u8]) -> Option<OnionAddress> {
+        let pubkey: [u8; 32] = inp.try_into().ok()?;
+        Some(OnionAddress(pubkey))
+    }
+
+    /// Compute the 2-byte checksum embedded in the hostname
+    fn checksum(pubkey: &[u8; 32]) -> [u8; 2] {
+        let mut hasher = Sha3_256::new();
+        hasher.update(b".onion checksum");
+        hasher.update(pubkey);
+        hasher.update([ONION_V3_VERSION]);
+        let digest = hasher.finalize();
+        [digest[0], digest[1]]
+    }
+
+    /// Get the `.onion` hostname for this service
+    pub fn hostname(&self) -> String {
+        let mut bytes = Vec::with_capacity(35);
+        bytes.extend_from_slice(&self.0);
+        bytes.extend_from_slice(&OnionAddress::checksum(&self.0));
+        bytes.push(ONION_V3_VERSION);
+
+        // 35 bytes is exactly 56 base32 characters, so there's no padding
+        let mut encoded = String::with_capacity(62);
+        let mut buffer: u16 = 0;
+        let mut bits = 0;
+        for byte in bytes.into_iter() {
+            buffer = (buffer << 8) | u16::from(byte);
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                let index = usize::from((buffer >> bits) & 0x1f);
+                encoded.push(char::from(ONION_BASE32_ALPHABET[index]));
+            }
+        }
+        encoded.push_str(".onion");
+        encoded
+    }
+
+    /// Get the synthetic peer address for this service
+    pub fn to_peer_address(&self) -> PeerAddress {
+        let mut addrbytes = [0u8; 16];
+        addrbytes[0..6].copy_from_slice(&ONION_PEER_ADDRESS_PREFIX);
+        addrbytes[6..16].copy_from_slice(&self.0[0..10]);
+        PeerAddress(addrbytes)
+    }
+
+    /// Is the given hostname an onion service?
+    pub fn is_onion_hostname(host: &str) -> bool {
+        host.to_ascii_lowercase().ends_with(".onion")
+    }
+}
+
+impl FromStr for OnionAddress {
+    type Err = Error;
+
+    /// Parse a v3 `.onion` hostname, and verify its checksum and version
+    fn from_str(host: &str) -> Result<OnionAddress, Error> {
+        let host = host.to_ascii_lowercase();
+        let Some(encoded) = host.strip_suffix(".onion") else {
+            return Err(Error::DecodeError(
+                "Failed to parse onion address: no .onion suffix".to_string(),
+            ));
+        };
+        if encoded.len() != 56 {
+            return Err(Error::DecodeError(format!(
+                "Failed to parse onion address: expected 56 characters, got {}",
+                encoded.len()
+            )));
+        }
+
+        let mut bytes = Vec::with_capacity(35);
+        let mut buffer: u16 = 0;
+        let mut bits = 0;
+        for ch in encoded.bytes() {
+            let Some(value) = ONION_BASE32_ALPHABET.iter().position(|c| *c == ch) else {
+                return Err(Error::DecodeError(format!(
+                    "Failed to parse onion address: invalid character '{}'",
+                    char::from(ch)
+                )));
+            };
+            buffer = (buffer << 5) | (value as u16);
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push(((buffer >> bits) & 0xff) as u8);
+            }
+        }
+
+        if bytes[34] != ONION_V3_VERSION {
+            return Err(Error::DecodeError(format!(
+                "Failed to parse onion address: unsupported version {}",
+                bytes[34]
+            )));
+        }
+
+        let mut pubkey = [0u8; 32];
+        pubkey.copy_from_slice(&bytes[0..32]);
+        if bytes[32..34] != OnionAddress::checksum(&pubkey) {
+            return Err(Error::DecodeError(
+                "Failed to parse onion address: bad checksum".to_string(),
+            ));
+        }
+        Ok(OnionAddress(pubkey))
+    }
+}
+
+impl fmt::Display for OnionAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.hostname())
+    }
+}
+
+impl fmt::Debug for OnionAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OnionAddress({})", self.hostname())
+    }
+}
+
+impl Serialize for OnionAddress {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.hostname())
+    }
+}
+
+impl<'de> Deserialize<'de> for OnionAddress {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<OnionAddress, D::Error> {
+        let inst = String::deserialize(d)?;
+        OnionAddress::from_str(&inst).map_err(de_Error::custom)
+    }
+}
+
 /// Peer address variants for the Host: header
 #[derive(Clone, PartialEq)]
 pub enum PeerHost {
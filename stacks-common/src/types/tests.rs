@@ -14,10 +14,14 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::str::FromStr;
+
+use super::net::{OnionAddress, PeerAddress};
 use super::{
     set_test_coinbase_schedule, CoinbaseInterval, StacksEpochId, COINBASE_INTERVALS_MAINNET,
     COINBASE_INTERVALS_TESTNET,
 };
+use crate::util::hash::hex_bytes;
 
 #[test]
 fn test_mainnet_coinbase_emissions() {
@@ -350,3 +354,75 @@ fn test_set_coinbase_intervals() {
         *COINBASE_INTERVALS_TESTNET
     );
 }
+
+#[test]
+fn test_onion_address_codec() {
+    let hostname = "duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczad.onion";
+    let onion = OnionAddress::from_str(hostname).unwrap();
+    assert_eq!(
+        onion.as_bytes().to_vec(),
+        hex_bytes("1d04a1d04a338c6e6ae970bfabee49049d6702250984ca950c01673f4ec034ad").unwrap()
+    );
+    assert_eq!(onion.hostname(), hostname);
+    assert_eq!(
+        OnionAddress::from_str(&hostname.to_ascii_uppercase()).unwrap(),
+        onion
+    );
+    assert!(OnionAddress::is_onion_hostname(hostname));
+    assert!(!OnionAddress::is_onion_hostname("seed.mainnet.hiro.so"));
+
+    // bad checksum
+    assert!(OnionAddress::from_str(
+        "duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczae.onion"
+    )
+    .is_err());
+    // wrong length
+    assert!(OnionAddress::from_str("duckduckgogg42xjoc72x3sjas.onion").is_err());
+    // not base32
+    assert!(OnionAddress::from_str(
+        "duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzcza1.onion"
+    )
+    .is_err());
+    // not an onion
+    assert!(
+        OnionAddress::from_str("duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczad").is_err()
+    );
+
+    // synthetic peer address is publicly routable
+    let addr = onion.to_peer_address();
+    assert_eq!(
+        addr,
+        PeerAddress([
+            0xfd, 0x87, 0xd8, 0x7e, 0xeb, 0x43, 0x1d, 0x04, 0xa1, 0xd0, 0x4a, 0x33, 0x8c, 0x6e,
+            0x6a, 0xe9
+        ])
+    );
+    assert!(addr.is_onion());
+    assert!(!addr.is_in_private_range());
+    assert!(!PeerAddress::from_ipv4(127, 0, 0, 1).is_onion());
+    assert!(PeerAddress([
+        0xfd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01
+    ])
+    .is_in_private_range());
+}
+
+#[test]
+fn test_proxied_hostname_address() {
+    let addr = PeerAddress::from_proxied_hostname("seed.mainnet.hiro.so");
+    assert_eq!(
+        addr,
+        PeerAddress::from_proxied_hostname("SEED.mainnet.hiro.so")
+    );
+    assert_ne!(
+        addr,
+        PeerAddress::from_proxied_hostname("seed.testnet.hiro.so")
+    );
+
+    // synthetic peer address is routable through the proxy, and isn't an onion
+    assert_eq!(addr.as_bytes()[0..6], [0xfd, 0x87, 0xd8, 0x7e, 0xeb, 0x42]);
+    assert!(addr.is_proxied_hostname());
+    assert!(!addr.is_onion());
+    assert!(!addr.is_in_private_range());
+    assert!(!PeerAddress::from_ipv4(127, 0, 0, 1).is_proxied_hostname());
+}
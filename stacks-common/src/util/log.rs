@@ -15,6 +15,8 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
 use std::time::{Duration, SystemTime};
 use std::{env, io, thread};
 
@@ -208,9 +210,9 @@ fn make_json_logger() -> Logger {
                       }),
     );
 
-    let drain = Mutex::new(slog_json::Json::default(std::io::stderr()));
-    let filtered_drain = slog::LevelFilter::new(drain, get_loglevel()).ignore_res();
-    slog::Logger::root(filtered_drain, def_keys)
+    // no level filter here -- the logging macros filter by level, per module
+    let drain = Mutex::new(slog_json::Json::default(std::io::stderr())).ignore_res();
+    slog::Logger::root(drain, def_keys)
 }
 
 #[cfg(not(feature = "slog_json"))]
@@ -257,16 +259,70 @@ fn inner_get_loglevel() -> slog::Level {
 
 lazy_static! {
     static ref LOGLEVEL: slog::Level = inner_get_loglevel();
+    /// Log level overrides for modules and their submodules, set at runtime
+    static ref MODULE_LOGLEVELS: RwLock<Vec<(String, slog::Level)>> = RwLock::new(vec![]);
 }
 
+/// Are there any module log level overrides?  Checked first so that the logging macros don't
+/// take the lock in the common case where there are none.
+static HAVE_MODULE_LOGLEVELS: AtomicBool = AtomicBool::new(false);
+
 pub fn get_loglevel() -> slog::Level {
     *LOGLEVEL
 }
 
+/// Is `module_path` the module `module`, or one of its submodules?
+fn is_module_or_submodule(module: &str, module_path: &str) -> bool {
+    module_path
+        .strip_prefix(module)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// Get the log level for code in the given module path, as given by `module_path!()`.  This is
+/// the level of the most specific override for the module or one of its ancestors, or the
+/// global log level if there is no such override.
+pub fn get_module_loglevel(module_path: &str) -> slog::Level {
+    if !HAVE_MODULE_LOGLEVELS.load(Ordering::Relaxed) {
+        return get_loglevel();
+    }
+    let Ok(overrides) = MODULE_LOGLEVELS.read() else {
+        return get_loglevel();
+    };
+    overrides
+        .iter()
+        .filter(|(module, _)| is_module_or_submodule(module, module_path))
+        .max_by_key(|(module, _)| module.len())
+        .map(|(_, level)| *level)
+        .unwrap_or_else(get_loglevel)
+}
+
+/// Override the log level for a module (e.g. `blockstack_lib::net`) and its submodules, or
+/// remove its override if `level` is `None`.
+pub fn set_module_loglevel(module: &str, level: Option<slog::Level>) {
+    let mut overrides = MODULE_LOGLEVELS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    overrides.retain(|(overridden, _)| overridden != module);
+    if let Some(level) = level {
+        overrides.push((module.to_string(), level));
+    }
+    HAVE_MODULE_LOGLEVELS.store(!overrides.is_empty(), Ordering::SeqCst);
+}
+
+/// Get all module log level overrides, sorted by module
+pub fn get_module_loglevels() -> Vec<(String, slog::Level)> {
+    let mut overrides = MODULE_LOGLEVELS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone();
+    overrides.sort();
+    overrides
+}
+
 #[macro_export]
 macro_rules! trace {
     ($($arg:tt)*) => ({
-        let cur_level = $crate::util::log::get_loglevel();
+        let cur_level = $crate::util::log::get_module_loglevel(module_path!());
         if slog::Level::Trace.is_at_least(cur_level) {
             slog_trace!($crate::util::log::LOGGER, $($arg)*)
         }
@@ -276,7 +332,7 @@ macro_rules! trace {
 #[macro_export]
 macro_rules! error {
     ($($arg:tt)*) => ({
-        let cur_level = $crate::util::log::get_loglevel();
+        let cur_level = $crate::util::log::get_module_loglevel(module_path!());
         if slog::Level::Error.is_at_least(cur_level) {
             slog_error!($crate::util::log::LOGGER, $($arg)*)
         }
@@ -286,7 +342,7 @@ macro_rules! error {
 #[macro_export]
 macro_rules! warn {
     ($($arg:tt)*) => ({
-        let cur_level = $crate::util::log::get_loglevel();
+        let cur_level = $crate::util::log::get_module_loglevel(module_path!());
         if slog::Level::Warning.is_at_least(cur_level) {
             slog_warn!($crate::util::log::LOGGER, $($arg)*)
         }
@@ -296,7 +352,7 @@ macro_rules! warn {
 #[macro_export]
 macro_rules! info {
     ($($arg:tt)*) => ({
-        let cur_level = $crate::util::log::get_loglevel();
+        let cur_level = $crate::util::log::get_module_loglevel(module_path!());
         if slog::Level::Info.is_at_least(cur_level) {
             slog_info!($crate::util::log::LOGGER, $($arg)*)
         }
@@ -306,7 +362,7 @@ macro_rules! info {
 #[macro_export]
 macro_rules! debug {
     ($($arg:tt)*) => ({
-        let cur_level = $crate::util::log::get_loglevel();
+        let cur_level = $crate::util::log::get_module_loglevel(module_path!());
         if slog::Level::Debug.is_at_least(cur_level) {
             slog_debug!($crate::util::log::LOGGER, $($arg)*)
         }
@@ -316,7 +372,7 @@ macro_rules! debug {
 #[macro_export]
 macro_rules! fatal {
     ($($arg:tt)*) => ({
-        let cur_level = $crate::util::log::get_loglevel();
+        let cur_level = $crate::util::log::get_module_loglevel(module_path!());
         if slog::Level::Critical.is_at_least(cur_level) {
             slog_crit!($crate::util::log::LOGGER, $($arg)*)
         }
@@ -349,3 +405,50 @@ fn isatty(stream: Stream) -> bool {
 fn isatty(stream: Stream) -> bool {
     false
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_module_loglevels() {
+        let default_level = get_loglevel();
+
+        set_module_loglevel("test_module_loglevels::net", Some(Level::Trace));
+        set_module_loglevel("test_module_loglevels::net::p2p", Some(Level::Error));
+
+        assert_eq!(
+            get_module_loglevel("test_module_loglevels::net"),
+            Level::Trace
+        );
+        assert_eq!(
+            get_module_loglevel("test_module_loglevels::net::relay"),
+            Level::Trace
+        );
+        // most specific override wins
+        assert_eq!(
+            get_module_loglevel("test_module_loglevels::net::p2p::inner"),
+            Level::Error
+        );
+        // prefixes only match whole module names
+        assert_eq!(
+            get_module_loglevel("test_module_loglevels::network"),
+            default_level
+        );
+        assert_eq!(get_module_loglevel("test_module_loglevels"), default_level);
+
+        let overrides = get_module_loglevels();
+        assert!(overrides.contains(&("test_module_loglevels::net".to_string(), Level::Trace)));
+
+        set_module_loglevel("test_module_loglevels::net::p2p", None);
+        assert_eq!(
+            get_module_loglevel("test_module_loglevels::net::p2p::inner"),
+            Level::Trace
+        );
+        set_module_loglevel("test_module_loglevels::net", None);
+        assert_eq!(
+            get_module_loglevel("test_module_loglevels::net::p2p::inner"),
+            default_level
+        );
+    }
+}
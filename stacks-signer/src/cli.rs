@@ -39,6 +39,8 @@ use stacks_common::address::{
 use stacks_common::define_u8_enum;
 use stacks_common::types::chainstate::StacksPrivateKey;
 
+use crate::key_provider::{KeyProviderError, SignerKeyProvider, SigningContext};
+
 extern crate alloc;
 
 /// The CLI arguments for the stacks signer
@@ -73,6 +75,10 @@ pub enum Command {
     VerifyVote(VerifyVoteArgs),
     /// Verify signer signatures by checking stackerdb slots contain the correct data
     MonitorSigners(MonitorSignersArgs),
+    /// Export the signing safety journal to a file. The signer must be stopped first.
+    ExportSigningJournal(SigningJournalArgs),
+    /// Import a signing safety journal exported from another host
+    ImportSigningJournal(SigningJournalArgs),
 }
 
 /// Basic arguments for all cyrptographic and stacker-db functionality
@@ -141,6 +147,17 @@ pub struct RunSignerArgs {
     pub config: PathBuf,
 }
 
+#[derive(Parser, Debug, Clone)]
+/// Arguments for the ExportSigningJournal and ImportSigningJournal commands
+pub struct SigningJournalArgs {
+    /// Path to signer config file
+    #[arg(long, short, value_name = "FILE")]
+    pub config: PathBuf,
+    /// Path to the signing journal interchange file
+    #[arg(long, short, value_name = "FILE")]
+    pub file: PathBuf,
+}
+
 #[derive(Parser, Debug, Clone)]
 /// Arguments for the Vote command
 pub struct GenerateVoteArgs {
@@ -196,6 +213,21 @@ impl VoteInfo {
         private_key.sign(digest.as_bytes())
     }
 
+    /// Sign the vote data with the given key provider and return the signature
+    pub fn sign_with_provider(
+        &self,
+        key_provider: &dyn SignerKeyProvider,
+    ) -> Result<MessageSignature, KeyProviderError> {
+        let digest = self.digest();
+        key_provider.sign(
+            digest.as_bytes(),
+            &SigningContext::SipVote {
+                sip: self.sip,
+                vote: self.vote.to_u8(),
+            },
+        )
+    }
+
     /// Verify the vote data against the provided public key and signature
     pub fn verify(
         &self,
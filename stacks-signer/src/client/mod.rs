@@ -373,12 +373,11 @@ pub(crate) mod tests {
         let mut signer_addresses = Vec::new();
 
         for signer_id in 0..num_signers {
-            let private_key = if signer_id == 0 {
-                config.stacks_private_key
+            let public_key = if signer_id == 0 {
+                config.stacks_public_key
             } else {
-                StacksPrivateKey::random()
+                StacksPublicKey::from_private(&StacksPrivateKey::random())
             };
-            let public_key = StacksPublicKey::from_private(&private_key);
 
             signer_id_to_pk.insert(signer_id, public_key);
             signer_pk_to_id.insert(public_key, signer_id);
@@ -407,7 +406,7 @@ pub(crate) mod tests {
                 signer_addresses,
             },
             signer_slot_ids,
-            stacks_private_key: config.stacks_private_key,
+            key_provider: config.key_provider.clone(),
             node_host: config.node_host.to_string(),
             mainnet: config.network.is_mainnet(),
             db_path: config.db_path.clone(),
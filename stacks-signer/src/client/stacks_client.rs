@@ -21,9 +21,9 @@ use blockstack_lib::chainstate::nakamoto::NakamotoBlock;
 use blockstack_lib::chainstate::stacks::boot::{NakamotoSignerEntry, SIGNERS_NAME};
 use blockstack_lib::chainstate::stacks::db::StacksBlockHeaderTypes;
 use blockstack_lib::chainstate::stacks::{
-    StacksTransaction, StacksTransactionSigner, TransactionAnchorMode, TransactionAuth,
-    TransactionContractCall, TransactionPayload, TransactionPostConditionMode,
-    TransactionSpendingCondition, TransactionVersion,
+    StacksTransaction, TransactionAnchorMode, TransactionAuth, TransactionContractCall,
+    TransactionPayload, TransactionPostConditionMode, TransactionSpendingCondition,
+    TransactionVersion,
 };
 use blockstack_lib::net::api::callreadonly::CallReadOnlyResponse;
 use blockstack_lib::net::api::get_tenures_fork_info::{
@@ -62,8 +62,6 @@ use crate::runloop::RewardCycleInfo;
 pub struct StacksClient {
     /// The stacks address of the signer
     stacks_address: StacksAddress,
-    /// The private key used in all stacks node communications
-    stacks_private_key: StacksPrivateKey,
     /// The stacks node HTTP base endpoint
     http_origin: String,
     /// The types of transactions
@@ -95,7 +93,6 @@ pub struct CurrentAndLastSortition {
 impl From<&GlobalConfig> for StacksClient {
     fn from(config: &GlobalConfig) -> Self {
         Self {
-            stacks_private_key: config.stacks_private_key,
             stacks_address: config.stacks_address,
             http_origin: format!("http://{}", config.node_host),
             tx_version: config.network.to_transaction_version(),
@@ -124,7 +121,6 @@ impl StacksClient {
         };
         let stacks_address = StacksAddress::p2pkh(mainnet, &pubkey);
         Self {
-            stacks_private_key,
             stacks_address,
             http_origin: format!("http://{}", node_host),
             tx_version,
@@ -794,23 +790,6 @@ impl StacksClient {
         unsigned_tx.chain_id = chain_id;
         Ok(unsigned_tx)
     }
-
-    /// Sign an unsigned transaction
-    pub fn sign_transaction(
-        &self,
-        unsigned_tx: StacksTransaction,
-    ) -> Result<StacksTransaction, ClientError> {
-        let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
-        tx_signer
-            .sign_origin(&self.stacks_private_key)
-            .map_err(|e| ClientError::TransactionGenerationFailure(e.to_string()))?;
-
-        tx_signer
-            .get_tx()
-            .ok_or(ClientError::TransactionGenerationFailure(
-                "Failed to generate transaction from a transaction signer".to_string(),
-            ))
-    }
 }
 
 #[cfg(test)]
@@ -18,6 +18,7 @@ use std::fmt::{Debug, Display};
 use std::fs;
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use blockstack_lib::chainstate::stacks::TransactionVersion;
@@ -33,6 +34,9 @@ use stacks_common::types::chainstate::{StacksAddress, StacksPrivateKey, StacksPu
 use stacks_common::util::hash::Hash160;
 
 use crate::client::SignerSlotID;
+use crate::key_provider::{
+    LocalKeyProvider, RemoteKeyProvider, SignerKeyProvider, DEFAULT_REMOTE_SIGNER_TIMEOUT_MS,
+};
 
 const EVENT_TIMEOUT_MS: u64 = 5000;
 const BLOCK_PROPOSAL_TIMEOUT_MS: u64 = 120_000;
@@ -145,8 +149,8 @@ pub struct SignerConfig {
     pub signer_entries: SignerEntries,
     /// The signer slot ids of all signers registered for this reward cycle
     pub signer_slot_ids: Vec<SignerSlotID>,
-    /// The private key for this signer
-    pub stacks_private_key: StacksPrivateKey,
+    /// The key provider which signs for this signer
+    pub key_provider: Arc<dyn SignerKeyProvider>,
     /// The node host for this signer
     pub node_host: String,
     /// Whether this signer is running on mainnet or not
@@ -184,8 +188,10 @@ pub struct GlobalConfig {
     pub node_host: String,
     /// endpoint to the event receiver
     pub endpoint: SocketAddr,
-    /// The signer's Stacks private key
-    pub stacks_private_key: StacksPrivateKey,
+    /// The signer's Stacks public key
+    pub stacks_public_key: StacksPublicKey,
+    /// The key provider which holds the signer's Stacks private key
+    pub key_provider: Arc<dyn SignerKeyProvider>,
     /// The signer's Stacks address
     pub stacks_address: StacksAddress,
     /// The network to use. One of "mainnet" or "testnet".
@@ -234,7 +240,16 @@ struct RawConfigFile {
     pub endpoint: String,
     /// The hex representation of the signer's Stacks private key used for communicating
     /// with the Stacks Node, including writing to the Stacker DB instance.
-    pub stacks_private_key: String,
+    /// Exactly one of this or `remote_signer_endpoint` must be set.
+    pub stacks_private_key: Option<String>,
+    /// The `host:port` of a remote signer which holds the signer's Stacks private key, and
+    /// signs on its behalf.  Requires `stacks_public_key`.
+    pub remote_signer_endpoint: Option<String>,
+    /// The hex representation of the signer's Stacks public key.  Required when using a
+    /// remote signer.
+    pub stacks_public_key: Option<String>,
+    /// The time to wait (in millisecs) for a response from the remote signer
+    pub remote_signer_timeout_ms: Option<u64>,
     /// The network to use. One of "mainnet" or "testnet".
     pub network: Network,
     /// The time to wait (in millisecs) for a response from the stacker-db instance
@@ -315,9 +330,65 @@ impl TryFrom<RawConfigFile> for GlobalConfig {
                 ConfigError::BadField("endpoint".to_string(), raw_data.endpoint.clone())
             })?;
 
-        let stacks_private_key = StacksPrivateKey::from_hex(&raw_data.stacks_private_key)
-            .map_err(|e| ConfigError::BadField("stacks_private_key".to_string(), e.into()))?;
-        let stacks_public_key = StacksPublicKey::from_private(&stacks_private_key);
+        let configured_public_key = raw_data
+            .stacks_public_key
+            .as_ref()
+            .map(|public_key| {
+                StacksPublicKey::from_hex(public_key)
+                    .map_err(|e| ConfigError::BadField("stacks_public_key".to_string(), e.into()))
+            })
+            .transpose()?;
+        let key_provider: Arc<dyn SignerKeyProvider> = match (
+            raw_data.stacks_private_key.as_ref(),
+            raw_data.remote_signer_endpoint,
+        ) {
+            (Some(stacks_private_key), None) => {
+                let stacks_private_key =
+                    StacksPrivateKey::from_hex(stacks_private_key).map_err(|e| {
+                        ConfigError::BadField("stacks_private_key".to_string(), e.into())
+                    })?;
+                let key_provider = LocalKeyProvider::new(stacks_private_key);
+                if configured_public_key
+                    .is_some_and(|public_key| &public_key != key_provider.public_key())
+                {
+                    return Err(ConfigError::BadField(
+                        "stacks_public_key".to_string(),
+                        "does not match stacks_private_key".to_string(),
+                    ));
+                }
+                Arc::new(key_provider)
+            }
+            (None, Some(remote_signer_endpoint)) => {
+                let Some(public_key) = configured_public_key else {
+                    return Err(ConfigError::InvalidConfig(
+                        "stacks_public_key is required with remote_signer_endpoint".to_string(),
+                    ));
+                };
+                remote_signer_endpoint.to_socket_addrs().map_err(|_| {
+                    ConfigError::BadField(
+                        "remote_signer_endpoint".to_string(),
+                        remote_signer_endpoint.clone(),
+                    )
+                })?;
+                let timeout = Duration::from_millis(
+                    raw_data
+                        .remote_signer_timeout_ms
+                        .unwrap_or(DEFAULT_REMOTE_SIGNER_TIMEOUT_MS),
+                );
+                Arc::new(RemoteKeyProvider::new(
+                    remote_signer_endpoint,
+                    public_key,
+                    timeout,
+                ))
+            }
+            _ => {
+                return Err(ConfigError::InvalidConfig(
+                    "exactly one of stacks_private_key or remote_signer_endpoint must be set"
+                        .to_string(),
+                ));
+            }
+        };
+        let stacks_public_key = *key_provider.public_key();
         let signer_hash = Hash160::from_data(stacks_public_key.to_bytes_compressed().as_slice());
         let stacks_address =
             StacksAddress::p2pkh_from_hash(raw_data.network.is_mainnet(), signer_hash);
@@ -388,7 +459,8 @@ impl TryFrom<RawConfigFile> for GlobalConfig {
         Ok(Self {
             node_host: raw_data.node_host,
             endpoint,
-            stacks_private_key,
+            stacks_public_key,
+            key_provider,
             stacks_address,
             network: raw_data.network,
             event_timeout,
@@ -450,9 +522,7 @@ Metrics endpoint: {metrics_endpoint}
             node_host = self.node_host,
             endpoint = self.endpoint,
             stacks_address = self.stacks_address,
-            public_key = to_hex(
-                &StacksPublicKey::from_private(&self.stacks_private_key).to_bytes_compressed()
-            ),
+            public_key = to_hex(&self.stacks_public_key.to_bytes_compressed()),
             network = self.network,
             db_path = self.db_path.to_str().unwrap_or_default(),
             metrics_endpoint = metrics_endpoint,
@@ -687,6 +757,71 @@ db_path = ":memory:"
         assert_eq!(config.to_chain_id(), CHAIN_ID_MAINNET);
     }
 
+    #[test]
+    fn test_remote_signer_config() {
+        let pk = StacksPrivateKey::from_hex(
+            "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+        )
+        .unwrap();
+        let public_key = StacksPublicKey::from_private(&pk);
+        let public_key_hex = to_hex(&public_key.to_bytes_compressed());
+        let base_toml = r#"
+node_host = "localhost"
+endpoint = "localhost:30000"
+network = "testnet"
+auth_password = "abcd"
+db_path = ":memory:"
+"#;
+
+        let config = GlobalConfig::load_from_str(&format!(
+            r#"{base_toml}
+remote_signer_endpoint = "127.0.0.1:30005"
+stacks_public_key = "{public_key_hex}"
+"#
+        ))
+        .unwrap();
+        assert_eq!(config.stacks_public_key, public_key);
+        assert_eq!(config.key_provider.public_key(), &public_key);
+        assert_eq!(
+            config.stacks_address,
+            StacksAddress::p2pkh(false, &public_key)
+        );
+
+        // a remote signer needs the public key
+        assert!(GlobalConfig::load_from_str(&format!(
+            r#"{base_toml}
+remote_signer_endpoint = "127.0.0.1:30005"
+"#
+        ))
+        .is_err());
+
+        // can't have both a private key and a remote signer
+        let sk_hex = pk.to_hex();
+        assert!(GlobalConfig::load_from_str(&format!(
+            r#"{base_toml}
+stacks_private_key = "{sk_hex}"
+remote_signer_endpoint = "127.0.0.1:30005"
+stacks_public_key = "{public_key_hex}"
+"#
+        ))
+        .is_err());
+
+        // or neither
+        assert!(GlobalConfig::load_from_str(base_toml).is_err());
+
+        // the public key must match the private key, if both are given
+        let other_public_key_hex = to_hex(
+            &StacksPublicKey::from_private(&StacksPrivateKey::random()).to_bytes_compressed(),
+        );
+        assert!(GlobalConfig::load_from_str(&format!(
+            r#"{base_toml}
+stacks_private_key = "{sk_hex}"
+stacks_public_key = "{other_public_key_hex}"
+"#
+        ))
+        .is_err());
+    }
+
     #[test]
     fn test_custom_chain_id() {
         let pk = StacksPrivateKey::from_hex(
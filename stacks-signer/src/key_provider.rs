@@ -0,0 +1,445 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The signer's Stacks private key is used to sign block responses, mock signatures and
+//! StackerDB chunks.  A [`SignerKeyProvider`] produces those signatures, either with a key held
+//! in memory ([`LocalKeyProvider`]) or by asking a separate process which holds the key
+//! ([`RemoteKeyProvider`]).
+//!
+//! The remote signing protocol is a single HTTP endpoint, `POST /v1/sign`, which takes a JSON
+//! [`RemoteSignRequest`] and answers a signing request with a `200` and a JSON
+//! [`RemoteSignResponse`].  The request describes what is being signed in its `context`, so
+//! the remote process can refuse to sign (e.g. a second, conflicting block at the same height).
+//! Any other response status is treated as a refusal, and its body as the reason.
+
+use std::fmt::Debug;
+use std::time::Duration;
+
+use blockstack_lib::chainstate::nakamoto::NakamotoBlock;
+use serde::{Deserialize, Serialize};
+use stacks_common::types::chainstate::{
+    ConsensusHash, StacksBlockId, StacksPrivateKey, StacksPublicKey,
+};
+use stacks_common::types::{PrivateKey, PublicKey};
+use stacks_common::util::hash::{to_hex, Sha512Trunc256Sum};
+use stacks_common::util::secp256k1::MessageSignature;
+
+/// Default time to wait for the remote signer to respond
+pub const DEFAULT_REMOTE_SIGNER_TIMEOUT_MS: u64 = 5_000;
+
+#[derive(thiserror::Error, Debug)]
+/// An error occurred while signing with the signer's key
+pub enum KeyProviderError {
+    /// The key failed to produce a signature
+    #[error("Failed to sign: {0}")]
+    SigningError(String),
+    /// The remote signer could not be reached
+    #[error("Remote signer request failed: {0}")]
+    RequestFailure(String),
+    /// The remote signer refused to sign
+    #[error("Remote signer refused to sign: {0}")]
+    Refused(String),
+    /// The remote signer's response was malformed
+    #[error("Malformed remote signer response: {0}")]
+    MalformedResponse(String),
+    /// The remote signer's signature was not made with the signer's key
+    #[error("Remote signer's signature does not match the signer's public key")]
+    InvalidSignature,
+}
+
+/// What a signature is being made over, so that a key provider can apply signing policies
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SigningContext {
+    /// Accepting a proposed block
+    BlockAcceptance {
+        /// The signer signature hash of the block
+        signer_signature_hash: Sha512Trunc256Sum,
+        /// The block's ID
+        block_id: StacksBlockId,
+        /// The block's tenure
+        consensus_hash: ConsensusHash,
+        /// The block's height
+        block_height: u64,
+    },
+    /// Rejecting a proposed block
+    BlockRejection {
+        /// The signer signature hash of the block
+        signer_signature_hash: Sha512Trunc256Sum,
+        /// Why the block is being rejected
+        reason: String,
+    },
+    /// Mock signing a mock proposal in epoch 2.5
+    MockSignature {
+        /// The burnchain height of the mock proposal
+        burn_block_height: u64,
+        /// The Stacks tip height of the mock proposal
+        stacks_tip_height: u64,
+    },
+    /// Writing a chunk to a StackerDB slot
+    StackerDbChunk {
+        /// The slot being written
+        slot_id: u32,
+        /// The slot's new version
+        slot_version: u32,
+    },
+    /// Authorizing the signer key for use in a pox-4 stacking operation
+    SignerKeyAuthorization {
+        /// The PoX address of the stacking operation
+        pox_address: String,
+        /// The reward cycle of the stacking operation
+        reward_cycle: u128,
+        /// The pox-4 function the authorization is for
+        topic: String,
+        /// The number of reward cycles of the stacking operation
+        period: u128,
+        /// The maximum amount of uSTX which can be stacked
+        max_amount: u128,
+        /// The authorization's ID
+        auth_id: u128,
+    },
+    /// Voting on a SIP
+    SipVote {
+        /// The SIP number
+        sip: u32,
+        /// The vote (0 is yes, 1 is no)
+        vote: u8,
+    },
+}
+
+impl SigningContext {
+    /// Context for accepting `block`
+    pub fn block_acceptance(block: &NakamotoBlock) -> Self {
+        Self::BlockAcceptance {
+            signer_signature_hash: block.header.signer_signature_hash(),
+            block_id: block.block_id(),
+            consensus_hash: block.header.consensus_hash,
+            block_height: block.header.chain_length,
+        }
+    }
+}
+
+/// A source of signatures made with the signer's Stacks private key
+pub trait SignerKeyProvider: Debug + Send + Sync {
+    /// The public key of the signer's private key
+    fn public_key(&self) -> &StacksPublicKey;
+
+    /// Sign the 32-byte `message_hash`, which is described by `context`, with the signer's
+    /// private key.  Returns a recoverable signature.
+    fn sign(
+        &self,
+        message_hash: &[u8],
+        context: &SigningContext,
+    ) -> Result<MessageSignature, KeyProviderError>;
+}
+
+/// A key provider which holds the signer's private key in memory
+pub struct LocalKeyProvider {
+    private_key: StacksPrivateKey,
+    public_key: StacksPublicKey,
+}
+
+impl LocalKeyProvider {
+    /// Create a key provider for the given private key
+    pub fn new(private_key: StacksPrivateKey) -> Self {
+        Self {
+            public_key: StacksPublicKey::from_private(&private_key),
+            private_key,
+        }
+    }
+}
+
+impl Debug for LocalKeyProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never log the private key
+        f.debug_struct("LocalKeyProvider")
+            .field("public_key", &self.public_key.to_hex())
+            .finish()
+    }
+}
+
+impl SignerKeyProvider for LocalKeyProvider {
+    fn public_key(&self) -> &StacksPublicKey {
+        &self.public_key
+    }
+
+    fn sign(
+        &self,
+        message_hash: &[u8],
+        _context: &SigningContext,
+    ) -> Result<MessageSignature, KeyProviderError> {
+        self.private_key
+            .sign(message_hash)
+            .map_err(|e| KeyProviderError::SigningError(e.to_string()))
+    }
+}
+
+/// The body of a remote signing request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteSignRequest {
+    /// Hex-encoded compressed public key of the key to sign with
+    pub public_key: String,
+    /// Hex-encoded 32-byte hash to sign
+    pub message_hash: String,
+    /// What is being signed
+    pub context: SigningContext,
+}
+
+/// The body of a successful remote signing response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteSignResponse {
+    /// Hex-encoded 65-byte recoverable signature over the message hash
+    pub signature: String,
+}
+
+/// A key provider which asks a separate process, which holds the signer's private key, to sign
+#[derive(Debug)]
+pub struct RemoteKeyProvider {
+    /// The remote signer's `host:port`
+    endpoint: String,
+    /// The signer's public key.  Every signature from the remote signer is checked against it.
+    public_key: StacksPublicKey,
+    /// The Client used to make HTTP connects
+    client: reqwest::blocking::Client,
+}
+
+impl RemoteKeyProvider {
+    /// Create a key provider for the remote signer at `endpoint` (a `host:port`), which holds the
+    /// private key of `public_key`
+    pub fn new(endpoint: String, public_key: StacksPublicKey, timeout: Duration) -> Self {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("FATAL: failed to build remote signer HTTP client");
+        Self {
+            endpoint,
+            public_key,
+            client,
+        }
+    }
+
+    /// The URL of the remote signer's signing endpoint
+    fn sign_path(&self) -> String {
+        format!("http://{}/v1/sign", self.endpoint)
+    }
+}
+
+impl SignerKeyProvider for RemoteKeyProvider {
+    fn public_key(&self) -> &StacksPublicKey {
+        &self.public_key
+    }
+
+    fn sign(
+        &self,
+        message_hash: &[u8],
+        context: &SigningContext,
+    ) -> Result<MessageSignature, KeyProviderError> {
+        let request = RemoteSignRequest {
+            public_key: to_hex(&self.public_key.to_bytes_compressed()),
+            message_hash: to_hex(message_hash),
+            context: context.clone(),
+        };
+        let response = self
+            .client
+            .post(self.sign_path())
+            .json(&request)
+            .send()
+            .map_err(|e| KeyProviderError::RequestFailure(e.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            let reason = response.text().unwrap_or_default();
+            return Err(KeyProviderError::Refused(format!("{status}: {reason}")));
+        }
+        let response: RemoteSignResponse = response
+            .json()
+            .map_err(|e| KeyProviderError::MalformedResponse(e.to_string()))?;
+        let signature = MessageSignature::from_hex(&response.signature)
+            .map_err(|e| KeyProviderError::MalformedResponse(e.to_string()))?;
+        if !self
+            .public_key
+            .verify(message_hash, &signature)
+            .unwrap_or(false)
+        {
+            return Err(KeyProviderError::InvalidSignature);
+        }
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    use super::*;
+
+    /// Serve one signing request with `privk`, or refuse it with a 403 if `refuse` is set.
+    /// Returns the received request.
+    fn serve_one(
+        listener: TcpListener,
+        privk: StacksPrivateKey,
+        refuse: bool,
+    ) -> JoinHandle<RemoteSignRequest> {
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = vec![];
+            let mut chunk = [0u8; 1024];
+            let request = loop {
+                let nr = stream.read(&mut chunk).unwrap();
+                buf.extend_from_slice(&chunk[..nr]);
+                let text = String::from_utf8_lossy(&buf).to_string();
+                let Some((headers, body)) = text.split_once("\r\n\r\n") else {
+                    continue;
+                };
+                let content_length: usize = headers
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse().unwrap())
+                    })
+                    .unwrap();
+                if body.len() >= content_length {
+                    break serde_json::from_str::<RemoteSignRequest>(body).unwrap();
+                }
+            };
+            let response = if refuse {
+                let reason = "already signed a block at this height";
+                format!(
+                    "HTTP/1.1 403 Forbidden\r\nContent-Length: {}\r\n\r\n{reason}",
+                    reason.len()
+                )
+            } else {
+                let message_hash =
+                    stacks_common::util::hash::hex_bytes(&request.message_hash).unwrap();
+                let signature = privk.sign(&message_hash).unwrap();
+                let body = serde_json::to_string(&RemoteSignResponse {
+                    signature: signature.to_hex(),
+                })
+                .unwrap();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+                    body.len()
+                )
+            };
+            stream.write_all(response.as_bytes()).unwrap();
+            request
+        })
+    }
+
+    fn mock_context() -> SigningContext {
+        SigningContext::StackerDbChunk {
+            slot_id: 1,
+            slot_version: 2,
+        }
+    }
+
+    #[test]
+    fn local_key_provider_signs() {
+        let privk = StacksPrivateKey::random();
+        let provider = LocalKeyProvider::new(privk);
+        let message_hash = [0x11; 32];
+        let signature = provider.sign(&message_hash, &mock_context()).unwrap();
+        assert!(provider
+            .public_key()
+            .verify(&message_hash, &signature)
+            .unwrap());
+        assert!(!format!("{provider:?}").contains(&privk.to_hex()));
+    }
+
+    #[test]
+    fn remote_key_provider_signs() {
+        let privk = StacksPrivateKey::random();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = listener.local_addr().unwrap().to_string();
+        let server = serve_one(listener, privk, false);
+
+        let provider = RemoteKeyProvider::new(
+            endpoint,
+            StacksPublicKey::from_private(&privk),
+            Duration::from_secs(5),
+        );
+        let message_hash = [0x22; 32];
+        let signature = provider.sign(&message_hash, &mock_context()).unwrap();
+        assert!(provider
+            .public_key()
+            .verify(&message_hash, &signature)
+            .unwrap());
+
+        let request = server.join().unwrap();
+        assert_eq!(request.message_hash, to_hex(&message_hash));
+        assert_eq!(request.context, mock_context());
+    }
+
+    #[test]
+    fn remote_key_provider_refusal() {
+        let privk = StacksPrivateKey::random();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = listener.local_addr().unwrap().to_string();
+        let server = serve_one(listener, privk, true);
+
+        let provider = RemoteKeyProvider::new(
+            endpoint,
+            StacksPublicKey::from_private(&privk),
+            Duration::from_secs(5),
+        );
+        match provider.sign(&[0x33; 32], &mock_context()) {
+            Err(KeyProviderError::Refused(reason)) => {
+                assert!(reason.contains("already signed a block at this height"))
+            }
+            res => panic!("expected refusal, got {res:?}"),
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn remote_key_provider_wrong_key() {
+        // the remote signer signs with a different key than the one we expect
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = listener.local_addr().unwrap().to_string();
+        let server = serve_one(listener, StacksPrivateKey::random(), false);
+
+        let provider = RemoteKeyProvider::new(
+            endpoint,
+            StacksPublicKey::from_private(&StacksPrivateKey::random()),
+            Duration::from_secs(5),
+        );
+        assert!(matches!(
+            provider.sign(&[0x44; 32], &mock_context()),
+            Err(KeyProviderError::InvalidSignature)
+        ));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn signing_context_json() {
+        let context = SigningContext::SipVote { sip: 31, vote: 0 };
+        let json = serde_json::to_value(&context).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "sip_vote",
+                "sip": 31,
+                "vote": 0,
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<SigningContext>(json).unwrap(),
+            context
+        );
+    }
+}
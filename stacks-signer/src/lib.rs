@@ -29,6 +29,8 @@ pub mod cli;
 pub mod client;
 /// The configuration module for the signer
 pub mod config;
+/// Backends which hold the signer's private key
+pub mod key_provider;
 /// The signer monitor for observing signer behaviours in the network
 pub mod monitor_signers;
 /// The monitoring server for the signer
@@ -26,11 +26,11 @@ extern crate serde;
 extern crate serde_json;
 extern crate toml;
 
+use std::fs;
 use std::io::{self, Write};
 
-use blockstack_lib::util_lib::signed_structured_data::pox4::make_pox_4_signer_key_signature;
+use blockstack_lib::util_lib::signed_structured_data::pox4::make_pox_4_signer_key_message_hash;
 use clap::Parser;
-use clarity::types::chainstate::StacksPublicKey;
 use clarity::util::sleep_ms;
 use libsigner::{SignerSession, VERSION_STRING};
 use libstackerdb::StackerDBChunkData;
@@ -40,11 +40,13 @@ use stacks_common::util::secp256k1::MessageSignature;
 use stacks_common::{debug, error};
 use stacks_signer::cli::{
     Cli, Command, GenerateStackingSignatureArgs, GenerateVoteArgs, GetChunkArgs,
-    GetLatestChunkArgs, MonitorSignersArgs, PutChunkArgs, RunSignerArgs, StackerDBArgs,
-    VerifyVoteArgs,
+    GetLatestChunkArgs, MonitorSignersArgs, PutChunkArgs, RunSignerArgs, SigningJournalArgs,
+    StackerDBArgs, VerifyVoteArgs,
 };
 use stacks_signer::config::GlobalConfig;
+use stacks_signer::key_provider::SigningContext;
 use stacks_signer::monitor_signers::SignerMonitor;
+use stacks_signer::signerdb::{SignerDb, SigningJournalExport, SIGNING_JOURNAL_FORMAT_VERSION};
 use stacks_signer::utils::stackerdb_session;
 use stacks_signer::v0::SpawnedSigner;
 use tracing_subscriber::prelude::*;
@@ -112,21 +114,29 @@ fn handle_generate_stacking_signature(
 ) -> MessageSignature {
     let config = GlobalConfig::try_from(&args.config).unwrap();
 
-    let private_key = config.stacks_private_key;
-    let public_key = StacksPublicKey::from_private(&private_key);
-    let pk_hex = to_hex(&public_key.to_bytes_compressed());
+    let pk_hex = to_hex(&config.stacks_public_key.to_bytes_compressed());
 
-    let signature = make_pox_4_signer_key_signature(
+    let message_hash = make_pox_4_signer_key_message_hash(
         &args.pox_address,
-        &private_key, //
         args.reward_cycle.into(),
         args.method.topic(),
         config.to_chain_id(),
         args.period.into(),
         args.max_amount,
         args.auth_id,
-    )
-    .expect("Failed to generate signature");
+    );
+    let context = SigningContext::SignerKeyAuthorization {
+        pox_address: args.pox_address.clone().to_b58(),
+        reward_cycle: args.reward_cycle.into(),
+        topic: args.method.topic().to_string(),
+        period: args.period.into(),
+        max_amount: args.max_amount,
+        auth_id: args.auth_id,
+    };
+    let signature = config
+        .key_provider
+        .sign(message_hash.as_bytes(), &context)
+        .expect("Failed to generate signature");
 
     let output_str = if args.json {
         serde_json::to_string(&serde_json::json!({
@@ -162,7 +172,10 @@ fn handle_check_config(args: RunSignerArgs) {
 
 fn handle_generate_vote(args: GenerateVoteArgs, do_print: bool) -> MessageSignature {
     let config = GlobalConfig::try_from(&args.config).unwrap();
-    let message_signature = args.vote_info.sign(&config.stacks_private_key).unwrap();
+    let message_signature = args
+        .vote_info
+        .sign_with_provider(config.key_provider.as_ref())
+        .unwrap();
     if do_print {
         println!("{}", to_hex(message_signature.as_bytes()));
     }
@@ -198,6 +211,50 @@ fn handle_monitor_signers(args: MonitorSignersArgs) {
     }
 }
 
+fn handle_export_signing_journal(args: SigningJournalArgs) {
+    let config = GlobalConfig::try_from(&args.config).unwrap();
+    let signer_db = SignerDb::new(&config.db_path).expect("Failed to open signer db");
+    let export = SigningJournalExport {
+        version: SIGNING_JOURNAL_FORMAT_VERSION,
+        signer_public_key: to_hex(&config.stacks_public_key.to_bytes_compressed()),
+        entries: signer_db
+            .export_signing_journal()
+            .expect("Failed to read signing journal"),
+    };
+    let export_json =
+        serde_json::to_string_pretty(&export).expect("Failed to serialize signing journal");
+    fs::write(&args.file, export_json).expect("Failed to write signing journal");
+    println!(
+        "Exported {} signing journal entries to {}",
+        export.entries.len(),
+        args.file.display()
+    );
+}
+
+fn handle_import_signing_journal(args: SigningJournalArgs) {
+    let config = GlobalConfig::try_from(&args.config).unwrap();
+    let import_json = fs::read_to_string(&args.file).expect("Failed to read signing journal");
+    let import: SigningJournalExport =
+        serde_json::from_str(&import_json).expect("Failed to parse signing journal");
+    assert_eq!(
+        import.version, SIGNING_JOURNAL_FORMAT_VERSION,
+        "Unsupported signing journal version"
+    );
+    assert_eq!(
+        import.signer_public_key,
+        to_hex(&config.stacks_public_key.to_bytes_compressed()),
+        "Signing journal was exported for a different signer key"
+    );
+    let mut signer_db = SignerDb::new(&config.db_path).expect("Failed to open signer db");
+    let updated = signer_db
+        .import_signing_journal(&import.entries)
+        .expect("Failed to import signing journal");
+    println!(
+        "Imported {} signing journal entries ({updated} inserted or updated)",
+        import.entries.len()
+    );
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -237,6 +294,12 @@ fn main() {
         Command::MonitorSigners(args) => {
             handle_monitor_signers(args);
         }
+        Command::ExportSigningJournal(args) => {
+            handle_export_signing_journal(args);
+        }
+        Command::ImportSigningJournal(args) => {
+            handle_import_signing_journal(args);
+        }
     }
 }
 
@@ -251,6 +314,7 @@ pub mod tests {
     use clarity::vm::{execute_v2, Value};
     use rand::{Rng, RngCore};
     use stacks_common::consts::CHAIN_ID_TESTNET;
+    use stacks_common::types::chainstate::StacksPublicKey;
     use stacks_common::types::PublicKey;
     use stacks_common::util::secp256k1::Secp256k1PublicKey;
     use stacks_signer::cli::{parse_pox_addr, VerifyVoteArgs, Vote, VoteInfo};
@@ -311,7 +375,7 @@ pub mod tests {
         };
 
         let signature = handle_generate_stacking_signature(args.clone(), false);
-        let public_key = Secp256k1PublicKey::from_private(&config.stacks_private_key);
+        let public_key = config.stacks_public_key;
 
         let valid = call_verify_signer_sig(
             &args.pox_address,
@@ -334,7 +398,7 @@ pub mod tests {
         args.max_amount = 100;
 
         let signature = handle_generate_stacking_signature(args.clone(), false);
-        let public_key = Secp256k1PublicKey::from_private(&config.stacks_private_key);
+        let public_key = config.stacks_public_key;
 
         let valid = call_verify_signer_sig(
             &args.pox_address,
@@ -367,7 +431,7 @@ pub mod tests {
 
         let signature = handle_generate_stacking_signature(args.clone(), false);
 
-        let public_key = Secp256k1PublicKey::from_private(&config.stacks_private_key);
+        let public_key = config.stacks_public_key;
 
         let message_hash = make_pox_4_signer_key_message_hash(
             &args.pox_address,
@@ -393,8 +457,7 @@ pub mod tests {
         };
         let config_file = "./src/tests/conf/signer-0.toml";
         let config = GlobalConfig::load_from_file(config_file).unwrap();
-        let private_key = config.stacks_private_key;
-        let public_key = StacksPublicKey::from_private(&private_key);
+        let public_key = config.stacks_public_key;
         let args = GenerateVoteArgs {
             config: config_file.into(),
             vote_info,
@@ -87,7 +87,7 @@ impl MonitoringServer {
         };
         let stacks_client = StacksClient::from(config);
         let http_server = HttpServer::http(endpoint).map_err(|_| MonitoringError::AlreadyBound)?;
-        let public_key = Secp256k1PublicKey::from_private(&config.stacks_private_key);
+        let public_key = config.stacks_public_key;
         let mut server = MonitoringServer::new(
             http_server,
             endpoint,
@@ -19,13 +19,11 @@ use std::path::Path;
 use std::time::{Duration, SystemTime};
 
 use blockstack_lib::chainstate::nakamoto::NakamotoBlock;
-use blockstack_lib::chainstate::stacks::TransactionPayload;
+use blockstack_lib::chainstate::stacks::{TenureChangeCause, TransactionPayload};
 use blockstack_lib::util_lib::db::{
     query_row, query_rows, sqlite_open, table_exists, tx_begin_immediate, u64_to_sql,
-    Error as DBError,
+    Error as DBError, FromColumn, FromRow,
 };
-#[cfg(any(test, feature = "testing"))]
-use blockstack_lib::util_lib::db::{FromColumn, FromRow};
 use clarity::types::chainstate::{BurnchainHeaderHash, StacksAddress};
 use clarity::types::Address;
 use libsigner::v0::messages::{RejectReason, RejectReasonPrefix};
@@ -286,6 +284,150 @@ impl BlockInfo {
     }
 }
 
+/// The version of the signing journal interchange format produced by
+/// [`SignerDb::export_signing_journal`]
+pub const SIGNING_JOURNAL_FORMAT_VERSION: u32 = 1;
+
+/// An entry in the signing safety journal. There is at most one entry per tenure,
+/// recording the highest block this signer has ever signed in it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SigningJournalEntry {
+    /// The consensus hash of the tenure
+    pub consensus_hash: ConsensusHash,
+    /// The consensus hash of the tenure that this tenure builds upon, if the signer
+    /// has signed the tenure's `BlockFound` tenure change block
+    pub parent_consensus_hash: Option<ConsensusHash>,
+    /// The highest block height signed in this tenure
+    pub block_height: u64,
+    /// The signer signature hash of the highest block signed in this tenure
+    pub signer_signature_hash: Sha512Trunc256Sum,
+    /// Whether the block at `block_height` was globally rejected, in which case it can
+    /// never be approved and a different block may be signed at the same height
+    pub globally_rejected: bool,
+    /// The time at which this entry was last updated (epoch seconds)
+    pub signed_time: u64,
+}
+
+impl SigningJournalEntry {
+    /// Check whether signing `block` would conflict with what this entry says
+    /// the signer has already signed in the block's tenure.
+    pub fn check_block(&self, block: &NakamotoBlock) -> Option<SigningConflict> {
+        let block_height = block.header.chain_length;
+        if block_height < self.block_height {
+            return Some(SigningConflict::SignedHigherBlock {
+                consensus_hash: self.consensus_hash,
+                signed_height: self.block_height,
+            });
+        }
+        if block_height == self.block_height
+            && !self.globally_rejected
+            && block.header.signer_signature_hash() != self.signer_signature_hash
+        {
+            return Some(SigningConflict::Equivocation {
+                consensus_hash: self.consensus_hash,
+                signed_height: self.block_height,
+                signed_signer_signature_hash: self.signer_signature_hash,
+            });
+        }
+        if let (Some(signed_parent), Some(proposed_parent)) = (
+            self.parent_consensus_hash.as_ref(),
+            block_found_parent_tenure(block),
+        ) {
+            if signed_parent != &proposed_parent {
+                return Some(SigningConflict::ConflictingParentTenure {
+                    consensus_hash: self.consensus_hash,
+                    signed_parent_consensus_hash: *signed_parent,
+                    proposed_parent_consensus_hash: proposed_parent,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// The reason a block cannot be signed without risking equivocation
+#[derive(Debug, Clone, PartialEq)]
+pub enum SigningConflict {
+    /// The signer already signed a higher block in this tenure
+    SignedHigherBlock {
+        /// The tenure in question
+        consensus_hash: ConsensusHash,
+        /// The height the signer has already signed
+        signed_height: u64,
+    },
+    /// The signer already signed a different block at this height in this tenure
+    Equivocation {
+        /// The tenure in question
+        consensus_hash: ConsensusHash,
+        /// The height the signer has already signed
+        signed_height: u64,
+        /// The signer signature hash of the block that was signed
+        signed_signer_signature_hash: Sha512Trunc256Sum,
+    },
+    /// The signer already signed a tenure change for this tenure that builds
+    /// upon a different parent tenure
+    ConflictingParentTenure {
+        /// The tenure in question
+        consensus_hash: ConsensusHash,
+        /// The parent tenure of the tenure change that was signed
+        signed_parent_consensus_hash: ConsensusHash,
+        /// The parent tenure of the proposed tenure change
+        proposed_parent_consensus_hash: ConsensusHash,
+    },
+}
+
+impl Display for SigningConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SignedHigherBlock {
+                consensus_hash,
+                signed_height,
+            } => write!(
+                f,
+                "already signed block at height {signed_height} in tenure {consensus_hash}"
+            ),
+            Self::Equivocation {
+                consensus_hash,
+                signed_height,
+                signed_signer_signature_hash,
+            } => write!(
+                f,
+                "already signed block {signed_signer_signature_hash} at height {signed_height} in tenure {consensus_hash}"
+            ),
+            Self::ConflictingParentTenure {
+                consensus_hash,
+                signed_parent_consensus_hash,
+                proposed_parent_consensus_hash,
+            } => write!(
+                f,
+                "tenure {consensus_hash} was signed as building on {signed_parent_consensus_hash}, not {proposed_parent_consensus_hash}"
+            ),
+        }
+    }
+}
+
+/// The interchange format used to move a signing journal between hosts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SigningJournalExport {
+    /// The version of the interchange format
+    pub version: u32,
+    /// The public key of the signer that produced the journal, hex-encoded
+    pub signer_public_key: String,
+    /// The journal entries
+    pub entries: Vec<SigningJournalEntry>,
+}
+
+/// If `block` starts a new tenure (i.e., contains a `BlockFound` tenure change),
+/// return the consensus hash of the tenure it builds upon.
+fn block_found_parent_tenure(block: &NakamotoBlock) -> Option<ConsensusHash> {
+    block.txs.first().and_then(|tx| match &tx.payload {
+        TransactionPayload::TenureChange(tc) if tc.cause == TenureChangeCause::BlockFound => {
+            Some(tc.prev_tenure_consensus_hash)
+        }
+        _ => None,
+    })
+}
+
 /// This struct manages a SQLite database connection
 /// for the signer.
 #[derive(Debug)]
@@ -502,6 +644,22 @@ CREATE TABLE IF NOT EXISTS tenure_activity (
     last_activity_time INTEGER NOT NULL
 ) STRICT;"#;
 
+static CREATE_SIGNING_JOURNAL_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS signing_journal (
+    -- the tenure in which this signer has signed a block
+    consensus_hash TEXT NOT NULL PRIMARY KEY,
+    -- the tenure that this tenure builds upon, if the BlockFound block was signed
+    parent_consensus_hash TEXT,
+    -- the highest block height signed in this tenure
+    block_height INTEGER NOT NULL,
+    -- the signer signature hash of the highest block signed in this tenure
+    signer_signature_hash TEXT NOT NULL,
+    -- whether that block was globally rejected
+    globally_rejected INTEGER NOT NULL,
+    -- the time at which this entry was last updated
+    signed_time INTEGER NOT NULL
+) STRICT;"#;
+
 static ADD_REJECT_CODE: &str = r#"
 ALTER TABLE block_rejection_signer_addrs
     ADD COLUMN reject_code INTEGER;
@@ -576,9 +734,14 @@ static SCHEMA_9: &[&str] = &[
     "INSERT INTO db_config (version) VALUES (9);",
 ];
 
+static SCHEMA_10: &[&str] = &[
+    CREATE_SIGNING_JOURNAL_TABLE,
+    "INSERT INTO db_config (version) VALUES (10);",
+];
+
 impl SignerDb {
     /// The current schema version used in this build of the signer binary.
-    pub const SCHEMA_VERSION: u32 = 9;
+    pub const SCHEMA_VERSION: u32 = 10;
 
     /// Create a new `SignerState` instance.
     /// This will create a new SQLite database at the given path
@@ -734,6 +897,20 @@ impl SignerDb {
         Ok(())
     }
 
+    /// Migrate from schema 9 to schema 10
+    fn schema_10_migration(tx: &Transaction) -> Result<(), DBError> {
+        if Self::get_schema_version(tx)? >= 10 {
+            // no migration necessary
+            return Ok(());
+        }
+
+        for statement in SCHEMA_10.iter() {
+            tx.execute_batch(statement)?;
+        }
+
+        Ok(())
+    }
+
     /// Register custom scalar functions used by the database
     fn register_scalar_functions(&self) -> Result<(), DBError> {
         // Register helper function for determining if a block is a tenure change transaction
@@ -776,7 +953,8 @@ impl SignerDb {
                 6 => Self::schema_7_migration(&sql_tx)?,
                 7 => Self::schema_8_migration(&sql_tx)?,
                 8 => Self::schema_9_migration(&sql_tx)?,
-                9 => break,
+                9 => Self::schema_10_migration(&sql_tx)?,
+                10 => break,
                 x => return Err(DBError::Other(format!(
                     "Database schema is newer than supported by this binary. Expected version = {}, Database version = {x}",
                     Self::SCHEMA_VERSION,
@@ -1237,11 +1415,20 @@ impl SignerDb {
 
     /// Mark a block as globally rejected. This removes the block from the pending
     /// validations table. This does **not** update the block's state in SignerDb.
+    /// If this block is the one recorded in the signing journal for its tenure, the
+    /// journal is updated so that a different block may be signed at its height.
     pub fn mark_block_globally_rejected(&self, block_info: &mut BlockInfo) -> Result<(), DBError> {
         block_info
             .mark_globally_rejected()
             .map_err(DBError::Other)?;
         self.remove_pending_block_validation(&block_info.signer_signature_hash())?;
+        self.db.execute(
+            "UPDATE signing_journal SET globally_rejected = 1 WHERE consensus_hash = ?1 AND signer_signature_hash = ?2",
+            params![
+                block_info.block.header.consensus_hash,
+                block_info.signer_signature_hash()
+            ],
+        )?;
         Ok(())
     }
     /// Update the tenure (identified by consensus_hash) last activity timestamp
@@ -1268,6 +1455,158 @@ impl SignerDb {
         })?;
         Ok(Some(last_activity_time))
     }
+
+    /// Get the signing journal entry for a tenure (identified by consensus_hash)
+    pub fn get_signing_journal_entry(
+        &self,
+        tenure: &ConsensusHash,
+    ) -> Result<Option<SigningJournalEntry>, DBError> {
+        Self::get_signing_journal_entry_inner(&self.db, tenure)
+    }
+
+    fn get_signing_journal_entry_inner(
+        conn: &Connection,
+        tenure: &ConsensusHash,
+    ) -> Result<Option<SigningJournalEntry>, DBError> {
+        let query = "SELECT consensus_hash, parent_consensus_hash, block_height, signer_signature_hash, globally_rejected, signed_time FROM signing_journal WHERE consensus_hash = ?1";
+        query_row(conn, query, params![tenure])
+    }
+
+    fn put_signing_journal_entry(
+        conn: &Connection,
+        entry: &SigningJournalEntry,
+    ) -> Result<(), DBError> {
+        conn.execute(
+            "INSERT OR REPLACE INTO signing_journal (consensus_hash, parent_consensus_hash, block_height, signer_signature_hash, globally_rejected, signed_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                entry.consensus_hash,
+                entry.parent_consensus_hash,
+                u64_to_sql(entry.block_height)?,
+                entry.signer_signature_hash,
+                entry.globally_rejected,
+                u64_to_sql(entry.signed_time)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Check whether signing `block` would conflict with a block this signer
+    /// has already signed, according to the signing journal.
+    pub fn check_signing_journal(
+        &self,
+        block: &NakamotoBlock,
+    ) -> Result<Option<SigningConflict>, DBError> {
+        Ok(self
+            .get_signing_journal_entry(&block.header.consensus_hash)?
+            .and_then(|entry| entry.check_block(block)))
+    }
+
+    /// Atomically check `block` against the signing journal and, if it does not
+    /// conflict, record it as signed. This must succeed before any signature over
+    /// the block is released. If the block conflicts, nothing is written and the
+    /// conflict is returned.
+    pub fn record_signed_block(
+        &mut self,
+        block: &NakamotoBlock,
+    ) -> Result<Option<SigningConflict>, DBError> {
+        let tx = tx_begin_immediate(&mut self.db)?;
+        let existing = Self::get_signing_journal_entry_inner(&tx, &block.header.consensus_hash)?;
+        if let Some(conflict) = existing.as_ref().and_then(|entry| entry.check_block(block)) {
+            return Ok(Some(conflict));
+        }
+        let entry = SigningJournalEntry {
+            consensus_hash: block.header.consensus_hash,
+            parent_consensus_hash: block_found_parent_tenure(block)
+                .or(existing.and_then(|entry| entry.parent_consensus_hash)),
+            block_height: block.header.chain_length,
+            signer_signature_hash: block.header.signer_signature_hash(),
+            globally_rejected: false,
+            signed_time: get_epoch_time_secs(),
+        };
+        debug!("Recording signed block in signing journal";
+            "consensus_hash" => %entry.consensus_hash,
+            "block_height" => entry.block_height,
+            "signer_signature_hash" => %entry.signer_signature_hash,
+        );
+        Self::put_signing_journal_entry(&tx, &entry)?;
+        tx.commit()?;
+        Ok(None)
+    }
+
+    /// Return every entry in the signing journal, ordered by block height
+    pub fn export_signing_journal(&self) -> Result<Vec<SigningJournalEntry>, DBError> {
+        let query = "SELECT consensus_hash, parent_consensus_hash, block_height, signer_signature_hash, globally_rejected, signed_time FROM signing_journal ORDER BY block_height ASC";
+        query_rows(&self.db, query, params![])
+    }
+
+    /// Merge signing journal entries (e.g., exported from another host) into this
+    /// signing journal. The recorded height of a tenure is never lowered. If the
+    /// imported entries contradict this journal (a different block at the same
+    /// height that was not globally rejected, or a different parent tenure),
+    /// nothing is imported and an error is returned, since one of the two signers
+    /// has already equivocated.
+    /// Returns the number of entries inserted or updated.
+    pub fn import_signing_journal(
+        &mut self,
+        entries: &[SigningJournalEntry],
+    ) -> Result<usize, DBError> {
+        let tx = tx_begin_immediate(&mut self.db)?;
+        let mut updated = 0;
+        for imported in entries {
+            let Some(existing) =
+                Self::get_signing_journal_entry_inner(&tx, &imported.consensus_hash)?
+            else {
+                Self::put_signing_journal_entry(&tx, imported)?;
+                updated += 1;
+                continue;
+            };
+            if existing.block_height == imported.block_height
+                && existing.signer_signature_hash != imported.signer_signature_hash
+                && !existing.globally_rejected
+                && !imported.globally_rejected
+            {
+                return Err(DBError::Other(format!(
+                    "Conflicting signed blocks at height {} in tenure {}: {} != {}",
+                    existing.block_height,
+                    existing.consensus_hash,
+                    existing.signer_signature_hash,
+                    imported.signer_signature_hash
+                )));
+            }
+            if let (Some(existing_parent), Some(imported_parent)) = (
+                existing.parent_consensus_hash,
+                imported.parent_consensus_hash,
+            ) {
+                if existing_parent != imported_parent {
+                    return Err(DBError::Other(format!(
+                        "Conflicting parent tenures for tenure {}: {existing_parent} != {imported_parent}",
+                        existing.consensus_hash
+                    )));
+                }
+            }
+            let mut merged = if imported.block_height > existing.block_height
+                || (imported.block_height == existing.block_height && existing.globally_rejected)
+            {
+                imported.clone()
+            } else {
+                existing.clone()
+            };
+            if merged.block_height == imported.block_height
+                && merged.signer_signature_hash == imported.signer_signature_hash
+            {
+                merged.globally_rejected |= imported.globally_rejected;
+            }
+            merged.parent_consensus_hash = existing
+                .parent_consensus_hash
+                .or(imported.parent_consensus_hash);
+            if merged != existing {
+                Self::put_signing_journal_entry(&tx, &merged)?;
+                updated += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
 }
 
 fn try_deserialize<T>(s: Option<String>) -> Result<Option<T>, DBError>
@@ -1280,6 +1619,19 @@ where
         .map_err(DBError::SerializationError)
 }
 
+impl FromRow<SigningJournalEntry> for SigningJournalEntry {
+    fn from_row(row: &rusqlite::Row) -> Result<Self, DBError> {
+        Ok(SigningJournalEntry {
+            consensus_hash: ConsensusHash::from_column(row, "consensus_hash")?,
+            parent_consensus_hash: row.get("parent_consensus_hash")?,
+            block_height: u64::from_column(row, "block_height")?,
+            signer_signature_hash: Sha512Trunc256Sum::from_column(row, "signer_signature_hash")?,
+            globally_rejected: row.get("globally_rejected")?,
+            signed_time: u64::from_column(row, "signed_time")?,
+        })
+    }
+}
+
 /// For tests, a struct to represent a pending block validation
 #[cfg(any(test, feature = "testing"))]
 pub struct PendingBlockValidation {
@@ -2193,4 +2545,219 @@ mod tests {
             .unwrap()
             .is_none());
     }
+
+    fn journal_block(consensus_hash: ConsensusHash, height: u64, timestamp: u64) -> NakamotoBlock {
+        let (_, block_proposal) = create_block_override(|b| {
+            b.block.header.consensus_hash = consensus_hash;
+            b.block.header.chain_length = height;
+            b.block.header.timestamp = timestamp;
+        });
+        block_proposal.block
+    }
+
+    fn journal_tenure_start_block(
+        consensus_hash: ConsensusHash,
+        parent_consensus_hash: ConsensusHash,
+        height: u64,
+    ) -> NakamotoBlock {
+        let tenure_change_payload = TenureChangePayload {
+            tenure_consensus_hash: consensus_hash,
+            prev_tenure_consensus_hash: parent_consensus_hash,
+            burn_view_consensus_hash: consensus_hash,
+            previous_tenure_end: StacksBlockId([0x03; 32]),
+            previous_tenure_blocks: 1,
+            cause: TenureChangeCause::BlockFound,
+            pubkey_hash: Hash160([0x00; 20]),
+        };
+        let tenure_change_tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&StacksPrivateKey::random()).unwrap(),
+            TransactionPayload::TenureChange(tenure_change_payload),
+        );
+        let mut block = journal_block(consensus_hash, height, 0);
+        block.txs.push(tenure_change_tx);
+        block
+    }
+
+    #[test]
+    fn signing_journal_prevents_equivocation() {
+        let db_path = tmp_db_path();
+        let mut db = SignerDb::new(db_path).expect("Failed to create signer db");
+        let consensus_hash = ConsensusHash([0x01; 20]);
+
+        let block_5 = journal_block(consensus_hash, 5, 1);
+        let block_5_conflict = journal_block(consensus_hash, 5, 2);
+        let block_4 = journal_block(consensus_hash, 4, 3);
+        let block_6 = journal_block(consensus_hash, 6, 4);
+        let other_tenure_block = journal_block(ConsensusHash([0x02; 20]), 4, 5);
+
+        assert_eq!(db.check_signing_journal(&block_5).unwrap(), None);
+        assert_eq!(db.record_signed_block(&block_5).unwrap(), None);
+        // Re-signing the same block is fine
+        assert_eq!(db.record_signed_block(&block_5).unwrap(), None);
+
+        let conflict = db.record_signed_block(&block_5_conflict).unwrap();
+        assert_eq!(
+            conflict,
+            Some(SigningConflict::Equivocation {
+                consensus_hash,
+                signed_height: 5,
+                signed_signer_signature_hash: block_5.header.signer_signature_hash(),
+            })
+        );
+        assert_eq!(
+            db.check_signing_journal(&block_5_conflict).unwrap(),
+            conflict
+        );
+
+        assert_eq!(
+            db.record_signed_block(&block_4).unwrap(),
+            Some(SigningConflict::SignedHigherBlock {
+                consensus_hash,
+                signed_height: 5,
+            })
+        );
+
+        // A conflicting block is never written to the journal
+        let entry = db
+            .get_signing_journal_entry(&consensus_hash)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.block_height, 5);
+        assert_eq!(
+            entry.signer_signature_hash,
+            block_5.header.signer_signature_hash()
+        );
+
+        // Once the signed block is globally rejected, another block at its height may be signed
+        let mut block_info_5 = BlockInfo::from(BlockProposal {
+            block: block_5.clone(),
+            burn_height: 7,
+            reward_cycle: 42,
+            block_proposal_data: BlockProposalData::empty(),
+        });
+        db.mark_block_globally_rejected(&mut block_info_5).unwrap();
+        assert_eq!(db.record_signed_block(&block_5_conflict).unwrap(), None);
+        assert_eq!(
+            db.record_signed_block(&block_5).unwrap(),
+            Some(SigningConflict::Equivocation {
+                consensus_hash,
+                signed_height: 5,
+                signed_signer_signature_hash: block_5_conflict.header.signer_signature_hash(),
+            })
+        );
+
+        assert_eq!(db.record_signed_block(&block_6).unwrap(), None);
+        assert_eq!(db.record_signed_block(&other_tenure_block).unwrap(), None);
+        let entry = db
+            .get_signing_journal_entry(&consensus_hash)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.block_height, 6);
+    }
+
+    #[test]
+    fn signing_journal_tracks_parent_tenure() {
+        let db_path = tmp_db_path();
+        let mut db = SignerDb::new(db_path).expect("Failed to create signer db");
+        let consensus_hash = ConsensusHash([0x03; 20]);
+        let parent_1 = ConsensusHash([0x01; 20]);
+        let parent_2 = ConsensusHash([0x02; 20]);
+
+        let tenure_start = journal_tenure_start_block(consensus_hash, parent_1, 10);
+        assert_eq!(db.record_signed_block(&tenure_start).unwrap(), None);
+
+        // The parent tenure survives subsequent blocks in the tenure
+        assert_eq!(
+            db.record_signed_block(&journal_block(consensus_hash, 11, 0))
+                .unwrap(),
+            None
+        );
+        let entry = db
+            .get_signing_journal_entry(&consensus_hash)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.parent_consensus_hash, Some(parent_1));
+
+        let conflicting_start = journal_tenure_start_block(consensus_hash, parent_2, 12);
+        assert_eq!(
+            db.check_signing_journal(&conflicting_start).unwrap(),
+            Some(SigningConflict::ConflictingParentTenure {
+                consensus_hash,
+                signed_parent_consensus_hash: parent_1,
+                proposed_parent_consensus_hash: parent_2,
+            })
+        );
+    }
+
+    #[test]
+    fn signing_journal_export_import() {
+        let mut db_1 = SignerDb::new(tmp_db_path()).expect("Failed to create signer db");
+        let mut db_2 = SignerDb::new(tmp_db_path()).expect("Failed to create signer db");
+        let consensus_hash_1 = ConsensusHash([0x01; 20]);
+        let consensus_hash_2 = ConsensusHash([0x02; 20]);
+
+        db_1.record_signed_block(&journal_tenure_start_block(
+            consensus_hash_1,
+            ConsensusHash([0x00; 20]),
+            1,
+        ))
+        .unwrap();
+        db_1.record_signed_block(&journal_block(consensus_hash_1, 3, 0))
+            .unwrap();
+        db_1.record_signed_block(&journal_block(consensus_hash_2, 4, 0))
+            .unwrap();
+        // db_2 has already signed further in tenure 2
+        db_2.record_signed_block(&journal_block(consensus_hash_2, 5, 0))
+            .unwrap();
+
+        let exported = db_1.export_signing_journal().unwrap();
+        assert_eq!(exported.len(), 2);
+        let json = serde_json::to_string(&SigningJournalExport {
+            version: SIGNING_JOURNAL_FORMAT_VERSION,
+            signer_public_key: "00".into(),
+            entries: exported.clone(),
+        })
+        .unwrap();
+        let parsed: SigningJournalExport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.entries, exported);
+
+        assert_eq!(db_2.import_signing_journal(&parsed.entries).unwrap(), 1);
+        // Importing again changes nothing
+        assert_eq!(db_2.import_signing_journal(&parsed.entries).unwrap(), 0);
+
+        assert_eq!(
+            db_2.get_signing_journal_entry(&consensus_hash_1).unwrap(),
+            db_1.get_signing_journal_entry(&consensus_hash_1).unwrap()
+        );
+        // The imported lower height did not overwrite the local one
+        assert_eq!(
+            db_2.get_signing_journal_entry(&consensus_hash_2)
+                .unwrap()
+                .unwrap()
+                .block_height,
+            5
+        );
+        assert!(db_2
+            .record_signed_block(&journal_block(consensus_hash_1, 2, 0))
+            .unwrap()
+            .is_some());
+
+        // An import that contradicts the local journal is rejected entirely
+        let mut conflicting = exported;
+        conflicting[1].block_height = 5;
+        conflicting.push(SigningJournalEntry {
+            consensus_hash: ConsensusHash([0x09; 20]),
+            parent_consensus_hash: None,
+            block_height: 9,
+            signer_signature_hash: Sha512Trunc256Sum([0x09; 32]),
+            globally_rejected: false,
+            signed_time: 0,
+        });
+        assert!(db_2.import_signing_journal(&conflicting).is_err());
+        assert!(db_2
+            .get_signing_journal_entry(&ConsensusHash([0x09; 20]))
+            .unwrap()
+            .is_none());
+    }
 }
@@ -15,6 +15,7 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use blockstack_lib::chainstate::nakamoto::{NakamotoBlock, NakamotoBlockHeader};
@@ -22,8 +23,7 @@ use blockstack_lib::net::api::postblock_proposal::{
     BlockValidateOk, BlockValidateReject, BlockValidateResponse, TOO_MANY_REQUESTS_STATUS,
 };
 use blockstack_lib::util_lib::db::Error as DBError;
-use clarity::types::chainstate::StacksPrivateKey;
-use clarity::types::{PrivateKey, StacksEpochId};
+use clarity::types::StacksEpochId;
 use clarity::util::hash::{MerkleHashFunc, Sha512Trunc256Sum};
 use clarity::util::secp256k1::Secp256k1PublicKey;
 use libsigner::v0::messages::{
@@ -40,8 +40,9 @@ use stacks_common::{debug, error, info, warn};
 use crate::chainstate::{ProposalEvalConfig, SortitionMinerStatus, SortitionsView};
 use crate::client::{ClientError, SignerSlotID, StackerDB, StacksClient};
 use crate::config::{SignerConfig, SignerConfigMode};
+use crate::key_provider::{SignerKeyProvider, SigningContext};
 use crate::runloop::SignerResult;
-use crate::signerdb::{BlockInfo, BlockState, SignerDb};
+use crate::signerdb::{BlockInfo, BlockState, SignerDb, SigningConflict};
 use crate::Signer as SignerTrait;
 
 /// Signer running mode (whether dry-run or real)
@@ -61,12 +62,12 @@ pub enum SignerMode {
 /// The stacks signer registered for the reward cycle
 #[derive(Debug)]
 pub struct Signer {
-    /// The private key of the signer
+    /// The key provider which signs for the signer
     #[cfg(any(test, feature = "testing"))]
-    pub private_key: StacksPrivateKey,
+    pub key_provider: Arc<dyn SignerKeyProvider>,
     #[cfg(not(any(test, feature = "testing")))]
-    /// The private key of the signer
-    private_key: StacksPrivateKey,
+    /// The key provider which signs for the signer
+    key_provider: Arc<dyn SignerKeyProvider>,
     /// The stackerdb client
     pub stackerdb: StackerDB<MessageSlotID>,
     /// Whether the signer is a mainnet signer or not
@@ -326,7 +327,7 @@ impl From<SignerConfig> for Signer {
         let proposal_config = ProposalEvalConfig::from(&signer_config);
 
         Self {
-            private_key: signer_config.stacks_private_key,
+            key_provider: signer_config.key_provider,
             stackerdb,
             mainnet: signer_config.mainnet,
             mode,
@@ -349,23 +350,52 @@ impl Signer {
     /// Returns None otherwise
     fn determine_response(&mut self, block_info: &BlockInfo) -> Option<BlockResponse> {
         let valid = block_info.valid?;
-        let response = if valid {
+        if valid {
             debug!("{self}: Accepting block {}", block_info.block.block_id());
             self.create_block_acceptance(&block_info.block)
         } else {
             debug!("{self}: Rejecting block {}", block_info.block.block_id());
             self.create_block_rejection(RejectReason::RejectedInPriorRound, &block_info.block)
-        };
-        Some(response)
+        }
     }
 
-    /// Create a block acceptance response for a block
-    pub fn create_block_acceptance(&self, block: &NakamotoBlock) -> BlockResponse {
+    /// Create a block acceptance response for a block.
+    /// The block is first recorded in the signing journal, so that no signature is
+    /// ever produced for a block that conflicts with one this signer already signed.
+    /// Returns None if the block conflicts with the signing journal or if the key
+    /// provider would not sign it.
+    pub fn create_block_acceptance(&mut self, block: &NakamotoBlock) -> Option<BlockResponse> {
+        match self.signer_db.record_signed_block(block) {
+            Ok(None) => {}
+            Ok(Some(conflict)) => {
+                warn!("{self}: Refusing to sign block that conflicts with the signing journal: {conflict}";
+                    "signer_sighash" => %block.header.signer_signature_hash(),
+                    "block_id" => %block.block_id(),
+                );
+                return None;
+            }
+            Err(e) => {
+                error!("{self}: Failed to record block in the signing journal: {e:?}";
+                    "signer_sighash" => %block.header.signer_signature_hash(),
+                    "block_id" => %block.block_id(),
+                );
+                return None;
+            }
+        }
         let signature = self
-            .private_key
-            .sign(block.header.signer_signature_hash().bits())
-            .expect("Failed to sign block");
-        BlockResponse::accepted(
+            .key_provider
+            .sign(
+                block.header.signer_signature_hash().bits(),
+                &SigningContext::block_acceptance(block),
+            )
+            .inspect_err(|e| {
+                error!("{self}: Failed to sign block acceptance: {e}";
+                    "signer_sighash" => %block.header.signer_signature_hash(),
+                    "block_id" => %block.block_id(),
+                )
+            })
+            .ok()?;
+        Some(BlockResponse::accepted(
             block.header.signer_signature_hash(),
             signature,
             self.signer_db.calculate_tenure_extend_timestamp(
@@ -375,18 +405,23 @@ impl Signer {
                 block,
                 true,
             ),
-        )
+        ))
     }
-    /// Create a block rejection response for a block with the given reject code
+
+    /// Create a block rejection response for a block with the given reject code.
+    /// Returns None if the key provider would not sign it.
     pub fn create_block_rejection(
         &self,
         reject_reason: RejectReason,
         block: &NakamotoBlock,
-    ) -> BlockResponse {
-        BlockResponse::rejected(
+    ) -> Option<BlockResponse> {
+        let context = SigningContext::BlockRejection {
+            signer_signature_hash: block.header.signer_signature_hash(),
+            reason: reject_reason.to_string(),
+        };
+        BlockRejection::new_with_signer(
             block.header.signer_signature_hash(),
             reject_reason,
-            &self.private_key,
             self.mainnet,
             self.signer_db.calculate_tenure_extend_timestamp(
                 self.proposal_config
@@ -395,8 +430,51 @@ impl Signer {
                 block,
                 false,
             ),
+            |signature_hash| {
+                self.key_provider
+                    .sign(signature_hash.as_bytes(), &context)
+                    .map_err(|e| e.to_string())
+            },
         )
+        .inspect_err(|e| {
+            error!("{self}: Failed to sign block rejection: {e}";
+                "signer_sighash" => %block.header.signer_signature_hash(),
+                "block_id" => %block.block_id(),
+            )
+        })
+        .ok()
+        .map(BlockResponse::Rejected)
+    }
+    /// Check if block should be rejected because signing it could conflict with a block
+    /// this signer has already signed, according to the signing journal.
+    /// Will return a BlockResponse::Rejection if the block conflicts, none otherwise.
+    fn check_block_against_signing_journal(&self, block: &NakamotoBlock) -> Option<BlockResponse> {
+        let conflict = match self.signer_db.check_signing_journal(block) {
+            Ok(conflict) => conflict?,
+            Err(e) => {
+                warn!("{self}: Failed to check the signing journal: {e:?}";
+                    "signer_sighash" => %block.header.signer_signature_hash(),
+                    "block_id" => %block.block_id(),
+                );
+                return self.create_block_rejection(
+                    RejectReason::ConnectivityIssues("error checking signing journal".to_string()),
+                    block,
+                );
+            }
+        };
+        warn!("{self}: Block proposal conflicts with the signing journal: {conflict}";
+            "signer_sighash" => %block.header.signer_signature_hash(),
+            "block_id" => %block.block_id(),
+        );
+        let reject_reason = match conflict {
+            SigningConflict::ConflictingParentTenure { .. } => RejectReason::DuplicateBlockFound,
+            SigningConflict::SignedHigherBlock { .. } | SigningConflict::Equivocation { .. } => {
+                RejectReason::SortitionViewMismatch
+            }
+        };
+        self.create_block_rejection(reject_reason, block)
     }
+
     /// Check if block should be rejected based on sortition state
     /// Will return a BlockResponse::Rejection if the block is invalid, none otherwise.
     fn check_block_against_sortition_state(
@@ -438,7 +516,7 @@ impl Signer {
                         "signer_sighash" => %signer_signature_hash,
                         "block_id" => %block_id,
                     );
-                    Some(self.create_block_rejection(RejectReason::ConnectivityIssues(e), block))
+                    self.create_block_rejection(RejectReason::ConnectivityIssues(e), block)
                 }
                 // Block proposal is bad
                 Err(reject_code) => {
@@ -449,7 +527,7 @@ impl Signer {
                         "reject_reason" => %reject_code,
                         "reject_code" => ?reject_code,
                     );
-                    Some(self.create_block_rejection(reject_code, block))
+                    self.create_block_rejection(reject_code, block)
                 }
                 // Block proposal passed check, still don't know if valid
                 Ok(_) => None,
@@ -460,7 +538,7 @@ impl Signer {
                 "signer_sighash" => %signer_signature_hash,
                 "block_id" => %block_id,
             );
-            Some(self.create_block_rejection(RejectReason::NoSortitionView, block))
+            self.create_block_rejection(RejectReason::NoSortitionView, block)
         }
     }
 
@@ -561,12 +639,14 @@ impl Signer {
         }
 
         // Check if proposal can be rejected now if not valid against sortition view
-        let block_response = self.check_block_against_sortition_state(
-            stacks_client,
-            sortition_state,
-            &block_proposal.block,
-            miner_pubkey,
-        );
+        let block_response = self
+            .check_block_against_sortition_state(
+                stacks_client,
+                sortition_state,
+                &block_proposal.block,
+                miner_pubkey,
+            )
+            .or_else(|| self.check_block_against_signing_journal(&block_proposal.block));
 
         #[cfg(any(test, feature = "testing"))]
         let block_response =
@@ -653,6 +733,10 @@ impl Signer {
     ) -> Option<BlockResponse> {
         let signer_signature_hash = proposed_block.header.signer_signature_hash();
         let proposed_block_consensus_hash = proposed_block.header.consensus_hash;
+        // Something may have been signed since the proposal was first checked against the signing journal.
+        if let Some(block_response) = self.check_block_against_signing_journal(proposed_block) {
+            return Some(block_response);
+        }
         // If this is a tenure change block, ensure that it confirms the correct number of blocks from the parent tenure.
         if let Some(tenure_change) = proposed_block.get_tenure_change_tx_payload() {
             // Ensure that the tenure change block confirms the expected parent block
@@ -666,22 +750,22 @@ impl Signer {
             ) {
                 Ok(true) => {}
                 Ok(false) => {
-                    return Some(self.create_block_rejection(
+                    return self.create_block_rejection(
                         RejectReason::SortitionViewMismatch,
                         proposed_block,
-                    ))
+                    )
                 }
                 Err(e) => {
                     warn!("{self}: Error checking block proposal: {e}";
                         "signer_sighash" => %signer_signature_hash,
                         "block_id" => %proposed_block.block_id()
                     );
-                    return Some(self.create_block_rejection(
+                    return self.create_block_rejection(
                         RejectReason::ConnectivityIssues(
                             "error checking block proposal".to_string(),
                         ),
                         proposed_block,
-                    ));
+                    );
                 }
             }
         }
@@ -700,10 +784,10 @@ impl Signer {
                         "proposed_chain_length" => proposed_block.header.chain_length,
                         "expected_at_least" => last_block_info.block.header.chain_length + 1,
                     );
-                    return Some(self.create_block_rejection(
+                    return self.create_block_rejection(
                         RejectReason::SortitionViewMismatch,
                         proposed_block,
-                    ));
+                    );
                 }
             }
             Ok(_) => {}
@@ -712,12 +796,12 @@ impl Signer {
                     "signer_sighash" => %signer_signature_hash,
                     "block_id" => %proposed_block.block_id()
                 );
-                return Some(self.create_block_rejection(
+                return self.create_block_rejection(
                     RejectReason::ConnectivityIssues(
                         "failed to check block against signer db".to_string(),
                     ),
                     proposed_block,
-                ));
+                );
             }
         }
         None
@@ -795,7 +879,7 @@ impl Signer {
             self.signer_db
                 .insert_block(&block_info)
                 .unwrap_or_else(|e| self.handle_insert_block_error(e));
-            let block_response = self.create_block_acceptance(&block_info.block);
+            let block_response = self.create_block_acceptance(&block_info.block)?;
             // have to save the signature _after_ the block info
             self.handle_block_signature(stacks_client, block_response.as_block_accepted()?);
             Some(block_response)
@@ -832,9 +916,12 @@ impl Signer {
                 return None;
             }
         }
-        let block_rejection = BlockRejection::from_validate_rejection(
+        let context = SigningContext::BlockRejection {
+            signer_signature_hash,
+            reason: block_validate_reject.reason.clone(),
+        };
+        let block_rejection = BlockRejection::from_validate_rejection_with_signer(
             block_validate_reject.clone(),
-            &self.private_key,
             self.mainnet,
             self.signer_db.calculate_tenure_extend_timestamp(
                 self.proposal_config
@@ -843,10 +930,22 @@ impl Signer {
                 &block_info.block,
                 false,
             ),
-        );
+            |signature_hash| {
+                self.key_provider
+                    .sign(signature_hash.as_bytes(), &context)
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .inspect_err(|e| {
+            error!("{self}: Failed to sign block rejection: {e}";
+                "signer_sighash" => %signer_signature_hash,
+            )
+        })
+        .ok();
         self.signer_db
             .insert_block(&block_info)
             .unwrap_or_else(|e| self.handle_insert_block_error(e));
+        let block_rejection = block_rejection?;
         self.handle_block_rejection(&block_rejection, sortition_state);
         Some(BlockResponse::Rejected(block_rejection))
     }
@@ -978,20 +1077,22 @@ impl Signer {
                 warn!("{self}: Failed to mark block as locally rejected: {e:?}");
             }
         };
-        debug!("{self}: Broadcasting a block response to stacks node: {rejection:?}");
-        let res = self
-            .stackerdb
-            .send_message_with_retry::<SignerMessage>(rejection.into());
+        if let Some(rejection) = rejection {
+            debug!("{self}: Broadcasting a block response to stacks node: {rejection:?}");
+            let res = self
+                .stackerdb
+                .send_message_with_retry::<SignerMessage>(rejection.into());
 
-        crate::monitoring::actions::record_block_response_latency(&block_info.block);
+            crate::monitoring::actions::record_block_response_latency(&block_info.block);
 
-        match res {
-            Err(e) => warn!("{self}: Failed to send block rejection to stacker-db: {e:?}"),
-            Ok(ack) if !ack.accepted => warn!(
-                "{self}: Block rejection not accepted by stacker-db: {:?}",
-                ack.reason
-            ),
-            Ok(_) => debug!("{self}: Block rejection accepted by stacker-db"),
+            match res {
+                Err(e) => warn!("{self}: Failed to send block rejection to stacker-db: {e:?}"),
+                Ok(ack) if !ack.accepted => warn!(
+                    "{self}: Block rejection not accepted by stacker-db: {:?}",
+                    ack.reason
+                ),
+                Ok(_) => debug!("{self}: Block rejection accepted by stacker-db"),
+            }
         }
         self.signer_db
             .insert_block(&block_info)
@@ -1330,7 +1431,21 @@ impl Signer {
     /// Send a mock signature to stackerdb to prove we are still alive
     fn mock_sign(&mut self, mock_proposal: MockProposal) {
         info!("{self}: Mock signing mock proposal: {mock_proposal:?}");
-        let mock_signature = MockSignature::new(mock_proposal, &self.private_key);
+        let context = SigningContext::MockSignature {
+            burn_block_height: mock_proposal.peer_info.burn_block_height,
+            stacks_tip_height: mock_proposal.peer_info.stacks_tip_height,
+        };
+        let mock_signature = match MockSignature::new_with_signer(mock_proposal, |signature_hash| {
+            self.key_provider
+                .sign(signature_hash.as_bytes(), &context)
+                .map_err(|e| e.to_string())
+        }) {
+            Ok(mock_signature) => mock_signature,
+            Err(e) => {
+                warn!("{self}: Failed to sign mock proposal: {e}");
+                return;
+            }
+        };
         let message = SignerMessage::MockSignature(mock_signature);
         if let Err(e) = self
             .stackerdb
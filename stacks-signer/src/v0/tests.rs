@@ -79,9 +79,7 @@ impl Signer {
         block_response: Option<BlockResponse>,
     ) -> Option<BlockResponse> {
         let public_keys = TEST_REJECT_ALL_BLOCK_PROPOSAL.get();
-        if public_keys.contains(
-            &stacks_common::types::chainstate::StacksPublicKey::from_private(&self.private_key),
-        ) {
+        if public_keys.contains(self.key_provider.public_key()) {
             warn!("{self}: Rejecting block proposal automatically due to testing directive";
                 "block_id" => %block_proposal.block.block_id(),
                 "height" => block_proposal.block.header.chain_length,
@@ -98,7 +96,7 @@ impl Signer {
             self.signer_db
                 .insert_block(block_info)
                 .unwrap_or_else(|e| self.handle_insert_block_error(e));
-            Some(self.create_block_rejection(RejectReason::TestingDirective, &block_proposal.block))
+            self.create_block_rejection(RejectReason::TestingDirective, &block_proposal.block)
         } else {
             block_response
         }
@@ -125,9 +123,7 @@ impl Signer {
     /// Ignore block proposals if the TEST_IGNORE_ALL_BLOCK_PROPOSALS flag is set for the signer's public key
     pub fn test_ignore_all_block_proposals(&self, block_proposal: &BlockProposal) -> bool {
         let public_keys = TEST_IGNORE_ALL_BLOCK_PROPOSALS.get();
-        if public_keys.contains(
-            &stacks_common::types::chainstate::StacksPublicKey::from_private(&self.private_key),
-        ) {
+        if public_keys.contains(self.key_provider.public_key()) {
             warn!("{self}: Ignoring block proposal due to testing directive";
                 "block_id" => %block_proposal.block.block_id(),
                 "height" => block_proposal.block.header.chain_length,
@@ -31,11 +31,15 @@ use blockstack_lib::burnchains::bitcoin::address::{
     ADDRESS_VERSION_MAINNET_SINGLESIG, ADDRESS_VERSION_TESTNET_SINGLESIG,
 };
 use blockstack_lib::burnchains::Address;
+use blockstack_lib::chainstate::stacks::envelope::{is_ordered, EnvelopeSigners, SigningEnvelope};
 use blockstack_lib::chainstate::stacks::{
-    StacksBlock, StacksBlockHeader, StacksMicroblock, StacksPrivateKey, StacksPublicKey,
-    StacksTransaction, StacksTransactionSigner, TokenTransferMemo, TransactionAnchorMode,
-    TransactionAuth, TransactionContractCall, TransactionPayload, TransactionSmartContract,
-    TransactionSpendingCondition, TransactionVersion, C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
+    AssetInfo, FungibleConditionCode, MultisigHashMode, NonfungibleConditionCode,
+    OrderIndependentMultisigHashMode, PostConditionPrincipal, SinglesigHashMode, StacksBlock,
+    StacksBlockHeader, StacksMicroblock, StacksPrivateKey, StacksPublicKey, StacksTransaction,
+    StacksTransactionSigner, TokenTransferMemo, TransactionAnchorMode, TransactionAuth,
+    TransactionContractCall, TransactionPayload, TransactionPostCondition,
+    TransactionPostConditionMode, TransactionSmartContract, TransactionSpendingCondition,
+    TransactionVersion, C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
     C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
 };
 use blockstack_lib::clarity_cli::vm_execute;
@@ -68,6 +72,7 @@ This CLI has these methods:
   decode-block       used to decode a hex-encoded Stacks block into a human-readable representation
   decode-microblock  used to decode a hex-encoded Stacks microblock into a human-readable representation
   decode-microblocks used to decode a hex-encoded stream of Stacks microblocks into a human-readable representation
+  envelope           used to create, sign, combine, inspect and finalize multisig and sponsored transactions offline
 
 For usage information on those methods, call `blockstack-cli [method] -h`
 
@@ -181,6 +186,64 @@ raw binary microblocks will be read from stdin.
 N.B. Stacks microblocks are not stored as files in the Stacks chainstate -- they are stored in
 block's sqlite database.";
 
+const ENVELOPE_USAGE: &str = "blockstack-cli (options) envelope [command] [args...]
+
+The envelope commands build a transaction that is signed offline by several parties, such as a
+multisig or sponsored transaction. An envelope is a JSON document holding the unsigned transaction,
+the public keys of its spending conditions, and the signatures collected so far. Each command that
+takes an [envelope] accepts a file name, or `-` to read the envelope from stdin.
+
+  envelope create (options) [origin-signer] [fee-rate] [nonce] [payload] [payload-args...]
+
+      Creates an unsigned envelope and outputs it. [origin-signer] describes the origin's spending
+      condition, as one of
+
+        p2pkh:[public-key-hex]
+        p2wpkh:[public-key-hex]
+        p2sh:[num-sigs]:[public-key-hex],[public-key-hex],...
+        p2wsh:[num-sigs]:[public-key-hex],[public-key-hex],...
+        order-independent-p2sh:[num-sigs]:[public-key-hex],[public-key-hex],...
+        order-independent-p2wsh:[num-sigs]:[public-key-hex],[public-key-hex],...
+
+      The keys of a p2sh or p2wsh condition must sign one after another, in the order listed. The
+      keys of an order-independent condition may sign in any order, including in parallel.
+
+      [payload] is one of the following, taking the same arguments as the command of that name:
+
+        token-transfer [recipient-address] [amount] [memo]
+        contract-call [contract-publisher-address] [contract-name] [function-name] [args...]
+        publish [contract-name] [file-name.clar]
+
+      Options must come before [origin-signer]:
+
+        --sponsored             the transaction's fee is paid by a sponsor, so [fee-rate] must be 0
+        --post-condition [hex]  adds a hex-serialized post-condition (may be repeated)
+        --post-condition-mode [allow|deny]
+                                sets the post-condition mode (default: deny)
+        --microblock-only       indicates to mine this transaction only in a microblock
+        --block-only            indicates to mine this transaction only in a block
+
+  envelope sign (--sponsor [sponsor-signer] [fee-rate] [nonce]) [envelope] [secret-key-hex]
+
+      Signs with the secret key of one of the origin's or sponsor's keys, and outputs the updated
+      envelope. The sponsor signs after the origin has all the signatures it needs. The first
+      sponsor key to sign sets the sponsor with `--sponsor`, where [sponsor-signer] has the same
+      format as [origin-signer], and [fee-rate] is the fee the sponsor pays.
+
+  envelope combine [envelope] [envelope...]
+
+      Merges the signatures in several envelopes for the same transaction, and outputs the result.
+
+  envelope inspect [envelope]
+
+      Outputs the transaction's payload, fee, post-conditions and signing progress as JSON, for
+      review before signing.
+
+  envelope finalize [envelope]
+
+      Outputs the hex string encoding of the signed transaction, once every signature is present.
+";
+
 #[derive(Debug)]
 enum CliError {
     ClarityRuntimeError(RuntimeErrorType),
@@ -367,6 +430,61 @@ fn parse_anchor_mode(
     }
 }
 
+fn parse_function_args(
+    val_args: &[String],
+    clarity_version: ClarityVersion,
+) -> Result<Vec<Value>, CliError> {
+    if val_args.len() % 2 != 0 {
+        return Err(
+            "contract-call arguments must be supplied as a list of `-e ...` or `-x 0000...` pairs"
+                .into(),
+        );
+    }
+
+    let mut arg_iterator = 0;
+    let mut values = Vec::new();
+    while arg_iterator < val_args.len() {
+        let eval_method = &val_args[arg_iterator];
+        let input = &val_args[arg_iterator + 1];
+        let value = match eval_method.as_str() {
+            "-x" => {
+                Value::try_deserialize_hex_untyped(input)?
+            },
+            "-e" => {
+                vm_execute(input, clarity_version)?
+                    .ok_or("Supplied argument did not evaluate to a Value")?
+            },
+            _ => {
+                return Err("contract-call arguments must be supplied as a list of `-e ...` or `-x 0000...` pairs".into())
+            }
+        };
+
+        values.push(value);
+        arg_iterator += 2;
+    }
+    Ok(values)
+}
+
+fn make_memo(memo: Option<&String>) -> TokenTransferMemo {
+    let mut memo_bytes = [0; 34];
+    let mut bytes = memo
+        .map(|memo| memo.as_bytes().to_vec())
+        .unwrap_or_default();
+    bytes.resize(34, 0);
+    memo_bytes.copy_from_slice(&bytes);
+    TokenTransferMemo(memo_bytes)
+}
+
+fn read_contract_file(contract_file: &str) -> Result<String, CliError> {
+    if contract_file == "-" {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer)?;
+        Ok(buffer)
+    } else {
+        Ok(fs::read_to_string(contract_file)?)
+    }
+}
+
 fn handle_contract_publish(
     args_slice: &[String],
     version: TransactionVersion,
@@ -390,13 +508,7 @@ fn handle_contract_publish(
     let contract_name = &args[3];
     let contract_file = &args[4];
 
-    let contract_contents = if contract_file == "-" {
-        let mut buffer = String::new();
-        io::stdin().read_to_string(&mut buffer)?;
-        buffer
-    } else {
-        fs::read_to_string(contract_file)?
-    };
+    let contract_contents = read_contract_file(contract_file)?;
 
     let sk_publisher = StacksPrivateKey::from_hex(sk_publisher)?;
 
@@ -449,36 +561,7 @@ fn handle_contract_call(
     let contract_name = &args[4];
     let function_name = &args[5];
 
-    let val_args = &args[6..];
-
-    if val_args.len() % 2 != 0 {
-        return Err(
-            "contract-call arguments must be supplied as a list of `-e ...` or `-x 0000...` pairs"
-                .into(),
-        );
-    }
-
-    let mut arg_iterator = 0;
-    let mut values = Vec::new();
-    while arg_iterator < val_args.len() {
-        let eval_method = &val_args[arg_iterator];
-        let input = &val_args[arg_iterator + 1];
-        let value = match eval_method.as_str() {
-            "-x" => {
-                Value::try_deserialize_hex_untyped(input)?
-            },
-            "-e" => {
-                vm_execute(input, clarity_version)?
-                    .ok_or("Supplied argument did not evaluate to a Value")?
-            },
-            _ => {
-                return Err("contract-call arguments must be supplied as a list of `-e ...` or `-x 0000...` pairs".into())
-            }
-        };
-
-        values.push(value);
-        arg_iterator += 2;
-    }
+    let values = parse_function_args(&args[6..], clarity_version)?;
 
     let sk_origin = StacksPrivateKey::from_hex(sk_origin)?;
 
@@ -537,17 +620,7 @@ fn handle_token_transfer(
     let recipient_address =
         PrincipalData::parse(&args[3]).map_err(|_e| "Failed to parse recipient")?;
     let amount = &args[4].parse()?;
-    let memo = {
-        let mut memo = [0; 34];
-        let mut bytes = if args.len() == 6 {
-            args[5].as_bytes().to_vec()
-        } else {
-            vec![]
-        };
-        bytes.resize(34, 0);
-        memo.copy_from_slice(&bytes);
-        TokenTransferMemo(memo)
-    };
+    let memo = make_memo(args.get(5));
 
     let payload = TransactionPayload::TokenTransfer(recipient_address, *amount, memo);
     let mut unsigned_tx = make_standard_single_sig_tx(
@@ -835,6 +908,442 @@ fn decode_microblocks(args: &[String], _version: TransactionVersion) -> Result<S
     }
 }
 
+/// Parse a signer description, such as `p2sh:2:[key],[key],[key]`, into a spending condition
+/// (with no nonce or fee) and its public keys
+fn parse_signer(
+    signer: &str,
+) -> Result<(TransactionSpendingCondition, Vec<StacksPublicKey>), CliError> {
+    let parts: Vec<&str> = signer.split(':').collect();
+    let (hash_mode, num_sigs, public_keys) = match parts.as_slice() {
+        [hash_mode, public_key] => (*hash_mode, 1, vec![StacksPublicKey::from_hex(public_key)?]),
+        [hash_mode, num_sigs, public_keys] => (
+            *hash_mode,
+            num_sigs.parse()?,
+            public_keys
+                .split(',')
+                .map(StacksPublicKey::from_hex)
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        _ => {
+            return Err(CliError::Message(format!(
+                "Invalid signer '{signer}'\n\nUSAGE:\n {ENVELOPE_USAGE}"
+            )))
+        }
+    };
+    let condition = match (hash_mode, parts.len()) {
+        ("p2pkh", 2) => TransactionSpendingCondition::new_singlesig_p2pkh(public_keys[0]),
+        ("p2wpkh", 2) => TransactionSpendingCondition::new_singlesig_p2wpkh(public_keys[0]),
+        ("p2sh", 3) => {
+            TransactionSpendingCondition::new_multisig_p2sh(num_sigs, public_keys.clone())
+        }
+        ("p2wsh", 3) => {
+            TransactionSpendingCondition::new_multisig_p2wsh(num_sigs, public_keys.clone())
+        }
+        ("order-independent-p2sh", 3) => {
+            TransactionSpendingCondition::new_multisig_order_independent_p2sh(
+                num_sigs,
+                public_keys.clone(),
+            )
+        }
+        ("order-independent-p2wsh", 3) => {
+            TransactionSpendingCondition::new_multisig_order_independent_p2wsh(
+                num_sigs,
+                public_keys.clone(),
+            )
+        }
+        _ => {
+            return Err(CliError::Message(format!(
+                "Invalid signer '{signer}'\n\nUSAGE:\n {ENVELOPE_USAGE}"
+            )))
+        }
+    }
+    .ok_or_else(|| CliError::Message(format!("Invalid public keys for signer '{signer}'")))?;
+    Ok((condition, public_keys))
+}
+
+/// The name of a spending condition's hash mode, as written in a signer description
+fn signer_hash_mode(condition: &TransactionSpendingCondition) -> &'static str {
+    match condition {
+        TransactionSpendingCondition::Singlesig(data) => match data.hash_mode {
+            SinglesigHashMode::P2PKH => "p2pkh",
+            SinglesigHashMode::P2WPKH => "p2wpkh",
+        },
+        TransactionSpendingCondition::Multisig(data) => match data.hash_mode {
+            MultisigHashMode::P2SH => "p2sh",
+            MultisigHashMode::P2WSH => "p2wsh",
+        },
+        TransactionSpendingCondition::OrderIndependentMultisig(data) => match data.hash_mode {
+            OrderIndependentMultisigHashMode::P2SH => "order-independent-p2sh",
+            OrderIndependentMultisigHashMode::P2WSH => "order-independent-p2wsh",
+        },
+    }
+}
+
+fn parse_envelope_payload(
+    args: &[String],
+    clarity_version: ClarityVersion,
+) -> Result<TransactionPayload, CliError> {
+    let payload = match args.first().map(String::as_str) {
+        Some("token-transfer") if args.len() == 3 || args.len() == 4 => {
+            let recipient_address =
+                PrincipalData::parse(&args[1]).map_err(|_e| "Failed to parse recipient")?;
+            let amount = args[2].parse()?;
+            TransactionPayload::TokenTransfer(recipient_address, amount, make_memo(args.get(3)))
+        }
+        Some("contract-call") if args.len() >= 4 => make_contract_call(
+            args[1].clone(),
+            args[2].clone(),
+            args[3].clone(),
+            parse_function_args(&args[4..], clarity_version)?,
+        )?
+        .into(),
+        Some("publish") if args.len() == 3 => {
+            make_contract_publish(args[1].clone(), read_contract_file(&args[2])?)?.into()
+        }
+        _ => {
+            return Err(CliError::Message(format!(
+                "Invalid or incomplete payload\n\nUSAGE:\n {ENVELOPE_USAGE}"
+            )))
+        }
+    };
+    Ok(payload)
+}
+
+fn take_flag_value(args: &mut Vec<String>, flag: &str) -> Result<String, CliError> {
+    if args.is_empty() {
+        return Err(CliError::Message(format!(
+            "{flag} requires a value\n\nUSAGE:\n {ENVELOPE_USAGE}"
+        )));
+    }
+    Ok(args.remove(0))
+}
+
+fn read_envelope(envelope_file: &str) -> Result<SigningEnvelope, CliError> {
+    let envelope_json = if envelope_file == "-" {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer)?;
+        buffer
+    } else {
+        fs::read_to_string(envelope_file)?
+    };
+    Ok(SigningEnvelope::from_json(&envelope_json)?)
+}
+
+fn envelope_create(
+    args_slice: &[String],
+    version: TransactionVersion,
+    chain_id: u32,
+    clarity_version: ClarityVersion,
+) -> Result<String, CliError> {
+    let mut args = args_slice.to_vec();
+    let mut sponsored = false;
+    let mut anchor_mode = TransactionAnchorMode::Any;
+    let mut post_condition_mode = TransactionPostConditionMode::Deny;
+    let mut post_conditions = vec![];
+    while args.first().is_some_and(|arg| arg.starts_with("--")) {
+        let flag = args.remove(0);
+        match flag.as_str() {
+            "--sponsored" => sponsored = true,
+            "--block-only" => anchor_mode = TransactionAnchorMode::OnChainOnly,
+            "--microblock-only" => anchor_mode = TransactionAnchorMode::OffChainOnly,
+            "--post-condition" => {
+                let post_condition_hex = take_flag_value(&mut args, &flag)?;
+                post_conditions.push(TransactionPostCondition::consensus_deserialize(
+                    &mut hex_bytes(&post_condition_hex)?.as_slice(),
+                )?);
+            }
+            "--post-condition-mode" => {
+                post_condition_mode = match take_flag_value(&mut args, &flag)?.as_str() {
+                    "allow" => TransactionPostConditionMode::Allow,
+                    "deny" => TransactionPostConditionMode::Deny,
+                    _ => return Err("--post-condition-mode must be `allow` or `deny`".into()),
+                }
+            }
+            _ => {
+                return Err(CliError::Message(format!(
+                    "Unknown option {flag}\n\nUSAGE:\n {ENVELOPE_USAGE}"
+                )))
+            }
+        }
+    }
+    if args.len() < 4 {
+        return Err(CliError::Message(format!(
+            "Incorrect argument count supplied \n\nUSAGE:\n {ENVELOPE_USAGE}"
+        )));
+    }
+
+    let (mut origin_condition, origin_keys) = parse_signer(&args[0])?;
+    let tx_fee = args[1].parse()?;
+    let nonce = args[2].parse()?;
+    if sponsored && tx_fee != 0 {
+        return Err(
+            "The sponsor pays the fee of a sponsored transaction, so [fee-rate] must be 0".into(),
+        );
+    }
+    let payload = parse_envelope_payload(&args[3..], clarity_version)?;
+
+    origin_condition.set_nonce(nonce);
+    origin_condition.set_tx_fee(tx_fee);
+    let auth = if sponsored {
+        TransactionAuth::Sponsored(
+            origin_condition,
+            TransactionSpendingCondition::new_initial_sighash(),
+        )
+    } else {
+        TransactionAuth::Standard(origin_condition)
+    };
+    let mut unsigned_tx = StacksTransaction::new(version, auth, payload);
+    unsigned_tx.chain_id = chain_id;
+    unsigned_tx.anchor_mode = anchor_mode;
+    unsigned_tx.post_condition_mode = post_condition_mode;
+    unsigned_tx.post_conditions = post_conditions;
+
+    Ok(SigningEnvelope::new(unsigned_tx, origin_keys)?.to_json())
+}
+
+fn envelope_sign(args_slice: &[String]) -> Result<String, CliError> {
+    let mut args = args_slice.to_vec();
+    let sponsor = if args.first().is_some_and(|arg| arg == "--sponsor") {
+        if args.len() < 4 {
+            return Err(CliError::Message(format!(
+                "Incorrect argument count supplied \n\nUSAGE:\n {ENVELOPE_USAGE}"
+            )));
+        }
+        let sponsor_args: Vec<_> = args.drain(..4).collect();
+        let (mut condition, public_keys) = parse_signer(&sponsor_args[1])?;
+        condition.set_tx_fee(sponsor_args[2].parse()?);
+        condition.set_nonce(sponsor_args[3].parse()?);
+        Some((condition, public_keys))
+    } else {
+        None
+    };
+    if args.len() != 2 {
+        return Err(CliError::Message(format!(
+            "Incorrect argument count supplied \n\nUSAGE:\n {ENVELOPE_USAGE}"
+        )));
+    }
+
+    let mut envelope = read_envelope(&args[0])?;
+    if let Some((condition, public_keys)) = sponsor {
+        envelope.set_sponsor(condition, public_keys)?;
+    }
+    let secret_key = StacksPrivateKey::from_hex(&args[1])?;
+    envelope.sign(&secret_key)?;
+    Ok(envelope.to_json())
+}
+
+fn envelope_combine(args: &[String]) -> Result<String, CliError> {
+    if args.len() < 2 {
+        return Err(CliError::Message(format!(
+            "Incorrect argument count supplied \n\nUSAGE:\n {ENVELOPE_USAGE}"
+        )));
+    }
+    let mut envelope = read_envelope(&args[0])?;
+    for envelope_file in args[1..].iter() {
+        envelope.combine(&read_envelope(envelope_file)?)?;
+    }
+    Ok(envelope.to_json())
+}
+
+fn describe_post_condition_principal(principal: &PostConditionPrincipal) -> String {
+    match principal {
+        PostConditionPrincipal::Origin => "origin".into(),
+        PostConditionPrincipal::Standard(addr) => addr.to_string(),
+        PostConditionPrincipal::Contract(addr, contract_name) => {
+            format!("{addr}.{contract_name}")
+        }
+    }
+}
+
+fn describe_post_condition(post_condition: &TransactionPostCondition) -> String {
+    let amount = |code: &FungibleConditionCode, amount: &u64| {
+        let bound = match code {
+            FungibleConditionCode::SentEq => "exactly",
+            FungibleConditionCode::SentGt => "more than",
+            FungibleConditionCode::SentGe => "at least",
+            FungibleConditionCode::SentLt => "less than",
+            FungibleConditionCode::SentLe => "at most",
+        };
+        format!("{bound} {amount}")
+    };
+    let asset = |asset_info: &AssetInfo| {
+        format!(
+            "{}.{}::{}",
+            asset_info.contract_address, asset_info.contract_name, asset_info.asset_name
+        )
+    };
+    match post_condition {
+        TransactionPostCondition::STX(principal, code, sent) => format!(
+            "{} sends {} uSTX",
+            describe_post_condition_principal(principal),
+            amount(code, sent)
+        ),
+        TransactionPostCondition::Fungible(principal, asset_info, code, sent) => format!(
+            "{} sends {} {}",
+            describe_post_condition_principal(principal),
+            amount(code, sent),
+            asset(asset_info)
+        ),
+        TransactionPostCondition::Nonfungible(principal, asset_info, value, code) => {
+            let sends = match code {
+                NonfungibleConditionCode::Sent => "sends",
+                NonfungibleConditionCode::NotSent => "does not send",
+            };
+            format!(
+                "{} {sends} {} {value}",
+                describe_post_condition_principal(principal),
+                asset(asset_info)
+            )
+        }
+    }
+}
+
+fn describe_payload(payload: &TransactionPayload) -> serde_json::Value {
+    match payload {
+        TransactionPayload::TokenTransfer(recipient, amount, memo) => serde_json::json!({
+            "type": "token-transfer",
+            "recipient": recipient.to_string(),
+            "amount": amount,
+            "memo": String::from_utf8_lossy(&memo.0).trim_end_matches('\0'),
+        }),
+        TransactionPayload::ContractCall(contract_call) => serde_json::json!({
+            "type": "contract-call",
+            "call": contract_call.to_string(),
+        }),
+        TransactionPayload::SmartContract(smart_contract, _) => serde_json::json!({
+            "type": "publish",
+            "contract_name": smart_contract.name.to_string(),
+            "code_length": smart_contract.code_body.len(),
+        }),
+        _ => serde_json::json!({ "type": payload.name() }),
+    }
+}
+
+/// Describe a spending condition and which of its keys have signed.  A key `can_sign` if its
+/// signature is still needed and, for an ordered multisig, no later key has signed yet.
+fn describe_signers(
+    condition: &TransactionSpendingCondition,
+    signers: &EnvelopeSigners,
+    mainnet: bool,
+    ready: bool,
+) -> serde_json::Value {
+    let signatures_required = usize::from(condition.signatures_required());
+    let needs_signatures = ready && signers.num_signatures() < signatures_required;
+    let ordered = is_ordered(condition);
+    let keys: Vec<_> = signers
+        .public_keys
+        .iter()
+        .zip(signers.signatures.iter())
+        .enumerate()
+        .map(|(index, (public_key, signature))| {
+            let later_signed = signers.signatures[index + 1..]
+                .iter()
+                .any(|sig| sig.is_some());
+            serde_json::json!({
+                "public_key": public_key.to_hex(),
+                "signed": signature.is_some(),
+                "can_sign": needs_signatures && signature.is_none() && !(ordered && later_signed),
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "address": condition.get_address(mainnet).to_string(),
+        "hash_mode": signer_hash_mode(condition),
+        "nonce": condition.nonce(),
+        "fee": condition.tx_fee(),
+        "signatures_required": signatures_required,
+        "signatures": signers.num_signatures(),
+        "keys": keys,
+    })
+}
+
+fn envelope_inspect(args: &[String]) -> Result<String, CliError> {
+    if args.len() != 1 {
+        return Err(CliError::Message(format!(
+            "Incorrect argument count supplied \n\nUSAGE:\n {ENVELOPE_USAGE}"
+        )));
+    }
+    let envelope = read_envelope(&args[0])?;
+    let tx = &envelope.transaction;
+    let mainnet = tx.is_mainnet();
+
+    let sponsor = match (tx.auth.sponsor(), &envelope.sponsor) {
+        (Some(sponsor_condition), Some(sponsor)) => describe_signers(
+            sponsor_condition,
+            sponsor,
+            mainnet,
+            envelope.origin_complete(),
+        ),
+        _ => serde_json::Value::Null,
+    };
+    // a sponsored transaction's fee is unknown until its sponsor is set
+    let fee = match (tx.auth.is_sponsored(), &envelope.sponsor) {
+        (true, None) => serde_json::Value::Null,
+        _ => tx.get_tx_fee().into(),
+    };
+    let post_condition_mode = match tx.post_condition_mode {
+        TransactionPostConditionMode::Allow => "allow",
+        TransactionPostConditionMode::Deny => "deny",
+    };
+    let post_conditions: Vec<_> = tx
+        .post_conditions
+        .iter()
+        .map(describe_post_condition)
+        .collect();
+
+    let description = serde_json::json!({
+        "network": if mainnet { "mainnet" } else { "testnet" },
+        "chain_id": format!("0x{:08x}", tx.chain_id),
+        "anchor_mode": format!("{:?}", tx.anchor_mode),
+        "payload": describe_payload(&tx.payload),
+        "fee": fee,
+        "post_condition_mode": post_condition_mode,
+        "post_conditions": post_conditions,
+        "origin": describe_signers(tx.auth.origin(), &envelope.origin, mainnet, true),
+        "sponsored": tx.auth.is_sponsored(),
+        "sponsor": sponsor,
+        "complete": envelope.is_complete(),
+    });
+    Ok(serde_json::to_string_pretty(&description).expect("Failed to serialize envelope to JSON"))
+}
+
+fn envelope_finalize(args: &[String]) -> Result<String, CliError> {
+    if args.len() != 1 {
+        return Err(CliError::Message(format!(
+            "Incorrect argument count supplied \n\nUSAGE:\n {ENVELOPE_USAGE}"
+        )));
+    }
+    let signed_tx = read_envelope(&args[0])?.finalize()?;
+
+    let mut signed_tx_bytes = vec![];
+    signed_tx
+        .consensus_serialize(&mut signed_tx_bytes)
+        .expect("FATAL: invalid signed transaction");
+    Ok(to_hex(&signed_tx_bytes))
+}
+
+fn handle_envelope(
+    args: &[String],
+    version: TransactionVersion,
+    chain_id: u32,
+    clarity_version: ClarityVersion,
+) -> Result<String, CliError> {
+    let Some((command, args)) = args.split_first() else {
+        return Err(CliError::Message(format!("USAGE:\n {ENVELOPE_USAGE}")));
+    };
+    if args.first().is_some_and(|arg| arg == "-h") {
+        return Err(CliError::Message(format!("USAGE:\n {ENVELOPE_USAGE}")));
+    }
+    match command.as_str() {
+        "create" => envelope_create(args, version, chain_id, clarity_version),
+        "sign" => envelope_sign(args),
+        "combine" => envelope_combine(args),
+        "inspect" => envelope_inspect(args),
+        "finalize" => envelope_finalize(args),
+        _ => Err(CliError::Message(format!("USAGE:\n {ENVELOPE_USAGE}"))),
+    }
+}
+
 fn main() {
     let mut argv: Vec<String> = env::args().collect();
 
@@ -887,6 +1396,7 @@ fn main_handler(mut argv: Vec<String>) -> Result<String, CliError> {
             "decode-block" => decode_block(args, tx_version),
             "decode-microblock" => decode_microblock(args, tx_version),
             "decode-microblocks" => decode_microblocks(args, tx_version),
+            "envelope" => handle_envelope(args, tx_version, chain_id, ClarityVersion::Clarity2),
             _ => Err(CliError::Usage),
         }
     } else {
@@ -896,6 +1406,8 @@ fn main_handler(mut argv: Vec<String>) -> Result<String, CliError> {
 
 #[cfg(test)]
 mod test {
+    use std::path::Path;
+
     use stacks_common::util::cargo_workspace;
 
     use super::*;
@@ -1277,4 +1789,168 @@ mod test {
         let tx = decode_transaction(&[result], TransactionVersion::Testnet).unwrap();
         assert!(tx.contains("chain_id\":305419896"));
     }
+
+    /// Write an envelope to a file in `dir`, as it would be carried to the next signer
+    fn write_envelope(dir: &Path, name: &str, envelope_json: &str) -> String {
+        let path = dir.join(format!("{name}.json"));
+        fs::write(&path, envelope_json).unwrap();
+        path.display().to_string()
+    }
+
+    fn make_signer_keys(count: usize) -> (Vec<String>, String) {
+        let privks: Vec<_> = (0..count).map(|_| StacksPrivateKey::random()).collect();
+        let pubks: Vec<_> = privks
+            .iter()
+            .map(|privk| StacksPublicKey::from_private(privk).to_hex())
+            .collect();
+        (
+            privks.iter().map(|privk| privk.to_hex()).collect(),
+            pubks.join(","),
+        )
+    }
+
+    fn decode_tx_hex(tx_hex: &str) -> StacksTransaction {
+        StacksTransaction::consensus_deserialize(&mut hex_bytes(tx_hex).unwrap().as_slice())
+            .unwrap()
+    }
+
+    #[test]
+    fn envelope_order_independent_multisig() {
+        let dir = tempfile::tempdir().unwrap();
+        let (privks, pubks) = make_signer_keys(3);
+        let signer = format!("order-independent-p2sh:2:{pubks}");
+        let create_args = [
+            "--testnet",
+            "envelope",
+            "create",
+            &signer,
+            "300",
+            "4",
+            "token-transfer",
+            "ST1A14RBKJ289E3DP89QAZE2RRHDPWP5RHMYFRCHV",
+            "10",
+            "Memo",
+        ];
+        let unsigned = main_handler(to_string_vec(&create_args)).unwrap();
+        let unsigned = write_envelope(dir.path(), "oi-unsigned", &unsigned);
+
+        // co-signers sign their own copies, in any order
+        let mut signed = vec![];
+        for (i, privk) in [&privks[2], &privks[0]].into_iter().enumerate() {
+            let sign_args = ["envelope", "sign", &unsigned, privk];
+            let envelope = main_handler(to_string_vec(&sign_args)).unwrap();
+            signed.push(write_envelope(
+                dir.path(),
+                &format!("oi-signed-{i}"),
+                &envelope,
+            ));
+        }
+
+        let finalize_args = ["envelope", "finalize", &signed[0]];
+        assert!(main_handler(to_string_vec(&finalize_args)).is_err());
+
+        let combine_args = ["envelope", "combine", &signed[0], &signed[1]];
+        let combined = main_handler(to_string_vec(&combine_args)).unwrap();
+        let combined = write_envelope(dir.path(), "oi-combined", &combined);
+
+        let inspect_args = ["envelope", "inspect", &combined];
+        let inspect: serde_json::Value =
+            serde_json::from_str(&main_handler(to_string_vec(&inspect_args)).unwrap()).unwrap();
+        assert_eq!(inspect["fee"], 300);
+        assert_eq!(inspect["payload"]["memo"], "Memo");
+        assert_eq!(inspect["origin"]["hash_mode"], "order-independent-p2sh");
+        assert_eq!(inspect["origin"]["signatures"], 2);
+        assert_eq!(inspect["complete"], true);
+
+        let finalize_args = ["envelope", "finalize", &combined];
+        let tx = decode_tx_hex(&main_handler(to_string_vec(&finalize_args)).unwrap());
+        tx.verify().unwrap();
+        assert_eq!(tx.get_origin_nonce(), 4);
+        assert_eq!(tx.chain_id, CHAIN_ID_TESTNET);
+    }
+
+    #[test]
+    fn envelope_sponsored_ordered_multisig() {
+        let dir = tempfile::tempdir().unwrap();
+        let (privks, pubks) = make_signer_keys(3);
+        let (sponsor_privks, sponsor_pubks) = make_signer_keys(1);
+        let signer = format!("p2sh:2:{pubks}");
+        let sponsor_signer = format!("p2pkh:{sponsor_pubks}");
+        let post_condition = TransactionPostCondition::STX(
+            PostConditionPrincipal::Origin,
+            FungibleConditionCode::SentLe,
+            10,
+        );
+        let post_condition = to_hex(&post_condition.serialize_to_vec());
+
+        let create_args = [
+            "envelope",
+            "create",
+            "--sponsored",
+            "--post-condition",
+            &post_condition,
+            &signer,
+            "1",
+            "0",
+            "token-transfer",
+            "SPJT598WY1RJN792HRKRHRQYFB7RJ5ZCG6J6GEZ4",
+            "10",
+        ];
+        assert!(main_handler(to_string_vec(&create_args)).is_err());
+        let create_args = [
+            "envelope",
+            "create",
+            "--sponsored",
+            "--post-condition",
+            &post_condition,
+            &signer,
+            "0",
+            "0",
+            "token-transfer",
+            "SPJT598WY1RJN792HRKRHRQYFB7RJ5ZCG6J6GEZ4",
+            "10",
+        ];
+        let envelope = main_handler(to_string_vec(&create_args)).unwrap();
+        let envelope = write_envelope(dir.path(), "sponsored-unsigned", &envelope);
+
+        let inspect_args = ["envelope", "inspect", &envelope];
+        let inspect: serde_json::Value =
+            serde_json::from_str(&main_handler(to_string_vec(&inspect_args)).unwrap()).unwrap();
+        assert_eq!(inspect["fee"], serde_json::Value::Null);
+        assert_eq!(inspect["post_condition_mode"], "deny");
+        assert_eq!(
+            inspect["post_conditions"][0],
+            "origin sends at most 10 uSTX"
+        );
+
+        // ordered keys sign one after another
+        let sign_args = ["envelope", "sign", &envelope, &privks[1]];
+        let envelope = main_handler(to_string_vec(&sign_args)).unwrap();
+        let envelope = write_envelope(dir.path(), "sponsored-origin-1", &envelope);
+        let sign_args = ["envelope", "sign", &envelope, &privks[0]];
+        assert!(main_handler(to_string_vec(&sign_args)).is_err());
+        let sign_args = ["envelope", "sign", &envelope, &privks[2]];
+        let envelope = main_handler(to_string_vec(&sign_args)).unwrap();
+        let envelope = write_envelope(dir.path(), "sponsored-origin-2", &envelope);
+
+        let sign_args = [
+            "envelope",
+            "sign",
+            "--sponsor",
+            &sponsor_signer,
+            "500",
+            "9",
+            &envelope,
+            &sponsor_privks[0],
+        ];
+        let envelope = main_handler(to_string_vec(&sign_args)).unwrap();
+        let envelope = write_envelope(dir.path(), "sponsored-signed", &envelope);
+
+        let finalize_args = ["envelope", "finalize", &envelope];
+        let tx = decode_tx_hex(&main_handler(to_string_vec(&finalize_args)).unwrap());
+        tx.verify().unwrap();
+        assert_eq!(tx.get_tx_fee(), 500);
+        assert_eq!(tx.auth.get_sponsor_nonce(), Some(9));
+        assert_eq!(tx.post_conditions.len(), 1);
+    }
 }
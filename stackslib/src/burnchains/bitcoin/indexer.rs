@@ -31,6 +31,7 @@ use stacks_common::deps_common::bitcoin::network::serialize::{
 };
 use stacks_common::deps_common::bitcoin::util::hash::Sha256dHash;
 use stacks_common::types::chainstate::BurnchainHeaderHash;
+use stacks_common::types::net::PeerHost;
 use stacks_common::util::{get_epoch_time_secs, log};
 
 use crate::burnchains::bitcoin::blocks::{
@@ -48,6 +49,7 @@ use crate::core::{
     EpochList, StacksEpoch, StacksEpochExtension, STACKS_EPOCHS_MAINNET, STACKS_EPOCHS_REGTEST,
     STACKS_EPOCHS_TESTNET,
 };
+use crate::net::socks::{self, Socks5Proxy};
 use crate::util_lib::db::Error as DBError;
 
 pub const USER_AGENT: &str = "Stacks/2.1";
@@ -113,6 +115,8 @@ pub struct BitcoinIndexerConfig {
     pub first_block: u64,
     pub magic_bytes: MagicBytes,
     pub epochs: Option<EpochList>,
+    /// SOCKS5 proxy to connect to the bitcoin peer through, if any
+    pub socks5_proxy: Option<Socks5Proxy>,
 }
 
 #[derive(Debug)]
@@ -148,6 +152,7 @@ impl BitcoinIndexerConfig {
             first_block,
             magic_bytes: BLOCKSTACK_MAGIC_MAINNET.clone(),
             epochs: None,
+            socks5_proxy: None,
         }
     }
 
@@ -164,6 +169,7 @@ impl BitcoinIndexerConfig {
             first_block: 0,
             magic_bytes: BLOCKSTACK_MAGIC_MAINNET.clone(),
             epochs: None,
+            socks5_proxy: None,
         }
     }
 
@@ -181,6 +187,7 @@ impl BitcoinIndexerConfig {
             first_block: 0,
             magic_bytes: BLOCKSTACK_MAGIC_MAINNET.clone(),
             epochs: None,
+            socks5_proxy: None,
         }
     }
 }
@@ -258,7 +265,17 @@ impl BitcoinIndexer {
     /// Bitcoin peer.  If we fail to connect, this method sets the socket
     /// to None.
     fn reconnect_peer(&mut self) -> Result<(), btc_error> {
-        match net::TcpStream::connect((self.config.peer_host.as_str(), self.config.peer_port)) {
+        let sock_res = match self.config.socks5_proxy {
+            Some(ref proxy) => {
+                let target =
+                    PeerHost::from_host_port(self.config.peer_host.clone(), self.config.peer_port);
+                socks::connect(proxy, &target, Duration::from_secs(self.runtime.timeout))
+            }
+            None => {
+                net::TcpStream::connect((self.config.peer_host.as_str(), self.config.peer_port))
+            }
+        };
+        match sock_res {
             Ok(s) => {
                 // Disable Nagle algorithm
                 s.set_nodelay(true).map_err(|_e| {
@@ -1589,6 +1606,7 @@ mod test {
             first_block: 0,
             magic_bytes: MagicBytes([105, 100]),
             epochs: None,
+            socks5_proxy: None,
         };
 
         if fs::metadata(&indexer_conf.spv_headers_path).is_ok() {
@@ -2785,6 +2785,33 @@ impl SortitionDB {
         Ok(db)
     }
 
+    /// Open the database on disk with a read-only sqlite connection, so that nothing can write to
+    /// it.  It must already exist and be instantiated.
+    pub fn open_readonly(path: &str, pox_constants: PoxConstants) -> Result<SortitionDB, db_error> {
+        let index_path = format!("{path}/marf.sqlite");
+        debug!("Open sortdb as 'readonly', with read-only index as '{index_path}'");
+
+        let marf = MARF::from_storage(
+            TrieFileStorage::open_readonly(&index_path, MARFOpenOpts::default())
+                .map_err(|_e| db_error::Corruption)?,
+        );
+        let (first_block_height, first_burn_header_hash) =
+            SortitionDB::get_first_block_height_and_hash(marf.sqlite_conn())?;
+
+        let mut db = SortitionDB {
+            path: path.to_string(),
+            marf,
+            readwrite: false,
+            dryrun: false,
+            pox_constants,
+            first_block_height,
+            first_burn_header_hash,
+        };
+
+        db.check_schema_version_or_error()?;
+        Ok(db)
+    }
+
     /// Open a new copy of this SortitionDB. Will use the same `readwrite` flag
     ///  of `self`.
     pub fn reopen(&self) -> Result<SortitionDB, db_error> {
@@ -0,0 +1,966 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Nakamoto block-assembly strategies.
+//!
+//! A `BlockAssemblyStrategy` decides which mempool transactions a miner puts into a block, and
+//! in which order.  The default strategy, `GreedyAssembly`, is the mempool walk of
+//! `StacksBlockBuilder::select_and_apply_transactions`: it visits transactions in fee-rate order
+//! and mines each one that fits.
+//!
+//! The other strategies load the mempool's candidates up front and group them into *chains*:
+//! the transactions of one origin, with consecutive nonces starting at the origin's account
+//! nonce.  They then repeatedly pick a chain and mine the next transaction(s) from it:
+//!
+//! * `max_fee_per_dimension` picks the chain head with the highest fee per unit of the block
+//!   budget that it would use in its scarcest dimension.
+//! * `fair` takes one transaction from each origin in turn, in order of arrival.
+//! * `priority_lanes` mines calls to a list of contracts first, highest fee first, and then
+//!   fills the rest of the block with the greedy walk.
+//! * `knapsack` packs the block against all five `ExecutionCost` dimensions and the block
+//!   length.  Each prefix of a chain is a package whose weight is the sum of the fractions of
+//!   the remaining budget that it would use in each dimension, and the package with the most
+//!   fee per weight is mined next.  Since the weights are recomputed against the remaining
+//!   budget, the dimensions that are running out count for more as the block fills up.
+//!
+//! All but the greedy strategy rank transactions by the cost estimator's predictions.
+//! Transactions that the estimator has no prediction for are mined last, in fee order.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Instant;
+
+use clarity::vm::ast::ASTRules;
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::profiler::CostDimension;
+use clarity::vm::types::QualifiedContractIdentifier;
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::StacksEpochId;
+use stacks_common::util::get_epoch_time_ms;
+
+use crate::burnchains::Txid;
+use crate::chainstate::stacks::db::{ClarityTx, StacksChainState};
+use crate::chainstate::stacks::miner::{
+    BlockBuilder, BlockBuilderSettings, BlockLimitFunction, TransactionError, TransactionEvent,
+    TransactionProblematic, TransactionResult, TransactionSkipped, TransactionSuccess,
+};
+use crate::chainstate::stacks::{
+    Error, StacksBlockBuilder, StacksTransaction, TenureChangeCause, TenureChangePayload,
+    TransactionPayload, MAX_EPOCH_SIZE,
+};
+use crate::core::mempool::{
+    MemPoolDB, MemPoolDropReason, MemPoolEventDispatcher, MemPoolTxInfo, MemPoolWalkSettings,
+    MemPoolWalkTxTypes,
+};
+
+/// The maximum number of mempool transactions that a strategy loads as candidates
+pub const MAX_ASSEMBLY_CANDIDATES: u64 = 20_000;
+/// The maximum number of transactions of one chain that the knapsack strategy packs at once
+pub const MAX_KNAPSACK_PACKAGE_LEN: usize = 25;
+
+/// A strategy for selecting the transactions of a Nakamoto block
+pub trait BlockAssemblyStrategy: Send + Sync {
+    /// The name of the strategy, as it is given in the miner config
+    fn name(&self) -> &'static str;
+
+    /// Select transactions for block inclusion from the mempool, and apply them to the ongoing
+    /// ClarityTx.  The `initial_txs` are mined first.  Invalid transactions are dropped from the
+    /// mempool.
+    /// Returns whether or not the miner got blocked, as well as the gathered tx events
+    fn select_and_apply_transactions(
+        &self,
+        epoch_tx: &mut ClarityTx,
+        builder: &mut dyn BlockBuilder,
+        mempool: &mut MemPoolDB,
+        tip_height: u64,
+        initial_txs: &[StacksTransaction],
+        settings: BlockBuilderSettings,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        ast_rules: ASTRules,
+    ) -> Result<(bool, Vec<TransactionEvent>), Error>;
+}
+
+/// The block-assembly strategy selected in the miner config
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum BlockAssembly {
+    /// Walk the mempool in fee-rate order
+    #[default]
+    Greedy,
+    /// Maximize the fee per unit of the scarcest block-limit dimension
+    MaxFeePerDimension,
+    /// Take one transaction from each origin in turn, in order of arrival
+    FairOrdering,
+    /// Mine calls to these contracts first, then walk the mempool in fee-rate order
+    PriorityLanes(Vec<QualifiedContractIdentifier>),
+    /// Pack the block against all of its limits with the cost estimator's predictions
+    Knapsack,
+}
+
+impl BlockAssembly {
+    /// Parse a strategy by its config name.  `priority_lanes` takes its contracts from
+    /// `lane_contracts`.
+    pub fn from_config(
+        name: &str,
+        lane_contracts: Vec<QualifiedContractIdentifier>,
+    ) -> Result<BlockAssembly, String> {
+        let assembly = match name {
+            "greedy" => BlockAssembly::Greedy,
+            "max_fee_per_dimension" => BlockAssembly::MaxFeePerDimension,
+            "fair" => BlockAssembly::FairOrdering,
+            "priority_lanes" => {
+                if lane_contracts.is_empty() {
+                    return Err(
+                        "the priority_lanes block assembly requires priority_lane_contracts".into(),
+                    );
+                }
+                BlockAssembly::PriorityLanes(lane_contracts)
+            }
+            "knapsack" => BlockAssembly::Knapsack,
+            _ => return Err(format!("unknown block assembly strategy '{name}'")),
+        };
+        Ok(assembly)
+    }
+
+    /// Instantiate the selected strategy
+    pub fn strategy(&self) -> Box<dyn BlockAssemblyStrategy> {
+        match self {
+            BlockAssembly::Greedy => Box::new(GreedyAssembly),
+            BlockAssembly::MaxFeePerDimension => Box::new(MaxFeePerDimensionAssembly),
+            BlockAssembly::FairOrdering => Box::new(FairOrderingAssembly),
+            BlockAssembly::PriorityLanes(contracts) => Box::new(PriorityLanesAssembly {
+                contracts: contracts.iter().cloned().collect(),
+            }),
+            BlockAssembly::Knapsack => Box::new(KnapsackAssembly),
+        }
+    }
+}
+
+/// The default strategy: the fee-rate ordered mempool walk
+pub struct GreedyAssembly;
+
+impl BlockAssemblyStrategy for GreedyAssembly {
+    fn name(&self) -> &'static str {
+        "greedy"
+    }
+
+    fn select_and_apply_transactions(
+        &self,
+        epoch_tx: &mut ClarityTx,
+        builder: &mut dyn BlockBuilder,
+        mempool: &mut MemPoolDB,
+        tip_height: u64,
+        initial_txs: &[StacksTransaction],
+        settings: BlockBuilderSettings,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        ast_rules: ASTRules,
+    ) -> Result<(bool, Vec<TransactionEvent>), Error> {
+        StacksBlockBuilder::select_and_apply_transactions(
+            epoch_tx,
+            builder,
+            mempool,
+            tip_height,
+            initial_txs,
+            settings,
+            event_observer,
+            ast_rules,
+        )
+    }
+}
+
+/// Mine the chain head with the most fee per unit of its scarcest dimension
+pub struct MaxFeePerDimensionAssembly;
+
+impl BlockAssemblyStrategy for MaxFeePerDimensionAssembly {
+    fn name(&self) -> &'static str {
+        "max_fee_per_dimension"
+    }
+
+    fn select_and_apply_transactions(
+        &self,
+        epoch_tx: &mut ClarityTx,
+        builder: &mut dyn BlockBuilder,
+        mempool: &mut MemPoolDB,
+        tip_height: u64,
+        initial_txs: &[StacksTransaction],
+        settings: BlockBuilderSettings,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        ast_rules: ASTRules,
+    ) -> Result<(bool, Vec<TransactionEvent>), Error> {
+        let assembled = assemble_in_order(
+            &mut MaxFeePerDimensionOrder,
+            self.name(),
+            epoch_tx,
+            builder,
+            mempool,
+            tip_height,
+            initial_txs,
+            &settings,
+            event_observer,
+            ast_rules,
+        )?;
+        Ok((assembled.blocked, assembled.tx_events))
+    }
+}
+
+/// Mine one transaction from each origin in turn
+pub struct FairOrderingAssembly;
+
+impl BlockAssemblyStrategy for FairOrderingAssembly {
+    fn name(&self) -> &'static str {
+        "fair"
+    }
+
+    fn select_and_apply_transactions(
+        &self,
+        epoch_tx: &mut ClarityTx,
+        builder: &mut dyn BlockBuilder,
+        mempool: &mut MemPoolDB,
+        tip_height: u64,
+        initial_txs: &[StacksTransaction],
+        settings: BlockBuilderSettings,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        ast_rules: ASTRules,
+    ) -> Result<(bool, Vec<TransactionEvent>), Error> {
+        let assembled = assemble_in_order(
+            &mut FairOrder::default(),
+            self.name(),
+            epoch_tx,
+            builder,
+            mempool,
+            tip_height,
+            initial_txs,
+            &settings,
+            event_observer,
+            ast_rules,
+        )?;
+        Ok((assembled.blocked, assembled.tx_events))
+    }
+}
+
+/// Mine calls to the lane contracts first, and then walk the mempool in fee-rate order
+pub struct PriorityLanesAssembly {
+    pub contracts: HashSet<QualifiedContractIdentifier>,
+}
+
+impl BlockAssemblyStrategy for PriorityLanesAssembly {
+    fn name(&self) -> &'static str {
+        "priority_lanes"
+    }
+
+    fn select_and_apply_transactions(
+        &self,
+        epoch_tx: &mut ClarityTx,
+        builder: &mut dyn BlockBuilder,
+        mempool: &mut MemPoolDB,
+        tip_height: u64,
+        initial_txs: &[StacksTransaction],
+        mut settings: BlockBuilderSettings,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        ast_rules: ASTRules,
+    ) -> Result<(bool, Vec<TransactionEvent>), Error> {
+        let ts_start = get_epoch_time_ms();
+        let mut assembled = assemble_in_order(
+            &mut PriorityLanesOrder {
+                contracts: &self.contracts,
+            },
+            self.name(),
+            epoch_tx,
+            builder,
+            mempool,
+            tip_height,
+            initial_txs,
+            &settings,
+            event_observer,
+            ast_rules,
+        )?;
+        if assembled.blocked
+            || assembled.tenure_start
+            || assembled.block_limit_hit == BlockLimitFunction::LIMIT_REACHED
+        {
+            return Ok((assembled.blocked, assembled.tx_events));
+        }
+
+        // the greedy walk gets whatever mining time is left
+        let elapsed =
+            u64::try_from(get_epoch_time_ms().saturating_sub(ts_start)).unwrap_or(u64::MAX);
+        settings.max_miner_time_ms = settings.max_miner_time_ms.saturating_sub(elapsed);
+        settings.mempool_settings.max_walk_time_ms = settings
+            .mempool_settings
+            .max_walk_time_ms
+            .saturating_sub(elapsed);
+        if settings.max_miner_time_ms == 0 {
+            return Ok((assembled.blocked, assembled.tx_events));
+        }
+
+        let (blocked, tx_events) = StacksBlockBuilder::select_and_apply_transactions(
+            epoch_tx,
+            builder,
+            mempool,
+            tip_height,
+            &[],
+            settings,
+            event_observer,
+            ast_rules,
+        )?;
+        assembled.tx_events.extend(tx_events);
+        Ok((blocked, assembled.tx_events))
+    }
+}
+
+/// Pack the block against all of its limits, using the cost estimator's predictions
+pub struct KnapsackAssembly;
+
+impl BlockAssemblyStrategy for KnapsackAssembly {
+    fn name(&self) -> &'static str {
+        "knapsack"
+    }
+
+    fn select_and_apply_transactions(
+        &self,
+        epoch_tx: &mut ClarityTx,
+        builder: &mut dyn BlockBuilder,
+        mempool: &mut MemPoolDB,
+        tip_height: u64,
+        initial_txs: &[StacksTransaction],
+        settings: BlockBuilderSettings,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        ast_rules: ASTRules,
+    ) -> Result<(bool, Vec<TransactionEvent>), Error> {
+        let assembled = assemble_in_order(
+            &mut KnapsackOrder,
+            self.name(),
+            epoch_tx,
+            builder,
+            mempool,
+            tip_height,
+            initial_txs,
+            &settings,
+            event_observer,
+            ast_rules,
+        )?;
+        Ok((assembled.blocked, assembled.tx_events))
+    }
+}
+
+/// A mempool transaction that a strategy may mine
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub info: MemPoolTxInfo,
+    /// The cost estimator's prediction of the transaction's execution cost, if it has one
+    pub estimated_cost: Option<ExecutionCost>,
+}
+
+impl Candidate {
+    pub fn fee(&self) -> u64 {
+        self.info.metadata.tx_fee
+    }
+
+    pub fn tx_len(&self) -> u64 {
+        self.info.metadata.len
+    }
+}
+
+/// The candidates of one origin, with consecutive nonces starting at its account nonce
+#[derive(Debug, Clone)]
+pub struct OriginChain {
+    pub origin: StacksAddress,
+    pub txs: VecDeque<Candidate>,
+}
+
+/// What is left of the block's budget
+#[derive(Debug, Clone)]
+pub struct RemainingBudget {
+    pub cost: ExecutionCost,
+    pub bytes: u64,
+}
+
+impl RemainingBudget {
+    pub fn new(
+        block_limit: &ExecutionCost,
+        cost_so_far: &ExecutionCost,
+        bytes_so_far: u64,
+    ) -> Self {
+        let mut cost = block_limit.clone();
+        if cost.sub(cost_so_far).is_err() {
+            cost = ExecutionCost::ZERO;
+        }
+        RemainingBudget {
+            cost,
+            bytes: u64::from(MAX_EPOCH_SIZE).saturating_sub(bytes_so_far),
+        }
+    }
+
+    /// The fractions of the remaining budget that a cost and a length would use, one per cost
+    /// dimension followed by the length.  A fraction above 1.0 does not fit.
+    pub fn fractions(&self, cost: &ExecutionCost, len: u64) -> [f64; 6] {
+        let mut fractions = [0.0; 6];
+        for (i, dimension) in CostDimension::ALL.iter().enumerate() {
+            fractions[i] = fraction(dimension.of(cost), dimension.of(&self.cost));
+        }
+        fractions[5] = fraction(len, self.bytes);
+        fractions
+    }
+}
+
+fn fraction(used: u64, remaining: u64) -> f64 {
+    if used == 0 {
+        0.0
+    } else if remaining == 0 {
+        f64::INFINITY
+    } else {
+        used as f64 / remaining as f64
+    }
+}
+
+/// How a strategy orders the candidates
+pub trait CandidateOrder {
+    /// Pick the chain to mine from next, and how many of its transactions to mine.  The chain
+    /// must not be empty.  Returns None to end the selection.
+    fn pick(&mut self, chains: &[OriginChain], budget: &RemainingBudget) -> Option<(usize, usize)>;
+}
+
+/// Find the chain head with the highest fee among those without a cost estimate
+fn best_unestimated_head(chains: &[OriginChain]) -> Option<(usize, usize)> {
+    chains
+        .iter()
+        .enumerate()
+        .filter_map(|(i, chain)| chain.txs.front().map(|tx| (i, tx)))
+        .filter(|(_, tx)| tx.estimated_cost.is_none())
+        .max_by_key(|(_, tx)| tx.fee())
+        .map(|(i, _)| (i, 1))
+}
+
+/// Find the candidate with the highest score, breaking ties in favor of the first one
+fn best_scored(scored: impl Iterator<Item = ((usize, usize), f64)>) -> Option<(usize, usize)> {
+    let mut best: Option<((usize, usize), f64)> = None;
+    for (pick, score) in scored {
+        match best {
+            Some((_, best_score)) if best_score >= score => {}
+            _ => best = Some((pick, score)),
+        }
+    }
+    best.map(|(pick, _)| pick)
+}
+
+/// Picks the chain head with the most fee per unit of its scarcest dimension
+pub struct MaxFeePerDimensionOrder;
+
+impl CandidateOrder for MaxFeePerDimensionOrder {
+    fn pick(&mut self, chains: &[OriginChain], budget: &RemainingBudget) -> Option<(usize, usize)> {
+        let scored = chains.iter().enumerate().filter_map(|(i, chain)| {
+            let tx = chain.txs.front()?;
+            let cost = tx.estimated_cost.as_ref()?;
+            let scarcest = budget
+                .fractions(cost, tx.tx_len())
+                .into_iter()
+                .fold(0.0, f64::max);
+            if scarcest > 1.0 {
+                return None;
+            }
+            Some(((i, 1), tx.fee() as f64 / scarcest.max(f64::MIN_POSITIVE)))
+        });
+        best_scored(scored).or_else(|| best_unestimated_head(chains))
+    }
+}
+
+/// Picks one transaction from each origin in turn.  The chains are visited in the order that
+/// their first transactions arrived.
+#[derive(Default)]
+pub struct FairOrder {
+    next_chain: usize,
+}
+
+impl CandidateOrder for FairOrder {
+    fn pick(
+        &mut self,
+        chains: &[OriginChain],
+        _budget: &RemainingBudget,
+    ) -> Option<(usize, usize)> {
+        let num_chains = chains.len();
+        let picked = (0..num_chains)
+            .map(|offset| (self.next_chain + offset) % num_chains)
+            .find(|i| !chains[*i].txs.is_empty())?;
+        self.next_chain = picked + 1;
+        Some((picked, 1))
+    }
+}
+
+/// Picks the chain head with the highest fee among calls to the lane contracts
+pub struct PriorityLanesOrder<'a> {
+    pub contracts: &'a HashSet<QualifiedContractIdentifier>,
+}
+
+impl PriorityLanesOrder<'_> {
+    pub fn is_lane_tx(&self, tx: &StacksTransaction) -> bool {
+        match &tx.payload {
+            TransactionPayload::ContractCall(cc) => {
+                self.contracts.contains(&cc.contract_identifier())
+            }
+            _ => false,
+        }
+    }
+}
+
+impl CandidateOrder for PriorityLanesOrder<'_> {
+    fn pick(
+        &mut self,
+        chains: &[OriginChain],
+        _budget: &RemainingBudget,
+    ) -> Option<(usize, usize)> {
+        chains
+            .iter()
+            .enumerate()
+            .filter_map(|(i, chain)| chain.txs.front().map(|tx| (i, tx)))
+            .filter(|(_, tx)| self.is_lane_tx(&tx.info.tx))
+            .max_by_key(|(_, tx)| tx.fee())
+            .map(|(i, _)| (i, 1))
+    }
+}
+
+/// Picks the chain prefix with the most fee per weight, where the weight of a prefix is the sum
+/// of the fractions of the remaining budget that it would use in each dimension
+pub struct KnapsackOrder;
+
+impl CandidateOrder for KnapsackOrder {
+    fn pick(&mut self, chains: &[OriginChain], budget: &RemainingBudget) -> Option<(usize, usize)> {
+        let mut scored = vec![];
+        for (i, chain) in chains.iter().enumerate() {
+            let mut fee = 0u64;
+            let mut cost = ExecutionCost::ZERO;
+            let mut len = 0u64;
+            for (count, tx) in chain.txs.iter().take(MAX_KNAPSACK_PACKAGE_LEN).enumerate() {
+                let Some(tx_cost) = tx.estimated_cost.as_ref() else {
+                    break;
+                };
+                if cost.add(tx_cost).is_err() {
+                    break;
+                }
+                fee = fee.saturating_add(tx.fee());
+                len = len.saturating_add(tx.tx_len());
+                let fractions = budget.fractions(&cost, len);
+                if fractions.iter().any(|f| *f > 1.0) {
+                    break;
+                }
+                let weight: f64 = fractions.iter().sum();
+                scored.push(((i, count + 1), fee as f64 / weight.max(f64::MIN_POSITIVE)));
+            }
+        }
+        best_scored(scored.into_iter()).or_else(|| best_unestimated_head(chains))
+    }
+}
+
+/// Is this the first block of a tenure?  If so, it only gets its initial transactions, so that
+/// the tenure starts quickly.
+fn is_tenure_start(initial_txs: &[StacksTransaction]) -> bool {
+    initial_txs.first().is_some_and(|tx| {
+        matches!(
+            &tx.payload,
+            TransactionPayload::TenureChange(TenureChangePayload {
+                cause: TenureChangeCause::BlockFound,
+                ..
+            })
+        )
+    })
+}
+
+/// Can this transaction be considered under the mempool walk settings?
+fn is_considered(info: &MemPoolTxInfo, settings: &MemPoolWalkSettings) -> bool {
+    let tx_type = match &info.tx.payload {
+        TransactionPayload::TokenTransfer(..) => Some(MemPoolWalkTxTypes::TokenTransfer),
+        TransactionPayload::SmartContract(..) => Some(MemPoolWalkTxTypes::SmartContract),
+        TransactionPayload::ContractCall(..) => Some(MemPoolWalkTxTypes::ContractCall),
+        _ => None,
+    };
+    if let Some(tx_type) = tx_type {
+        if !settings.txs_to_consider.contains(&tx_type) {
+            return false;
+        }
+    }
+    settings.filter_origins.is_empty()
+        || settings
+            .filter_origins
+            .contains(&info.metadata.origin_address)
+}
+
+/// Get the next usable nonce of an origin or sponsor, reading it from the chainstate once
+fn next_nonce(
+    next_nonces: &mut HashMap<StacksAddress, u64>,
+    epoch_tx: &mut ClarityTx,
+    address: &StacksAddress,
+) -> u64 {
+    *next_nonces
+        .entry(*address)
+        .or_insert_with(|| StacksChainState::get_nonce(epoch_tx, &(*address).into()))
+}
+
+/// Load the mempool's candidates, best fee rate first, and group them into chains of
+/// consecutive nonces starting at each origin's account nonce.  A sponsored transaction also
+/// needs the next nonce of its sponsor; the sponsor nonces are handed out to the chains in the
+/// order that they are built, which is the order of their first transaction's arrival.  Each
+/// account's nonce is read from the chainstate once, whether it is an origin, a sponsor, or
+/// both.  The chains are ordered by the arrival of their first transaction.
+pub fn gather_candidates(
+    epoch_tx: &mut ClarityTx,
+    mempool: &MemPoolDB,
+    settings: &MemPoolWalkSettings,
+    stacks_epoch_id: &StacksEpochId,
+) -> Result<Vec<OriginChain>, Error> {
+    let mut by_origin: HashMap<StacksAddress, Vec<MemPoolTxInfo>> = HashMap::new();
+    for info in mempool.get_txs_by_fee_rate(MAX_ASSEMBLY_CANDIDATES)? {
+        if is_considered(&info, settings) {
+            by_origin
+                .entry(info.metadata.origin_address)
+                .or_default()
+                .push(info);
+        }
+    }
+    let mut by_origin: Vec<_> = by_origin
+        .into_iter()
+        .map(|(origin, mut infos)| {
+            infos.sort_by_key(|info| info.metadata.origin_nonce);
+            (origin, infos)
+        })
+        .collect();
+    by_origin.sort_by_key(|(_, infos)| {
+        infos
+            .first()
+            .map(|info| (info.metadata.accept_time, info.metadata.txid))
+    });
+
+    // the next usable nonce of each origin and sponsor
+    let mut next_nonces: HashMap<StacksAddress, u64> = HashMap::new();
+    let mut chains = vec![];
+    for (origin, infos) in by_origin.into_iter() {
+        let mut txs = VecDeque::new();
+        for info in infos.into_iter() {
+            let origin_nonce = next_nonce(&mut next_nonces, epoch_tx, &origin);
+            if info.metadata.origin_nonce < origin_nonce {
+                continue;
+            }
+            if info.metadata.origin_nonce > origin_nonce {
+                break;
+            }
+            let sponsor = &info.metadata.sponsor_address;
+            if info.tx.auth.is_sponsored() {
+                if info.metadata.sponsor_nonce != next_nonce(&mut next_nonces, epoch_tx, sponsor) {
+                    break;
+                }
+                next_nonces.insert(*sponsor, info.metadata.sponsor_nonce + 1);
+            }
+            next_nonces.insert(origin, origin_nonce + 1);
+            let estimated_cost = mempool.estimate_tx_cost(&info.tx.payload, stacks_epoch_id);
+            txs.push_back(Candidate {
+                info,
+                estimated_cost,
+            });
+        }
+        if !txs.is_empty() {
+            chains.push(OriginChain { origin, txs });
+        }
+    }
+    chains.sort_by_key(|chain| {
+        chain
+            .txs
+            .front()
+            .map(|tx| (tx.info.metadata.accept_time, tx.info.metadata.txid))
+    });
+    Ok(chains)
+}
+
+/// The result of mining one candidate
+enum Mined {
+    /// The candidate was mined
+    Success,
+    /// The candidate was not mined, so the rest of its chain cannot be mined either
+    Failed,
+    /// Block assembly must stop
+    Stop,
+}
+
+/// The outcome of `assemble_in_order`
+struct Assembled {
+    blocked: bool,
+    tenure_start: bool,
+    block_limit_hit: BlockLimitFunction,
+    tx_events: Vec<TransactionEvent>,
+}
+
+/// The state of a block assembly, shared by all the candidate orders
+struct AssemblyRun<'a> {
+    settings: &'a BlockBuilderSettings,
+    ast_rules: ASTRules,
+    deadline: u128,
+    block_limit: ExecutionCost,
+    stacks_epoch_id: StacksEpochId,
+    block_limit_hit: BlockLimitFunction,
+    bytes_so_far: u64,
+    num_txs: u64,
+    tx_events: Vec<TransactionEvent>,
+    invalidated_txs: Vec<Txid>,
+    to_drop_and_blacklist: Vec<Txid>,
+    update_timings: Vec<(Txid, u64)>,
+}
+
+impl AssemblyRun<'_> {
+    fn is_blocked(&self) -> bool {
+        self.settings
+            .miner_status
+            .lock()
+            .expect("FATAL: mutex poisoned")
+            .is_blocked()
+    }
+
+    fn try_mine(
+        &mut self,
+        epoch_tx: &mut ClarityTx,
+        builder: &mut dyn BlockBuilder,
+        mempool: &mut MemPoolDB,
+        candidate: &Candidate,
+    ) -> Mined {
+        let txinfo = &candidate.info;
+        if let Some(time_estimate) = txinfo.metadata.time_estimate_ms {
+            let time_now = get_epoch_time_ms();
+            if time_now.saturating_add(time_estimate.into()) > self.deadline {
+                debug!("Mining tx would cause us to exceed our deadline, skipping";
+                       "txid" => %txinfo.tx.txid(),
+                       "deadline" => self.deadline,
+                       "now" => time_now,
+                       "estimate" => time_estimate);
+                return Mined::Failed;
+            }
+        }
+
+        let tx_start = Instant::now();
+        let tx_result = builder.try_mine_tx_with_len(
+            epoch_tx,
+            &txinfo.tx,
+            txinfo.metadata.len,
+            &self.block_limit_hit,
+            self.ast_rules,
+        );
+        let result_event = tx_result.convert_to_event();
+        if !matches!(result_event, TransactionEvent::Skipped(_)) {
+            self.tx_events.push(result_event);
+        }
+
+        match tx_result {
+            TransactionResult::Success(TransactionSuccess {
+                receipt,
+                soft_limit_reached,
+                ..
+            }) => {
+                if txinfo.metadata.time_estimate_ms.is_none() {
+                    // stay in i64 range to avoid running into issues when storing in
+                    //  rusqlite.
+                    let time_estimate_ms = u64::try_from(tx_start.elapsed().as_millis())
+                        .unwrap_or(u64::MAX)
+                        .min(i64::MAX as u64);
+                    self.update_timings
+                        .push((txinfo.tx.txid(), time_estimate_ms));
+                }
+                if candidate.estimated_cost.is_none() {
+                    mempool.notify_cost_estimator(
+                        &txinfo.tx.payload,
+                        &receipt.execution_cost,
+                        &self.block_limit,
+                        &self.stacks_epoch_id,
+                    );
+                }
+                self.num_txs += 1;
+                self.bytes_so_far = self.bytes_so_far.saturating_add(txinfo.metadata.len);
+                if soft_limit_reached
+                    && self.block_limit_hit != BlockLimitFunction::CONTRACT_LIMIT_HIT
+                {
+                    debug!("Soft block budget exceeded on tx {}", &txinfo.tx.txid());
+                    debug!("Switch to mining stx-transfers only");
+                    self.block_limit_hit = BlockLimitFunction::CONTRACT_LIMIT_HIT;
+                }
+                Mined::Success
+            }
+            TransactionResult::Skipped(TransactionSkipped { error, .. })
+            | TransactionResult::ProcessingError(TransactionError { error, .. }) => {
+                match &error {
+                    Error::StacksTransactionSkipped(_) => {}
+                    Error::BlockTooBigError => {
+                        debug!("Block budget exceeded on tx {}", &txinfo.tx.txid());
+                        if self.block_limit_hit == BlockLimitFunction::NO_LIMIT_HIT {
+                            debug!("Switch to mining stx-transfers only");
+                            self.block_limit_hit = BlockLimitFunction::CONTRACT_LIMIT_HIT;
+                        } else if self.block_limit_hit == BlockLimitFunction::CONTRACT_LIMIT_HIT {
+                            debug!("Stop mining anchored block due to limit exceeded");
+                            self.block_limit_hit = BlockLimitFunction::LIMIT_REACHED;
+                            return Mined::Stop;
+                        }
+                    }
+                    Error::TransactionTooBigError(measured_cost) => {
+                        if let Some(measured_cost) = measured_cost {
+                            mempool.notify_cost_estimator(
+                                &txinfo.tx.payload,
+                                measured_cost,
+                                &self.block_limit,
+                                &self.stacks_epoch_id,
+                            );
+                        }
+                        self.invalidated_txs.push(txinfo.metadata.txid);
+                    }
+                    Error::InvalidStacksTransaction(_, true) => {
+                        // if we have an invalid transaction that was quietly ignored, don't warn here either
+                    }
+                    e => {
+                        info!("Failed to apply tx {}: {:?}", &txinfo.tx.txid(), &e);
+                    }
+                }
+                Mined::Failed
+            }
+            TransactionResult::Problematic(TransactionProblematic { tx, .. }) => {
+                debug!("Drop and blacklist problematic transaction {}", &tx.txid());
+                self.to_drop_and_blacklist.push(tx.txid());
+                Mined::Failed
+            }
+        }
+    }
+}
+
+/// Mine the initial transactions, and then the mempool's candidates in the order given by
+/// `order`, until the block is full, the miner is preempted, the mining time is up, or there
+/// are no more candidates.
+fn assemble_in_order(
+    order: &mut dyn CandidateOrder,
+    strategy_name: &str,
+    epoch_tx: &mut ClarityTx,
+    builder: &mut dyn BlockBuilder,
+    mempool: &mut MemPoolDB,
+    tip_height: u64,
+    initial_txs: &[StacksTransaction],
+    settings: &BlockBuilderSettings,
+    event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ast_rules: ASTRules,
+) -> Result<Assembled, Error> {
+    let ts_start = get_epoch_time_ms();
+    let stacks_epoch_id = epoch_tx.get_epoch();
+    let block_limit = epoch_tx
+        .block_limit()
+        .expect("Failed to obtain block limit from miner's block connection");
+
+    let mut run = AssemblyRun {
+        settings,
+        ast_rules,
+        deadline: ts_start + u128::from(settings.max_miner_time_ms),
+        block_limit,
+        stacks_epoch_id,
+        block_limit_hit: BlockLimitFunction::NO_LIMIT_HIT,
+        bytes_so_far: 0,
+        num_txs: 0,
+        tx_events: vec![],
+        invalidated_txs: vec![],
+        to_drop_and_blacklist: vec![],
+        update_timings: vec![],
+    };
+
+    for initial_tx in initial_txs.iter() {
+        run.tx_events.push(
+            builder
+                .try_mine_tx(epoch_tx, initial_tx, ast_rules)?
+                .convert_to_event(),
+        );
+        run.bytes_so_far = run.bytes_so_far.saturating_add(initial_tx.tx_len());
+    }
+
+    // nakamoto miner tenure start heuristic:
+    //  mine an empty block so you can start your tenure quickly!
+    if is_tenure_start(initial_txs) {
+        info!("Nakamoto miner heuristic: during tenure change blocks, produce a fast short block to begin tenure");
+        return Ok(Assembled {
+            blocked: false,
+            tenure_start: true,
+            block_limit_hit: run.block_limit_hit,
+            tx_events: run.tx_events,
+        });
+    }
+
+    let mut chains = gather_candidates(
+        epoch_tx,
+        mempool,
+        &settings.mempool_settings,
+        &stacks_epoch_id,
+    )?;
+
+    debug!(
+        "Block transaction selection begins (parent height = {tip_height}, strategy = {strategy_name}, {} origins)",
+        chains.len()
+    );
+    let mut blocked = false;
+    let mut considered = 0;
+    'assembly: while run.block_limit_hit != BlockLimitFunction::LIMIT_REACHED {
+        blocked = run.is_blocked();
+        if blocked {
+            debug!("Miner stopping due to preemption");
+            break;
+        }
+        if get_epoch_time_ms() >= run.deadline {
+            debug!(
+                "Miner mining time exceeded ({} ms)",
+                settings.max_miner_time_ms
+            );
+            break;
+        }
+
+        let budget =
+            RemainingBudget::new(&run.block_limit, &epoch_tx.cost_so_far(), run.bytes_so_far);
+        let Some((chain_index, count)) = order.pick(&chains, &budget) else {
+            break;
+        };
+        for _ in 0..count.max(1) {
+            let Some(candidate) = chains[chain_index].txs.pop_front() else {
+                break;
+            };
+            considered += 1;
+            match run.try_mine(epoch_tx, builder, mempool, &candidate) {
+                Mined::Success => {}
+                Mined::Failed => {
+                    chains[chain_index].txs.clear();
+                    break;
+                }
+                Mined::Stop => break 'assembly,
+            }
+        }
+    }
+    debug!("Block transaction selection finished (parent height {}): {} transactions selected ({} considered)", &tip_height, run.num_txs, considered);
+
+    if !run.update_timings.is_empty() {
+        if let Err(e) = mempool.update_tx_time_estimates(&run.update_timings) {
+            warn!("Error while updating time estimates for mempool"; "err" => ?e);
+        }
+    }
+    if !run.to_drop_and_blacklist.is_empty() {
+        let _ = mempool.drop_and_blacklist_txs(&run.to_drop_and_blacklist);
+    }
+    mempool.drop_txs(&run.invalidated_txs)?;
+
+    if let Some(observer) = event_observer {
+        observer.mempool_txs_dropped(run.invalidated_txs, None, MemPoolDropReason::TOO_EXPENSIVE);
+        observer.mempool_txs_dropped(
+            run.to_drop_and_blacklist,
+            None,
+            MemPoolDropReason::PROBLEMATIC,
+        );
+    }
+
+    Ok(Assembled {
+        blocked,
+        tenure_start: false,
+        block_limit_hit: run.block_limit_hit,
+        tx_events: run.tx_events,
+    })
+}
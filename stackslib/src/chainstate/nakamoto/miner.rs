@@ -612,8 +612,11 @@ impl NakamotoBlockBuilder {
         .flatten()
         .collect();
 
+        let assembly = settings.block_assembly.strategy();
+        debug!("Miner: assembling Nakamoto block"; "strategy" => assembly.name());
+
         // TODO: update this mempool check to prioritize signer vote transactions over other transactions
-        let (blocked, tx_events) = match StacksBlockBuilder::select_and_apply_transactions(
+        let (blocked, tx_events) = match assembly.select_and_apply_transactions(
             &mut tenure_tx,
             &mut builder,
             mempool,
@@ -22,6 +22,7 @@ use std::path::PathBuf;
 use clarity::util::secp256k1::Secp256k1PublicKey;
 use clarity::vm::ast::ASTRules;
 use clarity::vm::costs::{ExecutionCost, LimitedCostTracker};
+use clarity::vm::database::state_proofs::StateRootHeader;
 use clarity::vm::database::{BurnStateDB, ClarityDatabase};
 use clarity::vm::events::StacksTransactionEvent;
 use clarity::vm::types::{PrincipalData, StacksAddressExtensions, TupleData};
@@ -116,6 +117,7 @@ use crate::util_lib::db::{
 };
 use crate::{chainstate, monitoring};
 
+pub mod assembly;
 pub mod coordinator;
 pub mod keys;
 pub mod miner;
@@ -729,6 +731,20 @@ impl StacksMessageCodec for NakamotoBlockHeader {
     }
 }
 
+impl StateRootHeader for NakamotoBlockHeader {
+    fn state_index_root(&self) -> &TrieHash {
+        &self.state_index_root
+    }
+
+    fn signer_signature_hash(&self) -> Sha512Trunc256Sum {
+        NakamotoBlockHeader::signer_signature_hash(self)
+    }
+
+    fn signer_signatures(&self) -> &[MessageSignature] {
+        &self.signer_signature
+    }
+}
+
 impl NakamotoBlockHeader {
     /// Calculate the message digest for miners to sign.
     /// This includes all fields _except_ the signatures.
@@ -4432,6 +4448,8 @@ impl NakamotoChainState {
             ));
         }
 
+        let contract_cache = clarity_instance.get_contract_cache();
+
         // begin processing this block
         let SetupBlockResult {
             mut clarity_tx,
@@ -4697,6 +4715,9 @@ impl NakamotoChainState {
 
         monitoring::set_last_block_transaction_count(u64::try_from(block.txs.len()).unwrap());
         monitoring::set_last_execution_cost_observed(&block_execution_cost, &block_limit);
+        if let Some(contract_cache) = contract_cache {
+            monitoring::set_contract_cache_stats(&contract_cache.stats());
+        }
 
         // get burn block stats, for the transaction receipt
         let (parent_burn_block_hash, parent_burn_block_height, parent_burn_block_timestamp) =
@@ -4905,7 +4926,7 @@ impl NakamotoChainState {
     /// Generate a "phantom" transaction to include STXMintEvents for
     /// lockups that could not be attached to a Coinbase transaction
     /// (because the block doesn't have a Coinbase transaction).
-    fn generate_phantom_unlock_tx(
+    pub(crate) fn generate_phantom_unlock_tx(
         events: Vec<StacksTransactionEvent>,
         config: &ChainstateConfig,
         stacks_block_height: u64,
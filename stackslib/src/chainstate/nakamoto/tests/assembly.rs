@@ -0,0 +1,507 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+
+use clarity::vm::ast::ASTRules;
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StacksAddressExtensions};
+use clarity::vm::Value;
+use rusqlite::params;
+use stacks_common::types::chainstate::{
+    BlockHeaderHash, ConsensusHash, StacksAddress, StacksPrivateKey, StacksPublicKey,
+};
+use stacks_common::types::StacksEpochId;
+use stacks_common::util::hash::Hash160;
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::nakamoto::assembly::*;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::miner::BlockBuilderSettings;
+use crate::chainstate::stacks::tests::{
+    get_stacks_account, make_coinbase, make_user_contract_call, make_user_stacks_transfer,
+};
+use crate::chainstate::stacks::{
+    StacksBlockBuilder, StacksTransaction, StacksTransactionSigner, TokenTransferMemo,
+    TransactionAuth, TransactionPayload, TransactionPostConditionMode, TransactionVersion,
+};
+use crate::core::mempool::{MemPoolDB, MemPoolTxInfo, MemPoolTxMetadata};
+use crate::net::test::{TestPeer, TestPeerConfig};
+use crate::util_lib::boot::{boot_code_addr, boot_code_id};
+
+fn make_candidate(
+    tx: StacksTransaction,
+    accept_time: u64,
+    estimated_cost: Option<ExecutionCost>,
+) -> Candidate {
+    let metadata = MemPoolTxMetadata {
+        txid: tx.txid(),
+        len: tx.tx_len(),
+        tx_fee: tx.get_tx_fee(),
+        tenure_consensus_hash: ConsensusHash([0x00; 20]),
+        tenure_block_header_hash: BlockHeaderHash([0x00; 32]),
+        coinbase_height: 1,
+        origin_address: tx.origin_address(),
+        origin_nonce: tx.get_origin_nonce(),
+        sponsor_address: tx.origin_address(),
+        sponsor_nonce: tx.get_origin_nonce(),
+        last_known_origin_nonce: None,
+        last_known_sponsor_nonce: None,
+        accept_time,
+        time_estimate_ms: None,
+    };
+    Candidate {
+        info: MemPoolTxInfo { tx, metadata },
+        estimated_cost,
+    }
+}
+
+fn make_transfer(
+    sender: &StacksPrivateKey,
+    nonce: u64,
+    fee: u64,
+    estimated_cost: Option<ExecutionCost>,
+) -> Candidate {
+    let recipient = PrincipalData::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap();
+    let tx = make_user_stacks_transfer(sender, nonce, fee, &recipient, 1);
+    make_candidate(tx, nonce, estimated_cost)
+}
+
+fn make_chain(txs: Vec<Candidate>) -> OriginChain {
+    OriginChain {
+        origin: txs[0].info.metadata.origin_address,
+        txs: txs.into_iter().collect(),
+    }
+}
+
+fn uniform_cost(amount: u64) -> ExecutionCost {
+    ExecutionCost {
+        write_length: amount,
+        write_count: amount,
+        read_length: amount,
+        read_count: amount,
+        runtime: amount,
+    }
+}
+
+/// A budget with much less room for writes than for anything else
+fn write_constrained_budget() -> RemainingBudget {
+    RemainingBudget {
+        cost: ExecutionCost {
+            write_length: 100,
+            ..uniform_cost(1000)
+        },
+        bytes: 1_000_000,
+    }
+}
+
+#[test]
+fn remaining_budget_fractions() {
+    let budget = RemainingBudget::new(&uniform_cost(100), &uniform_cost(60), 1000);
+    assert_eq!(budget.cost, uniform_cost(40));
+    assert_eq!(
+        budget.bytes,
+        u64::from(crate::chainstate::stacks::MAX_EPOCH_SIZE) - 1000
+    );
+
+    let budget = RemainingBudget {
+        cost: ExecutionCost {
+            runtime: 0,
+            ..uniform_cost(40)
+        },
+        bytes: 100,
+    };
+    let fractions = budget.fractions(
+        &ExecutionCost {
+            runtime: 0,
+            ..uniform_cost(10)
+        },
+        50,
+    );
+    assert_eq!(fractions, [0.0, 0.25, 0.25, 0.25, 0.25, 0.5]);
+    assert_eq!(budget.fractions(&uniform_cost(1), 0)[0], f64::INFINITY);
+}
+
+#[test]
+fn knapsack_prefers_packages_that_spare_scarce_dimensions() {
+    let privks: Vec<_> = (0..4).map(|_| StacksPrivateKey::random()).collect();
+    let mut chains = vec![
+        // a high fee, but almost all of the remaining write length
+        make_chain(vec![make_transfer(
+            &privks[0],
+            0,
+            1000,
+            Some(ExecutionCost {
+                write_length: 90,
+                ..uniform_cost(10)
+            }),
+        )]),
+        // a lower fee, but cheap
+        make_chain(vec![make_transfer(
+            &privks[1],
+            0,
+            500,
+            Some(uniform_cost(10)),
+        )]),
+        // a cheap, low-fee transaction that a high-fee descendant pays for
+        make_chain(vec![
+            make_transfer(&privks[2], 0, 10, Some(uniform_cost(1))),
+            make_transfer(&privks[2], 1, 1000, Some(uniform_cost(1))),
+        ]),
+    ];
+    let budget = write_constrained_budget();
+
+    assert_eq!(KnapsackOrder.pick(&chains, &budget), Some((2, 2)));
+    // the greedy-by-head order only sees the low-fee parent
+    assert_eq!(MaxFeePerDimensionOrder.pick(&chains, &budget), Some((1, 1)));
+
+    chains[2].txs.clear();
+    assert_eq!(KnapsackOrder.pick(&chains, &budget), Some((1, 1)));
+    chains[1].txs.clear();
+    assert_eq!(KnapsackOrder.pick(&chains, &budget), Some((0, 1)));
+
+    // transactions that do not fit are passed over for ones without an estimate
+    let chains = vec![
+        make_chain(vec![make_transfer(
+            &privks[0],
+            0,
+            1000,
+            Some(ExecutionCost {
+                write_length: 101,
+                ..uniform_cost(1)
+            }),
+        )]),
+        make_chain(vec![make_transfer(&privks[1], 0, 1, None)]),
+        make_chain(vec![make_transfer(&privks[3], 0, 2, None)]),
+    ];
+    assert_eq!(KnapsackOrder.pick(&chains, &budget), Some((2, 1)));
+    assert_eq!(MaxFeePerDimensionOrder.pick(&chains, &budget), Some((2, 1)));
+}
+
+#[test]
+fn fair_order_round_robins_origins() {
+    let privks: Vec<_> = (0..3).map(|_| StacksPrivateKey::random()).collect();
+    let mut chains = vec![
+        make_chain(vec![
+            make_transfer(&privks[0], 0, 1, None),
+            make_transfer(&privks[0], 1, 1, None),
+        ]),
+        make_chain(vec![make_transfer(&privks[1], 0, 1000, None)]),
+        make_chain(vec![
+            make_transfer(&privks[2], 0, 1, None),
+            make_transfer(&privks[2], 1, 1, None),
+        ]),
+    ];
+    let budget = write_constrained_budget();
+
+    let mut order = FairOrder::default();
+    let mut picked = vec![];
+    while let Some((chain_index, count)) = order.pick(&chains, &budget) {
+        assert_eq!(count, 1);
+        chains[chain_index].txs.pop_front().unwrap();
+        picked.push(chain_index);
+    }
+    assert_eq!(picked, vec![0, 1, 2, 0, 2]);
+}
+
+#[test]
+fn priority_lanes_order_only_picks_lane_calls() {
+    let privks: Vec<_> = (0..4).map(|_| StacksPrivateKey::random()).collect();
+    let lane = QualifiedContractIdentifier::parse("ST000000000000000000002AMW42H.bns").unwrap();
+    let contracts = HashSet::from([lane.clone()]);
+    let call = |privk: &StacksPrivateKey, nonce, fee, contract: &QualifiedContractIdentifier| {
+        let tx = make_user_contract_call(
+            privk,
+            nonce,
+            fee,
+            &contract.issuer.clone().into(),
+            contract.name.as_str(),
+            "name-resolve",
+            vec![],
+        );
+        make_candidate(tx, nonce, None)
+    };
+    let other = QualifiedContractIdentifier::parse("ST000000000000000000002AMW42H.pox-4").unwrap();
+
+    let mut chains = vec![
+        make_chain(vec![make_transfer(&privks[0], 0, 5000, None)]),
+        make_chain(vec![call(&privks[1], 0, 10, &lane)]),
+        make_chain(vec![
+            call(&privks[2], 0, 20, &lane),
+            call(&privks[2], 1, 5000, &other),
+        ]),
+        make_chain(vec![call(&privks[3], 0, 5000, &other)]),
+    ];
+    let budget = write_constrained_budget();
+    let mut order = PriorityLanesOrder {
+        contracts: &contracts,
+    };
+
+    assert_eq!(order.pick(&chains, &budget), Some((2, 1)));
+    chains[2].txs.pop_front();
+    assert_eq!(order.pick(&chains, &budget), Some((1, 1)));
+    chains[1].txs.pop_front();
+    assert_eq!(order.pick(&chains, &budget), None);
+    assert_eq!(chains.iter().map(|c| c.txs.len()).sum::<usize>(), 3);
+}
+
+/// The transactions that `assemble_block` submits to the mempool, in order of arrival
+struct MempoolTxs {
+    /// An origin's first two transactions
+    chain: [Txid; 2],
+    /// The same origin's transaction after a nonce gap
+    gapped: Txid,
+    /// Two origins' transactions, paid for by the same sponsor
+    sponsored: [Txid; 2],
+    /// A low-fee call to the priority lane contract
+    lane_call: Txid,
+    /// A high-fee transfer
+    transfer: Txid,
+}
+
+fn make_sponsored_transfer(
+    origin: &StacksPrivateKey,
+    sponsor: &StacksPrivateKey,
+    sponsor_nonce: u64,
+    tx_fee: u64,
+) -> StacksTransaction {
+    let auth = TransactionAuth::from_p2pkh(origin)
+        .unwrap()
+        .into_sponsored(TransactionAuth::from_p2pkh(sponsor).unwrap())
+        .unwrap();
+    let recipient = PrincipalData::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap();
+    let payload = TransactionPayload::TokenTransfer(recipient, 1, TokenTransferMemo([0; 34]));
+    let mut tx = StacksTransaction::new(TransactionVersion::Testnet, auth, payload);
+    tx.chain_id = 0x80000000;
+    tx.post_condition_mode = TransactionPostConditionMode::Allow;
+    tx.set_origin_nonce(0);
+    tx.set_sponsor_nonce(sponsor_nonce).unwrap();
+    tx.set_tx_fee(tx_fee);
+
+    let mut tx_signer = StacksTransactionSigner::new(&tx);
+    tx_signer.sign_origin(origin).unwrap();
+    tx_signer.sign_sponsor(sponsor).unwrap();
+    tx_signer.get_tx().unwrap()
+}
+
+/// Fill a real mempool with transfers, sponsored transfers, a nonce gap and a priority-lane
+/// call, and mine and process a block with `strategy` on top of the genesis block.  Returns the
+/// block's transactions after the coinbase, and the submitted transactions.
+fn assemble_block(
+    test_name: &str,
+    strategy: &dyn BlockAssemblyStrategy,
+) -> (Vec<Txid>, MempoolTxs) {
+    let privks: Vec<_> = (0..6).map(|_| StacksPrivateKey::random()).collect();
+    let addresses: Vec<_> = privks
+        .iter()
+        .map(|privk| StacksAddress::p2pkh(false, &StacksPublicKey::from_private(privk)))
+        .collect();
+    let (chain_privk, sponsor_privk, lane_privk, transfer_privk) =
+        (&privks[0], &privks[1], &privks[4], &privks[5]);
+
+    let mut peer_config = TestPeerConfig::new(test_name, 2040, 2041);
+    peer_config.initial_balances = addresses
+        .iter()
+        .map(|addr| (addr.to_account_principal(), 1_000_000_000))
+        .collect();
+    let burnchain = peer_config.burnchain.clone();
+    let mut peer = TestPeer::new(peer_config);
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &peer.chainstate_path).unwrap();
+
+    let recipient = PrincipalData::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap();
+    let mempool_txs = vec![
+        make_user_stacks_transfer(chain_privk, 0, 2000, &recipient, 1),
+        make_user_stacks_transfer(chain_privk, 1, 1500, &recipient, 1),
+        make_user_stacks_transfer(chain_privk, 3, 5000, &recipient, 1),
+        make_sponsored_transfer(&privks[2], sponsor_privk, 0, 1200),
+        make_sponsored_transfer(&privks[3], sponsor_privk, 1, 1100),
+        make_user_contract_call(
+            lane_privk,
+            0,
+            300,
+            &boot_code_addr(false),
+            "pox",
+            "allow-contract-caller",
+            vec![
+                Value::Principal(addresses[0].to_account_principal()),
+                Value::none(),
+            ],
+        ),
+        make_user_stacks_transfer(transfer_privk, 0, 3000, &recipient, 1),
+    ];
+    let txids: Vec<_> = mempool_txs.iter().map(|tx| tx.txid()).collect();
+
+    let tip =
+        SortitionDB::get_canonical_burn_chain_tip(peer.sortdb.as_ref().unwrap().conn()).unwrap();
+    let (burn_ops, stacks_block, microblocks) = peer.make_tenure(
+        |ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, _, _| {
+            let parent_tip = StacksChainState::get_genesis_header_info(chainstate.db()).unwrap();
+            let coinbase_tx = make_coinbase(miner, 0);
+
+            for (accept_time, tx) in mempool_txs.iter().enumerate() {
+                mempool
+                    .submit(
+                        chainstate,
+                        sortdb,
+                        &parent_tip.consensus_hash,
+                        &parent_tip.anchored_header.block_hash(),
+                        tx,
+                        None,
+                        &ExecutionCost::max_value(),
+                        &StacksEpochId::Epoch20,
+                    )
+                    .unwrap();
+                // arrive in order, so that the sponsor's nonces are handed out in order
+                mempool
+                    .conn()
+                    .execute(
+                        "UPDATE mempool SET accept_time = ?1 WHERE txid = ?2",
+                        params![accept_time as i64, tx.txid()],
+                    )
+                    .unwrap();
+            }
+
+            let sort_handle = sortdb.index_handle_at_tip();
+            let mut builder = StacksBlockBuilder::make_block_builder(
+                &burnchain,
+                false,
+                &parent_tip,
+                vrf_proof,
+                tip.total_burn,
+                Hash160([0; 20]),
+            )
+            .unwrap();
+            let mut miner_epoch_info = builder
+                .pre_epoch_begin(chainstate, &sort_handle, true)
+                .unwrap();
+            let (mut epoch_tx, _) = builder
+                .epoch_begin(&sort_handle, &mut miner_epoch_info)
+                .unwrap();
+            let (blocked, _) = strategy
+                .select_and_apply_transactions(
+                    &mut epoch_tx,
+                    &mut builder,
+                    &mut mempool,
+                    parent_tip.stacks_block_height,
+                    &[coinbase_tx],
+                    BlockBuilderSettings::limited(),
+                    None,
+                    ASTRules::PrecheckSize,
+                )
+                .unwrap();
+            assert!(!blocked);
+            let block = builder.mine_anchored_block(&mut epoch_tx);
+            builder.epoch_finish(epoch_tx).unwrap();
+            (block, vec![])
+        },
+    );
+    peer.next_burnchain_block(burn_ops);
+    peer.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
+
+    // the block was accepted, and spent the nonces of all but the gapped transaction
+    for (i, nonce) in [2, 2, 1, 1, 1, 1].into_iter().enumerate() {
+        let account = get_stacks_account(&mut peer, &addresses[i].to_account_principal());
+        assert_eq!(account.nonce, nonce, "wrong nonce for account {i}");
+    }
+
+    let block_txids = stacks_block.txs[1..].iter().map(|tx| tx.txid()).collect();
+    let mempool_txs = MempoolTxs {
+        chain: [txids[0], txids[1]],
+        gapped: txids[2],
+        sponsored: [txids[3], txids[4]],
+        lane_call: txids[5],
+        transfer: txids[6],
+    };
+    (block_txids, mempool_txs)
+}
+
+/// Check that a block has all of the mempool's minable transactions, with each origin's in
+/// nonce order
+fn assert_mined_all(block_txids: &[Txid], txs: &MempoolTxs) {
+    let mut expected = vec![
+        txs.chain[0],
+        txs.chain[1],
+        txs.sponsored[0],
+        txs.sponsored[1],
+        txs.lane_call,
+        txs.transfer,
+    ];
+    let mut mined = block_txids.to_vec();
+    expected.sort();
+    mined.sort();
+    assert_eq!(mined, expected);
+    assert!(!block_txids.contains(&txs.gapped));
+
+    let position = |txid| block_txids.iter().position(|t| t == txid).unwrap();
+    assert!(position(&txs.chain[0]) < position(&txs.chain[1]));
+    assert!(position(&txs.sponsored[0]) < position(&txs.sponsored[1]));
+}
+
+#[test]
+fn max_fee_per_dimension_assembles_mempool_block() {
+    let (block_txids, txs) = assemble_block(
+        function_name!(),
+        &*BlockAssembly::MaxFeePerDimension.strategy(),
+    );
+    assert_mined_all(&block_txids, &txs);
+}
+
+#[test]
+fn knapsack_assembles_mempool_block() {
+    let (block_txids, txs) = assemble_block(function_name!(), &*BlockAssembly::Knapsack.strategy());
+    assert_mined_all(&block_txids, &txs);
+}
+
+#[test]
+fn fair_ordering_assembles_mempool_block() {
+    let (block_txids, txs) =
+        assemble_block(function_name!(), &*BlockAssembly::FairOrdering.strategy());
+    assert_mined_all(&block_txids, &txs);
+    // one transaction from each origin in turn, in order of arrival
+    assert_eq!(
+        block_txids,
+        vec![
+            txs.chain[0],
+            txs.sponsored[0],
+            txs.sponsored[1],
+            txs.lane_call,
+            txs.transfer,
+            txs.chain[1],
+        ]
+    );
+}
+
+#[test]
+fn priority_lanes_assembles_mempool_block() {
+    let lane = boot_code_id("pox", false);
+    let (block_txids, txs) = assemble_block(
+        function_name!(),
+        &*BlockAssembly::PriorityLanes(vec![lane]).strategy(),
+    );
+    // the lane call comes first despite its fee, and the greedy walk mines the rest
+    assert_mined_all(&block_txids, &txs);
+    assert_eq!(block_txids[0], txs.lane_call);
+    assert_eq!(
+        block_txids[1..],
+        [
+            txs.transfer,
+            txs.chain[0],
+            txs.chain[1],
+            txs.sponsored[0],
+            txs.sponsored[1],
+        ]
+    );
+}
@@ -154,6 +154,7 @@ fn test_path(name: &str) -> String {
     format!("/tmp/stacks-node-tests/nakamoto-tests/{}", name)
 }
 
+pub mod assembly;
 pub mod node;
 
 #[test]
@@ -23,6 +23,7 @@ use clarity::vm::clarity::{Error as ClarityError, TransactionConnection};
 use clarity::vm::contexts::ContractContext;
 use clarity::vm::costs::cost_functions::ClarityCostFunction;
 use clarity::vm::costs::{ClarityCostFunctionReference, CostStateSummary, LimitedCostTracker};
+use clarity::vm::database::state_proofs::StateProofSigner;
 use clarity::vm::database::{
     ClarityDatabase, DataVariableMetadata, NULL_BURN_STATE_DB, NULL_HEADER_DB,
 };
@@ -221,6 +222,15 @@ pub struct NakamotoSignerEntry {
     pub weight: u32,
 }
 
+impl From<&NakamotoSignerEntry> for StateProofSigner {
+    fn from(entry: &NakamotoSignerEntry) -> Self {
+        StateProofSigner {
+            signing_key: entry.signing_key,
+            weight: entry.weight,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct RewardSet {
     pub rewarded_addresses: Vec<PoxAddress>,
@@ -1381,6 +1391,7 @@ pub mod signers_tests;
 pub mod test {
     use std::collections::{HashMap, HashSet};
     use std::fs;
+    use std::sync::Arc;
 
     use clarity::boot_util::boot_code_addr;
     use clarity::vm::contracts::Contract;
@@ -1861,7 +1872,10 @@ pub mod test {
         account
     }
 
-    fn get_contract(peer: &mut TestPeer, addr: &QualifiedContractIdentifier) -> Option<Contract> {
+    fn get_contract(
+        peer: &mut TestPeer,
+        addr: &QualifiedContractIdentifier,
+    ) -> Option<Arc<Contract>> {
         let contract_opt = with_sortdb(peer, |ref mut chainstate, ref mut sortdb| {
             let (consensus_hash, block_bhh) =
                 SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn()).unwrap();
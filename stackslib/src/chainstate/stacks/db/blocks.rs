@@ -74,7 +74,9 @@ use crate::clarity_vm::database::SortitionDBRef;
 use crate::core::mempool::{MemPoolDB, MAXIMUM_MEMPOOL_TX_CHAINING};
 use crate::core::*;
 use crate::cost_estimates::EstimatorError;
-use crate::monitoring::{set_last_block_transaction_count, set_last_execution_cost_observed};
+use crate::monitoring::{
+    set_contract_cache_stats, set_last_block_transaction_count, set_last_execution_cost_observed,
+};
 use crate::net::relay::Relayer;
 use crate::net::{BlocksInvData, Error as net_error};
 use crate::util_lib::boot::boot_code_id;
@@ -135,6 +137,13 @@ pub enum MemPoolRejection {
     NoTenureChangeViaMempool,
     NoSuchChainTip(ConsensusHash, BlockHeaderHash),
     ConflictingNonceInMempool,
+    InsufficientReplacementFee {
+        expected: u64,
+        actual: u64,
+    },
+    TooManyReplacements {
+        max_replacements: u64,
+    },
     TooMuchChaining {
         max_nonce: u64,
         actual_nonce: u64,
@@ -281,6 +290,16 @@ impl MemPoolRejection {
                 Some(json!({"message": e.to_string()})),
             ),
             ConflictingNonceInMempool => ("ConflictingNonceInMempool", None),
+            InsufficientReplacementFee { expected, actual } => (
+                "InsufficientReplacementFee",
+                Some(json!({
+                    "expected": expected,
+                    "actual": actual})),
+            ),
+            TooManyReplacements { max_replacements } => (
+                "TooManyReplacements",
+                Some(json!({ "max_replacements": max_replacements })),
+            ),
             ContractAlreadyExists(id) => (
                 "ContractAlreadyExists",
                 Some(json!({ "contract_identifier": id.to_string() })),
@@ -5505,6 +5524,8 @@ impl StacksChainState {
         .expect("BUG: Failed to load snapshot for block snapshot during Stacks block processing")
         .parent_burn_header_hash;
 
+        let contract_cache = clarity_instance.get_contract_cache();
+
         let SetupBlockResult {
             mut clarity_tx,
             mut tx_receipts,
@@ -5885,6 +5906,9 @@ impl StacksChainState {
             u64::try_from(block.txs.len()).expect("more than 2^64 txs"),
         );
         set_last_execution_cost_observed(&block_execution_cost, &block_limit);
+        if let Some(contract_cache) = contract_cache {
+            set_contract_cache_stats(&contract_cache.stats());
+        }
 
         // // The coinbase height is the same as the stacks block height in epoch 2.x
         let coinbase_height = new_tip.stacks_block_height;
@@ -6676,7 +6700,7 @@ impl StacksChainState {
         // 2: it must be validly signed.
         let epoch = clarity_connection.get_epoch().clone();
 
-        StacksChainState::process_transaction_precheck(chainstate_config, tx, epoch)
+        StacksChainState::process_transaction_precheck(chainstate_config, tx, epoch, true)
             .map_err(MemPoolRejection::FailedToValidate)?;
 
         // 3: it must pay a tx fee
@@ -17,6 +17,7 @@
 use std::collections::{HashMap, HashSet};
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::{fmt, fs, io};
 
 pub use clarity::vm::analysis::errors::CheckErrors;
@@ -42,7 +43,7 @@ impl StacksChainState {
     pub fn get_contract<T: ClarityConnection>(
         clarity_tx: &mut T,
         contract_id: &QualifiedContractIdentifier,
-    ) -> Result<Option<Contract>, Error> {
+    ) -> Result<Option<Arc<Contract>>, Error> {
         clarity_tx
             .with_clarity_db_readonly(|ref mut db| match db.get_contract(contract_id) {
                 Ok(c) => Ok(Some(c)),
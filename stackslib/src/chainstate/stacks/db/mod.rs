@@ -15,10 +15,11 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::btree_map::Entry;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::prelude::*;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
 use std::{fmt, fs, io};
 
 use clarity::vm::analysis::analysis_db::AnalysisDatabase;
@@ -27,6 +28,7 @@ use clarity::vm::ast::ASTRules;
 use clarity::vm::clarity::TransactionConnection;
 use clarity::vm::contexts::OwnedEnvironment;
 use clarity::vm::costs::{ExecutionCost, LimitedCostTracker};
+use clarity::vm::database::contract_cache::{ContractCache, DEFAULT_CONTRACT_CACHE_SIZE};
 use clarity::vm::database::{
     BurnStateDB, ClarityDatabase, HeadersDB, STXBalance, SqliteConnection, NULL_BURN_STATE_DB,
 };
@@ -86,20 +88,24 @@ use crate::net::atlas::BNS_CHARS_REGEX;
 use crate::net::Error as net_error;
 use crate::util_lib::boot::{boot_code_acc, boot_code_addr, boot_code_id, boot_code_tx_auth};
 use crate::util_lib::db::{
-    query_count, query_row, tx_begin_immediate, tx_busy_handler, DBConn, DBTx, Error as db_error,
-    FromColumn, FromRow, IndexDBConn, IndexDBTx,
+    query_count, query_row, query_rows, tx_begin_immediate, tx_busy_handler, DBConn, DBTx,
+    Error as db_error, FromColumn, FromRow, IndexDBConn, IndexDBTx,
 };
 
 pub mod accounts;
 pub mod blocks;
 pub mod contracts;
 pub mod headers;
+pub mod snapshot;
 pub mod transactions;
 pub mod unconfirmed;
 
 lazy_static! {
     pub static ref TRANSACTION_LOG: bool =
         std::env::var("STACKS_TRANSACTION_LOG") == Ok("1".into());
+    /// Contract caches of the open chainstates, by Clarity MARF path
+    static ref CONTRACT_CACHES: Mutex<HashMap<String, Weak<ContractCache>>> =
+        Mutex::new(HashMap::new());
 }
 
 /// Fault injection struct for various kinds of faults we'd like to introduce into the system
@@ -127,6 +133,9 @@ pub struct StacksChainState {
     pub root_path: String,
     pub unconfirmed_state: Option<UnconfirmedState>,
     pub fault_injection: StacksChainStateFaults,
+    /// If true, then record every processed transaction in the `transactions` table so it can
+    /// be looked up by txid later on (i.e. via the `/v3/transactions/:txid` RPC endpoint).
+    pub txindex: bool,
     marf_opts: Option<MARFOpenOpts>,
 }
 
@@ -299,15 +308,15 @@ impl DBConfig {
         });
         match epoch_id {
             StacksEpochId::Epoch10 => true,
-            StacksEpochId::Epoch20 => version_u32 >= 1 && version_u32 <= 8,
-            StacksEpochId::Epoch2_05 => version_u32 >= 2 && version_u32 <= 8,
-            StacksEpochId::Epoch21 => version_u32 >= 3 && version_u32 <= 8,
-            StacksEpochId::Epoch22 => version_u32 >= 3 && version_u32 <= 8,
-            StacksEpochId::Epoch23 => version_u32 >= 3 && version_u32 <= 8,
-            StacksEpochId::Epoch24 => version_u32 >= 3 && version_u32 <= 8,
-            StacksEpochId::Epoch25 => version_u32 >= 3 && version_u32 <= 8,
-            StacksEpochId::Epoch30 => version_u32 >= 3 && version_u32 <= 8,
-            StacksEpochId::Epoch31 => version_u32 >= 3 && version_u32 <= 8,
+            StacksEpochId::Epoch20 => version_u32 >= 1 && version_u32 <= 9,
+            StacksEpochId::Epoch2_05 => version_u32 >= 2 && version_u32 <= 9,
+            StacksEpochId::Epoch21 => version_u32 >= 3 && version_u32 <= 9,
+            StacksEpochId::Epoch22 => version_u32 >= 3 && version_u32 <= 9,
+            StacksEpochId::Epoch23 => version_u32 >= 3 && version_u32 <= 9,
+            StacksEpochId::Epoch24 => version_u32 >= 3 && version_u32 <= 9,
+            StacksEpochId::Epoch25 => version_u32 >= 3 && version_u32 <= 9,
+            StacksEpochId::Epoch30 => version_u32 >= 3 && version_u32 <= 9,
+            StacksEpochId::Epoch31 => version_u32 >= 3 && version_u32 <= 9,
         }
     }
 }
@@ -429,6 +438,73 @@ impl FromRow<DBConfig> for DBConfig {
     }
 }
 
+/// A record of a processed transaction in the transaction index (i.e. the `transactions` table).
+/// A transaction can have one such record per block that included it, since blocks on different
+/// forks can include the same transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionIndexEntry {
+    pub txid: Txid,
+    /// The index block hash of the block that included this transaction
+    pub index_block_hash: StacksBlockId,
+    /// The hex-encoded transaction, or `BTC(txid)` for burnchain operations
+    pub tx_hex: String,
+    /// Human-readable transaction result
+    pub result: String,
+    /// Position of the transaction in its block.
+    /// Will be `None` for records written before schema version 9.
+    pub tx_index: Option<u32>,
+    /// Hex-encoded, consensus-serialized Clarity result.
+    /// Will be `None` for records written before schema version 9.
+    pub result_hex: Option<String>,
+    /// JSON-encoded list of the events emitted by this transaction.
+    /// Will be `None` for records written before schema version 9.
+    pub events: Option<serde_json::Value>,
+}
+
+impl TransactionIndexEntry {
+    /// Encode a receipt's events the same way the event dispatcher does, but with event
+    /// indexes relative to the transaction instead of the block.
+    pub fn events_to_json(receipt: &StacksTransactionReceipt) -> Result<String, db_error> {
+        let txid = receipt.transaction.txid();
+        let events = receipt
+            .events
+            .iter()
+            .enumerate()
+            .map(|(event_index, event)| {
+                event.json_serialize(event_index, &txid, !receipt.post_condition_aborted)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| db_error::TypeError)?;
+        Ok(serde_json::to_string(&events)?)
+    }
+}
+
+impl FromRow<TransactionIndexEntry> for TransactionIndexEntry {
+    fn from_row(row: &Row) -> Result<TransactionIndexEntry, db_error> {
+        let txid = Txid::from_column(row, "txid")?;
+        let index_block_hash = StacksBlockId::from_column(row, "index_block_hash")?;
+        let tx_hex: String = row.get_unwrap("tx_hex");
+        let result: String = row.get_unwrap("result");
+        let tx_index: Option<u32> = row.get_unwrap("tx_index");
+        let result_hex: Option<String> = row.get_unwrap("result_hex");
+        let events_json: Option<String> = row.get_unwrap("events");
+        let events = events_json
+            .map(|events_json| serde_json::from_str(&events_json))
+            .transpose()
+            .map_err(|_| db_error::ParseError)?;
+
+        Ok(TransactionIndexEntry {
+            txid,
+            index_block_hash,
+            tx_hex,
+            result,
+            tx_index,
+            result_hex,
+            events,
+        })
+    }
+}
+
 impl FromRow<StacksHeaderInfo> for StacksHeaderInfo {
     fn from_row(row: &Row) -> Result<StacksHeaderInfo, db_error> {
         let block_height: u64 = u64::from_column(row, "block_height")?;
@@ -482,6 +558,8 @@ pub type StacksDBConn<'a> = IndexDBConn<'a, (), StacksBlockId>;
 pub struct ClarityTx<'a, 'b> {
     block: ClarityBlockConnection<'a, 'b>,
     pub config: DBConfig,
+    /// Whether or not transactions' signatures are verified before they are processed
+    verify_signatures: bool,
 }
 
 impl ClarityConnection for ClarityTx<'_, '_> {
@@ -513,6 +591,16 @@ impl<'a, 'b> ClarityTx<'a, 'b> {
         self.block.get_epoch()
     }
 
+    /// Process transactions without verifying their signatures.  Only for simulating
+    /// transactions, whose results are never persisted.
+    pub fn skip_signature_verification(&mut self) {
+        self.verify_signatures = false;
+    }
+
+    pub fn verifies_signatures(&self) -> bool {
+        self.verify_signatures
+    }
+
     /// Set the ClarityTx's cost tracker.
     /// Returns the replaced cost tracker.
     fn set_cost_tracker(&mut self, new_tracker: LimitedCostTracker) -> LimitedCostTracker {
@@ -607,6 +695,8 @@ pub struct ChainstateTx<'a> {
     pub blocks_path: String,
     pub tx: StacksDBTx<'a>,
     pub root_path: String,
+    /// Whether or not to store processed transactions in the `transactions` table
+    pub txindex: bool,
 }
 
 impl<'a> ChainstateTx<'a> {
@@ -615,12 +705,14 @@ impl<'a> ChainstateTx<'a> {
         blocks_path: String,
         root_path: String,
         config: DBConfig,
+        txindex: bool,
     ) -> ChainstateTx<'a> {
         ChainstateTx {
             config,
             blocks_path,
             tx,
             root_path,
+            txindex,
         }
     }
 
@@ -641,14 +733,35 @@ impl<'a> ChainstateTx<'a> {
         block_id: &StacksBlockId,
         events: &[StacksTransactionReceipt],
     ) {
-        if *TRANSACTION_LOG {
-            let insert =
-                "INSERT INTO transactions (txid, index_block_hash, tx_hex, result) VALUES (?, ?, ?, ?)";
+        if self.txindex || *TRANSACTION_LOG {
+            let insert = "INSERT OR REPLACE INTO transactions (txid, index_block_hash, tx_hex, result, tx_index, result_hex, events) VALUES (?, ?, ?, ?, ?, ?, ?)";
             for tx_event in events.iter() {
                 let txid = tx_event.transaction.txid();
                 let tx_hex = tx_event.transaction.serialize_to_dbstring();
                 let result = tx_event.result.to_string();
-                let params = params![txid, block_id, tx_hex, result];
+                // Still index the transaction if its result or events can't be encoded; the
+                // affected column is left NULL, the same as for rows predating schema 9.
+                let result_hex = tx_event
+                    .result
+                    .serialize_to_hex()
+                    .inspect_err(|e| {
+                        warn!("Failed to serialize TX result: {:?}", e; "txid" => %txid);
+                    })
+                    .ok();
+                let events_json = TransactionIndexEntry::events_to_json(tx_event)
+                    .inspect_err(|e| {
+                        warn!("Failed to serialize TX events: {:?}", e; "txid" => %txid);
+                    })
+                    .ok();
+                let params = params![
+                    txid,
+                    block_id,
+                    tx_hex,
+                    result,
+                    tx_event.tx_index,
+                    result_hex,
+                    events_json
+                ];
                 if let Err(e) = self.tx.tx().execute(insert, params) {
                     warn!("Failed to log TX: {}", e);
                 }
@@ -676,7 +789,7 @@ impl<'a> DerefMut for ChainstateTx<'a> {
     }
 }
 
-pub const CHAINSTATE_VERSION: &str = "8";
+pub const CHAINSTATE_VERSION: &str = "9";
 
 const CHAINSTATE_INITIAL_SCHEMA: &[&str] = &[
     "PRAGMA foreign_keys = ON;",
@@ -875,6 +988,25 @@ const CHAINSTATE_SCHEMA_3: &[&str] = &[
     "#,
 ];
 
+const CHAINSTATE_SCHEMA_4: &[&str] = &[
+    // new in schema version 9
+    // the transaction index also records each transaction's position in its block, its
+    // consensus-serialized result, and its events, so it can be served over RPC.
+    // Rows written before this migration will have NULL in these columns.
+    r#"
+    ALTER TABLE transactions ADD COLUMN tx_index INTEGER;
+    "#,
+    r#"
+    ALTER TABLE transactions ADD COLUMN result_hex TEXT;
+    "#,
+    r#"
+    ALTER TABLE transactions ADD COLUMN events TEXT;
+    "#,
+    r#"
+    UPDATE db_config SET version = "9";
+    "#,
+];
+
 const CHAINSTATE_INDEXES: &[&str] = &[
     "CREATE INDEX IF NOT EXISTS index_block_hash_to_primary_key ON block_headers(index_block_hash,consensus_hash,block_hash);",
     "CREATE INDEX IF NOT EXISTS block_headers_hash_index ON block_headers(block_hash,block_height);",
@@ -1126,6 +1258,14 @@ impl StacksChainState {
                         tx.execute_batch(cmd)?;
                     }
                 }
+                "8" => {
+                    info!(
+                        "Migrating chainstate schema from version 8 to 9: extend the transaction index"
+                    );
+                    for cmd in CHAINSTATE_SCHEMA_4.iter() {
+                        tx.execute_batch(cmd)?;
+                    }
+                }
                 _ => {
                     error!(
                         "Invalid chain state database: expected version = {}, got {}",
@@ -1804,6 +1944,27 @@ impl StacksChainState {
         Ok(())
     }
 
+    /// Get the contract cache shared by all chainstates that are open on the Clarity MARF at
+    /// `clarity_state_index_root` (e.g. the relayer's and the miner's).  The cache is dropped
+    /// once all of them are closed.
+    ///
+    /// Unconfirmed-state Clarity instances don't use it: contracts deployed in microblocks are
+    /// keyed by the unconfirmed chain tip, which stays the same while the microblock stream it
+    /// covers is extended or replaced.
+    fn shared_contract_cache(clarity_state_index_root: &str) -> Arc<ContractCache> {
+        let mut caches = CONTRACT_CACHES.lock().unwrap_or_else(|e| e.into_inner());
+        caches.retain(|_, cache| cache.strong_count() > 0);
+        if let Some(cache) = caches
+            .get(clarity_state_index_root)
+            .and_then(|cache| cache.upgrade())
+        {
+            return cache;
+        }
+        let cache = Arc::new(ContractCache::new(DEFAULT_CONTRACT_CACHE_SIZE));
+        caches.insert(clarity_state_index_root.to_string(), Arc::downgrade(&cache));
+        cache
+    }
+
     pub fn open_and_exec(
         mainnet: bool,
         chain_id: u32,
@@ -1858,7 +2019,10 @@ impl StacksChainState {
         )
         .map_err(|e| Error::ClarityError(e.into()))?;
 
-        let clarity_state = ClarityInstance::new(mainnet, chain_id, vm_state);
+        let mut clarity_state = ClarityInstance::new(mainnet, chain_id, vm_state);
+        clarity_state.set_contract_cache(Some(StacksChainState::shared_contract_cache(
+            &clarity_state_index_root,
+        )));
 
         let mut chainstate = StacksChainState {
             mainnet,
@@ -1872,6 +2036,7 @@ impl StacksChainState {
             root_path: path_str.to_string(),
             unconfirmed_state: None,
             fault_injection: StacksChainStateFaults::new(),
+            txindex: false,
             marf_opts,
         };
 
@@ -1927,12 +2092,30 @@ impl StacksChainState {
         let clarity_instance = &mut self.clarity_state;
         let inner_tx = StacksDBTx::new(&mut self.state_index, ());
 
-        let chainstate_tx =
-            ChainstateTx::new(inner_tx, blocks_path, self.root_path.clone(), config);
+        let chainstate_tx = ChainstateTx::new(
+            inner_tx,
+            blocks_path,
+            self.root_path.clone(),
+            config,
+            self.txindex,
+        );
 
         Ok((chainstate_tx, clarity_instance))
     }
 
+    /// Look up all of the blocks that have included the given transaction, as recorded by the
+    /// transaction index. The transaction index is only maintained if `txindex` is set (or if
+    /// `STACKS_TRANSACTION_LOG=1`), so an empty list does not mean that the transaction was never
+    /// mined.
+    pub fn get_transaction_index_entries(
+        conn: &Connection,
+        txid: &Txid,
+    ) -> Result<Vec<TransactionIndexEntry>, Error> {
+        let sql = "SELECT * FROM transactions WHERE txid = ?1 ORDER BY id ASC";
+        let args = params![txid];
+        Ok(query_rows(conn, sql, args)?)
+    }
+
     // NOTE: used for testing in the stacks testnet code.
     // DO NOT CALL FROM PRODUCTION
     pub fn clarity_eval_read_only(
@@ -2116,6 +2299,7 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            verify_signatures: true,
         }
     }
 
@@ -2253,6 +2437,7 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            verify_signatures: true,
         }
     }
 
@@ -2329,6 +2514,7 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            verify_signatures: true,
         }
     }
 
@@ -2383,6 +2569,7 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            verify_signatures: true,
         }
     }
 
@@ -2437,6 +2624,7 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            verify_signatures: true,
         }
     }
 
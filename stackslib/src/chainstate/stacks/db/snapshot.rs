@@ -0,0 +1,1561 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Snapshots of the Clarity state MARF at a single block.
+//!
+//! A snapshot is a newline-delimited JSON file of `SnapshotRecord`s, in this order:
+//!  - one `Header`, identifying the block and its `state_index_root`;
+//!  - one `Trie` per block from the first block up to and including the block, by height, with
+//!    the block's trie;
+//!  - one `Data` per side-store data row;
+//!  - one `Metadata` per side-store metadata row written by the block or its ancestors;
+//!  - one `Row` per matching row in the headers DB;
+//!  - one `Row` per sortition in the sortition DB, from the block's sortition back to the first
+//!    sortition.
+//!
+//! Nothing in a snapshot is trusted.  Importing one requires the index block hash and the
+//! `state_index_root` to expect, which the operator gets from a node they already trust.  The tries
+//! are loaded into an empty MARF with all of their hashes recalculated, and the import only succeeds
+//! if the block's root hash is the expected `state_index_root`.  Since each trie's root hash commits
+//! to its ancestors' tries, a tampered trie is rejected, and so is side-store data that does not hash
+//! to its key.
+//!
+//! Side-store metadata is not committed to by the MARF, so it is checked against the imported
+//! tries instead.  Each contract's metadata must be stored at the block that deployed it, per the
+//! contract's commitment in the MARF.  Its source must hash to the commitment's hash, and parsing
+//! and analyzing the source again, in the epoch that the MARF records for that block, must produce
+//! its stored analysis.  The contract's evaluated context (e.g. the values of its constants) is
+//! not re-derived, since that would mean replaying the block that deployed it.  The only metadata
+//! stored at other blocks is the cost-voting contract's tally of votes, which is imported as-is.
+//!
+//! An import writes to three databases, which cannot be committed together.  The rebuilt trie and
+//! its side-store data are committed in one transaction, then the sortition rows, and finally the
+//! block header, so the node does not see the block until everything else is in place.  An import
+//! that is interrupted part-way can be run again: it skips loading tries that are already there,
+//! and skips rows that are already stored.  A row that differs from the stored row with the same
+//! key fails the import.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+#[cfg(any(test, feature = "testing"))]
+use std::path::PathBuf;
+#[cfg(any(test, feature = "testing"))]
+use std::sync::LazyLock;
+
+use clarity::vm::analysis::{run_analysis, AnalysisDatabase, ContractAnalysis};
+use clarity::vm::ast::{build_ast_with_rules, ASTRules};
+use clarity::vm::costs::LimitedCostTracker;
+use clarity::vm::database::clarity_db::ContractDataVarName;
+use clarity::vm::database::sqlite::{
+    sqlite_get_contract_hash, sqlite_get_metadata, sqlite_get_metadata_manual,
+};
+use clarity::vm::database::{
+    ClarityBackingStore, ClarityDatabase, ClarityDeserializable, ClaritySerializable,
+    SqliteConnection, StoreType, NULL_BURN_STATE_DB, NULL_HEADER_DB,
+};
+use clarity::vm::errors::{InterpreterError, InterpreterResult};
+use clarity::vm::types::QualifiedContractIdentifier;
+use clarity::vm::ClarityVersion;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, ErrorCode, OptionalExtension};
+use stacks_common::types::chainstate::{SortitionId, StacksBlockId, TrieHash};
+use stacks_common::util::hash::{hex_bytes, to_hex, Sha512Trunc256Sum};
+#[cfg(any(test, feature = "testing"))]
+use stacks_common::util::tests::TestFlag;
+
+use crate::chainstate::burn::ConsensusHash;
+use crate::chainstate::nakamoto::NakamotoChainState;
+use crate::chainstate::stacks::db::{StacksBlockHeaderTypes, StacksHeaderInfo};
+use crate::chainstate::stacks::index::marf::{MarfConnection, MarfTransaction, MARF};
+use crate::chainstate::stacks::index::{Error as MARFError, MARFValue};
+use crate::chainstate::stacks::Error;
+use crate::util_lib::boot::boot_code_id;
+use crate::util_lib::db::Error as db_error;
+
+/// Version of the snapshot file format
+pub const MARF_SNAPSHOT_VERSION: u32 = 1;
+
+/// Headers DB tables that hold a block's header row
+const SNAPSHOT_HEADER_TABLES: &[&str] = &["nakamoto_block_headers", "block_headers"];
+/// Sortition DB tables that hold the block's sortition history
+const SNAPSHOT_SORTITION_TABLES: &[&str] = &["snapshots"];
+/// Side-store metadata keys are `clr-meta::<contract>::<key>`
+const METADATA_KEY_PREFIX: &str = "clr-meta::";
+/// Metadata key of the cost-voting contract's tally of votes, which is stored at whichever block
+/// last tallied them
+const COST_VOTING_STATE_SUMMARY_KEY: &str = "::state_summary";
+
+#[cfg(any(test, feature = "testing"))]
+/// Fail the import of this snapshot file right after its trie is committed
+pub static TEST_SNAPSHOT_IMPORT_INTERRUPT: LazyLock<TestFlag<Option<PathBuf>>> =
+    LazyLock::new(TestFlag::default);
+
+/// Database that a snapshotted row belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotDB {
+    Headers,
+    Sortition,
+}
+
+/// A single sqlite column value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum SnapshotSqlValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    /// hex-encoded
+    Blob(String),
+}
+
+impl From<SqlValue> for SnapshotSqlValue {
+    fn from(value: SqlValue) -> Self {
+        match value {
+            SqlValue::Null => SnapshotSqlValue::Null,
+            SqlValue::Integer(i) => SnapshotSqlValue::Integer(i),
+            SqlValue::Real(r) => SnapshotSqlValue::Real(r),
+            SqlValue::Text(s) => SnapshotSqlValue::Text(s),
+            SqlValue::Blob(b) => SnapshotSqlValue::Blob(to_hex(&b)),
+        }
+    }
+}
+
+impl SnapshotSqlValue {
+    fn into_sql_value(self) -> Result<SqlValue, Error> {
+        let value = match self {
+            SnapshotSqlValue::Null => SqlValue::Null,
+            SnapshotSqlValue::Integer(i) => SqlValue::Integer(i),
+            SnapshotSqlValue::Real(r) => SqlValue::Real(r),
+            SnapshotSqlValue::Text(s) => SqlValue::Text(s),
+            SnapshotSqlValue::Blob(b) => SqlValue::Blob(
+                hex_bytes(&b).map_err(|_| snapshot_error("Blob column is not valid hex"))?,
+            ),
+        };
+        Ok(value)
+    }
+}
+
+/// One line of a snapshot file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SnapshotRecord {
+    Header {
+        version: u32,
+        index_block_hash: StacksBlockId,
+        consensus_hash: ConsensusHash,
+        block_height: u64,
+        state_index_root: TrieHash,
+    },
+    Trie {
+        block_id: StacksBlockId,
+        /// hex-encoded trie blob, as exported by `MARF::export_tries()`
+        trie: String,
+    },
+    Data {
+        /// hex-encoded `MARFValue` of `value`
+        key: String,
+        value: String,
+    },
+    Metadata {
+        blockhash: StacksBlockId,
+        key: String,
+        value: Option<String>,
+    },
+    Row {
+        db: SnapshotDB,
+        table: String,
+        columns: Vec<(String, SnapshotSqlValue)>,
+    },
+}
+
+/// Summary of an imported snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotImportSummary {
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    pub state_index_root: TrieHash,
+    pub num_tries: u64,
+}
+
+fn snapshot_error(msg: &str) -> Error {
+    Error::DBError(db_error::Other(format!("Invalid MARF snapshot: {msg}")))
+}
+
+/// Interrupt a snapshot import for testing
+#[cfg(any(test, feature = "testing"))]
+fn fault_injection_interrupt_import(snapshot_path: &Path) -> Result<(), Error> {
+    if TEST_SNAPSHOT_IMPORT_INTERRUPT.get().as_deref() == Some(snapshot_path) {
+        warn!("Snapshot import is interrupted due to testing directive");
+        return Err(snapshot_error("import interrupted"));
+    }
+    Ok(())
+}
+
+/// Get a text column from a snapshotted row
+fn get_text_column<'a>(columns: &'a [(String, SnapshotSqlValue)], name: &str) -> Option<&'a str> {
+    columns.iter().find_map(|(column, value)| match value {
+        SnapshotSqlValue::Text(text) if column == name => Some(text.as_str()),
+        _ => None,
+    })
+}
+
+fn get_state_index_root(header: &StacksHeaderInfo) -> TrieHash {
+    match &header.anchored_header {
+        StacksBlockHeaderTypes::Epoch2(header) => header.state_index_root.clone(),
+        StacksBlockHeaderTypes::Nakamoto(header) => header.state_index_root.clone(),
+    }
+}
+
+fn write_record<W: Write>(out: &mut W, record: &SnapshotRecord) -> Result<(), MARFError> {
+    serde_json::to_writer(&mut *out, record).map_err(std::io::Error::from)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Write out every row of `table` in `conn` whose `key_column` is `key`
+fn write_rows<W: Write>(
+    out: &mut W,
+    conn: &Connection,
+    db: SnapshotDB,
+    table: &str,
+    key_column: &str,
+    key: &str,
+) -> Result<(), Error> {
+    let sql = format!("SELECT * FROM {table} WHERE {key_column} = ?1");
+    let mut stmt = conn.prepare(&sql)?;
+    let column_names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let mut rows = stmt.query(params![key])?;
+    while let Some(row) = rows.next()? {
+        let mut columns = Vec::with_capacity(column_names.len());
+        for (i, name) in column_names.iter().enumerate() {
+            let value: SqlValue = row.get(i)?;
+            columns.push((name.clone(), value.into()));
+        }
+        let record = SnapshotRecord::Row {
+            db,
+            table: table.to_string(),
+            columns,
+        };
+        write_record(out, &record)?;
+    }
+    Ok(())
+}
+
+/// Write a snapshot of the Clarity state at `index_block_hash` to `out`.
+///
+/// `side_store` is a separate connection to the sqlite DB that backs `clarity_marf`.
+pub fn export_marf_snapshot<W: Write>(
+    clarity_marf: &mut MARF<StacksBlockId>,
+    side_store: &Connection,
+    headers_conn: &Connection,
+    sortdb_conn: &Connection,
+    index_block_hash: &StacksBlockId,
+    out: &mut W,
+) -> Result<(), Error> {
+    let header = NakamotoChainState::get_block_header(headers_conn, index_block_hash)?
+        .ok_or(Error::NoSuchBlockError)?;
+    let state_index_root = get_state_index_root(&header);
+    let root_hash = clarity_marf.get_root_hash_at(index_block_hash)?;
+    if root_hash != state_index_root {
+        return Err(Error::InvalidChainstateDB);
+    }
+
+    write_record(
+        out,
+        &SnapshotRecord::Header {
+            version: MARF_SNAPSHOT_VERSION,
+            index_block_hash: index_block_hash.clone(),
+            consensus_hash: header.consensus_hash.clone(),
+            block_height: header.stacks_block_height,
+            state_index_root,
+        },
+    )?;
+
+    let mut blocks = vec![];
+    clarity_marf.export_tries(index_block_hash, |block_id, trie_blob| {
+        blocks.push(block_id.clone());
+        write_record(
+            out,
+            &SnapshotRecord::Trie {
+                block_id: block_id.clone(),
+                trie: to_hex(&trie_blob),
+            },
+        )
+    })?;
+
+    let mut stmt = side_store.prepare("SELECT key, value FROM data_table")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let record = SnapshotRecord::Data {
+            key: row.get(0)?,
+            value: row.get(1)?,
+        };
+        write_record(out, &record)?;
+    }
+
+    let mut stmt =
+        side_store.prepare("SELECT key, value FROM metadata_table WHERE blockhash = ?1")?;
+    for blockhash in blocks.iter() {
+        let mut rows = stmt.query(params![blockhash])?;
+        while let Some(row) = rows.next()? {
+            let record = SnapshotRecord::Metadata {
+                blockhash: blockhash.clone(),
+                key: row.get(0)?,
+                value: row.get(1)?,
+            };
+            write_record(out, &record)?;
+        }
+    }
+
+    for table in SNAPSHOT_HEADER_TABLES {
+        write_rows(
+            out,
+            headers_conn,
+            SnapshotDB::Headers,
+            table,
+            "index_block_hash",
+            &index_block_hash.to_hex(),
+        )?;
+    }
+    // the block's sortition, and every sortition before it
+    let mut sortition_id: SortitionId = sortdb_conn
+        .query_row(
+            "SELECT sortition_id FROM snapshots WHERE consensus_hash = ?1",
+            params![header.consensus_hash],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or(Error::NoSuchBlockError)?;
+    loop {
+        for table in SNAPSHOT_SORTITION_TABLES {
+            write_rows(
+                out,
+                sortdb_conn,
+                SnapshotDB::Sortition,
+                table,
+                "sortition_id",
+                &sortition_id.to_hex(),
+            )?;
+        }
+        let parent_sortition_id: SortitionId = sortdb_conn.query_row(
+            "SELECT parent_sortition_id FROM snapshots WHERE sortition_id = ?1",
+            params![sortition_id],
+            |row| row.get(0),
+        )?;
+        if parent_sortition_id == sortition_id {
+            // the first sortition is its own parent
+            break;
+        }
+        sortition_id = parent_sortition_id;
+    }
+    out.flush().map_err(Error::WriteError)?;
+    Ok(())
+}
+
+/// Iterate over the records in a snapshot file
+fn read_records(
+    snapshot_path: &Path,
+) -> Result<impl Iterator<Item = Result<SnapshotRecord, Error>>, Error> {
+    let file = File::open(snapshot_path).map_err(Error::ReadError)?;
+    let records = BufReader::new(file).lines().map(|line| {
+        let line = line.map_err(Error::ReadError)?;
+        serde_json::from_str(&line).map_err(|e| Error::DBError(db_error::SerializationError(e)))
+    });
+    Ok(records)
+}
+
+/// Insert a snapshotted row into `conn`, unless the very same row is already there (e.g. the
+/// first sortition, or a row from an interrupted import).  Fails if a different row has the same
+/// key.
+fn insert_row(
+    conn: &Connection,
+    table: &str,
+    columns: Vec<(String, SnapshotSqlValue)>,
+) -> Result<(), Error> {
+    let mut names = Vec::with_capacity(columns.len());
+    let mut values = Vec::with_capacity(columns.len());
+    for (name, value) in columns.into_iter() {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(snapshot_error(&format!("bad column name '{name}'")));
+        }
+        names.push(name);
+        values.push(value.into_sql_value()?);
+    }
+    let matches: Vec<_> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| format!("{name} IS ?{}", i + 1))
+        .collect();
+    let sql = format!("SELECT 1 FROM {table} WHERE {}", matches.join(" AND "));
+    let exists = conn
+        .query_row(&sql, params_from_iter(values.iter()), |_| Ok(()))
+        .optional()?
+        .is_some();
+    if exists {
+        return Ok(());
+    }
+    let placeholders: Vec<_> = (1..=values.len()).map(|i| format!("?{i}")).collect();
+    let sql = format!(
+        "INSERT INTO {table} ({}) VALUES ({})",
+        names.join(", "),
+        placeholders.join(", ")
+    );
+    match conn.execute(&sql, params_from_iter(values)) {
+        Ok(_) => Ok(()),
+        Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == ErrorCode::ConstraintViolation => {
+            Err(snapshot_error(&format!(
+                "row differs from the existing row in '{table}'"
+            )))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Check that the snapshotted sortition rows are the history of the sortition with
+/// `consensus_hash`, in order, and that it starts at the first sortition in `sortdb_conn`.
+fn check_sortition_history(
+    sortdb_conn: &Connection,
+    consensus_hash: &ConsensusHash,
+    sortitions: &[Vec<(String, SnapshotSqlValue)>],
+) -> Result<(), Error> {
+    let mut expected_sortition_id = None;
+    for (i, columns) in sortitions.iter().enumerate() {
+        let (Some(sortition_id), Some(parent_sortition_id)) = (
+            get_text_column(columns, "sortition_id"),
+            get_text_column(columns, "parent_sortition_id"),
+        ) else {
+            return Err(snapshot_error("sortition row is missing its sortition IDs"));
+        };
+        if i == 0 && get_text_column(columns, "consensus_hash") != Some(&consensus_hash.to_hex()) {
+            return Err(snapshot_error(
+                "sortition history is not for the block's sortition",
+            ));
+        }
+        if expected_sortition_id.is_some_and(|expected| expected != sortition_id) {
+            return Err(snapshot_error("sortition history is out of order"));
+        }
+        if parent_sortition_id == sortition_id {
+            // this is the first sortition, which the node already has
+            let first_sortition: Option<String> = sortdb_conn
+                .query_row(
+                    "SELECT sortition_id FROM snapshots WHERE sortition_id = parent_sortition_id",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if first_sortition.as_deref() != Some(sortition_id) {
+                return Err(snapshot_error(
+                    "sortition history does not start at this node's first sortition",
+                ));
+            }
+            if i + 1 != sortitions.len() {
+                return Err(snapshot_error(
+                    "sortition history continues past the first sortition",
+                ));
+            }
+            return Ok(());
+        }
+        expected_sortition_id = Some(parent_sortition_id);
+    }
+    Err(snapshot_error(
+        "sortition history does not reach the first sortition",
+    ))
+}
+
+/// Import the snapshot at `snapshot_path`, which must be of the Clarity state at
+/// `index_block_hash` with root hash `state_index_root`.  The caller must get both from a source
+/// it trusts, since the snapshot itself is not trusted.
+///
+/// `clarity_marf` must be empty, and is loaded with the snapshot's tries.  The snapshot's header
+/// and sortition rows are written to `headers_conn` and `sortdb_conn`, which must already have
+/// their schemas.  Nothing is stored unless the block's recalculated root hash is
+/// `state_index_root`.  If an earlier import of the same block was interrupted, this finishes
+/// it.
+pub fn import_marf_snapshot(
+    snapshot_path: &Path,
+    index_block_hash: &StacksBlockId,
+    state_index_root: &TrieHash,
+    clarity_marf: &mut MARF<StacksBlockId>,
+    headers_conn: &mut Connection,
+    sortdb_conn: &mut Connection,
+) -> Result<SnapshotImportSummary, Error> {
+    let mut records = read_records(snapshot_path)?;
+    let Some(SnapshotRecord::Header {
+        version,
+        index_block_hash: snapshot_index_block_hash,
+        consensus_hash,
+        block_height,
+        state_index_root: snapshot_state_index_root,
+    }) = records.next().transpose()?
+    else {
+        return Err(snapshot_error("does not start with a header"));
+    };
+    if version != MARF_SNAPSHOT_VERSION {
+        return Err(snapshot_error(&format!("unsupported version {version}")));
+    }
+    if snapshot_index_block_hash != *index_block_hash {
+        return Err(snapshot_error(&format!(
+            "snapshot is of {snapshot_index_block_hash}, not {index_block_hash}"
+        )));
+    }
+    if snapshot_state_index_root != *state_index_root {
+        return Err(snapshot_error(&format!(
+            "snapshot has state_index_root {snapshot_state_index_root}, not {state_index_root}"
+        )));
+    }
+
+    // first, stage the header and sortition rows
+    let headers_tx = headers_conn.transaction()?;
+    let sortdb_tx = sortdb_conn.transaction()?;
+    let mut sortitions = vec![];
+    for record in records {
+        match record? {
+            SnapshotRecord::Row { db, table, columns } => {
+                let (conn, tables) = match db {
+                    SnapshotDB::Headers => (&*headers_tx, SNAPSHOT_HEADER_TABLES),
+                    SnapshotDB::Sortition => (&*sortdb_tx, SNAPSHOT_SORTITION_TABLES),
+                };
+                if !tables.contains(&table.as_str()) {
+                    return Err(snapshot_error(&format!("unexpected table '{table}'")));
+                }
+                if db == SnapshotDB::Sortition {
+                    sortitions.push(columns.clone());
+                }
+                insert_row(conn, &table, columns)?;
+            }
+            _ => {}
+        }
+    }
+    // the imported block header must agree with the expected block
+    let header = NakamotoChainState::get_block_header(&headers_tx, index_block_hash)?
+        .ok_or_else(|| snapshot_error("missing the block header"))?;
+    if header.consensus_hash != consensus_hash
+        || header.stacks_block_height != block_height
+        || get_state_index_root(&header) != *state_index_root
+    {
+        return Err(snapshot_error("header does not match the block header"));
+    }
+    check_sortition_history(&sortdb_tx, &consensus_hash, &sortitions)?;
+
+    // second, load the tries and their side store, unless an interrupted import already did
+    let mut num_tries = 0;
+    match clarity_marf.get_root_hash_at(index_block_hash) {
+        Ok(root_hash) if root_hash == *state_index_root => {
+            info!("Trie for {index_block_hash} is already imported");
+        }
+        Ok(root_hash) => {
+            return Err(snapshot_error(&format!(
+                "existing trie for {index_block_hash} has root hash {root_hash}"
+            )));
+        }
+        Err(MARFError::NotFoundError) => {
+            let tries = read_records(snapshot_path)?.filter_map(|record| match record {
+                Ok(SnapshotRecord::Trie { block_id, trie }) => {
+                    num_tries += 1;
+                    Some(
+                        hex_bytes(&trie)
+                            .map(|trie_blob| (block_id.clone(), trie_blob))
+                            .map_err(|_| {
+                                MARFError::CorruptionError(format!(
+                                    "Trie for {block_id} is not valid hex"
+                                ))
+                            }),
+                    )
+                }
+                Ok(_) => None,
+                Err(e) => Some(Err(MARFError::CorruptionError(e.to_string()))),
+            });
+            clarity_marf.import_tries(index_block_hash, tries, state_index_root, |marf_tx| {
+                load_side_store(snapshot_path, marf_tx)
+                    .and_then(|_| verify_metadata(marf_tx, index_block_hash))
+                    .map_err(|e| MARFError::CorruptionError(e.to_string()))
+            })?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fault_injection_interrupt_import(snapshot_path)?;
+
+    // finally, store the sortition rows and the header
+    sortdb_tx.commit()?;
+    headers_tx.commit()?;
+
+    Ok(SnapshotImportSummary {
+        index_block_hash: index_block_hash.clone(),
+        block_height,
+        state_index_root: state_index_root.clone(),
+        num_tries,
+    })
+}
+
+/// Write the snapshot's side-store data and metadata.  Each data row must hash to its key.
+fn load_side_store(
+    snapshot_path: &Path,
+    marf_tx: &mut MarfTransaction<StacksBlockId>,
+) -> Result<(), Error> {
+    let side_store_tx = marf_tx.sqlite_tx();
+    SqliteConnection::initialize_conn(side_store_tx)?;
+    for record in read_records(snapshot_path)? {
+        match record? {
+            SnapshotRecord::Data { key, value } => {
+                if MARFValue::from_value(&value).to_hex() != key {
+                    return Err(snapshot_error(&format!(
+                        "data for {key} has the wrong hash"
+                    )));
+                }
+                side_store_tx.execute(
+                    "REPLACE INTO data_table (key, value) VALUES (?1, ?2)",
+                    params![key, value],
+                )?;
+            }
+            SnapshotRecord::Metadata {
+                blockhash,
+                key,
+                value,
+            } => {
+                side_store_tx.execute(
+                    "INSERT INTO metadata_table (blockhash, key, value) VALUES (?1, ?2, ?3)",
+                    params![blockhash, key, value],
+                )?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Check the side-store metadata that `load_side_store()` wrote against the tries imported up to
+/// `index_block_hash`, which are not committed yet.
+fn verify_metadata(
+    marf_tx: &mut MarfTransaction<StacksBlockId>,
+    index_block_hash: &StacksBlockId,
+) -> Result<(), Error> {
+    let tip_height = marf_tx
+        .get_block_height(index_block_hash, index_block_hash)?
+        .ok_or(Error::NoSuchBlockError)?;
+    // the block's own height maps to the hash it was built under
+    let mut blocks = HashSet::from([index_block_hash.clone()]);
+    for height in 0..tip_height {
+        blocks.extend(marf_tx.get_block_at_height(height, index_block_hash)?);
+    }
+
+    // the keys of each contract's metadata, with the blocks they are stored at
+    let mut contracts: BTreeMap<QualifiedContractIdentifier, Vec<(StacksBlockId, String)>> =
+        BTreeMap::new();
+    {
+        let mut stmt = marf_tx
+            .sqlite_tx()
+            .prepare("SELECT blockhash, key FROM metadata_table")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let blockhash: StacksBlockId = row.get(0)?;
+            let key: String = row.get(1)?;
+            if !blocks.contains(&blockhash) {
+                return Err(snapshot_error(&format!(
+                    "metadata {key} is stored at {blockhash}, which is not an imported block"
+                )));
+            }
+            let contract = key
+                .strip_prefix(METADATA_KEY_PREFIX)
+                .and_then(|key| key.split_once("::"))
+                .and_then(|(contract, contract_key)| {
+                    let contract = QualifiedContractIdentifier::parse(contract).ok()?;
+                    Some((contract, contract_key.to_string()))
+                });
+            let Some((contract, contract_key)) = contract else {
+                return Err(snapshot_error(&format!("unexpected metadata key {key}")));
+            };
+            contracts
+                .entry(contract)
+                .or_default()
+                .push((blockhash, contract_key));
+        }
+    }
+
+    for (contract, keys) in contracts.iter() {
+        let mut store = SnapshotStore {
+            marf: &mut *marf_tx,
+            tip: index_block_hash.clone(),
+        };
+        let (deploy_block, contract_hash) = store.get_contract_hash(contract).map_err(|_| {
+            snapshot_error(&format!("metadata for {contract}, which is not deployed"))
+        })?;
+        let is_cost_voting = [true, false]
+            .iter()
+            .any(|mainnet| *contract == boot_code_id("cost-voting", *mainnet));
+        for (blockhash, key) in keys.iter() {
+            if *blockhash != deploy_block
+                && !(is_cost_voting && key == COST_VOTING_STATE_SUMMARY_KEY)
+            {
+                return Err(snapshot_error(&format!(
+                    "metadata {key} of {contract} is stored at {blockhash}, not at {deploy_block}"
+                )));
+            }
+        }
+        store.tip = deploy_block;
+        verify_contract(&mut store, contract, &contract_hash)?;
+    }
+    Ok(())
+}
+
+/// Check a contract's source and analysis.  `store` must be at the block that deployed it.
+fn verify_contract(
+    store: &mut SnapshotStore,
+    contract: &QualifiedContractIdentifier,
+    contract_hash: &Sha512Trunc256Sum,
+) -> Result<(), Error> {
+    let invalid = |what: &str| snapshot_error(&format!("{what} of {contract}"));
+    let mut get_metadata = |key: &str| {
+        store
+            .get_metadata(contract, key)
+            .map_err(|e| snapshot_error(&format!("failed to read {key} of {contract}: {e}")))
+    };
+
+    let src_key = ClarityDatabase::make_metadata_key(
+        StoreType::Contract,
+        ContractDataVarName::ContractSrc.as_str(),
+    );
+    let source = get_metadata(&src_key)?.ok_or_else(|| invalid("missing source"))?;
+    if Sha512Trunc256Sum::from_data(source.as_bytes()) != *contract_hash {
+        return Err(invalid("wrong source"));
+    }
+    let size_key = ClarityDatabase::make_metadata_key(
+        StoreType::Contract,
+        ContractDataVarName::ContractSize.as_str(),
+    );
+    if get_metadata(&size_key)? != Some((source.len() as u64).serialize()) {
+        return Err(invalid("wrong size"));
+    }
+    let stored_analysis = get_metadata(AnalysisDatabase::storage_key())?
+        .ok_or_else(|| invalid("missing analysis"))?;
+
+    // the Clarity version is chosen by the deploying transaction, which is not in the snapshot,
+    // but it must be one that the epoch supports
+    let version = ContractAnalysis::deserialize(&stored_analysis)
+        .map_err(|_| invalid("malformed analysis"))?
+        .clarity_version;
+    let epoch = {
+        let mut clarity_db = ClarityDatabase::new(store, &NULL_HEADER_DB, &NULL_BURN_STATE_DB);
+        clarity_db.begin();
+        let epoch = clarity_db.get_clarity_epoch_version();
+        clarity_db
+            .roll_back()
+            .and(epoch)
+            .map_err(|e| snapshot_error(&format!("failed to read the epoch of {contract}: {e}")))?
+    };
+    if version > ClarityVersion::default_for_epoch(epoch) {
+        return Err(invalid("unsupported Clarity version"));
+    }
+
+    let contract_ast = build_ast_with_rules(
+        contract,
+        &source,
+        &mut (),
+        version,
+        epoch,
+        ASTRules::Typical,
+    )
+    .map_err(|_| invalid("unparseable source"))?;
+    let analysis = run_analysis(
+        contract,
+        &contract_ast.expressions,
+        &mut AnalysisDatabase::new(store),
+        false,
+        LimitedCostTracker::new_free(),
+        epoch,
+        version,
+        false,
+    )
+    .map_err(|_| invalid("failed analysis"))?;
+    if analysis.serialize() != stored_analysis {
+        return Err(invalid("wrong analysis"));
+    }
+    Ok(())
+}
+
+/// A read-only view of the Clarity state in a MARF that is still being imported, at `tip`.  No
+/// block is open for writing, so `tip` stands in for the open chain tip.
+struct SnapshotStore<'a, 'b> {
+    marf: &'a mut MarfTransaction<'b, StacksBlockId>,
+    tip: StacksBlockId,
+}
+
+impl SnapshotStore<'_, '_> {
+    fn read_only_error() -> clarity::vm::errors::Error {
+        InterpreterError::Expect("Attempted to write to an imported snapshot".into()).into()
+    }
+
+    fn unsupported_error() -> clarity::vm::errors::Error {
+        InterpreterError::Expect("Proofs are not available from an imported snapshot".into()).into()
+    }
+}
+
+impl ClarityBackingStore for SnapshotStore<'_, '_> {
+    fn put_all_data(&mut self, _items: Vec<(String, String)>) -> InterpreterResult<()> {
+        Err(Self::read_only_error())
+    }
+
+    fn get_data(&mut self, key: &str) -> InterpreterResult<Option<String>> {
+        let marf_value = match self.marf.get(&self.tip, key) {
+            Ok(marf_value) => marf_value,
+            Err(MARFError::NotFoundError) => None,
+            Err(e) => return Err(InterpreterError::MarfFailure(e.to_string()).into()),
+        };
+        let Some(marf_value) = marf_value else {
+            return Ok(None);
+        };
+        let side_key = marf_value.to_hex();
+        SqliteConnection::get(self.marf.sqlite_tx(), &side_key)?
+            .ok_or_else(|| {
+                InterpreterError::Expect(format!(
+                    "MARF contained value_hash not found in side storage: {side_key}"
+                ))
+                .into()
+            })
+            .map(Some)
+    }
+
+    fn get_data_from_path(&mut self, _hash: &TrieHash) -> InterpreterResult<Option<String>> {
+        Err(Self::unsupported_error())
+    }
+
+    fn get_data_with_proof(&mut self, _key: &str) -> InterpreterResult<Option<(String, Vec<u8>)>> {
+        Err(Self::unsupported_error())
+    }
+
+    fn get_data_with_proof_from_path(
+        &mut self,
+        _hash: &TrieHash,
+    ) -> InterpreterResult<Option<(String, Vec<u8>)>> {
+        Err(Self::unsupported_error())
+    }
+
+    fn set_block_hash(&mut self, bhh: StacksBlockId) -> InterpreterResult<StacksBlockId> {
+        Ok(std::mem::replace(&mut self.tip, bhh))
+    }
+
+    fn get_block_at_height(&mut self, height: u32) -> Option<StacksBlockId> {
+        self.marf.get_block_at_height(height, &self.tip).ok()?
+    }
+
+    fn get_current_block_height(&mut self) -> u32 {
+        self.marf
+            .get_block_height(&self.tip, &self.tip)
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| panic!("Unexpected MARF failure: no height for {}", &self.tip))
+    }
+
+    fn get_open_chain_tip_height(&mut self) -> u32 {
+        self.get_current_block_height()
+    }
+
+    fn get_open_chain_tip(&mut self) -> StacksBlockId {
+        self.tip.clone()
+    }
+
+    fn get_side_store(&mut self) -> &Connection {
+        self.marf.sqlite_tx()
+    }
+
+    fn get_contract_hash(
+        &mut self,
+        contract: &QualifiedContractIdentifier,
+    ) -> InterpreterResult<(StacksBlockId, Sha512Trunc256Sum)> {
+        sqlite_get_contract_hash(self, contract)
+    }
+
+    fn insert_metadata(
+        &mut self,
+        _contract: &QualifiedContractIdentifier,
+        _key: &str,
+        _value: &str,
+    ) -> InterpreterResult<()> {
+        Err(Self::read_only_error())
+    }
+
+    fn get_metadata(
+        &mut self,
+        contract: &QualifiedContractIdentifier,
+        key: &str,
+    ) -> InterpreterResult<Option<String>> {
+        sqlite_get_metadata(self, contract, key)
+    }
+
+    fn get_metadata_manual(
+        &mut self,
+        at_height: u32,
+        contract: &QualifiedContractIdentifier,
+        key: &str,
+    ) -> InterpreterResult<Option<String>> {
+        sqlite_get_metadata_manual(self, at_height, contract, key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use clarity::vm::clarity::TransactionConnection;
+    use clarity::vm::costs::ExecutionCost;
+    use clarity::vm::test_util::{TEST_BURN_STATE_DB, TEST_HEADER_DB};
+    use clarity::vm::types::{BuffData, PrincipalData};
+    use clarity::vm::Value;
+    use rand::Rng;
+    use stacks_common::consts::{
+        CHAIN_ID_TESTNET, FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH,
+    };
+    use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksAddress};
+
+    use super::*;
+    use crate::burnchains::PoxConstants;
+    use crate::chainstate::burn::db::sortdb::SortitionDB;
+    use crate::chainstate::stacks::db::{ChainStateBootData, StacksChainState};
+    use crate::chainstate::stacks::{
+        StacksBlockHeader, MINER_BLOCK_CONSENSUS_HASH, MINER_BLOCK_HEADER_HASH,
+    };
+    use crate::clarity_vm::clarity::ClarityInstance;
+    use crate::clarity_vm::database::marf::MarfedKV;
+    use crate::core::{StacksEpoch, StacksEpochExtension};
+
+    fn random_node_path() -> String {
+        format!(
+            "/tmp/stacks-node-tests/unit-tests-snapshot/node-{}",
+            to_hex(&rand::thread_rng().gen::<[u8; 32]>())
+        )
+    }
+
+    fn genesis_block_id() -> StacksBlockId {
+        StacksBlockId::new(&FIRST_BURNCHAIN_CONSENSUS_HASH, &FIRST_STACKS_BLOCK_HASH)
+    }
+
+    fn clarity_marf_path(node_path: &str) -> String {
+        StacksChainState::vm_state_index_marf_path(format!("{node_path}/chainstate").into())
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    fn headers_path(node_path: &str) -> String {
+        StacksChainState::header_index_root_path(format!("{node_path}/chainstate").into())
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    fn sortdb_path(node_path: &str) -> String {
+        format!("{node_path}/burnchain/sortition/marf.sqlite")
+    }
+
+    fn connect_sortdb(node_path: &str) {
+        SortitionDB::connect(
+            &format!("{node_path}/burnchain/sortition"),
+            0,
+            &BurnchainHeaderHash::zero(),
+            0,
+            &StacksEpoch::unit_test_2_5(0),
+            PoxConstants::testnet_default(),
+            None,
+            true,
+        )
+        .unwrap();
+    }
+
+    /// Add a sortition after the first one to the sortition DB at `node_path`
+    fn add_sortition(node_path: &str, consensus_hash: &ConsensusHash) {
+        let conn = Connection::open(sortdb_path(node_path)).unwrap();
+        conn.execute_batch(
+            "CREATE TEMP TABLE next_snapshot AS
+             SELECT * FROM snapshots WHERE sortition_id = parent_sortition_id",
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE next_snapshot SET
+             parent_sortition_id = sortition_id, parent_burn_header_hash = burn_header_hash,
+             sortition_id = ?1, burn_header_hash = ?2, consensus_hash = ?3, index_root = ?4,
+             block_height = 1",
+            params![
+                SortitionId([0x01; 32]),
+                BurnchainHeaderHash([0x01; 32]),
+                consensus_hash,
+                TrieHash([0x01; 32])
+            ],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO snapshots SELECT * FROM next_snapshot", [])
+            .unwrap();
+    }
+
+    /// Boot a node, add a block on top of genesis with a key in its Clarity state, and export a
+    /// snapshot of that block.  Returns the block's index block hash.
+    fn make_snapshot(node_path: &str, snapshot_path: &str) -> StacksBlockId {
+        let owner = PrincipalData::from(StacksAddress::burn_address(false));
+        let mut boot_data = ChainStateBootData {
+            initial_balances: vec![(owner, 1000)],
+            post_flight_callback: None,
+            first_burnchain_block_hash: BurnchainHeaderHash::zero(),
+            first_burnchain_block_height: 0,
+            first_burnchain_block_timestamp: 0,
+            pox_constants: PoxConstants::testnet_default(),
+            get_bulk_initial_lockups: None,
+            get_bulk_initial_balances: None,
+            get_bulk_initial_names: None,
+            get_bulk_initial_namespaces: None,
+        };
+        StacksChainState::open_and_exec(
+            false,
+            CHAIN_ID_TESTNET,
+            &format!("{node_path}/chainstate"),
+            Some(&mut boot_data),
+            None,
+        )
+        .unwrap();
+        connect_sortdb(node_path);
+        let consensus_hash = ConsensusHash([0x02; 20]);
+        add_sortition(node_path, &consensus_hash);
+
+        // like a real block, the trie is built under the miner's block ID, since the real one
+        // depends on its root hash
+        let mut clarity_marf = StacksChainState::open_index(&clarity_marf_path(node_path)).unwrap();
+        let side_store = Connection::open(clarity_marf_path(node_path)).unwrap();
+        let miner_block_id =
+            StacksBlockId::new(&MINER_BLOCK_CONSENSUS_HASH, &MINER_BLOCK_HEADER_HASH);
+        clarity_marf
+            .begin(&genesis_block_id(), &miner_block_id)
+            .unwrap();
+        let value = MARFValue::from_value("snapshot-value");
+        clarity_marf.insert("snapshot-key", value.clone()).unwrap();
+        side_store
+            .execute(
+                "INSERT INTO data_table (key, value) VALUES (?1, ?2)",
+                params![value.to_hex(), "snapshot-value"],
+            )
+            .unwrap();
+        let state_index_root = clarity_marf.seal().unwrap();
+
+        let mut header = StacksBlockHeader::genesis_block_header();
+        header.parent_block = FIRST_STACKS_BLOCK_HASH;
+        header.total_work.work = 1;
+        header.state_index_root = state_index_root;
+        let mut tip_info =
+            StacksHeaderInfo::genesis(TrieHash([0x00; 32]), &BurnchainHeaderHash::zero(), 0, 0);
+        tip_info.anchored_header = header.clone().into();
+        tip_info.stacks_block_height = 1;
+        tip_info.consensus_hash = consensus_hash.clone();
+        tip_info.burn_header_hash = BurnchainHeaderHash([0x01; 32]);
+        tip_info.burn_header_height = 1;
+        let block_id = header.index_block_hash(&consensus_hash);
+        clarity_marf.commit_to(&block_id).unwrap();
+
+        let mut headers_conn = Connection::open(headers_path(node_path)).unwrap();
+        let headers_tx = headers_conn.transaction().unwrap();
+        StacksChainState::insert_stacks_block_header(
+            &headers_tx,
+            &genesis_block_id(),
+            &tip_info,
+            &ExecutionCost::ZERO,
+            0,
+        )
+        .unwrap();
+        headers_tx.commit().unwrap();
+
+        let sortdb_conn = Connection::open(sortdb_path(node_path)).unwrap();
+        let mut out = File::create(snapshot_path).unwrap();
+        export_marf_snapshot(
+            &mut clarity_marf,
+            &side_store,
+            &headers_conn,
+            &sortdb_conn,
+            &block_id,
+            &mut out,
+        )
+        .unwrap();
+        block_id
+    }
+
+    /// Make a node with empty chainstate and sortition DBs to import a snapshot into
+    fn make_empty_node(node_path: &str) {
+        StacksChainState::make_chainstate_dirs(&format!("{node_path}/chainstate")).unwrap();
+        fs::create_dir_all(StacksChainState::vm_state_index_root_path(
+            format!("{node_path}/chainstate").into(),
+        ))
+        .unwrap();
+        StacksChainState::open_db(false, CHAIN_ID_TESTNET, &headers_path(node_path)).unwrap();
+        connect_sortdb(node_path);
+    }
+
+    fn get_state_index_root_at(node_path: &str, block_id: &StacksBlockId) -> TrieHash {
+        let headers_conn = Connection::open(headers_path(node_path)).unwrap();
+        let header = NakamotoChainState::get_block_header(&headers_conn, block_id)
+            .unwrap()
+            .unwrap();
+        get_state_index_root(&header)
+    }
+
+    fn import(
+        snapshot_path: &str,
+        node_path: &str,
+        index_block_hash: &StacksBlockId,
+        state_index_root: &TrieHash,
+    ) -> Result<SnapshotImportSummary, Error> {
+        let mut clarity_marf = StacksChainState::open_index(&clarity_marf_path(node_path)).unwrap();
+        let mut headers_conn = Connection::open(headers_path(node_path)).unwrap();
+        let mut sortdb_conn = Connection::open(sortdb_path(node_path)).unwrap();
+        import_marf_snapshot(
+            Path::new(snapshot_path),
+            index_block_hash,
+            state_index_root,
+            &mut clarity_marf,
+            &mut headers_conn,
+            &mut sortdb_conn,
+        )
+    }
+
+    /// Check that nothing was imported into the node at `node_path`
+    fn assert_not_imported(node_path: &str, block_id: &StacksBlockId) {
+        let mut clarity_marf = StacksChainState::open_index(&clarity_marf_path(node_path)).unwrap();
+        for block_id in [&genesis_block_id(), block_id] {
+            assert!(matches!(
+                clarity_marf.get_root_hash_at(block_id),
+                Err(MARFError::NotFoundError)
+            ));
+        }
+        let headers_conn = Connection::open(headers_path(node_path)).unwrap();
+        assert!(
+            NakamotoChainState::get_block_header(&headers_conn, block_id)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    /// Check that the node at `node_path` has the same Clarity state at `block_id`, and at
+    /// genesis, as the node at `source_path`
+    fn assert_imported(node_path: &str, source_path: &str, block_id: &StacksBlockId) {
+        let state_index_root = get_state_index_root_at(source_path, block_id);
+        assert_eq!(
+            get_state_index_root_at(node_path, block_id),
+            state_index_root
+        );
+
+        let mut states = vec![];
+        for path in [source_path, node_path] {
+            let mut clarity_marf = StacksChainState::open_index(&clarity_marf_path(path)).unwrap();
+            assert_eq!(
+                clarity_marf.get_root_hash_at(block_id).unwrap(),
+                state_index_root
+            );
+            assert_eq!(
+                clarity_marf.get(block_id, "snapshot-key").unwrap(),
+                Some(MARFValue::from_value("snapshot-value"))
+            );
+            let mut leaves = vec![];
+            for block_id in [&genesis_block_id(), block_id] {
+                let mut block_leaves = vec![];
+                clarity_marf
+                    .walk_leaves(block_id, |path, value| {
+                        block_leaves.push((path, value));
+                        Ok(())
+                    })
+                    .unwrap();
+                block_leaves.sort_by(|a, b| a.0.cmp(&b.0));
+                leaves.push(block_leaves);
+            }
+
+            let side_store = Connection::open(clarity_marf_path(path)).unwrap();
+            let data_rows: i64 = side_store
+                .query_row("SELECT COUNT(*) FROM data_table", [], |row| row.get(0))
+                .unwrap();
+            states.push((leaves, data_rows));
+        }
+        assert!(!states[0].0[0].is_empty());
+        assert_eq!(states[0], states[1]);
+    }
+
+    /// Rewrite the snapshot's records with `tamper`, which returns true once it has changed one
+    fn tamper_with_snapshot<F: FnMut(&mut SnapshotRecord) -> bool>(
+        snapshot_path: &str,
+        mut tamper: F,
+    ) {
+        let mut records: Vec<SnapshotRecord> = read_records(Path::new(snapshot_path))
+            .unwrap()
+            .map(|record| record.unwrap())
+            .collect();
+        assert!(records.iter_mut().any(|record| tamper(record)));
+
+        let mut out = File::create(snapshot_path).unwrap();
+        for record in records.iter() {
+            write_record(&mut out, record).unwrap();
+        }
+    }
+
+    /// Flip the last byte of a hex-encoded trie blob, which is part of a leaf's value
+    fn flip_last_byte(trie: &mut String) {
+        let mut trie_blob = hex_bytes(trie).unwrap();
+        *trie_blob.last_mut().unwrap() ^= 0x01;
+        *trie = to_hex(&trie_blob);
+    }
+
+    #[test]
+    fn test_marf_snapshot_import() {
+        let source_path = random_node_path();
+        let node_path = random_node_path();
+        let snapshot_path = format!("{source_path}.snapshot");
+        let block_id = make_snapshot(&source_path, &snapshot_path);
+        make_empty_node(&node_path);
+
+        let state_index_root = get_state_index_root_at(&source_path, &block_id);
+        let summary = import(&snapshot_path, &node_path, &block_id, &state_index_root).unwrap();
+        assert_eq!(summary.index_block_hash, block_id);
+        assert_eq!(summary.block_height, 1);
+        assert_eq!(summary.state_index_root, state_index_root);
+        assert!(summary.num_tries >= 2);
+        assert_imported(&node_path, &source_path, &block_id);
+
+        // the block's sortition is imported with the block
+        let sortdb_conn = Connection::open(sortdb_path(&node_path)).unwrap();
+        let num_sortitions: i64 = sortdb_conn
+            .query_row(
+                "SELECT COUNT(*) FROM snapshots WHERE consensus_hash = ?1",
+                params![ConsensusHash([0x02; 20])],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(num_sortitions, 1);
+    }
+
+    #[test]
+    fn test_marf_snapshot_rejects_tampered_snapshot() {
+        let source_path = random_node_path();
+        let node_path = random_node_path();
+        let snapshot_path = format!("{source_path}.snapshot");
+        let block_id = make_snapshot(&source_path, &snapshot_path);
+        make_empty_node(&node_path);
+
+        let state_index_root = get_state_index_root_at(&source_path, &block_id);
+
+        // side-store data that does not hash to its key
+        let data_snapshot_path = format!("{source_path}.data.snapshot");
+        fs::copy(&snapshot_path, &data_snapshot_path).unwrap();
+        tamper_with_snapshot(&data_snapshot_path, |record| match record {
+            SnapshotRecord::Data { value, .. } => {
+                value.push_str("00");
+                true
+            }
+            _ => false,
+        });
+        import(
+            &data_snapshot_path,
+            &node_path,
+            &block_id,
+            &state_index_root,
+        )
+        .unwrap_err();
+        assert_not_imported(&node_path, &block_id);
+
+        // the block's own trie, and an ancestor's trie
+        for (i, name) in ["tip", "ancestor"].iter().enumerate() {
+            let trie_snapshot_path = format!("{source_path}.{name}.snapshot");
+            fs::copy(&snapshot_path, &trie_snapshot_path).unwrap();
+            tamper_with_snapshot(&trie_snapshot_path, |record| match record {
+                SnapshotRecord::Trie { block_id: id, trie } if (id == &block_id) == (i == 0) => {
+                    flip_last_byte(trie);
+                    true
+                }
+                _ => false,
+            });
+            import(
+                &trie_snapshot_path,
+                &node_path,
+                &block_id,
+                &state_index_root,
+            )
+            .unwrap_err();
+            assert_not_imported(&node_path, &block_id);
+        }
+
+        // the untampered snapshot still imports
+        import(&snapshot_path, &node_path, &block_id, &state_index_root).unwrap();
+        assert_imported(&node_path, &source_path, &block_id);
+    }
+
+    #[test]
+    fn test_marf_snapshot_rejects_tampered_metadata() {
+        let source_path = random_node_path();
+        let node_path = random_node_path();
+        let snapshot_path = format!("{source_path}.snapshot");
+        let block_id = make_snapshot(&source_path, &snapshot_path);
+        make_empty_node(&node_path);
+
+        let state_index_root = get_state_index_root_at(&source_path, &block_id);
+        let costs = boot_code_id("costs", false);
+        let costs_key = |key: &str| format!("{METADATA_KEY_PREFIX}{costs}::{key}");
+        let contract_key = |name: ContractDataVarName| {
+            costs_key(&ClarityDatabase::make_metadata_key(
+                StoreType::Contract,
+                name.as_str(),
+            ))
+        };
+
+        // a boot contract's source, which no longer hashes to its commitment
+        let source_key = contract_key(ContractDataVarName::ContractSrc);
+        let source_snapshot_path = format!("{source_path}.source.snapshot");
+        fs::copy(&snapshot_path, &source_snapshot_path).unwrap();
+        tamper_with_snapshot(&source_snapshot_path, |record| match record {
+            SnapshotRecord::Metadata {
+                key,
+                value: Some(value),
+                ..
+            } if *key == source_key => {
+                value.push(' ');
+                true
+            }
+            _ => false,
+        });
+
+        // its analysis, which analyzing its source does not reproduce
+        let analysis_key = costs_key(AnalysisDatabase::storage_key());
+        let analysis_snapshot_path = format!("{source_path}.analysis.snapshot");
+        fs::copy(&snapshot_path, &analysis_snapshot_path).unwrap();
+        tamper_with_snapshot(&analysis_snapshot_path, |record| match record {
+            SnapshotRecord::Metadata {
+                key,
+                value: Some(value),
+                ..
+            } if *key == analysis_key => {
+                let mut analysis: serde_json::Value = serde_json::from_str(value).unwrap();
+                let eligible = analysis["is_cost_contract_eligible"].as_bool().unwrap();
+                analysis["is_cost_contract_eligible"] = (!eligible).into();
+                *value = analysis.to_string();
+                true
+            }
+            _ => false,
+        });
+
+        // metadata for a contract that was never deployed
+        let context_key = contract_key(ContractDataVarName::Contract);
+        let undeployed_snapshot_path = format!("{source_path}.undeployed.snapshot");
+        fs::copy(&snapshot_path, &undeployed_snapshot_path).unwrap();
+        tamper_with_snapshot(&undeployed_snapshot_path, |record| match record {
+            SnapshotRecord::Metadata { key, .. } if *key == context_key => {
+                let undeployed = boot_code_id("undeployed", false);
+                *key = key.replace(&costs.to_string(), &undeployed.to_string());
+                true
+            }
+            _ => false,
+        });
+
+        for path in [
+            &source_snapshot_path,
+            &analysis_snapshot_path,
+            &undeployed_snapshot_path,
+        ] {
+            import(path, &node_path, &block_id, &state_index_root).unwrap_err();
+            assert_not_imported(&node_path, &block_id);
+        }
+
+        // the untampered snapshot still imports
+        import(&snapshot_path, &node_path, &block_id, &state_index_root).unwrap();
+        assert_imported(&node_path, &source_path, &block_id);
+    }
+
+    #[test]
+    fn test_marf_snapshot_rejects_conflicting_row() {
+        let source_path = random_node_path();
+        let node_path = random_node_path();
+        let snapshot_path = format!("{source_path}.snapshot");
+        let block_id = make_snapshot(&source_path, &snapshot_path);
+        make_empty_node(&node_path);
+
+        let state_index_root = get_state_index_root_at(&source_path, &block_id);
+
+        // the node already has the first sortition, so a different one is not imported over it
+        tamper_with_snapshot(&snapshot_path, |record| match record {
+            SnapshotRecord::Row {
+                db: SnapshotDB::Sortition,
+                columns,
+                ..
+            } if get_text_column(columns, "sortition_id")
+                == get_text_column(columns, "parent_sortition_id") =>
+            {
+                let (_, timestamp) = columns
+                    .iter_mut()
+                    .find(|(name, _)| name == "burn_header_timestamp")
+                    .unwrap();
+                *timestamp = SnapshotSqlValue::Integer(1);
+                true
+            }
+            _ => false,
+        });
+        import(&snapshot_path, &node_path, &block_id, &state_index_root).unwrap_err();
+        assert_not_imported(&node_path, &block_id);
+    }
+
+    #[test]
+    fn test_marf_snapshot_next_block() {
+        let source_path = random_node_path();
+        let node_path = random_node_path();
+        let snapshot_path = format!("{source_path}.snapshot");
+        let block_id = make_snapshot(&source_path, &snapshot_path);
+        make_empty_node(&node_path);
+
+        let state_index_root = get_state_index_root_at(&source_path, &block_id);
+        import(&snapshot_path, &node_path, &block_id, &state_index_root).unwrap();
+
+        // process the next block on top of the imported one.  Beginning it loads the cost
+        // contract, and it deploys a contract that calls a boot contract, so both need the
+        // imported metadata.
+        let vm_path =
+            StacksChainState::vm_state_index_root_path(format!("{node_path}/chainstate").into());
+        let marf_kv = MarfedKV::open(vm_path.to_str().unwrap(), None, None).unwrap();
+        let mut clarity_instance = ClarityInstance::new(false, CHAIN_ID_TESTNET, marf_kv);
+        let next_block_id = StacksBlockId([0x03; 32]);
+        let owner = PrincipalData::from(StacksAddress::burn_address(false));
+        let recipient = PrincipalData::from(StacksAddress::burn_address(true));
+        let contract_id = QualifiedContractIdentifier::local("next-block").unwrap();
+        let contract = format!(
+            "(define-read-only (analysis-cost (n uint))
+               (contract-call? '{} cost_analysis_type_annotate n))",
+            boot_code_id("costs", false)
+        );
+
+        let mut conn = clarity_instance.begin_block(
+            &block_id,
+            &next_block_id,
+            &TEST_HEADER_DB,
+            &TEST_BURN_STATE_DB,
+        );
+        conn.as_transaction(|tx| {
+            tx.run_stx_transfer(&owner, &recipient, 100, &BuffData::empty())
+                .unwrap();
+            let (ast, analysis) = tx
+                .analyze_smart_contract(
+                    &contract_id,
+                    ClarityVersion::Clarity1,
+                    &contract,
+                    ASTRules::PrecheckSize,
+                )
+                .unwrap();
+            tx.initialize_smart_contract(
+                &contract_id,
+                ClarityVersion::Clarity1,
+                &ast,
+                &contract,
+                None,
+                |_, _| false,
+            )
+            .unwrap();
+            tx.save_analysis(&contract_id, &analysis).unwrap();
+        });
+        let (result, _, _) = conn
+            .as_transaction(|tx| {
+                tx.run_contract_call(
+                    &owner,
+                    None,
+                    &contract_id,
+                    "analysis-cost",
+                    &[Value::UInt(1)],
+                    |_, _| false,
+                )
+            })
+            .unwrap();
+        assert!(matches!(result, Value::Tuple(_)));
+        conn.commit_block();
+
+        let mut clarity_marf =
+            StacksChainState::open_index(&clarity_marf_path(&node_path)).unwrap();
+        assert_eq!(
+            clarity_marf
+                .get_block_height(&next_block_id, &next_block_id)
+                .unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            clarity_marf.get(&next_block_id, "snapshot-key").unwrap(),
+            Some(MARFValue::from_value("snapshot-value"))
+        );
+        let balance = clarity_instance
+            .eval_read_only(
+                &next_block_id,
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+                &contract_id,
+                &format!("(stx-get-balance '{recipient})"),
+                ASTRules::PrecheckSize,
+            )
+            .unwrap();
+        assert_eq!(balance, Value::UInt(100));
+    }
+
+    #[test]
+    fn test_marf_snapshot_rejects_mismatched_root() {
+        let source_path = random_node_path();
+        let node_path = random_node_path();
+        let snapshot_path = format!("{source_path}.snapshot");
+        let block_id = make_snapshot(&source_path, &snapshot_path);
+        make_empty_node(&node_path);
+
+        let state_index_root = get_state_index_root_at(&source_path, &block_id);
+
+        import(&snapshot_path, &node_path, &block_id, &TrieHash([0x01; 32])).unwrap_err();
+        assert_not_imported(&node_path, &block_id);
+
+        import(
+            &snapshot_path,
+            &node_path,
+            &StacksBlockId([0x01; 32]),
+            &state_index_root,
+        )
+        .unwrap_err();
+        assert_not_imported(&node_path, &block_id);
+    }
+
+    #[test]
+    fn test_marf_snapshot_interrupted_import() {
+        let source_path = random_node_path();
+        let node_path = random_node_path();
+        let snapshot_path = format!("{source_path}.snapshot");
+        let block_id = make_snapshot(&source_path, &snapshot_path);
+        make_empty_node(&node_path);
+
+        let state_index_root = get_state_index_root_at(&source_path, &block_id);
+
+        // interrupted after the tries are committed, but before the header is
+        TEST_SNAPSHOT_IMPORT_INTERRUPT.set(Some(PathBuf::from(&snapshot_path)));
+        import(&snapshot_path, &node_path, &block_id, &state_index_root).unwrap_err();
+        TEST_SNAPSHOT_IMPORT_INTERRUPT.set(None);
+
+        let mut clarity_marf =
+            StacksChainState::open_index(&clarity_marf_path(&node_path)).unwrap();
+        assert_eq!(
+            clarity_marf.get_root_hash_at(&block_id).unwrap(),
+            state_index_root
+        );
+        let headers_conn = Connection::open(headers_path(&node_path)).unwrap();
+        assert!(
+            NakamotoChainState::get_block_header(&headers_conn, &block_id)
+                .unwrap()
+                .is_none()
+        );
+
+        // running the import again finishes it
+        import(&snapshot_path, &node_path, &block_id, &state_index_root).unwrap();
+        assert_imported(&node_path, &source_path, &block_id);
+    }
+}
@@ -523,6 +523,7 @@ impl StacksChainState {
         config: &DBConfig,
         tx: &StacksTransaction,
         epoch_id: StacksEpochId,
+        verify_signatures: bool,
     ) -> Result<(), Error> {
         // valid auth?
         if !tx.auth.is_supported_in_epoch(epoch_id) {
@@ -534,7 +535,9 @@ impl StacksChainState {
 
             return Err(Error::InvalidStacksTransaction(msg, false));
         }
-        tx.verify().map_err(Error::NetError)?;
+        if verify_signatures {
+            tx.verify().map_err(Error::NetError)?;
+        }
 
         // destined for us?
         if config.chain_id != tx.chain_id {
@@ -574,7 +577,7 @@ impl StacksChainState {
     /// Apply a post-conditions check.
     /// Return true if they all pass.
     /// Return false if at least one fails.
-    fn check_transaction_postconditions(
+    pub fn check_transaction_postconditions(
         post_conditions: &[TransactionPostCondition],
         post_condition_mode: &TransactionPostConditionMode,
         origin_account: &StacksAccount,
@@ -1475,7 +1478,12 @@ impl StacksChainState {
         debug!("Process transaction {} ({})", tx.txid(), tx.payload.name());
         let epoch = clarity_block.get_epoch();
 
-        StacksChainState::process_transaction_precheck(&clarity_block.config, tx, epoch)?;
+        StacksChainState::process_transaction_precheck(
+            &clarity_block.config,
+            tx,
+            epoch,
+            clarity_block.verifies_signatures(),
+        )?;
 
         // what version of Clarity did the transaction caller want? And, is it valid now?
         let clarity_version = StacksChainState::get_tx_clarity_version(clarity_block, tx)?;
@@ -0,0 +1,958 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Partially-signed transaction envelopes.
+//!
+//! A `SigningEnvelope` carries an unsigned transaction together with the public keys of its
+//! spending condition(s) and whichever signatures have been collected so far.  Co-signers can
+//! each sign a copy of the envelope offline, the copies can be combined, and a sponsor can
+//! attach its own spending condition (and fee) once the origin has signed.  When enough
+//! signatures are present, the envelope is finalized into a fully-signed transaction.
+//!
+//! Signatures are kept out of the transaction until it is finalized, since an ordered multisig
+//! condition commits to its signatures (and public keys) as one sequence of auth fields.
+
+use serde::{Deserialize, Serialize};
+use stacks_common::address::AddressHashMode;
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::PrivateKey;
+use stacks_common::util::hash::{hex_bytes, to_hex};
+use stacks_common::util::secp256k1::MessageSignature;
+
+use crate::burnchains::Txid;
+use crate::chainstate::stacks::{
+    MultisigHashMode, OrderIndependentMultisigHashMode, SinglesigHashMode, StacksPrivateKey,
+    StacksPublicKey, StacksTransaction, TransactionAuth, TransactionAuthFlags,
+    TransactionPublicKeyEncoding, TransactionSpendingCondition,
+};
+use crate::net::Error as net_error;
+
+/// Version of the envelope format produced by this module
+pub const SIGNING_ENVELOPE_VERSION: u32 = 1;
+
+/// The public keys of one spending condition, in the order in which the condition commits to
+/// them, along with the signature collected from each key so far.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvelopeSigners {
+    #[serde(
+        serialize_with = "hex_ser_public_keys",
+        deserialize_with = "hex_deser_public_keys"
+    )]
+    pub public_keys: Vec<StacksPublicKey>,
+    pub signatures: Vec<Option<MessageSignature>>,
+}
+
+/// An unsigned transaction and the signatures collected for it so far
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "EnvelopeJson", try_from = "EnvelopeJson")]
+pub struct SigningEnvelope {
+    pub version: u32,
+    /// The transaction to sign.  Its spending conditions carry their nonces and fees, but no
+    /// signatures or public keys.  A sponsored transaction's sponsor condition is the
+    /// initial-sighash placeholder until a sponsor is set.
+    pub transaction: StacksTransaction,
+    pub origin: EnvelopeSigners,
+    /// Only set for a sponsored transaction, once its sponsor is known
+    pub sponsor: Option<EnvelopeSigners>,
+}
+
+/// The encoding of a `SigningEnvelope`.  A multisig spending condition without its signatures
+/// cannot be consensus-serialized, so the transaction is encoded with the initial-sighash
+/// placeholder in place of each spending condition, and each condition is rebuilt from its
+/// signers' public keys and the parameters stored alongside them.
+#[derive(Serialize, Deserialize)]
+struct EnvelopeJson {
+    version: u32,
+    #[serde(serialize_with = "hex_ser_tx", deserialize_with = "hex_deser_tx")]
+    transaction: StacksTransaction,
+    origin: ConditionJson,
+    sponsor: Option<ConditionJson>,
+}
+
+/// A spending condition's signers, and the parameters needed to rebuild it from their keys
+#[derive(Serialize, Deserialize)]
+struct ConditionJson {
+    hash_mode: u8,
+    signatures_required: u16,
+    nonce: u64,
+    fee: u64,
+    #[serde(flatten)]
+    signers: EnvelopeSigners,
+}
+
+fn hex_ser_tx<S: serde::Serializer>(tx: &StacksTransaction, s: S) -> Result<S::Ok, S::Error> {
+    let inst = to_hex(&tx.serialize_to_vec());
+    s.serialize_str(inst.as_str())
+}
+
+fn hex_deser_tx<'de, D: serde::Deserializer<'de>>(d: D) -> Result<StacksTransaction, D::Error> {
+    let inst_str = String::deserialize(d)?;
+    let bytes = hex_bytes(&inst_str).map_err(serde::de::Error::custom)?;
+    StacksTransaction::consensus_deserialize(&mut bytes.as_slice())
+        .map_err(serde::de::Error::custom)
+}
+
+fn hex_ser_public_keys<S: serde::Serializer>(
+    public_keys: &[StacksPublicKey],
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    let inst: Vec<_> = public_keys.iter().map(|pk| pk.to_hex()).collect();
+    inst.serialize(s)
+}
+
+fn hex_deser_public_keys<'de, D: serde::Deserializer<'de>>(
+    d: D,
+) -> Result<Vec<StacksPublicKey>, D::Error> {
+    let inst_strs = Vec::<String>::deserialize(d)?;
+    inst_strs
+        .iter()
+        .map(|inst_str| StacksPublicKey::from_hex(inst_str).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+impl EnvelopeSigners {
+    pub fn new(public_keys: Vec<StacksPublicKey>) -> EnvelopeSigners {
+        let signatures = vec![None; public_keys.len()];
+        EnvelopeSigners {
+            public_keys,
+            signatures,
+        }
+    }
+
+    pub fn num_signatures(&self) -> usize {
+        self.signatures.iter().filter(|sig| sig.is_some()).count()
+    }
+
+    /// Find the first slot for `public_key` that has not been signed yet.  Keys are compared
+    /// without regard to their compression, since a secret key does not always record it.
+    pub fn unsigned_slot(&self, public_key: &StacksPublicKey) -> Option<usize> {
+        let key_bytes = public_key.to_bytes_compressed();
+        self.public_keys
+            .iter()
+            .zip(self.signatures.iter())
+            .position(|(pk, sig)| sig.is_none() && pk.to_bytes_compressed() == key_bytes)
+    }
+
+    /// Merge in the signatures from another copy of the same signer set
+    fn merge(&mut self, other: &EnvelopeSigners) -> Result<(), net_error> {
+        for ((pk, sig), other_sig) in self
+            .public_keys
+            .iter()
+            .zip(self.signatures.iter_mut())
+            .zip(other.signatures.iter())
+        {
+            match (*sig, *other_sig) {
+                (Some(mine), Some(theirs)) if mine != theirs => {
+                    return Err(net_error::SigningError(format!(
+                        "Envelopes carry different signatures from key {}",
+                        pk.to_hex()
+                    )));
+                }
+                (None, Some(theirs)) => *sig = Some(theirs),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Does this spending condition chain its signatures, so that keys must sign in key order?
+pub fn is_ordered(condition: &TransactionSpendingCondition) -> bool {
+    !matches!(
+        condition,
+        TransactionSpendingCondition::OrderIndependentMultisig(_)
+    )
+}
+
+fn address_hash_mode(condition: &TransactionSpendingCondition) -> AddressHashMode {
+    match condition {
+        TransactionSpendingCondition::Singlesig(data) => data.hash_mode.to_address_hash_mode(),
+        TransactionSpendingCondition::Multisig(data) => data.hash_mode.to_address_hash_mode(),
+        TransactionSpendingCondition::OrderIndependentMultisig(data) => {
+            data.hash_mode.to_address_hash_mode()
+        }
+    }
+}
+
+fn key_encoding(public_key: &StacksPublicKey) -> TransactionPublicKeyEncoding {
+    if public_key.compressed() {
+        TransactionPublicKeyEncoding::Compressed
+    } else {
+        TransactionPublicKeyEncoding::Uncompressed
+    }
+}
+
+/// A copy of `condition` with its signatures and public keys removed, but its nonce and fee kept
+fn without_auth_fields(condition: &TransactionSpendingCondition) -> TransactionSpendingCondition {
+    let mut condition = condition.clone();
+    let (nonce, tx_fee) = (condition.nonce(), condition.tx_fee());
+    condition.clear();
+    condition.set_nonce(nonce);
+    condition.set_tx_fee(tx_fee);
+    condition
+}
+
+/// A copy of `condition` carrying the collected signatures (and the public keys of the keys
+/// that did not sign), in key order
+fn with_auth_fields(
+    condition: &TransactionSpendingCondition,
+    signers: &EnvelopeSigners,
+) -> TransactionSpendingCondition {
+    let mut condition = without_auth_fields(condition);
+    let slots = signers.public_keys.iter().zip(signers.signatures.iter());
+    match condition {
+        TransactionSpendingCondition::Singlesig(ref mut data) => {
+            if let Some((_, Some(sig))) = slots.last() {
+                data.set_signature(*sig);
+            }
+        }
+        TransactionSpendingCondition::Multisig(ref mut data) => {
+            for (pk, sig) in slots {
+                match sig {
+                    Some(sig) => data.push_signature(key_encoding(pk), *sig),
+                    None => data.push_public_key(*pk),
+                }
+            }
+        }
+        TransactionSpendingCondition::OrderIndependentMultisig(ref mut data) => {
+            for (pk, sig) in slots {
+                match sig {
+                    Some(sig) => data.push_signature(key_encoding(pk), *sig),
+                    None => data.push_public_key(*pk),
+                }
+            }
+        }
+    }
+    condition
+}
+
+fn hash_mode_byte(condition: &TransactionSpendingCondition) -> u8 {
+    match condition {
+        TransactionSpendingCondition::Singlesig(data) => data.hash_mode.clone() as u8,
+        TransactionSpendingCondition::Multisig(data) => data.hash_mode.clone() as u8,
+        TransactionSpendingCondition::OrderIndependentMultisig(data) => {
+            data.hash_mode.clone() as u8
+        }
+    }
+}
+
+impl ConditionJson {
+    fn new(condition: &TransactionSpendingCondition, signers: EnvelopeSigners) -> ConditionJson {
+        ConditionJson {
+            hash_mode: hash_mode_byte(condition),
+            signatures_required: condition.signatures_required(),
+            nonce: condition.nonce(),
+            fee: condition.tx_fee(),
+            signers,
+        }
+    }
+
+    /// Rebuild the spending condition, without auth fields, from its signers' public keys
+    fn to_condition(&self) -> Result<TransactionSpendingCondition, net_error> {
+        let public_keys = self.signers.public_keys.clone();
+        let condition = if let Some(hash_mode) = SinglesigHashMode::from_u8(self.hash_mode) {
+            let [public_key] = public_keys[..] else {
+                return Err(net_error::DeserializeError(
+                    "A single-signature spending condition must have one public key".into(),
+                ));
+            };
+            match hash_mode {
+                SinglesigHashMode::P2PKH => {
+                    TransactionSpendingCondition::new_singlesig_p2pkh(public_key)
+                }
+                SinglesigHashMode::P2WPKH => {
+                    TransactionSpendingCondition::new_singlesig_p2wpkh(public_key)
+                }
+            }
+        } else if let Some(hash_mode) = MultisigHashMode::from_u8(self.hash_mode) {
+            match hash_mode {
+                MultisigHashMode::P2SH => TransactionSpendingCondition::new_multisig_p2sh(
+                    self.signatures_required,
+                    public_keys,
+                ),
+                MultisigHashMode::P2WSH => TransactionSpendingCondition::new_multisig_p2wsh(
+                    self.signatures_required,
+                    public_keys,
+                ),
+            }
+        } else if let Some(hash_mode) = OrderIndependentMultisigHashMode::from_u8(self.hash_mode) {
+            match hash_mode {
+                OrderIndependentMultisigHashMode::P2SH => {
+                    TransactionSpendingCondition::new_multisig_order_independent_p2sh(
+                        self.signatures_required,
+                        public_keys,
+                    )
+                }
+                OrderIndependentMultisigHashMode::P2WSH => {
+                    TransactionSpendingCondition::new_multisig_order_independent_p2wsh(
+                        self.signatures_required,
+                        public_keys,
+                    )
+                }
+            }
+        } else {
+            return Err(net_error::DeserializeError(format!(
+                "Unknown spending condition hash mode {}",
+                self.hash_mode
+            )));
+        };
+        let mut condition = condition.ok_or_else(|| {
+            net_error::DeserializeError("Failed to generate address from public keys".into())
+        })?;
+        condition.set_nonce(self.nonce);
+        condition.set_tx_fee(self.fee);
+        Ok(condition)
+    }
+}
+
+impl From<SigningEnvelope> for EnvelopeJson {
+    fn from(envelope: SigningEnvelope) -> EnvelopeJson {
+        let SigningEnvelope {
+            version,
+            mut transaction,
+            origin,
+            sponsor,
+        } = envelope;
+        let origin = ConditionJson::new(transaction.auth.origin(), origin);
+        let sponsor = match (transaction.auth.sponsor(), sponsor) {
+            (Some(sponsor_condition), Some(sponsor)) => {
+                Some(ConditionJson::new(sponsor_condition, sponsor))
+            }
+            _ => None,
+        };
+        transaction.auth = match transaction.auth {
+            TransactionAuth::Standard(_) => {
+                TransactionAuth::Standard(TransactionSpendingCondition::new_initial_sighash())
+            }
+            TransactionAuth::Sponsored(..) => TransactionAuth::Sponsored(
+                TransactionSpendingCondition::new_initial_sighash(),
+                TransactionSpendingCondition::new_initial_sighash(),
+            ),
+        };
+        EnvelopeJson {
+            version,
+            transaction,
+            origin,
+            sponsor,
+        }
+    }
+}
+
+impl TryFrom<EnvelopeJson> for SigningEnvelope {
+    type Error = net_error;
+
+    fn try_from(json: EnvelopeJson) -> Result<SigningEnvelope, net_error> {
+        let EnvelopeJson {
+            version,
+            mut transaction,
+            origin,
+            sponsor,
+        } = json;
+        let placeholder = TransactionSpendingCondition::new_initial_sighash();
+        if *transaction.auth.origin() != placeholder
+            || transaction
+                .auth
+                .sponsor()
+                .is_some_and(|sponsor_condition| *sponsor_condition != placeholder)
+        {
+            return Err(net_error::DeserializeError(
+                "Envelope transaction must carry placeholder spending conditions".into(),
+            ));
+        }
+        let origin_condition = origin.to_condition()?;
+        transaction.auth = match transaction.auth {
+            TransactionAuth::Standard(_) => TransactionAuth::Standard(origin_condition),
+            TransactionAuth::Sponsored(..) => TransactionAuth::Sponsored(
+                origin_condition,
+                sponsor
+                    .as_ref()
+                    .map(ConditionJson::to_condition)
+                    .transpose()?
+                    .unwrap_or(placeholder),
+            ),
+        };
+        Ok(SigningEnvelope {
+            version,
+            transaction,
+            origin: origin.signers,
+            sponsor: sponsor.map(|sponsor| sponsor.signers),
+        })
+    }
+}
+
+/// Check that `signers` are the keys behind `condition`, and that `condition` carries no auth
+/// fields of its own
+fn check_signers(
+    condition: &TransactionSpendingCondition,
+    signers: &EnvelopeSigners,
+) -> Result<(), net_error> {
+    if signers.signatures.len() != signers.public_keys.len() {
+        return Err(net_error::DeserializeError(
+            "Envelope must have one signature slot per public key".into(),
+        ));
+    }
+    if *condition != without_auth_fields(condition) {
+        return Err(net_error::DeserializeError(
+            "Envelope transaction must not carry signatures or public keys".into(),
+        ));
+    }
+    let signatures_required = condition.signatures_required();
+    if signers.num_signatures() > usize::from(signatures_required) {
+        return Err(net_error::DeserializeError(format!(
+            "Envelope has more than the {signatures_required} signatures its spending condition requires"
+        )));
+    }
+    let addr = StacksAddress::from_public_keys(
+        0,
+        &address_hash_mode(condition),
+        usize::from(signatures_required),
+        &signers.public_keys,
+    )
+    .ok_or_else(|| {
+        net_error::DeserializeError("Failed to generate address from public keys".into())
+    })?;
+    if addr.bytes() != condition.address_mainnet().bytes() {
+        return Err(net_error::DeserializeError(
+            "Public keys do not match the spending condition's signer".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// The sighash that the key in slot `index` signs.  In an ordered condition, this commits to
+/// every signature made by an earlier key.
+fn slot_sighash(
+    condition: &TransactionSpendingCondition,
+    signers: &EnvelopeSigners,
+    initial_sighash: &Txid,
+    cond_code: &TransactionAuthFlags,
+    index: usize,
+) -> Txid {
+    let mut cur_sighash = *initial_sighash;
+    if is_ordered(condition) {
+        for (pk, sig) in signers.public_keys[..index]
+            .iter()
+            .zip(signers.signatures[..index].iter())
+        {
+            let Some(sig) = sig else {
+                continue;
+            };
+            let sighash_presign = TransactionSpendingCondition::make_sighash_presign(
+                &cur_sighash,
+                cond_code,
+                condition.tx_fee(),
+                condition.nonce(),
+            );
+            cur_sighash =
+                TransactionSpendingCondition::make_sighash_postsign(&sighash_presign, pk, sig);
+        }
+    }
+    TransactionSpendingCondition::make_sighash_presign(
+        &cur_sighash,
+        cond_code,
+        condition.tx_fee(),
+        condition.nonce(),
+    )
+}
+
+/// Check that every collected signature was made by its slot's key over the right sighash
+fn check_signatures(
+    condition: &TransactionSpendingCondition,
+    signers: &EnvelopeSigners,
+    initial_sighash: &Txid,
+    cond_code: &TransactionAuthFlags,
+) -> Result<(), net_error> {
+    for (index, (pk, sig)) in signers
+        .public_keys
+        .iter()
+        .zip(signers.signatures.iter())
+        .enumerate()
+    {
+        let Some(sig) = sig else {
+            continue;
+        };
+        let sighash = slot_sighash(condition, signers, initial_sighash, cond_code, index);
+        let signed_by = StacksPublicKey::recover_to_pubkey(sighash.as_bytes(), sig)
+            .map_err(|ve| net_error::VerifyingError(ve.to_string()))?;
+        if signed_by.to_bytes_compressed() != pk.to_bytes_compressed() {
+            let hint = if is_ordered(condition) {
+                "; keys of an ordered multisig must sign one after another, in key order"
+            } else {
+                ""
+            };
+            return Err(net_error::VerifyingError(format!(
+                "Signature from key {} does not match the transaction{hint}",
+                pk.to_hex()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Sign slot `index` of `condition`
+fn sign_slot(
+    condition: &TransactionSpendingCondition,
+    signers: &EnvelopeSigners,
+    initial_sighash: &Txid,
+    cond_code: &TransactionAuthFlags,
+    index: usize,
+    privk: &StacksPrivateKey,
+) -> Result<MessageSignature, net_error> {
+    let signatures_required = condition.signatures_required();
+    if signers.num_signatures() >= usize::from(signatures_required) {
+        return Err(net_error::SigningError(format!(
+            "Spending condition already has the {signatures_required} signatures it requires"
+        )));
+    }
+    if is_ordered(condition) {
+        if let Some(later) = signers.signatures[index + 1..]
+            .iter()
+            .position(|sig| sig.is_some())
+        {
+            return Err(net_error::SigningError(format!(
+                "Key {} has already signed, so key {} can no longer sign; keys of an ordered multisig must sign in key order",
+                signers.public_keys[index + 1 + later].to_hex(),
+                signers.public_keys[index].to_hex()
+            )));
+        }
+    }
+    let sighash = slot_sighash(condition, signers, initial_sighash, cond_code, index);
+    privk
+        .sign(sighash.as_bytes())
+        .map_err(|se| net_error::SigningError(se.to_string()))
+}
+
+impl SigningEnvelope {
+    /// Wrap an unsigned transaction.  `origin_keys` are the public keys of its origin spending
+    /// condition, in key order.  The sponsor of a sponsored transaction is set later, with
+    /// `set_sponsor()`.
+    pub fn new(
+        mut transaction: StacksTransaction,
+        origin_keys: Vec<StacksPublicKey>,
+    ) -> Result<SigningEnvelope, net_error> {
+        let origin_condition = without_auth_fields(transaction.auth.origin());
+        let origin = EnvelopeSigners::new(origin_keys);
+        check_signers(&origin_condition, &origin)?;
+        transaction.auth = match transaction.auth {
+            TransactionAuth::Standard(_) => TransactionAuth::Standard(origin_condition),
+            TransactionAuth::Sponsored(..) => TransactionAuth::Sponsored(
+                origin_condition,
+                TransactionSpendingCondition::new_initial_sighash(),
+            ),
+        };
+        Ok(SigningEnvelope {
+            version: SIGNING_ENVELOPE_VERSION,
+            transaction,
+            origin,
+            sponsor: None,
+        })
+    }
+
+    /// Decode an envelope from JSON, and check that it is well-formed
+    pub fn from_json(json: &str) -> Result<SigningEnvelope, net_error> {
+        let envelope: SigningEnvelope = serde_json::from_str(json)
+            .map_err(|e| net_error::DeserializeError(format!("Invalid envelope: {e}")))?;
+        envelope.validate()?;
+        Ok(envelope)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("FATAL: failed to serialize envelope")
+    }
+
+    /// Check that the signer sets match the transaction's spending conditions, and that every
+    /// collected signature is valid
+    pub fn validate(&self) -> Result<(), net_error> {
+        if self.version != SIGNING_ENVELOPE_VERSION {
+            return Err(net_error::DeserializeError(format!(
+                "Unsupported envelope version {}",
+                self.version
+            )));
+        }
+        let origin_condition = self.transaction.auth.origin();
+        check_signers(origin_condition, &self.origin)?;
+        check_signatures(
+            origin_condition,
+            &self.origin,
+            &self.initial_sighash(),
+            &TransactionAuthFlags::AuthStandard,
+        )?;
+        match (&self.transaction.auth, &self.sponsor) {
+            (TransactionAuth::Standard(_), None) => Ok(()),
+            (TransactionAuth::Standard(_), Some(_)) => Err(net_error::DeserializeError(
+                "Envelope has a sponsor, but its transaction is not sponsored".into(),
+            )),
+            (TransactionAuth::Sponsored(_, sponsor_condition), None) => {
+                if *sponsor_condition != TransactionSpendingCondition::new_initial_sighash() {
+                    return Err(net_error::DeserializeError(
+                        "Envelope has a sponsor spending condition, but no sponsor keys".into(),
+                    ));
+                }
+                Ok(())
+            }
+            (TransactionAuth::Sponsored(_, sponsor_condition), Some(sponsor)) => {
+                check_signers(sponsor_condition, sponsor)?;
+                if sponsor.num_signatures() == 0 {
+                    return Ok(());
+                }
+                check_signatures(
+                    sponsor_condition,
+                    sponsor,
+                    &self.origin_sighash()?,
+                    &TransactionAuthFlags::AuthSponsored,
+                )
+            }
+        }
+    }
+
+    /// The sighash that the first origin signer signs over
+    pub fn initial_sighash(&self) -> Txid {
+        let mut tx = self.transaction.clone();
+        tx.auth = tx.auth.into_initial_sighash_auth();
+        tx.txid()
+    }
+
+    pub fn origin_complete(&self) -> bool {
+        self.origin.num_signatures()
+            >= usize::from(self.transaction.auth.origin().signatures_required())
+    }
+
+    pub fn sponsor_complete(&self) -> bool {
+        match (self.transaction.auth.sponsor(), &self.sponsor) {
+            (Some(sponsor_condition), Some(sponsor)) => {
+                sponsor.num_signatures() >= usize::from(sponsor_condition.signatures_required())
+            }
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.origin_complete() && self.sponsor_complete()
+    }
+
+    /// The sighash the sponsor signs over, which commits to all of the origin's signatures
+    fn origin_sighash(&self) -> Result<Txid, net_error> {
+        if !self.origin_complete() {
+            return Err(net_error::SigningError(
+                "The origin must finish signing before the sponsor signs".into(),
+            ));
+        }
+        with_auth_fields(self.transaction.auth.origin(), &self.origin)
+            .verify(&self.initial_sighash(), &TransactionAuthFlags::AuthStandard)
+    }
+
+    /// Set the sponsor of a sponsored transaction.  `condition` carries the sponsor's nonce and
+    /// fee, and `public_keys` are its keys, in key order.
+    pub fn set_sponsor(
+        &mut self,
+        condition: TransactionSpendingCondition,
+        public_keys: Vec<StacksPublicKey>,
+    ) -> Result<(), net_error> {
+        if self
+            .sponsor
+            .as_ref()
+            .is_some_and(|sponsor| sponsor.num_signatures() > 0)
+        {
+            return Err(net_error::SigningError(
+                "The sponsor has already started signing".into(),
+            ));
+        }
+        let condition = without_auth_fields(&condition);
+        let sponsor = EnvelopeSigners::new(public_keys);
+        check_signers(&condition, &sponsor)?;
+        let TransactionAuth::Sponsored(_, ref mut sponsor_condition) = self.transaction.auth else {
+            return Err(net_error::SigningError(
+                "Transaction is not sponsored".into(),
+            ));
+        };
+        *sponsor_condition = condition;
+        self.sponsor = Some(sponsor);
+        Ok(())
+    }
+
+    /// Sign with `privk`, which must belong to one of the origin's keys or, once the origin has
+    /// finished signing, one of the sponsor's keys
+    pub fn sign(&mut self, privk: &StacksPrivateKey) -> Result<(), net_error> {
+        let public_key = StacksPublicKey::from_private(privk);
+        if let Some(index) = self.origin.unsigned_slot(&public_key) {
+            let sig = sign_slot(
+                self.transaction.auth.origin(),
+                &self.origin,
+                &self.initial_sighash(),
+                &TransactionAuthFlags::AuthStandard,
+                index,
+                privk,
+            )?;
+            self.origin.signatures[index] = Some(sig);
+            return Ok(());
+        }
+
+        let sponsor_slot = match (self.transaction.auth.sponsor(), &self.sponsor) {
+            (Some(sponsor_condition), Some(sponsor)) => sponsor
+                .unsigned_slot(&public_key)
+                .map(|index| (sponsor_condition, sponsor, index)),
+            _ => None,
+        };
+        let Some((sponsor_condition, sponsor, index)) = sponsor_slot else {
+            return Err(net_error::SigningError(format!(
+                "Key {} has no unsigned slot in this transaction",
+                public_key.to_hex()
+            )));
+        };
+        let sig = sign_slot(
+            sponsor_condition,
+            sponsor,
+            &self.origin_sighash()?,
+            &TransactionAuthFlags::AuthSponsored,
+            index,
+            privk,
+        )?;
+        if let Some(sponsor) = self.sponsor.as_mut() {
+            sponsor.signatures[index] = Some(sig);
+        }
+        Ok(())
+    }
+
+    /// Merge the signatures from another envelope for the same transaction.  If only the other
+    /// envelope has a sponsor, its sponsor is adopted.
+    pub fn combine(&mut self, other: &SigningEnvelope) -> Result<(), net_error> {
+        if self.initial_sighash() != other.initial_sighash()
+            || self.transaction.auth.origin() != other.transaction.auth.origin()
+            || self.origin.public_keys != other.origin.public_keys
+        {
+            return Err(net_error::SigningError(
+                "Envelopes are for different transactions".into(),
+            ));
+        }
+
+        let mut combined = self.clone();
+        combined.origin.merge(&other.origin)?;
+        match (&mut combined.sponsor, &other.sponsor) {
+            (_, None) => {}
+            (None, Some(_)) => {
+                combined.transaction.auth = other.transaction.auth.clone();
+                combined.sponsor = other.sponsor.clone();
+            }
+            (Some(sponsor), Some(other_sponsor)) => {
+                if self.transaction.auth.sponsor() != other.transaction.auth.sponsor()
+                    || sponsor.public_keys != other_sponsor.public_keys
+                {
+                    return Err(net_error::SigningError(
+                        "Envelopes have different sponsors".into(),
+                    ));
+                }
+                sponsor.merge(other_sponsor)?;
+            }
+        }
+
+        combined.validate()?;
+        *self = combined;
+        Ok(())
+    }
+
+    /// Produce the fully-signed transaction
+    pub fn finalize(&self) -> Result<StacksTransaction, net_error> {
+        let origin_condition = self.transaction.auth.origin();
+        if !self.origin_complete() {
+            return Err(net_error::SigningError(format!(
+                "Origin has {} of the {} signatures it requires",
+                self.origin.num_signatures(),
+                origin_condition.signatures_required()
+            )));
+        }
+        let origin_condition = with_auth_fields(origin_condition, &self.origin);
+
+        let mut tx = self.transaction.clone();
+        tx.auth = match (&self.transaction.auth, &self.sponsor) {
+            (TransactionAuth::Standard(_), _) => TransactionAuth::Standard(origin_condition),
+            (TransactionAuth::Sponsored(..), None) => {
+                return Err(net_error::SigningError(
+                    "Transaction is sponsored, but has no sponsor yet".into(),
+                ));
+            }
+            (TransactionAuth::Sponsored(_, sponsor_condition), Some(sponsor)) => {
+                if !self.sponsor_complete() {
+                    return Err(net_error::SigningError(format!(
+                        "Sponsor has {} of the {} signatures it requires",
+                        sponsor.num_signatures(),
+                        sponsor_condition.signatures_required()
+                    )));
+                }
+                TransactionAuth::Sponsored(
+                    origin_condition,
+                    with_auth_fields(sponsor_condition, sponsor),
+                )
+            }
+        };
+        tx.verify()?;
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clarity::vm::types::PrincipalData;
+
+    use super::*;
+    use crate::chainstate::stacks::{TokenTransferMemo, TransactionPayload, TransactionVersion};
+
+    fn make_transfer(auth: TransactionAuth) -> StacksTransaction {
+        let recipient = PrincipalData::parse("ST1A14RBKJ289E3DP89QAZE2RRHDPWP5RHMYFRCHV").unwrap();
+        StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth,
+            TransactionPayload::TokenTransfer(recipient, 123, TokenTransferMemo([0u8; 34])),
+        )
+    }
+
+    fn make_keys(count: usize) -> (Vec<StacksPrivateKey>, Vec<StacksPublicKey>) {
+        let privks: Vec<_> = (0..count).map(|_| StacksPrivateKey::random()).collect();
+        let pubks = privks.iter().map(StacksPublicKey::from_private).collect();
+        (privks, pubks)
+    }
+
+    fn make_multisig_envelope(
+        order_independent: bool,
+        sponsored: bool,
+    ) -> (Vec<StacksPrivateKey>, SigningEnvelope) {
+        let (privks, pubks) = make_keys(3);
+        let mut condition = if order_independent {
+            TransactionSpendingCondition::new_multisig_order_independent_p2sh(2, pubks.clone())
+        } else {
+            TransactionSpendingCondition::new_multisig_p2sh(2, pubks.clone())
+        }
+        .unwrap();
+        condition.set_nonce(5);
+        let auth = if sponsored {
+            TransactionAuth::Sponsored(
+                condition,
+                TransactionSpendingCondition::new_initial_sighash(),
+            )
+        } else {
+            condition.set_tx_fee(300);
+            TransactionAuth::Standard(condition)
+        };
+        let envelope = SigningEnvelope::new(make_transfer(auth), pubks).unwrap();
+        (privks, envelope)
+    }
+
+    /// Round-trip an envelope through JSON, as a co-signer on another machine would see it
+    fn transport(envelope: &SigningEnvelope) -> SigningEnvelope {
+        SigningEnvelope::from_json(&envelope.to_json()).unwrap()
+    }
+
+    #[test]
+    fn ordered_multisig_signs_in_key_order() {
+        let (privks, envelope) = make_multisig_envelope(false, false);
+
+        // the third key cannot sign once it would have to come before a signature
+        let mut out_of_order = transport(&envelope);
+        out_of_order.sign(&privks[2]).unwrap();
+        assert!(out_of_order.sign(&privks[0]).is_err());
+
+        let mut first = transport(&envelope);
+        first.sign(&privks[0]).unwrap();
+        assert!(!first.is_complete());
+        assert!(first.finalize().is_err());
+
+        let mut second = transport(&first);
+        second.sign(&privks[2]).unwrap();
+        assert!(second.is_complete());
+        assert!(second.sign(&privks[1]).is_err());
+
+        let tx = transport(&second).finalize().unwrap();
+        tx.verify().unwrap();
+        assert_eq!(tx.get_tx_fee(), 300);
+        assert_eq!(tx.get_origin_nonce(), 5);
+
+        // signatures made in parallel do not chain, so they cannot be combined
+        let mut parallel = transport(&envelope);
+        parallel.sign(&privks[2]).unwrap();
+        let err = first.clone().combine(&parallel).unwrap_err();
+        assert!(err.to_string().contains("in key order"), "{err}");
+    }
+
+    #[test]
+    fn order_independent_multisig_combines_parallel_signatures() {
+        let (privks, envelope) = make_multisig_envelope(true, false);
+
+        let mut first = transport(&envelope);
+        first.sign(&privks[2]).unwrap();
+        let mut second = transport(&envelope);
+        second.sign(&privks[0]).unwrap();
+
+        let mut combined = first.clone();
+        combined.combine(&second).unwrap();
+        assert!(combined.is_complete());
+        // combining is idempotent
+        combined.combine(&first).unwrap();
+        combined.finalize().unwrap().verify().unwrap();
+
+        let (_, other_envelope) = make_multisig_envelope(true, false);
+        assert!(combined.combine(&other_envelope).is_err());
+    }
+
+    #[test]
+    fn sponsor_signs_after_origin() {
+        let (privks, mut envelope) = make_multisig_envelope(false, true);
+        let (sponsor_privks, sponsor_pubks) = make_keys(1);
+        let mut sponsor_condition =
+            TransactionSpendingCondition::new_singlesig_p2pkh(sponsor_pubks[0]).unwrap();
+        sponsor_condition.set_nonce(7);
+        sponsor_condition.set_tx_fee(1000);
+
+        // the origin can sign before or after the sponsor is chosen
+        envelope.sign(&privks[0]).unwrap();
+        envelope
+            .set_sponsor(sponsor_condition, sponsor_pubks)
+            .unwrap();
+        let mut envelope = transport(&envelope);
+        assert!(envelope.sign(&sponsor_privks[0]).is_err());
+        envelope.sign(&privks[1]).unwrap();
+        assert!(!envelope.is_complete());
+        assert!(envelope.finalize().is_err());
+
+        envelope.sign(&sponsor_privks[0]).unwrap();
+        let tx = transport(&envelope).finalize().unwrap();
+        tx.verify().unwrap();
+        assert_eq!(tx.get_tx_fee(), 1000);
+        assert_eq!(tx.auth.get_sponsor_nonce(), Some(7));
+        assert_eq!(tx.get_origin_nonce(), 5);
+
+        let (_, standard) = make_multisig_envelope(false, false);
+        let (_, pubks) = make_keys(1);
+        let condition = TransactionSpendingCondition::new_singlesig_p2pkh(pubks[0]).unwrap();
+        assert!(standard.clone().set_sponsor(condition, pubks).is_err());
+    }
+
+    #[test]
+    fn envelope_rejects_mismatched_keys() {
+        let (_, pubks) = make_keys(3);
+        let (_, other_pubks) = make_keys(1);
+        let condition = TransactionSpendingCondition::new_multisig_p2sh(2, pubks.clone()).unwrap();
+        let tx = make_transfer(TransactionAuth::Standard(condition));
+
+        assert!(SigningEnvelope::new(tx.clone(), other_pubks).is_err());
+        let mut reordered = pubks.clone();
+        reordered.swap(0, 1);
+        assert!(SigningEnvelope::new(tx.clone(), reordered).is_err());
+
+        let mut envelope = SigningEnvelope::new(tx, pubks).unwrap();
+        envelope.origin.signatures.pop();
+        assert!(SigningEnvelope::from_json(&envelope.to_json()).is_err());
+    }
+}
@@ -168,7 +168,6 @@ impl TrieFile {
     }
 
     /// Read a trie blob in its entirety from the blobs file
-    #[cfg(test)]
     pub fn read_trie_blob(&mut self, db: &Connection, block_id: u32) -> Result<Vec<u8>, Error> {
         let (offset, length) = trie_sql::get_external_trie_offset_length(db, block_id)?;
         self.seek(SeekFrom::Start(offset))?;
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashMap;
 use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 use std::ops::DerefMut;
 use std::path::PathBuf;
@@ -30,12 +31,16 @@ use crate::chainstate::stacks::index::node::{
     clear_backptr, is_backptr, set_backptr, CursorError, TrieCursor, TrieNode, TrieNode16,
     TrieNode256, TrieNode4, TrieNode48, TrieNodeID, TrieNodeType, TriePtr, TRIEPTR_SIZE,
 };
+use crate::chainstate::stacks::index::proofs::{
+    TrieMerkleMultiProofExtension, TrieMerkleProofExtension,
+};
 use crate::chainstate::stacks::index::storage::{
     TrieFileStorage, TrieHashCalculationMode, TrieStorageConnection, TrieStorageTransaction,
 };
 use crate::chainstate::stacks::index::trie::Trie;
 use crate::chainstate::stacks::index::{
-    ClarityMarfTrieId, Error, MARFValue, MarfTrieId, TrieLeaf, TrieMerkleProof,
+    ClarityMarfTrieId, Error, MARFValue, MarfTrieId, TrieLeaf, TrieMerkleMultiProof,
+    TrieMerkleProof,
 };
 use crate::util_lib::db::Error as db_error;
 
@@ -185,6 +190,26 @@ pub trait MarfConnection<T: MarfTrieId> {
         })
     }
 
+    /// Resolve a batch of TrieHashes from the MARF to MARFValues with respect to the given
+    /// block, and make one multi-proof for all of them: of inclusion for the hashes that have
+    /// values, and of exclusion for the ones that don't.
+    fn get_with_multi_proof_from_hashes(
+        &mut self,
+        block_hash: &T,
+        hashes: &[TrieHash],
+    ) -> Result<(Vec<Option<MARFValue>>, TrieMerkleMultiProof<T>), Error> {
+        self.with_conn(|conn| {
+            let mut entries = Vec::with_capacity(hashes.len());
+            for hash in hashes.iter() {
+                let marf_value = MARF::get_by_path(conn, block_hash, hash)?;
+                entries.push((hash.clone(), marf_value));
+            }
+            let proof = TrieMerkleMultiProof::from_paths(conn, &entries, block_hash)?;
+            let values = entries.into_iter().map(|(_, value)| value).collect();
+            Ok((values, proof))
+        })
+    }
+
     fn get_block_at_height(&mut self, height: u32, tip: &T) -> Result<Option<T>, Error> {
         self.with_conn(|c| MARF::get_block_at_height(c, height, tip))
     }
@@ -1630,3 +1655,146 @@ impl<T: MarfTrieId> MARF<T> {
         self.storage.into_sqlite_conn()
     }
 }
+
+// snapshot methods
+impl<T: MarfTrieId> MARF<T> {
+    /// Visit every leaf in the trie for `block_hash`, following back-pointers into ancestor
+    /// tries.  `visit` is called with each leaf's full path and value, in no particular order.
+    pub fn walk_leaves<F>(&mut self, block_hash: &T, mut visit: F) -> Result<(), Error>
+    where
+        F: FnMut(TrieHash, MARFValue) -> Result<(), Error>,
+    {
+        let mut storage = self.storage.connection();
+        storage.open_block(block_hash)?;
+        let root_block_id = storage.get_cur_block_identifier()?;
+        let root = Trie::read_root_nohash(&mut storage)?;
+
+        // (node, local ID of the block it lives in, path bytes consumed to reach it)
+        let mut frontier = vec![(root, root_block_id, vec![])];
+        while let Some((node, block_id, mut path)) = frontier.pop() {
+            path.extend_from_slice(node.path_bytes());
+            if let TrieNodeType::Leaf(leaf) = &node {
+                let leaf_path = TrieHash::from_bytes(&path).ok_or_else(|| {
+                    Error::CorruptionError(format!(
+                        "Leaf path in {block_hash} has {} bytes",
+                        path.len()
+                    ))
+                })?;
+                visit(leaf_path, leaf.data)?;
+                continue;
+            }
+            for ptr in node.ptrs().iter() {
+                if ptr.id() == TrieNodeID::Empty as u8 {
+                    continue;
+                }
+                let (child_block_id, child_ptr) = if is_backptr(ptr.id()) {
+                    (ptr.back_block(), ptr.from_backptr())
+                } else {
+                    (block_id, ptr.clone())
+                };
+                let child_block = storage.get_block_from_local_id(child_block_id)?.clone();
+                storage.open_block_known_id(&child_block, child_block_id)?;
+                let child = storage.read_nodetype_nohash(&child_ptr)?;
+
+                let mut child_path = path.clone();
+                child_path.push(ptr.chr());
+                frontier.push((child, child_block_id, child_path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Export the tries for `block_hash` and all of its ancestors, in order of block height
+    /// (starting from 0), as trie blobs that `import_tries()` can load into another MARF.  `visit`
+    /// is called with each trie's block hash and blob.
+    pub fn export_tries<F>(&mut self, block_hash: &T, mut visit: F) -> Result<(), Error>
+    where
+        F: FnMut(&T, Vec<u8>) -> Result<(), Error>,
+    {
+        let height = self
+            .get_block_height_of(block_hash, block_hash)?
+            .ok_or(Error::NotFoundError)?;
+        let mut heights = HashMap::new();
+        for ancestor_height in 0..=height {
+            // a block's own height maps to the hash it was built under, which is not necessarily
+            // the hash it was committed to
+            let ancestor = if ancestor_height == height {
+                block_hash.clone()
+            } else {
+                self.get_bhh_at_height(block_hash, ancestor_height)?
+                    .ok_or(Error::NotFoundError)?
+            };
+            let trie_blob = self
+                .storage
+                .connection()
+                .export_trie_blob(&ancestor, |back_block| {
+                    heights.get(back_block).copied().ok_or_else(|| {
+                        Error::CorruptionError(format!(
+                            "Trie for {ancestor} points back to {back_block}, which is not an ancestor"
+                        ))
+                    })
+                })?;
+            visit(&ancestor, trie_blob)?;
+            heights.insert(ancestor, ancestor_height);
+        }
+        Ok(())
+    }
+
+    /// Import the tries for `block_hash` and all of its ancestors into this empty MARF, as
+    /// exported by `export_tries()`.  Nothing is stored unless the last trie is for `block_hash`
+    /// and its root hash is `expected_root_hash`, which commits to every ancestor's trie via the
+    /// Merkle skip-list.  `load_side_store` is then called with the still-uncommitted transaction,
+    /// so it can read the imported tries and write to the MARF's sqlite DB; the tries are only
+    /// stored if it succeeds.
+    pub fn import_tries<I, F>(
+        &mut self,
+        block_hash: &T,
+        tries: I,
+        expected_root_hash: &TrieHash,
+        load_side_store: F,
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = Result<(T, Vec<u8>), Error>>,
+        F: FnOnce(&mut MarfTransaction<T>) -> Result<(), Error>,
+    {
+        if self.storage.readonly() {
+            return Err(Error::ReadOnlyError);
+        }
+        let mut tx = self.begin_tx()?;
+        let storage = &mut tx.storage;
+        if storage.num_blocks() > 0 {
+            return Err(Error::ExistsError);
+        }
+
+        // imported block hashes, by height
+        let mut imported: Vec<T> = vec![];
+        let mut root_hash = None;
+        for trie in tries {
+            let (trie_block_hash, trie_blob) = trie?;
+            let parent = imported.last().cloned().unwrap_or_else(T::sentinel);
+            let trie_root_hash =
+                storage.import_trie_blob(&trie_block_hash, &parent, trie_blob, |height| {
+                    imported.get(height as usize).cloned().ok_or_else(|| {
+                        Error::CorruptionError(format!(
+                            "Trie for {trie_block_hash} points back to missing height {height}"
+                        ))
+                    })
+                })?;
+            imported.push(trie_block_hash);
+            root_hash = Some(trie_root_hash);
+        }
+
+        if imported.last() != Some(block_hash) {
+            return Err(Error::CorruptionError(format!(
+                "Imported tries do not end with {block_hash}"
+            )));
+        }
+        if root_hash.as_ref() != Some(expected_root_hash) {
+            return Err(Error::CorruptionError(format!(
+                "Imported trie for {block_hash} has root hash {root_hash:?}, but expected {expected_root_hash}"
+            )));
+        }
+        load_side_store(&mut tx)?;
+        tx.commit()
+    }
+}
@@ -42,51 +42,11 @@ pub mod trie_sql;
 #[cfg(test)]
 pub mod test;
 
-#[derive(Debug)]
-pub struct TrieMerkleProof<T: MarfTrieId>(pub Vec<TrieMerkleProofType<T>>);
-
-pub trait ClarityMarfTrieId:
-    PartialEq + Clone + std::fmt::Display + std::fmt::Debug + std::convert::From<[u8; 32]>
-{
-    fn as_bytes(&self) -> &[u8];
-    fn to_bytes(self) -> [u8; 32];
-    fn from_bytes(from: [u8; 32]) -> Self;
-    fn sentinel() -> Self;
-}
-
-#[derive(Clone)]
-pub enum TrieMerkleProofType<T> {
-    Node4((u8, ProofTrieNode<T>, [TrieHash; 3])),
-    Node16((u8, ProofTrieNode<T>, [TrieHash; 15])),
-    Node48((u8, ProofTrieNode<T>, [TrieHash; 47])),
-    Node256((u8, ProofTrieNode<T>, [TrieHash; 255])),
-    Leaf((u8, TrieLeaf)),
-    Shunt((i64, Vec<TrieHash>)),
-}
-
-/// Merkle Proof Trie Pointers have a different structure
-///   than the runtime representation --- the proof includes
-///   the block header hash for back pointers.
-#[derive(Debug, Clone, PartialEq)]
-pub struct ProofTrieNode<T> {
-    pub id: u8,
-    pub path: Vec<u8>,
-    pub ptrs: Vec<ProofTriePtr<T>>,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub struct ProofTriePtr<T> {
-    pub id: u8,
-    pub chr: u8,
-    pub back_block: T,
-}
-
-/// Leaf of a Trie.
-#[derive(Clone)]
-pub struct TrieLeaf {
-    pub path: Vec<u8>,   // path to be lazily expanded
-    pub data: MARFValue, // the actual data
-}
+pub use stacks_common::types::marf::{
+    ClarityMarfTrieId, MARFValue, ProofTrieNode, ProofTriePtr, TrieLeaf, TrieMerkleMultiProof,
+    TrieMerkleMultiProofEntry, TrieMerkleProof, TrieMerkleProofType, MARF_VALUE_ENCODED_SIZE,
+    SENTINEL_ARRAY,
+};
 
 pub trait MarfTrieId:
     ClarityMarfTrieId
@@ -100,143 +60,12 @@ pub trait MarfTrieId:
 {
 }
 
-pub const SENTINEL_ARRAY: [u8; 32] = [255u8; 32];
-
-macro_rules! impl_clarity_marf_trie_id {
-    ($thing:ident) => {
-        impl ClarityMarfTrieId for $thing {
-            fn as_bytes(&self) -> &[u8] {
-                self.as_ref()
-            }
-            fn to_bytes(self) -> [u8; 32] {
-                self.0
-            }
-            fn sentinel() -> Self {
-                Self(SENTINEL_ARRAY.clone())
-            }
-            fn from_bytes(bytes: [u8; 32]) -> Self {
-                Self(bytes)
-            }
-        }
-
-        impl From<MARFValue> for $thing {
-            fn from(m: MARFValue) -> Self {
-                let h = m.0;
-                let mut d = [0u8; 32];
-                for i in 0..32 {
-                    d[i] = h[i];
-                }
-                for i in 32..h.len() {
-                    if h[i] != 0 {
-                        panic!(
-                            "Failed to convert MARF value into BHH: data stored after 32nd byte"
-                        );
-                    }
-                }
-                Self(d)
-            }
-        }
-    };
-}
-
-impl_clarity_marf_trie_id!(BurnchainHeaderHash);
-impl_clarity_marf_trie_id!(StacksBlockId);
-impl_clarity_marf_trie_id!(SortitionId);
-#[cfg(test)]
-impl_clarity_marf_trie_id!(BlockHeaderHash);
-
 impl MarfTrieId for SortitionId {}
 impl MarfTrieId for StacksBlockId {}
 impl MarfTrieId for BurnchainHeaderHash {}
 #[cfg(test)]
 impl MarfTrieId for BlockHeaderHash {}
 
-/// Structure that holds the actual data in a MARF leaf node.
-/// It only stores the hash of some value string, but we add 8 extra bytes for future extensions.
-/// If not used (the rule today), then they should all be 0.
-pub struct MARFValue(pub [u8; 40]);
-impl_array_newtype!(MARFValue, u8, 40);
-impl_array_hexstring_fmt!(MARFValue);
-impl_byte_array_newtype!(MARFValue, u8, 40);
-impl_byte_array_message_codec!(MARFValue, 40);
-pub const MARF_VALUE_ENCODED_SIZE: u32 = 40;
-
-impl From<u32> for MARFValue {
-    fn from(value: u32) -> MARFValue {
-        let h = value.to_le_bytes();
-        let mut d = [0u8; MARF_VALUE_ENCODED_SIZE as usize];
-        if h.len() > MARF_VALUE_ENCODED_SIZE as usize {
-            panic!("Cannot convert a u32 into a MARF Value.");
-        }
-        d[..h.len()].copy_from_slice(&h[..]);
-        MARFValue(d)
-    }
-}
-
-impl<T: MarfTrieId> From<T> for MARFValue {
-    fn from(bhh: T) -> MARFValue {
-        let h = bhh.to_bytes();
-        let mut d = [0u8; MARF_VALUE_ENCODED_SIZE as usize];
-        if h.len() > MARF_VALUE_ENCODED_SIZE as usize {
-            panic!("Cannot convert a BHH into a MARF Value.");
-        }
-        d[..h.len()].copy_from_slice(&h[..]);
-        MARFValue(d)
-    }
-}
-
-impl From<MARFValue> for u32 {
-    fn from(m: MARFValue) -> u32 {
-        let h = m.0;
-        let mut d = [0u8; 4];
-
-        d[..4].copy_from_slice(&h[..4]);
-        for i in 4..h.len() {
-            if h[i] != 0 {
-                panic!("Failed to convert MARF value into u32: data stored after 4th byte");
-            }
-        }
-        u32::from_le_bytes(d)
-    }
-}
-
-impl MARFValue {
-    /// Construct from a TRIEHASH_ENCODED_SIZE-length slice
-    pub fn from_value_hash_bytes(h: &[u8; TRIEHASH_ENCODED_SIZE]) -> MARFValue {
-        let mut d = [0u8; MARF_VALUE_ENCODED_SIZE as usize];
-        d[..TRIEHASH_ENCODED_SIZE].copy_from_slice(&h[..TRIEHASH_ENCODED_SIZE]);
-        MARFValue(d)
-    }
-
-    /// Construct from a TrieHash
-    pub fn from_value_hash(h: &TrieHash) -> MARFValue {
-        MARFValue::from_value_hash_bytes(h.as_bytes())
-    }
-
-    /// Construct from a String that encodes a value inserted into the underlying data store
-    pub fn from_value(s: &str) -> MARFValue {
-        let mut tmp = [0u8; 32];
-
-        let mut hasher = TrieHasher::new();
-        hasher.update(s.as_bytes());
-        tmp.copy_from_slice(hasher.finalize().as_slice());
-
-        MARFValue::from_value_hash_bytes(&tmp)
-    }
-
-    /// Convert to a byte vector
-    pub fn to_vec(&self) -> Vec<u8> {
-        self.as_bytes().to_vec()
-    }
-
-    /// Extract the value hash from the MARF value
-    pub fn to_value_hash(&self) -> TrieHash {
-        let mut h = [0u8; TRIEHASH_ENCODED_SIZE];
-        h.copy_from_slice(&self.0[0..TRIEHASH_ENCODED_SIZE]);
-        TrieHash(h)
-    }
-}
-
 #[derive(Debug)]
 pub enum Error {
     NotOpenedError,
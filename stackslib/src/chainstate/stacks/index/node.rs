@@ -59,17 +59,7 @@ impl error::Error for CursorError {
     }
 }
 
-// All numeric values of a Trie node when encoded.
-// They are all 7-bit numbers -- the 8th bit is used to indicate whether or not the value
-// identifies a back-pointer to be followed.
-define_u8_enum!(TrieNodeID {
-    Empty = 0,
-    Leaf = 1,
-    Node4 = 2,
-    Node16 = 3,
-    Node48 = 4,
-    Node256 = 5
-});
+pub use stacks_common::types::marf::TrieNodeID;
 
 /// A node ID encodes a back-pointer if its high bit is set
 pub fn is_backptr(id: u8) -> bool {
@@ -577,56 +567,6 @@ impl<T: MarfTrieId> TrieCursor<T> {
     }
 }
 
-impl PartialEq for TrieLeaf {
-    fn eq(&self, other: &TrieLeaf) -> bool {
-        self.path == other.path && self.data.as_bytes() == other.data.as_bytes()
-    }
-}
-
-impl TrieLeaf {
-    pub fn new(path: &[u8], data: &[u8]) -> TrieLeaf {
-        assert!(data.len() <= 40);
-        let mut bytes = [0u8; 40];
-        bytes.copy_from_slice(&data[..]);
-        TrieLeaf {
-            path: path.to_owned(),
-            data: MARFValue(bytes),
-        }
-    }
-
-    pub fn from_value(path: &[u8], value: MARFValue) -> TrieLeaf {
-        TrieLeaf {
-            path: path.to_owned(),
-            data: value,
-        }
-    }
-}
-
-impl fmt::Debug for TrieLeaf {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "TrieLeaf(path={} data={})",
-            &to_hex(&self.path),
-            &self.data.to_hex()
-        )
-    }
-}
-
-impl StacksMessageCodec for TrieLeaf {
-    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
-        self.path.consensus_serialize(fd)?;
-        self.data.consensus_serialize(fd)
-    }
-
-    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<TrieLeaf, codec_error> {
-        let path = read_next(fd)?;
-        let data = read_next(fd)?;
-
-        Ok(TrieLeaf { path, data })
-    }
-}
-
 /// Trie node with four children
 #[derive(Clone, PartialEq)]
 pub struct TrieNode4 {
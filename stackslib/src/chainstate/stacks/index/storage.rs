@@ -38,8 +38,8 @@ use stacks_common::util::hash::to_hex;
 use stacks_common::util::log;
 
 use crate::chainstate::stacks::index::bits::{
-    get_node_byte_len, get_node_hash, read_block_identifier, read_hash_bytes, read_node_hash_bytes,
-    read_nodetype, read_root_hash, write_nodetype_bytes,
+    get_leaf_hash, get_node_byte_len, get_node_hash, read_block_identifier, read_hash_bytes,
+    read_node_hash_bytes, read_nodetype, read_root_hash, write_nodetype_bytes,
 };
 use crate::chainstate::stacks::index::cache::*;
 use crate::chainstate::stacks::index::file::{TrieFile, TrieFileNodeHashReader};
@@ -897,10 +897,24 @@ impl<T: MarfTrieId> TrieRAM<T> {
 
         let mut next_index = 1;
 
+        // nodes are stored in breadth-first order, so each child is stored after the last one.
+        // Anything else (e.g. a cycle) is corruption.
+        let mut last_disk_ptr = root_disk_ptr;
+        let mut check_disk_ptr = |ptr: &TriePtr| {
+            if u64::from(ptr.ptr()) <= last_disk_ptr {
+                return Err(Error::CorruptionError(format!(
+                    "Trie for {bhh:?} has a node out of order at {ptr:?}"
+                )));
+            }
+            last_disk_ptr = u64::from(ptr.ptr());
+            Ok(())
+        };
+
         if let TrieNodeType::Node256(ref mut data) = root_node {
             // queue children in the same order we stored them
             for ptr in data.ptrs.iter_mut() {
                 if ptr.id() != TrieNodeID::Empty as u8 && !is_backptr(ptr.id()) {
+                    check_disk_ptr(ptr)?;
                     frontier.push_back((*ptr).clone());
 
                     // fix up ptrs
@@ -937,6 +951,7 @@ impl<T: MarfTrieId> TrieRAM<T> {
 
                 for ptr in ptrs {
                     if ptr.id() != TrieNodeID::Empty as u8 && !is_backptr(ptr.id()) {
+                        check_disk_ptr(ptr)?;
                         frontier.push_back((*ptr).clone());
 
                         // fix up ptrs
@@ -1824,6 +1839,125 @@ impl<'a, T: MarfTrieId> TrieStorageTransaction<'a, T> {
         Ok(())
     }
 
+    /// Extend the forest of Tries to include a confirmed block whose trie is an exported trie blob
+    /// (see `TrieStorageConnection::export_trie_blob()`), and flush it.  The blob's back-pointers
+    /// identify their blocks by height, and `unmap_back_block` maps each height back to the
+    /// ancestor of `bhh` at that height, which must already be in the forest.
+    ///
+    /// The blob is not trusted.  Every node hash is recalculated, and every back-pointer must
+    /// point to the node it would reach by walking its own path from the root of its ancestor's
+    /// trie.  Returns the trie's MARF root hash.
+    pub fn import_trie_blob<F>(
+        &mut self,
+        bhh: &T,
+        parent: &T,
+        blob: Vec<u8>,
+        mut unmap_back_block: F,
+    ) -> Result<TrieHash, Error>
+    where
+        F: FnMut(u32) -> Result<T, Error>,
+    {
+        let mut trie_ram = TrieRAM::load(&mut Cursor::new(blob), bhh)?;
+        if trie_ram.parent != *parent {
+            return Err(Error::CorruptionError(format!(
+                "Trie for {bhh} has parent {}, not {parent}",
+                &trie_ram.parent
+            )));
+        }
+
+        // TrieRAM::load() numbers the nodes in breadth-first order, so each node's path prefix
+        // is known before the node is visited.
+        let mut prefixes = vec![None; trie_ram.data.len()];
+        prefixes[0] = Some(vec![]);
+        let mut backptrs = vec![];
+        for i in 0..trie_ram.data.len() {
+            let mut path = prefixes[i].take().ok_or_else(|| {
+                Error::CorruptionError(format!("Trie for {bhh} has an unreachable node"))
+            })?;
+            let (node, hash) = &mut trie_ram.data[i];
+            path.extend_from_slice(node.path_bytes());
+            if let TrieNodeType::Leaf(leaf) = node {
+                *hash = get_leaf_hash(leaf);
+                continue;
+            }
+            for ptr in node.ptrs_mut().iter_mut() {
+                if ptr.id() == TrieNodeID::Empty as u8 {
+                    continue;
+                }
+                let mut child_path = path.clone();
+                child_path.push(ptr.chr());
+                if is_backptr(ptr.id()) {
+                    let back_block = unmap_back_block(ptr.back_block())?;
+                    ptr.back_block = self.get_block_id_caching(&back_block)?;
+                    backptrs.push((ptr.clone(), back_block, child_path));
+                } else {
+                    let prefix = prefixes.get_mut(ptr.ptr() as usize).ok_or_else(|| {
+                        Error::CorruptionError(format!("Trie for {bhh} has a dangling pointer"))
+                    })?;
+                    *prefix = Some(child_path);
+                }
+            }
+        }
+        for (backptr, back_block, path) in backptrs.iter() {
+            self.check_imported_backptr(backptr, back_block, path)?;
+        }
+
+        self.open_block(parent)?;
+        self.extend_to_block(bhh)?;
+        self.data.uncommitted_writes = Some((bhh.clone(), UncommittedState::RW(trie_ram)));
+
+        // The MARF root hash mixes in the root hashes of ancestors found via the trie's own
+        // block-height keys.  Look them up first, so that a trie without them is an error.
+        Trie::get_trie_ancestor_hashes_bytes(self)?;
+
+        let hash_calculation_mode = self.hash_calculation_mode;
+        self.hash_calculation_mode = TrieHashCalculationMode::Deferred;
+        let root_hash_res = self.seal();
+        self.hash_calculation_mode = hash_calculation_mode;
+        let root_hash = root_hash_res?;
+        self.flush()?;
+        Ok(root_hash)
+    }
+
+    /// Check that an imported trie's `backptr`, which is reached along `path` from that trie's
+    /// root, points to the node reached along `path` from the root of `back_block`'s trie.
+    fn check_imported_backptr(
+        &mut self,
+        backptr: &TriePtr,
+        back_block: &T,
+        path: &[u8],
+    ) -> Result<(), Error> {
+        let corrupt = || {
+            Error::CorruptionError(format!(
+                "Back-pointer {backptr:?} does not point to its node in {back_block}"
+            ))
+        };
+        self.open_block_known_id(back_block, backptr.back_block())?;
+        let root_ptr = self.root_trieptr();
+        let mut node = self.read_nodetype_nohash(&root_ptr)?;
+        let mut consumed = vec![];
+        loop {
+            consumed.extend_from_slice(node.path_bytes());
+            if !path.starts_with(&consumed) {
+                return Err(corrupt());
+            }
+            let chr = *path.get(consumed.len()).ok_or_else(corrupt)?;
+            let ptr = node.walk(chr).ok_or_else(corrupt)?;
+            if is_backptr(ptr.id()) {
+                // the node must be in `back_block` itself
+                return Err(corrupt());
+            }
+            if consumed.len() + 1 == path.len() {
+                if ptr.id() != clear_backptr(backptr.id()) || ptr.ptr() != backptr.ptr() {
+                    return Err(corrupt());
+                }
+                return Ok(());
+            }
+            node = self.read_nodetype_nohash(&ptr)?;
+            consumed.push(chr);
+        }
+    }
+
     /// Extend the forest of Tries to include a new unconfirmed block.
     /// If the unconfirmed block (bhh) already exists, then load up its trie as the uncommitted_writes
     /// trie.
@@ -1972,6 +2106,47 @@ impl<T: MarfTrieId> TrieStorageConnection<'_, T> {
         root_hash_res
     }
 
+    /// Read a confirmed block's trie blob in its entirety
+    fn read_trie_blob(&mut self, bhh: &T) -> Result<Vec<u8>, Error> {
+        let block_id =
+            trie_sql::get_confirmed_block_identifier(&self.db, bhh)?.ok_or(Error::NotFoundError)?;
+        match self.blobs.as_mut() {
+            Some(blobs) => blobs.read_trie_blob(&self.db, block_id),
+            None => {
+                let mut fd = trie_sql::open_trie_blob_readonly(&self.db, block_id)?;
+                let mut trie_blob = vec![];
+                fd.read_to_end(&mut trie_blob)?;
+                Ok(trie_blob)
+            }
+        }
+    }
+
+    /// Export the trie for a confirmed block as a trie blob that can be imported into another
+    /// MARF with `TrieStorageTransaction::import_trie_blob()`.  Local block IDs are specific to
+    /// this MARF, so each back-pointer's block is given by `map_back_block` instead (i.e. its
+    /// height).
+    pub fn export_trie_blob<F>(&mut self, bhh: &T, mut map_back_block: F) -> Result<Vec<u8>, Error>
+    where
+        F: FnMut(&T) -> Result<u32, Error>,
+    {
+        let trie_blob = self.read_trie_blob(bhh)?;
+        let mut trie_ram = TrieRAM::load(&mut Cursor::new(trie_blob), bhh)?;
+        for (node, _) in trie_ram.data.iter_mut() {
+            if node.is_leaf() {
+                continue;
+            }
+            for ptr in node.ptrs_mut().iter_mut() {
+                if is_backptr(ptr.id()) {
+                    let back_block = self.get_block_hash_caching(ptr.back_block())?;
+                    ptr.back_block = map_back_block(back_block)?;
+                }
+            }
+        }
+        let mut buffer = Cursor::new(vec![]);
+        trie_ram.dump_consume(&mut buffer)?;
+        Ok(buffer.into_inner())
+    }
+
     pub fn check_cached_ancestor_hashes_bytes(&mut self, bhh: &T) -> Option<Vec<TrieHash>> {
         if let Some((ref cached_bhh, ref cached_bytes)) = self.data.trie_ancestor_hash_bytes_cache {
             if cached_bhh == bhh {
@@ -2227,3 +2227,111 @@ fn test_marf_unconfirmed() {
     .unwrap_err();
     assert!(matches!(e, Error::NotFoundError));
 }
+
+#[test]
+fn marf_snapshot_export_import() {
+    for marf_opts in MARFOpenOpts::all().into_iter() {
+        test_debug!("With {:?}", &marf_opts);
+        let mut marf = MARF::from_storage(TrieFileStorage::new_memory(marf_opts.clone()).unwrap());
+
+        let mut parent = BlockHeaderHash::sentinel();
+        for i in 0..20u8 {
+            let block = BlockHeaderHash([i + 1; 32]);
+            marf.begin(&parent, &block).unwrap();
+            marf.insert(
+                &format!("key-{i}"),
+                MARFValue::from_value(&format!("value-{i}")),
+            )
+            .unwrap();
+            // overwritten in every block, so only the latest value is in the snapshot
+            marf.insert("shared", MARFValue::from_value(&format!("shared-{i}")))
+                .unwrap();
+            marf.commit().unwrap();
+            parent = block;
+        }
+        let tip = parent;
+
+        // a fork off of block 5, so local block IDs differ from block heights
+        marf.begin(&BlockHeaderHash([5; 32]), &BlockHeaderHash([0xfe; 32]))
+            .unwrap();
+        marf.insert("shared", MARFValue::from_value("fork"))
+            .unwrap();
+        marf.commit().unwrap();
+
+        let mut tries = vec![];
+        marf.export_tries(&tip, |block_hash, trie_blob| {
+            tries.push((block_hash.clone(), trie_blob));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(tries.len(), 20);
+        assert_eq!(tries[0].0, BlockHeaderHash([1; 32]));
+        assert_eq!(tries[19].0, tip);
+        let root_hash = marf.get_root_hash_at(&tip).unwrap();
+
+        // a tampered ancestor trie changes the root hash, so nothing is imported
+        let mut tampered = tries.clone();
+        let trie_blob = &mut tampered[3].1;
+        *trie_blob.last_mut().unwrap() ^= 0x01;
+        let mut bad_import =
+            MARF::from_storage(TrieFileStorage::new_memory(marf_opts.clone()).unwrap());
+        bad_import
+            .import_tries(&tip, tampered.into_iter().map(Ok), &root_hash, |_| Ok(()))
+            .unwrap_err();
+        assert!(matches!(
+            bad_import.get_root_hash_at(&BlockHeaderHash([1; 32])),
+            Err(Error::NotFoundError)
+        ));
+
+        // so is a snapshot that stops short of the tip
+        let mut bad_import =
+            MARF::from_storage(TrieFileStorage::new_memory(marf_opts.clone()).unwrap());
+        bad_import
+            .import_tries(
+                &tip,
+                tries[..19].iter().cloned().map(Ok),
+                &root_hash,
+                |_| Ok(()),
+            )
+            .unwrap_err();
+
+        let mut imported =
+            MARF::from_storage(TrieFileStorage::new_memory(marf_opts.clone()).unwrap());
+        imported
+            .import_tries(&tip, tries.into_iter().map(Ok), &root_hash, |_| Ok(()))
+            .unwrap();
+        assert_eq!(imported.get_root_hash_at(&tip).unwrap(), root_hash);
+
+        for i in 0..20u8 {
+            assert_eq!(
+                imported.get(&tip, &format!("key-{i}")).unwrap(),
+                Some(MARFValue::from_value(&format!("value-{i}")))
+            );
+        }
+        assert_eq!(
+            imported.get(&tip, "shared").unwrap(),
+            Some(MARFValue::from_value("shared-19"))
+        );
+        // ancestors' tries are imported too
+        assert_eq!(
+            imported.get(&BlockHeaderHash([5; 32]), "shared").unwrap(),
+            Some(MARFValue::from_value("shared-4"))
+        );
+
+        // both MARFs can be extended from the snapshotted block, with the same results
+        let next = BlockHeaderHash([0xfd; 32]);
+        for m in [&mut marf, &mut imported] {
+            m.begin(&tip, &next).unwrap();
+            m.insert("next", MARFValue::from_value("next")).unwrap();
+            m.commit().unwrap();
+        }
+        assert_eq!(
+            marf.get_root_hash_at(&next).unwrap(),
+            imported.get_root_hash_at(&next).unwrap()
+        );
+        assert_eq!(
+            imported.get(&next, "key-3").unwrap(),
+            Some(MARFValue::from_value("value-3"))
+        );
+    }
+}
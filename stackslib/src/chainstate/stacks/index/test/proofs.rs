@@ -14,6 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashSet;
+
+use stacks_common::codec::StacksMessageCodec;
+
 use super::*;
 use crate::chainstate::stacks::index::marf::*;
 use crate::chainstate::stacks::index::test::*;
@@ -197,3 +201,127 @@ fn ncc_verifier_catches_stale_proof() {
     println!("DEBUG: verify(old_v)");
     assert!(!proof_5.verify(&triepath_4, &marf_value_4, &root_hash_5, &root_to_block));
 }
+
+#[test]
+fn multi_proof_verifies_batch_of_keys() {
+    let marf_opts = MARFOpenOpts::default();
+    let mut m = MARF::from_path(":memory:", marf_opts).unwrap();
+
+    let mut parent = BlockHeaderHash::sentinel();
+    let mut keys = vec![];
+    for i in 0..10u8 {
+        let block = BlockHeaderHash([i; 32]);
+        m.begin(&parent, &block).unwrap();
+        for j in 0..5u8 {
+            let key = format!("key-{i}-{j}");
+            let value = format!("value-{i}-{j}");
+            m.insert(&key, MARFValue::from_value(&value)).unwrap();
+            keys.push((key, value));
+        }
+        m.commit().unwrap();
+        parent = block;
+    }
+    let tip = parent;
+    let root_hash = m.get_root_hash_at(&tip).unwrap();
+
+    // prove keys from the tip and from ancestors, plus some that aren't there
+    let mut paths: Vec<_> = keys
+        .iter()
+        .step_by(3)
+        .map(|(key, _)| TrieHash::from_key(key))
+        .collect();
+    let present = paths.len();
+    for i in 0..20 {
+        paths.push(TrieHash::from_key(&format!("missing-key-{i}")));
+    }
+
+    let (values, proof) = m.get_with_multi_proof_from_hashes(&tip, &paths).unwrap();
+    assert_eq!(values.len(), paths.len());
+    assert!(values[present..].iter().all(|value| value.is_none()));
+    assert_eq!(proof.entries.len(), paths.len());
+    for ((key, value), marf_value) in keys.iter().step_by(3).zip(values.iter()) {
+        assert_eq!(marf_value.as_ref(), Some(&MARFValue::from_value(value)));
+        let entry = proof.get_entry(&TrieHash::from_key(key)).unwrap();
+        assert_eq!(entry.value, Some(MARFValue::from_value(value)));
+    }
+    for path in paths[present..].iter() {
+        assert_eq!(proof.get_entry(path).unwrap().value, None);
+    }
+    assert!(!proof.ancestor_roots.is_empty());
+
+    // shared proof nodes are only stored once
+    let total_nodes: usize = proof.entries.iter().map(|entry| entry.nodes.len()).sum();
+    assert!(proof.nodes.len() < total_nodes);
+
+    // the proof survives serialization, and verifies as a whole and entry by entry
+    let proof: TrieMerkleMultiProof<BlockHeaderHash> =
+        TrieMerkleMultiProof::consensus_deserialize(&mut &proof.serialize_to_vec()[..]).unwrap();
+    assert!(proof.verify(&root_hash));
+    let root_to_block: HashMap<_, _> = proof.ancestor_roots.iter().cloned().collect();
+    for (i, entry) in proof.entries.iter().enumerate() {
+        let single_proof = proof.get_proof(i).unwrap();
+        match entry.value.as_ref() {
+            Some(value) => {
+                assert!(single_proof.verify(&entry.path, value, &root_hash, &root_to_block));
+                assert!(!single_proof.verify_exclusion(&entry.path, &root_hash, &root_to_block));
+            }
+            None => {
+                assert!(single_proof.verify_exclusion(&entry.path, &root_hash, &root_to_block));
+                // an exclusion proof for one path doesn't prove another is absent
+                assert!(!single_proof.verify_exclusion(
+                    &TrieHash::from_key(&keys[0].0),
+                    &root_hash,
+                    &root_to_block
+                ));
+            }
+        }
+    }
+
+    // both kinds of exclusion proof are covered: a leaf for another path, and a node with no
+    // child for the path
+    let terminal_kinds: HashSet<_> = proof.entries[present..]
+        .iter()
+        .map(|entry| {
+            let node = &proof.nodes[entry.nodes[0] as usize];
+            matches!(node, TrieMerkleProofType::Terminal(_))
+        })
+        .collect();
+    assert_eq!(terminal_kinds.len(), 2);
+
+    // a present key can't be proven absent
+    let present_path = TrieHash::from_key(&keys[0].0);
+    assert!(matches!(
+        TrieMerkleProof::from_absent_path(&mut m.borrow_storage_backend(), &present_path, &tip),
+        Err(Error::NotFoundError)
+    ));
+
+    // a proof for a different root hash fails
+    let parent_root_hash = m.get_root_hash_at(&BlockHeaderHash([8; 32])).unwrap();
+    assert!(!proof.verify(&parent_root_hash));
+
+    // a wrong value fails
+    let mut bad_proof = TrieMerkleMultiProof {
+        nodes: proof.nodes.clone(),
+        entries: proof.entries.clone(),
+        ancestor_roots: proof.ancestor_roots.clone(),
+    };
+    bad_proof.entries[0].value = Some(MARFValue::from_value("wrong"));
+    assert!(!bad_proof.verify(&root_hash));
+
+    // a present key claimed to be absent fails, and vice versa
+    bad_proof.entries[0].value = None;
+    assert!(!bad_proof.verify(&root_hash));
+    bad_proof.entries = proof.entries.clone();
+    bad_proof.entries[present].value = Some(MARFValue::from_value("wrong"));
+    assert!(!bad_proof.verify(&root_hash));
+
+    // missing ancestor roots fail
+    bad_proof.entries = proof.entries.clone();
+    bad_proof.ancestor_roots.clear();
+    assert!(!bad_proof.verify(&root_hash));
+
+    // bad node indexes fail
+    bad_proof.ancestor_roots = proof.ancestor_roots.clone();
+    bad_proof.entries[0].nodes.push(u32::MAX);
+    assert!(!bad_proof.verify(&root_hash));
+}
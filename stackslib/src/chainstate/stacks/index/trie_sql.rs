@@ -164,9 +164,11 @@ pub fn migrate_tables_if_needed<T: MarfTrieId>(conn: &mut Connection) -> Result<
             }
         }
     }
+    // a read-only connection can't record this, and the next read-write open will
     if first_version == SQL_MARF_SCHEMA_VERSION
         && get_migrated_version(conn) != SQL_MARF_SCHEMA_VERSION
         && !trie_sql::detect_partial_migration(conn)?
+        && !conn.is_readonly(DatabaseName::Main)?
     {
         // no migration will need to happen, so stop checking
         debug!("Marking MARF data as fully-migrated");
@@ -49,6 +49,7 @@ use crate::chainstate::burn::db::sortdb::{
 };
 use crate::chainstate::burn::operations::*;
 use crate::chainstate::burn::*;
+use crate::chainstate::nakamoto::assembly::BlockAssembly;
 use crate::chainstate::stacks::address::StacksAddressExtensions;
 use crate::chainstate::stacks::db::blocks::{MemPoolRejection, SetupBlockResult};
 use crate::chainstate::stacks::db::transactions::{
@@ -219,6 +220,8 @@ pub struct BlockBuilderSettings {
     pub miner_status: Arc<Mutex<MinerStatus>>,
     /// Should the builder attempt to confirm any parent microblocks
     pub confirm_microblocks: bool,
+    /// How the Nakamoto miner selects the transactions of a block
+    pub block_assembly: BlockAssembly,
 }
 
 impl BlockBuilderSettings {
@@ -230,6 +233,7 @@ impl BlockBuilderSettings {
             mempool_settings: MemPoolWalkSettings::default(),
             miner_status: Arc::new(Mutex::new(MinerStatus::make_ready(0))),
             confirm_microblocks: true,
+            block_assembly: BlockAssembly::default(),
         }
     }
 
@@ -241,6 +245,7 @@ impl BlockBuilderSettings {
             mempool_settings: MemPoolWalkSettings::zero(),
             miner_status: Arc::new(Mutex::new(MinerStatus::make_ready(0))),
             confirm_microblocks: true,
+            block_assembly: BlockAssembly::default(),
         }
     }
 }
@@ -2244,7 +2249,7 @@ impl StacksBlockBuilder {
     /// Applies them to the ongoing ClarityTx.
     /// If invalid transactions are encountered, they are dropped from the mempool.
     /// Returns whether or not the miner got blocked, as well as the gathered tx events
-    pub fn select_and_apply_transactions<B: BlockBuilder>(
+    pub fn select_and_apply_transactions<B: BlockBuilder + ?Sized>(
         epoch_tx: &mut ClarityTx,
         builder: &mut B,
         mempool: &mut MemPoolDB,
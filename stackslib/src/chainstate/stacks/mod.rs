@@ -65,6 +65,7 @@ pub mod auth;
 pub mod block;
 pub mod boot;
 pub mod db;
+pub mod envelope;
 pub mod events;
 pub mod index;
 pub mod miner;
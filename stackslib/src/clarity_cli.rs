@@ -16,11 +16,14 @@
 
 use std::ffi::OsStr;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::{env, fs, io, process};
 
 use clarity::vm::coverage::CoverageReporter;
+use clarity::vm::debugger::Debugger;
+use clarity::vm::profiler::Profiler;
+use clarity::vm::tooling::unit_test::{discover_tests, TestFunction};
 use lazy_static::lazy_static;
 use rand::Rng;
 use rusqlite::types::ToSql;
@@ -46,9 +49,16 @@ use crate::chainstate::stacks::boot::{
 };
 use crate::chainstate::stacks::index::storage::TrieFileStorage;
 use crate::chainstate::stacks::index::{ClarityMarfTrieId, MarfTrieId};
+use crate::clarity::vm::analysis::contract_interface_builder::bindings::{
+    generate_rust_bindings, generate_typescript_bindings, infer_print_events,
+};
 use crate::clarity::vm::analysis::contract_interface_builder::build_contract_interface;
+use crate::clarity::vm::analysis::cost_bounds::{
+    CostBoundsChecker, FunctionCostBound, KnownContract,
+};
 use crate::clarity::vm::analysis::errors::{CheckError, CheckResult};
 use crate::clarity::vm::analysis::{AnalysisDatabase, ContractAnalysis};
+use crate::clarity::vm::ast::formatter::format_contract;
 use crate::clarity::vm::ast::{build_ast_with_rules, ASTRules};
 use crate::clarity::vm::contexts::{AssetMap, GlobalContext, OwnedEnvironment};
 use crate::clarity::vm::costs::{ExecutionCost, LimitedCostTracker};
@@ -56,14 +66,20 @@ use crate::clarity::vm::database::{
     BurnStateDB, ClarityDatabase, HeadersDB, STXBalance, SqliteConnection, NULL_BURN_STATE_DB,
 };
 use crate::clarity::vm::errors::{Error, InterpreterResult, RuntimeErrorType};
-use crate::clarity::vm::types::{OptionalData, PrincipalData, QualifiedContractIdentifier};
+use crate::clarity::vm::events::StacksTransactionEvent;
+use crate::clarity::vm::types::{
+    OptionalData, PrincipalData, QualifiedContractIdentifier, StandardPrincipalData,
+};
 use crate::clarity::vm::{
     analysis, ast, eval_all, ClarityVersion, ContractContext, ContractName, SymbolicExpression,
     SymbolicExpressionType, Value,
 };
+use crate::clarity_vm::database::fork::{ChainstateFork, ForkedMarfStore};
 use crate::clarity_vm::database::marf::{MarfedKV, WritableMarfStore};
 use crate::clarity_vm::database::MemoryBackingStore;
-use crate::core::{StacksEpochId, BLOCK_LIMIT_MAINNET_205, HELIUM_BLOCK_LIMIT_20};
+use crate::core::{
+    StacksEpochId, BLOCK_LIMIT_MAINNET_205, HELIUM_BLOCK_LIMIT_20, STACKS_EPOCHS_MAINNET,
+};
 use crate::util_lib::boot::{boot_code_addr, boot_code_id};
 use crate::util_lib::db::{sqlite_open, FromColumn};
 use crate::util_lib::strings::StacksString;
@@ -112,7 +128,11 @@ fn print_usage(invoked_by: &str) {
 where command is one of:
 
   initialize         to initialize a local VM state database.
-  check              to typecheck a potential contract definition.
+  fork               to initialize a local VM state database from a node's chainstate at a given
+                     block. `launch`, `execute` and `eval` then run against it without modifying
+                     the node's databases.
+  check              to typecheck a potential contract definition, optionally bounding
+                     the worst-case cost of each of its functions with --costs.
   launch             to launch a initialize a new contract in the local state database.
   eval               to evaluate (in read-only mode) a program in a given contract context.
   eval_at_chaintip   like `eval`, but does not advance to a new block.
@@ -120,8 +140,17 @@ where command is one of:
                      must be passed eval string via stdin.
   eval_raw           to typecheck and evaluate an expression without a contract or database context.
   repl               to typecheck and evaluate expressions in a stdin/stdout loop.
-  execute            to execute a public function of a defined contract.
+  execute            to execute a public function of a defined contract. Pass --profile PREFIX
+                     to write flamegraphs and a summary of where its cost was spent.
+  debug              like `execute`, but in a step debugger. Pass --script to run debugger
+                     commands from a file instead of stdin.
   generate_address   to generate a random Stacks public address for testing purposes.
+  generate_bindings  to generate Rust and TypeScript client bindings for a local or deployed
+                     contract.
+  fmt                to format contract source files in place. Pass --check to only list the files
+                     that are not formatted.
+  test               to deploy contracts into a fresh in-memory VM state and run the `test-*`
+                     public functions of test contracts against it, each in isolation.
 ",
         invoked_by
     );
@@ -190,6 +219,20 @@ impl ClarityStorage for WritableMarfStore<'_> {
     }
 }
 
+impl ClarityStorage for ForkedMarfStore<'_> {
+    fn get_clarity_db<'a>(
+        &'a mut self,
+        headers_db: &'a dyn HeadersDB,
+        burn_db: &'a dyn BurnStateDB,
+    ) -> ClarityDatabase<'a> {
+        self.as_clarity_db(headers_db, burn_db)
+    }
+
+    fn get_analysis_db(&mut self) -> AnalysisDatabase<'_> {
+        self.as_analysis_db()
+    }
+}
+
 impl ClarityStorage for MemoryBackingStore {
     fn get_clarity_db<'a>(
         &'a mut self,
@@ -204,6 +247,26 @@ impl ClarityStorage for MemoryBackingStore {
     }
 }
 
+/// The chain that the VM runs against: either the CLI's own simulated chain, or a fork of a
+/// node's chainstate.
+struct CLIChainView<'a> {
+    mainnet: bool,
+    epoch: StacksEpochId,
+    headers_db: &'a dyn HeadersDB,
+    burn_db: &'a dyn BurnStateDB,
+}
+
+impl<'a> CLIChainView<'a> {
+    fn simulated(header_db: &'a CLIHeadersDB) -> CLIChainView<'a> {
+        CLIChainView {
+            mainnet: header_db.is_mainnet(),
+            epoch: DEFAULT_CLI_EPOCH,
+            headers_db: header_db,
+            burn_db: &NULL_BURN_STATE_DB,
+        }
+    }
+}
+
 fn run_analysis_free<C: ClarityStorage>(
     contract_identifier: &QualifiedContractIdentifier,
     expressions: &mut [SymbolicExpression],
@@ -224,15 +287,71 @@ fn run_analysis_free<C: ClarityStorage>(
     )
 }
 
-fn run_analysis<C: ClarityStorage>(
+/// Type-check a contract with a type map, so that the types of its expressions (such as its
+/// `print` events) can be inferred for bindings and cost bounds.
+fn run_typed_analysis<C: ClarityStorage>(
     contract_identifier: &QualifiedContractIdentifier,
-    expressions: &mut [SymbolicExpression],
+    content: &str,
+    clarity_version: ClarityVersion,
+    marf_kv: &mut C,
+) -> Result<ContractAnalysis, String> {
+    let ast = parse(contract_identifier, content, clarity_version)
+        .map_err(|e| format!("Failed to parse program: {}", e))?;
+    analysis::run_analysis(
+        contract_identifier,
+        &ast,
+        &mut marf_kv.get_analysis_db(),
+        false,
+        LimitedCostTracker::new_free(),
+        DEFAULT_CLI_EPOCH,
+        clarity_version,
+        true,
+    )
+    .map_err(|(e, _)| format!("Checks failed: {}", e))
+}
+
+/// Compute the worst-case cost of each public and read-only function of a contract in
+/// `DEFAULT_CLI_EPOCH`, following `contract-call?`s into the contracts deployed in `marf_kv`.
+fn run_cost_bounds<C: ClarityStorage>(
+    contract_identifier: &QualifiedContractIdentifier,
+    content: &str,
     header_db: &CLIHeadersDB,
     marf_kv: &mut C,
-    save_contract: bool,
-) -> Result<ContractAnalysis, (CheckError, LimitedCostTracker)> {
+) -> Result<Vec<FunctionCostBound>, String> {
     let mainnet = header_db.is_mainnet();
     let clarity_version = ClarityVersion::default_for_epoch(DEFAULT_CLI_EPOCH);
+    let analysis = run_typed_analysis(contract_identifier, content, clarity_version, marf_kv)?;
+    let block_limit = STACKS_EPOCHS_MAINNET[DEFAULT_CLI_EPOCH].block_limit.clone();
+
+    let mut checker = CostBoundsChecker::new(DEFAULT_CLI_EPOCH, mainnet)
+        .map_err(|e| e.to_string())?
+        .with_resolver(|target| {
+            let (src, size, clarity_version) = {
+                let mut db = marf_kv.get_clarity_db(header_db, &NULL_BURN_STATE_DB);
+                db.begin();
+                let src = db.get_contract_src(target);
+                let size = db.get_contract_size(target).ok();
+                let analysis = db.load_contract_analysis(target).ok().flatten();
+                db.roll_back().ok()?;
+                (src?, size?, analysis?.clarity_version)
+            };
+            let analysis = run_typed_analysis(target, &src, clarity_version, marf_kv).ok()?;
+            Some(KnownContract { analysis, size })
+        });
+    checker
+        .run(&analysis, &block_limit)
+        .map_err(|e| e.to_string())
+}
+
+fn run_analysis<C: ClarityStorage + ?Sized>(
+    contract_identifier: &QualifiedContractIdentifier,
+    expressions: &mut [SymbolicExpression],
+    chain: &CLIChainView,
+    marf_kv: &mut C,
+    save_contract: bool,
+) -> Result<ContractAnalysis, (CheckError, LimitedCostTracker)> {
+    let mainnet = chain.mainnet;
+    let clarity_version = ClarityVersion::default_for_epoch(chain.epoch);
     let cost_track = LimitedCostTracker::new(
         mainnet,
         default_chain_id(mainnet),
@@ -241,8 +360,8 @@ fn run_analysis<C: ClarityStorage>(
         } else {
             HELIUM_BLOCK_LIMIT_20.clone()
         },
-        &mut marf_kv.get_clarity_db(header_db, &NULL_BURN_STATE_DB),
-        DEFAULT_CLI_EPOCH,
+        &mut marf_kv.get_clarity_db(chain.headers_db, chain.burn_db),
+        chain.epoch,
     )
     .unwrap();
     analysis::run_analysis(
@@ -251,7 +370,7 @@ fn run_analysis<C: ClarityStorage>(
         &mut marf_kv.get_analysis_db(),
         save_contract,
         cost_track,
-        DEFAULT_CLI_EPOCH,
+        chain.epoch,
         clarity_version,
         // no type map data is used in the clarity_cli
         false,
@@ -383,6 +502,54 @@ where
     result
 }
 
+fn get_fork_db_path(db_path: &str) -> String {
+    let mut fork_db_path_buf = PathBuf::from(db_path);
+    fork_db_path_buf.push("fork.sqlite");
+    fork_db_path_buf
+        .to_str()
+        .unwrap_or_else(|| panic!("FATAL: failed to convert '{}' to a string", db_path))
+        .to_string()
+}
+
+// like in_block, but if the VM state in `db_path` was created by `fork`, then `f` runs against
+//   the fork's overlay of the node's chainstate instead of the CLI's own chain.
+fn in_vm_block<F, R>(db_path: &str, f: F) -> R
+where
+    F: FnOnce(&CLIChainView, &mut dyn ClarityStorage) -> R,
+{
+    let fork_db_path = get_fork_db_path(db_path);
+    if fs::metadata(&fork_db_path).is_ok() {
+        let mut fork = friendly_expect(
+            ChainstateFork::open(&fork_db_path),
+            "Failed to open forked chainstate.",
+        );
+        let (mainnet, epoch) = (fork.is_mainnet(), fork.epoch());
+        return friendly_expect(
+            fork.with_store(|store, headers_db, burn_db| {
+                let chain = CLIChainView {
+                    mainnet,
+                    epoch,
+                    headers_db,
+                    burn_db,
+                };
+                f(&chain, store)
+            }),
+            "Failed to open forked chainstate.",
+        );
+    }
+
+    let header_db = friendly_expect(CLIHeadersDB::resume(db_path), "Failed to open CLI DB");
+    let marf_kv = friendly_expect(
+        MarfedKV::open(db_path, None, None),
+        "Failed to open VM database.",
+    );
+    let (_, _, result) = in_block(header_db, marf_kv, |header_db, mut marf| {
+        let result = f(&CLIChainView::simulated(&header_db), &mut marf);
+        (header_db, marf, result)
+    });
+    result
+}
+
 fn at_block<F, R>(blockhash: &str, mut marf_kv: MarfedKV, f: F) -> R
 where
     F: FnOnce(WritableMarfStore) -> (WritableMarfStore, R),
@@ -407,17 +574,20 @@ fn default_chain_id(mainnet: bool) -> u32 {
     chain_id
 }
 
-fn with_env_costs<F, R>(
-    mainnet: bool,
-    header_db: &CLIHeadersDB,
-    marf: &mut WritableMarfStore,
+fn with_env_costs<C, F, R>(
+    chain: &CLIChainView,
+    marf: &mut C,
     coverage: Option<&mut CoverageReporter>,
+    debugger: Option<&mut Debugger>,
+    profiler: Option<&mut Profiler>,
     f: F,
 ) -> (R, ExecutionCost)
 where
+    C: ClarityStorage + ?Sized,
     F: FnOnce(&mut OwnedEnvironment) -> R,
 {
-    let mut db = marf.as_clarity_db(header_db, &NULL_BURN_STATE_DB);
+    let mainnet = chain.mainnet;
+    let mut db = marf.get_clarity_db(chain.headers_db, chain.burn_db);
     let cost_track = LimitedCostTracker::new(
         mainnet,
         default_chain_id(mainnet),
@@ -427,7 +597,7 @@ where
             HELIUM_BLOCK_LIMIT_20.clone()
         },
         &mut db,
-        DEFAULT_CLI_EPOCH,
+        chain.epoch,
     )
     .unwrap();
     let mut vm_env = OwnedEnvironment::new_cost_limited(
@@ -435,16 +605,38 @@ where
         default_chain_id(mainnet),
         db,
         cost_track,
-        DEFAULT_CLI_EPOCH,
+        chain.epoch,
     );
     if let Some(coverage) = coverage {
         vm_env.add_eval_hook(coverage);
     }
+    if let Some(debugger) = debugger {
+        vm_env.add_eval_hook(debugger);
+    }
+    if let Some(profiler) = profiler {
+        vm_env.add_eval_hook(profiler);
+    }
     let result = f(&mut vm_env);
     let cost = vm_env.get_cost_total();
     (result, cost)
 }
 
+/// Write a profile's folded stacks and summary to files starting with `profile_prefix`, and
+/// get the paths of the files written.
+fn save_profile(profile_prefix: Option<String>, profiler: Option<Profiler>) -> Option<Vec<String>> {
+    let (profile_prefix, profiler) = (profile_prefix?, profiler?);
+    let files = friendly_expect(
+        profiler.to_files(Path::new(&profile_prefix)),
+        &format!("Failed to write profile to {}", profile_prefix),
+    );
+    Some(
+        files
+            .into_iter()
+            .map(|path| path.display().to_string())
+            .collect(),
+    )
+}
+
 /// Execute program in a transient environment. To be used only by CLI tools
 ///  for program evaluation, not by consensus critical code.
 pub fn vm_execute(program: &str, clarity_version: ClarityVersion) -> Result<Option<Value>, Error> {
@@ -494,6 +686,24 @@ fn save_coverage(
     }
 }
 
+/// Produce an lcov file from all of the coverage reference and coverage files in
+/// `coverage_folder`.
+fn make_lcov(coverage_folder: &str, lcov_output_file: &str) -> io::Result<()> {
+    let mut register_files = vec![];
+    let mut coverage_files = vec![];
+    for folder_entry in fs::read_dir(coverage_folder)? {
+        let entry_path = folder_entry?.path();
+        if entry_path.is_file() {
+            if entry_path.extension() == Some(OsStr::new("clarcovref")) {
+                register_files.push(entry_path)
+            } else if entry_path.extension() == Some(OsStr::new("clarcov")) {
+                coverage_files.push(entry_path)
+            }
+        }
+    }
+    CoverageReporter::produce_lcov(lcov_output_file, &register_files, &coverage_files)
+}
+
 struct CLIHeadersDB {
     db_path: String,
     conn: Connection,
@@ -823,6 +1033,30 @@ struct InitialAllocation {
     amount: u64,
 }
 
+/// The deployer of test contracts, if the test plan does not give one
+const DEFAULT_TEST_DEPLOYER: &str = "S1G2081040G2081040G2081040G208105NK8PE5";
+
+/// What `test` deploys, and the state it deploys into
+#[derive(Deserialize)]
+struct TestPlan {
+    /// The contracts under test, deployed in order before the test contracts
+    #[serde(default)]
+    contracts: Vec<TestPlanContract>,
+    /// The test contract files, each deployed as `<deployer>.<file stem>`
+    tests: Vec<String>,
+    deployer: Option<String>,
+    /// The `tx-sender` of tests that do not have a `@sender`. Defaults to the deployer.
+    sender: Option<String>,
+    #[serde(default)]
+    allocations: Vec<InitialAllocation>,
+}
+
+#[derive(Deserialize)]
+struct TestPlanContract {
+    contract_identifier: String,
+    path: String,
+}
+
 fn consume_arg(
     args: &mut Vec<String>,
     argnames: &[&str],
@@ -970,6 +1204,81 @@ fn install_boot_code<C: ClarityStorage>(header_db: &CLIHeadersDB, marf: &mut C)
         .unwrap();
 }
 
+fn set_stx_balance(db: &mut ClarityDatabase, principal: &PrincipalData, amount: u128) {
+    let mut snapshot = db.get_stx_balance_snapshot_genesis(principal).unwrap();
+    snapshot.set_balance(STXBalance::initial(amount));
+    snapshot.save().unwrap();
+}
+
+/// Run a unit test of a deployed test contract, with the block height advanced and the STX
+/// balances set by its annotations.  Everything the test writes is rolled back afterwards, so
+/// that each test runs against the state left by deploying the contracts.
+fn run_unit_test(
+    chain: &CLIChainView,
+    marf: &mut MemoryBackingStore,
+    coverage: Option<&mut CoverageReporter>,
+    sender: StandardPrincipalData,
+    contract_identifier: &QualifiedContractIdentifier,
+    test: &TestFunction,
+) -> (
+    Result<(Value, Vec<StacksTransactionEvent>), Error>,
+    ExecutionCost,
+) {
+    let mainnet = chain.mainnet;
+    let deploy_height = marf.get_block_height();
+    marf.set_block_height(deploy_height.saturating_add(test.annotations.advance_blocks));
+
+    let result_and_cost = {
+        let mut db = marf.as_clarity_db();
+        let cost_track = LimitedCostTracker::new(
+            mainnet,
+            default_chain_id(mainnet),
+            if mainnet {
+                BLOCK_LIMIT_MAINNET_205.clone()
+            } else {
+                HELIUM_BLOCK_LIMIT_20.clone()
+            },
+            &mut db,
+            chain.epoch,
+        )
+        .unwrap();
+        // the cost tracker must be loaded before the test's transaction is opened
+        db.begin();
+        for (principal, amount) in test.annotations.balances.iter() {
+            set_stx_balance(&mut db, principal, *amount);
+        }
+        let mut vm_env = OwnedEnvironment::new_cost_limited(
+            mainnet,
+            default_chain_id(mainnet),
+            db,
+            cost_track,
+            chain.epoch,
+        );
+        if let Some(coverage) = coverage {
+            vm_env.add_eval_hook(coverage);
+        }
+        let result = vm_env
+            .execute_transaction(
+                sender.into(),
+                None,
+                contract_identifier.clone(),
+                &test.name,
+                &[],
+            )
+            .map(|(value, _, events)| (value, events));
+        let cost = vm_env.get_cost_total();
+        let (mut db, _) = vm_env
+            .destruct()
+            .expect("BUG: unit test did not finish its transaction");
+        db.roll_back()
+            .expect("FATAL: failed to roll back unit test");
+        (result, cost)
+    };
+
+    marf.set_block_height(deploy_height);
+    result_and_cost
+}
+
 pub fn add_costs(result: &mut serde_json::Value, costs: bool, runtime: ExecutionCost) {
     if costs {
         result["costs"] = serde_json::to_value(runtime).unwrap();
@@ -982,6 +1291,18 @@ pub fn add_assets(result: &mut serde_json::Value, assets: bool, asset_map: Asset
     }
 }
 
+pub fn add_debugger_transcript(result: &mut serde_json::Value, debugger: Option<&Debugger>) {
+    if let Some(debugger) = debugger {
+        result["debugger"] = serde_json::to_value(debugger.transcript()).unwrap();
+    }
+}
+
+pub fn add_profile_files(result: &mut serde_json::Value, profile_files: Option<&Vec<String>>) {
+    if let Some(profile_files) = profile_files {
+        result["profile"] = serde_json::to_value(profile_files).unwrap();
+    }
+}
+
 pub fn add_serialized_output(result: &mut serde_json::Value, value: Value) {
     let result_raw = {
         let bytes = value.serialize_to_vec().unwrap();
@@ -1102,6 +1423,54 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 )
             }
         }
+        "fork" => {
+            let mut argv = args.to_vec();
+
+            let mainnet = !matches!(consume_arg(&mut argv, &["--testnet"], false), Ok(Some(_)));
+
+            if argv.len() != 4 {
+                eprintln!(
+                    "Usage: {} {} [--testnet] [node-working-dir] [index-block-hash] [vm-state.db]",
+                    invoked_by, argv[0]
+                );
+                eprintln!("   node-working-dir is the working directory of a Stacks node, which contains its chainstate/ and burnchain/ directories.");
+                eprintln!("   The node's databases are only read. All writes go to vm-state.db.");
+                eprintln!(
+                    "   If --testnet is given, then the node is expected to be a testnet node."
+                );
+                panic_test!();
+            }
+
+            let fork_block = friendly_expect(
+                StacksBlockId::from_hex(&argv[2]),
+                "Failed to parse index block hash.",
+            );
+            let vm_filename = &argv[3];
+            friendly_expect(
+                fs::create_dir_all(vm_filename),
+                &format!("Failed to create {}", vm_filename),
+            );
+            let fork = friendly_expect(
+                ChainstateFork::create(
+                    &argv[1],
+                    &fork_block,
+                    mainnet,
+                    &get_fork_db_path(vm_filename),
+                ),
+                "Failed to fork chainstate.",
+            );
+
+            (
+                0,
+                Some(json!({
+                    "message": "Chainstate forked.",
+                    "network": if mainnet { "mainnet" } else { "testnet" },
+                    "index_block_hash": fork.fork_block(),
+                    "stacks_block_height": fork.stacks_block_height(),
+                    "epoch": fork.epoch().to_string(),
+                })),
+            )
+        }
         "generate_address" => {
             // random 20 bytes
             let random_bytes = rand::thread_rng().gen::<[u8; 20]>();
@@ -1174,7 +1543,7 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 "Failed to parse program",
             );
 
-            let contract_analysis_res = {
+            let (contract_analysis_res, function_costs) = {
                 if argv.len() >= 3 {
                     // use a persisted marf
                     if testnet_given {
@@ -1189,24 +1558,30 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                         "Failed to open VM database.",
                     );
 
-                    let result = at_chaintip(&argv[2], marf_kv, |mut marf| {
-                        let result =
-                            run_analysis(&contract_id, &mut ast, &header_db, &mut marf, false);
-                        (marf, result)
-                    });
-                    result
+                    at_chaintip(&argv[2], marf_kv, |mut marf| {
+                        let chain = CLIChainView::simulated(&header_db);
+                        let result = run_analysis(&contract_id, &mut ast, &chain, &mut marf, false);
+                        let function_costs = (costs && result.is_ok()).then(|| {
+                            run_cost_bounds(&contract_id, &content, &header_db, &mut marf)
+                        });
+                        (marf, (result, function_costs))
+                    })
                 } else {
                     let header_db = CLIHeadersDB::new_memory(mainnet);
                     let mut analysis_marf = MemoryBackingStore::new();
 
                     install_boot_code(&header_db, &mut analysis_marf);
-                    run_analysis(
+                    let result = run_analysis(
                         &contract_id,
                         &mut ast,
-                        &header_db,
+                        &CLIChainView::simulated(&header_db),
                         &mut analysis_marf,
                         false,
-                    )
+                    );
+                    let function_costs = (costs && result.is_ok()).then(|| {
+                        run_cost_bounds(&contract_id, &content, &header_db, &mut analysis_marf)
+                    });
+                    (result, function_costs)
                 }
             };
 
@@ -1234,6 +1609,16 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 contract_analysis.take_contract_cost_tracker().get_total(),
             );
 
+            match function_costs {
+                Some(Ok(bounds)) => {
+                    result["function_costs"] = serde_json::to_value(bounds).unwrap();
+                }
+                Some(Err(e)) => {
+                    result["function_costs_error"] = json!(e);
+                }
+                None => {}
+            }
+
             if output_analysis {
                 result["analysis"] =
                     serde_json::to_value(&build_contract_interface(&contract_analysis).unwrap())
@@ -1241,6 +1626,209 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             }
             (0, Some(result))
         }
+        "generate_bindings" => {
+            let mut argv = args.to_vec();
+            let rust_file = consume_arg(&mut argv, &["--rust"], true).unwrap_or(None);
+            let typescript_file = consume_arg(&mut argv, &["--typescript"], true).unwrap_or(None);
+            let deployed = matches!(consume_arg(&mut argv, &["--deployed"], false), Ok(Some(_)));
+
+            if argv.len() < 3 {
+                eprintln!(
+                    "Usage: {} {} [--rust FILE] [--typescript FILE] [contract-identifier] [contract-definition.clar] (vm-state.db)",
+                    invoked_by, argv[0]
+                );
+                eprintln!(
+                    "       {} {} [--rust FILE] [--typescript FILE] --deployed [contract-identifier] [vm-state.db]",
+                    invoked_by, argv[0]
+                );
+                panic_test!();
+            }
+
+            let contract_identifier = friendly_expect(
+                QualifiedContractIdentifier::parse(&argv[1]),
+                "Failed to parse contract identifier.",
+            );
+
+            let analysis_res = if deployed {
+                let vm_filename = &argv[2];
+                let header_db =
+                    friendly_expect(CLIHeadersDB::resume(vm_filename), "Failed to open CLI DB");
+                let marf_kv = friendly_expect(
+                    MarfedKV::open(vm_filename, None, None),
+                    "Failed to open VM database.",
+                );
+                at_chaintip(vm_filename, marf_kv, |mut marf| {
+                    let content = {
+                        let mut db = marf.get_clarity_db(&header_db, &NULL_BURN_STATE_DB);
+                        db.begin();
+                        let content = db.get_contract_src(&contract_identifier);
+                        friendly_expect(db.roll_back(), "Failed to roll back VM database.");
+                        content
+                    };
+                    let result = match content {
+                        Some(content) => run_typed_analysis(
+                            &contract_identifier,
+                            &content,
+                            ClarityVersion::default_for_epoch(DEFAULT_CLI_EPOCH),
+                            &mut marf,
+                        ),
+                        None => Err(format!("No such contract: {}", &contract_identifier)),
+                    };
+                    (marf, result)
+                })
+            } else {
+                let content: String = friendly_expect(
+                    fs::read_to_string(&argv[2]),
+                    &format!("Error reading file: {}", argv[2]),
+                );
+                if argv.len() >= 4 {
+                    // analyze against the contracts in a persisted marf
+                    let vm_filename = &argv[3];
+                    let marf_kv = friendly_expect(
+                        MarfedKV::open(vm_filename, None, None),
+                        "Failed to open VM database.",
+                    );
+                    at_chaintip(vm_filename, marf_kv, |mut marf| {
+                        let result = run_typed_analysis(
+                            &contract_identifier,
+                            &content,
+                            ClarityVersion::default_for_epoch(DEFAULT_CLI_EPOCH),
+                            &mut marf,
+                        );
+                        (marf, result)
+                    })
+                } else {
+                    let header_db = CLIHeadersDB::new_memory(true);
+                    let mut analysis_marf = MemoryBackingStore::new();
+                    install_boot_code(&header_db, &mut analysis_marf);
+                    run_typed_analysis(
+                        &contract_identifier,
+                        &content,
+                        ClarityVersion::default_for_epoch(DEFAULT_CLI_EPOCH),
+                        &mut analysis_marf,
+                    )
+                }
+            };
+
+            let contract_analysis = match analysis_res {
+                Ok(contract_analysis) => contract_analysis,
+                Err(e) => {
+                    return (
+                        1,
+                        Some(json!({
+                            "error": {
+                                "analysis": serde_json::to_value(&e).unwrap()
+                            }
+                        })),
+                    );
+                }
+            };
+
+            let interface = friendly_expect(
+                build_contract_interface(&contract_analysis),
+                "Failed to build contract interface.",
+            );
+            let print_events = infer_print_events(&contract_analysis);
+            let rust = generate_rust_bindings(&contract_identifier, &interface, &print_events);
+            let typescript =
+                generate_typescript_bindings(&contract_identifier, &interface, &print_events);
+
+            let mut result = json!({
+                "message": "Bindings generated.",
+                "print_events": print_events.len(),
+            });
+            if rust_file.is_none() && typescript_file.is_none() {
+                result["rust"] = serde_json::Value::String(rust);
+                result["typescript"] = serde_json::Value::String(typescript);
+            } else {
+                let mut files = vec![];
+                for (path, bindings) in [(rust_file, rust), (typescript_file, typescript)] {
+                    if let Some(path) = path {
+                        friendly_expect(
+                            fs::write(&path, bindings),
+                            &format!("Failed to write {}", &path),
+                        );
+                        files.push(serde_json::Value::String(path));
+                    }
+                }
+                result["files"] = serde_json::Value::Array(files);
+            }
+            (0, Some(result))
+        }
+        "fmt" => {
+            let mut argv = args.to_vec();
+            let check = matches!(consume_arg(&mut argv, &["--check"], false), Ok(Some(_)));
+
+            if argv.len() < 2 {
+                eprintln!(
+                    "Usage: {} {} [--check] [contract-definition.clar|-]...",
+                    invoked_by, argv[0]
+                );
+                panic_test!();
+            }
+
+            let clarity_version = ClarityVersion::default_for_epoch(DEFAULT_CLI_EPOCH);
+            let mut changed = vec![];
+            let mut result = json!({});
+            for path in argv[1..].iter() {
+                let content: String = if path == "-" {
+                    let mut buffer = String::new();
+                    friendly_expect(
+                        io::stdin().read_to_string(&mut buffer),
+                        "Error reading from stdin.",
+                    );
+                    buffer
+                } else {
+                    friendly_expect(
+                        fs::read_to_string(path),
+                        &format!("Error reading file: {}", path),
+                    )
+                };
+
+                let formatted = match format_contract(&content, clarity_version, DEFAULT_CLI_EPOCH)
+                {
+                    Ok(formatted) => formatted,
+                    Err(e) => {
+                        return (
+                            1,
+                            Some(json!({
+                                "error": {
+                                    "file": path,
+                                    "format": e.to_string(),
+                                }
+                            })),
+                        );
+                    }
+                };
+
+                if formatted != content {
+                    changed.push(serde_json::Value::String(path.clone()));
+                }
+                if path == "-" {
+                    result["formatted"] = serde_json::Value::String(formatted);
+                } else if !check && formatted != content {
+                    friendly_expect(
+                        fs::write(path, formatted),
+                        &format!("Failed to write {}", path),
+                    );
+                }
+            }
+
+            if check {
+                let code = if changed.is_empty() { 0 } else { 1 };
+                result["message"] = if changed.is_empty() {
+                    "All files are formatted.".into()
+                } else {
+                    "Some files are not formatted.".into()
+                };
+                result["unformatted"] = serde_json::Value::Array(changed);
+                (code, Some(result))
+            } else {
+                result["message"] = "Formatted.".into();
+                result["changed"] = serde_json::Value::Array(changed);
+                (0, Some(result))
+            }
+        }
         "repl" => {
             let mut argv = args.to_vec();
             let mainnet = !matches!(consume_arg(&mut argv, &["--testnet"], false), Ok(Some(_)));
@@ -1376,30 +1964,21 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
 
             let evalInput = get_eval_input(invoked_by, &argv);
             let vm_filename = if argv.len() == 3 { &argv[2] } else { &argv[3] };
-            let header_db =
-                friendly_expect(CLIHeadersDB::resume(vm_filename), "Failed to open CLI DB");
-            let marf_kv = friendly_expect(
-                MarfedKV::open(vm_filename, None, None),
-                "Failed to open VM database.",
-            );
-            let mainnet = header_db.is_mainnet();
             let placeholder_context = ContractContext::new(
                 QualifiedContractIdentifier::transient(),
                 ClarityVersion::Clarity2,
             );
 
-            let (_, _, result_and_cost) = in_block(header_db, marf_kv, |header_db, mut marf| {
-                let result_and_cost =
-                    with_env_costs(mainnet, &header_db, &mut marf, None, |vm_env| {
-                        vm_env
-                            .get_exec_environment(None, None, &placeholder_context)
-                            .eval_read_only_with_rules(
-                                &evalInput.contract_identifier,
-                                &evalInput.content,
-                                ASTRules::PrecheckSize,
-                            )
-                    });
-                (header_db, marf, result_and_cost)
+            let result_and_cost = in_vm_block(vm_filename, |chain, marf| {
+                with_env_costs(chain, marf, None, None, None, |vm_env| {
+                    vm_env
+                        .get_exec_environment(None, None, &placeholder_context)
+                        .eval_read_only_with_rules(
+                            &evalInput.contract_identifier,
+                            &evalInput.content,
+                            ASTRules::PrecheckSize,
+                        )
+                })
             });
 
             match result_and_cost {
@@ -1443,7 +2022,6 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 "Failed to open VM database.",
             );
 
-            let mainnet = header_db.is_mainnet();
             let placeholder_context = ContractContext::new(
                 QualifiedContractIdentifier::transient(),
                 ClarityVersion::Clarity2,
@@ -1455,10 +2033,11 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             };
             let result_and_cost = at_chaintip(vm_filename, marf_kv, |mut marf| {
                 let result_and_cost = with_env_costs(
-                    mainnet,
-                    &header_db,
+                    &CLIChainView::simulated(&header_db),
                     &mut marf,
                     coverage.as_mut(),
+                    None,
+                    None,
                     |vm_env| {
                         vm_env
                             .get_exec_environment(None, None, &placeholder_context)
@@ -1535,14 +2114,14 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 MarfedKV::open(vm_filename, None, None),
                 "Failed to open VM database.",
             );
-            let mainnet = header_db.is_mainnet();
             let placeholder_context = ContractContext::new(
                 QualifiedContractIdentifier::transient(),
                 ClarityVersion::Clarity2,
             );
+            let chain = CLIChainView::simulated(&header_db);
             let result_and_cost = at_block(chain_tip, marf_kv, |mut marf| {
                 let result_and_cost =
-                    with_env_costs(mainnet, &header_db, &mut marf, None, |vm_env| {
+                    with_env_costs(&chain, &mut marf, None, None, None, |vm_env| {
                         vm_env
                             .get_exec_environment(None, None, &placeholder_context)
                             .eval_read_only_with_rules(
@@ -1634,48 +2213,25 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 .expect("Coverage reference file generation failure");
             }
 
-            // let header_db = CLIHeadersDB::new(vm_filename, false);
-
-            let header_db =
-                friendly_expect(CLIHeadersDB::resume(vm_filename), "Failed to open CLI DB");
-            let marf_kv = friendly_expect(
-                MarfedKV::open(vm_filename, None, None),
-                "Failed to open VM database.",
-            );
-            let mainnet = header_db.is_mainnet();
-
             let mut coverage = if coverage_folder.is_some() {
                 Some(CoverageReporter::new())
             } else {
                 None
             };
-            let (_, _, analysis_result_and_cost) =
-                in_block(header_db, marf_kv, |header_db, mut marf| {
-                    let analysis_result =
-                        run_analysis(&contract_identifier, &mut ast, &header_db, &mut marf, true);
-                    match analysis_result {
-                        Err(e) => (header_db, marf, Err(e)),
-                        Ok(analysis) => {
-                            let result_and_cost = with_env_costs(
-                                mainnet,
-                                &header_db,
-                                &mut marf,
-                                coverage.as_mut(),
-                                |vm_env| {
-                                    vm_env.initialize_versioned_contract(
-                                        contract_identifier,
-                                        ClarityVersion::Clarity2,
-                                        &contract_content,
-                                        None,
-                                        ASTRules::PrecheckSize,
-                                    )
-                                },
-                            );
-                            let (result, cost) = result_and_cost;
-                            (header_db, marf, Ok((analysis, (result, cost))))
-                        }
-                    }
-                });
+            let analysis_result_and_cost = in_vm_block(vm_filename, |chain, marf| {
+                let analysis = run_analysis(&contract_identifier, &mut ast, chain, marf, true)?;
+                let result_and_cost =
+                    with_env_costs(chain, marf, coverage.as_mut(), None, None, |vm_env| {
+                        vm_env.initialize_versioned_contract(
+                            contract_identifier,
+                            ClarityVersion::Clarity2,
+                            &contract_content,
+                            None,
+                            ASTRules::PrecheckSize,
+                        )
+                    });
+                Ok((analysis, result_and_cost))
+            });
 
             match analysis_result_and_cost {
                 Ok((contract_analysis, (Ok((_x, asset_map, events)), cost))) => {
@@ -1723,26 +2279,37 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 ),
             }
         }
-        "execute" => {
+        "execute" | "debug" => {
             let mut argv = args.to_vec();
             let coverage_folder = consume_arg(&mut argv, &["--c"], true).unwrap_or(None);
 
             let costs = matches!(consume_arg(&mut argv, &["--costs"], false), Ok(Some(_)));
             let assets = matches!(consume_arg(&mut argv, &["--assets"], false), Ok(Some(_)));
+            let profile_prefix = friendly_expect(
+                consume_arg(&mut argv, &["--profile"], true),
+                "Failed to parse --profile argument",
+            );
+
+            let debug = argv[0] == "debug";
+            let debug_script = if debug {
+                friendly_expect(
+                    consume_arg(&mut argv, &["--script"], true),
+                    "Failed to parse --script argument",
+                )
+            } else {
+                None
+            };
 
             if argv.len() < 5 {
-                eprintln!("Usage: {} {} [--costs] [--assets] [vm-state.db] [contract-identifier] [public-function-name] [sender-address] [args...]", invoked_by, argv[0]);
+                if debug {
+                    eprintln!("Usage: {} {} [--script debug-script.txt] [--costs] [--assets] [--profile PREFIX] [vm-state.db] [contract-identifier] [public-function-name] [sender-address] [args...]", invoked_by, argv[0]);
+                } else {
+                    eprintln!("Usage: {} {} [--costs] [--assets] [--profile PREFIX] [vm-state.db] [contract-identifier] [public-function-name] [sender-address] [args...]", invoked_by, argv[0]);
+                }
                 panic_test!();
             }
 
             let vm_filename = &argv[1];
-            let header_db =
-                friendly_expect(CLIHeadersDB::resume(vm_filename), "Failed to open CLI DB");
-            let marf_kv = friendly_expect(
-                MarfedKV::open(vm_filename, None, None),
-                "Failed to open VM database.",
-            );
-            let mainnet = header_db.is_mainnet();
             let contract_identifier = friendly_expect(
                 QualifiedContractIdentifier::parse(&argv[2]),
                 "Failed to parse contract identifier.",
@@ -1781,12 +2348,27 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             } else {
                 None
             };
-            let (_, _, result_and_cost) = in_block(header_db, marf_kv, |header_db, mut marf| {
-                let result_and_cost = with_env_costs(
-                    mainnet,
-                    &header_db,
-                    &mut marf,
+            // a scripted debugger's transcript is reported in the output, so that it can be
+            //  checked in CI
+            let mut debugger = match (debug, debug_script.as_ref()) {
+                (false, _) => None,
+                (true, None) => Some(Debugger::interactive()),
+                (true, Some(script_file)) => {
+                    let script = friendly_expect(
+                        fs::read_to_string(script_file),
+                        &format!("Error reading debugger script {}", script_file),
+                    );
+                    Some(Debugger::scripted(&script))
+                }
+            };
+            let mut profiler = profile_prefix.as_ref().map(|_| Profiler::new());
+            let result_and_cost = in_vm_block(vm_filename, |chain, marf| {
+                with_env_costs(
+                    chain,
+                    marf,
                     coverage.as_mut(),
+                    debugger.as_mut(),
+                    profiler.as_mut(),
                     |vm_env| {
                         vm_env.execute_transaction(
                             sender,
@@ -1796,10 +2378,10 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                             &arguments,
                         )
                     },
-                );
-                let (result, cost) = result_and_cost;
-                (header_db, marf, (result, cost))
+                )
             });
+            let debugger = debugger.filter(|_| debug_script.is_some());
+            let profile_files = save_profile(profile_prefix, profiler);
 
             match result_and_cost {
                 (Ok((x, asset_map, events)), cost) => {
@@ -1815,6 +2397,8 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                             add_serialized_output(&mut result, *data.data);
                             add_costs(&mut result, costs, cost);
                             add_assets(&mut result, assets, asset_map);
+                            add_debugger_transcript(&mut result, debugger.as_ref());
+                            add_profile_files(&mut result, profile_files.as_ref());
 
                             let events_json: Vec<_> = events
                                 .into_iter()
@@ -1835,53 +2419,305 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                             add_costs(&mut result, costs, cost);
                             add_serialized_output(&mut result, *data.data);
                             add_assets(&mut result, assets, asset_map);
+                            add_debugger_transcript(&mut result, debugger.as_ref());
+                            add_profile_files(&mut result, profile_files.as_ref());
 
                             (0, Some(result))
                         }
                     } else {
-                        let result = json!({
+                        let mut result = json!({
                             "error": {
                                 "runtime": "Expected a ResponseType result from transaction.",
                                 "output": serde_json::to_value(&x).unwrap()
                             },
                             "success": false,
                         });
+                        add_debugger_transcript(&mut result, debugger.as_ref());
+                        add_profile_files(&mut result, profile_files.as_ref());
                         (1, Some(result))
                     }
                 }
                 (Err(error), ..) => {
-                    let result = json!({
+                    let mut result = json!({
                         "error": {
                             "runtime": "Transaction execution error.",
                             "error": serde_json::to_value(&format!("{}", error)).unwrap()
                         },
                         "success": false,
                     });
+                    add_debugger_transcript(&mut result, debugger.as_ref());
+                    add_profile_files(&mut result, profile_files.as_ref());
                     (1, Some(result))
                 }
             }
         }
-        "make_lcov" => {
-            let mut register_files = vec![];
-            let mut coverage_files = vec![];
-            let coverage_folder = &args[1];
-            let lcov_output_file = &args[2];
-            for folder_entry in
-                fs::read_dir(coverage_folder).expect("Failed to read the coverage folder")
+        "test" => {
+            let mut argv = args.to_vec();
+            let mainnet = !matches!(consume_arg(&mut argv, &["--testnet"], false), Ok(Some(_)));
+            let costs = matches!(consume_arg(&mut argv, &["--costs"], false), Ok(Some(_)));
+            let coverage_folder = consume_arg(&mut argv, &["--c"], true).unwrap_or(None);
+            let lcov_output_file = friendly_expect(
+                consume_arg(&mut argv, &["--lcov"], true),
+                "Failed to parse --lcov argument",
+            );
+
+            if argv.len() != 2 || (lcov_output_file.is_some() && coverage_folder.is_none()) {
+                eprintln!(
+                    "Usage: {} {} [--testnet] [--costs] [--c coverage-folder [--lcov lcov-output-file]] [test-plan.json]",
+                    invoked_by, argv[0]
+                );
+                eprintln!("   test-plan.json is a JSON object like {{ \"contracts\": [{{ \"contract_identifier\": \"S1G2081040G2081040G2081040G208105NK8PE5.tokens\", \"path\": \"tokens.clar\" }}], \"tests\": [\"tokens_test.clar\"] }}.");
+                eprintln!("   It can also give the \"deployer\" of the test contracts, the default \"sender\" of the tests, and initial \"allocations\" like `initialize`.");
+                eprintln!("   Paths are relative to the directory of test-plan.json.");
+                eprintln!("   If --lcov is given, the coverage in coverage-folder is also written to lcov-output-file, like `make_lcov`.");
+                panic_test!();
+            }
+
+            let plan_file = &argv[1];
+            let plan_json = friendly_expect(
+                fs::read_to_string(plan_file),
+                &format!("Error reading file: {}", plan_file),
+            );
+            let plan: TestPlan =
+                friendly_expect(serde_json::from_str(&plan_json), "Failure parsing JSON");
+            let plan_dir = Path::new(plan_file).parent().unwrap_or(Path::new("."));
+
+            let deployer = friendly_expect(
+                PrincipalData::parse_standard_principal(
+                    plan.deployer.as_deref().unwrap_or(DEFAULT_TEST_DEPLOYER),
+                ),
+                "Failed to parse deployer in JSON",
+            );
+            let default_sender = match plan.sender.as_ref() {
+                Some(sender) => friendly_expect(
+                    PrincipalData::parse_standard_principal(sender),
+                    "Failed to parse sender in JSON",
+                ),
+                None => deployer.clone(),
+            };
+
+            // the contracts to deploy, and whether each one is a test contract
+            let mut deployments = vec![];
+            for contract in plan.contracts.iter() {
+                let contract_identifier = friendly_expect(
+                    QualifiedContractIdentifier::parse(&contract.contract_identifier),
+                    "Failed to parse contract identifier.",
+                );
+                deployments.push((contract_identifier, plan_dir.join(&contract.path), false));
+            }
+            for test_file in plan.tests.iter() {
+                let test_path = plan_dir.join(test_file);
+                let contract_name = friendly_expect(
+                    ContractName::try_from(
+                        test_path
+                            .file_stem()
+                            .and_then(|stem| stem.to_str())
+                            .unwrap_or("")
+                            .to_string(),
+                    ),
+                    &format!(
+                        "Test contract file name is not a contract name: {}",
+                        test_file
+                    ),
+                );
+                let contract_identifier =
+                    QualifiedContractIdentifier::new(deployer.clone(), contract_name);
+                deployments.push((contract_identifier, test_path, true));
+            }
+
+            let header_db = CLIHeadersDB::new_memory(mainnet);
+            let mut marf = MemoryBackingStore::new();
+            install_boot_code(&header_db, &mut marf);
             {
-                let folder_entry =
-                    folder_entry.expect("Failed to read entry in the coverage folder");
-                let entry_path = folder_entry.path();
-                if entry_path.is_file() {
-                    if entry_path.extension() == Some(OsStr::new("clarcovref")) {
-                        register_files.push(entry_path)
-                    } else if entry_path.extension() == Some(OsStr::new("clarcov")) {
-                        coverage_files.push(entry_path)
+                let mut db = marf.as_clarity_db();
+                db.begin();
+                for allocation in plan.allocations.iter() {
+                    let principal = friendly_expect(
+                        PrincipalData::parse(&allocation.principal),
+                        "Failed to parse principal in JSON",
+                    );
+                    set_stx_balance(&mut db, &principal, allocation.amount as u128);
+                }
+                db.commit().unwrap();
+            }
+
+            let chain = CLIChainView::simulated(&header_db);
+            let clarity_version = ClarityVersion::default_for_epoch(chain.epoch);
+            let mut coverage = if coverage_folder.is_some() {
+                Some(CoverageReporter::new())
+            } else {
+                None
+            };
+            let mut test_contracts = vec![];
+            for (contract_identifier, path, is_test) in deployments.into_iter() {
+                let src_file = path.display().to_string();
+                let content: String = friendly_expect(
+                    fs::read_to_string(&path),
+                    &format!("Error reading file: {}", src_file),
+                );
+                let mut ast = friendly_expect(
+                    parse(&contract_identifier, &content, clarity_version),
+                    "Failed to parse program.",
+                );
+
+                if let Some(ref coverage_folder) = coverage_folder {
+                    let mut coverage_file = PathBuf::from(coverage_folder);
+                    coverage_file.push(&format!(
+                        "test_{}_{}",
+                        contract_identifier.name,
+                        get_epoch_time_ms()
+                    ));
+                    coverage_file.set_extension("clarcovref");
+                    CoverageReporter::register_src_file(
+                        &contract_identifier,
+                        &src_file,
+                        &ast,
+                        &coverage_file,
+                    )
+                    .expect("Coverage reference file generation failure");
+                }
+
+                let deployed =
+                    match run_analysis(&contract_identifier, &mut ast, &chain, &mut marf, true) {
+                        Ok(_) => {
+                            let (result, _) = with_env_costs(
+                                &chain,
+                                &mut marf,
+                                coverage.as_mut(),
+                                None,
+                                None,
+                                |vm_env| {
+                                    vm_env.initialize_versioned_contract(
+                                        contract_identifier.clone(),
+                                        clarity_version,
+                                        &content,
+                                        None,
+                                        ASTRules::PrecheckSize,
+                                    )
+                                },
+                            );
+                            result.map_err(|error| format!("{}", error))
+                        }
+                        Err((error, _)) => Err(format!("{}", error)),
+                    };
+                if let Err(error) = deployed {
+                    return (
+                        1,
+                        Some(json!({
+                            "error": {
+                                "contract": contract_identifier.to_string(),
+                                "initialization": error,
+                            }
+                        })),
+                    );
+                }
+
+                if is_test {
+                    let tests = match discover_tests(&content) {
+                        Ok(tests) => tests,
+                        Err(error) => {
+                            return (
+                                1,
+                                Some(json!({
+                                    "error": {
+                                        "contract": contract_identifier.to_string(),
+                                        "discovery": error.to_string(),
+                                    }
+                                })),
+                            );
+                        }
+                    };
+                    test_contracts.push((contract_identifier, tests));
+                }
+            }
+
+            let mut passed = 0;
+            let mut failed = 0;
+            let mut test_results = vec![];
+            for (contract_identifier, tests) in test_contracts.iter() {
+                for test in tests.iter() {
+                    let sender = test
+                        .annotations
+                        .sender
+                        .clone()
+                        .unwrap_or_else(|| default_sender.clone());
+                    let (result, cost) = run_unit_test(
+                        &chain,
+                        &mut marf,
+                        coverage.as_mut(),
+                        sender,
+                        contract_identifier,
+                        test,
+                    );
+
+                    let mut test_result = json!({
+                        "contract": contract_identifier.to_string(),
+                        "test": test.name.to_string(),
+                    });
+                    let success = match result {
+                        Ok((Value::Response(data), events)) => {
+                            test_result["output"] = serde_json::to_value(&data.data).unwrap();
+                            add_serialized_output(&mut test_result, *data.data);
+                            let events_json: Vec<_> = events
+                                .into_iter()
+                                .map(|event| {
+                                    event.json_serialize(0, &Txid([0u8; 32]), true).unwrap()
+                                })
+                                .collect();
+                            test_result["events"] = serde_json::Value::Array(events_json);
+                            data.committed
+                        }
+                        Ok((x, _)) => {
+                            test_result["error"] = json!({
+                                "runtime": "Expected a ResponseType result from transaction.",
+                                "output": serde_json::to_value(&x).unwrap()
+                            });
+                            false
+                        }
+                        Err(error) => {
+                            test_result["error"] = json!({
+                                "runtime": "Transaction execution error.",
+                                "error": serde_json::to_value(&format!("{}", error)).unwrap()
+                            });
+                            false
+                        }
+                    };
+                    test_result["success"] = success.into();
+                    add_costs(&mut test_result, costs, cost);
+
+                    if success {
+                        passed += 1;
+                    } else {
+                        failed += 1;
                     }
+                    test_results.push(test_result);
                 }
             }
-            CoverageReporter::produce_lcov(lcov_output_file, &register_files, &coverage_files)
-                .expect("Failed to produce an lcov output");
+
+            save_coverage(coverage_folder.clone(), coverage, "test");
+
+            let mut result = json!({
+                "message": format!("{} passed; {} failed.", passed, failed),
+                "passed": passed,
+                "failed": failed,
+                "tests": test_results,
+            });
+            if let (Some(coverage_folder), Some(lcov_output_file)) =
+                (coverage_folder, lcov_output_file)
+            {
+                friendly_expect(
+                    make_lcov(&coverage_folder, &lcov_output_file),
+                    "Failed to produce an lcov output",
+                );
+                result["lcov"] = lcov_output_file.into();
+            }
+
+            (if failed == 0 { 0 } else { 1 }, Some(result))
+        }
+        "make_lcov" => {
+            let coverage_folder = &args[1];
+            let lcov_output_file = &args[2];
+            make_lcov(coverage_folder, lcov_output_file).expect("Failed to produce an lcov output");
             (0, None)
         }
         _ => {
@@ -2116,6 +2952,17 @@ mod test {
         assert!(result["costs"] != json!(null));
         assert!(result["assets"] == json!(null));
 
+        let function_costs = result["function_costs"].as_array().unwrap();
+        assert_eq!(function_costs.len(), 2);
+        assert_eq!(function_costs[0]["name"], json!("preorder"));
+        assert_eq!(function_costs[1]["name"], json!("register"));
+        for function_cost in function_costs.iter() {
+            // the call to the deployed tokens contract is resolved
+            assert_eq!(function_cost["unbounded_calls"], json!([]));
+            assert_eq!(function_cost["exceeds_block_limit"], json!(false));
+            assert!(function_cost["cost"]["runtime"].as_u64().unwrap() > 0);
+        }
+
         eprintln!("launch names with costs and assets");
         let invoked = invoke_command(
             "test",
@@ -2158,6 +3005,41 @@ mod test {
         assert!(result["events"].as_array().unwrap().is_empty());
         assert_eq!(result["output"], json!({"UInt": 1000}));
 
+        eprintln!("execute tokens with profile");
+        let profile_prefix = format!("/tmp/profile_{}", rand::thread_rng().gen::<i32>());
+        let invoked = invoke_command(
+            "test",
+            &[
+                "execute".to_string(),
+                "--profile".to_string(),
+                profile_prefix.clone(),
+                db_name.clone(),
+                "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                "mint!".to_string(),
+                "SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR".to_string(),
+                "(+ u900 u100)".to_string(),
+            ],
+        );
+
+        let exit = invoked.0;
+        let result = invoked.1.unwrap();
+
+        assert_eq!(exit, 0);
+        assert_eq!(result["profile"].as_array().unwrap().len(), 6);
+        let runtime = fs::read_to_string(format!("{}.runtime.folded", profile_prefix)).unwrap();
+        assert!(runtime
+            .lines()
+            .all(|line| line.starts_with("S1G2081040G2081040G2081040G208105NK8PE5.tokens")));
+        let summary: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(format!("{}.json", profile_prefix)).unwrap())
+                .unwrap();
+        assert!(summary["total"]["runtime"].as_u64().unwrap() > 0);
+        assert_eq!(
+            summary["frames"]["S1G2081040G2081040G2081040G208105NK8PE5.tokens::token-credit!"]
+                ["calls"],
+            json!(1)
+        );
+
         eprintln!("eval tokens");
         let invoked = invoke_command(
             "test",
@@ -2271,6 +3153,406 @@ mod test {
         assert!(result["costs"] != json!(null));
     }
 
+    #[test]
+    fn test_debug_script() {
+        let db_name = format!("/tmp/db_{}", rand::thread_rng().gen::<i32>());
+        let script_name = format!("/tmp/debug_script_{}", rand::thread_rng().gen::<i32>());
+
+        eprintln!("initialize");
+        invoke_command("test", &["initialize".to_string(), db_name.clone()]);
+
+        eprintln!("launch tokens");
+        let invoked = invoke_command(
+            "test",
+            &[
+                "launch".to_string(),
+                "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                cargo_workspace_as_string("sample/contracts/tokens.clar"),
+                db_name.clone(),
+            ],
+        );
+        assert_eq!(invoked.0, 0);
+
+        // stop on entry, step over the balance lookup, then run until the map write
+        fs::write(
+            &script_name,
+            "# stopped at entry\n\
+             watch tokens\n\
+             locals\n\
+             next\n\
+             stack\n\
+             continue\n\
+             stack\n\
+             locals\n",
+        )
+        .unwrap();
+
+        eprintln!("debug tokens");
+        let invoked = invoke_command(
+            "test",
+            &[
+                "debug".to_string(),
+                "--script".to_string(),
+                script_name,
+                db_name,
+                "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                "mint!".to_string(),
+                "SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR".to_string(),
+                "u1000".to_string(),
+            ],
+        );
+
+        let exit = invoked.0;
+        let result = invoked.1.unwrap();
+        eprintln!("{}", serde_json::to_string_pretty(&result).unwrap());
+
+        // debugging does not change the outcome
+        assert_eq!(exit, 0);
+        assert_eq!(result["output"], json!({"UInt": 1000}));
+
+        let transcript: Vec<_> = result["debugger"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|line| line.as_str().unwrap().to_string())
+            .collect();
+        let position = |prefix: &str| {
+            transcript
+                .iter()
+                .position(|line| line.starts_with(prefix))
+                .unwrap_or_else(|| panic!("no line starting with {:?}", prefix))
+        };
+
+        let entry = position("stopped at S1G2081040G2081040G2081040G208105NK8PE5.tokens:");
+        assert!(transcript[entry].ends_with("(entry)"));
+        let amount = position("  amount = u1000");
+        let step = position("> next");
+        assert!(entry < amount && amount < step);
+        assert!(transcript[step + 1].ends_with("(step)"));
+        assert!(transcript[step + 2].contains("get-balance"));
+
+        // the watched write is reported with its arguments and result
+        let watch = position(
+            "watch: S1G2081040G2081040G2081040G208105NK8PE5.tokens::tokens (map-set tokens",
+        );
+        assert!(transcript[watch].ends_with("=> true"));
+        assert!(transcript[watch + 1].ends_with("(watch)"));
+        let frame = position("  #0 S1G2081040G2081040G2081040G208105NK8PE5.tokens::token-credit!");
+        assert!(watch < frame);
+        assert!(transcript[frame + 1]
+            .starts_with("  #1 S1G2081040G2081040G2081040G208105NK8PE5.tokens (top level)"));
+        assert!(watch < position("  current-amount = u10000"));
+
+        // the debugger detaches once the script is done
+        assert_eq!(transcript.last().unwrap(), "end of input; detaching");
+    }
+
+    #[test]
+    fn test_debug_breakpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_name = dir.path().join("db").to_str().unwrap().to_string();
+        let script_name = dir
+            .path()
+            .join("debug_script")
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        eprintln!("initialize");
+        invoke_command("test", &["initialize".to_string(), db_name.clone()]);
+
+        eprintln!("launch tokens");
+        let invoked = invoke_command(
+            "test",
+            &[
+                "launch".to_string(),
+                "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                cargo_workspace_as_string("sample/contracts/tokens.clar"),
+                db_name.clone(),
+            ],
+        );
+        assert_eq!(invoked.0, 0);
+
+        // break on the map write in `token-credit!`, and run to it from the entry stop
+        fs::write(&script_name, "break 10\ncontinue\nlocals\ncontinue\n").unwrap();
+
+        eprintln!("debug tokens");
+        let invoked = invoke_command(
+            "test",
+            &[
+                "debug".to_string(),
+                "--script".to_string(),
+                script_name,
+                db_name,
+                "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                "mint!".to_string(),
+                "SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR".to_string(),
+                "u1000".to_string(),
+            ],
+        );
+
+        let exit = invoked.0;
+        let result = invoked.1.unwrap();
+        eprintln!("{}", serde_json::to_string_pretty(&result).unwrap());
+        assert_eq!(exit, 0);
+        assert_eq!(result["output"], json!({"UInt": 1000}));
+
+        let transcript: Vec<_> = result["debugger"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|line| line.as_str().unwrap().to_string())
+            .collect();
+        let position = |line: &str| {
+            transcript
+                .iter()
+                .position(|l| l == line)
+                .unwrap_or_else(|| panic!("no line {:?}", line))
+        };
+
+        let set = position("breakpoint at S1G2081040G2081040G2081040G208105NK8PE5.tokens:10");
+        let hit =
+            position("stopped at S1G2081040G2081040G2081040G208105NK8PE5.tokens:10 (breakpoint 1)");
+        assert!(set < hit);
+        assert!(transcript[hit + 1].starts_with("  ( map-set tokens"));
+        assert!(hit < position("  amount = u1000"));
+
+        // the breakpoint only fires once per call, and the last `continue` runs to completion
+        assert_eq!(
+            transcript
+                .iter()
+                .filter(|line| line.starts_with("stopped at"))
+                .count(),
+            2
+        );
+        assert_eq!(transcript.last().unwrap(), "> continue");
+    }
+
+    #[test]
+    fn test_generate_bindings() {
+        let db_name = format!("/tmp/db_{}", rand::thread_rng().gen::<i32>());
+        let rust_name = format!("/tmp/bindings_{}.rs", rand::thread_rng().gen::<i32>());
+
+        eprintln!("initialize");
+        invoke_command("test", &["initialize".to_string(), db_name.clone()]);
+
+        eprintln!("launch tokens");
+        let invoked = invoke_command(
+            "test",
+            &[
+                "launch".to_string(),
+                "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                cargo_workspace_as_string("sample/contracts/tokens.clar"),
+                db_name.clone(),
+            ],
+        );
+        assert_eq!(invoked.0, 0);
+
+        eprintln!("generate_bindings deployed tokens");
+        let invoked = invoke_command(
+            "test",
+            &[
+                "generate_bindings".to_string(),
+                "--deployed".to_string(),
+                "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                db_name.clone(),
+            ],
+        );
+        assert_eq!(invoked.0, 0);
+        let result = invoked.1.unwrap();
+        let rust = result["rust"].as_str().unwrap();
+        assert!(rust.contains(
+            "pub const CONTRACT_ID: &str = \"S1G2081040G2081040G2081040G208105NK8PE5.tokens\";"
+        ));
+        assert!(rust.contains("pub fn token_transfer_payload(to: PrincipalData, amount: u128)"));
+        assert!(rust.contains("pub fn mint_payload(amount: u128)"));
+        // private functions have no bindings
+        assert!(!rust.contains("get_balance"));
+        let typescript = result["typescript"].as_str().unwrap();
+        assert!(
+            typescript.contains("export function tokenTransferPayload(to: string, amount: bigint)")
+        );
+
+        eprintln!("generate_bindings deployed missing contract");
+        let invoked = invoke_command(
+            "test",
+            &[
+                "generate_bindings".to_string(),
+                "--deployed".to_string(),
+                "S1G2081040G2081040G2081040G208105NK8PE5.no-such-contract".to_string(),
+                db_name.clone(),
+            ],
+        );
+        assert_eq!(invoked.0, 1);
+
+        // names calls into tokens, so it is analyzed against the deployed contracts
+        eprintln!("generate_bindings local names");
+        let invoked = invoke_command(
+            "test",
+            &[
+                "generate_bindings".to_string(),
+                "--rust".to_string(),
+                rust_name.clone(),
+                "S1G2081040G2081040G2081040G208105NK8PE5.names".to_string(),
+                cargo_workspace_as_string("sample/contracts/names.clar"),
+                db_name,
+            ],
+        );
+        assert_eq!(invoked.0, 0);
+        let result = invoked.1.unwrap();
+        assert_eq!(result["files"], json!([rust_name.clone()]));
+        assert!(result.get("rust").is_none());
+        let rust = fs::read_to_string(&rust_name).unwrap();
+        assert!(rust.contains("pub fn preorder_payload("));
+    }
+
+    #[test]
+    fn test_fmt() {
+        let clar_name = format!("/tmp/fmt_{}.clar", rand::thread_rng().gen::<i32>());
+        fs::write(
+            &clar_name,
+            ";; counter\n(define-data-var count   uint u0)\n(define-public (incr) (begin (var-set count (+ (var-get count) u1)) ;; bump\n (ok (var-get count))))",
+        )
+        .unwrap();
+
+        eprintln!("fmt --check unformatted");
+        let invoked = invoke_command(
+            "test",
+            &["fmt".to_string(), "--check".to_string(), clar_name.clone()],
+        );
+        assert_eq!(invoked.0, 1);
+        assert_eq!(
+            invoked.1.unwrap()["unformatted"],
+            json!([clar_name.clone()])
+        );
+
+        eprintln!("fmt");
+        let invoked = invoke_command("test", &["fmt".to_string(), clar_name.clone()]);
+        assert_eq!(invoked.0, 0);
+        assert_eq!(invoked.1.unwrap()["changed"], json!([clar_name.clone()]));
+        assert_eq!(
+            fs::read_to_string(&clar_name).unwrap(),
+            ";; counter\n(define-data-var count uint u0)\n(define-public (incr)\n  (begin\n    (var-set count (+ (var-get count) u1)) ;; bump\n    (ok (var-get count))))\n"
+        );
+
+        eprintln!("fmt --check formatted");
+        let invoked = invoke_command(
+            "test",
+            &["fmt".to_string(), "--check".to_string(), clar_name.clone()],
+        );
+        assert_eq!(invoked.0, 0);
+        assert_eq!(invoked.1.unwrap()["unformatted"], json!([]));
+
+        eprintln!("fmt invalid source");
+        fs::write(&clar_name, "(define-public (incr)").unwrap();
+        let invoked = invoke_command("test", &["fmt".to_string(), clar_name]);
+        assert_eq!(invoked.0, 1);
+    }
+
+    #[test]
+    fn test_unit_tests() {
+        let test_dir = format!("/tmp/unit_tests_{}", rand::thread_rng().gen::<i32>());
+        let coverage_dir = format!("{}/coverage", test_dir);
+        fs::create_dir_all(&coverage_dir).unwrap();
+
+        fs::write(
+            format!("{}/counter.clar", test_dir),
+            "(define-data-var count uint u0)
+(define-public (incr)
+  (begin
+    (var-set count (+ (var-get count) u1))
+    (print { event: \"incr\", count: (var-get count) })
+    (ok (var-get count))))
+(define-read-only (get-count) (var-get count))",
+        )
+        .unwrap();
+        fs::write(
+            format!("{}/counter_test.clar", test_dir),
+            "(define-public (test-incr)
+  (contract-call? 'S1G2081040G2081040G2081040G208105NK8PE5.counter incr))
+
+;; writes of earlier tests are rolled back
+(define-public (test-isolated)
+  (ok (asserts! (is-eq (contract-call? 'S1G2081040G2081040G2081040G208105NK8PE5.counter get-count) u0) (err u1))))
+
+;; @sender SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR
+;; @advance-blocks 5
+;; @balance SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR 500
+(define-public (test-annotations)
+  (begin
+    (asserts! (is-eq tx-sender 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR) (err u1))
+    (asserts! (is-eq block-height u5) (err u2))
+    (asserts! (is-eq (stx-get-balance tx-sender) u500) (err u3))
+    (ok (stx-get-balance 'S1G2081040G2081040G2081040G208105NK8PE5))))
+
+(define-public (test-fails) (if (is-eq u1 u1) (err u7) (ok true)))",
+        )
+        .unwrap();
+        let plan_name = format!("{}/plan.json", test_dir);
+        fs::write(
+            &plan_name,
+            r#"{
+  "contracts": [{ "contract_identifier": "S1G2081040G2081040G2081040G208105NK8PE5.counter", "path": "counter.clar" }],
+  "tests": ["counter_test.clar"],
+  "allocations": [{ "principal": "S1G2081040G2081040G2081040G208105NK8PE5", "amount": 1000 }]
+}"#,
+        )
+        .unwrap();
+        let lcov_name = format!("{}/lcov.info", test_dir);
+
+        let invoked = invoke_command(
+            "test",
+            &[
+                "test".to_string(),
+                "--c".to_string(),
+                coverage_dir,
+                "--lcov".to_string(),
+                lcov_name.clone(),
+                plan_name,
+            ],
+        );
+        let exit = invoked.0;
+        let result = invoked.1.unwrap();
+        eprintln!("{}", serde_json::to_string_pretty(&result).unwrap());
+
+        assert_eq!(exit, 1);
+        assert_eq!(result["passed"], 3);
+        assert_eq!(result["failed"], 1);
+
+        let tests = result["tests"].as_array().unwrap();
+        let names: Vec<_> = tests
+            .iter()
+            .map(|test| test["test"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "test-incr",
+                "test-isolated",
+                "test-annotations",
+                "test-fails"
+            ]
+        );
+        for test in tests.iter() {
+            assert_eq!(
+                test["contract"],
+                "S1G2081040G2081040G2081040G208105NK8PE5.counter_test"
+            );
+        }
+
+        assert_eq!(tests[0]["success"], true);
+        assert_eq!(tests[0]["output"], json!({"UInt": 1}));
+        assert_eq!(tests[0]["events"].as_array().unwrap().len(), 1);
+        assert_eq!(tests[1]["success"], true);
+        assert_eq!(tests[2]["success"], true);
+        assert_eq!(tests[2]["output"], json!({"UInt": 1000}));
+        assert_eq!(tests[3]["success"], false);
+        assert_eq!(tests[3]["output"], json!({"UInt": 7}));
+
+        assert_eq!(result["lcov"], lcov_name.as_str());
+        assert!(fs::metadata(&lcov_name).is_ok());
+    }
+
     #[test]
     fn test_assets() {
         let db_name = format!("/tmp/db_{}", rand::thread_rng().gen::<i32>());
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::sync::{Arc, Mutex};
 use std::{error, fmt, thread};
 
 use clarity::vm::analysis::errors::{CheckError, CheckErrors};
@@ -24,6 +25,7 @@ use clarity::vm::clarity::TransactionConnection;
 pub use clarity::vm::clarity::{ClarityConnection, Error};
 use clarity::vm::contexts::{AssetMap, Environment, OwnedEnvironment};
 use clarity::vm::costs::{CostTracker, ExecutionCost, LimitedCostTracker};
+use clarity::vm::database::contract_cache::ContractCache;
 use clarity::vm::database::{
     BurnStateDB, ClarityDatabase, HeadersDB, RollbackWrapper, RollbackWrapperPersistedLog,
     STXBalance, SqliteConnection, NULL_BURN_STATE_DB, NULL_HEADER_DB,
@@ -34,7 +36,7 @@ use clarity::vm::types::{
     AssetIdentifier, BuffData, OptionalData, PrincipalData, QualifiedContractIdentifier, TupleData,
     TypeSignature, Value,
 };
-use clarity::vm::{analysis, ast, ClarityVersion, ContractName};
+use clarity::vm::{analysis, ast, ClarityVersion, ContractName, EvalHook};
 use stacks_common::consts::{CHAIN_ID_TESTNET, SIGNER_SLOTS_PER_USER};
 use stacks_common::types::chainstate::{
     BlockHeaderHash, BurnchainHeaderHash, SortitionId, StacksAddress, StacksBlockId, TrieHash,
@@ -68,6 +70,9 @@ use crate::util_lib::boot::{boot_code_acc, boot_code_addr, boot_code_id, boot_co
 use crate::util_lib::db::Error as DatabaseError;
 use crate::util_lib::strings::StacksString;
 
+/// An `EvalHook` that is shared by the transactions of a `ClarityInstance`'s blocks
+pub type SharedEvalHook = Arc<Mutex<dyn EvalHook + Send>>;
+
 ///
 /// A high-level interface for interacting with the Clarity VM.
 ///
@@ -94,6 +99,7 @@ pub struct ClarityInstance {
     datastore: MarfedKV,
     mainnet: bool,
     chain_id: u32,
+    eval_hook: Option<SharedEvalHook>,
 }
 
 ///
@@ -123,6 +129,7 @@ pub struct ClarityBlockConnection<'a, 'b> {
     mainnet: bool,
     chain_id: u32,
     epoch: StacksEpochId,
+    eval_hook: Option<SharedEvalHook>,
 }
 
 ///
@@ -139,6 +146,7 @@ pub struct ClarityTransactionConnection<'a, 'b> {
     mainnet: bool,
     chain_id: u32,
     epoch: StacksEpochId,
+    eval_hook: Option<SharedEvalHook>,
 }
 
 pub struct ClarityReadOnlyConnection<'a> {
@@ -191,6 +199,7 @@ impl ClarityBlockConnection<'_, '_> {
             mainnet: false,
             chain_id: CHAIN_ID_TESTNET,
             epoch,
+            eval_hook: None,
         }
     }
 
@@ -249,9 +258,16 @@ impl ClarityInstance {
             datastore,
             mainnet,
             chain_id,
+            eval_hook: None,
         }
     }
 
+    /// Add `eval_hook` to the evaluation of every transaction in this instance's blocks, such
+    /// as to profile the blocks that are replayed by `stacks-inspect`.
+    pub fn set_eval_hook(&mut self, eval_hook: Option<SharedEvalHook>) {
+        self.eval_hook = eval_hook;
+    }
+
     pub fn with_marf<F, R>(&mut self, f: F) -> R
     where
         F: FnOnce(&mut MARF<StacksBlockId>) -> R,
@@ -259,6 +275,16 @@ impl ClarityInstance {
         f(self.datastore.get_marf())
     }
 
+    /// Share a cache of deserialized contracts between all of this instance's block and
+    /// read-only connections
+    pub fn set_contract_cache(&mut self, contract_cache: Option<Arc<ContractCache>>) {
+        self.datastore.set_contract_cache(contract_cache);
+    }
+
+    pub fn get_contract_cache(&self) -> Option<Arc<ContractCache>> {
+        self.datastore.get_contract_cache().cloned()
+    }
+
     pub fn is_mainnet(&self) -> bool {
         self.mainnet
     }
@@ -317,6 +343,7 @@ impl ClarityInstance {
             mainnet: self.mainnet,
             chain_id: self.chain_id,
             epoch: epoch.epoch_id,
+            eval_hook: self.eval_hook.clone(),
         }
     }
 
@@ -341,6 +368,7 @@ impl ClarityInstance {
             mainnet: self.mainnet,
             chain_id: self.chain_id,
             epoch,
+            eval_hook: self.eval_hook.clone(),
         }
     }
 
@@ -367,6 +395,7 @@ impl ClarityInstance {
             mainnet: self.mainnet,
             chain_id: self.chain_id,
             epoch,
+            eval_hook: self.eval_hook.clone(),
         };
 
         let use_mainnet = self.mainnet;
@@ -463,6 +492,7 @@ impl ClarityInstance {
             mainnet: self.mainnet,
             chain_id: self.chain_id,
             epoch,
+            eval_hook: self.eval_hook.clone(),
         };
 
         let use_mainnet = self.mainnet;
@@ -571,6 +601,7 @@ impl ClarityInstance {
             mainnet: self.mainnet,
             chain_id: self.chain_id,
             epoch: epoch.epoch_id,
+            eval_hook: self.eval_hook.clone(),
         }
     }
 
@@ -718,6 +749,12 @@ impl PreCommitClarityBlock<'_> {
             .commit_to(&self.commit_to)
             .expect("FATAL: failed to commit block");
     }
+
+    /// Drops all of the block's writes instead of committing them
+    pub fn rollback(self) {
+        debug!("Rolling back Clarity block connection"; "index_block" => %self.commit_to);
+        self.datastore.rollback_block();
+    }
 }
 
 impl<'a> ClarityBlockConnection<'a, '_> {
@@ -1583,6 +1620,7 @@ impl<'a> ClarityBlockConnection<'a, '_> {
             mainnet,
             chain_id,
             epoch: self.epoch,
+            eval_hook: self.eval_hook.clone(),
         }
     }
 
@@ -1717,6 +1755,12 @@ impl TransactionConnection for ClarityTransactionConnection<'_, '_> {
                     self.burn_state_db,
                 );
 
+                // hold the eval hook for as long as the environment that uses it
+                let mut eval_hook = self
+                    .eval_hook
+                    .as_ref()
+                    .map(|hook| hook.lock().expect("FATAL: eval hook lock is poisoned"));
+
                 // wrap the whole contract-call in a claritydb transaction,
                 //   so we can abort on call_back's boolean retun
                 db.begin();
@@ -1727,6 +1771,9 @@ impl TransactionConnection for ClarityTransactionConnection<'_, '_> {
                     cost_track,
                     self.epoch,
                 );
+                if let Some(eval_hook) = eval_hook.as_mut() {
+                    vm_env.add_eval_hook(&mut **eval_hook);
+                }
                 let result = to_do(&mut vm_env);
                 let (mut db, cost_track) = vm_env
                     .destruct()
@@ -1925,6 +1972,7 @@ mod tests {
     use std::fs;
 
     use clarity::vm::analysis::errors::CheckErrors;
+    use clarity::vm::database::contract_cache::DEFAULT_CONTRACT_CACHE_SIZE;
     use clarity::vm::database::{ClarityBackingStore, STXBalance};
     use clarity::vm::test_util::{TEST_BURN_STATE_DB, TEST_HEADER_DB};
     use clarity::vm::types::{StandardPrincipalData, Value};
@@ -2233,6 +2281,127 @@ mod tests {
         assert!(conn.get_contract_hash(&contract_identifier).is_ok());
     }
 
+    #[test]
+    pub fn test_contract_cache_fork_safety() {
+        let marf = MarfedKV::temporary();
+        let mut clarity_instance = ClarityInstance::new(false, CHAIN_ID_TESTNET, marf);
+        let cache = Arc::new(ContractCache::new(DEFAULT_CONTRACT_CACHE_SIZE));
+        clarity_instance.set_contract_cache(Some(cache.clone()));
+
+        let contract_identifier = QualifiedContractIdentifier::local("foo").unwrap();
+
+        clarity_instance
+            .begin_test_genesis_block(
+                &StacksBlockId::sentinel(),
+                &StacksBlockId([0; 32]),
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+            )
+            .commit_block();
+
+        let deploy = |conn: &mut ClarityBlockConnection, contract: &str| {
+            conn.as_transaction(|conn| {
+                let (ct_ast, ct_analysis) = conn
+                    .analyze_smart_contract(
+                        &contract_identifier,
+                        ClarityVersion::Clarity1,
+                        contract,
+                        ASTRules::PrecheckSize,
+                    )
+                    .unwrap();
+                conn.initialize_smart_contract(
+                    &contract_identifier,
+                    ClarityVersion::Clarity1,
+                    &ct_ast,
+                    contract,
+                    None,
+                    |_, _| false,
+                )
+                .unwrap();
+                conn.save_analysis(&contract_identifier, &ct_analysis)
+                    .unwrap();
+            });
+        };
+        let call = |conn: &mut ClarityBlockConnection| {
+            conn.as_transaction(|tx| {
+                tx.run_contract_call(
+                    &StandardPrincipalData::transient().into(),
+                    None,
+                    &contract_identifier,
+                    "foo",
+                    &[Value::Int(3)],
+                    |_, _| false,
+                )
+            })
+            .unwrap()
+            .0
+        };
+
+        // contracts deployed in the open block are never cached
+        {
+            let mut conn = clarity_instance.begin_block(
+                &StacksBlockId([0; 32]),
+                &StacksBlockId([1; 32]),
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+            );
+            deploy(&mut conn, "(define-public (foo (x int)) (ok (+ x x)))");
+            assert_eq!(call(&mut conn), Value::okay(Value::Int(6)).unwrap());
+            conn.commit_block();
+        }
+        assert_eq!(cache.entries_for(&contract_identifier), 0);
+
+        // once committed, the contract is cached and served from the cache
+        {
+            let mut conn = clarity_instance.begin_block(
+                &StacksBlockId([1; 32]),
+                &StacksBlockId([2; 32]),
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+            );
+            assert_eq!(call(&mut conn), Value::okay(Value::Int(6)).unwrap());
+            assert_eq!(call(&mut conn), Value::okay(Value::Int(6)).unwrap());
+            conn.commit_block();
+        }
+        assert_eq!(cache.entries_for(&contract_identifier), 1);
+        assert!(cache.stats().hits >= 1);
+
+        // a sibling fork deploys a different contract with the same name
+        {
+            let mut conn = clarity_instance.begin_block(
+                &StacksBlockId([0; 32]),
+                &StacksBlockId([3; 32]),
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+            );
+            deploy(&mut conn, "(define-public (foo (x int)) (ok (* x x x)))");
+            conn.commit_block();
+        }
+
+        // neither fork is served the other fork's contract
+        {
+            let mut conn = clarity_instance.begin_block(
+                &StacksBlockId([3; 32]),
+                &StacksBlockId([4; 32]),
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+            );
+            assert_eq!(call(&mut conn), Value::okay(Value::Int(27)).unwrap());
+            conn.commit_block();
+        }
+        {
+            let mut conn = clarity_instance.begin_block(
+                &StacksBlockId([2; 32]),
+                &StacksBlockId([5; 32]),
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+            );
+            assert_eq!(call(&mut conn), Value::okay(Value::Int(6)).unwrap());
+            conn.commit_block();
+        }
+        assert_eq!(cache.entries_for(&contract_identifier), 2);
+    }
+
     #[test]
     pub fn test_block_roll_back() {
         let marf = MarfedKV::temporary();
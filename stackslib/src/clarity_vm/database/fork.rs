@@ -0,0 +1,600 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A copy-on-write fork of a node's chainstate, for running Clarity code against real chain
+//! state (e.g. from `clarity_cli`) without mutating the node's databases.
+//!
+//! The node's Clarity MARF and headers index are opened read-only at a _fork block_.  Everything
+//! the VM writes goes to a separate _overlay_ database, as if it were written in a single block
+//! (the _overlay block_) that builds on the fork block.  Reads at the overlay block check the
+//! overlay first, and then fall back to the fork block.
+
+use std::fs;
+
+use clarity::vm::analysis::AnalysisDatabase;
+use clarity::vm::database::sqlite::{sqlite_get_contract_hash, sqlite_insert_metadata};
+use clarity::vm::database::{
+    BurnStateDB, ClarityBackingStore, ClarityDatabase, HeadersDB, SpecialCaseHandler,
+    SqliteConnection,
+};
+use clarity::vm::errors::{InterpreterError, InterpreterResult, RuntimeErrorType};
+use clarity::vm::types::QualifiedContractIdentifier;
+use rusqlite::{params, Connection, OpenFlags};
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::types::chainstate::{
+    BlockHeaderHash, BurnchainHeaderHash, ConsensusHash, SortitionId, StacksAddress, StacksBlockId,
+    TrieHash, VRFSeed,
+};
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+use crate::burnchains::PoxConstants;
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::nakamoto::NakamotoChainState;
+use crate::chainstate::stacks::index::marf::{MARFOpenOpts, MarfConnection, MARF};
+use crate::chainstate::stacks::index::storage::TrieFileStorage;
+use crate::chainstate::stacks::index::Error as MARFError;
+use crate::chainstate::stacks::Error as ChainstateError;
+use crate::clarity_vm::special::handle_contract_call_special_cases;
+use crate::core::StacksEpochId;
+use crate::util_lib::db::{sqlite_open, Error as DBError};
+
+/// The id of the block that holds a fork's writes.  It is derived from the fork block, so that
+/// it can never collide with a block in the node's chainstate.
+pub fn overlay_block_id(fork_block: &StacksBlockId) -> StacksBlockId {
+    let mut preimage = b"clarity-fork-overlay".to_vec();
+    preimage.extend_from_slice(fork_block.as_bytes());
+    StacksBlockId(Sha512Trunc256Sum::from_data(&preimage).0)
+}
+
+/// A fork of a node's chainstate at a given block, with its writes stored in an overlay
+/// database.  The overlay database also records where the node's chainstate is, so that the fork
+/// can be reopened with `ChainstateFork::open()`.
+pub struct ChainstateFork {
+    mainnet: bool,
+    epoch: StacksEpochId,
+    fork_block: StacksBlockId,
+    stacks_block_height: u64,
+    clarity_marf: MARF<StacksBlockId>,
+    headers_db: ForkHeadersDB,
+    sortdb: SortitionDB,
+    sortition_id: SortitionId,
+    overlay: Connection,
+}
+
+impl ChainstateFork {
+    /// Fork the chainstate of the node whose working directory is `node_path` at `fork_block`,
+    /// creating a new overlay database at `overlay_path`.
+    pub fn create(
+        node_path: &str,
+        fork_block: &StacksBlockId,
+        mainnet: bool,
+        overlay_path: &str,
+    ) -> Result<ChainstateFork, ChainstateError> {
+        if fs::metadata(overlay_path).is_ok() {
+            return Err(DBError::ExistsError.into());
+        }
+        let overlay = sqlite_open(
+            overlay_path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
+            false,
+        )?;
+        SqliteConnection::initialize_conn(&overlay)?;
+        overlay.execute(
+            "CREATE TABLE fork_config (node_path TEXT NOT NULL, fork_block TEXT NOT NULL, mainnet INTEGER NOT NULL)",
+            [],
+        )?;
+        overlay.execute(
+            "INSERT INTO fork_config (node_path, fork_block, mainnet) VALUES (?1, ?2, ?3)",
+            params![node_path, fork_block, mainnet],
+        )?;
+
+        ChainstateFork::open_node(node_path, fork_block, mainnet, overlay).inspect_err(|_| {
+            // don't leave a fork behind that can't be opened
+            let _ = fs::remove_file(overlay_path);
+        })
+    }
+
+    /// Reopen a fork created with `ChainstateFork::create()`.
+    pub fn open(overlay_path: &str) -> Result<ChainstateFork, ChainstateError> {
+        let overlay = sqlite_open(overlay_path, OpenFlags::SQLITE_OPEN_READ_WRITE, false)?;
+        SqliteConnection::check_schema(&overlay)?;
+        let (node_path, fork_block, mainnet) = overlay.query_row(
+            "SELECT node_path, fork_block, mainnet FROM fork_config",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, StacksBlockId>(1)?,
+                    row.get::<_, bool>(2)?,
+                ))
+            },
+        )?;
+        ChainstateFork::open_node(&node_path, &fork_block, mainnet, overlay)
+    }
+
+    fn open_node(
+        node_path: &str,
+        fork_block: &StacksBlockId,
+        mainnet: bool,
+        overlay: Connection,
+    ) -> Result<ChainstateFork, ChainstateError> {
+        let mut marf_opts = MARFOpenOpts::default();
+        marf_opts.external_blobs = true;
+
+        let index_path = format!("{node_path}/chainstate/vm/index.sqlite");
+        let index = MARF::from_storage(TrieFileStorage::open_readonly(
+            &index_path,
+            marf_opts.clone(),
+        )?);
+        let clarity_path = format!("{node_path}/chainstate/vm/clarity/marf.sqlite");
+        let mut clarity_marf =
+            MARF::from_storage(TrieFileStorage::open_readonly(&clarity_path, marf_opts)?);
+        clarity_marf.open_block(fork_block)?;
+
+        let header = NakamotoChainState::get_block_header(index.sqlite_conn(), fork_block)?
+            .ok_or(ChainstateError::NoSuchBlockError)?;
+
+        // report a missing sortition DB as such, and not as a corrupt one
+        let sortdb_path = format!("{node_path}/burnchain/sortition");
+        if fs::metadata(format!("{sortdb_path}/marf.sqlite")).is_err() {
+            return Err(DBError::NoDBError.into());
+        }
+        let pox_constants = if mainnet {
+            PoxConstants::mainnet_default()
+        } else {
+            PoxConstants::testnet_default()
+        };
+        let sortdb = SortitionDB::open_readonly(&sortdb_path, pox_constants)?;
+        // Nakamoto blocks are evaluated against their burn view, not their tenure's sortition
+        let burn_view = header.burn_view.as_ref().unwrap_or(&header.consensus_hash);
+        let sortition_id = SortitionDB::get_block_snapshot_consensus(sortdb.conn(), burn_view)?
+            .ok_or(DBError::NotFoundError)?
+            .sortition_id;
+        let epoch = SortitionDB::get_stacks_epoch(sortdb.conn(), header.burn_header_height.into())?
+            .ok_or(DBError::NotFoundError)?
+            .epoch_id;
+
+        Ok(ChainstateFork {
+            mainnet,
+            epoch,
+            fork_block: *fork_block,
+            stacks_block_height: header.stacks_block_height,
+            clarity_marf,
+            headers_db: ForkHeadersDB {
+                index,
+                fork_block: *fork_block,
+                overlay_block: overlay_block_id(fork_block),
+            },
+            sortdb,
+            sortition_id,
+            overlay,
+        })
+    }
+
+    pub fn is_mainnet(&self) -> bool {
+        self.mainnet
+    }
+
+    /// The epoch of the fork block
+    pub fn epoch(&self) -> StacksEpochId {
+        self.epoch
+    }
+
+    pub fn fork_block(&self) -> &StacksBlockId {
+        &self.fork_block
+    }
+
+    /// The Stacks block height of the fork block
+    pub fn stacks_block_height(&self) -> u64 {
+        self.stacks_block_height
+    }
+
+    /// Run `f` with a store that reads and writes the overlay block, along with the headers and
+    /// burnchain state of the fork block.
+    pub fn with_store<F, R>(&mut self, f: F) -> Result<R, ChainstateError>
+    where
+        F: FnOnce(&mut ForkedMarfStore, &dyn HeadersDB, &dyn BurnStateDB) -> R,
+    {
+        let burn_db = self.sortdb.index_handle(&self.sortition_id);
+        let mut store =
+            ForkedMarfStore::new(&mut self.clarity_marf, &self.overlay, &self.fork_block)?;
+        Ok(f(&mut store, &self.headers_db, &burn_db))
+    }
+}
+
+/// A `ClarityBackingStore` that layers an overlay database over a read-only Clarity MARF.
+/// Data and contract metadata written through it go to the overlay, at the overlay block.  Values
+/// from the overlay have no MARF proofs.
+pub struct ForkedMarfStore<'a> {
+    chain_tip: StacksBlockId,
+    fork_block: StacksBlockId,
+    fork_height: u32,
+    overlay_block: StacksBlockId,
+    marf: &'a mut MARF<StacksBlockId>,
+    overlay: &'a Connection,
+}
+
+impl<'a> ForkedMarfStore<'a> {
+    /// Open a store at the overlay block of `fork_block`.  `overlay` must have the schema of
+    /// `SqliteConnection::initialize_conn()`.
+    pub fn new(
+        marf: &'a mut MARF<StacksBlockId>,
+        overlay: &'a Connection,
+        fork_block: &StacksBlockId,
+    ) -> Result<ForkedMarfStore<'a>, ChainstateError> {
+        marf.open_block(fork_block)?;
+        let fork_height = marf
+            .get_block_height_of(fork_block, fork_block)?
+            .ok_or(ChainstateError::NoSuchBlockError)?;
+        let overlay_block = overlay_block_id(fork_block);
+        Ok(ForkedMarfStore {
+            chain_tip: overlay_block,
+            fork_block: *fork_block,
+            fork_height,
+            overlay_block,
+            marf,
+            overlay,
+        })
+    }
+
+    pub fn as_clarity_db<'b>(
+        &'b mut self,
+        headers_db: &'b dyn HeadersDB,
+        burn_state_db: &'b dyn BurnStateDB,
+    ) -> ClarityDatabase<'b> {
+        ClarityDatabase::new(self, headers_db, burn_state_db)
+    }
+
+    pub fn as_analysis_db(&mut self) -> AnalysisDatabase<'_> {
+        AnalysisDatabase::new(self)
+    }
+
+    fn at_overlay(&self) -> bool {
+        self.chain_tip == self.overlay_block
+    }
+
+    /// The block of the node's chainstate that reads fall back to
+    fn base_tip(&self) -> StacksBlockId {
+        if self.at_overlay() {
+            self.fork_block
+        } else {
+            self.chain_tip
+        }
+    }
+
+    /// The side store that holds the metadata of `bhh`
+    fn side_store_for(&self, bhh: &StacksBlockId) -> &Connection {
+        if bhh == &self.overlay_block {
+            self.overlay
+        } else {
+            self.marf.sqlite_conn()
+        }
+    }
+
+    fn get_with_proof_from_path(
+        &mut self,
+        hash: &TrieHash,
+    ) -> InterpreterResult<Option<(String, Vec<u8>)>> {
+        if self.at_overlay() {
+            if let Some(data) = SqliteConnection::get(self.overlay, &hash.to_string())? {
+                return Ok(Some((data, vec![])));
+            }
+        }
+        let base_tip = self.base_tip();
+        self.marf
+            .get_with_proof_from_hash(&base_tip, hash)
+            .or_else(|e| match e {
+                MARFError::NotFoundError => Ok(None),
+                _ => Err(e),
+            })
+            .map_err(|_| InterpreterError::Expect("ERROR: Unexpected MARF Failure on GET".into()))?
+            .map(|(marf_value, proof)| {
+                let side_key = marf_value.to_hex();
+                let data = SqliteConnection::get(self.marf.sqlite_conn(), &side_key)?.ok_or_else(
+                    || {
+                        InterpreterError::Expect(format!(
+                            "ERROR: MARF contained value_hash not found in side storage: {}",
+                            side_key
+                        ))
+                    },
+                )?;
+                Ok((data, proof.serialize_to_vec()))
+            })
+            .transpose()
+    }
+
+    fn get_from_path(&mut self, hash: &TrieHash) -> InterpreterResult<Option<String>> {
+        if self.at_overlay() {
+            if let Some(data) = SqliteConnection::get(self.overlay, &hash.to_string())? {
+                return Ok(Some(data));
+            }
+        }
+        let base_tip = self.base_tip();
+        self.marf
+            .get_from_hash(&base_tip, hash)
+            .or_else(|e| match e {
+                MARFError::NotFoundError => Ok(None),
+                _ => Err(e),
+            })
+            .map_err(|_| InterpreterError::Expect("ERROR: Unexpected MARF Failure on GET".into()))?
+            .map(|marf_value| {
+                let side_key = marf_value.to_hex();
+                SqliteConnection::get(self.marf.sqlite_conn(), &side_key)?.ok_or_else(|| {
+                    InterpreterError::Expect(format!(
+                        "ERROR: MARF contained value_hash not found in side storage: {}",
+                        side_key
+                    ))
+                    .into()
+                })
+            })
+            .transpose()
+    }
+}
+
+impl ClarityBackingStore for ForkedMarfStore<'_> {
+    fn get_side_store(&mut self) -> &Connection {
+        self.overlay
+    }
+
+    fn get_cc_special_cases_handler(&self) -> Option<SpecialCaseHandler> {
+        Some(&handle_contract_call_special_cases)
+    }
+
+    /// Sets the chain tip at which queries will happen.  Used for `(at-block ..)`
+    fn set_block_hash(&mut self, bhh: StacksBlockId) -> InterpreterResult<StacksBlockId> {
+        if bhh != self.overlay_block {
+            self.marf
+                .check_ancestor_block_hash(&bhh)
+                .map_err(|e| match e {
+                    MARFError::NotFoundError | MARFError::NonMatchingForks(..) => {
+                        test_debug!("No such block {:?} ({:?})", &bhh, &e);
+                        RuntimeErrorType::UnknownBlockHeaderHash(BlockHeaderHash(bhh.0))
+                    }
+                    _ => panic!("ERROR: Unexpected MARF failure: {}", e),
+                })?;
+        }
+        let result = Ok(self.chain_tip);
+        self.chain_tip = bhh;
+        result
+    }
+
+    fn get_current_block_height(&mut self) -> u32 {
+        if self.at_overlay() {
+            return self.fork_height + 1;
+        }
+        self.marf
+            .get_block_height_of(&self.chain_tip, &self.chain_tip)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Unexpected MARF failure: Failed to get current block height of {}: {:?}",
+                    &self.chain_tip, &e
+                )
+            })
+            .unwrap_or_else(|| {
+                panic!(
+                    "Failed to obtain current block height of {} (got None)",
+                    &self.chain_tip
+                )
+            })
+    }
+
+    fn get_block_at_height(&mut self, block_height: u32) -> Option<StacksBlockId> {
+        if self.at_overlay() && block_height == self.fork_height + 1 {
+            return Some(self.overlay_block);
+        }
+        let base_tip = self.base_tip();
+        self.marf
+            .get_bhh_at_height(&base_tip, block_height)
+            .unwrap_or_else(|_| {
+                panic!(
+                    "Unexpected MARF failure: failed to get block at height {} off of {}.",
+                    block_height, &base_tip
+                )
+            })
+    }
+
+    fn get_open_chain_tip(&mut self) -> StacksBlockId {
+        self.overlay_block
+    }
+
+    fn get_open_chain_tip_height(&mut self) -> u32 {
+        self.fork_height + 1
+    }
+
+    fn get_data_with_proof(&mut self, key: &str) -> InterpreterResult<Option<(String, Vec<u8>)>> {
+        self.get_with_proof_from_path(&TrieHash::from_key(key))
+    }
+
+    fn get_data_with_proof_from_path(
+        &mut self,
+        hash: &TrieHash,
+    ) -> InterpreterResult<Option<(String, Vec<u8>)>> {
+        self.get_with_proof_from_path(hash)
+    }
+
+    fn get_data(&mut self, key: &str) -> InterpreterResult<Option<String>> {
+        trace!("ForkedMarfStore get: {:?} tip={}", key, &self.chain_tip);
+        self.get_from_path(&TrieHash::from_key(key))
+    }
+
+    fn get_data_from_path(&mut self, hash: &TrieHash) -> InterpreterResult<Option<String>> {
+        self.get_from_path(hash)
+    }
+
+    /// Writes go to the overlay, keyed by their MARF path
+    fn put_all_data(&mut self, items: Vec<(String, String)>) -> InterpreterResult<()> {
+        for (key, value) in items.into_iter() {
+            trace!("ForkedMarfStore put '{}' = '{}'", &key, &value);
+            SqliteConnection::put(self.overlay, &TrieHash::from_key(&key).to_string(), &value)?;
+        }
+        Ok(())
+    }
+
+    fn get_contract_hash(
+        &mut self,
+        contract: &QualifiedContractIdentifier,
+    ) -> InterpreterResult<(StacksBlockId, Sha512Trunc256Sum)> {
+        sqlite_get_contract_hash(self, contract)
+    }
+
+    fn insert_metadata(
+        &mut self,
+        contract: &QualifiedContractIdentifier,
+        key: &str,
+        value: &str,
+    ) -> InterpreterResult<()> {
+        sqlite_insert_metadata(self, contract, key, value)
+    }
+
+    fn get_metadata(
+        &mut self,
+        contract: &QualifiedContractIdentifier,
+        key: &str,
+    ) -> InterpreterResult<Option<String>> {
+        let (bhh, _) = self.get_contract_hash(contract)?;
+        SqliteConnection::get_metadata(self.side_store_for(&bhh), &bhh, &contract.to_string(), key)
+    }
+
+    fn get_metadata_manual(
+        &mut self,
+        at_height: u32,
+        contract: &QualifiedContractIdentifier,
+        key: &str,
+    ) -> InterpreterResult<Option<String>> {
+        let bhh = self.get_block_at_height(at_height).ok_or_else(|| {
+            warn!("Unknown block height when manually querying metadata"; "block_height" => at_height);
+            RuntimeErrorType::BadBlockHeight(at_height.to_string())
+        })?;
+        SqliteConnection::get_metadata(self.side_store_for(&bhh), &bhh, &contract.to_string(), key)
+    }
+}
+
+/// The headers of a node's chainstate, in which the overlay block of a fork has the headers of
+/// its fork block.
+pub struct ForkHeadersDB {
+    index: MARF<StacksBlockId>,
+    fork_block: StacksBlockId,
+    overlay_block: StacksBlockId,
+}
+
+impl ForkHeadersDB {
+    fn resolve<'a>(&'a self, id_bhh: &'a StacksBlockId) -> &'a StacksBlockId {
+        if id_bhh == &self.overlay_block {
+            &self.fork_block
+        } else {
+            id_bhh
+        }
+    }
+}
+
+impl HeadersDB for ForkHeadersDB {
+    fn get_stacks_block_header_hash_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+        epoch: &StacksEpochId,
+    ) -> Option<BlockHeaderHash> {
+        self.index
+            .get_stacks_block_header_hash_for_block(self.resolve(id_bhh), epoch)
+    }
+
+    fn get_burn_header_hash_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+    ) -> Option<BurnchainHeaderHash> {
+        self.index
+            .get_burn_header_hash_for_block(self.resolve(id_bhh))
+    }
+
+    fn get_consensus_hash_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+        epoch: &StacksEpochId,
+    ) -> Option<ConsensusHash> {
+        self.index
+            .get_consensus_hash_for_block(self.resolve(id_bhh), epoch)
+    }
+
+    fn get_vrf_seed_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+        epoch: &StacksEpochId,
+    ) -> Option<VRFSeed> {
+        self.index
+            .get_vrf_seed_for_block(self.resolve(id_bhh), epoch)
+    }
+
+    fn get_stacks_block_time_for_block(&self, id_bhh: &StacksBlockId) -> Option<u64> {
+        self.index
+            .get_stacks_block_time_for_block(self.resolve(id_bhh))
+    }
+
+    fn get_burn_block_time_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+        epoch: Option<&StacksEpochId>,
+    ) -> Option<u64> {
+        self.index
+            .get_burn_block_time_for_block(self.resolve(id_bhh), epoch)
+    }
+
+    fn get_burn_block_height_for_block(&self, id_bhh: &StacksBlockId) -> Option<u32> {
+        self.index
+            .get_burn_block_height_for_block(self.resolve(id_bhh))
+    }
+
+    fn get_miner_address(
+        &self,
+        id_bhh: &StacksBlockId,
+        epoch: &StacksEpochId,
+    ) -> Option<StacksAddress> {
+        self.index.get_miner_address(self.resolve(id_bhh), epoch)
+    }
+
+    fn get_burnchain_tokens_spent_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+        epoch: &StacksEpochId,
+    ) -> Option<u128> {
+        self.index
+            .get_burnchain_tokens_spent_for_block(self.resolve(id_bhh), epoch)
+    }
+
+    fn get_burnchain_tokens_spent_for_winning_block(
+        &self,
+        id_bhh: &StacksBlockId,
+        epoch: &StacksEpochId,
+    ) -> Option<u128> {
+        self.index
+            .get_burnchain_tokens_spent_for_winning_block(self.resolve(id_bhh), epoch)
+    }
+
+    fn get_tokens_earned_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+        epoch: &StacksEpochId,
+    ) -> Option<u128> {
+        self.index
+            .get_tokens_earned_for_block(self.resolve(id_bhh), epoch)
+    }
+
+    fn get_stacks_height_for_tenure_height(
+        &self,
+        tip: &StacksBlockId,
+        tenure_height: u32,
+    ) -> Option<u32> {
+        self.index
+            .get_stacks_height_for_tenure_height(self.resolve(tip), tenure_height)
+    }
+}
@@ -1,8 +1,10 @@
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use clarity::util::hash::Sha512Trunc256Sum;
 use clarity::vm::analysis::AnalysisDatabase;
+use clarity::vm::database::contract_cache::ContractCache;
 use clarity::vm::database::sqlite::{
     sqlite_get_contract_hash, sqlite_get_metadata, sqlite_get_metadata_manual,
     sqlite_insert_metadata,
@@ -37,6 +39,7 @@ use crate::util_lib::db::{Error as DatabaseError, IndexDBConn};
 pub struct MarfedKV {
     chain_tip: StacksBlockId,
     marf: MARF<StacksBlockId>,
+    contract_cache: Option<Arc<ContractCache>>,
 }
 
 impl MarfedKV {
@@ -94,7 +97,11 @@ impl MarfedKV {
             None => StacksBlockId::sentinel(),
         };
 
-        Ok(MarfedKV { marf, chain_tip })
+        Ok(MarfedKV {
+            marf,
+            chain_tip,
+            contract_cache: None,
+        })
     }
 
     pub fn open_unconfirmed(
@@ -108,7 +115,11 @@ impl MarfedKV {
             None => StacksBlockId::sentinel(),
         };
 
-        Ok(MarfedKV { marf, chain_tip })
+        Ok(MarfedKV {
+            marf,
+            chain_tip,
+            contract_cache: None,
+        })
     }
 
     // used by benchmarks
@@ -139,7 +150,11 @@ impl MarfedKV {
 
         let chain_tip = StacksBlockId::sentinel();
 
-        MarfedKV { marf, chain_tip }
+        MarfedKV {
+            marf,
+            chain_tip,
+            contract_cache: None,
+        }
     }
 
     pub fn begin_read_only<'a>(
@@ -161,6 +176,7 @@ impl MarfedKV {
         ReadOnlyMarfStore {
             chain_tip,
             marf: &mut self.marf,
+            contract_cache: self.contract_cache.clone(),
         }
     }
 
@@ -183,6 +199,7 @@ impl MarfedKV {
         Ok(ReadOnlyMarfStore {
             chain_tip,
             marf: &mut self.marf,
+            contract_cache: self.contract_cache.clone(),
         })
     }
 
@@ -221,6 +238,7 @@ impl MarfedKV {
         WritableMarfStore {
             chain_tip,
             marf: tx,
+            contract_cache: self.contract_cache.clone(),
         }
     }
 
@@ -246,6 +264,7 @@ impl MarfedKV {
         WritableMarfStore {
             chain_tip,
             marf: tx,
+            contract_cache: self.contract_cache.clone(),
         }
     }
 
@@ -253,6 +272,15 @@ impl MarfedKV {
         &self.chain_tip
     }
 
+    /// Share a cache of deserialized contracts between all connections to this store
+    pub fn set_contract_cache(&mut self, contract_cache: Option<Arc<ContractCache>>) {
+        self.contract_cache = contract_cache;
+    }
+
+    pub fn get_contract_cache(&self) -> Option<&Arc<ContractCache>> {
+        self.contract_cache.as_ref()
+    }
+
     pub fn get_marf(&mut self) -> &mut MARF<StacksBlockId> {
         &mut self.marf
     }
@@ -273,11 +301,13 @@ impl MarfedKV {
 pub struct WritableMarfStore<'a> {
     chain_tip: StacksBlockId,
     marf: MarfTransaction<'a, StacksBlockId>,
+    contract_cache: Option<Arc<ContractCache>>,
 }
 
 pub struct ReadOnlyMarfStore<'a> {
     chain_tip: StacksBlockId,
     marf: &'a mut MARF<StacksBlockId>,
+    contract_cache: Option<Arc<ContractCache>>,
 }
 
 impl ReadOnlyMarfStore<'_> {
@@ -308,6 +338,14 @@ impl ClarityBackingStore for ReadOnlyMarfStore<'_> {
         Some(&handle_contract_call_special_cases)
     }
 
+    fn get_contract_cache(&self) -> Option<Arc<ContractCache>> {
+        self.contract_cache.clone()
+    }
+
+    fn is_at_or_above_open_chain_tip(&mut self, _block_height: u32) -> bool {
+        false
+    }
+
     /// Sets the chain tip at which queries will happen.  Used for `(at-block ..)`
     fn set_block_hash(&mut self, bhh: StacksBlockId) -> InterpreterResult<StacksBlockId> {
         self.marf
@@ -654,6 +692,10 @@ impl ClarityBackingStore for WritableMarfStore<'_> {
         Some(&handle_contract_call_special_cases)
     }
 
+    fn get_contract_cache(&self) -> Option<Arc<ContractCache>> {
+        self.contract_cache.clone()
+    }
+
     fn get_data(&mut self, key: &str) -> InterpreterResult<Option<String>> {
         trace!("MarfedKV get: {:?} tip={}", key, &self.chain_tip);
         self.marf
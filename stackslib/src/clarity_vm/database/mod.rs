@@ -40,6 +40,7 @@ use crate::clarity_vm::special::handle_contract_call_special_cases;
 use crate::core::{StacksEpoch, StacksEpochId};
 use crate::util_lib::db::{DBConn, Error as DBError, FromColumn, FromRow};
 
+pub mod fork;
 pub mod marf;
 
 pub trait GetTenureStartId {
@@ -1190,13 +1191,17 @@ impl BurnStateDB for SortitionHandleConn<'_> {
 
 pub struct MemoryBackingStore {
     side_store: Connection,
+    block_height: u32,
 }
 
 impl MemoryBackingStore {
     pub fn new() -> MemoryBackingStore {
         let side_store = SqliteConnection::memory().unwrap();
 
-        let mut memory_marf = MemoryBackingStore { side_store };
+        let mut memory_marf = MemoryBackingStore {
+            side_store,
+            block_height: 0,
+        };
 
         memory_marf.as_clarity_db().initialize();
 
@@ -1220,6 +1225,25 @@ impl MemoryBackingStore {
     pub fn as_analysis_db(&mut self) -> AnalysisDatabase<'_> {
         AnalysisDatabase::new(self)
     }
+
+    pub fn get_block_height(&self) -> u32 {
+        self.block_height
+    }
+
+    /// Move the chain tip to `height`.  The blocks at heights `1..=height` get made-up index
+    /// block hashes, so that block info can be looked up for them.  Nothing is committed per
+    /// block: all data stays visible at every height.
+    pub fn set_block_height(&mut self, height: u32) {
+        self.block_height = height;
+    }
+
+    fn block_id_at_height(height: u32) -> StacksBlockId {
+        if height == 0 {
+            StacksBlockId::sentinel()
+        } else {
+            StacksBlockId(Sha512Trunc256Sum::from_data(&height.to_be_bytes()).0)
+        }
+    }
 }
 
 impl ClarityBackingStore for MemoryBackingStore {
@@ -1254,23 +1278,23 @@ impl ClarityBackingStore for MemoryBackingStore {
     }
 
     fn get_block_at_height(&mut self, height: u32) -> Option<StacksBlockId> {
-        if height == 0 {
-            Some(StacksBlockId::sentinel())
+        if height <= self.block_height {
+            Some(Self::block_id_at_height(height))
         } else {
             None
         }
     }
 
     fn get_open_chain_tip(&mut self) -> StacksBlockId {
-        StacksBlockId::sentinel()
+        Self::block_id_at_height(self.block_height)
     }
 
     fn get_open_chain_tip_height(&mut self) -> u32 {
-        0
+        self.block_height
     }
 
     fn get_current_block_height(&mut self) -> u32 {
-        0
+        self.block_height
     }
 
     fn get_cc_special_cases_handler(&self) -> Option<SpecialCaseHandler> {
@@ -0,0 +1,312 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+
+use clarity::vm::ast::ASTRules;
+use clarity::vm::contexts::OwnedEnvironment;
+use clarity::vm::database::SqliteConnection;
+use clarity::vm::test_util::{TEST_BURN_STATE_DB, TEST_HEADER_DB};
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, Value};
+use clarity::vm::SymbolicExpression;
+use rand::Rng;
+use rusqlite::Connection;
+use stacks_common::consts::{
+    CHAIN_ID_TESTNET, FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH,
+};
+use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksBlockId};
+use stacks_common::types::StacksEpochId;
+use stacks_common::util::hash::to_hex;
+
+use crate::burnchains::PoxConstants;
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::stacks::db::{ChainStateBootData, StacksChainState};
+use crate::chainstate::stacks::index::marf::{MARFOpenOpts, MARF};
+use crate::chainstate::stacks::index::storage::TrieFileStorage;
+use crate::chainstate::stacks::index::ClarityMarfTrieId;
+use crate::clarity_vm::database::fork::{ChainstateFork, ForkedMarfStore};
+use crate::clarity_vm::database::marf::MarfedKV;
+use crate::core::{StacksEpoch, StacksEpochExtension};
+
+const EPOCH: StacksEpochId = StacksEpochId::Epoch25;
+
+const COUNTER: &str = "(define-data-var count uint u1)
+    (define-public (incr) (begin (var-set count (+ u1 (var-get count))) (ok (var-get count))))
+    (define-read-only (get-count) (var-get count))";
+
+fn with_fork<F, R>(
+    marf: &mut MARF<StacksBlockId>,
+    overlay: &Connection,
+    fork_block: &StacksBlockId,
+    f: F,
+) -> R
+where
+    F: FnOnce(&mut OwnedEnvironment) -> R,
+{
+    let mut store = ForkedMarfStore::new(marf, overlay, fork_block).unwrap();
+    let mut owned_env = OwnedEnvironment::new(
+        store.as_clarity_db(&TEST_HEADER_DB, &TEST_BURN_STATE_DB),
+        EPOCH,
+    );
+    f(&mut owned_env)
+}
+
+fn eval(
+    owned_env: &mut OwnedEnvironment,
+    contract: &QualifiedContractIdentifier,
+    program: &str,
+) -> Value {
+    owned_env.eval_read_only(contract, program).unwrap().0
+}
+
+#[test]
+fn test_fork_is_copy_on_write() {
+    let path = format!(
+        "/tmp/stacks-node-tests/unit-tests-fork/{}",
+        to_hex(&rand::thread_rng().gen::<[u8; 32]>())
+    );
+    let first_block = StacksBlockId::new(&FIRST_BURNCHAIN_CONSENSUS_HASH, &FIRST_STACKS_BLOCK_HASH);
+    let fork_block = StacksBlockId([1; 32]);
+    let counter = QualifiedContractIdentifier::local("counter").unwrap();
+    let reader = QualifiedContractIdentifier::local("reader").unwrap();
+    let sender = PrincipalData::parse("S1G2081040G2081040G2081040G208105NK8PE5").unwrap();
+
+    // the node's chainstate
+    let mut marf_kv = MarfedKV::open(&path, None, None).unwrap();
+    {
+        let mut store = marf_kv.begin(&StacksBlockId::sentinel(), &first_block);
+        store
+            .as_clarity_db(&TEST_HEADER_DB, &TEST_BURN_STATE_DB)
+            .initialize();
+        store.test_commit();
+    }
+    {
+        let mut store = marf_kv.begin(&first_block, &fork_block);
+        {
+            let mut owned_env = OwnedEnvironment::new(
+                store.as_clarity_db(&TEST_HEADER_DB, &TEST_BURN_STATE_DB),
+                EPOCH,
+            );
+            owned_env
+                .initialize_contract(counter.clone(), COUNTER, None, ASTRules::PrecheckSize)
+                .unwrap();
+        }
+        store.test_commit();
+    }
+
+    let mut marf = MARF::from_storage(
+        TrieFileStorage::open_readonly(&format!("{path}/marf.sqlite"), {
+            let mut marf_opts = MARFOpenOpts::default();
+            marf_opts.external_blobs = true;
+            marf_opts
+        })
+        .unwrap(),
+    );
+    let overlay = SqliteConnection::memory().unwrap();
+
+    // reads fall back to the fork block, and writes persist in the overlay
+    with_fork(&mut marf, &overlay, &fork_block, |owned_env| {
+        assert_eq!(eval(owned_env, &counter, "(get-count)"), Value::UInt(1));
+        let (result, ..) = owned_env
+            .execute_transaction(sender.clone(), None, counter.clone(), "incr", &[])
+            .unwrap();
+        assert_eq!(result, Value::okay(Value::UInt(2)).unwrap());
+    });
+    with_fork(&mut marf, &overlay, &fork_block, |owned_env| {
+        assert_eq!(eval(owned_env, &counter, "(get-count)"), Value::UInt(2));
+
+        // contracts deployed in the fork can call the node's contracts
+        owned_env
+            .initialize_contract(
+                reader.clone(),
+                "(define-read-only (read) (contract-call? .counter get-count))",
+                None,
+                ASTRules::PrecheckSize,
+            )
+            .unwrap();
+    });
+    with_fork(&mut marf, &overlay, &fork_block, |owned_env| {
+        assert_eq!(eval(owned_env, &reader, "(read)"), Value::UInt(2));
+    });
+
+    // the node's chainstate is unchanged
+    let mut store = marf_kv.begin_read_only(Some(&fork_block));
+    let mut owned_env = OwnedEnvironment::new(
+        store.as_clarity_db(&TEST_HEADER_DB, &TEST_BURN_STATE_DB),
+        EPOCH,
+    );
+    assert_eq!(
+        eval(&mut owned_env, &counter, "(get-count)"),
+        Value::UInt(1)
+    );
+    assert!(owned_env.eval_read_only(&reader, "(read)").is_err());
+}
+
+const TRANSFER: &str = "(define-public (send (amount uint))
+    (stx-transfer? amount tx-sender (as-contract tx-sender)))";
+
+/// Boot a node's chainstate and sortition DB in `node_path`, with `balance` uSTX for `owner`.
+fn make_node(node_path: &str, owner: &PrincipalData, balance: u64) {
+    let mut boot_data = ChainStateBootData {
+        initial_balances: vec![(owner.clone(), balance)],
+        post_flight_callback: None,
+        first_burnchain_block_hash: BurnchainHeaderHash::zero(),
+        first_burnchain_block_height: 0,
+        first_burnchain_block_timestamp: 0,
+        pox_constants: PoxConstants::testnet_default(),
+        get_bulk_initial_lockups: None,
+        get_bulk_initial_balances: None,
+        get_bulk_initial_names: None,
+        get_bulk_initial_namespaces: None,
+    };
+    StacksChainState::open_and_exec(
+        false,
+        CHAIN_ID_TESTNET,
+        &format!("{node_path}/chainstate"),
+        Some(&mut boot_data),
+        None,
+    )
+    .unwrap();
+    SortitionDB::connect(
+        &format!("{node_path}/burnchain/sortition"),
+        0,
+        &BurnchainHeaderHash::zero(),
+        0,
+        &StacksEpoch::unit_test_2_5(0),
+        PoxConstants::testnet_default(),
+        None,
+        true,
+    )
+    .unwrap();
+}
+
+/// Fork the genesis block of a new node, and have `owner` send `amount` uSTX in the fork.
+fn fork_and_send(
+    node_path: &str,
+    overlay_path: &str,
+    owner: &PrincipalData,
+    amount: u128,
+) -> ChainstateFork {
+    let genesis = StacksBlockId::new(&FIRST_BURNCHAIN_CONSENSUS_HASH, &FIRST_STACKS_BLOCK_HASH);
+    let transfer = QualifiedContractIdentifier::local("transfer").unwrap();
+    let mut fork = ChainstateFork::create(node_path, &genesis, false, overlay_path).unwrap();
+    let epoch = fork.epoch();
+    fork.with_store(|store, headers_db, burn_db| {
+        let mut owned_env = OwnedEnvironment::new(store.as_clarity_db(headers_db, burn_db), epoch);
+        owned_env
+            .initialize_contract(transfer.clone(), TRANSFER, None, ASTRules::PrecheckSize)
+            .unwrap();
+        let (result, ..) = owned_env
+            .execute_transaction(
+                owner.clone(),
+                None,
+                transfer,
+                "send",
+                &[SymbolicExpression::atom_value(Value::UInt(amount))],
+            )
+            .unwrap();
+        assert_eq!(result, Value::okay_true());
+    })
+    .unwrap();
+    fork
+}
+
+fn fork_balance(fork: &mut ChainstateFork, owner: &PrincipalData, at_fork_block: bool) -> Value {
+    let epoch = fork.epoch();
+    let program = if at_fork_block {
+        format!(
+            "(at-block 0x{} (stx-get-balance '{owner}))",
+            fork.fork_block()
+        )
+    } else {
+        format!("(stx-get-balance '{owner})")
+    };
+    fork.with_store(|store, headers_db, burn_db| {
+        let mut owned_env = OwnedEnvironment::new(store.as_clarity_db(headers_db, burn_db), epoch);
+        eval(
+            &mut owned_env,
+            &QualifiedContractIdentifier::local("transfer").unwrap(),
+            &program,
+        )
+    })
+    .unwrap()
+}
+
+fn random_node_path() -> String {
+    format!(
+        "/tmp/stacks-node-tests/unit-tests-fork/node-{}",
+        to_hex(&rand::thread_rng().gen::<[u8; 32]>())
+    )
+}
+
+#[test]
+fn test_chainstate_fork_leaves_node_unchanged() {
+    let node_path = random_node_path();
+    let owner = PrincipalData::parse("S1G2081040G2081040G2081040G208105NK8PE5").unwrap();
+    make_node(&node_path, &owner, 1000);
+
+    let node_files = [
+        "chainstate/vm/index.sqlite",
+        "chainstate/vm/index.sqlite.blobs",
+        "chainstate/vm/clarity/marf.sqlite",
+        "chainstate/vm/clarity/marf.sqlite.blobs",
+        "burnchain/sortition/marf.sqlite",
+    ];
+    let read_node_files = || {
+        node_files
+            .iter()
+            .map(|file| fs::read(format!("{node_path}/{file}")).unwrap())
+            .collect::<Vec<_>>()
+    };
+    let before = read_node_files();
+
+    let mut fork = fork_and_send(&node_path, &format!("{node_path}/fork.sqlite"), &owner, 100);
+    assert_eq!(fork_balance(&mut fork, &owner, false), Value::UInt(900));
+    drop(fork);
+
+    for ((file, before), after) in node_files.iter().zip(before).zip(read_node_files()) {
+        assert!(before == after, "{file} was changed by the fork");
+    }
+}
+
+#[test]
+fn test_chainstate_fork_reopen() {
+    let node_path = random_node_path();
+    let overlay_path = format!("{node_path}/fork.sqlite");
+    let owner = PrincipalData::parse("S1G2081040G2081040G2081040G208105NK8PE5").unwrap();
+    make_node(&node_path, &owner, 1000);
+    drop(fork_and_send(&node_path, &overlay_path, &owner, 100));
+
+    // a fork can't be created over an existing one
+    let genesis = StacksBlockId::new(&FIRST_BURNCHAIN_CONSENSUS_HASH, &FIRST_STACKS_BLOCK_HASH);
+    assert!(ChainstateFork::create(&node_path, &genesis, false, &overlay_path).is_err());
+
+    let mut fork = ChainstateFork::open(&overlay_path).unwrap();
+    assert_eq!(fork.fork_block(), &genesis);
+    assert!(!fork.is_mainnet());
+    assert_eq!(fork_balance(&mut fork, &owner, false), Value::UInt(900));
+}
+
+#[test]
+fn test_chainstate_fork_reads_at_fork_block() {
+    let node_path = random_node_path();
+    let owner = PrincipalData::parse("S1G2081040G2081040G2081040G208105NK8PE5").unwrap();
+    make_node(&node_path, &owner, 1000);
+
+    let mut fork = fork_and_send(&node_path, &format!("{node_path}/fork.sqlite"), &owner, 100);
+    assert_eq!(fork_balance(&mut fork, &owner, false), Value::UInt(900));
+    // the fork block itself doesn't see the overlay's writes
+    assert_eq!(fork_balance(&mut fork, &owner, true), Value::UInt(1000));
+}
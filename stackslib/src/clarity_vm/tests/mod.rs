@@ -20,6 +20,7 @@ pub mod contracts;
 pub mod costs;
 pub mod epoch_switch;
 pub mod events;
+pub mod fork_store;
 pub mod forking;
 pub mod large_contract;
 pub mod simple_tests;
@@ -18,16 +18,21 @@
 
 use std::any::type_name;
 use std::cell::LazyCell;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use std::{env, fs, io, process, thread};
 
 use clarity::types::chainstate::SortitionId;
+use clarity::vm::profiler::Profiler;
 use db::blocks::DummyEventDispatcher;
 use db::ChainstateTx;
 use regex::Regex;
 use rusqlite::{Connection, OpenFlags};
-use stacks_common::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash, StacksBlockId};
+use stacks_common::types::chainstate::{
+    BlockHeaderHash, BurnchainHeaderHash, StacksBlockId, TrieHash,
+};
 use stacks_common::types::sqlite::NO_PARAMS;
 use stacks_common::util::get_epoch_time_ms;
 use stacks_common::util::hash::Hash160;
@@ -39,14 +44,20 @@ use crate::chainstate::burn::db::sortdb::{
     get_ancestor_sort_id, SortitionDB, SortitionHandle, SortitionHandleContext,
 };
 use crate::chainstate::burn::{BlockSnapshot, ConsensusHash};
-use crate::chainstate::coordinator::OnChainRewardSetProvider;
+use crate::chainstate::coordinator::{
+    calculate_paid_rewards, dispatcher_announce_burn_ops, BlockEventDispatcher,
+    Error as CoordinatorError, OnChainRewardSetProvider,
+};
 use crate::chainstate::nakamoto::miner::{BlockMetadata, NakamotoBlockBuilder, NakamotoTenureInfo};
 use crate::chainstate::nakamoto::{NakamotoBlock, NakamotoChainState};
+use crate::chainstate::stacks::boot::RewardSetData;
 use crate::chainstate::stacks::db::blocks::StagingBlock;
+use crate::chainstate::stacks::db::snapshot::{export_marf_snapshot, import_marf_snapshot};
 use crate::chainstate::stacks::db::{StacksBlockHeaderTypes, StacksChainState, StacksHeaderInfo};
+use crate::chainstate::stacks::index::marf::{MARFOpenOpts, MARF};
 use crate::chainstate::stacks::miner::*;
 use crate::chainstate::stacks::{Error as ChainstateError, *};
-use crate::clarity_vm::clarity::ClarityInstance;
+use crate::clarity_vm::clarity::{ClarityInstance, SharedEvalHook};
 use crate::clarity_vm::database::GetTenureStartId;
 use crate::config::{Config, ConfigFile, DEFAULT_MAINNET_CONFIG};
 use crate::core::*;
@@ -119,12 +130,38 @@ pub fn drain_common_opts(argv: &mut Vec<String>, start_at: usize) -> CommonOpts
     opts
 }
 
+/// Remove `--profile <prefix>` from `argv`, and make a profiler for the Clarity code that a
+/// command evaluates
+fn drain_profile_opt(argv: &mut Vec<String>) -> Option<(String, Arc<Mutex<Profiler>>)> {
+    let i = argv.iter().position(|arg| arg == "--profile")?;
+    if i + 1 >= argv.len() {
+        eprintln!("Expected a file prefix after --profile");
+        process::exit(1);
+    }
+    let prefix = argv.remove(i + 1);
+    argv.remove(i);
+    Some((prefix, Arc::new(Mutex::new(Profiler::new()))))
+}
+
+/// Write a profile's flamegraphs and summary to files starting with `prefix`
+fn save_profile(prefix: &str, profiler: &Mutex<Profiler>) {
+    let profiler = profiler.lock().expect("FATAL: profiler lock is poisoned");
+    let files = profiler
+        .to_files(Path::new(prefix))
+        .unwrap_or_else(|e| panic!("Failed to write profile to {prefix}: {e}"));
+    for file in files {
+        println!("Wrote profile {}", file.display());
+    }
+}
+
 /// Replay blocks from chainstate database
 /// Terminates on error using `process::exit()`
 ///
 /// Arguments:
 ///  - `argv`: Args in CLI format: `<command-name> [args...]`
 pub fn command_replay_block(argv: &[String], conf: Option<&Config>) {
+    let mut argv = argv.to_vec();
+    let profile = drain_profile_opt(&mut argv);
     let print_help_and_exit = || -> ! {
         let n = &argv[0];
         eprintln!("Usage:");
@@ -133,6 +170,10 @@ pub fn command_replay_block(argv: &[String], conf: Option<&Config>) {
         eprintln!("  {n} <database-path> index-range <start-block> <end-block>");
         eprintln!("  {n} <database-path> range <start-block> <end-block>");
         eprintln!("  {n} <database-path> <first|last> <block-count>");
+        eprintln!("Options:");
+        eprintln!(
+            "  --profile <prefix>  write flamegraphs and a summary of Clarity costs to <prefix>.*"
+        );
         process::exit(1);
     };
     let start = Instant::now();
@@ -192,9 +233,15 @@ pub fn command_replay_block(argv: &[String], conf: Option<&Config>) {
         if i % 100 == 0 {
             println!("Checked {i}...");
         }
-        replay_staging_block(db_path, index_block_hash, conf);
+        let eval_hook = profile
+            .as_ref()
+            .map(|(_, profiler)| profiler.clone() as SharedEvalHook);
+        replay_staging_block(db_path, index_block_hash, conf, eval_hook);
     }
     println!("Finished. run_time_seconds = {}", start.elapsed().as_secs());
+    if let Some((prefix, profiler)) = profile {
+        save_profile(&prefix, &profiler);
+    }
 }
 
 /// Replay blocks from chainstate database
@@ -203,6 +250,8 @@ pub fn command_replay_block(argv: &[String], conf: Option<&Config>) {
 /// Arguments:
 ///  - `argv`: Args in CLI format: `<command-name> [args...]`
 pub fn command_replay_block_nakamoto(argv: &[String], conf: Option<&Config>) {
+    let mut argv = argv.to_vec();
+    let profile = drain_profile_opt(&mut argv);
     let print_help_and_exit = || -> ! {
         let n = &argv[0];
         eprintln!("Usage:");
@@ -211,6 +260,10 @@ pub fn command_replay_block_nakamoto(argv: &[String], conf: Option<&Config>) {
         eprintln!("  {n} <database-path> index-range <start-block> <end-block>");
         eprintln!("  {n} <database-path> range <start-block> <end-block>");
         eprintln!("  {n} <database-path> <first|last> <block-count>");
+        eprintln!("Options:");
+        eprintln!(
+            "  --profile <prefix>  write flamegraphs and a summary of Clarity costs to <prefix>.*"
+        );
         process::exit(1);
     };
     let start = Instant::now();
@@ -280,9 +333,15 @@ pub fn command_replay_block_nakamoto(argv: &[String], conf: Option<&Config>) {
         if i % 100 == 0 {
             println!("Checked {i}...");
         }
-        replay_naka_staging_block(db_path, index_block_hash, conf);
+        let eval_hook = profile
+            .as_ref()
+            .map(|(_, profiler)| profiler.clone() as SharedEvalHook);
+        replay_naka_staging_block(db_path, index_block_hash, conf, eval_hook);
     }
     println!("Finished. run_time_seconds = {}", start.elapsed().as_secs());
+    if let Some((prefix, profiler)) = profile {
+        save_profile(&prefix, &profiler);
+    }
 }
 
 /// Replay mock mined blocks from JSON files
@@ -551,8 +610,14 @@ pub fn command_try_mine(argv: &[String], conf: Option<&Config>) {
     process::exit(code);
 }
 
-/// Fetch and process a `StagingBlock` from database and call `replay_block()` to validate
-fn replay_staging_block(db_path: &str, index_block_hash_hex: &str, conf: Option<&Config>) {
+/// Fetch and process a `StagingBlock` from database and call `replay_block()` to validate.
+/// If `eval_hook` is given, it is added to the evaluation of the block's transactions.
+fn replay_staging_block(
+    db_path: &str,
+    index_block_hash_hex: &str,
+    conf: Option<&Config>,
+    eval_hook: Option<SharedEvalHook>,
+) {
     let block_id = StacksBlockId::from_hex(index_block_hash_hex).unwrap();
     let chain_state_path = format!("{db_path}/chainstate/");
     let sort_db_path = format!("{db_path}/burnchain/sortition");
@@ -582,52 +647,72 @@ fn replay_staging_block(db_path: &str, index_block_hash_hex: &str, conf: Option<
         true,
     )
     .unwrap();
+
+    chainstate.clarity_state.set_eval_hook(eval_hook);
+    if let Err(e) = replay_stored_block::<DummyEventDispatcher>(
+        &mut sortdb,
+        &mut chainstate,
+        &burnchain_blocks_db,
+        &block_id,
+        None,
+    ) {
+        println!("Failed processing block! block = {block_id}, error = {e:?}");
+        process::exit(1);
+    }
+}
+
+/// Load an epoch 2.x block that has been processed, along with its staging DB entry, and call
+/// `replay_block()` to re-evaluate it.  Nothing is committed.  If `dispatcher_opt` is given, the
+/// block is announced to it exactly as it would have been when it was first processed.
+fn replay_stored_block<T: BlockEventDispatcher>(
+    sortdb: &mut SortitionDB,
+    chainstate: &mut StacksChainState,
+    burnchain_blocks_db: &BurnchainDB,
+    block_id: &StacksBlockId,
+    dispatcher_opt: Option<&T>,
+) -> Result<(), ChainstateError> {
     let sort_tx = sortdb.tx_begin_at_tip();
 
     let blocks_path = chainstate.blocks_path.clone();
-    let (mut chainstate_tx, clarity_instance) = chainstate
-        .chainstate_tx_begin()
-        .expect("Failed to start chainstate tx");
+    let (mut chainstate_tx, clarity_instance) = chainstate.chainstate_tx_begin()?;
     let mut next_staging_block =
-        StacksChainState::load_staging_block_info(&chainstate_tx.tx, &block_id)
-            .expect("Failed to load staging block data")
-            .expect("No such index block hash in block database");
+        StacksChainState::load_staging_block_info(&chainstate_tx.tx, block_id)?
+            .ok_or(ChainstateError::NoSuchBlockError)?;
 
     next_staging_block.block_data = StacksChainState::load_block_bytes(
         &blocks_path,
         &next_staging_block.consensus_hash,
         &next_staging_block.anchored_block_hash,
-    )
-    .unwrap()
+    )?
     .unwrap_or_default();
 
     let Some(parent_header_info) =
-        StacksChainState::get_parent_header_info(&mut chainstate_tx, &next_staging_block).unwrap()
+        StacksChainState::get_parent_header_info(&mut chainstate_tx, &next_staging_block)?
     else {
-        println!("Failed to load parent head info for block: {index_block_hash_hex}");
-        return;
+        println!("Failed to load parent head info for block: {block_id}");
+        return Ok(());
     };
 
-    let block =
-        StacksChainState::extract_stacks_block(&next_staging_block).expect("Failed to get block");
+    let block = StacksChainState::extract_stacks_block(&next_staging_block)?;
     let block_size = next_staging_block.block_data.len() as u64;
 
     replay_block(
         sort_tx,
         chainstate_tx,
         clarity_instance,
-        &burnchain_blocks_db,
+        burnchain_blocks_db,
         &parent_header_info,
         &next_staging_block.parent_microblock_hash,
         next_staging_block.parent_microblock_seq,
-        &block_id,
+        block_id,
         &block,
         block_size,
         &next_staging_block.consensus_hash,
         &next_staging_block.anchored_block_hash,
         next_staging_block.commit_burn,
         next_staging_block.sortition_burn,
-    );
+        dispatcher_opt,
+    )
 }
 
 /// Process a mock mined block and call `replay_block()` to validate
@@ -686,7 +771,7 @@ fn replay_mock_mined_block(db_path: &str, block: AssembledAnchorBlock, conf: Opt
         return;
     };
 
-    replay_block(
+    if let Err(e) = replay_block::<DummyEventDispatcher>(
         sort_tx,
         chainstate_tx,
         clarity_instance,
@@ -702,11 +787,17 @@ fn replay_mock_mined_block(db_path: &str, block: AssembledAnchorBlock, conf: Opt
         // I think the burn is used for miner rewards but not necessary for validation
         0,
         0,
-    );
+        None,
+    ) {
+        println!("Failed processing block! block = {block_id}, error = {e:?}");
+        process::exit(1);
+    }
 }
 
-/// Validate a block against chainstate
-fn replay_block(
+/// Validate a block against chainstate, and check that it has the expected cost.
+/// Returns `InvalidStacksBlock` if it does not.  Nothing is committed.  If `dispatcher_opt` is
+/// given, the block is announced to it exactly as it would have been when it was first processed.
+fn replay_block<T: BlockEventDispatcher>(
     mut sort_tx: IndexDBTx<SortitionHandleContext, SortitionId>,
     mut chainstate_tx: ChainstateTx,
     clarity_instance: &mut ClarityInstance,
@@ -721,7 +812,8 @@ fn replay_block(
     block_hash: &BlockHeaderHash,
     block_commit_burn: u64,
     block_sortition_burn: u64,
-) {
+    dispatcher_opt: Option<&T>,
+) -> Result<(), ChainstateError> {
     let parent_block_header = match &parent_header_info.anchored_header {
         StacksBlockHeaderTypes::Epoch2(bh) => bh,
         StacksBlockHeaderTypes::Nakamoto(_) => panic!("Nakamoto blocks not supported yet"),
@@ -729,10 +821,10 @@ fn replay_block(
     let parent_block_hash = parent_block_header.block_hash();
 
     let Some(cost) =
-        StacksChainState::get_stacks_block_anchored_cost(chainstate_tx.conn(), block_id).unwrap()
+        StacksChainState::get_stacks_block_anchored_cost(chainstate_tx.conn(), block_id)?
     else {
         println!("No header info found for {block_id}");
-        return;
+        return Ok(());
     };
 
     let Some(next_microblocks) = StacksChainState::inner_find_parent_microblock_stream(
@@ -742,14 +834,14 @@ fn replay_block(
         &parent_header_info.consensus_hash,
         parent_microblock_hash,
         parent_microblock_seq,
-    )
-    .unwrap() else {
+    )?
+    else {
         println!("No microblock stream found for {block_id}");
-        return;
+        return Ok(());
     };
 
-    let (burn_header_hash, burn_header_height, burn_header_timestamp, _winning_block_txid) =
-        match SortitionDB::get_block_snapshot_consensus(&sort_tx, block_consensus_hash).unwrap() {
+    let (burn_header_hash, burn_header_height, burn_header_timestamp, winning_block_txid) =
+        match SortitionDB::get_block_snapshot_consensus(&sort_tx, block_consensus_hash)? {
             Some(sn) => (
                 sn.burn_header_hash,
                 sn.block_height as u32,
@@ -776,7 +868,7 @@ fn replay_block(
             &parent_header_info.consensus_hash
         );
         println!("{msg}");
-        return;
+        return Ok(());
     }
 
     // validation check -- validate parent microblocks and find the ones that connect the
@@ -787,8 +879,7 @@ fn replay_block(
         block_hash,
         block,
         next_microblocks,
-    )
-    .unwrap();
+    )?;
     let (last_microblock_hash, last_microblock_seq) = match next_microblocks.len() {
         0 => (EMPTY_MICROBLOCK_PARENT_HASH.clone(), 0),
         _ => {
@@ -807,12 +898,11 @@ fn replay_block(
         sort_tx.tx(),
         block_consensus_hash,
         block_hash,
-    )
-    .unwrap();
+    )?;
 
     let pox_constants = sort_tx.context.pox_constants.clone();
 
-    match StacksChainState::append_block(
+    let (mut receipt, clarity_commit, _) = StacksChainState::append_block(
         &mut chainstate_tx,
         clarity_instance,
         &mut sort_tx,
@@ -829,25 +919,79 @@ fn replay_block(
         block_sortition_burn,
         block_am.weight(),
         true,
-    ) {
-        Ok((receipt, _, _)) => {
-            if receipt.anchored_block_cost != cost {
-                println!("Failed processing block! block = {block_id}. Unexpected cost. expected = {cost}, evaluated = {}",
-                         receipt.anchored_block_cost);
-                process::exit(1);
-            }
+    )?;
+    // the block was evaluated atop a placeholder MARF tip; drop it, so the next block can be
+    // replayed with this same chainstate
+    clarity_commit.rollback();
+    if receipt.anchored_block_cost != cost {
+        return Err(ChainstateError::InvalidStacksBlock(format!(
+            "Unexpected cost. expected = {cost}, evaluated = {}",
+            receipt.anchored_block_cost
+        )));
+    }
 
-            info!("Block processed successfully! block = {block_id}");
-        }
-        Err(e) => {
-            println!("Failed processing block! block = {block_id}, error = {e:?}");
-            process::exit(1);
-        }
-    };
+    if let Some(dispatcher) = dispatcher_opt {
+        // the block was appended without advancing the chain tip, so `receipt.header` is a
+        // placeholder, and the reward set calculated in it (if any) was not reported; announce
+        // the header and reward set that were stored when the block was processed
+        receipt.header = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            chainstate_tx.conn(),
+            block_id,
+        )?
+        .ok_or(ChainstateError::NoSuchBlockError)?;
+        let reward_set_data = NakamotoChainState::get_reward_set(chainstate_tx.conn(), block_id)?
+            .and_then(|reward_set| {
+                // same as StacksChainState::append_block()
+                let first_block_height = sort_tx.context.first_block_height;
+                let parent_burn_block_height = u64::from(receipt.parent_burn_block_height);
+                pox_constants
+                    .reward_cycle_of_prepare_phase(first_block_height, parent_burn_block_height)
+                    .or_else(|| {
+                        pox_constants
+                            .block_height_to_reward_cycle(
+                                first_block_height,
+                                parent_burn_block_height,
+                            )
+                            .map(|cycle| cycle + 1)
+                    })
+                    .map(|cycle| RewardSetData::new(reward_set, cycle))
+            });
+
+        // same as StacksChainState::process_next_staging_block()
+        let parent_id = StacksBlockId::new(&parent_header_info.consensus_hash, &parent_block_hash);
+        dispatcher.announce_block(
+            &block.clone().into(),
+            &receipt.header,
+            &receipt.tx_receipts,
+            &parent_id,
+            winning_block_txid,
+            &receipt.matured_rewards,
+            receipt.matured_rewards_info.as_ref(),
+            receipt.parent_burn_block_hash,
+            receipt.parent_burn_block_height,
+            receipt.parent_burn_block_timestamp,
+            &receipt.anchored_block_cost,
+            &receipt.parent_microblocks_cost,
+            &pox_constants,
+            &reward_set_data,
+            &None,
+            None,
+            receipt.header.stacks_block_height,
+        );
+    }
+
+    info!("Block processed successfully! block = {block_id}");
+    Ok(())
 }
 
-/// Fetch and process a NakamotoBlock from database and call `replay_block_nakamoto()` to validate
-fn replay_naka_staging_block(db_path: &str, index_block_hash_hex: &str, conf: &Config) {
+/// Fetch and process a NakamotoBlock from database and call `replay_block_nakamoto()` to
+/// validate.  If `eval_hook` is given, it is added to the evaluation of the block's transactions.
+fn replay_naka_staging_block(
+    db_path: &str,
+    index_block_hash_hex: &str,
+    conf: &Config,
+    eval_hook: Option<SharedEvalHook>,
+) {
     let block_id = StacksBlockId::from_hex(index_block_hash_hex).unwrap();
     let chain_state_path = format!("{db_path}/chainstate/");
     let sort_db_path = format!("{db_path}/burnchain/sortition");
@@ -879,14 +1023,28 @@ fn replay_naka_staging_block(db_path: &str, index_block_hash_hex: &str, conf: &C
         .get_nakamoto_block(&block_id)
         .unwrap()
         .unwrap();
-    replay_block_nakamoto(&mut sortdb, &mut chainstate, &block, block_size).unwrap();
+    chainstate.clarity_state.set_eval_hook(eval_hook);
+    if let Err(e) = replay_block_nakamoto::<DummyEventDispatcher>(
+        &mut sortdb,
+        &mut chainstate,
+        &block,
+        block_size,
+        None,
+    ) {
+        println!("Failed processing block! block = {block_id}, error = {e:?}");
+        process::exit(1);
+    }
 }
 
-fn replay_block_nakamoto(
+/// Re-evaluate a Nakamoto block atop its parent, and check that it has the expected cost.
+/// Returns `InvalidStacksBlock` if it does not.  Nothing is committed.  If `dispatcher_opt` is
+/// given, the block is announced to it exactly as it would have been when it was first processed.
+fn replay_block_nakamoto<T: BlockEventDispatcher>(
     sort_db: &mut SortitionDB,
     stacks_chain_state: &mut StacksChainState,
     block: &NakamotoBlock,
     block_size: u64,
+    dispatcher_opt: Option<&T>,
 ) -> Result<(), ChainstateError> {
     // find corresponding snapshot
     let next_ready_block_snapshot =
@@ -1120,7 +1278,6 @@ fn replay_block_nakamoto(
     // though it will always be None), which gets the borrow-checker to believe that it's safe
     // to access `stacks_chain_state` again.  In the `Ok(..)` case, it's instead sufficient so
     // simply commit the block before beginning the second transaction to mark it processed.
-    let block_id = block.block_id();
     let mut burn_view_handle = sort_db.index_handle(&burnchain_view_sn.sortition_id);
     let (ok_opt, err_opt) = match NakamotoChainState::append_block(
         &mut chainstate_tx,
@@ -1142,16 +1299,60 @@ fn replay_block_nakamoto(
         &active_reward_set,
         true,
     ) {
-        Ok((receipt, _, _, _)) => (Some(receipt), None),
+        Ok((receipt, _, reward_set_data, phantom_unlock_events)) => (
+            Some((receipt, reward_set_data, phantom_unlock_events)),
+            None,
+        ),
         Err(e) => (None, Some(e)),
     };
 
-    if let Some(receipt) = ok_opt {
+    if let Some((mut receipt, reward_set_data, phantom_unlock_events)) = ok_opt {
         // check the cost
         let evaluated_cost = receipt.anchored_block_cost.clone();
         if evaluated_cost != expected_cost {
-            println!("Failed processing block! block = {block_id}. Unexpected cost. expected = {expected_cost}, evaluated = {evaluated_cost}");
-            process::exit(1);
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Unexpected cost. expected = {expected_cost}, evaluated = {evaluated_cost}"
+            )));
+        }
+
+        if let Some(dispatcher) = dispatcher_opt {
+            // same as NakamotoChainState::process_next_nakamoto_block()
+            if let Some(unlock_receipt) = NakamotoChainState::generate_phantom_unlock_tx(
+                phantom_unlock_events,
+                &chainstate_tx.config,
+                block.header.chain_length,
+            ) {
+                receipt.tx_receipts.push(unlock_receipt);
+            }
+            // the block was appended without advancing the chain tip, so `receipt.header` is a
+            // placeholder; announce the header that was stored when the block was processed
+            let header =
+                NakamotoChainState::get_block_header(&chainstate_tx.tx, &block.header.block_id())?
+                    .ok_or(ChainstateError::NoSuchBlockError)?;
+            let block_event = (
+                block.clone(),
+                parent_header_info.anchored_header.block_hash(),
+            )
+                .into();
+            dispatcher.announce_block(
+                &block_event,
+                &header,
+                &receipt.tx_receipts,
+                &block.header.parent_block_id,
+                next_ready_block_snapshot.winning_block_txid.clone(),
+                &receipt.matured_rewards,
+                receipt.matured_rewards_info.as_ref(),
+                receipt.parent_burn_block_hash.clone(),
+                receipt.parent_burn_block_height,
+                receipt.parent_burn_block_timestamp,
+                &receipt.anchored_block_cost,
+                &receipt.parent_microblocks_cost,
+                &pox_constants,
+                &reward_set_data,
+                &Some(block.header.pox_treatment.clone()),
+                Some(block.header.timestamp),
+                receipt.coinbase_height,
+            );
         }
     }
 
@@ -1179,9 +1380,278 @@ fn replay_block_nakamoto(
     Ok(())
 }
 
+/// Re-emit the events for the canonical burnchain blocks with heights in
+/// `start_height..=end_height`, and for the canonical Stacks blocks elected in them (in epoch
+/// 2.x) or in the tenures started in them (in Nakamoto), to `dispatcher`.
+///
+/// Each burnchain block is announced first, followed by its Stacks blocks in order.  The Stacks
+/// blocks are re-evaluated atop their parents in order to regenerate their transaction receipts,
+/// but nothing is committed.
+pub fn replay_events<T: BlockEventDispatcher>(
+    sort_db: &mut SortitionDB,
+    chainstate: &mut StacksChainState,
+    burnchain_db: &BurnchainDB,
+    burnchain: &Burnchain,
+    start_height: u64,
+    end_height: u64,
+    dispatcher: &T,
+) -> Result<(), CoordinatorError> {
+    let sort_tip = SortitionDB::get_canonical_burn_chain_tip(sort_db.conn())?;
+
+    // find the canonical Stacks blocks in range, grouped by the height of the sortition that
+    // elected them (or their tenure)
+    let mut stacks_blocks: BTreeMap<u64, Vec<(StacksBlockId, bool)>> = BTreeMap::new();
+    let mut cursor = NakamotoChainState::get_canonical_block_header(chainstate.db(), sort_db)?;
+    while let Some(header) = cursor {
+        let burn_height = u64::from(header.burn_header_height);
+        if burn_height < start_height || header.stacks_block_height == 0 {
+            break;
+        }
+        let block_id = header.index_block_hash();
+        let is_nakamoto = header.is_nakamoto_block();
+        if burn_height <= end_height {
+            stacks_blocks
+                .entry(burn_height)
+                .or_default()
+                .push((block_id.clone(), is_nakamoto));
+        }
+        let parent_block_id = match &header.anchored_header {
+            StacksBlockHeaderTypes::Nakamoto(nakamoto_header) => {
+                nakamoto_header.parent_block_id.clone()
+            }
+            StacksBlockHeaderTypes::Epoch2(_) => {
+                StacksChainState::get_parent_block_id(chainstate.db(), &block_id)?
+                    .ok_or(ChainstateError::NoSuchBlockError)?
+            }
+        };
+        cursor = NakamotoChainState::get_block_header(chainstate.db(), &parent_block_id)?;
+    }
+
+    for burn_height in start_height..=end_height {
+        let Some(snapshot) = SortitionDB::get_ancestor_snapshot(
+            &sort_db.index_conn(),
+            burn_height,
+            &sort_tip.sortition_id,
+        )?
+        else {
+            info!("No canonical burnchain block at height {burn_height}; stopping");
+            break;
+        };
+
+        // same as ChainsCoordinator::handle_new_nakamoto_burnchain_block()
+        let burn_block =
+            BurnchainDB::get_burnchain_block(burnchain_db.conn(), &snapshot.burn_header_hash)?;
+        let paid_rewards = calculate_paid_rewards(&burn_block.ops);
+        let parent_snapshot =
+            SortitionDB::get_block_snapshot(sort_db.conn(), &snapshot.parent_sortition_id)?
+                .ok_or(CoordinatorError::NoSortitions)?;
+        // the coordinator had this reward set when it processed the block, so it can't be
+        // missing now; replaying without it would announce the wrong reward recipients
+        let next_pox_info = if burnchain.is_reward_cycle_start(burn_height) {
+            Some(sort_db.get_preprocessed_reward_set_of(&snapshot.sortition_id)?)
+        } else {
+            None
+        };
+        let reward_set_info = sort_db.get_next_block_recipients(
+            burnchain,
+            &parent_snapshot,
+            next_pox_info.as_ref(),
+        )?;
+
+        info!(
+            "Replay burnchain block {} at height {burn_height}",
+            &snapshot.burn_header_hash
+        );
+        dispatcher_announce_burn_ops(
+            dispatcher,
+            &burn_block.header,
+            paid_rewards,
+            reward_set_info,
+            &snapshot.consensus_hash,
+        );
+
+        let Some(block_ids) = stacks_blocks.remove(&burn_height) else {
+            continue;
+        };
+        // blocks were found from the chain tip backwards
+        for (block_id, is_nakamoto) in block_ids.into_iter().rev() {
+            if !is_nakamoto {
+                replay_stored_block(
+                    sort_db,
+                    chainstate,
+                    burnchain_db,
+                    &block_id,
+                    Some(dispatcher),
+                )?;
+                continue;
+            }
+            let (block, block_size) = chainstate
+                .nakamoto_blocks_db()
+                .get_nakamoto_block(&block_id)?
+                .ok_or(ChainstateError::NoSuchBlockError)?;
+            replay_block_nakamoto(sort_db, chainstate, &block, block_size, Some(dispatcher))?;
+        }
+    }
+    Ok(())
+}
+
+/// Open a chainstate's Clarity state MARF
+fn open_clarity_marf(db_path: &str) -> MARF<StacksBlockId> {
+    let chainstate_path = PathBuf::from(format!("{db_path}/chainstate"));
+    let marf_path = StacksChainState::vm_state_index_marf_path(chainstate_path);
+    let marf_path = marf_path.to_str().expect("Invalid MARF path");
+    let mut open_opts = MARFOpenOpts::default();
+    open_opts.external_blobs = true;
+    MARF::from_path(marf_path, open_opts)
+        .unwrap_or_else(|e| panic!("Failed to open {marf_path}: {e}"))
+}
+
+/// Write a snapshot of the Clarity state MARF at a given block, along with its side-store data
+/// and the block's headers DB and sortition DB rows
+/// Terminates on error using `process::exit()`
+///
+/// Arguments:
+///  - `argv`: Args in CLI format: `<command-name> [args...]`
+pub fn command_export_marf_snapshot(argv: &[String]) {
+    let print_help_and_exit = || -> ! {
+        let n = &argv[0];
+        eprintln!("Usage: {n} <database-path> <index-block-hash> <snapshot-file>");
+        eprintln!();
+        eprintln!(
+            "Write a snapshot of the Clarity state at <index-block-hash> to <snapshot-file>,"
+        );
+        eprintln!("which can be loaded into a new node with `import-marf-snapshot`.");
+        process::exit(1);
+    };
+    let db_path = argv.get(1).unwrap_or_else(|| print_help_and_exit());
+    let index_block_hash = argv
+        .get(2)
+        .map(|hex| StacksBlockId::from_hex(hex).expect("Invalid <index-block-hash>"))
+        .unwrap_or_else(|| print_help_and_exit());
+    let snapshot_path = argv.get(3).unwrap_or_else(|| print_help_and_exit());
+
+    let start = Instant::now();
+    let mut clarity_marf = open_clarity_marf(db_path);
+    let marf_path =
+        StacksChainState::vm_state_index_marf_path(format!("{db_path}/chainstate").into());
+    let headers_path =
+        StacksChainState::header_index_root_path(format!("{db_path}/chainstate").into());
+    let sort_db_path = format!("{db_path}/burnchain/sortition/marf.sqlite");
+    let open_read_only = |path: &Path| {
+        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .unwrap_or_else(|e| panic!("Failed to open {}: {e}", path.display()))
+    };
+    let side_store = open_read_only(&marf_path);
+    let headers_conn = open_read_only(&headers_path);
+    let sortdb_conn = open_read_only(Path::new(&sort_db_path));
+
+    let file = fs::File::create(snapshot_path)
+        .unwrap_or_else(|e| panic!("Failed to create {snapshot_path}: {e}"));
+    let mut out = io::BufWriter::new(file);
+    if let Err(e) = export_marf_snapshot(
+        &mut clarity_marf,
+        &side_store,
+        &headers_conn,
+        &sortdb_conn,
+        &index_block_hash,
+        &mut out,
+    ) {
+        eprintln!("Failed to export snapshot of {index_block_hash}: {e}");
+        process::exit(1);
+    }
+    println!(
+        "Wrote snapshot of {index_block_hash} to {snapshot_path} in {}s",
+        start.elapsed().as_secs()
+    );
+}
+
+/// Rebuild the Clarity state MARF of an empty chainstate from a snapshot, and load the
+/// snapshotted block's headers DB and sortition DB rows
+/// Terminates on error using `process::exit()`
+///
+/// Arguments:
+///  - `argv`: Args in CLI format: `<command-name> [args...]`
+pub fn command_import_marf_snapshot(argv: &[String]) {
+    let print_help_and_exit = || -> ! {
+        let n = &argv[0];
+        eprintln!(
+            "Usage: {n} <snapshot-file> <database-path> <index-block-hash> <state-index-root>"
+        );
+        eprintln!();
+        eprintln!(
+            "Rebuild the Clarity state in <database-path> from <snapshot-file>, which was written"
+        );
+        eprintln!(
+            "by `export-marf-snapshot`. The Clarity state MARF must be empty, and the headers and"
+        );
+        eprintln!("sortition databases must already exist.");
+        eprintln!();
+        eprintln!(
+            "The snapshot is not trusted: it must be of <index-block-hash>, and the rebuilt MARF's"
+        );
+        eprintln!(
+            "root hash must be <state-index-root>. Get both from a node you trust. An interrupted"
+        );
+        eprintln!("import can be finished by running it again.");
+        process::exit(1);
+    };
+    let snapshot_path = argv.get(1).unwrap_or_else(|| print_help_and_exit());
+    let db_path = argv.get(2).unwrap_or_else(|| print_help_and_exit());
+    let index_block_hash = argv
+        .get(3)
+        .map(|hex| StacksBlockId::from_hex(hex).expect("Invalid <index-block-hash>"))
+        .unwrap_or_else(|| print_help_and_exit());
+    let state_index_root = argv
+        .get(4)
+        .map(|hex| TrieHash::from_hex(hex).expect("Invalid <state-index-root>"))
+        .unwrap_or_else(|| print_help_and_exit());
+
+    let start = Instant::now();
+    let headers_path =
+        StacksChainState::header_index_root_path(format!("{db_path}/chainstate").into());
+    let sort_db_path = format!("{db_path}/burnchain/sortition/marf.sqlite");
+    let open_read_write = |path: &Path| {
+        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE)
+            .unwrap_or_else(|e| panic!("Failed to open {}: {e}", path.display()))
+    };
+    let mut headers_conn = open_read_write(&headers_path);
+    let mut sortdb_conn = open_read_write(Path::new(&sort_db_path));
+    let marf_dir =
+        StacksChainState::vm_state_index_root_path(format!("{db_path}/chainstate").into());
+    fs::create_dir_all(&marf_dir)
+        .unwrap_or_else(|e| panic!("Failed to create {}: {e}", marf_dir.display()));
+    let mut clarity_marf = open_clarity_marf(db_path);
+
+    match import_marf_snapshot(
+        Path::new(snapshot_path),
+        &index_block_hash,
+        &state_index_root,
+        &mut clarity_marf,
+        &mut headers_conn,
+        &mut sortdb_conn,
+    ) {
+        Ok(summary) => println!(
+            "Imported {} tries up to {} (height {}, state_index_root {}) in {}s",
+            summary.num_tries,
+            &summary.index_block_hash,
+            summary.block_height,
+            &summary.state_index_root,
+            start.elapsed().as_secs()
+        ),
+        Err(e) => {
+            eprintln!("Failed to import snapshot {snapshot_path}: {e}");
+            process::exit(1);
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use super::*;
+    use crate::chainstate::nakamoto::coordinator::tests::boot_nakamoto;
+    use crate::chainstate::nakamoto::tests::node::TestStacker;
+    use crate::chainstate::stacks::events::StacksTransactionReceipt;
+    use crate::net::test::{TestEventObserver, TestEventObserverBlock, TestPeer};
 
     fn parse_cli_command(s: &str) -> Vec<String> {
         s.split(' ').map(String::from).collect()
@@ -1210,4 +1680,147 @@ pub mod test {
         assert_eq!(argv, argv_expected);
         assert!(opts.config.is_some());
     }
+
+    /// Boot a peer into Nakamoto with `observer` attached, and mine one Nakamoto tenure.
+    /// Returns the peer, the burnchain height of the tenure's sortition and its blocks.
+    fn boot_and_mine_tenure<'a>(
+        test_name: &str,
+        observer: &'a TestEventObserver,
+    ) -> (TestPeer<'a>, u64, Vec<NakamotoBlock>) {
+        let (mut test_signers, test_stackers) = TestStacker::common_signing_set();
+        let mut peer = boot_nakamoto(
+            test_name,
+            vec![],
+            &mut test_signers,
+            &test_stackers,
+            Some(observer),
+        );
+
+        let (burn_ops, mut tenure_change, miner_key) =
+            peer.begin_nakamoto_tenure(TenureChangeCause::BlockFound);
+        let (burn_height, _, consensus_hash) = peer.next_burnchain_block(burn_ops);
+        let vrf_proof = peer.make_nakamoto_vrf_proof(miner_key);
+        tenure_change.tenure_consensus_hash = consensus_hash.clone();
+        tenure_change.burn_view_consensus_hash = consensus_hash;
+        let tenure_change_tx = peer.miner.make_nakamoto_tenure_change(tenure_change);
+        let coinbase_tx = peer.miner.make_nakamoto_coinbase(None, vrf_proof);
+        let blocks: Vec<_> = peer
+            .make_nakamoto_tenure(
+                tenure_change_tx,
+                coinbase_tx,
+                &mut test_signers,
+                |_miner, _chainstate, _sort_dbconn, _blocks| vec![],
+            )
+            .into_iter()
+            .map(|(block, ..)| block)
+            .collect();
+        assert!(!blocks.is_empty());
+        (peer, burn_height, blocks)
+    }
+
+    /// Replay the events for `start_height..=end_height` from `peer`'s chainstate, and return
+    /// the Stacks blocks that were announced again
+    fn replay(
+        peer: &mut TestPeer,
+        start_height: u64,
+        end_height: u64,
+    ) -> Vec<TestEventObserverBlock> {
+        let burnchain = peer.config.burnchain.clone();
+        let burnchain_db = BurnchainDB::open(&burnchain.get_burnchaindb_path(), false).unwrap();
+        let mut sortdb = peer.sortdb.take().unwrap();
+        let replay_observer = TestEventObserver::new();
+        replay_events(
+            &mut sortdb,
+            &mut peer.stacks_node.as_mut().unwrap().chainstate,
+            &burnchain_db,
+            &burnchain,
+            start_height,
+            end_height,
+            &replay_observer,
+        )
+        .unwrap();
+        peer.sortdb = Some(sortdb);
+        replay_observer.get_blocks()
+    }
+
+    /// Check that each replayed block was announced the same way as when it was first processed
+    fn assert_same_announcements(
+        replayed: &[TestEventObserverBlock],
+        announced: &[TestEventObserverBlock],
+    ) {
+        assert_eq!(replayed.len(), announced.len());
+        let txids = |receipts: &[StacksTransactionReceipt]| -> Vec<_> {
+            receipts.iter().map(|r| r.transaction.txid()).collect()
+        };
+        for (replayed, announced) in replayed.iter().zip(announced) {
+            assert_eq!(replayed.metadata, announced.metadata);
+            assert_eq!(replayed.parent, announced.parent);
+            assert_eq!(replayed.winner_txid, announced.winner_txid);
+            assert_eq!(txids(&replayed.receipts), txids(&announced.receipts));
+            assert_eq!(replayed.matured_rewards, announced.matured_rewards);
+            assert_eq!(
+                replayed.reward_set_data.is_some(),
+                announced.reward_set_data.is_some()
+            );
+        }
+    }
+
+    #[test]
+    fn test_replay_events() {
+        let observer = TestEventObserver::new();
+        let (mut peer, burn_height, blocks) = boot_and_mine_tenure(function_name!(), &observer);
+
+        // the events announced when the tenure's blocks were first processed
+        let announced = observer.get_blocks();
+        let announced = &announced[announced.len() - blocks.len()..];
+
+        // each of the tenure's blocks is announced again, in order, with the same receipts
+        let replayed = replay(&mut peer, burn_height, burn_height);
+        for (replayed, block) in replayed.iter().zip(blocks.iter()) {
+            assert_eq!(replayed.metadata.index_block_hash(), block.block_id());
+        }
+        assert_same_announcements(&replayed, announced);
+
+        // nothing was committed by the replay
+        let tip = NakamotoChainState::get_canonical_block_header(
+            peer.stacks_node.as_ref().unwrap().chainstate.db(),
+            peer.sortdb.as_ref().unwrap(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(tip.index_block_hash(), blocks.last().unwrap().block_id());
+    }
+
+    #[test]
+    fn test_replay_events_across_epoch_3() {
+        let observer = TestEventObserver::new();
+        let (mut peer, burn_height, blocks) = boot_and_mine_tenure(function_name!(), &observer);
+
+        let epoch_3_start = SortitionDB::get_stacks_epoch_by_epoch_id(
+            peer.sortdb.as_ref().unwrap().conn(),
+            &StacksEpochId::Epoch30,
+        )
+        .unwrap()
+        .unwrap()
+        .start_height;
+        let start_height = epoch_3_start - 3;
+
+        // the events announced when the blocks in range were first processed
+        let announced: Vec<_> = observer
+            .get_blocks()
+            .into_iter()
+            .filter(|block| u64::from(block.metadata.burn_header_height) >= start_height)
+            .collect();
+        assert!(announced
+            .iter()
+            .any(|block| block.metadata.is_epoch_2_block()));
+
+        // both the epoch 2.x blocks and the Nakamoto blocks are announced again, in order
+        let replayed = replay(&mut peer, start_height, burn_height);
+        assert_same_announcements(&replayed, &announced);
+        assert_eq!(
+            replayed.last().unwrap().metadata.index_block_hash(),
+            blocks.last().unwrap().block_id()
+        );
+    }
 }
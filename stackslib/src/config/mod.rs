@@ -17,7 +17,7 @@
 pub mod chain_data;
 
 use std::collections::{BTreeMap, HashMap, HashSet};
-use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::{Arc, LazyLock, Mutex};
@@ -30,7 +30,7 @@ use rand::RngCore;
 use serde::Deserialize;
 use stacks_common::consts::SIGNER_SLOTS_PER_USER;
 use stacks_common::types::chainstate::StacksAddress;
-use stacks_common::types::net::PeerAddress;
+use stacks_common::types::net::{OnionAddress, PeerAddress};
 use stacks_common::types::Address;
 use stacks_common::util::get_epoch_time_ms;
 use stacks_common::util::hash::hex_bytes;
@@ -39,6 +39,7 @@ use stacks_common::util::secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey};
 use crate::burnchains::affirmation::AffirmationMap;
 use crate::burnchains::bitcoin::BitcoinNetworkType;
 use crate::burnchains::{Burnchain, MagicBytes, PoxConstants, BLOCKSTACK_MAGIC_MAINNET};
+use crate::chainstate::nakamoto::assembly::BlockAssembly;
 use crate::chainstate::nakamoto::signer_set::NakamotoSigners;
 use crate::chainstate::stacks::boot::MINERS_NAME;
 use crate::chainstate::stacks::index::marf::MARFOpenOpts;
@@ -47,6 +48,7 @@ use crate::chainstate::stacks::miner::{BlockBuilderSettings, MinerStatus};
 use crate::chainstate::stacks::MAX_BLOCK_LEN;
 use crate::config::chain_data::MinerStats;
 use crate::core::mempool::{MemPoolWalkSettings, MemPoolWalkTxTypes};
+use crate::core::mempool_policy::MemPoolPolicy;
 use crate::core::{
     MemPoolDB, StacksEpoch, StacksEpochExtension, StacksEpochId,
     BITCOIN_TESTNET_FIRST_BLOCK_HEIGHT, BITCOIN_TESTNET_STACKS_25_BURN_HEIGHT,
@@ -60,6 +62,7 @@ use crate::cost_estimates::metrics::{CostMetric, ProportionalDotProduct, UnitMet
 use crate::cost_estimates::{CostEstimator, FeeEstimator, PessimisticEstimator, UnitEstimator};
 use crate::net::atlas::AtlasConfig;
 use crate::net::connection::{ConnectionOptions, DEFAULT_BLOCK_PROPOSAL_MAX_AGE_SECS};
+use crate::net::socks::Socks5Proxy;
 use crate::net::{Neighbor, NeighborAddress, NeighborKey};
 use crate::types::chainstate::BurnchainHeaderHash;
 use crate::types::EpochList;
@@ -590,13 +593,24 @@ impl Config {
             .make_cost_metric()
             .unwrap_or_else(|| Box::new(UnitMetric));
 
-        MemPoolDB::open(
+        let mut mempool = MemPoolDB::open(
             self.is_mainnet(),
             self.burnchain.chain_id,
             &self.get_chainstate_path_str(),
             cost_estimator,
             metric,
-        )
+        )?;
+        mempool.policy = self.make_mempool_policy();
+        Ok(mempool)
+    }
+
+    /// Make the mempool's replace-by-fee and package policy from the node config
+    pub fn make_mempool_policy(&self) -> MemPoolPolicy {
+        MemPoolPolicy {
+            rbf_min_fee_bump_percent: self.node.mempool_rbf_min_fee_bump_percent,
+            rbf_max_replacements: self.node.mempool_rbf_max_replacements,
+            package_fee_rates: self.node.mempool_package_fee_rates,
+        }
     }
 
     /// Load up a Burnchain and apply config settings to it.
@@ -830,6 +844,12 @@ impl Config {
 
         let is_mainnet = burnchain.mode == "mainnet";
 
+        let mut connection_options = match config_file.connection_options {
+            Some(opts) => opts.into_config(is_mainnet)?,
+            None => HELIUM_DEFAULT_CONNECTION_OPTIONS.clone(),
+        };
+        let proxied = connection_options.socks5_proxy.is_some();
+
         // Parse the node config
         let (mut node, bootstrap_node, deny_nodes) = match config_file.node {
             Some(node) => {
@@ -843,16 +863,26 @@ impl Config {
 
         if let Some(bootstrap_node) = bootstrap_node {
             if resolve_bootstrap_nodes {
-                node.set_bootstrap_nodes(
+                node.set_bootstrap_nodes_with_proxy(
                     bootstrap_node,
                     burnchain.chain_id,
                     burnchain.peer_version,
+                    proxied,
                 );
             }
         } else if is_mainnet && resolve_bootstrap_nodes {
             let bootstrap_node = ConfigFile::mainnet().node.unwrap().bootstrap_node.unwrap();
-            node.set_bootstrap_nodes(bootstrap_node, burnchain.chain_id, burnchain.peer_version);
+            node.set_bootstrap_nodes_with_proxy(
+                bootstrap_node,
+                burnchain.chain_id,
+                burnchain.peer_version,
+                proxied,
+            );
         }
+        // the p2p network hands these hostnames to the proxy in lieu of their synthetic addresses
+        connection_options
+            .socks5_hostnames
+            .extend(node.bootstrap_hostnames.iter().cloned());
         if let Some(deny_nodes) = deny_nodes {
             node.set_deny_nodes(deny_nodes, burnchain.chain_id, burnchain.peer_version);
         }
@@ -933,11 +963,6 @@ impl Config {
             });
         };
 
-        let connection_options = match config_file.connection_options {
-            Some(opts) => opts.into_config(is_mainnet)?,
-            None => HELIUM_DEFAULT_CONNECTION_OPTIONS.clone(),
-        };
-
         let estimation = match config_file.fee_estimation {
             Some(f) => FeeEstimationConfig::from(f),
             None => default_estimator,
@@ -1104,6 +1129,7 @@ impl Config {
             },
             miner_status,
             confirm_microblocks: false,
+            block_assembly: miner_config.block_assembly,
         }
     }
 
@@ -1146,6 +1172,8 @@ impl Config {
             },
             miner_status,
             confirm_microblocks: true,
+            // epoch 2.x blocks are always assembled with the greedy mempool walk
+            block_assembly: BlockAssembly::Greedy,
         }
     }
 
@@ -1247,6 +1275,9 @@ pub struct BurnchainConfig {
     /// This value is passed as the `maximumCount` query option to the
     /// `listunspent` RPC call.
     pub max_unspent_utxos: Option<u64>,
+    /// SOCKS5 proxy through which to reach bitcoind's RPC and p2p interfaces, if any.  The proxy
+    /// resolves `peer_host`, so it can be a `.onion` address.
+    pub socks5_proxy: Option<Socks5Proxy>,
 }
 
 impl BurnchainConfig {
@@ -1288,6 +1319,7 @@ impl BurnchainConfig {
             affirmation_overrides: HashMap::new(),
             fault_injection_burnchain_block_delay: 0,
             max_unspent_utxos: Some(1024),
+            socks5_proxy: None,
         }
     }
     pub fn get_rpc_url(&self, wallet: Option<String>) -> String {
@@ -1388,6 +1420,7 @@ pub struct BurnchainConfigFile {
     pub affirmation_overrides: Option<Vec<AffirmationOverride>>,
     pub fault_injection_burnchain_block_delay: Option<u64>,
     pub max_unspent_utxos: Option<u64>,
+    pub socks5_proxy: Option<String>,
 }
 
 impl BurnchainConfigFile {
@@ -1500,6 +1533,16 @@ impl BurnchainConfigFile {
             }
         }
 
+        let socks5_proxy = self
+            .socks5_proxy
+            .as_ref()
+            .map(|proxy| {
+                proxy
+                    .parse::<Socks5Proxy>()
+                    .map_err(|e| format!("Invalid burnchain.socks5_proxy: {e}"))
+            })
+            .transpose()?;
+
         let mut config = BurnchainConfig {
             chain: self.chain.unwrap_or(default_burnchain_config.chain),
             chain_id: match self.chain_id {
@@ -1532,6 +1575,8 @@ impl BurnchainConfigFile {
                 .commit_anchor_block_within
                 .unwrap_or(default_burnchain_config.commit_anchor_block_within),
             peer_host: match self.peer_host.as_ref() {
+                // the proxy will resolve it
+                Some(peer_host) if socks5_proxy.is_some() => peer_host.clone(),
                 Some(peer_host) => {
                     format!("{}:1", &peer_host)
                         .to_socket_addrs()
@@ -1614,6 +1659,7 @@ impl BurnchainConfigFile {
                     assert!(val <= 1024, "Value for max_unspent_utxos should be <= 1024");
                 })
                 .or(default_burnchain_config.max_unspent_utxos),
+            socks5_proxy,
         };
 
         if let BitcoinNetworkType::Mainnet = config.get_bitcoin_network().1 {
@@ -1657,6 +1703,11 @@ pub struct NodeConfig {
     pub p2p_address: String,
     pub local_peer_seed: Vec<u8>,
     pub bootstrap_node: Vec<Neighbor>,
+    /// onion services behind the synthetic addresses of any `.onion` bootstrap nodes
+    pub bootstrap_onion_addresses: Vec<OnionAddress>,
+    /// hostnames of bootstrap nodes that our SOCKS5 proxy resolves, and the synthetic addresses
+    /// that stand in for them
+    pub bootstrap_hostnames: Vec<(PeerAddress, String)>,
     pub deny_nodes: Vec<Neighbor>,
     pub miner: bool,
     pub stacker: bool,
@@ -1693,6 +1744,20 @@ pub struct NodeConfig {
     pub chain_liveness_poll_time_secs: u64,
     /// stacker DBs we replicate
     pub stacker_dbs: Vec<QualifiedContractIdentifier>,
+    /// Whether or not to record every processed transaction in the chainstate's transaction
+    /// index, so confirmed transactions can be looked up via `/v3/transactions/:txid`.
+    pub txindex: bool,
+    /// Address on which to serve the event stream (server-sent events), if at all
+    pub event_stream_bind: Option<String>,
+    /// Number of most-recent payloads the event stream retains for clients to resume from
+    pub event_stream_retention: u64,
+    /// Minimum fee increase, in percent, for a transaction to replace another by fee
+    pub mempool_rbf_min_fee_bump_percent: u64,
+    /// Maximum number of times the transaction at an origin nonce can be replaced by fee
+    pub mempool_rbf_max_replacements: Option<u64>,
+    /// Whether or not the mempool walk orders transactions by package
+    /// (child-pays-for-parent) fee rate
+    pub mempool_package_fee_rates: bool,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -1932,6 +1997,8 @@ impl Default for NodeConfig {
             data_url: format!("http://127.0.0.1:{rpc_port}"),
             p2p_address: format!("127.0.0.1:{rpc_port}"),
             bootstrap_node: vec![],
+            bootstrap_onion_addresses: vec![],
+            bootstrap_hostnames: vec![],
             deny_nodes: vec![],
             local_peer_seed: local_peer_seed.to_vec(),
             miner: false,
@@ -1956,6 +2023,12 @@ impl Default for NodeConfig {
             fault_injection_hide_blocks: false,
             chain_liveness_poll_time_secs: 300,
             stacker_dbs: vec![],
+            txindex: false,
+            event_stream_bind: None,
+            event_stream_retention: 10_000,
+            mempool_rbf_min_fee_bump_percent: 0,
+            mempool_rbf_max_replacements: None,
+            mempool_package_fee_rates: false,
         }
     }
 }
@@ -2017,6 +2090,19 @@ impl NodeConfig {
     }
 
     pub fn add_bootstrap_node(&mut self, bootstrap_node: &str, chain_id: u32, peer_version: u32) {
+        self.add_bootstrap_node_with_proxy(bootstrap_node, chain_id, peer_version, false)
+    }
+
+    /// Like `add_bootstrap_node()`, but if `proxied` is true, then the node's hostname is left for
+    /// our SOCKS5 proxy to resolve.  The node is represented by a synthetic address, and the
+    /// hostname is recorded in `bootstrap_hostnames`.
+    pub fn add_bootstrap_node_with_proxy(
+        &mut self,
+        bootstrap_node: &str,
+        chain_id: u32,
+        peer_version: u32,
+        proxied: bool,
+    ) {
         let parts: Vec<&str> = bootstrap_node.split('@').collect();
         if parts.len() != 2 {
             panic!("Invalid bootstrap node '{bootstrap_node}': expected PUBKEY@IP:PORT");
@@ -2024,6 +2110,45 @@ impl NodeConfig {
         let (pubkey_str, hostport) = (parts[0], parts[1]);
         let pubkey = Secp256k1PublicKey::from_hex(pubkey_str)
             .unwrap_or_else(|_| panic!("Invalid public key '{pubkey_str}'"));
+
+        // onion services can't be resolved here (our SOCKS5 proxy does that), so they're
+        // represented by their synthetic peer addresses
+        if let Some((host, port)) = hostport
+            .rsplit_once(':')
+            .filter(|(host, _)| OnionAddress::is_onion_hostname(host))
+        {
+            let onion = host
+                .parse::<OnionAddress>()
+                .unwrap_or_else(|e| panic!("Invalid onion address '{host}': {e}"));
+            let port = port
+                .parse::<u16>()
+                .unwrap_or_else(|_| panic!("Invalid port in '{hostport}'"));
+            let sockaddr = onion.to_peer_address().to_socketaddr(port);
+            let neighbor = NodeConfig::default_neighbor(sockaddr, pubkey, chain_id, peer_version);
+            self.bootstrap_node.push(neighbor);
+            self.bootstrap_onion_addresses.push(onion);
+            return;
+        }
+
+        if let Some((host, port)) = hostport.rsplit_once(':').filter(|(host, _)| {
+            proxied
+                && host
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .parse::<IpAddr>()
+                    .is_err()
+        }) {
+            let port = port
+                .parse::<u16>()
+                .unwrap_or_else(|_| panic!("Invalid port in '{hostport}'"));
+            let addrbytes = PeerAddress::from_proxied_hostname(host);
+            let sockaddr = addrbytes.to_socketaddr(port);
+            let neighbor = NodeConfig::default_neighbor(sockaddr, pubkey, chain_id, peer_version);
+            self.bootstrap_node.push(neighbor);
+            self.bootstrap_hostnames.push((addrbytes, host.to_string()));
+            return;
+        }
+
         debug!("Resolve '{hostport}'");
 
         let mut attempts = 0;
@@ -2064,10 +2189,21 @@ impl NodeConfig {
         bootstrap_nodes: String,
         chain_id: u32,
         peer_version: u32,
+    ) {
+        self.set_bootstrap_nodes_with_proxy(bootstrap_nodes, chain_id, peer_version, false)
+    }
+
+    /// Like `set_bootstrap_nodes()`, but see `add_bootstrap_node_with_proxy()`
+    pub fn set_bootstrap_nodes_with_proxy(
+        &mut self,
+        bootstrap_nodes: String,
+        chain_id: u32,
+        peer_version: u32,
+        proxied: bool,
     ) {
         for part in bootstrap_nodes.split(',') {
             if !part.is_empty() {
-                self.add_bootstrap_node(part, chain_id, peer_version);
+                self.add_bootstrap_node_with_proxy(part, chain_id, peer_version, proxied);
             }
         }
     }
@@ -2177,6 +2313,8 @@ pub struct MinerConfig {
     pub tenure_extend_cost_threshold: u64,
     /// Define the timeout to apply while waiting for signers responses, based on the amount of rejections
     pub block_rejection_timeout_steps: HashMap<u32, Duration>,
+    /// How to select the transactions of a Nakamoto block
+    pub block_assembly: BlockAssembly,
 }
 
 impl Default for MinerConfig {
@@ -2226,6 +2364,7 @@ impl Default for MinerConfig {
                 rejections_timeouts_default_map.insert(30, Duration::from_secs(0));
                 rejections_timeouts_default_map
             },
+            block_assembly: BlockAssembly::default(),
         }
     }
 }
@@ -2275,6 +2414,9 @@ pub struct ConnectionOptionsFile {
     pub force_disconnect_interval: Option<u64>,
     pub antientropy_public: Option<bool>,
     pub private_neighbors: Option<bool>,
+    pub encrypt_transport: Option<bool>,
+    pub socks5_proxy: Option<String>,
+    pub onion_address: Option<String>,
     pub auth_token: Option<String>,
     pub antientropy_retry: Option<u64>,
     pub reject_blocks_pushed: Option<bool>,
@@ -2293,6 +2435,39 @@ impl ConnectionOptionsFile {
                     .map_err(|e| format!("Invalid connection_option.public_ip_address: {e}"))
             })
             .transpose()?;
+        let onion_address = self
+            .onion_address
+            .map(|onion_address| {
+                let (host, port) = onion_address.rsplit_once(':').ok_or_else(|| {
+                    "Invalid connection_options.onion_address: expected `<onion>:<port>`"
+                        .to_string()
+                })?;
+                let onion = host
+                    .parse::<OnionAddress>()
+                    .map_err(|e| format!("Invalid connection_options.onion_address: {e}"))?;
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|e| format!("Invalid connection_options.onion_address port: {e}"))?;
+                Ok::<_, String>((onion, port))
+            })
+            .transpose()?;
+        if ip_addr.is_some() && onion_address.is_some() {
+            return Err(
+                "connection_options.public_ip_address and connection_options.onion_address are mutually exclusive"
+                    .into(),
+            );
+        }
+        // an onion service is advertised by its synthetic address
+        let ip_addr =
+            ip_addr.or_else(|| onion_address.map(|(onion, port)| (onion.to_peer_address(), port)));
+        let socks5_proxy = self
+            .socks5_proxy
+            .map(|proxy| {
+                proxy
+                    .parse::<Socks5Proxy>()
+                    .map_err(|e| format!("Invalid connection_options.socks5_proxy: {e}"))
+            })
+            .transpose()?;
         let mut read_only_call_limit = HELIUM_DEFAULT_CONNECTION_OPTIONS
             .read_only_call_limit
             .clone();
@@ -2410,6 +2585,9 @@ impl ConnectionOptionsFile {
             max_sockets: self.max_sockets.unwrap_or(800) as usize,
             antientropy_public: self.antientropy_public.unwrap_or(true),
             private_neighbors: self.private_neighbors.unwrap_or(false),
+            encrypt_transport: self.encrypt_transport.unwrap_or(default.encrypt_transport),
+            socks5_proxy,
+            onion_address,
             auth_token: self.auth_token,
             antientropy_retry: self.antientropy_retry.unwrap_or(default.antientropy_retry),
             reject_blocks_pushed: self
@@ -2474,6 +2652,18 @@ pub struct NodeConfigFile {
     pub stacker_dbs: Option<Vec<String>>,
     /// fault injection: fail to push blocks with this probability (0-100)
     pub fault_injection_block_push_fail_probability: Option<u8>,
+    /// Maintain an index of confirmed transactions
+    pub txindex: Option<bool>,
+    /// Serve the event stream on this address
+    pub event_stream_bind: Option<String>,
+    /// Number of payloads the event stream retains
+    pub event_stream_retention: Option<u64>,
+    /// Minimum fee increase, in percent, for a replace-by-fee
+    pub mempool_rbf_min_fee_bump_percent: Option<u64>,
+    /// Maximum number of replace-by-fees per origin nonce
+    pub mempool_rbf_max_replacements: Option<u64>,
+    /// Order the mempool walk by package fee rate
+    pub mempool_package_fee_rates: Option<bool>,
 }
 
 impl NodeConfigFile {
@@ -2494,6 +2684,8 @@ impl NodeConfigFile {
             p2p_bind: self.p2p_bind.unwrap_or(default_node_config.p2p_bind),
             p2p_address: self.p2p_address.unwrap_or(rpc_bind.clone()),
             bootstrap_node: vec![],
+            bootstrap_onion_addresses: vec![],
+            bootstrap_hostnames: vec![],
             deny_nodes: vec![],
             data_url: self
                 .data_url
@@ -2572,6 +2764,21 @@ impl NodeConfigFile {
             } else {
                 default_node_config.fault_injection_block_push_fail_probability
             },
+            txindex: self.txindex.unwrap_or(default_node_config.txindex),
+            event_stream_bind: self.event_stream_bind,
+            event_stream_retention: self
+                .event_stream_retention
+                .unwrap_or(default_node_config.event_stream_retention)
+                .max(1),
+            mempool_rbf_min_fee_bump_percent: self
+                .mempool_rbf_min_fee_bump_percent
+                .unwrap_or(default_node_config.mempool_rbf_min_fee_bump_percent),
+            mempool_rbf_max_replacements: self
+                .mempool_rbf_max_replacements
+                .or(default_node_config.mempool_rbf_max_replacements),
+            mempool_package_fee_rates: self
+                .mempool_package_fee_rates
+                .unwrap_or(default_node_config.mempool_package_fee_rates),
         };
         Ok(node_config)
     }
@@ -2625,6 +2832,8 @@ pub struct MinerConfigFile {
     pub tenure_timeout_secs: Option<u64>,
     pub tenure_extend_cost_threshold: Option<u64>,
     pub block_rejection_timeout_steps: Option<HashMap<String, u64>>,
+    pub block_assembly: Option<String>,
+    pub priority_lane_contracts: Option<String>,
 }
 
 impl MinerConfigFile {
@@ -2658,6 +2867,23 @@ impl MinerConfigFile {
             } else {
                 miner_default_config.tenure_cost_limit_per_block_percentage
             };
+
+        let block_assembly = if let Some(block_assembly) = &self.block_assembly {
+            let mut lane_contracts = vec![];
+            if let Some(contracts) = &self.priority_lane_contracts {
+                for contract_str in contracts.split(',') {
+                    let contract_id = QualifiedContractIdentifier::parse(contract_str.trim())
+                        .map_err(|e| {
+                            format!("miner.priority_lane_contracts: invalid '{contract_str}': {e}")
+                        })?;
+                    lane_contracts.push(contract_id);
+                }
+            }
+            BlockAssembly::from_config(block_assembly, lane_contracts)
+                .map_err(|e| format!("miner.block_assembly: {e}"))?
+        } else {
+            miner_default_config.block_assembly
+        };
         Ok(MinerConfig {
             first_attempt_time_ms: self
                 .first_attempt_time_ms
@@ -2786,7 +3012,8 @@ impl MinerConfigFile {
                 } else{
                     miner_default_config.block_rejection_timeout_steps
                 }
-            }
+            },
+            block_assembly,
         })
     }
 }
@@ -2853,7 +3080,7 @@ pub enum EventKeyType {
 }
 
 impl EventKeyType {
-    fn from_string(raw_key: &str) -> Option<EventKeyType> {
+    pub fn from_string(raw_key: &str) -> Option<EventKeyType> {
         if raw_key == "*" {
             return Some(EventKeyType::AnyEvent);
         }
@@ -3372,4 +3599,61 @@ mod tests {
             assert_eq!(config.chain_id, CHAIN_ID_TESTNET);
         }
     }
+
+    #[test]
+    fn test_into_config_default_block_assembly() {
+        fn make_miner_config_file(
+            block_assembly: Option<&str>,
+            priority_lane_contracts: Option<&str>,
+        ) -> MinerConfigFile {
+            MinerConfigFile {
+                mining_key: Some(
+                    "0000000000000000000000000000000000000000000000000000000000000001".to_string(),
+                ),
+                block_assembly: block_assembly.map(String::from),
+                priority_lane_contracts: priority_lane_contracts.map(String::from),
+                ..MinerConfigFile::default()
+            }
+        }
+
+        let config = make_miner_config_file(None, None)
+            .into_config_default(MinerConfig::default())
+            .unwrap();
+        assert_eq!(config.block_assembly, BlockAssembly::Greedy);
+
+        let config = make_miner_config_file(Some("knapsack"), None)
+            .into_config_default(MinerConfig::default())
+            .unwrap();
+        assert_eq!(config.block_assembly, BlockAssembly::Knapsack);
+
+        let config = make_miner_config_file(
+            Some("priority_lanes"),
+            Some("SP000000000000000000002Q6VF78.pox-4, SP000000000000000000002Q6VF78.bns"),
+        )
+        .into_config_default(MinerConfig::default())
+        .unwrap();
+        assert_eq!(
+            config.block_assembly,
+            BlockAssembly::PriorityLanes(vec![
+                QualifiedContractIdentifier::parse("SP000000000000000000002Q6VF78.pox-4").unwrap(),
+                QualifiedContractIdentifier::parse("SP000000000000000000002Q6VF78.bns").unwrap(),
+            ])
+        );
+
+        let err = make_miner_config_file(Some("priority_lanes"), None)
+            .into_config_default(MinerConfig::default())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "miner.block_assembly: the priority_lanes block assembly requires priority_lane_contracts"
+        );
+
+        let err = make_miner_config_file(Some("fastest"), None)
+            .into_config_default(MinerConfig::default())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "miner.block_assembly: unknown block assembly strategy 'fastest'"
+        );
+    }
 }
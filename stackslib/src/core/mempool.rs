@@ -55,6 +55,9 @@ use crate::chainstate::stacks::{
     Error as ChainstateError, StacksBlock, StacksMicroblock, StacksTransaction, TransactionPayload,
 };
 use crate::clarity_vm::clarity::ClarityConnection;
+use crate::core::mempool_policy::{
+    MemPoolPolicy, ReplaceByFeeDecision, MEMPOOL_PACKAGE_FEE_RATE_QUERY,
+};
 use crate::core::{
     ExecutionCost, StacksEpochId, FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH,
 };
@@ -69,6 +72,7 @@ use crate::util_lib::db::{
     tx_begin_immediate, tx_busy_handler, u64_to_sql, DBConn, DBTx, Error as db_error, Error,
     FromColumn, FromRow,
 };
+use crate::util_lib::sketch::{SetSketch, SKETCH_MAX_CELLS, SKETCH_NUM_HASHES};
 use crate::{cost_estimates, monitoring};
 
 // maximum number of confirmations a transaction can have before it's garbage-collected
@@ -99,6 +103,11 @@ pub const DEFAULT_BLACKLIST_MAX_SIZE: u64 = 134217728; // 2**27 -- the blacklist
 // loading the bloom filter, even though the bloom filter is larger.
 const DEFAULT_MAX_TX_TAGS: u32 = 2048;
 
+// minimum number of cells in a mempool sketch.
+// Mempool sketches are sized to recover a difference of up to a quarter of our recent
+// transactions, at 1.5 cells per differing transaction.
+const MEMPOOL_SKETCH_MIN_CELLS: u32 = SKETCH_NUM_HASHES * 64;
+
 /// A node-specific transaction tag -- the first 8 bytes of siphash(local-seed,txid)
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
 pub struct TxTag(pub [u8; 8]);
@@ -112,6 +121,11 @@ impl TxTag {
         let result_64 = hasher.finish();
         TxTag(result_64.to_be_bytes())
     }
+
+    /// Key for this tag in a mempool sketch
+    pub fn to_sketch_key(&self) -> u64 {
+        u64::from_be_bytes(self.0)
+    }
 }
 
 impl std::fmt::Display for TxTag {
@@ -135,13 +149,17 @@ impl StacksMessageCodec for TxTag {
 
 define_u8_enum!(MemPoolSyncDataID {
     BloomFilter = 0x01,
-    TxTags = 0x02
+    TxTags = 0x02,
+    Sketch = 0x03
 });
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum MemPoolSyncData {
     BloomFilter(BloomFilter<BloomNodeHasher>),
     TxTags([u8; 32], Vec<TxTag>),
+    /// Sketch of the requester's recent transactions' tags, computed with the given seed.  Only
+    /// sent to peers that advertise the MEMPOOL_SKETCH service bit.
+    Sketch([u8; 32], SetSketch),
 }
 
 pub enum MempoolIterationStopReason {
@@ -164,6 +182,11 @@ impl StacksMessageCodec for MemPoolSyncData {
                 write_next(fd, seed)?;
                 write_next(fd, tags)?;
             }
+            MemPoolSyncData::Sketch(ref seed, ref sketch) => {
+                write_next(fd, &MemPoolSyncDataID::Sketch.to_u8())?;
+                write_next(fd, seed)?;
+                write_next(fd, sketch)?;
+            }
         }
         Ok(())
     }
@@ -183,6 +206,11 @@ impl StacksMessageCodec for MemPoolSyncData {
                 let txtags: Vec<TxTag> = read_next(fd)?;
                 Ok(MemPoolSyncData::TxTags(seed, txtags))
             }
+            MemPoolSyncDataID::Sketch => {
+                let seed: [u8; 32] = read_next(fd)?;
+                let sketch: SetSketch = read_next(fd)?;
+                Ok(MemPoolSyncData::Sketch(seed, sketch))
+            }
         }
     }
 }
@@ -355,6 +383,12 @@ pub enum MemPoolDropReason {
     STALE_COLLECT,
     TOO_EXPENSIVE,
     PROBLEMATIC,
+    /// A replacement did not bump the fee by enough (see `MemPoolPolicy`)
+    RBF_INSUFFICIENT_FEE_BUMP,
+    /// A replacement was for a nonce that was already replaced too many times
+    RBF_TOO_MANY_REPLACEMENTS,
+    /// The node operator dropped the transaction through the admin RPC
+    ADMIN_REQUEST,
 }
 
 pub struct ConsiderTransaction {
@@ -380,6 +414,13 @@ impl std::fmt::Display for MemPoolDropReason {
             MemPoolDropReason::REPLACE_ACROSS_FORK => write!(f, "ReplaceAcrossFork"),
             MemPoolDropReason::REPLACE_BY_FEE => write!(f, "ReplaceByFee"),
             MemPoolDropReason::PROBLEMATIC => write!(f, "Problematic"),
+            MemPoolDropReason::RBF_INSUFFICIENT_FEE_BUMP => {
+                write!(f, "ReplaceByFeeInsufficientBump")
+            }
+            MemPoolDropReason::RBF_TOO_MANY_REPLACEMENTS => {
+                write!(f, "ReplaceByFeeTooManyReplacements")
+            }
+            MemPoolDropReason::ADMIN_REQUEST => write!(f, "AdminRequest"),
         }
     }
 }
@@ -803,6 +844,21 @@ const MEMPOOL_SCHEMA_7_TIME_ESTIMATES: &[&str] = &[
     "#,
 ];
 
+const MEMPOOL_SCHEMA_8_RBF_REPLACEMENTS: &[&str] = &[
+    r#"
+    -- Number of times the transaction at each origin nonce has been replaced by fee
+    CREATE TABLE rbf_replacements(
+        address TEXT NOT NULL,
+        nonce INTEGER NOT NULL,
+        count INTEGER NOT NULL,
+        PRIMARY KEY (address, nonce)
+    );
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (8)
+    "#,
+];
+
 const MEMPOOL_INDEXES: &[&str] = &[
     "CREATE INDEX IF NOT EXISTS by_txid ON mempool(txid);",
     "CREATE INDEX IF NOT EXISTS by_height ON mempool(height);",
@@ -827,12 +883,15 @@ pub struct MemPoolDB {
     metric: Box<dyn CostMetric>,
     pub blacklist_timeout: u64,
     pub blacklist_max_size: u64,
+    /// Replace-by-fee and package policy
+    pub policy: MemPoolPolicy,
 }
 
 pub struct MemPoolTx<'a> {
     tx: DBTx<'a>,
     admitter: &'a mut MemPoolAdmitter,
     bloom_counter: Option<&'a mut BloomCounter<BloomNodeHasher>>,
+    policy: MemPoolPolicy,
 }
 
 impl<'a> Deref for MemPoolTx<'a> {
@@ -853,11 +912,13 @@ impl<'a> MemPoolTx<'a> {
         tx: DBTx<'a>,
         admitter: &'a mut MemPoolAdmitter,
         bloom_counter: &'a mut BloomCounter<BloomNodeHasher>,
+        policy: MemPoolPolicy,
     ) -> MemPoolTx<'a> {
         MemPoolTx {
             tx,
             admitter,
             bloom_counter: Some(bloom_counter),
+            policy,
         }
     }
 
@@ -1291,6 +1352,9 @@ impl MemPoolDB {
                     MemPoolDB::instantiate_schema_7(tx)?;
                 }
                 7 => {
+                    MemPoolDB::instantiate_rbf_replacements(tx)?;
+                }
+                8 => {
                     break;
                 }
                 _ => {
@@ -1377,6 +1441,16 @@ impl MemPoolDB {
         Ok(())
     }
 
+    /// Add the replace-by-fee counts table
+    #[cfg_attr(test, mutants::skip)]
+    fn instantiate_rbf_replacements(tx: &DBTx) -> Result<(), db_error> {
+        for sql_exec in MEMPOOL_SCHEMA_8_RBF_REPLACEMENTS {
+            tx.execute_batch(sql_exec)?;
+        }
+
+        Ok(())
+    }
+
     #[cfg_attr(test, mutants::skip)]
     pub fn db_path(chainstate_root_path: &str) -> Result<String, db_error> {
         let mut path = PathBuf::from(chainstate_root_path);
@@ -1439,6 +1513,7 @@ impl MemPoolDB {
             metric,
             blacklist_timeout: DEFAULT_BLACKLIST_TIMEOUT,
             blacklist_max_size: DEFAULT_BLACKLIST_MAX_SIZE,
+            policy: MemPoolPolicy::default(),
         })
     }
 
@@ -1564,6 +1639,48 @@ impl MemPoolDB {
         Ok(updated)
     }
 
+    /// Predict the execution cost of a transaction payload with the mempool's `CostEstimator`.
+    /// Returns None if the estimator has no estimate for it.
+    pub fn estimate_tx_cost(
+        &self,
+        payload: &TransactionPayload,
+        stacks_epoch_id: &StacksEpochId,
+    ) -> Option<ExecutionCost> {
+        match self.cost_estimator.estimate_cost(payload, stacks_epoch_id) {
+            Ok(cost) => Some(cost),
+            Err(EstimatorError::NoEstimateAvailable) => None,
+            Err(e) => {
+                warn!("Error while estimating mempool tx cost"; "error" => ?e);
+                None
+            }
+        }
+    }
+
+    /// Update the mempool's `CostEstimator` with the measured execution cost of a transaction
+    pub fn notify_cost_estimator(
+        &mut self,
+        payload: &TransactionPayload,
+        actual_cost: &ExecutionCost,
+        block_limit: &ExecutionCost,
+        stacks_epoch_id: &StacksEpochId,
+    ) {
+        if let Err(e) =
+            self.cost_estimator
+                .notify_event(payload, actual_cost, block_limit, stacks_epoch_id)
+        {
+            warn!("Error updating estimator"; "error" => ?e);
+        }
+    }
+
+    /// Get up to `max_txs` transactions across all tips, in descending fee rate order.
+    /// Transactions without a fee rate estimate come last, in descending fee order.
+    pub fn get_txs_by_fee_rate(&self, max_txs: u64) -> Result<Vec<MemPoolTxInfo>, db_error> {
+        let sql = "SELECT * FROM mempool
+                   ORDER BY fee_rate IS NULL, fee_rate DESC, tx_fee DESC
+                   LIMIT ?1";
+        query_rows(self.conn(), sql, params![u64_to_sql(max_txs)?])
+    }
+
     /// Helper method to record nonces to a retry-buffer.
     /// This is needed for when we try to write-through a new (address, nonce) pair to the on-disk
     /// `nonces` cache, but the write fails due to lock contention from another thread.  The
@@ -1648,12 +1765,18 @@ impl MemPoolDB {
             .query(NO_PARAMS)
             .map_err(Error::SqliteError)?;
 
-        let sql = "
+        // with package fee rates, a transaction is ranked by the best fee rate of any package it
+        // starts, so that high-fee descendants can pay for it
+        let sql = if self.policy.package_fee_rates {
+            MEMPOOL_PACKAGE_FEE_RATE_QUERY
+        } else {
+            "
             SELECT txid, origin_nonce, origin_address, sponsor_nonce, sponsor_address, fee_rate
             FROM mempool
             WHERE fee_rate IS NOT NULL
             ORDER BY fee_rate DESC
-            ";
+            "
+        };
         let mut query_stmt_fee = self.db.prepare(sql).map_err(Error::SqliteError)?;
         let mut fee_iterator = query_stmt_fee
             .query(NO_PARAMS)
@@ -1930,6 +2053,7 @@ impl MemPoolDB {
             tx,
             &mut self.admitter,
             &mut self.bloom_counter,
+            self.policy.clone(),
         ))
     }
 
@@ -1938,6 +2062,12 @@ impl MemPoolDB {
             .map(|row_opt: Option<i64>| row_opt.is_some())
     }
 
+    /// How many transactions are in the mempool, across all tips?
+    pub fn count_txs(conn: &DBConn) -> Result<u64, db_error> {
+        let sql = "SELECT COUNT(*) FROM mempool";
+        Ok(query_int(conn, sql, NO_PARAMS)? as u64)
+    }
+
     pub fn get_tx(conn: &DBConn, txid: &Txid) -> Result<Option<MemPoolTxInfo>, db_error> {
         query_row(conn, "SELECT * FROM mempool WHERE txid = ?1", params![txid])
     }
@@ -2112,15 +2242,27 @@ impl MemPoolDB {
         let mut replace_reason = MemPoolDropReason::REPLACE_BY_FEE;
 
         // if so, is this a replace-by-fee? or a replace-in-chain-tip?
-        let add_tx = if let Some(ref prior_tx) = prior_tx {
-            if tx_fee > prior_tx.tx_fee {
+        if let Some(ref prior_tx) = prior_tx {
+            let num_replacements = MemPoolPolicy::get_num_replacements(
+                tx,
+                &prior_tx.origin_address,
+                prior_tx.origin_nonce,
+            )?;
+            let decision = tx
+                .policy
+                .check_replace_by_fee(prior_tx, tx_fee, num_replacements);
+            if decision == ReplaceByFeeDecision::Accept {
                 // is this a replace-by-fee ?
                 debug!(
                     "Can replace {} with {} for {},{} by fee ({} < {})",
                     &prior_tx.txid, &txid, origin_address, origin_nonce, &prior_tx.tx_fee, &tx_fee
                 );
                 replace_reason = MemPoolDropReason::REPLACE_BY_FEE;
-                true
+                MemPoolPolicy::record_replacement(
+                    tx,
+                    &prior_tx.origin_address,
+                    prior_tx.origin_nonce,
+                )?;
             } else if !MemPoolDB::are_blocks_in_same_fork(
                 chainstate,
                 &prior_tx.tenure_consensus_hash,
@@ -2134,10 +2276,9 @@ impl MemPoolDB {
                     &prior_tx.txid, &txid, origin_address, origin_nonce
                 );
                 replace_reason = MemPoolDropReason::REPLACE_ACROSS_FORK;
-                true
             } else {
-                // there's a >= fee tx in this fork, cannot add
-                info!("TX conflicts with sponsor/origin nonce in same fork with >= fee";
+                // the replace-by-fee policy does not allow this tx in this fork, cannot add
+                info!("TX conflicts with sponsor/origin nonce in same fork and cannot replace it";
                       "new_txid" => %txid,
                       "old_txid" => %prior_tx.txid,
                       "origin_addr" => %origin_address,
@@ -2145,16 +2286,18 @@ impl MemPoolDB {
                       "sponsor_addr" => %sponsor_address,
                       "sponsor_nonce" => sponsor_nonce,
                       "new_fee" => tx_fee,
-                      "old_fee" => prior_tx.tx_fee);
-                false
+                      "old_fee" => prior_tx.tx_fee,
+                      "num_replacements" => num_replacements,
+                      "decision" => ?decision);
+                if let (Some(reason), Some(event_observer)) =
+                    (decision.drop_reason(), event_observer)
+                {
+                    event_observer.mempool_txs_dropped(vec![txid], None, reason);
+                }
+                return Err(decision
+                    .into_rejection(tx_fee)
+                    .unwrap_or(MemPoolRejection::ConflictingNonceInMempool));
             }
-        } else {
-            // no conflicting TX with this origin/sponsor, go ahead and add
-            true
-        };
-
-        if !add_tx {
-            return Err(MemPoolRejection::ConflictingNonceInMempool);
         }
 
         tx.update_bloom_counter(
@@ -2252,6 +2395,7 @@ impl MemPoolDB {
         let sql = "DELETE FROM mempool WHERE accept_time < ?1";
 
         tx.execute(sql, args)?;
+        MemPoolPolicy::garbage_collect_replacements(tx)?;
         increment_stx_mempool_gc();
         Ok(())
     }
@@ -2274,6 +2418,7 @@ impl MemPoolDB {
         let sql = "DELETE FROM mempool WHERE height < ?1";
 
         tx.execute(sql, args)?;
+        MemPoolPolicy::garbage_collect_replacements(tx)?;
         increment_stx_mempool_gc();
         Ok(())
     }
@@ -2715,7 +2860,14 @@ impl MemPoolDB {
     /// Get the transaction ID list that represents the set of transactions that are represented in
     /// the bloom counter.
     pub fn get_bloom_txids(&self) -> Result<Vec<Txid>, db_error> {
-        let max_height = match MemPoolDB::get_max_coinbase_height(self.conn())? {
+        Self::static_get_bloom_txids(self.conn())
+    }
+
+    /// Get the transaction ID list that represents the set of transactions that are represented in
+    /// the bloom counter -- i.e. those within BLOOM_COUNTER_DEPTH coinbase heights of the highest
+    /// transaction in the mempool.
+    pub fn static_get_bloom_txids(conn: &DBConn) -> Result<Vec<Txid>, db_error> {
+        let max_height = match MemPoolDB::get_max_coinbase_height(conn)? {
             Some(h) => h,
             None => {
                 // mempool is empty
@@ -2725,7 +2877,7 @@ impl MemPoolDB {
         let min_height = max_height.saturating_sub(BLOOM_COUNTER_DEPTH as u64);
         let sql = "SELECT mempool.txid FROM mempool WHERE height > ?1 AND height <= ?2 AND NOT EXISTS (SELECT 1 FROM removed_txids WHERE txid = mempool.txid)";
         let args = params![u64_to_sql(min_height)?, u64_to_sql(max_height)?];
-        query_rows(self.conn(), sql, args)
+        query_rows(conn, sql, args)
     }
 
     /// Get the transaction tag list that represents the set of recent transactions we have.
@@ -2740,6 +2892,26 @@ impl MemPoolDB {
         })
     }
 
+    /// Find the recent transactions whose tags, computed with `seed`, are in `tags`.
+    /// Used to rebuild compact Nakamoto blocks, whose transactions are identified only by tag.
+    /// More than one transaction may be returned for a tag if their tags collide.
+    pub fn find_txs_by_tags(
+        &self,
+        seed: &[u8],
+        tags: &HashSet<TxTag>,
+    ) -> Result<Vec<StacksTransaction>, db_error> {
+        let mut txs = vec![];
+        for txid in self.get_bloom_txids()?.into_iter() {
+            if !tags.contains(&TxTag::from(seed, &txid)) {
+                continue;
+            }
+            if let Some(tx_info) = MemPoolDB::get_tx(self.conn(), &txid)? {
+                txs.push(tx_info.tx);
+            }
+        }
+        Ok(txs)
+    }
+
     /// How many recent transactions are there -- i.e. within BLOOM_COUNTER_DEPTH coinbase heights of
     /// the chain tip?
     pub fn get_num_recent_txs(conn: &DBConn) -> Result<u64, db_error> {
@@ -2770,6 +2942,59 @@ impl MemPoolDB {
         }
     }
 
+    /// Make a mempool sync request for a peer that supports set reconciliation.
+    /// Uses a MemPoolSyncData::Sketch variant, which lets the peer work out exactly which of its
+    /// recent transactions we are missing, provided our mempools don't differ by too much.
+    pub fn make_mempool_sketch_sync_data(&self) -> Result<MemPoolSyncData, db_error> {
+        let seed = self.bloom_counter.get_seed().clone();
+        let tags = self.get_txtags(&seed)?;
+        let num_cells = u32::try_from(tags.len().saturating_mul(3) / 8)
+            .unwrap_or(SKETCH_MAX_CELLS)
+            .max(MEMPOOL_SKETCH_MIN_CELLS);
+
+        let mut sketch = SetSketch::new(num_cells);
+        for tag in tags.iter() {
+            sketch.insert(tag.to_sketch_key());
+        }
+        Ok(MemPoolSyncData::Sketch(seed, sketch))
+    }
+
+    /// Reconcile a remote peer's mempool sketch with our recent transactions.  The sketch is made
+    /// from the requester's `get_bloom_txids()`, so our side of the difference is made from the
+    /// same set of our transactions.
+    /// Returns Ok(Some(txids)) with the recent transactions that the remote peer does not have.
+    /// Returns Ok(None) if the sketch could not be decoded, because our mempools differ by more
+    /// than the sketch can represent.  The caller should fall back to a bloom filter or tag list.
+    pub fn reconcile_mempool_sketch(
+        conn: &DBConn,
+        seed: &[u8],
+        sketch: &SetSketch,
+    ) -> Result<Option<HashSet<Txid>>, db_error> {
+        let txids = Self::static_get_bloom_txids(conn)?;
+
+        let mut local_sketch = SetSketch::new(sketch.num_cells());
+        let mut txids_by_key = HashMap::new();
+        for txid in txids.into_iter() {
+            let key = TxTag::from(seed, &txid).to_sketch_key();
+            local_sketch.insert(key);
+            txids_by_key.insert(key, txid);
+        }
+
+        let Some((local_only, _)) = local_sketch
+            .subtract(sketch)
+            .and_then(|difference| difference.decode())
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(
+            local_only
+                .into_iter()
+                .filter_map(|key| txids_by_key.remove(&key))
+                .collect(),
+        ))
+    }
+
     /// Get the hashed txid for a txid
     pub fn get_randomized_txid(&self, txid: &Txid) -> Result<Option<Txid>, db_error> {
         let sql = "SELECT hashed_txid FROM randomized_txids WHERE txid = ?1 LIMIT 1";
@@ -2810,6 +3035,75 @@ impl MemPoolDB {
         max_txs: u64,
         max_run: u64,
     ) -> Result<(Vec<StacksTransaction>, Option<Txid>, u64), db_error> {
+        match data {
+            MemPoolSyncData::BloomFilter(ref bf) => Self::static_find_next_transactions_where(
+                conn,
+                coinbase_height,
+                last_randomized_txid,
+                max_txs,
+                max_run,
+                |txid| bf.contains_raw(&txid.0),
+            ),
+            MemPoolSyncData::TxTags(ref seed, ref tags) => {
+                let tags_table: HashSet<_> = tags.iter().cloned().collect();
+                Self::static_find_next_transactions_where(
+                    conn,
+                    coinbase_height,
+                    last_randomized_txid,
+                    max_txs,
+                    max_run,
+                    |txid| tags_table.contains(&TxTag::from(seed, txid)),
+                )
+            }
+            MemPoolSyncData::Sketch(ref seed, ref sketch) => {
+                let missing_txids = Self::reconcile_mempool_sketch(conn, seed, sketch)?.ok_or(
+                    db_error::Other("Failed to decode mempool sketch".to_string()),
+                )?;
+                Self::static_find_next_reconciled_transactions(
+                    conn,
+                    &missing_txids,
+                    coinbase_height,
+                    last_randomized_txid,
+                    max_txs,
+                    max_run,
+                )
+            }
+        }
+    }
+
+    /// Get the next batch of transactions from our mempool that are in `missing_txids`, as
+    /// obtained from `reconcile_mempool_sketch()`.  Pages through the mempool the same way as
+    /// `static_find_next_missing_transactions()`.
+    pub fn static_find_next_reconciled_transactions(
+        conn: &DBConn,
+        missing_txids: &HashSet<Txid>,
+        coinbase_height: u64,
+        last_randomized_txid: &Txid,
+        max_txs: u64,
+        max_run: u64,
+    ) -> Result<(Vec<StacksTransaction>, Option<Txid>, u64), db_error> {
+        Self::static_find_next_transactions_where(
+            conn,
+            coinbase_height,
+            last_randomized_txid,
+            max_txs,
+            max_run,
+            |txid| !missing_txids.contains(txid),
+        )
+    }
+
+    /// Get the next batch of transactions from our mempool for which `remote_has_tx` is false.
+    fn static_find_next_transactions_where<F>(
+        conn: &DBConn,
+        coinbase_height: u64,
+        last_randomized_txid: &Txid,
+        max_txs: u64,
+        max_run: u64,
+        remote_has_tx: F,
+    ) -> Result<(Vec<StacksTransaction>, Option<Txid>, u64), db_error>
+    where
+        F: Fn(&Txid) -> bool,
+    {
         let mut ret = vec![];
         let sql = "SELECT mempool.txid AS txid, mempool.tx AS tx, randomized_txids.hashed_txid AS hashed_txid \
                    FROM mempool JOIN randomized_txids \
@@ -2826,13 +3120,6 @@ impl MemPoolDB {
             u64_to_sql(max_run)?,
         ];
 
-        let mut tags_table = HashSet::new();
-        if let MemPoolSyncData::TxTags(_, ref tags) = data {
-            for tag in tags.iter() {
-                tags_table.insert(tag.clone());
-            }
-        }
-
         let mut stmt = conn.prepare(sql)?;
         let mut rows = stmt.query(args)?;
         let mut num_rows_visited = 0;
@@ -2854,13 +3141,7 @@ impl MemPoolDB {
             );
             next_page = Some(hashed_txid);
 
-            let contains = match data {
-                MemPoolSyncData::BloomFilter(ref bf) => bf.contains_raw(&txid.0),
-                MemPoolSyncData::TxTags(ref seed, ..) => {
-                    tags_table.contains(&TxTag::from(seed, &txid))
-                }
-            };
-            if contains {
+            if remote_has_tx(&txid) {
                 // remote peer already has this one
                 continue;
             }
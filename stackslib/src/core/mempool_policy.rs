@@ -0,0 +1,218 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Mempool replacement and package policy.
+//!
+//! A transaction that spends the same origin or sponsor nonce as a transaction already in the
+//! mempool may replace it by fee (RBF).  The rules for doing so are set by `MemPoolPolicy`:
+//! how much higher the replacement's fee must be, and how many times the same nonce may be
+//! replaced.
+//!
+//! A chain of transactions from the same origin with consecutive nonces forms a *package*.
+//! Since none of a package's transactions can be mined before its lower-nonce transactions,
+//! a high-fee transaction can pay for its low-fee ancestors (child-pays-for-parent).  When
+//! `package_fee_rates` is set, the mempool walk orders each transaction by the best aggregate
+//! fee rate of any package that it starts, instead of by its own fee rate alone.
+
+use rusqlite::params;
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::sqlite::NO_PARAMS;
+
+use crate::chainstate::stacks::db::blocks::MemPoolRejection;
+use crate::core::mempool::{MemPoolDropReason, MemPoolTxMetadata};
+use crate::util_lib::db::{query_row, u64_to_sql, DBConn, DBTx, Error as db_error};
+
+/// Mempool replace-by-fee and package policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemPoolPolicy {
+    /// A replacement transaction's fee must exceed the replaced transaction's fee by at least
+    /// this percentage.  If 0, then any higher fee will do.
+    pub rbf_min_fee_bump_percent: u64,
+    /// The maximum number of times the transaction at a given origin nonce may be replaced by
+    /// fee.  If None, then there is no limit.
+    pub rbf_max_replacements: Option<u64>,
+    /// Whether or not the mempool walk considers package (child-pays-for-parent) fee rates
+    pub package_fee_rates: bool,
+}
+
+impl Default for MemPoolPolicy {
+    fn default() -> Self {
+        Self {
+            rbf_min_fee_bump_percent: 0,
+            rbf_max_replacements: None,
+            package_fee_rates: false,
+        }
+    }
+}
+
+/// A decision on whether or not a transaction may replace a conflicting transaction by fee.
+/// Rejections carry the reason code reported to the mempool's event observer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplaceByFeeDecision {
+    /// The replacement is allowed
+    Accept,
+    /// The replacement's fee is not higher than the prior transaction's fee
+    FeeNotHigher,
+    /// The replacement's fee is higher, but not by the minimum bump
+    InsufficientFeeBump { min_fee: u64 },
+    /// The prior transaction's nonce has already been replaced the maximum number of times
+    TooManyReplacements { max_replacements: u64 },
+}
+
+impl ReplaceByFeeDecision {
+    /// The drop reason to report to the event observer for the rejected transaction, if any
+    pub fn drop_reason(&self) -> Option<MemPoolDropReason> {
+        match self {
+            ReplaceByFeeDecision::Accept | ReplaceByFeeDecision::FeeNotHigher => None,
+            ReplaceByFeeDecision::InsufficientFeeBump { .. } => {
+                Some(MemPoolDropReason::RBF_INSUFFICIENT_FEE_BUMP)
+            }
+            ReplaceByFeeDecision::TooManyReplacements { .. } => {
+                Some(MemPoolDropReason::RBF_TOO_MANY_REPLACEMENTS)
+            }
+        }
+    }
+
+    /// The rejection to return to the submitter, if any
+    pub fn into_rejection(self, tx_fee: u64) -> Option<MemPoolRejection> {
+        match self {
+            ReplaceByFeeDecision::Accept => None,
+            ReplaceByFeeDecision::FeeNotHigher => Some(MemPoolRejection::ConflictingNonceInMempool),
+            ReplaceByFeeDecision::InsufficientFeeBump { min_fee } => {
+                Some(MemPoolRejection::InsufficientReplacementFee {
+                    expected: min_fee,
+                    actual: tx_fee,
+                })
+            }
+            ReplaceByFeeDecision::TooManyReplacements { max_replacements } => {
+                Some(MemPoolRejection::TooManyReplacements { max_replacements })
+            }
+        }
+    }
+}
+
+impl MemPoolPolicy {
+    /// The minimum fee a transaction must pay to replace a transaction paying `prior_fee`
+    pub fn min_replacement_fee(&self, prior_fee: u64) -> u64 {
+        let bump = u128::from(prior_fee) * u128::from(self.rbf_min_fee_bump_percent) / 100;
+        let bump = u64::try_from(bump).unwrap_or(u64::MAX).max(1);
+        prior_fee.saturating_add(bump)
+    }
+
+    /// Decide whether or not a transaction paying `tx_fee` may replace `prior_tx` by fee, given
+    /// that `prior_tx`'s origin nonce has already been replaced `num_replacements` times.
+    pub fn check_replace_by_fee(
+        &self,
+        prior_tx: &MemPoolTxMetadata,
+        tx_fee: u64,
+        num_replacements: u64,
+    ) -> ReplaceByFeeDecision {
+        if tx_fee <= prior_tx.tx_fee {
+            return ReplaceByFeeDecision::FeeNotHigher;
+        }
+        let min_fee = self.min_replacement_fee(prior_tx.tx_fee);
+        if tx_fee < min_fee {
+            return ReplaceByFeeDecision::InsufficientFeeBump { min_fee };
+        }
+        if let Some(max_replacements) = self.rbf_max_replacements {
+            if num_replacements >= max_replacements {
+                return ReplaceByFeeDecision::TooManyReplacements { max_replacements };
+            }
+        }
+        ReplaceByFeeDecision::Accept
+    }
+
+    /// How many times has the transaction at this origin nonce been replaced by fee?
+    pub fn get_num_replacements(
+        conn: &DBConn,
+        origin_address: &StacksAddress,
+        origin_nonce: u64,
+    ) -> Result<u64, db_error> {
+        let sql = "SELECT count FROM rbf_replacements WHERE address = ?1 AND nonce = ?2";
+        let args = params![origin_address.to_string(), u64_to_sql(origin_nonce)?];
+        let count: Option<i64> = query_row(conn, sql, args)?;
+        Ok(count.map(|count| count as u64).unwrap_or(0))
+    }
+
+    /// Record that the transaction at this origin nonce was replaced by fee
+    pub fn record_replacement(
+        tx: &DBTx,
+        origin_address: &StacksAddress,
+        origin_nonce: u64,
+    ) -> Result<(), db_error> {
+        let sql = "INSERT INTO rbf_replacements (address, nonce, count) VALUES (?1, ?2, 1)
+                   ON CONFLICT(address, nonce) DO UPDATE SET count = count + 1";
+        let args = params![origin_address.to_string(), u64_to_sql(origin_nonce)?];
+        tx.execute(sql, args)?;
+        Ok(())
+    }
+
+    /// Forget the replacement counts of origin nonces that no longer have a transaction in the
+    /// mempool
+    pub fn garbage_collect_replacements(tx: &DBTx) -> Result<(), db_error> {
+        let sql = "DELETE FROM rbf_replacements WHERE NOT EXISTS
+                   (SELECT 1 FROM mempool WHERE origin_address = address AND origin_nonce = nonce)";
+        tx.execute(sql, NO_PARAMS)?;
+        Ok(())
+    }
+}
+
+/// Query for the mempool walk's fee-rate-ordered candidates, ranked by package fee rate.
+///
+/// Packages are runs of consecutive origin nonces (found by grouping on `origin_nonce -
+/// ROW_NUMBER()`), with one transaction per origin nonce: if several share a nonce, only the one
+/// with the best fee rate counts towards its package.  Each transaction's `ancestor_rate` is the
+/// aggregate fee rate of itself and every lower-nonce transaction in its package, where a
+/// transaction's cost is `tx_fee / fee_rate`.  It is NULL if any of them lacks a positive fee
+/// rate.  A transaction's package fee rate is then the highest `ancestor_rate` of itself or any
+/// of its descendants -- i.e. the best rate a miner gets by mining it along with some of its
+/// descendants.
+pub const MEMPOOL_PACKAGE_FEE_RATE_QUERY: &str = "
+    WITH nonces AS (
+        SELECT origin_address, origin_nonce, tx_fee,
+               CASE WHEN fee_rate > 0 THEN fee_rate END AS fee_rate,
+               ROW_NUMBER() OVER (
+                   PARTITION BY origin_address, origin_nonce ORDER BY fee_rate DESC
+               ) AS rank
+        FROM mempool
+    ),
+    chains AS (
+        SELECT origin_address, origin_nonce, tx_fee, fee_rate,
+               origin_nonce - ROW_NUMBER() OVER (PARTITION BY origin_address ORDER BY origin_nonce) AS chain_id
+        FROM nonces
+        WHERE rank = 1
+    ),
+    ancestors AS (
+        SELECT *,
+               CASE WHEN COUNT(fee_rate) OVER w = COUNT(*) OVER w
+                    THEN SUM(tx_fee) OVER w * 1.0 / SUM(tx_fee / fee_rate) OVER w
+               END AS ancestor_rate
+        FROM chains
+        WINDOW w AS (PARTITION BY origin_address, chain_id ORDER BY origin_nonce)
+    ),
+    packages AS (
+        SELECT origin_address, origin_nonce,
+               MAX(ancestor_rate) OVER (
+                   PARTITION BY origin_address, chain_id ORDER BY origin_nonce
+                   ROWS BETWEEN CURRENT ROW AND UNBOUNDED FOLLOWING
+               ) AS package_fee_rate
+        FROM ancestors
+    )
+    SELECT m.txid, m.origin_nonce, m.origin_address, m.sponsor_nonce, m.sponsor_address, m.fee_rate
+    FROM mempool m
+    JOIN packages p ON p.origin_address = m.origin_address AND p.origin_nonce = m.origin_nonce
+    WHERE m.fee_rate IS NOT NULL
+    ORDER BY MAX(m.fee_rate, COALESCE(p.package_fee_rate, m.fee_rate)) DESC
+    ";
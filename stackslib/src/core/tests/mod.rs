@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 use std::{cmp, io};
@@ -42,6 +43,7 @@ use stacks_common::util::{get_epoch_time_ms, get_epoch_time_secs, log, sleep_ms}
 use super::MemPoolDB;
 use crate::burnchains::{Address, Txid};
 use crate::chainstate::burn::ConsensusHash;
+use crate::chainstate::nakamoto::NakamotoBlock;
 use crate::chainstate::stacks::db::blocks::MemPoolRejection;
 use crate::chainstate::stacks::db::test::{
     chainstate_path, instantiate_chainstate, instantiate_chainstate_with_balances,
@@ -49,26 +51,29 @@ use crate::chainstate::stacks::db::test::{
 use crate::chainstate::stacks::db::{StacksChainState, StacksHeaderInfo};
 use crate::chainstate::stacks::events::StacksTransactionReceipt;
 use crate::chainstate::stacks::index::MarfTrieId;
-use crate::chainstate::stacks::miner::TransactionResult;
+use crate::chainstate::stacks::miner::{TransactionEvent, TransactionResult};
 use crate::chainstate::stacks::test::codec_all_transactions;
 use crate::chainstate::stacks::{
     CoinbasePayload, Error as ChainstateError, SinglesigHashMode, SinglesigSpendingCondition,
-    StacksBlockHeader, StacksMicroblockHeader, StacksPrivateKey, StacksPublicKey,
-    StacksTransaction, StacksTransactionSigner, TokenTransferMemo, TransactionAnchorMode,
-    TransactionAuth, TransactionContractCall, TransactionPayload, TransactionPostConditionMode,
-    TransactionPublicKeyEncoding, TransactionSmartContract, TransactionSpendingCondition,
-    TransactionVersion, C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
+    StacksBlock, StacksBlockHeader, StacksMicroblock, StacksMicroblockHeader, StacksPrivateKey,
+    StacksPublicKey, StacksTransaction, StacksTransactionSigner, TokenTransferMemo,
+    TransactionAnchorMode, TransactionAuth, TransactionContractCall, TransactionPayload,
+    TransactionPostConditionMode, TransactionPublicKeyEncoding, TransactionSmartContract,
+    TransactionSpendingCondition, TransactionVersion, C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
     C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
 };
 use crate::core::mempool::{
-    db_get_all_nonces, MemPoolSyncData, MemPoolWalkSettings, MemPoolWalkTxTypes, TxTag,
-    BLOOM_COUNTER_DEPTH, BLOOM_COUNTER_ERROR_RATE, MAX_BLOOM_COUNTER_TXS,
+    db_get_all_nonces, MemPoolDropReason, MemPoolEventDispatcher, MemPoolSyncData, MemPoolTx,
+    MemPoolWalkSettings, MemPoolWalkTxTypes, ProposalCallbackReceiver, TxTag, BLOOM_COUNTER_DEPTH,
+    BLOOM_COUNTER_ERROR_RATE, MAX_BLOOM_COUNTER_TXS,
 };
+use crate::core::mempool_policy::MemPoolPolicy;
 use crate::core::{FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH};
 use crate::net::Error as NetError;
 use crate::util_lib::bloom::test::setup_bloom_counter;
 use crate::util_lib::bloom::*;
 use crate::util_lib::db::{tx_begin_immediate, DBConn, FromRow};
+use crate::util_lib::sketch::SetSketch;
 use crate::util_lib::strings::StacksString;
 
 const FOO_CONTRACT: &str = "(define-public (foo) (ok 1))
@@ -2101,6 +2106,9 @@ fn test_make_mempool_sync_data() {
                         assert!(recent_set.contains(tag));
                     }
                 }
+                MemPoolSyncData::Sketch(..) => {
+                    panic!("make_mempool_sync_data() should never make a sketch");
+                }
             }
 
             let mut nonrecent_fp_rate = 0.0f64;
@@ -2409,6 +2417,135 @@ fn test_find_next_missing_transactions() {
     assert!(next_page_opt.is_none());
 }
 
+#[test]
+fn test_find_next_reconciled_transactions() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, function_name!());
+    let chainstate_path = chainstate_path(function_name!());
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    let addr = StacksAddress::new(1, Hash160([0xff; 20])).unwrap();
+
+    let block_height = 10;
+    let mut txids = vec![];
+
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    for i in 0..200 {
+        let pk = StacksPrivateKey::random();
+        let mut tx = StacksTransaction {
+            version: TransactionVersion::Testnet,
+            chain_id: 0x80000000,
+            auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
+            anchor_mode: TransactionAnchorMode::Any,
+            post_condition_mode: TransactionPostConditionMode::Allow,
+            post_conditions: vec![],
+            payload: TransactionPayload::TokenTransfer(
+                addr.to_account_principal(),
+                123,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        };
+        tx.set_tx_fee(1000);
+        tx.set_origin_nonce(0);
+
+        let txid = tx.txid();
+        let tx_bytes = tx.serialize_to_vec();
+        let origin_addr = tx.origin_address();
+        let origin_nonce = tx.get_origin_nonce();
+        let sponsor_addr = tx.sponsor_address().unwrap_or(origin_addr.clone());
+        let sponsor_nonce = tx.get_sponsor_nonce().unwrap_or(origin_nonce);
+        let tx_fee = tx.get_tx_fee();
+
+        // should succeed
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1 + (block_height as u8); 20]),
+            &BlockHeaderHash([0x2 + (block_height as u8); 32]),
+            false, // don't resolve the above chain tip since it doesn't exist
+            txid.clone(),
+            tx_bytes,
+            tx_fee,
+            block_height,
+            &origin_addr,
+            origin_nonce,
+            &sponsor_addr,
+            sponsor_nonce,
+            None,
+        )
+        .unwrap();
+
+        eprintln!("Added {} {}", i, &txid);
+        txids.push(txid);
+    }
+    mempool_tx.commit().unwrap();
+
+    // a requester with an identical mempool is missing nothing
+    let sync_data = mempool.make_mempool_sketch_sync_data().unwrap();
+    let (txs, _, _) = mempool
+        .find_next_missing_transactions(&sync_data, block_height, &Txid([0u8; 32]), 1000, 1000)
+        .unwrap();
+    assert!(txs.is_empty());
+
+    // a requester that has all but 20 of our transactions, plus 20 of its own, gets exactly the
+    // 20 it is missing
+    let seed = [0x33; 32];
+    let mut sketch = SetSketch::new(150);
+    for txid in txids[20..].iter() {
+        sketch.insert(TxTag::from(&seed, txid).to_sketch_key());
+    }
+    for i in 0..20u8 {
+        sketch.insert(TxTag::from(&seed, &Txid([i; 32])).to_sketch_key());
+    }
+
+    let missing_txids = MemPoolDB::reconcile_mempool_sketch(mempool.conn(), &seed, &sketch)
+        .unwrap()
+        .unwrap();
+    let expected_txids: HashSet<_> = txids[0..20].iter().cloned().collect();
+    assert_eq!(missing_txids, expected_txids);
+
+    // paginated access works too
+    let mut last_txid = Txid([0u8; 32]);
+    let page_size = 16;
+    let mut all_txids = HashSet::new();
+    loop {
+        let (txs, next_page_opt, num_visited) = mempool
+            .find_next_missing_transactions(
+                &MemPoolSyncData::Sketch(seed.clone(), sketch.clone()),
+                block_height,
+                &last_txid,
+                1000,
+                page_size,
+            )
+            .unwrap();
+        assert!(num_visited <= page_size);
+        for tx in txs.into_iter() {
+            assert!(all_txids.insert(tx.txid()));
+        }
+        let Some(next_page) = next_page_opt else {
+            break;
+        };
+        last_txid = next_page;
+    }
+    assert_eq!(all_txids, expected_txids);
+
+    // a requester whose mempool differs too much cannot be reconciled
+    let empty_sketch = SetSketch::new(30);
+    assert!(
+        MemPoolDB::reconcile_mempool_sketch(mempool.conn(), &seed, &empty_sketch)
+            .unwrap()
+            .is_none()
+    );
+    assert!(mempool
+        .find_next_missing_transactions(
+            &MemPoolSyncData::Sketch(seed, empty_sketch),
+            block_height,
+            &Txid([0u8; 32]),
+            1000,
+            1000,
+        )
+        .is_err());
+}
+
 #[test]
 fn test_drop_and_blacklist_txs_by_time() {
     let mut chainstate = instantiate_chainstate(false, 0x80000000, function_name!());
@@ -2777,3 +2914,320 @@ fn test_filter_txs_by_type() {
         },
     );
 }
+
+/// Records the mempool drop events it receives
+#[derive(Default)]
+struct DropRecorder {
+    drops: RefCell<Vec<(Vec<Txid>, Option<Txid>, String)>>,
+}
+
+impl MemPoolEventDispatcher for DropRecorder {
+    fn get_proposal_callback_receiver(&self) -> Option<Box<dyn ProposalCallbackReceiver>> {
+        None
+    }
+
+    fn mempool_txs_dropped(
+        &self,
+        txids: Vec<Txid>,
+        new_txid: Option<Txid>,
+        reason: MemPoolDropReason,
+    ) {
+        self.drops
+            .borrow_mut()
+            .push((txids, new_txid, reason.to_string()));
+    }
+
+    fn mined_block_event(
+        &self,
+        _target_burn_height: u64,
+        _block: &StacksBlock,
+        _block_size_bytes: u64,
+        _consumed: &ExecutionCost,
+        _confirmed_microblock_cost: &ExecutionCost,
+        _tx_results: Vec<TransactionEvent>,
+    ) {
+    }
+
+    fn mined_microblock_event(
+        &self,
+        _microblock: &StacksMicroblock,
+        _tx_results: Vec<TransactionEvent>,
+        _anchor_block_consensus_hash: ConsensusHash,
+        _anchor_block: BlockHeaderHash,
+    ) {
+    }
+
+    fn mined_nakamoto_block_event(
+        &self,
+        _target_burn_height: u64,
+        _block: &NakamotoBlock,
+        _block_size_bytes: u64,
+        _consumed: &ExecutionCost,
+        _tx_results: Vec<TransactionEvent>,
+    ) {
+    }
+}
+
+/// Make a token transfer with the given signer, nonce, and fee
+fn make_policy_test_tx(signer: u8, nonce: u64, tx_fee: u64) -> StacksTransaction {
+    let spending_condition = TransactionSpendingCondition::Singlesig(SinglesigSpendingCondition {
+        signer: Hash160([signer; 20]),
+        hash_mode: SinglesigHashMode::P2PKH,
+        key_encoding: TransactionPublicKeyEncoding::Uncompressed,
+        nonce,
+        tx_fee,
+        signature: MessageSignature::from_raw(&[0xff; 65]),
+    });
+    let recipient = StacksAddress::new(1, Hash160([0xff; 20])).unwrap();
+    StacksTransaction {
+        version: TransactionVersion::Testnet,
+        chain_id: 0x80000000,
+        auth: TransactionAuth::Standard(spending_condition),
+        anchor_mode: TransactionAnchorMode::Any,
+        post_condition_mode: TransactionPostConditionMode::Allow,
+        post_conditions: Vec::new(),
+        payload: TransactionPayload::TokenTransfer(
+            recipient.into(),
+            123,
+            TokenTransferMemo([0u8; 34]),
+        ),
+    }
+}
+
+#[test]
+fn mempool_rbf_policy() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, function_name!());
+    let chainstate_path = chainstate_path(function_name!());
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    mempool.policy = MemPoolPolicy {
+        rbf_min_fee_bump_percent: 10,
+        rbf_max_replacements: Some(2),
+        package_fee_rates: false,
+    };
+
+    let recorder = DropRecorder::default();
+    let origin_address = StacksAddress::new(22, Hash160([0x11; 20])).unwrap();
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+
+    let mut try_add = |mempool_tx: &mut MemPoolTx, tx_fee: u64| {
+        let tx = make_policy_test_tx(0x11, 0, tx_fee);
+        let txid = tx.txid();
+        let res = MemPoolDB::try_add_tx(
+            mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            false, // don't resolve the above chain tip since it doesn't exist
+            txid,
+            tx.serialize_to_vec(),
+            tx_fee,
+            100,
+            &origin_address,
+            0,
+            &origin_address,
+            0,
+            Some(&recorder),
+        );
+        (txid, res)
+    };
+
+    let (first_txid, res) = try_add(&mut mempool_tx, 1000);
+    res.unwrap();
+
+    // not enough of a bump
+    let (txid, res) = try_add(&mut mempool_tx, 1050);
+    match res.unwrap_err() {
+        MemPoolRejection::InsufficientReplacementFee { expected, actual } => {
+            assert_eq!(expected, 1100);
+            assert_eq!(actual, 1050);
+        }
+        e => panic!("Unexpected rejection: {e:?}"),
+    }
+    assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+    assert_eq!(
+        recorder.drops.borrow_mut().pop().unwrap(),
+        (vec![txid], None, "ReplaceByFeeInsufficientBump".to_string())
+    );
+
+    // two replacements are allowed
+    let (second_txid, res) = try_add(&mut mempool_tx, 1100);
+    res.unwrap();
+    assert_eq!(
+        recorder.drops.borrow_mut().pop().unwrap(),
+        (
+            vec![first_txid],
+            Some(second_txid),
+            "ReplaceByFee".to_string()
+        )
+    );
+    let (third_txid, res) = try_add(&mut mempool_tx, 1210);
+    res.unwrap();
+    assert_eq!(
+        recorder.drops.borrow_mut().pop().unwrap(),
+        (
+            vec![second_txid],
+            Some(third_txid),
+            "ReplaceByFee".to_string()
+        )
+    );
+    assert_eq!(
+        MemPoolPolicy::get_num_replacements(&mempool_tx, &origin_address, 0).unwrap(),
+        2
+    );
+
+    // but not a third
+    let (txid, res) = try_add(&mut mempool_tx, 2000);
+    match res.unwrap_err() {
+        MemPoolRejection::TooManyReplacements { max_replacements } => {
+            assert_eq!(max_replacements, 2);
+        }
+        e => panic!("Unexpected rejection: {e:?}"),
+    }
+    assert_eq!(
+        recorder.drops.borrow_mut().pop().unwrap(),
+        (
+            vec![txid],
+            None,
+            "ReplaceByFeeTooManyReplacements".to_string()
+        )
+    );
+
+    // a replacement without a higher fee is rejected as before, without a drop event
+    let (_, res) = try_add(&mut mempool_tx, 1210);
+    match res.unwrap_err() {
+        MemPoolRejection::ConflictingNonceInMempool => {}
+        e => panic!("Unexpected rejection: {e:?}"),
+    }
+    assert!(recorder.drops.borrow().is_empty());
+    assert!(MemPoolDB::db_has_tx(&mempool_tx, &third_txid).unwrap());
+
+    // replacement counts are forgotten once the nonce's transaction is garbage-collected
+    MemPoolDB::garbage_collect_by_coinbase_height(&mempool_tx, 101, None).unwrap();
+    assert_eq!(
+        MemPoolPolicy::get_num_replacements(&mempool_tx, &origin_address, 0).unwrap(),
+        0
+    );
+    mempool_tx.commit().unwrap();
+}
+
+#[test]
+/// This test verifies that with package fee rates, a high-fee transaction pays for its
+/// lower-nonce, low-fee ancestor (child-pays-for-parent).
+fn test_iterate_candidates_package_fee_rates() {
+    let mut chainstate =
+        instantiate_chainstate_with_balances(false, 0x80000000, function_name!(), vec![]);
+    let chainstate_path = chainstate_path(function_name!());
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+    let b_2 = make_block(&mut chainstate, ConsensusHash([0x2; 20]), &b_1, 2, 2);
+
+    let mut mempool_settings = MemPoolWalkSettings::default();
+    mempool_settings.consider_no_estimate_tx_prob = 0;
+    let mut tx_events = Vec::new();
+
+    // (signer, nonce, fee, fee rate).  Each transaction's cost is fee / fee rate = 100.
+    // A's package of both transactions has a fee rate of 10100 / 200 = 50.5, which beats B.
+    let tx_specs = [
+        (0xaa, 0, 100, 1.0),
+        (0xaa, 1, 10_000, 100.0),
+        (0xbb, 0, 1_000, 10.0),
+    ];
+    let mut txids = vec![];
+    for (signer, nonce, tx_fee, fee_rate) in tx_specs {
+        let tx = make_policy_test_tx(signer, nonce, tx_fee);
+        let address = StacksAddress::new(26, Hash160([signer; 20])).unwrap();
+        let txid = tx.txid();
+
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &b_1.0,
+            &b_1.1,
+            true,
+            txid,
+            tx.serialize_to_vec(),
+            tx_fee,
+            100,
+            &address,
+            nonce,
+            &address,
+            nonce,
+            None,
+        )
+        .unwrap();
+        mempool_tx
+            .execute(
+                "UPDATE mempool SET fee_rate = ? WHERE txid = ?",
+                params![Some(fee_rate), txid],
+            )
+            .unwrap();
+        mempool_tx.commit().unwrap();
+        txids.push(txid);
+    }
+
+    let mut walk = |mempool: &mut MemPoolDB| {
+        let _ = mempool.reset_nonce_cache();
+        let mut considered = vec![];
+        chainstate.with_read_only_clarity_tx(
+            &TEST_BURN_STATE_DB,
+            &StacksBlockHeader::make_index_block_hash(&b_2.0, &b_2.1),
+            |clarity_conn| {
+                mempool
+                    .iterate_candidates::<_, ChainstateError, _>(
+                        clarity_conn,
+                        &mut tx_events,
+                        mempool_settings.clone(),
+                        |_, available_tx, _| {
+                            considered.push(available_tx.tx.metadata.txid);
+                            Ok(Some(
+                                // Generate any success result
+                                TransactionResult::success(
+                                    &available_tx.tx.tx,
+                                    available_tx.tx.metadata.tx_fee,
+                                    StacksTransactionReceipt::from_stx_transfer(
+                                        available_tx.tx.tx.clone(),
+                                        vec![],
+                                        Value::okay(Value::Bool(true)).unwrap(),
+                                        ExecutionCost::ZERO,
+                                    ),
+                                )
+                                .convert_to_event(),
+                            ))
+                        },
+                    )
+                    .unwrap();
+            },
+        );
+        considered
+    };
+
+    // by each transaction's own fee rate, B goes first
+    let considered = walk(&mut mempool);
+    assert_eq!(considered, vec![txids[2], txids[0], txids[1]]);
+
+    // by package fee rate, A's package goes first
+    mempool.policy.package_fee_rates = true;
+    let considered = walk(&mut mempool);
+    assert_eq!(considered, vec![txids[0], txids[1], txids[2]]);
+
+    // a zero fee rate doesn't make a transaction's cost vanish from its package
+    mempool
+        .db
+        .execute(
+            "UPDATE mempool SET fee_rate = 0 WHERE txid = ?",
+            params![txids[0]],
+        )
+        .unwrap();
+    let considered = walk(&mut mempool);
+    assert_eq!(considered[0], txids[2]);
+}
@@ -1613,6 +1613,16 @@ check if the associated microblocks can be downloaded
         process::exit(0);
     }
 
+    if argv[1] == "export-marf-snapshot" {
+        cli::command_export_marf_snapshot(&argv[1..]);
+        process::exit(0);
+    }
+
+    if argv[1] == "import-marf-snapshot" {
+        cli::command_import_marf_snapshot(&argv[1..]);
+        process::exit(0);
+    }
+
     if argv[1] == "dump-consts" {
         dump_consts();
     }
@@ -21,6 +21,7 @@ use std::sync::Mutex;
 use std::{fmt, fs};
 
 use clarity::vm::costs::ExecutionCost;
+use clarity::vm::database::contract_cache::ContractCacheStats;
 use lazy_static::lazy_static;
 use rusqlite::{OpenFlags, OptionalExtension};
 use stacks_common::types::sqlite::NO_PARAMS;
@@ -135,6 +136,21 @@ pub fn set_last_block_transaction_count(transactions_in_block: u64) {
         .set(i64::try_from(transactions_in_block).unwrap_or(i64::MAX));
 }
 
+/// Log the Clarity contract cache's hit rate and memory use.
+#[allow(unused_variables)]
+pub fn set_contract_cache_stats(stats: &ContractCacheStats) {
+    #[cfg(feature = "monitoring_prom")]
+    {
+        prometheus::CONTRACT_CACHE_HITS.set(i64::try_from(stats.hits).unwrap_or(i64::MAX));
+        prometheus::CONTRACT_CACHE_MISSES.set(i64::try_from(stats.misses).unwrap_or(i64::MAX));
+        prometheus::CONTRACT_CACHE_EVICTIONS
+            .set(i64::try_from(stats.evictions).unwrap_or(i64::MAX));
+        prometheus::CONTRACT_CACHE_ENTRIES.set(i64::try_from(stats.entries).unwrap_or(i64::MAX));
+        prometheus::CONTRACT_CACHE_SIZE.set(i64::try_from(stats.size).unwrap_or(i64::MAX));
+        prometheus::CONTRACT_CACHE_HIT_RATE.set(stats.hit_rate());
+    }
+}
+
 /// Log `execution_cost` as a ratio of `block_limit`.
 #[allow(unused_variables)]
 pub fn set_last_mined_execution_cost_observed(
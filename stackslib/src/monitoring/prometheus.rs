@@ -217,6 +217,36 @@ lazy_static! {
         "Total count of processed contract calls"
     )).unwrap();
 
+    pub static ref CONTRACT_CACHE_HITS: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_contract_cache_hits",
+        "Total count of contract loads served from the contract cache"
+    )).unwrap();
+
+    pub static ref CONTRACT_CACHE_MISSES: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_contract_cache_misses",
+        "Total count of cacheable contract loads that missed the contract cache"
+    )).unwrap();
+
+    pub static ref CONTRACT_CACHE_EVICTIONS: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_contract_cache_evictions",
+        "Total count of contracts evicted from the contract cache"
+    )).unwrap();
+
+    pub static ref CONTRACT_CACHE_ENTRIES: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_contract_cache_entries",
+        "Number of contracts in the contract cache"
+    )).unwrap();
+
+    pub static ref CONTRACT_CACHE_SIZE: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_contract_cache_size_bytes",
+        "Total serialized size of the contracts in the contract cache"
+    )).unwrap();
+
+    pub static ref CONTRACT_CACHE_HIT_RATE: Gauge = register_gauge!(opts!(
+        "stacks_node_contract_cache_hit_rate",
+        "Fraction of cacheable contract loads served from the contract cache"
+    )).unwrap();
+
     pub static ref MEMPOOL_OUTSTANDING_TXS: IntGauge = register_int_gauge!(opts!(
         "stacks_node_mempool_outstanding_txs",
         "Number of still-unprocessed transactions received by this node since it started",
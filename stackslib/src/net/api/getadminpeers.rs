@@ -0,0 +1,233 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use regex::{Captures, Regex};
+use stacks_common::types::net::{PeerAddress, PeerHost};
+use stacks_common::util::get_epoch_time_secs;
+use stacks_common::util::hash::Hash160;
+
+use super::postadmincidrs::cidr_to_string;
+use crate::net::api::check_admin_auth;
+use crate::net::db::PeerDB;
+use crate::net::http::{
+    parse_json, Error, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble,
+};
+use crate::net::httpcore::{
+    HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+};
+use crate::net::p2p::PeerNetwork;
+use crate::net::{Error as NetError, StacksNodeState};
+
+pub static PATH: &str = "/v3/admin/peers";
+
+/// A peer we have a connection to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminConnectedPeer {
+    pub event_id: usize,
+    /// Address of the connection
+    pub ip: PeerAddress,
+    pub port: u16,
+    /// Address the peer gave in its handshake, if it has completed one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handshake_ip: Option<PeerAddress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handshake_port: Option<u16>,
+    pub public_key_hash: Option<Hash160>,
+    pub outbound: bool,
+    pub authenticated: bool,
+    pub encrypted: bool,
+    pub age: u64,
+}
+
+/// A peer that is denied in the peer DB
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminDeniedPeer {
+    pub ip: PeerAddress,
+    pub port: u16,
+    pub public_key_hash: Hash160,
+    /// When the ban expires, in seconds since the epoch
+    pub denied_until: u64,
+}
+
+/// Struct given back from a call to `GET /v3/admin/peers`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminPeersInfo {
+    pub connected: Vec<AdminConnectedPeer>,
+    pub denied: Vec<AdminDeniedPeer>,
+    pub deny_cidrs: Vec<String>,
+    pub allow_cidrs: Vec<String>,
+}
+
+impl AdminPeersInfo {
+    /// Load connection and deny/allow information from the peer network
+    pub fn from_p2p(network: &PeerNetwork) -> Result<AdminPeersInfo, NetError> {
+        let network_id = network.get_local_peer().network_id;
+        let peerdb_conn = network.peerdb_conn();
+
+        let mut connected: Vec<_> = network
+            .iter_peer_convos()
+            .map(|(event_id, convo)| {
+                let nk = convo.to_neighbor_key();
+                let handshake_nk = convo
+                    .is_authenticated()
+                    .then(|| convo.to_handshake_neighbor_key());
+                AdminConnectedPeer {
+                    event_id: *event_id,
+                    ip: nk.addrbytes,
+                    port: nk.port,
+                    handshake_ip: handshake_nk.as_ref().map(|nk| nk.addrbytes),
+                    handshake_port: handshake_nk.as_ref().map(|nk| nk.port),
+                    public_key_hash: convo.get_public_key_hash(),
+                    outbound: convo.is_outbound(),
+                    authenticated: convo.is_authenticated(),
+                    encrypted: convo.connection.is_transport_encrypted(),
+                    age: convo.age(),
+                }
+            })
+            .collect();
+        connected.sort_by_key(|peer| peer.event_id);
+
+        let denied = PeerDB::get_denied_peers(peerdb_conn, network_id, get_epoch_time_secs())?
+            .into_iter()
+            .map(|neighbor| AdminDeniedPeer {
+                ip: neighbor.addr.addrbytes,
+                port: neighbor.addr.port,
+                public_key_hash: Hash160::from_node_public_key(&neighbor.public_key),
+                denied_until: u64::try_from(neighbor.denied).unwrap_or(0),
+            })
+            .collect();
+
+        let deny_cidrs = PeerDB::get_denied_cidrs(peerdb_conn)?
+            .iter()
+            .map(|(prefix, mask)| cidr_to_string(prefix, *mask))
+            .collect();
+        let allow_cidrs = PeerDB::get_allowed_cidrs(peerdb_conn)?
+            .iter()
+            .map(|(prefix, mask)| cidr_to_string(prefix, *mask))
+            .collect();
+
+        Ok(AdminPeersInfo {
+            connected,
+            denied,
+            deny_cidrs,
+            allow_cidrs,
+        })
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RPCGetAdminPeersRequestHandler {
+    pub auth: Option<String>,
+}
+
+impl RPCGetAdminPeersRequestHandler {
+    pub fn new(auth: Option<String>) -> Self {
+        Self { auth }
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCGetAdminPeersRequestHandler {
+    fn verb(&self) -> &'static str {
+        "GET"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(&format!("^{PATH}$")).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        PATH
+    }
+
+    /// Try to decode this request.
+    /// There's nothing to load here, so just make sure the request is well-formed.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        _body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        check_admin_auth(self.auth.as_deref(), preamble)?;
+        if preamble.get_content_length() != 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected 0-length body for admin peers".to_string(),
+            ));
+        }
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+}
+
+impl RPCRequestHandler for RPCGetAdminPeersRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {}
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        _contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let peers_info =
+            node.with_node_state(|network, _sortdb, _chainstate, _mempool, _rpc_args| {
+                AdminPeersInfo::from_p2p(network)
+            })?;
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&peers_info)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCGetAdminPeersRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let peers_info: AdminPeersInfo = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(peers_info)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new request for the node's peers and deny/allow rules
+    pub fn new_get_admin_peers(host: PeerHost, auth: &str) -> StacksHttpRequest {
+        let mut request = StacksHttpRequest::new_for_peer(
+            host,
+            "GET".into(),
+            PATH.into(),
+            HttpRequestContents::new(),
+        )
+        .expect("FATAL: failed to construct request from infallible data");
+        request.add_header("authorization".into(), auth.into());
+        request
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_admin_peers(self) -> Result<AdminPeersInfo, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let response_json: serde_json::Value = contents.try_into()?;
+        let peers_info: AdminPeersInfo = serde_json::from_value(response_json)
+            .map_err(|_e| Error::DecodeError("Failed to decode JSON".to_string()))?;
+        Ok(peers_info)
+    }
+}
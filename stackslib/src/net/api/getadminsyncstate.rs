@@ -0,0 +1,290 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use regex::{Captures, Regex};
+use stacks_common::types::chainstate::{ConsensusHash, StacksBlockId};
+use stacks_common::types::net::PeerHost;
+
+use crate::net::api::check_admin_auth;
+use crate::net::http::{
+    parse_json, Error, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble,
+};
+use crate::net::httpcore::{
+    HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+};
+use crate::net::p2p::PeerNetwork;
+use crate::net::{Error as NetError, StacksNodeState};
+
+pub static PATH: &str = "/v3/admin/sync_state";
+
+/// Epoch 2.x inventory sync state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminEpoch2InvState {
+    /// How many peers we are tracking inventories for
+    pub num_peers: usize,
+    pub num_inv_syncs: u64,
+    pub last_change_at: u64,
+    pub block_sortition_start: u64,
+}
+
+/// Nakamoto inventory sync state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminNakamotoInvState {
+    /// How many peers we have tenure inventories for
+    pub num_peers: usize,
+}
+
+/// Epoch 2.x block downloader state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminEpoch2DownloaderState {
+    pub state: String,
+    pub block_sortition_height: u64,
+    pub microblock_sortition_height: u64,
+    pub num_blocks_downloaded: u64,
+    pub num_microblocks_downloaded: u64,
+    pub inflight_block_requests: usize,
+    pub inflight_microblock_requests: usize,
+}
+
+/// Nakamoto block downloader state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminNakamotoDownloaderState {
+    pub state: String,
+    pub reward_cycle: u64,
+    pub num_wanted_tenures: usize,
+    pub num_scheduled_tenures: usize,
+    pub num_available_tenures: usize,
+    pub inflight_tenure_downloads: usize,
+    pub unconfirmed_tenure_downloads: usize,
+    pub nakamoto_tip: StacksBlockId,
+}
+
+/// StackerDB replica sync state, for one StackerDB
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminStackerDBSyncState {
+    pub contract_id: String,
+    pub state: String,
+    pub rc_consensus_hash: Option<ConsensusHash>,
+    pub num_slots: usize,
+    pub num_replicas: usize,
+    pub num_connected_replicas: usize,
+    pub total_stored: u64,
+    pub total_pushed: u64,
+    pub rounds: u64,
+    pub last_run_ts: u64,
+}
+
+/// Struct given back from a call to `GET /v3/admin/sync_state`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminSyncStateInfo {
+    pub work_state: String,
+    pub nakamoto_work_state: String,
+    pub num_state_machine_passes: u64,
+    pub num_inv_sync_passes: u64,
+    pub num_downloader_passes: u64,
+    pub epoch2_inv: Option<AdminEpoch2InvState>,
+    pub nakamoto_inv: Option<AdminNakamotoInvState>,
+    pub epoch2_downloader: Option<AdminEpoch2DownloaderState>,
+    pub nakamoto_downloader: Option<AdminNakamotoDownloaderState>,
+    pub stackerdbs: Vec<AdminStackerDBSyncState>,
+}
+
+impl AdminSyncStateInfo {
+    /// Snapshot the state of the peer network's sync state machines
+    pub fn from_p2p(network: &PeerNetwork) -> AdminSyncStateInfo {
+        let epoch2_inv = network
+            .inv_state
+            .as_ref()
+            .map(|inv_state| AdminEpoch2InvState {
+                num_peers: inv_state.block_stats.len(),
+                num_inv_syncs: inv_state.num_inv_syncs,
+                last_change_at: inv_state.last_change_at,
+                block_sortition_start: inv_state.block_sortition_start,
+            });
+
+        let nakamoto_inv =
+            network
+                .inv_state_nakamoto
+                .as_ref()
+                .map(|inv_state| AdminNakamotoInvState {
+                    num_peers: inv_state.inventories.len(),
+                });
+
+        let epoch2_downloader =
+            network
+                .block_downloader
+                .as_ref()
+                .map(|downloader| AdminEpoch2DownloaderState {
+                    state: format!("{:?}", &downloader.state),
+                    block_sortition_height: downloader.block_sortition_height,
+                    microblock_sortition_height: downloader.microblock_sortition_height,
+                    num_blocks_downloaded: downloader.num_blocks_downloaded,
+                    num_microblocks_downloaded: downloader.num_microblocks_downloaded,
+                    inflight_block_requests: downloader.getblock_requests.len(),
+                    inflight_microblock_requests: downloader.getmicroblocks_requests.len(),
+                });
+
+        let nakamoto_downloader = network
+            .block_downloader_nakamoto
+            .as_ref()
+            .map(|downloader| AdminNakamotoDownloaderState {
+                state: downloader.state.to_string(),
+                reward_cycle: downloader.reward_cycle,
+                num_wanted_tenures: downloader.wanted_tenures.len(),
+                num_scheduled_tenures: downloader.tenure_download_schedule.len(),
+                num_available_tenures: downloader.available_tenures.len(),
+                inflight_tenure_downloads: downloader.tenure_downloads.inflight(),
+                unconfirmed_tenure_downloads: downloader.unconfirmed_tenure_downloads.len(),
+                nakamoto_tip: downloader.nakamoto_tip,
+            });
+
+        let mut stackerdbs: Vec<_> = network
+            .stacker_db_syncs
+            .iter()
+            .flat_map(|syncs| syncs.values())
+            .map(|sync| AdminStackerDBSyncState {
+                contract_id: sync.smart_contract_id.to_string(),
+                state: format!("{:?}", &sync.state),
+                rc_consensus_hash: sync.rc_consensus_hash,
+                num_slots: sync.num_slots,
+                num_replicas: sync.replicas.len(),
+                num_connected_replicas: sync.connected_replicas.len(),
+                total_stored: sync.total_stored,
+                total_pushed: sync.total_pushed,
+                rounds: u64::try_from(sync.rounds).unwrap_or(u64::MAX),
+                last_run_ts: sync.last_run_ts,
+            })
+            .collect();
+        stackerdbs.sort_by(|a, b| a.contract_id.cmp(&b.contract_id));
+
+        AdminSyncStateInfo {
+            work_state: format!("{:?}", &network.work_state),
+            nakamoto_work_state: format!("{:?}", &network.nakamoto_work_state),
+            num_state_machine_passes: network.num_state_machine_passes,
+            num_inv_sync_passes: network.num_inv_sync_passes,
+            num_downloader_passes: network.num_downloader_passes,
+            epoch2_inv,
+            nakamoto_inv,
+            epoch2_downloader,
+            nakamoto_downloader,
+            stackerdbs,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RPCGetAdminSyncStateRequestHandler {
+    pub auth: Option<String>,
+}
+
+impl RPCGetAdminSyncStateRequestHandler {
+    pub fn new(auth: Option<String>) -> Self {
+        Self { auth }
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCGetAdminSyncStateRequestHandler {
+    fn verb(&self) -> &'static str {
+        "GET"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(&format!("^{PATH}$")).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        PATH
+    }
+
+    /// Try to decode this request.
+    /// There's nothing to load here, so just make sure the request is well-formed.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        _body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        check_admin_auth(self.auth.as_deref(), preamble)?;
+        if preamble.get_content_length() != 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected 0-length body for admin sync state".to_string(),
+            ));
+        }
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+}
+
+impl RPCRequestHandler for RPCGetAdminSyncStateRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {}
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        _contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let sync_state =
+            node.with_node_state(|network, _sortdb, _chainstate, _mempool, _rpc_args| {
+                AdminSyncStateInfo::from_p2p(network)
+            });
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&sync_state)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCGetAdminSyncStateRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let sync_state: AdminSyncStateInfo = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(sync_state)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new request for the node's sync state machines
+    pub fn new_get_admin_sync_state(host: PeerHost, auth: &str) -> StacksHttpRequest {
+        let mut request = StacksHttpRequest::new_for_peer(
+            host,
+            "GET".into(),
+            PATH.into(),
+            HttpRequestContents::new(),
+        )
+        .expect("FATAL: failed to construct request from infallible data");
+        request.add_header("authorization".into(), auth.into());
+        request
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_admin_sync_state(self) -> Result<AdminSyncStateInfo, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let response_json: serde_json::Value = contents.try_into()?;
+        let sync_state: AdminSyncStateInfo = serde_json::from_value(response_json)
+            .map_err(|_e| Error::DecodeError("Failed to decode JSON".to_string()))?;
+        Ok(sync_state)
+    }
+}
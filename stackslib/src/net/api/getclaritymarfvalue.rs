@@ -14,16 +14,20 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use clarity::vm::clarity::ClarityConnection;
+use clarity::vm::database::SqliteConnection;
 use clarity::vm::representations::CONTRACT_PRINCIPAL_REGEX_STRING;
 use lazy_static::lazy_static;
 use regex::{Captures, Regex};
-use stacks_common::types::chainstate::TrieHash;
+use stacks_common::types::chainstate::{StacksBlockId, TrieHash};
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::to_hex;
 
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::index::marf::MarfConnection;
+use crate::chainstate::stacks::index::Error as MARFError;
 use crate::net::http::{
     parse_json, Error, HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble,
-    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble,
+    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
     request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler,
@@ -40,14 +44,140 @@ pub struct ClarityMarfResponse {
     pub marf_proof: Option<String>,
 }
 
+/// Maximum number of MARF key hashes that can be read in one request.  Each one takes 65 bytes
+/// of the request path, which has to fit in the HTTP preamble.
+pub const MAX_CLARITY_MARF_BATCH_KEYS: usize = 32;
+
+/// Response to a batch read of Clarity state, from `/v2/clarity/marf` or `/v2/map_entry`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClarityMarfBatchResponse {
+    /// The block the values were read at
+    pub index_block_hash: StacksBlockId,
+    /// Hex-serialized value of each requested key, in request order, or None if it has no value
+    pub data: Vec<Option<String>>,
+    /// Hex-encoded `TrieMerkleMultiProof` of every requested key's value or absence
+    #[serde(rename = "proof")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marf_proof: Option<String>,
+}
+
+/// Read the Clarity side-store data of a batch of MARF key hashes as of `tip`, and if
+/// `with_proof` is set, make one multi-proof for all of them.
+pub(crate) fn read_clarity_marf_batch(
+    chainstate: &mut StacksChainState,
+    tip: &StacksBlockId,
+    marf_key_hashes: &[TrieHash],
+    with_proof: bool,
+) -> Result<ClarityMarfBatchResponse, MARFError> {
+    let clarity_instance = match chainstate.unconfirmed_state.as_mut() {
+        Some(unconfirmed_state)
+            if unconfirmed_state.unconfirmed_chain_tip == *tip
+                && unconfirmed_state.is_readable() =>
+        {
+            &mut unconfirmed_state.clarity_inst
+        }
+        _ => &mut chainstate.clarity_state,
+    };
+
+    clarity_instance.with_marf(|marf| {
+        let (values, marf_proof) = if with_proof {
+            let (values, proof) = marf.get_with_multi_proof_from_hashes(tip, marf_key_hashes)?;
+            (values, Some(format!("0x{}", proof.to_hex())))
+        } else {
+            let values = marf_key_hashes
+                .iter()
+                .map(|marf_key_hash| marf.get_from_hash(tip, marf_key_hash))
+                .collect::<Result<Vec<_>, _>>()?;
+            (values, None)
+        };
+
+        let mut data = Vec::with_capacity(values.len());
+        for value in values.into_iter() {
+            let Some(value) = value else {
+                data.push(None);
+                continue;
+            };
+            let value_hex = SqliteConnection::get(marf.sqlite_conn(), &value.to_hex())
+                .map_err(|e| MARFError::CorruptionError(format!("{e:?}")))?
+                .ok_or_else(|| {
+                    MARFError::CorruptionError(format!(
+                        "MARF value {} not found in side storage",
+                        value.to_hex()
+                    ))
+                })?;
+            data.push(Some(format!("0x{value_hex}")));
+        }
+
+        Ok(ClarityMarfBatchResponse {
+            index_block_hash: tip.clone(),
+            data,
+            marf_proof,
+        })
+    })
+}
+
+/// Turn the result of `read_clarity_marf_batch()` into an HTTP response
+pub(crate) fn make_clarity_marf_batch_response(
+    preamble: &HttpRequestPreamble,
+    batch_res: Result<ClarityMarfBatchResponse, MARFError>,
+    node: &mut StacksNodeState,
+) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+    let data_resp = match batch_res {
+        Ok(data_resp) => data_resp,
+        Err(MARFError::NotFoundError) => {
+            return StacksHttpResponse::new_error(
+                preamble,
+                &HttpNotFound::new("Chain tip not found".to_string()),
+            )
+            .try_into_contents()
+            .map_err(NetError::from);
+        }
+        Err(e) => {
+            return StacksHttpResponse::new_error(
+                preamble,
+                &HttpServerError::new(format!("Failed to read Clarity state: {e:?}")),
+            )
+            .try_into_contents()
+            .map_err(NetError::from);
+        }
+    };
+
+    let mut preamble = HttpResponsePreamble::ok_json(preamble);
+    preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+    let body = HttpResponseContents::try_from_json(&data_resp)?;
+    Ok((preamble, body))
+}
+
+/// Decode either a single or a batch response body.  Batch responses have a list of `data`.
+pub(crate) fn parse_json_maybe_batch<T>(
+    preamble: &HttpResponsePreamble,
+    body: &[u8],
+) -> Result<HttpResponsePayload, Error>
+where
+    T: serde::de::DeserializeOwned + serde::Serialize,
+{
+    let json: serde_json::Value = parse_json(preamble, body)?;
+    if json.get("data").is_some_and(|data| data.is_array()) {
+        let batch: ClarityMarfBatchResponse = serde_json::from_value(json)?;
+        Ok(HttpResponsePayload::try_from_json(batch)?)
+    } else {
+        let single: T = serde_json::from_value(json)?;
+        Ok(HttpResponsePayload::try_from_json(single)?)
+    }
+}
+
 #[derive(Clone)]
 pub struct RPCGetClarityMarfRequestHandler {
     pub marf_key_hash: Option<TrieHash>,
+    /// Set instead of `marf_key_hash` if more than one key is requested
+    pub marf_key_hashes: Option<Vec<TrieHash>>,
 }
 impl RPCGetClarityMarfRequestHandler {
     pub fn new() -> Self {
         Self {
             marf_key_hash: None,
+            marf_key_hashes: None,
         }
     }
 }
@@ -59,7 +189,7 @@ impl HttpRequest for RPCGetClarityMarfRequestHandler {
     }
 
     fn path_regex(&self) -> Regex {
-        Regex::new(r#"^/v2/clarity/marf/(?P<marf_key_hash>[0-9a-f]{64})$"#).unwrap()
+        Regex::new(r#"^/v2/clarity/marf/(?P<marf_key_hash>[0-9a-f]{64}(,[0-9a-f]{64})*)$"#).unwrap()
     }
 
     fn metrics_identifier(&self) -> &str {
@@ -68,6 +198,7 @@ impl HttpRequest for RPCGetClarityMarfRequestHandler {
 
     /// Try to decode this request.
     /// There's nothing to load here, so just make sure the request is well-formed.
+    /// The path holds one MARF key hash, or a comma-separated batch of them.
     fn try_parse_request(
         &mut self,
         preamble: &HttpRequestPreamble,
@@ -81,14 +212,31 @@ impl HttpRequest for RPCGetClarityMarfRequestHandler {
             ));
         }
 
-        let marf_key = if let Some(key_str) = captures.name("marf_key_hash") {
-            TrieHash::from_hex(key_str.as_str())
-                .map_err(|e| Error::Http(400, format!("Invalid hash string: {e:?}")))?
+        let mut marf_keys = if let Some(keys_str) = captures.name("marf_key_hash") {
+            keys_str
+                .as_str()
+                .split(',')
+                .map(|key_str| {
+                    TrieHash::from_hex(key_str)
+                        .map_err(|e| Error::Http(400, format!("Invalid hash string: {e:?}")))
+                })
+                .collect::<Result<Vec<_>, _>>()?
         } else {
             return Err(Error::Http(404, "Missing `marf_key_hash`".to_string()));
         };
 
-        self.marf_key_hash = Some(marf_key);
+        if marf_keys.len() > MAX_CLARITY_MARF_BATCH_KEYS {
+            return Err(Error::Http(
+                400,
+                format!("At most {MAX_CLARITY_MARF_BATCH_KEYS} MARF key hashes can be read"),
+            ));
+        }
+
+        if marf_keys.len() == 1 {
+            self.marf_key_hash = marf_keys.pop();
+        } else {
+            self.marf_key_hashes = Some(marf_keys);
+        }
 
         let contents = HttpRequestContents::new().query_string(query);
         Ok(contents)
@@ -100,6 +248,7 @@ impl RPCRequestHandler for RPCGetClarityMarfRequestHandler {
     /// Reset internal state
     fn restart(&mut self) {
         self.marf_key_hash = None;
+        self.marf_key_hashes = None;
     }
 
     /// Make the response
@@ -109,10 +258,11 @@ impl RPCRequestHandler for RPCGetClarityMarfRequestHandler {
         contents: HttpRequestContents,
         node: &mut StacksNodeState,
     ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
-        let marf_key_hash = self
-            .marf_key_hash
-            .take()
-            .ok_or(NetError::SendError("`marf_key_hash` not set".to_string()))?;
+        let marf_key_hashes = self.marf_key_hashes.take();
+        let marf_key_hash = self.marf_key_hash.take();
+        if marf_key_hash.is_none() && marf_key_hashes.is_none() {
+            return Err(NetError::SendError("`marf_key_hash` not set".to_string()));
+        }
 
         let tip = match node.load_stacks_chain_tip(&preamble, &contents) {
             Ok(tip) => tip,
@@ -123,6 +273,15 @@ impl RPCRequestHandler for RPCGetClarityMarfRequestHandler {
 
         let with_proof = contents.get_with_proof();
 
+        let Some(marf_key_hash) = marf_key_hash else {
+            let marf_key_hashes = marf_key_hashes.unwrap_or_default();
+            let batch_res =
+                node.with_node_state(|_network, _sortdb, chainstate, _mempool, _rpc_args| {
+                    read_clarity_marf_batch(chainstate, &tip, &marf_key_hashes, with_proof)
+                });
+            return make_clarity_marf_batch_response(&preamble, batch_res, node);
+        };
+
         let data_opt = node.with_node_state(|_network, sortdb, chainstate, _mempool, _rpc_args| {
             chainstate.maybe_read_only_clarity_tx(
                 &sortdb.index_handle_at_block(chainstate, &tip)?,
@@ -184,8 +343,7 @@ impl HttpResponse for RPCGetClarityMarfRequestHandler {
         preamble: &HttpResponsePreamble,
         body: &[u8],
     ) -> Result<HttpResponsePayload, Error> {
-        let marf_value: ClarityMarfResponse = parse_json(preamble, body)?;
-        Ok(HttpResponsePayload::try_from_json(marf_value)?)
+        parse_json_maybe_batch::<ClarityMarfResponse>(preamble, body)
     }
 }
 
@@ -206,6 +364,25 @@ impl StacksHttpRequest {
         )
         .expect("FATAL: failed to construct request from infallible data")
     }
+
+    /// Make a new request to read a batch of MARF key hashes, proven by one multi-proof
+    pub fn new_getclaritymarf_batch(
+        host: PeerHost,
+        marf_key_hashes: &[TrieHash],
+        tip_req: TipRequest,
+        with_proof: bool,
+    ) -> StacksHttpRequest {
+        let marf_key_hashes: Vec<_> = marf_key_hashes.iter().map(|hash| hash.to_hex()).collect();
+        StacksHttpRequest::new_for_peer(
+            host,
+            "GET".into(),
+            format!("/v2/clarity/marf/{}", marf_key_hashes.join(",")),
+            HttpRequestContents::new()
+                .for_tip(tip_req)
+                .query_arg("proof".into(), if with_proof { "1" } else { "0" }.into()),
+        )
+        .expect("FATAL: failed to construct request from infallible data")
+    }
 }
 
 impl StacksHttpResponse {
@@ -216,4 +393,12 @@ impl StacksHttpResponse {
             .map_err(|_e| NetError::DeserializeError("Failed to load from JSON".to_string()))?;
         Ok(resp)
     }
+
+    pub fn decode_clarity_marf_batch_response(self) -> Result<ClarityMarfBatchResponse, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let contents_json: serde_json::Value = contents.try_into()?;
+        let resp: ClarityMarfBatchResponse = serde_json::from_value(contents_json)
+            .map_err(|_e| NetError::DeserializeError("Failed to load from JSON".to_string()))?;
+        Ok(resp)
+    }
 }
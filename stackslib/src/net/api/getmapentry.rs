@@ -19,6 +19,7 @@ use std::io::{Read, Write};
 use clarity::vm::ast::parser::v1::CLARITY_NAME_REGEX;
 use clarity::vm::clarity::ClarityConnection;
 use clarity::vm::costs::LimitedCostTracker;
+use clarity::vm::database::state_proofs::map_entry_key_hash;
 use clarity::vm::database::{ClarityDatabase, STXBalance, StoreType};
 use clarity::vm::representations::{
     CONTRACT_NAME_REGEX_STRING, PRINCIPAL_DATA_REGEX_STRING, STANDARD_PRINCIPAL_REGEX_STRING,
@@ -39,6 +40,10 @@ use crate::chainstate::burn::db::sortdb::SortitionDB;
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::Error as ChainError;
 use crate::core::mempool::MemPoolDB;
+use crate::net::api::getclaritymarfvalue::{
+    make_clarity_marf_batch_response, parse_json_maybe_batch, read_clarity_marf_batch,
+    ClarityMarfBatchResponse,
+};
 use crate::net::http::{
     parse_json, Error, HttpContentType, HttpNotFound, HttpRequest, HttpRequestContents,
     HttpRequestPayload, HttpRequestPreamble, HttpResponse, HttpResponseContents,
@@ -62,11 +67,16 @@ pub struct MapEntryResponse {
     pub marf_proof: Option<String>,
 }
 
+/// Maximum number of map keys that can be read in one request
+pub const MAX_MAP_ENTRY_BATCH_KEYS: usize = 256;
+
 #[derive(Clone)]
 pub struct RPCGetMapEntryRequestHandler {
     pub contract_identifier: Option<QualifiedContractIdentifier>,
     pub map_name: Option<ClarityName>,
     pub key: Option<Value>,
+    /// Set instead of `key` if the body is a list of keys
+    pub keys: Option<Vec<Value>>,
 }
 impl RPCGetMapEntryRequestHandler {
     pub fn new() -> Self {
@@ -74,6 +84,7 @@ impl RPCGetMapEntryRequestHandler {
             contract_identifier: None,
             map_name: None,
             key: None,
+            keys: None,
         }
     }
 }
@@ -99,6 +110,7 @@ impl HttpRequest for RPCGetMapEntryRequestHandler {
     /// Try to decode this request.
     /// The body must be a hex string, encoded as a JSON string.
     /// So, something like `"123abc"`.  It encodes the map key as a serialized Clarity value.
+    /// To read a batch of entries, the body is instead a JSON list of such strings.
     fn try_parse_request(
         &mut self,
         preamble: &HttpRequestPreamble,
@@ -124,15 +136,33 @@ impl HttpRequest for RPCGetMapEntryRequestHandler {
         let map_name = request::get_clarity_name(captures, "map")?;
 
         let mut body_ptr = body;
-        let value_hex: String = serde_json::from_reader(&mut body_ptr)
+        let body_json: serde_json::Value = serde_json::from_reader(&mut body_ptr)
             .map_err(|_e| Error::DecodeError("Failed to parse JSON body".into()))?;
 
-        let value = Value::try_deserialize_hex_untyped(&value_hex)
-            .map_err(|_e| Error::DecodeError("Failed to deserialize key value".into()))?;
+        let deserialize_key = |value_hex: &serde_json::Value| {
+            value_hex
+                .as_str()
+                .and_then(|value_hex| Value::try_deserialize_hex_untyped(value_hex).ok())
+                .ok_or_else(|| Error::DecodeError("Failed to deserialize key value".into()))
+        };
+
+        if let Some(values_hex) = body_json.as_array() {
+            if values_hex.is_empty() || values_hex.len() > MAX_MAP_ENTRY_BATCH_KEYS {
+                return Err(Error::DecodeError(format!(
+                    "Invalid Http request: expected 1 to {MAX_MAP_ENTRY_BATCH_KEYS} map keys"
+                )));
+            }
+            let values = values_hex
+                .iter()
+                .map(deserialize_key)
+                .collect::<Result<Vec<_>, _>>()?;
+            self.keys = Some(values);
+        } else {
+            self.key = Some(deserialize_key(&body_json)?);
+        }
 
         self.contract_identifier = Some(contract_identifier);
         self.map_name = Some(map_name);
-        self.key = Some(value);
 
         Ok(HttpRequestContents::new().query_string(query))
     }
@@ -145,6 +175,7 @@ impl RPCRequestHandler for RPCGetMapEntryRequestHandler {
         self.contract_identifier = None;
         self.map_name = None;
         self.key = None;
+        self.keys = None;
     }
 
     /// Make the response
@@ -162,10 +193,11 @@ impl RPCRequestHandler for RPCGetMapEntryRequestHandler {
             .map_name
             .take()
             .ok_or(NetError::SendError("`map_name` not set".into()))?;
-        let key = self
-            .key
-            .take()
-            .ok_or(NetError::SendError("`key` not set".into()))?;
+        let keys = self.keys.take();
+        let key = self.key.take();
+        if key.is_none() && keys.is_none() {
+            return Err(NetError::SendError("`key` not set".into()));
+        }
 
         let tip = match node.load_stacks_chain_tip(&preamble, &contents) {
             Ok(tip) => tip,
@@ -174,6 +206,20 @@ impl RPCRequestHandler for RPCGetMapEntryRequestHandler {
             }
         };
         let with_proof = contents.get_with_proof();
+
+        let Some(key) = key else {
+            let marf_key_hashes = keys
+                .unwrap_or_default()
+                .iter()
+                .map(|key| map_entry_key_hash(&contract_identifier, &map_name, key))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| NetError::SerializeError(e.to_string()))?;
+            let batch_res =
+                node.with_node_state(|_network, _sortdb, chainstate, _mempool, _rpc_args| {
+                    read_clarity_marf_batch(chainstate, &tip, &marf_key_hashes, with_proof)
+                });
+            return make_clarity_marf_batch_response(&preamble, batch_res, node);
+        };
         let key =
             ClarityDatabase::make_key_for_data_map_entry(&contract_identifier, &map_name, &key)
                 .map_err(|e| NetError::SerializeError(format!("{:?}", &e)))?;
@@ -243,8 +289,7 @@ impl HttpResponse for RPCGetMapEntryRequestHandler {
         preamble: &HttpResponsePreamble,
         body: &[u8],
     ) -> Result<HttpResponsePayload, Error> {
-        let map_entry: MapEntryResponse = parse_json(preamble, body)?;
-        Ok(HttpResponsePayload::try_from_json(map_entry)?)
+        parse_json_maybe_batch::<MapEntryResponse>(preamble, body)
     }
 }
 
@@ -276,6 +321,40 @@ impl StacksHttpRequest {
         )
         .expect("FATAL: failed to construct request from infallible data")
     }
+
+    /// Make a new request for a batch of entries in a data map, proven by one multi-proof
+    pub fn new_getmapentry_batch(
+        host: PeerHost,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        map_name: ClarityName,
+        keys: &[Value],
+        tip_req: TipRequest,
+        with_proof: bool,
+    ) -> StacksHttpRequest {
+        let keys_hex = keys
+            .iter()
+            .map(|key| {
+                serde_json::Value::String(
+                    key.serialize_to_hex()
+                        .expect("FATAL: invalid key could not be serialized"),
+                )
+            })
+            .collect();
+        StacksHttpRequest::new_for_peer(
+            host,
+            "POST".into(),
+            format!(
+                "/v2/map_entry/{}/{}/{}",
+                &contract_addr, &contract_name, &map_name
+            ),
+            HttpRequestContents::new()
+                .for_tip(tip_req)
+                .query_arg("proof".into(), if with_proof { "1" } else { "0" }.into())
+                .payload_json(serde_json::Value::Array(keys_hex)),
+        )
+        .expect("FATAL: failed to construct request from infallible data")
+    }
 }
 
 impl StacksHttpResponse {
@@ -286,4 +365,8 @@ impl StacksHttpResponse {
             .map_err(|_e| NetError::DeserializeError("Failed to load from JSON".to_string()))?;
         Ok(resp)
     }
+
+    pub fn decode_map_entry_batch_response(self) -> Result<ClarityMarfBatchResponse, NetError> {
+        self.decode_clarity_marf_batch_response()
+    }
 }
@@ -0,0 +1,257 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use regex::{Captures, Regex};
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::types::net::PeerHost;
+
+use crate::burnchains::Txid;
+use crate::chainstate::nakamoto::NakamotoChainState;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::Error as ChainError;
+use crate::net::http::{
+    parse_json, Error, HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble,
+    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+};
+use crate::net::httpcore::{
+    request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler,
+    StacksHttpRequest, StacksHttpResponse,
+};
+use crate::net::{Error as NetError, StacksNodeState, TipRequest};
+
+/// A block which included a confirmed transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfirmedTransactionBlock {
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    /// Position of the transaction within this block.
+    /// Not known for transactions indexed before chainstate schema version 9.
+    pub tx_index: Option<u32>,
+    /// Whether or not this block is an ancestor of the requested chain tip
+    pub canonical: bool,
+    /// Human-readable transaction result
+    pub result: String,
+    /// Hex-encoded, consensus-serialized transaction result
+    pub result_hex: Option<String>,
+    /// Events emitted by the transaction, in the same format as the event observer's
+    /// `new_block` payload
+    pub events: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfirmedTransactionResponse {
+    pub txid: Txid,
+    /// Hex-encoded transaction
+    pub tx: String,
+    /// Index block hash of the block on the requested chain tip's fork which includes this
+    /// transaction, if there is one.
+    pub canonical_index_block_hash: Option<StacksBlockId>,
+    /// All blocks that included this transaction, across all forks
+    pub blocks: Vec<ConfirmedTransactionBlock>,
+}
+
+#[derive(Clone)]
+pub struct RPCGetTransactionRequestHandler {
+    pub txid: Option<Txid>,
+}
+impl RPCGetTransactionRequestHandler {
+    pub fn new() -> Self {
+        Self { txid: None }
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCGetTransactionRequestHandler {
+    fn verb(&self) -> &'static str {
+        "GET"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(r#"^/v3/transactions/(?P<txid>[0-9a-f]{64})$"#).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        "/v3/transactions/:txid"
+    }
+
+    /// Try to decode this request.
+    /// There's nothing to load here, so just make sure the request is well-formed.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        if preamble.get_content_length() != 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected 0-length body for GetTransaction".to_string(),
+            ));
+        }
+
+        let txid = request::get_txid(captures, "txid")?;
+        self.txid = Some(txid);
+
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+}
+
+impl RPCRequestHandler for RPCGetTransactionRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {
+        self.txid = None;
+    }
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let txid = self
+            .txid
+            .take()
+            .ok_or(NetError::SendError("`txid` no set".into()))?;
+
+        let tip = match node.load_stacks_chain_tip(&preamble, &contents) {
+            Ok(tip) => tip,
+            Err(error_resp) => {
+                return error_resp.try_into_contents().map_err(NetError::from);
+            }
+        };
+
+        let txinfo_res =
+            node.with_node_state(|_network, _sortdb, chainstate, _mempool, _rpc_args| {
+                let entries =
+                    StacksChainState::get_transaction_index_entries(chainstate.db(), &txid)?;
+                let Some(first_entry) = entries.first() else {
+                    return Err(ChainError::NoSuchBlockError);
+                };
+                let tx = first_entry.tx_hex.clone();
+
+                let mut canonical_index_block_hash = None;
+                let mut blocks = Vec::with_capacity(entries.len());
+                for entry in entries.into_iter() {
+                    let Some(header) = NakamotoChainState::get_block_header(
+                        chainstate.db(),
+                        &entry.index_block_hash,
+                    )?
+                    else {
+                        // block was indexed, but its header was never stored (e.g. the
+                        // chainstate transaction which processed it was rolled back)
+                        continue;
+                    };
+                    let canonical = chainstate
+                        .index_conn()
+                        .get_ancestor_block_hash(header.stacks_block_height, &tip)?
+                        .map(|ancestor_id| ancestor_id == entry.index_block_hash)
+                        .unwrap_or(false);
+
+                    if canonical {
+                        canonical_index_block_hash = Some(entry.index_block_hash.clone());
+                    }
+
+                    blocks.push(ConfirmedTransactionBlock {
+                        index_block_hash: entry.index_block_hash,
+                        block_height: header.stacks_block_height,
+                        tx_index: entry.tx_index,
+                        canonical,
+                        result: entry.result,
+                        result_hex: entry.result_hex,
+                        events: entry.events,
+                    });
+                }
+
+                Ok(ConfirmedTransactionResponse {
+                    txid: txid.clone(),
+                    tx,
+                    canonical_index_block_hash,
+                    blocks,
+                })
+            });
+
+        let txinfo = match txinfo_res {
+            Ok(txinfo) => txinfo,
+            Err(ChainError::NoSuchBlockError) => {
+                return StacksHttpResponse::new_error(
+                    &preamble,
+                    &HttpNotFound::new(format!(
+                        "Transaction {} not found in the transaction index",
+                        &txid
+                    )),
+                )
+                .try_into_contents()
+                .map_err(NetError::from);
+            }
+            Err(e) => {
+                return StacksHttpResponse::new_error(
+                    &preamble,
+                    &HttpServerError::new(format!(
+                        "Failed to query transaction {}: {:?}",
+                        &txid, &e
+                    )),
+                )
+                .try_into_contents()
+                .map_err(NetError::from);
+            }
+        };
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&txinfo)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCGetTransactionRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let txinfo: ConfirmedTransactionResponse = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(txinfo)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new get-confirmed-tx request
+    pub fn new_gettransaction(
+        host: PeerHost,
+        txid: Txid,
+        tip_req: TipRequest,
+    ) -> StacksHttpRequest {
+        StacksHttpRequest::new_for_peer(
+            host,
+            "GET".into(),
+            format!("/v3/transactions/{}", &txid),
+            HttpRequestContents::new().for_tip(tip_req),
+        )
+        .expect("FATAL: failed to construct request from infallible data")
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_gettransaction(self) -> Result<ConfirmedTransactionResponse, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let response_json: serde_json::Value = contents.try_into()?;
+        let txinfo: ConfirmedTransactionResponse = serde_json::from_value(response_json)
+            .map_err(|_e| Error::DecodeError("Failed to decode JSON".to_string()))?;
+        Ok(txinfo)
+    }
+}
@@ -39,6 +39,8 @@ use crate::stacks_common::codec::StacksMessageCodec;
 pub mod callreadonly;
 pub mod get_tenures_fork_info;
 pub mod getaccount;
+pub mod getadminpeers;
+pub mod getadminsyncstate;
 pub mod getattachment;
 pub mod getattachmentsinv;
 pub mod getblock;
@@ -68,8 +70,13 @@ pub mod getstxtransfercost;
 pub mod gettenure;
 pub mod gettenureinfo;
 pub mod gettenuretip;
+pub mod gettransaction;
 pub mod gettransaction_unconfirmed;
 pub mod liststackerdbreplicas;
+pub mod postadmincidrs;
+pub mod postadminloglevels;
+pub mod postadminmempool;
+pub mod postadminpeers;
 pub mod postblock;
 pub mod postblock_proposal;
 #[warn(unused_imports)]
@@ -79,6 +86,7 @@ pub mod postmempoolquery;
 pub mod postmicroblock;
 pub mod poststackerdbchunk;
 pub mod posttransaction;
+pub mod posttransaction_simulate;
 
 #[cfg(test)]
 mod tests;
@@ -92,6 +100,12 @@ impl StacksHttp {
             self.read_only_call_limit.clone(),
         ));
         self.register_rpc_endpoint(getaccount::RPCGetAccountRequestHandler::new());
+        self.register_rpc_endpoint(getadminpeers::RPCGetAdminPeersRequestHandler::new(
+            self.auth_token.clone(),
+        ));
+        self.register_rpc_endpoint(getadminsyncstate::RPCGetAdminSyncStateRequestHandler::new(
+            self.auth_token.clone(),
+        ));
         self.register_rpc_endpoint(getattachment::RPCGetAttachmentRequestHandler::new());
         self.register_rpc_endpoint(getattachmentsinv::RPCGetAttachmentsInvRequestHandler::new());
         self.register_rpc_endpoint(getblock::RPCBlocksRequestHandler::new());
@@ -131,6 +145,7 @@ impl StacksHttp {
         self.register_rpc_endpoint(gettenureinfo::RPCNakamotoTenureInfoRequestHandler::new());
         self.register_rpc_endpoint(gettenuretip::RPCNakamotoTenureTipRequestHandler::new());
         self.register_rpc_endpoint(get_tenures_fork_info::GetTenuresForkInfo::default());
+        self.register_rpc_endpoint(gettransaction::RPCGetTransactionRequestHandler::new());
         self.register_rpc_endpoint(
             gettransaction_unconfirmed::RPCGetTransactionUnconfirmedRequestHandler::new(),
         );
@@ -138,6 +153,18 @@ impl StacksHttp {
         self.register_rpc_endpoint(
             liststackerdbreplicas::RPCListStackerDBReplicasRequestHandler::new(),
         );
+        self.register_rpc_endpoint(postadmincidrs::RPCPostAdminCidrsRequestHandler::new(
+            self.auth_token.clone(),
+        ));
+        self.register_rpc_endpoint(
+            postadminloglevels::RPCPostAdminLogLevelsRequestHandler::new(self.auth_token.clone()),
+        );
+        self.register_rpc_endpoint(postadminmempool::RPCPostAdminMempoolRequestHandler::new(
+            self.auth_token.clone(),
+        ));
+        self.register_rpc_endpoint(postadminpeers::RPCPostAdminPeersRequestHandler::new(
+            self.auth_token.clone(),
+        ));
         self.register_rpc_endpoint(postblock::RPCPostBlockRequestHandler::new());
         self.register_rpc_endpoint(postblock_proposal::RPCBlockProposalRequestHandler::new(
             self.auth_token.clone(),
@@ -150,7 +177,30 @@ impl StacksHttp {
         self.register_rpc_endpoint(postmicroblock::RPCPostMicroblockRequestHandler::new());
         self.register_rpc_endpoint(poststackerdbchunk::RPCPostStackerDBChunkRequestHandler::new());
         self.register_rpc_endpoint(posttransaction::RPCPostTransactionRequestHandler::new());
+        self.register_rpc_endpoint(
+            posttransaction_simulate::RPCPostTransactionSimulateRequestHandler::new(
+                self.auth_token.clone(),
+            ),
+        );
+    }
+}
+
+/// Check the `authorization` header of a request to one of the admin endpoints.  These are
+/// only enabled if the node has an auth token.
+pub(crate) fn check_admin_auth(
+    auth: Option<&str>,
+    preamble: &HttpRequestPreamble,
+) -> Result<(), Error> {
+    let Some(password) = auth else {
+        return Err(Error::Http(400, "Bad Request.".into()));
+    };
+    let Some(auth_header) = preamble.headers.get("authorization") else {
+        return Err(Error::Http(401, "Unauthorized".into()));
+    };
+    if auth_header != password {
+        return Err(Error::Http(401, "Unauthorized".into()));
     }
+    Ok(())
 }
 
 /// Helper conversion for NetError to Error
@@ -0,0 +1,310 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use regex::{Captures, Regex};
+use stacks_common::codec::MAX_PAYLOAD_LEN;
+use stacks_common::types::net::{PeerAddress, PeerHost};
+
+use crate::net::api::check_admin_auth;
+use crate::net::db::PeerDB;
+use crate::net::http::{
+    parse_json, Error, HttpContentType, HttpRequest, HttpRequestContents, HttpRequestPreamble,
+    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble,
+};
+use crate::net::httpcore::{
+    HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+};
+use crate::net::p2p::{DropReason, DropSource, PeerNetwork};
+use crate::net::{Error as NetError, StacksNodeState};
+
+pub static PATH: &str = "/v3/admin/cidrs";
+
+/// Parse a CIDR prefix like `10.0.0.0/8` or `fd00::/8` into the prefix and mask length that
+/// `PeerDB` uses.  IPv4 prefixes are mapped into the IPv6 address space.  Bits past the mask
+/// length are cleared.
+pub fn parse_cidr(cidr: &str) -> Result<(PeerAddress, u32), String> {
+    let Some((ip_str, mask_str)) = cidr.split_once('/') else {
+        return Err(format!(
+            "Invalid CIDR prefix `{cidr}`: expected `address/length`"
+        ));
+    };
+    let ip: IpAddr = ip_str
+        .parse()
+        .map_err(|_e| format!("Invalid CIDR prefix `{cidr}`: bad address"))?;
+    let mask: u32 = mask_str
+        .parse()
+        .map_err(|_e| format!("Invalid CIDR prefix `{cidr}`: bad length"))?;
+    let (max_mask, mapped_bits) = match ip {
+        IpAddr::V4(_) => (32, 96),
+        IpAddr::V6(_) => (128, 0),
+    };
+    if mask == 0 || mask > max_mask {
+        return Err(format!(
+            "Invalid CIDR prefix `{cidr}`: length must be between 1 and {max_mask}"
+        ));
+    }
+
+    let mask = mask + mapped_bits;
+    let prefix = u128::from_be_bytes(PeerAddress::from_ip(&ip).0) & cidr_bitmask(mask);
+    Ok((PeerAddress(prefix.to_be_bytes()), mask))
+}
+
+/// Format a prefix and mask length from `PeerDB` as a CIDR prefix
+pub fn cidr_to_string(prefix: &PeerAddress, mask: u32) -> String {
+    match prefix.ipv4_octets() {
+        Some(octets) if mask >= 96 => format!("{}/{}", Ipv4Addr::from(octets), mask - 96),
+        _ => format!("{}/{}", Ipv6Addr::from(prefix.0), mask),
+    }
+}
+
+/// Is `addr` in the CIDR prefix?
+pub fn cidr_contains(prefix: &PeerAddress, mask: u32, addr: &PeerAddress) -> bool {
+    let bitmask = cidr_bitmask(mask);
+    u128::from_be_bytes(prefix.0) & bitmask == u128::from_be_bytes(addr.0) & bitmask
+}
+
+/// The bitmask for a mask length between 1 and 128
+fn cidr_bitmask(mask: u32) -> u128 {
+    u128::MAX << (128 - mask)
+}
+
+/// What to do with a CIDR prefix
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminCidrAction {
+    /// Deny all peers in the prefix, and disconnect from the ones we're connected to
+    Deny,
+    /// Always allow peers in the prefix
+    Allow,
+    /// Remove a deny rule
+    RemoveDeny,
+    /// Remove an allow rule
+    RemoveAllow,
+}
+
+/// Request body for `POST /v3/admin/cidrs`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminCidrRequest {
+    pub action: AdminCidrAction,
+    pub cidr: String,
+}
+
+/// Response to `POST /v3/admin/cidrs`: the CIDR rules after the change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminCidrsInfo {
+    pub deny_cidrs: Vec<String>,
+    pub allow_cidrs: Vec<String>,
+    /// How many connections were dropped because of a new deny rule
+    pub disconnected: usize,
+}
+
+impl AdminCidrsInfo {
+    /// Apply a CIDR rule change to the peer network
+    pub fn apply(
+        network: &mut PeerNetwork,
+        action: AdminCidrAction,
+        prefix: &PeerAddress,
+        mask: u32,
+    ) -> Result<AdminCidrsInfo, NetError> {
+        let tx = network.peerdb.tx_begin()?;
+        match action {
+            AdminCidrAction::Deny => PeerDB::add_deny_cidr(&tx, prefix, mask)?,
+            AdminCidrAction::Allow => PeerDB::add_allow_cidr(&tx, prefix, mask)?,
+            AdminCidrAction::RemoveDeny => PeerDB::remove_deny_cidr(&tx, prefix, mask)?,
+            AdminCidrAction::RemoveAllow => PeerDB::remove_allow_cidr(&tx, prefix, mask)?,
+        };
+        tx.commit()?;
+
+        let mut disconnected = 0;
+        if action == AdminCidrAction::Deny {
+            let denied: Vec<_> = network
+                .iter_peer_convos()
+                .map(|(_, convo)| convo.to_neighbor_key())
+                .filter(|nk| cidr_contains(prefix, mask, &nk.addrbytes))
+                .collect();
+            for nk in denied.iter() {
+                network.deregister_neighbor(nk, DropReason::BannedConnection, DropSource::AdminRPC);
+            }
+            disconnected = denied.len();
+        }
+
+        info!(
+            "Admin RPC: applied CIDR rule {:?} {}",
+            &action,
+            cidr_to_string(prefix, mask)
+        );
+
+        let peerdb_conn = network.peerdb_conn();
+        Ok(AdminCidrsInfo {
+            deny_cidrs: PeerDB::get_denied_cidrs(peerdb_conn)?
+                .iter()
+                .map(|(prefix, mask)| cidr_to_string(prefix, *mask))
+                .collect(),
+            allow_cidrs: PeerDB::get_allowed_cidrs(peerdb_conn)?
+                .iter()
+                .map(|(prefix, mask)| cidr_to_string(prefix, *mask))
+                .collect(),
+            disconnected,
+        })
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RPCPostAdminCidrsRequestHandler {
+    pub request: Option<(AdminCidrAction, PeerAddress, u32)>,
+    pub auth: Option<String>,
+}
+
+impl RPCPostAdminCidrsRequestHandler {
+    pub fn new(auth: Option<String>) -> Self {
+        Self {
+            request: None,
+            auth,
+        }
+    }
+
+    /// Decode a JSON-encoded CIDR rule change
+    fn parse_json(body: &[u8]) -> Result<(AdminCidrAction, PeerAddress, u32), Error> {
+        let request: AdminCidrRequest = serde_json::from_slice(body)
+            .map_err(|e| Error::DecodeError(format!("Failed to parse body: {e}")))?;
+        let (prefix, mask) = parse_cidr(&request.cidr).map_err(Error::DecodeError)?;
+        Ok((request.action, prefix, mask))
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCPostAdminCidrsRequestHandler {
+    fn verb(&self) -> &'static str {
+        "POST"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(&format!("^{PATH}$")).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        PATH
+    }
+
+    /// Try to decode this request.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        check_admin_auth(self.auth.as_deref(), preamble)?;
+        if preamble.get_content_length() == 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected non-zero-length body for admin CIDR rules"
+                    .to_string(),
+            ));
+        }
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(Error::DecodeError(
+                "Invalid Http request: admin CIDR rules body is too big".to_string(),
+            ));
+        }
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(Error::DecodeError(
+                "Invalid Http request: admin CIDR rules take application/json".to_string(),
+            ));
+        }
+
+        self.request = Some(Self::parse_json(body)?);
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+}
+
+impl RPCRequestHandler for RPCPostAdminCidrsRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {
+        self.request = None;
+    }
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        _contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let (action, prefix, mask) = self
+            .request
+            .take()
+            .ok_or(NetError::SendError("`request` not set".into()))?;
+
+        let cidrs_info =
+            node.with_node_state(|network, _sortdb, _chainstate, _mempool, _rpc_args| {
+                AdminCidrsInfo::apply(network, action, &prefix, mask)
+            })?;
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&cidrs_info)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCPostAdminCidrsRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let cidrs_info: AdminCidrsInfo = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(cidrs_info)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new request to change the node's CIDR rules
+    pub fn new_post_admin_cidr(
+        host: PeerHost,
+        action: AdminCidrAction,
+        cidr: &str,
+        auth: &str,
+    ) -> StacksHttpRequest {
+        let request = AdminCidrRequest {
+            action,
+            cidr: cidr.to_string(),
+        };
+        let mut request = StacksHttpRequest::new_for_peer(
+            host,
+            "POST".into(),
+            PATH.into(),
+            HttpRequestContents::new().payload_json(
+                serde_json::to_value(request).expect("FATAL: failed to encode infallible data"),
+            ),
+        )
+        .expect("FATAL: failed to construct request from infallible data");
+        request.add_header("authorization".into(), auth.into());
+        request
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_admin_cidrs(self) -> Result<AdminCidrsInfo, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let response_json: serde_json::Value = contents.try_into()?;
+        let cidrs_info: AdminCidrsInfo = serde_json::from_value(response_json)
+            .map_err(|_e| Error::DecodeError("Failed to decode JSON".to_string()))?;
+        Ok(cidrs_info)
+    }
+}
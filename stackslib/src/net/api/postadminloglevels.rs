@@ -0,0 +1,255 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use regex::{Captures, Regex};
+use stacks_common::codec::MAX_PAYLOAD_LEN;
+use stacks_common::types::net::PeerHost;
+use stacks_common::util::log::{get_loglevel, get_module_loglevels, set_module_loglevel};
+
+use crate::net::api::check_admin_auth;
+use crate::net::http::{
+    parse_json, Error, HttpContentType, HttpRequest, HttpRequestContents, HttpRequestPreamble,
+    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble,
+};
+use crate::net::httpcore::{
+    HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+};
+use crate::net::{Error as NetError, StacksNodeState};
+
+pub static PATH: &str = "/v3/admin/log_levels";
+
+/// The most module log levels that can be set in one request
+pub const MAX_ADMIN_LOG_LEVELS: usize = 256;
+
+/// Parse a log level name, like `debug` or `trace`
+pub fn parse_log_level(level: &str) -> Result<slog::Level, String> {
+    match level.to_lowercase().as_str() {
+        "critical" => Ok(slog::Level::Critical),
+        "error" => Ok(slog::Level::Error),
+        "warn" | "warning" => Ok(slog::Level::Warning),
+        "info" => Ok(slog::Level::Info),
+        "debug" => Ok(slog::Level::Debug),
+        "trace" => Ok(slog::Level::Trace),
+        _ => Err(format!("Invalid log level `{level}`")),
+    }
+}
+
+/// The name of a log level, as accepted by `parse_log_level`
+pub fn log_level_name(level: slog::Level) -> String {
+    level.as_str().to_lowercase()
+}
+
+/// Is `module` a plausible module path, like `blockstack_lib::net::p2p`?
+fn is_valid_module_path(module: &str) -> bool {
+    !module.is_empty()
+        && module
+            .split("::")
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_alphanumeric() || c == '_'))
+}
+
+/// Request body for `POST /v3/admin/log_levels`.  Each module maps to its new log level, or to
+/// `null` to remove its override.  An empty map just reads the current levels.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminLogLevelsRequest {
+    #[serde(default)]
+    pub levels: BTreeMap<String, Option<String>>,
+}
+
+/// Response to `POST /v3/admin/log_levels`: the log levels after the change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminLogLevelsInfo {
+    /// The log level for modules without an override
+    pub default: String,
+    /// The module log level overrides
+    pub modules: BTreeMap<String, String>,
+}
+
+impl AdminLogLevelsInfo {
+    /// Get the current log levels
+    pub fn current() -> AdminLogLevelsInfo {
+        AdminLogLevelsInfo {
+            default: log_level_name(get_loglevel()),
+            modules: get_module_loglevels()
+                .into_iter()
+                .map(|(module, level)| (module, log_level_name(level)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RPCPostAdminLogLevelsRequestHandler {
+    pub levels: Option<Vec<(String, Option<slog::Level>)>>,
+    pub auth: Option<String>,
+}
+
+impl RPCPostAdminLogLevelsRequestHandler {
+    pub fn new(auth: Option<String>) -> Self {
+        Self { levels: None, auth }
+    }
+
+    /// Decode and validate a JSON-encoded log level change
+    fn parse_json(body: &[u8]) -> Result<Vec<(String, Option<slog::Level>)>, Error> {
+        let request: AdminLogLevelsRequest = serde_json::from_slice(body)
+            .map_err(|e| Error::DecodeError(format!("Failed to parse body: {e}")))?;
+        if request.levels.len() > MAX_ADMIN_LOG_LEVELS {
+            return Err(Error::DecodeError(format!(
+                "Too many log levels: at most {MAX_ADMIN_LOG_LEVELS} are allowed"
+            )));
+        }
+        request
+            .levels
+            .into_iter()
+            .map(|(module, level)| {
+                if !is_valid_module_path(&module) {
+                    return Err(Error::DecodeError(format!(
+                        "Invalid module path `{module}`"
+                    )));
+                }
+                let level = level
+                    .map(|level| parse_log_level(&level))
+                    .transpose()
+                    .map_err(Error::DecodeError)?;
+                Ok((module, level))
+            })
+            .collect()
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCPostAdminLogLevelsRequestHandler {
+    fn verb(&self) -> &'static str {
+        "POST"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(&format!("^{PATH}$")).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        PATH
+    }
+
+    /// Try to decode this request.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        check_admin_auth(self.auth.as_deref(), preamble)?;
+        if preamble.get_content_length() == 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected non-zero-length body for admin log levels"
+                    .to_string(),
+            ));
+        }
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(Error::DecodeError(
+                "Invalid Http request: admin log levels body is too big".to_string(),
+            ));
+        }
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(Error::DecodeError(
+                "Invalid Http request: admin log levels take application/json".to_string(),
+            ));
+        }
+
+        self.levels = Some(Self::parse_json(body)?);
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+}
+
+impl RPCRequestHandler for RPCPostAdminLogLevelsRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {
+        self.levels = None;
+    }
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        _contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let levels = self
+            .levels
+            .take()
+            .ok_or(NetError::SendError("`levels` not set".into()))?;
+
+        for (module, level) in levels.into_iter() {
+            info!(
+                "Admin RPC: set log level";
+                "module" => &module,
+                "level" => level.map(log_level_name).unwrap_or_else(|| "default".into())
+            );
+            set_module_loglevel(&module, level);
+        }
+        let log_levels = AdminLogLevelsInfo::current();
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&log_levels)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCPostAdminLogLevelsRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let log_levels: AdminLogLevelsInfo = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(log_levels)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new request to change (or, with no levels, read) the node's module log levels
+    pub fn new_post_admin_log_levels(
+        host: PeerHost,
+        levels: BTreeMap<String, Option<String>>,
+        auth: &str,
+    ) -> StacksHttpRequest {
+        let request = AdminLogLevelsRequest { levels };
+        let mut request = StacksHttpRequest::new_for_peer(
+            host,
+            "POST".into(),
+            PATH.into(),
+            HttpRequestContents::new().payload_json(
+                serde_json::to_value(request).expect("FATAL: failed to encode infallible data"),
+            ),
+        )
+        .expect("FATAL: failed to construct request from infallible data");
+        request.add_header("authorization".into(), auth.into());
+        request
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_admin_log_levels(self) -> Result<AdminLogLevelsInfo, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let response_json: serde_json::Value = contents.try_into()?;
+        let log_levels: AdminLogLevelsInfo = serde_json::from_value(response_json)
+            .map_err(|_e| Error::DecodeError("Failed to decode JSON".to_string()))?;
+        Ok(log_levels)
+    }
+}
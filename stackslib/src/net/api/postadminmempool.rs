@@ -0,0 +1,267 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use regex::{Captures, Regex};
+use stacks_common::codec::MAX_PAYLOAD_LEN;
+use stacks_common::types::net::PeerHost;
+
+use crate::burnchains::Txid;
+use crate::core::mempool::{MemPoolDB, MemPoolDropReason, MemPoolEventDispatcher};
+use crate::net::api::check_admin_auth;
+use crate::net::http::{
+    parse_json, Error, HttpContentType, HttpRequest, HttpRequestContents, HttpRequestPreamble,
+    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble,
+};
+use crate::net::httpcore::{
+    HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+};
+use crate::net::p2p::PeerNetwork;
+use crate::net::{Error as NetError, StacksNodeState};
+
+pub static PATH: &str = "/v3/admin/mempool";
+
+/// The most transactions that can be dropped in one request
+pub const MAX_ADMIN_MEMPOOL_TXIDS: usize = 1024;
+
+/// Request body for `POST /v3/admin/mempool`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AdminMempoolRequest {
+    /// Remove transactions from the mempool.  They can be re-submitted.
+    Drop { txids: Vec<Txid> },
+    /// Remove transactions from the mempool and refuse them until the blacklist entry expires
+    Blacklist { txids: Vec<Txid> },
+    /// Run mempool garbage collection now instead of waiting for the next block
+    GarbageCollect,
+}
+
+/// Response to `POST /v3/admin/mempool`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminMempoolResult {
+    /// How many transactions were removed
+    pub removed: u64,
+    /// How many transactions are in the mempool now
+    pub mempool_size: u64,
+}
+
+impl AdminMempoolResult {
+    /// Apply a mempool request
+    pub fn apply(
+        network: &PeerNetwork,
+        mempool: &mut MemPoolDB,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        request: &AdminMempoolRequest,
+    ) -> Result<AdminMempoolResult, NetError> {
+        let size_before = MemPoolDB::count_txs(mempool.conn())?;
+        match request {
+            AdminMempoolRequest::Drop { txids } | AdminMempoolRequest::Blacklist { txids } => {
+                let mut present = vec![];
+                for txid in txids.iter() {
+                    if MemPoolDB::db_has_tx(mempool.conn(), txid)? {
+                        present.push(*txid);
+                    }
+                }
+                if let AdminMempoolRequest::Blacklist { .. } = request {
+                    info!(
+                        "Admin RPC: drop and blacklist {} transaction(s)",
+                        txids.len()
+                    );
+                    mempool.drop_and_blacklist_txs(txids)?;
+                } else {
+                    info!("Admin RPC: drop {} transaction(s)", txids.len());
+                    mempool.drop_txs(txids)?;
+                }
+                if let Some(event_observer) = event_observer {
+                    if !present.is_empty() {
+                        event_observer.mempool_txs_dropped(
+                            present,
+                            None,
+                            MemPoolDropReason::ADMIN_REQUEST,
+                        );
+                    }
+                }
+            }
+            AdminMempoolRequest::GarbageCollect => {
+                let behavior = network
+                    .get_current_epoch()
+                    .epoch_id
+                    .mempool_garbage_behavior();
+                info!(
+                    "Admin RPC: garbage-collect mempool";
+                    "chain_height" => network.stacks_tip.height,
+                    "behavior" => ?behavior
+                );
+                mempool.garbage_collect(network.stacks_tip.height, &behavior, event_observer)?;
+            }
+        }
+        let mempool_size = MemPoolDB::count_txs(mempool.conn())?;
+        Ok(AdminMempoolResult {
+            removed: size_before.saturating_sub(mempool_size),
+            mempool_size,
+        })
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RPCPostAdminMempoolRequestHandler {
+    pub request: Option<AdminMempoolRequest>,
+    pub auth: Option<String>,
+}
+
+impl RPCPostAdminMempoolRequestHandler {
+    pub fn new(auth: Option<String>) -> Self {
+        Self {
+            request: None,
+            auth,
+        }
+    }
+
+    /// Decode a JSON-encoded mempool request
+    fn parse_json(body: &[u8]) -> Result<AdminMempoolRequest, Error> {
+        let request: AdminMempoolRequest = serde_json::from_slice(body)
+            .map_err(|e| Error::DecodeError(format!("Failed to parse body: {e}")))?;
+        match &request {
+            AdminMempoolRequest::Drop { txids } | AdminMempoolRequest::Blacklist { txids } => {
+                if txids.is_empty() {
+                    return Err(Error::DecodeError("No txids given".to_string()));
+                }
+                if txids.len() > MAX_ADMIN_MEMPOOL_TXIDS {
+                    return Err(Error::DecodeError(format!(
+                        "Too many txids: at most {MAX_ADMIN_MEMPOOL_TXIDS} are allowed"
+                    )));
+                }
+            }
+            AdminMempoolRequest::GarbageCollect => {}
+        }
+        Ok(request)
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCPostAdminMempoolRequestHandler {
+    fn verb(&self) -> &'static str {
+        "POST"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(&format!("^{PATH}$")).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        PATH
+    }
+
+    /// Try to decode this request.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        check_admin_auth(self.auth.as_deref(), preamble)?;
+        if preamble.get_content_length() == 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected non-zero-length body for admin mempool request"
+                    .to_string(),
+            ));
+        }
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(Error::DecodeError(
+                "Invalid Http request: admin mempool request body is too big".to_string(),
+            ));
+        }
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(Error::DecodeError(
+                "Invalid Http request: admin mempool request takes application/json".to_string(),
+            ));
+        }
+
+        self.request = Some(Self::parse_json(body)?);
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+}
+
+impl RPCRequestHandler for RPCPostAdminMempoolRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {
+        self.request = None;
+    }
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        _contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let request = self
+            .request
+            .take()
+            .ok_or(NetError::SendError("`request` not set".into()))?;
+
+        let result = node.with_node_state(|network, _sortdb, _chainstate, mempool, rpc_args| {
+            AdminMempoolResult::apply(network, mempool, rpc_args.event_observer, &request)
+        })?;
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&result)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCPostAdminMempoolRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let result: AdminMempoolResult = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(result)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new request to drop transactions from, or garbage-collect, the mempool
+    pub fn new_post_admin_mempool(
+        host: PeerHost,
+        request: &AdminMempoolRequest,
+        auth: &str,
+    ) -> StacksHttpRequest {
+        let mut request = StacksHttpRequest::new_for_peer(
+            host,
+            "POST".into(),
+            PATH.into(),
+            HttpRequestContents::new().payload_json(
+                serde_json::to_value(request).expect("FATAL: failed to encode infallible data"),
+            ),
+        )
+        .expect("FATAL: failed to construct request from infallible data");
+        request.add_header("authorization".into(), auth.into());
+        request
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_admin_mempool_result(self) -> Result<AdminMempoolResult, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let response_json: serde_json::Value = contents.try_into()?;
+        let result: AdminMempoolResult = serde_json::from_value(response_json)
+            .map_err(|_e| Error::DecodeError("Failed to decode JSON".to_string()))?;
+        Ok(result)
+    }
+}
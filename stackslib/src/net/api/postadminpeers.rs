@@ -0,0 +1,263 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use regex::{Captures, Regex};
+use stacks_common::codec::MAX_PAYLOAD_LEN;
+use stacks_common::types::net::{PeerAddress, PeerHost};
+use stacks_common::util::get_epoch_time_secs;
+
+use crate::net::api::check_admin_auth;
+use crate::net::db::PeerDB;
+use crate::net::http::{
+    parse_json, Error, HttpContentType, HttpRequest, HttpRequestContents, HttpRequestPreamble,
+    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble,
+};
+use crate::net::httpcore::{
+    HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+};
+use crate::net::p2p::{DropReason, DropSource, PeerNetwork};
+use crate::net::{Error as NetError, StacksNodeState, DENY_BAN_DURATION};
+
+pub static PATH: &str = "/v3/admin/peers";
+
+/// Request body for `POST /v3/admin/peers`.  The peer is identified by the address of its
+/// connection or the address it gave in its handshake, as reported by `GET /v3/admin/peers`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AdminPeerRequest {
+    /// Disconnect from the peer.  It may reconnect later.
+    Disconnect { ip: PeerAddress, port: u16 },
+    /// Disconnect from the peer and deny it for `duration` seconds (one day by default)
+    Ban {
+        ip: PeerAddress,
+        port: u16,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        duration: Option<u64>,
+    },
+    /// Lift a ban on the peer.  It stays denied if a CIDR deny rule covers it.
+    Unban { ip: PeerAddress, port: u16 },
+}
+
+/// Response to `POST /v3/admin/peers`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminPeerResult {
+    /// How many connections were dropped
+    pub disconnected: usize,
+    /// When the peer's ban expires, in seconds since the epoch, if it is banned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub denied_until: Option<u64>,
+}
+
+impl AdminPeerResult {
+    /// Apply a peer request to the peer network
+    pub fn apply(
+        network: &mut PeerNetwork,
+        request: &AdminPeerRequest,
+    ) -> Result<AdminPeerResult, NetError> {
+        let network_id = network.get_local_peer().network_id;
+        match request {
+            AdminPeerRequest::Disconnect { ip, port } => {
+                info!("Admin RPC: disconnect peer {}:{}", ip.pretty_print(), port);
+                let disconnected = network.deregister_peer_address(
+                    ip,
+                    *port,
+                    DropReason::AdminRequest,
+                    DropSource::AdminRPC,
+                );
+                Ok(AdminPeerResult {
+                    disconnected: disconnected.len(),
+                    denied_until: None,
+                })
+            }
+            AdminPeerRequest::Ban { ip, port, duration } => {
+                let denied_until =
+                    get_epoch_time_secs().saturating_add(duration.unwrap_or(DENY_BAN_DURATION));
+                info!(
+                    "Admin RPC: ban peer {}:{} until {}",
+                    ip.pretty_print(),
+                    port,
+                    denied_until
+                );
+
+                let tx = network.peerdb.tx_begin()?;
+                PeerDB::set_deny_peer(&tx, network_id, ip, *port, denied_until)?;
+                tx.commit()?;
+
+                let disconnected = network.deregister_peer_address(
+                    ip,
+                    *port,
+                    DropReason::BannedConnection,
+                    DropSource::AdminRPC,
+                );
+                Ok(AdminPeerResult {
+                    disconnected: disconnected.len(),
+                    denied_until: Some(denied_until),
+                })
+            }
+            AdminPeerRequest::Unban { ip, port } => {
+                info!("Admin RPC: unban peer {}:{}", ip.pretty_print(), port);
+                let tx = network.peerdb.tx_begin()?;
+                // don't preemptively insert a peer we don't know about
+                if PeerDB::has_peer(&tx, network_id, ip, *port)? {
+                    PeerDB::set_deny_peer(&tx, network_id, ip, *port, 0)?;
+                }
+                tx.commit()?;
+                Ok(AdminPeerResult {
+                    disconnected: 0,
+                    denied_until: None,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RPCPostAdminPeersRequestHandler {
+    pub request: Option<AdminPeerRequest>,
+    pub auth: Option<String>,
+}
+
+impl RPCPostAdminPeersRequestHandler {
+    pub fn new(auth: Option<String>) -> Self {
+        Self {
+            request: None,
+            auth,
+        }
+    }
+
+    /// Decode a JSON-encoded peer request
+    fn parse_json(body: &[u8]) -> Result<AdminPeerRequest, Error> {
+        serde_json::from_slice(body)
+            .map_err(|e| Error::DecodeError(format!("Failed to parse body: {e}")))
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCPostAdminPeersRequestHandler {
+    fn verb(&self) -> &'static str {
+        "POST"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(&format!("^{PATH}$")).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        PATH
+    }
+
+    /// Try to decode this request.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        check_admin_auth(self.auth.as_deref(), preamble)?;
+        if preamble.get_content_length() == 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected non-zero-length body for admin peer request"
+                    .to_string(),
+            ));
+        }
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(Error::DecodeError(
+                "Invalid Http request: admin peer request body is too big".to_string(),
+            ));
+        }
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(Error::DecodeError(
+                "Invalid Http request: admin peer request takes application/json".to_string(),
+            ));
+        }
+
+        self.request = Some(Self::parse_json(body)?);
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+}
+
+impl RPCRequestHandler for RPCPostAdminPeersRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {
+        self.request = None;
+    }
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        _contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let request = self
+            .request
+            .take()
+            .ok_or(NetError::SendError("`request` not set".into()))?;
+
+        let result =
+            node.with_node_state(|network, _sortdb, _chainstate, _mempool, _rpc_args| {
+                AdminPeerResult::apply(network, &request)
+            })?;
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&result)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCPostAdminPeersRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let result: AdminPeerResult = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(result)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new request to disconnect, ban or unban a peer
+    pub fn new_post_admin_peer(
+        host: PeerHost,
+        request: &AdminPeerRequest,
+        auth: &str,
+    ) -> StacksHttpRequest {
+        let mut request = StacksHttpRequest::new_for_peer(
+            host,
+            "POST".into(),
+            PATH.into(),
+            HttpRequestContents::new().payload_json(
+                serde_json::to_value(request).expect("FATAL: failed to encode infallible data"),
+            ),
+        )
+        .expect("FATAL: failed to construct request from infallible data");
+        request.add_header("authorization".into(), auth.into());
+        request
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_admin_peer_result(self) -> Result<AdminPeerResult, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let response_json: serde_json::Value = contents.try_into()?;
+        let result: AdminPeerResult = serde_json::from_value(response_json)
+            .map_err(|_e| Error::DecodeError("Failed to decode JSON".to_string()))?;
+        Ok(result)
+    }
+}
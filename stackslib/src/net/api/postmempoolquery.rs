@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashSet;
 use std::fs::OpenOptions;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::{fs, io};
@@ -84,6 +85,8 @@ impl RPCMempoolQueryRequestHandler {
 pub struct StacksMemPoolStream {
     /// Mempool sync data requested
     pub tx_query: MemPoolSyncData,
+    /// If the request was a sketch, then these are the txids the requester is missing
+    pub reconciled_txids: Option<HashSet<Txid>>,
     /// last txid loaded
     pub last_randomized_txid: Txid,
     /// number of transactions visited in the DB so far
@@ -116,6 +119,7 @@ impl StacksMemPoolStream {
 
         Self {
             tx_query,
+            reconciled_txids: None,
             last_randomized_txid,
             num_txs: 0,
             max_txs,
@@ -156,7 +160,16 @@ impl HttpChunkGenerator for StacksMemPoolStream {
         }
 
         let remaining = self.max_txs.saturating_sub(self.num_txs);
-        let (next_txs, next_last_randomized_txid_opt, num_rows_visited) =
+        let find_res = if let Some(reconciled_txids) = self.reconciled_txids.as_ref() {
+            MemPoolDB::static_find_next_reconciled_transactions(
+                &self.mempool_db,
+                reconciled_txids,
+                self.coinbase_height,
+                &self.last_randomized_txid,
+                1,
+                remaining,
+            )
+        } else {
             MemPoolDB::static_find_next_missing_transactions(
                 &self.mempool_db,
                 &self.tx_query,
@@ -165,7 +178,9 @@ impl HttpChunkGenerator for StacksMemPoolStream {
                 1,
                 remaining,
             )
-            .map_err(|e| format!("Failed to find next missing transactions: {:?}", &e))?;
+        };
+        let (next_txs, next_last_randomized_txid_opt, num_rows_visited) =
+            find_res.map_err(|e| format!("Failed to find next missing transactions: {:?}", &e))?;
 
         debug!(
             "Streaming mempool propagation stepped";
@@ -292,7 +307,26 @@ impl RPCRequestHandler for RPCMempoolQueryRequestHandler {
                 }
             };
 
-            Ok(StacksMemPoolStream::new(mempool_db, mempool_query, max_txs, coinbase_height, page_id))
+            // a sketch is decoded once, up front, so we can tell the requester to fall back to
+            // another query if we can't decode it
+            let reconciled_txids = match mempool_query {
+                MemPoolSyncData::Sketch(ref seed, ref sketch) => {
+                    match MemPoolDB::reconcile_mempool_sketch(&mempool_db, seed, sketch) {
+                        Ok(Some(txids)) => Some(txids),
+                        Ok(None) => {
+                            return Err(StacksHttpResponse::new_error(&preamble, &HttpBadRequest::new("Failed to decode mempool sketch".to_string())));
+                        }
+                        Err(e) => {
+                            return Err(StacksHttpResponse::new_error(&preamble, &HttpServerError::new(format!("Failed to reconcile mempool sketch: {:?}", &e))));
+                        }
+                    }
+                }
+                _ => None,
+            };
+
+            let mut stream = StacksMemPoolStream::new(mempool_db, mempool_query, max_txs, coinbase_height, page_id);
+            stream.reconciled_txids = reconciled_txids;
+            Ok(stream)
         });
 
         let stream = match stream_res {
@@ -0,0 +1,514 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use clarity::vm::ast::ASTRules;
+use clarity::vm::contexts::AssetMap;
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::database::STXBalance;
+use clarity::vm::events::{FTEventType, NFTEventType, STXEventType, StacksTransactionEvent};
+use regex::{Captures, Regex};
+use stacks_common::codec::{StacksMessageCodec, MAX_PAYLOAD_LEN};
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::types::net::PeerHost;
+use stacks_common::util::hash::{hex_bytes, to_hex};
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::nakamoto::miner::NakamotoBlockBuilder;
+use crate::chainstate::nakamoto::NakamotoChainState;
+use crate::chainstate::stacks::db::{StacksAccount, StacksBlockHeaderTypes, StacksChainState};
+use crate::chainstate::stacks::events::StacksTransactionReceipt;
+use crate::chainstate::stacks::miner::{BlockBuilder, BlockLimitFunction, TransactionResult};
+use crate::chainstate::stacks::{
+    Error as ChainError, StacksTransaction, TransactionPostConditionMode,
+};
+use crate::net::http::{
+    parse_json, Error, HttpBadRequest, HttpContentType, HttpNotFound, HttpRequest,
+    HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
+    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+};
+use crate::net::httpcore::{
+    HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttpRequest,
+    StacksHttpResponse,
+};
+use crate::net::{Error as NetError, StacksNodeState, TipRequest};
+
+/// Maximum number of transactions that can be simulated in one request
+pub const MAX_SIMULATED_TRANSACTIONS: usize = 64;
+
+/// Request body for `POST /v3/transactions/simulate`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionSimulationRequest {
+    /// Hex-encoded transactions to simulate, in the order in which they should be evaluated.
+    /// Signatures are not checked, so these transactions may be unsigned.
+    pub transactions: Vec<String>,
+}
+
+/// The outcome of simulating a single transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulatedTransaction {
+    pub txid: Txid,
+    /// Whether or not the transaction could be mined at this point.
+    /// If false, `abort_reason` says why, and none of the transaction's effects are visible to
+    /// subsequent simulated transactions.
+    pub mineable: bool,
+    /// Why the transaction could not be mined (e.g. a bad nonce or insufficient balance)
+    pub abort_reason: Option<String>,
+    /// Fee that would be charged
+    pub fee: u64,
+    pub execution_cost: ExecutionCost,
+    /// Hex-encoded, consensus-serialized Clarity result
+    pub result: Option<String>,
+    /// Error from the Clarity VM, if the transaction's result came from a runtime error
+    pub vm_error: Option<String>,
+    /// Whether or not the post-conditions aborted the transaction
+    pub post_condition_aborted: bool,
+    /// Whether or not each post-condition (in order) holds, when considered on its own
+    pub post_conditions: Vec<bool>,
+    /// Assets sent by each principal, in the same format as `AssetMap::to_json()`
+    pub asset_map: Option<serde_json::Value>,
+    /// Events emitted by the transaction, in the same format as the event observer's
+    /// `new_block` payload
+    pub events: Vec<serde_json::Value>,
+}
+
+impl SimulatedTransaction {
+    fn aborted(tx: &StacksTransaction, reason: String) -> Self {
+        Self {
+            txid: tx.txid(),
+            mineable: false,
+            abort_reason: Some(reason),
+            fee: 0,
+            execution_cost: ExecutionCost::ZERO,
+            result: None,
+            vm_error: None,
+            post_condition_aborted: false,
+            post_conditions: vec![],
+            asset_map: None,
+            events: vec![],
+        }
+    }
+
+    /// Summarize a mined transaction's receipt.
+    /// The VM does not report the asset map to the caller, so it is rebuilt from the asset
+    /// transfer and burn events (which are emitted at the same points the asset map is updated).
+    fn from_receipt(
+        tx: &StacksTransaction,
+        fee: u64,
+        receipt: StacksTransactionReceipt,
+    ) -> Result<Self, ChainError> {
+        let txid = tx.txid();
+        let asset_map = asset_map_from_events(&receipt.events)?;
+        let origin_account = StacksAccount {
+            principal: tx.origin_address().into(),
+            nonce: tx.get_origin_nonce(),
+            stx_balance: STXBalance::zero(),
+        };
+        let post_conditions = tx
+            .post_conditions
+            .iter()
+            .map(|post_condition| {
+                StacksChainState::check_transaction_postconditions(
+                    std::slice::from_ref(post_condition),
+                    &TransactionPostConditionMode::Allow,
+                    &origin_account,
+                    &asset_map,
+                    txid.clone(),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let events = receipt
+            .events
+            .iter()
+            .enumerate()
+            .map(|(event_index, event)| {
+                event.json_serialize(event_index, &txid, !receipt.post_condition_aborted)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ChainError::InvalidStacksTransaction(format!("{e:?}"), false))?;
+
+        let result = receipt
+            .result
+            .serialize_to_hex()
+            .map_err(|e| ChainError::InvalidStacksTransaction(format!("{e:?}"), false))?;
+
+        Ok(Self {
+            txid,
+            mineable: true,
+            abort_reason: None,
+            fee,
+            execution_cost: receipt.execution_cost,
+            result: Some(format!("0x{result}")),
+            vm_error: receipt.vm_error,
+            post_condition_aborted: receipt.post_condition_aborted,
+            post_conditions,
+            asset_map: Some(asset_map.to_json()),
+            events,
+        })
+    }
+}
+
+/// Rebuild a transaction's asset map from the events it emitted
+fn asset_map_from_events(events: &[StacksTransactionEvent]) -> Result<AssetMap, ChainError> {
+    let mut asset_map = AssetMap::new();
+    for event in events.iter() {
+        match event {
+            StacksTransactionEvent::STXEvent(STXEventType::STXTransferEvent(data)) => {
+                asset_map.add_stx_transfer(&data.sender, data.amount)?;
+            }
+            StacksTransactionEvent::STXEvent(STXEventType::STXBurnEvent(data)) => {
+                asset_map.add_stx_burn(&data.sender, data.amount)?;
+            }
+            StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(data)) => {
+                asset_map.add_token_transfer(
+                    &data.sender,
+                    data.asset_identifier.clone(),
+                    data.amount,
+                )?;
+            }
+            StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(data)) => {
+                asset_map.add_token_transfer(
+                    &data.sender,
+                    data.asset_identifier.clone(),
+                    data.amount,
+                )?;
+            }
+            StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(data)) => {
+                asset_map.add_asset_transfer(
+                    &data.sender,
+                    data.asset_identifier.clone(),
+                    data.value.clone(),
+                );
+            }
+            StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(data)) => {
+                asset_map.add_asset_transfer(
+                    &data.sender,
+                    data.asset_identifier.clone(),
+                    data.value.clone(),
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(asset_map)
+}
+
+/// Evaluate `txs` in order atop the Nakamoto block `tip`, as if they were the next transactions
+/// in a block that continued `tip`'s tenure.  This uses the same block-building path as block
+/// proposal validation, but the resulting state is always rolled back.
+///
+/// The transactions are never run on the caller's handles.  Instead, this opens a private
+/// chainstate (with its own Clarity instance) and a read-only sortition DB, much like block
+/// proposal validation does, so a simulation can't leave anything behind in the RPC thread's
+/// connections.
+///
+/// Signatures are not checked, but everything else is (nonces, balances, fees, block limits).
+pub fn simulate_transactions(
+    sortdb: &SortitionDB,
+    chainstate: &StacksChainState,
+    tip: &StacksBlockId,
+    txs: &[StacksTransaction],
+) -> Result<Vec<SimulatedTransaction>, ChainError> {
+    let sortdb = SortitionDB::open(&sortdb.path, false, sortdb.pox_constants.clone())?;
+    let (mut chainstate, _) = chainstate.reopen()?;
+    simulate_transactions_at(&sortdb, &mut chainstate, tip, txs)
+}
+
+fn simulate_transactions_at(
+    sortdb: &SortitionDB,
+    chainstate: &mut StacksChainState,
+    tip: &StacksBlockId,
+    txs: &[StacksTransaction],
+) -> Result<Vec<SimulatedTransaction>, ChainError> {
+    let parent_header = NakamotoChainState::get_block_header(chainstate.db(), tip)?
+        .ok_or(ChainError::NoSuchBlockError)?;
+    let StacksBlockHeaderTypes::Nakamoto(parent_nakamoto_header) = &parent_header.anchored_header
+    else {
+        return Err(ChainError::InvalidStacksBlock(
+            "Transactions can only be simulated atop a Nakamoto block".into(),
+        ));
+    };
+    let burn_view = parent_header
+        .burn_view
+        .clone()
+        .ok_or_else(|| ChainError::InvalidStacksBlock(format!("No burn view for block {tip}")))?;
+    let burn_view_sn = SortitionDB::get_block_snapshot_consensus(sortdb.conn(), &burn_view)?
+        .ok_or_else(|| {
+            ChainError::InvalidStacksBlock(format!("No sortition for burn view {burn_view}"))
+        })?;
+    let burn_dbconn = sortdb.index_handle(&burn_view_sn.sortition_id);
+
+    let mut builder = NakamotoBlockBuilder::new(
+        &parent_header,
+        &parent_header.consensus_hash,
+        parent_nakamoto_header.burn_spent,
+        None,
+        None,
+        parent_nakamoto_header.pox_treatment.len(),
+        None,
+    )?;
+    let mut miner_tenure_info = builder.load_tenure_info(chainstate, &burn_dbconn, None)?;
+    let mut tenure_tx = builder.tenure_begin(&burn_dbconn, &mut miner_tenure_info)?;
+    tenure_tx.skip_signature_verification();
+
+    let mut simulated = Vec::with_capacity(txs.len());
+    for tx in txs.iter() {
+        let tx_result = builder.try_mine_tx_with_len(
+            &mut tenure_tx,
+            tx,
+            tx.tx_len(),
+            &BlockLimitFunction::NO_LIMIT_HIT,
+            ASTRules::PrecheckSize,
+        );
+        let simulated_tx = match tx_result {
+            TransactionResult::Success(s) => {
+                SimulatedTransaction::from_receipt(tx, s.fee, s.receipt)?
+            }
+            TransactionResult::Skipped(s) => {
+                SimulatedTransaction::aborted(tx, format!("Skipped: {}", s.error))
+            }
+            TransactionResult::ProcessingError(e) => {
+                SimulatedTransaction::aborted(tx, format!("Processing error: {}", e.error))
+            }
+            TransactionResult::Problematic(p) => {
+                SimulatedTransaction::aborted(tx, format!("Problematic: {}", p.error))
+            }
+        };
+        simulated.push(simulated_tx);
+    }
+
+    // never persist anything
+    tenure_tx.rollback_block();
+    Ok(simulated)
+}
+
+#[derive(Clone, Default)]
+pub struct RPCPostTransactionSimulateRequestHandler {
+    pub transactions: Option<Vec<StacksTransaction>>,
+    pub auth: Option<String>,
+}
+
+impl RPCPostTransactionSimulateRequestHandler {
+    pub fn new(auth: Option<String>) -> Self {
+        Self {
+            transactions: None,
+            auth,
+        }
+    }
+
+    /// Decode a JSON-encoded simulation request
+    fn parse_json(body: &[u8]) -> Result<Vec<StacksTransaction>, Error> {
+        let request: TransactionSimulationRequest = serde_json::from_slice(body)
+            .map_err(|e| Error::DecodeError(format!("Failed to parse body: {e}")))?;
+
+        if request.transactions.is_empty() {
+            return Err(Error::DecodeError(
+                "Invalid Http request: no transactions to simulate".to_string(),
+            ));
+        }
+        if request.transactions.len() > MAX_SIMULATED_TRANSACTIONS {
+            return Err(Error::DecodeError(format!(
+                "Invalid Http request: at most {MAX_SIMULATED_TRANSACTIONS} transactions can be simulated"
+            )));
+        }
+
+        request
+            .transactions
+            .iter()
+            .map(|tx_hex| {
+                let tx_bytes = hex_bytes(tx_hex.strip_prefix("0x").unwrap_or(tx_hex))
+                    .map_err(|_e| Error::DecodeError("Failed to parse tx".into()))?;
+                StacksTransaction::consensus_deserialize(&mut &tx_bytes[..]).map_err(|e| {
+                    Error::DecodeError(format!("Failed to deserialize transaction: {e}"))
+                })
+            })
+            .collect()
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCPostTransactionSimulateRequestHandler {
+    fn verb(&self) -> &'static str {
+        "POST"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(r#"^/v3/transactions/simulate$"#).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        "/v3/transactions/simulate"
+    }
+
+    /// Try to decode this request.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        // If no authorization is set, then the simulation endpoint is not enabled
+        let Some(password) = &self.auth else {
+            return Err(Error::Http(400, "Bad Request.".into()));
+        };
+        let Some(auth_header) = preamble.headers.get("authorization") else {
+            return Err(Error::Http(401, "Unauthorized".into()));
+        };
+        if auth_header != password {
+            return Err(Error::Http(401, "Unauthorized".into()));
+        }
+        if preamble.get_content_length() == 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected non-zero-length body for transaction simulation"
+                    .to_string(),
+            ));
+        }
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(Error::DecodeError(
+                "Invalid Http request: transaction simulation body is too big".to_string(),
+            ));
+        }
+
+        let transactions = match preamble.content_type {
+            Some(HttpContentType::JSON) => Self::parse_json(body)?,
+            Some(_) => {
+                return Err(Error::DecodeError(
+                    "Wrong Content-Type for transaction simulation; expected application/json"
+                        .to_string(),
+                ))
+            }
+            None => {
+                return Err(Error::DecodeError(
+                    "Missing Content-Type for transaction simulation".to_string(),
+                ))
+            }
+        };
+
+        self.transactions = Some(transactions);
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+}
+
+impl RPCRequestHandler for RPCPostTransactionSimulateRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {
+        self.transactions = None;
+    }
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let transactions = self
+            .transactions
+            .take()
+            .ok_or(NetError::SendError("`transactions` not set".into()))?;
+
+        let tip = match node.load_stacks_chain_tip(&preamble, &contents) {
+            Ok(tip) => tip,
+            Err(error_resp) => {
+                return error_resp.try_into_contents().map_err(NetError::from);
+            }
+        };
+
+        let simulation_res =
+            node.with_node_state(|_network, sortdb, chainstate, _mempool, _rpc_args| {
+                simulate_transactions(sortdb, chainstate, &tip, &transactions)
+            });
+
+        let simulated = match simulation_res {
+            Ok(simulated) => simulated,
+            Err(ChainError::InvalidStacksBlock(msg)) => {
+                return StacksHttpResponse::new_error(&preamble, &HttpBadRequest::new(msg))
+                    .try_into_contents()
+                    .map_err(NetError::from);
+            }
+            Err(ChainError::NoSuchBlockError) => {
+                return StacksHttpResponse::new_error(
+                    &preamble,
+                    &HttpNotFound::new(format!("No such block {tip}")),
+                )
+                .try_into_contents()
+                .map_err(NetError::from);
+            }
+            Err(e) => {
+                return StacksHttpResponse::new_error(
+                    &preamble,
+                    &HttpServerError::new(format!("Failed to simulate transactions: {e:?}")),
+                )
+                .try_into_contents()
+                .map_err(NetError::from);
+            }
+        };
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&simulated)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCPostTransactionSimulateRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let simulated: Vec<SimulatedTransaction> = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(simulated)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new transaction simulation request
+    pub fn new_post_transaction_simulate(
+        host: PeerHost,
+        txs: &[StacksTransaction],
+        tip_req: TipRequest,
+    ) -> StacksHttpRequest {
+        let request = TransactionSimulationRequest {
+            transactions: txs
+                .iter()
+                .map(|tx| to_hex(&tx.serialize_to_vec()))
+                .collect(),
+        };
+        StacksHttpRequest::new_for_peer(
+            host,
+            "POST".into(),
+            "/v3/transactions/simulate".into(),
+            HttpRequestContents::new().for_tip(tip_req).payload_json(
+                serde_json::to_value(request).expect("FATAL: failed to encode infallible data"),
+            ),
+        )
+        .expect("FATAL: failed to construct request from infallible data")
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_transaction_simulation(self) -> Result<Vec<SimulatedTransaction>, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let response_json: serde_json::Value = contents.try_into()?;
+        let simulated: Vec<SimulatedTransaction> = serde_json::from_value(response_json)
+            .map_err(|_e| Error::DecodeError("Failed to decode JSON".to_string()))?;
+        Ok(simulated)
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use stacks_common::types::net::PeerAddress;
+
+use super::test_rpc;
+use crate::net::api::postadmincidrs::AdminCidrAction;
+use crate::net::api::postadminpeers::AdminPeerRequest;
+use crate::net::api::*;
+use crate::net::connection::ConnectionOptions;
+use crate::net::http::Error as HttpError;
+use crate::net::httpcore::{StacksHttp, StacksHttpRequest};
+use crate::net::{Error as NetError, ProtocolFamily};
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let request = StacksHttpRequest::new_get_admin_peers(addr.into(), "password");
+    let bytes = request.try_serialize().unwrap();
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler =
+        getadminpeers::RPCGetAdminPeersRequestHandler::new(Some("password".to_string()));
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    // parsed request consumes headers that would not be in a constructed reqeuest
+    parsed_request.clear_headers();
+    parsed_request.add_header("authorization".into(), "password".into());
+    let (preamble, _contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+
+    // wrong password
+    let request = StacksHttpRequest::new_get_admin_peers(addr.into(), "wrong password");
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    match http.handle_try_parse_request(
+        &mut handler,
+        &parsed_preamble.expect_request(),
+        &bytes[offset..],
+    ) {
+        Err(NetError::Http(HttpError::Http(err_code, message))) => {
+            assert_eq!(err_code, 401);
+            assert_eq!(message, "Unauthorized");
+        }
+        x => {
+            error!("Expected HTTP 401, got {:?}", &x);
+            panic!("expected error");
+        }
+    }
+
+    // no admin password configured
+    let mut handler = getadminpeers::RPCGetAdminPeersRequestHandler::new(None);
+    let request = StacksHttpRequest::new_get_admin_peers(addr.into(), "password");
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    match http.handle_try_parse_request(
+        &mut handler,
+        &parsed_preamble.expect_request(),
+        &bytes[offset..],
+    ) {
+        Err(NetError::Http(HttpError::Http(err_code, _))) => {
+            assert_eq!(err_code, 400);
+        }
+        x => {
+            error!("Expected HTTP 400, got {:?}", &x);
+            panic!("expected error");
+        }
+    }
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+
+    let mut requests = vec![];
+
+    // nothing denied yet
+    let request = StacksHttpRequest::new_get_admin_peers(addr.into(), "password");
+    requests.push(request);
+
+    // deny a peer and a CIDR prefix, and read them back
+    let request = StacksHttpRequest::new_post_admin_peer(
+        addr.into(),
+        &AdminPeerRequest::Ban {
+            ip: PeerAddress::from_ipv4(1, 2, 3, 4),
+            port: 20444,
+            duration: Some(3600),
+        },
+        "password",
+    );
+    requests.push(request);
+
+    let request = StacksHttpRequest::new_post_admin_cidr(
+        addr.into(),
+        AdminCidrAction::Deny,
+        "10.0.0.0/8",
+        "password",
+    );
+    requests.push(request);
+
+    let request = StacksHttpRequest::new_get_admin_peers(addr.into(), "password");
+    requests.push(request);
+
+    // wrong password
+    let request = StacksHttpRequest::new_get_admin_peers(addr.into(), "wrong password");
+    requests.push(request);
+
+    let mut responses = test_rpc(function_name!(), requests);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    let peers_info = response.decode_admin_peers().unwrap();
+    assert!(peers_info.denied.is_empty());
+    assert!(peers_info.deny_cidrs.is_empty());
+    assert!(peers_info.allow_cidrs.is_empty());
+
+    let _ = responses.remove(0).decode_admin_peer_result().unwrap();
+    let _ = responses.remove(0).decode_admin_cidrs().unwrap();
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    let peers_info = response.decode_admin_peers().unwrap();
+    assert_eq!(peers_info.denied.len(), 1);
+    assert_eq!(peers_info.denied[0].ip, PeerAddress::from_ipv4(1, 2, 3, 4));
+    assert_eq!(peers_info.denied[0].port, 20444);
+    assert_eq!(peers_info.deny_cidrs, vec!["10.0.0.0/8".to_string()]);
+
+    let response = responses.remove(0);
+    let (preamble, _body) = response.destruct();
+    assert_eq!(preamble.status_code, 401);
+}
@@ -0,0 +1,101 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use super::test_rpc;
+use crate::net::api::*;
+use crate::net::connection::ConnectionOptions;
+use crate::net::http::Error as HttpError;
+use crate::net::httpcore::{HttpPreambleExtensions, StacksHttp, StacksHttpRequest};
+use crate::net::{Error as NetError, ProtocolFamily};
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let request = StacksHttpRequest::new_get_admin_sync_state(addr.into(), "password");
+    let bytes = request.try_serialize().unwrap();
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler =
+        getadminsyncstate::RPCGetAdminSyncStateRequestHandler::new(Some("password".to_string()));
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    // parsed request consumes headers that would not be in a constructed reqeuest
+    parsed_request.clear_headers();
+    parsed_request.add_header("authorization".into(), "password".into());
+    let (preamble, _contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+
+    // wrong password
+    let request = StacksHttpRequest::new_get_admin_sync_state(addr.into(), "wrong password");
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    match http.handle_try_parse_request(
+        &mut handler,
+        &parsed_preamble.expect_request(),
+        &bytes[offset..],
+    ) {
+        Err(NetError::Http(HttpError::Http(err_code, message))) => {
+            assert_eq!(err_code, 401);
+            assert_eq!(message, "Unauthorized");
+        }
+        x => {
+            error!("Expected HTTP 401, got {:?}", &x);
+            panic!("expected error");
+        }
+    }
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+
+    let mut requests = vec![];
+
+    let request = StacksHttpRequest::new_get_admin_sync_state(addr.into(), "password");
+    requests.push(request);
+
+    let mut responses = test_rpc(function_name!(), requests);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+
+    assert_eq!(
+        response.preamble().get_canonical_stacks_tip_height(),
+        Some(1)
+    );
+
+    let sync_state = response.decode_admin_sync_state().unwrap();
+    assert!(!sync_state.work_state.is_empty());
+    assert!(!sync_state.nakamoto_work_state.is_empty());
+
+    // each StackerDB the test peer replicates reports its sync state
+    for stackerdb in sync_state.stackerdbs.iter() {
+        assert!(!stackerdb.state.is_empty());
+    }
+}
@@ -15,6 +15,10 @@
 
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
+use clarity::vm::database::state_proofs::{
+    data_var_key_hash, decode_state_proof, map_entry_key_hash, verify_state_proof, StateProofError,
+    StateProofSigner,
+};
 use clarity::vm::database::{ClarityDeserializable, STXBalance};
 use clarity::vm::types::{QualifiedContractIdentifier, StacksAddressExtensions, TypeSignature};
 use clarity::vm::{ClarityName, ContractName, Value};
@@ -23,14 +27,21 @@ use stacks_common::types::chainstate::{StacksAddress, TrieHash};
 use stacks_common::types::net::PeerHost;
 use stacks_common::types::Address;
 
-use super::test_rpc;
+use super::{test_rpc, TestRPC};
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::coordinator::OnChainRewardSetProvider;
+use crate::chainstate::nakamoto::coordinator::load_nakamoto_reward_set;
+use crate::chainstate::nakamoto::NakamotoChainState;
+use crate::chainstate::stacks::boot::{POX_4_NAME, SIGNERS_NAME};
 use crate::net::api::*;
 use crate::net::connection::ConnectionOptions;
 use crate::net::httpcore::{
     HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
     StacksHttpRequest,
 };
+use crate::net::test::TestEventObserver;
 use crate::net::{ProtocolFamily, TipRequest};
+use crate::util_lib::boot::{boot_code_addr, boot_code_id};
 
 #[test]
 fn test_try_parse_request() {
@@ -203,3 +214,258 @@ fn test_try_make_response() {
     assert_eq!(balance.amount_unlocked(), 1_000_000_000);
     assert_eq!(balance.amount_locked(), 0);
 }
+
+#[test]
+fn test_try_parse_batch_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let keys = vec![
+        TrieHash::from_key("vm-epoch::epoch-version"),
+        TrieHash::from_key("vm::ST1SJ3DTE5DN7X54YDH5D64R3BCB6A2AG2ZQ8YPD5.counter::1::count"),
+        TrieHash::from_key("vm-epoch::epoch-version"),
+    ];
+    let request = StacksHttpRequest::new_getclaritymarf_batch(
+        addr.into(),
+        &keys,
+        TipRequest::SpecificTip(StacksBlockId([0x22; 32])),
+        true,
+    );
+    let bytes = request.try_serialize().unwrap();
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler = getclaritymarfvalue::RPCGetClarityMarfRequestHandler::new();
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    // consumed the list of hashes
+    assert!(handler.marf_key_hash.is_none());
+    assert_eq!(handler.marf_key_hashes, Some(keys));
+
+    parsed_request.clear_headers();
+    let (preamble, _contents) = parsed_request.destruct();
+    assert_eq!(&preamble, request.preamble());
+
+    handler.restart();
+    assert!(handler.marf_key_hashes.is_none());
+
+    // too many keys
+    let keys = vec![
+        TrieHash::from_key("vm-epoch::epoch-version");
+        getclaritymarfvalue::MAX_CLARITY_MARF_BATCH_KEYS + 1
+    ];
+    let request = StacksHttpRequest::new_getclaritymarf_batch(
+        addr.into(),
+        &keys,
+        TipRequest::UseLatestAnchoredTip,
+        true,
+    );
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    assert!(http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .is_err());
+}
+
+#[test]
+fn test_try_make_batch_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let keys = vec![
+        TrieHash::from_key("vm::ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world::1::bar"),
+        TrieHash::from_key(
+            "vm::ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world::1::does-not-exist",
+        ),
+    ];
+
+    let mut requests = vec![];
+
+    // query an existing and a missing key
+    let request = StacksHttpRequest::new_getclaritymarf_batch(
+        addr.into(),
+        &keys,
+        TipRequest::UseLatestAnchoredTip,
+        true,
+    );
+    requests.push(request);
+
+    // query a nonexistent tip
+    let request = StacksHttpRequest::new_getclaritymarf_batch(
+        addr.into(),
+        &keys,
+        TipRequest::SpecificTip(StacksBlockId([0x11; 32])),
+        true,
+    );
+    requests.push(request);
+
+    let mut responses = test_rpc(function_name!(), requests);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    assert_eq!(
+        response.preamble().get_canonical_stacks_tip_height(),
+        Some(1)
+    );
+
+    let resp = response.decode_clarity_marf_batch_response().unwrap();
+    assert_eq!(
+        resp.data,
+        vec![
+            Some("0x0000000000000000000000000000000000".to_string()),
+            None
+        ]
+    );
+    let proof = decode_state_proof(resp.marf_proof.as_ref().unwrap()).unwrap();
+    assert_eq!(proof.entries.len(), 2);
+    assert!(proof.get_entry(&keys[0]).unwrap().value.is_some());
+    assert!(proof.get_entry(&keys[1]).unwrap().value.is_none());
+
+    // no such tip
+    let response = responses.remove(0);
+    let (preamble, _body) = response.destruct();
+    assert_eq!(preamble.status_code, 404);
+}
+
+/// Fetch batch proofs of Clarity state from a Nakamoto node, and verify them against the tip
+/// block's signed header without a chainstate
+#[test]
+fn test_batch_proof_verifies_against_nakamoto_block() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let test_observer = TestEventObserver::new();
+    let mut rpc_test = TestRPC::setup_nakamoto(function_name!(), &test_observer);
+    let tip = rpc_test.canonical_tip.clone();
+
+    // the tip's header and signer set
+    let peer = &mut rpc_test.peer_1;
+    let burnchain = peer.config.burnchain.clone();
+    let sortdb = peer.sortdb.take().unwrap();
+    let header = NakamotoChainState::get_block_header(peer.chainstate().db(), &tip)
+        .unwrap()
+        .unwrap()
+        .anchored_header
+        .as_stacks_nakamoto()
+        .unwrap()
+        .clone();
+    let tenure_sn =
+        SortitionDB::get_block_snapshot_consensus(sortdb.conn(), &header.consensus_hash)
+            .unwrap()
+            .unwrap();
+    let sort_tip_sn = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).unwrap();
+    let reward_set = load_nakamoto_reward_set(
+        burnchain
+            .block_height_to_reward_cycle(tenure_sn.block_height)
+            .unwrap(),
+        &sort_tip_sn.sortition_id,
+        &burnchain,
+        peer.chainstate(),
+        &header.parent_block_id,
+        &sortdb,
+        &OnChainRewardSetProvider::new(),
+    )
+    .unwrap()
+    .unwrap()
+    .0
+    .known_selected_anchor_block_owned()
+    .unwrap();
+    peer.sortdb = Some(sortdb);
+    let signers: Vec<_> = reward_set
+        .signers
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(StateProofSigner::from)
+        .collect();
+
+    let signers_contract = boot_code_id(SIGNERS_NAME, false);
+    let pox_contract = boot_code_id(POX_4_NAME, false);
+    let map_keys: Vec<_> = (0..10).chain([1_000_000]).map(Value::UInt).collect();
+    let var_keys = vec![
+        data_var_key_hash(&signers_contract, "last-set-cycle"),
+        data_var_key_hash(&pox_contract, "configured"),
+        data_var_key_hash(&pox_contract, "does-not-exist"),
+    ];
+
+    let requests = vec![
+        StacksHttpRequest::new_getmapentry_batch(
+            addr.into(),
+            boot_code_addr(false),
+            SIGNERS_NAME.into(),
+            "cycle-set-height".into(),
+            &map_keys,
+            TipRequest::SpecificTip(tip.clone()),
+            true,
+        ),
+        StacksHttpRequest::new_getclaritymarf_batch(
+            addr.into(),
+            &var_keys,
+            TipRequest::SpecificTip(tip.clone()),
+            true,
+        ),
+    ];
+    let mut responses = rpc_test.run(requests);
+
+    // map entries, some of which exist
+    let resp = responses
+        .remove(0)
+        .decode_map_entry_batch_response()
+        .unwrap();
+    assert_eq!(resp.index_block_hash, tip);
+    assert!(resp.data.iter().any(|data| data.is_some()));
+    assert!(resp.data.last().unwrap().is_none());
+
+    let map_paths: Vec<_> = map_keys
+        .iter()
+        .map(|key| map_entry_key_hash(&signers_contract, "cycle-set-height", key).unwrap())
+        .collect();
+    let entries: Vec<_> = map_paths
+        .iter()
+        .cloned()
+        .zip(resp.data.iter().map(|data| data.as_deref()))
+        .collect();
+    let proof = decode_state_proof(resp.marf_proof.as_ref().unwrap()).unwrap();
+    verify_state_proof(&header, &signers, &proof, &entries).unwrap();
+
+    // the proof can't claim a missing entry exists, or vice versa
+    let mut bad_entries = entries.clone();
+    bad_entries.last_mut().unwrap().1 = Some("0x0a0100000000000000000000000000000001");
+    assert_eq!(
+        verify_state_proof(&header, &signers, &proof, &bad_entries),
+        Err(StateProofError::ValueMismatch(
+            map_paths.last().unwrap().clone()
+        ))
+    );
+
+    // data vars, one of which does not exist
+    let resp = responses
+        .remove(0)
+        .decode_clarity_marf_batch_response()
+        .unwrap();
+    assert_eq!(resp.index_block_hash, tip);
+    assert!(resp.data[0].is_some());
+    assert!(resp.data[1].is_some());
+    assert!(resp.data[2].is_none());
+
+    let entries: Vec<_> = var_keys
+        .iter()
+        .cloned()
+        .zip(resp.data.iter().map(|data| data.as_deref()))
+        .collect();
+    let proof = decode_state_proof(resp.marf_proof.as_ref().unwrap()).unwrap();
+    verify_state_proof(&header, &signers, &proof, &entries).unwrap();
+
+    // the proof is only good against this block's state root
+    let mut other_header = header.clone();
+    other_header.state_index_root = TrieHash([0x01; 32]);
+    assert!(verify_state_proof(&other_header, &signers, &proof, &entries).is_err());
+}
@@ -16,10 +16,12 @@
 
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
+use clarity::vm::database::state_proofs::{decode_state_proof, map_entry_key_hash};
 use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StacksAddressExtensions};
 use clarity::vm::{ClarityName, ContractName, Value};
 use stacks_common::codec::StacksMessageCodec;
 use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::marf::MARFValue;
 use stacks_common::types::net::PeerHost;
 use stacks_common::types::Address;
 
@@ -213,6 +215,167 @@ fn test_try_make_response() {
     assert_eq!(resp.marf_proof, Some("".to_string()));
 }
 
+#[test]
+fn test_try_parse_batch_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let keys = vec![Value::UInt(1), Value::UInt(2), Value::UInt(1)];
+    let request = StacksHttpRequest::new_getmapentry_batch(
+        addr.into(),
+        StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap(),
+        "hello-world".try_into().unwrap(),
+        "test-map".into(),
+        &keys,
+        TipRequest::SpecificTip(StacksBlockId([0x22; 32])),
+        true,
+    );
+    let bytes = request.try_serialize().unwrap();
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler = getmapentry::RPCGetMapEntryRequestHandler::new();
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    // consumed the list of keys
+    assert!(handler.key.is_none());
+    assert_eq!(handler.keys, Some(keys));
+
+    parsed_request.clear_headers();
+    let (preamble, _contents) = parsed_request.destruct();
+    assert_eq!(&preamble, request.preamble());
+
+    handler.restart();
+    assert!(handler.keys.is_none());
+
+    // no keys, and too many keys
+    for num_keys in [0, getmapentry::MAX_MAP_ENTRY_BATCH_KEYS + 1] {
+        let keys: Vec<_> = (0..num_keys).map(|i| Value::UInt(i as u128)).collect();
+        let request = StacksHttpRequest::new_getmapentry_batch(
+            addr.into(),
+            StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap(),
+            "hello-world".try_into().unwrap(),
+            "test-map".into(),
+            &keys,
+            TipRequest::UseLatestAnchoredTip,
+            true,
+        );
+        let bytes = request.try_serialize().unwrap();
+        let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+        assert!(http
+            .handle_try_parse_request(
+                &mut handler,
+                &parsed_preamble.expect_request(),
+                &bytes[offset..],
+            )
+            .is_err());
+    }
+}
+
+#[test]
+fn test_try_make_batch_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let contract_addr =
+        StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap();
+    let keys = vec![Value::UInt(1), Value::UInt(2)];
+
+    let mut requests = vec![];
+
+    // query an existing and a missing entry
+    let request = StacksHttpRequest::new_getmapentry_batch(
+        addr.into(),
+        contract_addr.clone(),
+        "hello-world".try_into().unwrap(),
+        "test-map".into(),
+        &keys,
+        TipRequest::UseLatestAnchoredTip,
+        true,
+    );
+    requests.push(request);
+
+    // same, without a proof
+    let request = StacksHttpRequest::new_getmapentry_batch(
+        addr.into(),
+        contract_addr.clone(),
+        "hello-world".try_into().unwrap(),
+        "test-map".into(),
+        &keys,
+        TipRequest::UseLatestAnchoredTip,
+        false,
+    );
+    requests.push(request);
+
+    // query a nonexistent tip
+    let request = StacksHttpRequest::new_getmapentry_batch(
+        addr.into(),
+        contract_addr.clone(),
+        "hello-world".try_into().unwrap(),
+        "test-map".into(),
+        &keys,
+        TipRequest::SpecificTip(StacksBlockId([0x11; 32])),
+        true,
+    );
+    requests.push(request);
+
+    let mut responses = test_rpc(function_name!(), requests);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    assert_eq!(
+        response.preamble().get_canonical_stacks_tip_height(),
+        Some(1)
+    );
+
+    let resp = response.decode_map_entry_batch_response().unwrap();
+    assert_eq!(
+        resp.data,
+        vec![
+            Some("0x0a0100000000000000000000000000000002".to_string()),
+            None
+        ]
+    );
+
+    // one proof covers the value of the first key and the absence of the second
+    let contract =
+        QualifiedContractIdentifier::parse("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world")
+            .unwrap();
+    let proof = decode_state_proof(resp.marf_proof.as_ref().unwrap()).unwrap();
+    assert_eq!(proof.entries.len(), 2);
+    let present_path = map_entry_key_hash(&contract, "test-map", &keys[0]).unwrap();
+    let absent_path = map_entry_key_hash(&contract, "test-map", &keys[1]).unwrap();
+    assert_eq!(
+        proof.get_entry(&present_path).unwrap().value,
+        Some(MARFValue::from_value(
+            "0a0100000000000000000000000000000002"
+        ))
+    );
+    assert_eq!(proof.get_entry(&absent_path).unwrap().value, None);
+
+    let response = responses.remove(0);
+    let resp = response.decode_map_entry_batch_response().unwrap();
+    assert_eq!(
+        resp.data,
+        vec![
+            Some("0x0a0100000000000000000000000000000002".to_string()),
+            None
+        ]
+    );
+    assert!(resp.marf_proof.is_none());
+
+    // no such tip
+    let response = responses.remove(0);
+    let (preamble, _body) = response.destruct();
+    assert_eq!(preamble.status_code, 404);
+}
+
 /*
 #[test]
 #[ignore]
@@ -57,6 +57,8 @@ use crate::net::{
 mod callreadonly;
 mod get_tenures_fork_info;
 mod getaccount;
+mod getadminpeers;
+mod getadminsyncstate;
 mod getattachment;
 mod getattachmentsinv;
 mod getblock;
@@ -85,8 +87,13 @@ mod getstxtransfercost;
 mod gettenure;
 mod gettenureinfo;
 mod gettenuretip;
+mod gettransaction;
 mod gettransaction_unconfirmed;
 mod liststackerdbreplicas;
+mod postadmincidrs;
+mod postadminloglevels;
+mod postadminmempool;
+mod postadminpeers;
 mod postblock;
 mod postblock_proposal;
 mod postblock_v3;
@@ -95,6 +102,7 @@ mod postmempoolquery;
 mod postmicroblock;
 mod poststackerdbchunk;
 mod posttransaction;
+mod posttransaction_simulate;
 
 const TEST_CONTRACT: &str = "
     (define-trait test-trait
@@ -339,6 +347,13 @@ impl<'a> TestRPC<'a> {
         peer_1.rpc_handler_args = rpc_handler_args_opt_1;
         peer_2.rpc_handler_args = rpc_handler_args_opt_2;
 
+        // index confirmed transactions.  Blocks are processed by the coordinator's own
+        // chainstate handle, so it needs to be set there too.
+        peer_1.chainstate().txindex = true;
+        peer_2.chainstate().txindex = true;
+        peer_1.coord.chain_state_db.txindex = true;
+        peer_2.coord.chain_state_db.txindex = true;
+
         // mine one block with a contract in it
         // first the coinbase
         // make a coinbase for this miner
@@ -0,0 +1,202 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use stacks_common::types::net::PeerAddress;
+
+use super::test_rpc;
+use crate::net::api::postadmincidrs::{cidr_contains, cidr_to_string, parse_cidr, AdminCidrAction};
+use crate::net::api::*;
+use crate::net::connection::ConnectionOptions;
+use crate::net::http::Error as HttpError;
+use crate::net::httpcore::{RPCRequestHandler, StacksHttp, StacksHttpRequest};
+use crate::net::{Error as NetError, ProtocolFamily};
+
+#[test]
+fn test_parse_cidr() {
+    let (prefix, mask) = parse_cidr("10.1.2.3/8").unwrap();
+    assert_eq!(prefix, PeerAddress::from_ipv4(10, 0, 0, 0));
+    assert_eq!(mask, 104);
+    assert_eq!(cidr_to_string(&prefix, mask), "10.0.0.0/8");
+    assert!(cidr_contains(
+        &prefix,
+        mask,
+        &PeerAddress::from_ipv4(10, 255, 0, 1)
+    ));
+    assert!(!cidr_contains(
+        &prefix,
+        mask,
+        &PeerAddress::from_ipv4(11, 0, 0, 1)
+    ));
+
+    let (prefix, mask) = parse_cidr("1.2.3.4/32").unwrap();
+    assert_eq!(prefix, PeerAddress::from_ipv4(1, 2, 3, 4));
+    assert_eq!(mask, 128);
+    assert_eq!(cidr_to_string(&prefix, mask), "1.2.3.4/32");
+
+    let (prefix, mask) = parse_cidr("fd00::1/8").unwrap();
+    assert_eq!(
+        prefix,
+        PeerAddress([0xfd, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+    );
+    assert_eq!(mask, 8);
+    assert_eq!(cidr_to_string(&prefix, mask), "fd00::/8");
+
+    for bad in [
+        "10.0.0.0",
+        "10.0.0.0/0",
+        "10.0.0.0/33",
+        "fd00::/129",
+        "10.0.0/8",
+        "10.0.0.0/abc",
+        "",
+    ] {
+        assert!(parse_cidr(bad).is_err(), "parsed {bad}");
+    }
+}
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let request = StacksHttpRequest::new_post_admin_cidr(
+        addr.into(),
+        AdminCidrAction::Deny,
+        "192.168.0.0/16",
+        "password",
+    );
+    let bytes = request.try_serialize().unwrap();
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler =
+        postadmincidrs::RPCPostAdminCidrsRequestHandler::new(Some("password".to_string()));
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    assert_eq!(
+        handler.request,
+        Some((
+            AdminCidrAction::Deny,
+            PeerAddress::from_ipv4(192, 168, 0, 0),
+            112
+        ))
+    );
+
+    // parsed request consumes headers that would not be in a constructed reqeuest
+    parsed_request.clear_headers();
+    parsed_request.add_header("authorization".into(), "password".into());
+    let (preamble, _contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+
+    handler.restart();
+    assert!(handler.request.is_none());
+
+    // bad CIDR prefix
+    let request = StacksHttpRequest::new_post_admin_cidr(
+        addr.into(),
+        AdminCidrAction::Deny,
+        "192.168.0.0/64",
+        "password",
+    );
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    match http.handle_try_parse_request(
+        &mut handler,
+        &parsed_preamble.expect_request(),
+        &bytes[offset..],
+    ) {
+        Err(NetError::Http(HttpError::DecodeError(..))) => {}
+        x => {
+            error!("Expected decode error, got {:?}", &x);
+            panic!("expected error");
+        }
+    }
+
+    // wrong password
+    let request = StacksHttpRequest::new_post_admin_cidr(
+        addr.into(),
+        AdminCidrAction::Deny,
+        "192.168.0.0/16",
+        "wrong password",
+    );
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    match http.handle_try_parse_request(
+        &mut handler,
+        &parsed_preamble.expect_request(),
+        &bytes[offset..],
+    ) {
+        Err(NetError::Http(HttpError::Http(err_code, message))) => {
+            assert_eq!(err_code, 401);
+            assert_eq!(message, "Unauthorized");
+        }
+        x => {
+            error!("Expected HTTP 401, got {:?}", &x);
+            panic!("expected error");
+        }
+    }
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+
+    let mut requests = vec![];
+    for (action, cidr) in [
+        (AdminCidrAction::Deny, "10.0.0.0/8"),
+        (AdminCidrAction::Deny, "fd00::/8"),
+        (AdminCidrAction::Allow, "192.168.1.0/24"),
+        (AdminCidrAction::RemoveDeny, "10.0.0.0/8"),
+        (AdminCidrAction::RemoveAllow, "192.168.1.0/24"),
+    ] {
+        let request = StacksHttpRequest::new_post_admin_cidr(addr.into(), action, cidr, "password");
+        requests.push(request);
+    }
+
+    let mut responses = test_rpc(function_name!(), requests);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    let cidrs_info = response.decode_admin_cidrs().unwrap();
+    assert_eq!(cidrs_info.deny_cidrs, vec!["10.0.0.0/8".to_string()]);
+    assert!(cidrs_info.allow_cidrs.is_empty());
+    assert_eq!(cidrs_info.disconnected, 0);
+
+    let cidrs_info = responses.remove(0).decode_admin_cidrs().unwrap();
+    assert_eq!(cidrs_info.deny_cidrs.len(), 2);
+    assert!(cidrs_info.deny_cidrs.contains(&"fd00::/8".to_string()));
+
+    let cidrs_info = responses.remove(0).decode_admin_cidrs().unwrap();
+    assert_eq!(cidrs_info.allow_cidrs, vec!["192.168.1.0/24".to_string()]);
+
+    let cidrs_info = responses.remove(0).decode_admin_cidrs().unwrap();
+    assert_eq!(cidrs_info.deny_cidrs, vec!["fd00::/8".to_string()]);
+    assert_eq!(cidrs_info.allow_cidrs, vec!["192.168.1.0/24".to_string()]);
+
+    let cidrs_info = responses.remove(0).decode_admin_cidrs().unwrap();
+    assert_eq!(cidrs_info.deny_cidrs, vec!["fd00::/8".to_string()]);
+    assert!(cidrs_info.allow_cidrs.is_empty());
+}
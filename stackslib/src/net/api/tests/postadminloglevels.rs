@@ -0,0 +1,171 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use stacks_common::util::log::get_module_loglevel;
+
+use super::test_rpc;
+use crate::net::api::postadminloglevels::parse_log_level;
+use crate::net::api::*;
+use crate::net::connection::ConnectionOptions;
+use crate::net::http::Error as HttpError;
+use crate::net::httpcore::{RPCRequestHandler, StacksHttp, StacksHttpRequest};
+use crate::net::{Error as NetError, ProtocolFamily};
+
+#[test]
+fn test_parse_log_level() {
+    assert_eq!(parse_log_level("trace").unwrap(), slog::Level::Trace);
+    assert_eq!(parse_log_level("DEBUG").unwrap(), slog::Level::Debug);
+    assert_eq!(parse_log_level("warn").unwrap(), slog::Level::Warning);
+    assert_eq!(parse_log_level("warning").unwrap(), slog::Level::Warning);
+    assert_eq!(parse_log_level("critical").unwrap(), slog::Level::Critical);
+    assert!(parse_log_level("loud").is_err());
+    assert!(parse_log_level("").is_err());
+}
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let mut levels = BTreeMap::new();
+    levels.insert("blockstack_lib::net".to_string(), Some("debug".to_string()));
+    levels.insert("blockstack_lib::chainstate".to_string(), None);
+
+    let request =
+        StacksHttpRequest::new_post_admin_log_levels(addr.into(), levels.clone(), "password");
+    let bytes = request.try_serialize().unwrap();
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler =
+        postadminloglevels::RPCPostAdminLogLevelsRequestHandler::new(Some("password".to_string()));
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    assert_eq!(
+        handler.levels,
+        Some(vec![
+            ("blockstack_lib::chainstate".to_string(), None),
+            ("blockstack_lib::net".to_string(), Some(slog::Level::Debug)),
+        ])
+    );
+
+    // parsed request consumes headers that would not be in a constructed reqeuest
+    parsed_request.clear_headers();
+    parsed_request.add_header("authorization".into(), "password".into());
+    let (preamble, _contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+
+    handler.restart();
+    assert!(handler.levels.is_none());
+
+    // bad module paths and levels
+    for (module, level) in [
+        ("blockstack_lib::net", "loud"),
+        ("blockstack_lib::", "debug"),
+        ("blockstack lib", "debug"),
+        ("", "debug"),
+    ] {
+        let mut levels = BTreeMap::new();
+        levels.insert(module.to_string(), Some(level.to_string()));
+        let request = StacksHttpRequest::new_post_admin_log_levels(addr.into(), levels, "password");
+        let bytes = request.try_serialize().unwrap();
+        let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+        match http.handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        ) {
+            Err(NetError::Http(HttpError::DecodeError(..))) => {}
+            x => {
+                error!("Expected decode error, got {:?}", &x);
+                panic!("expected error");
+            }
+        }
+    }
+
+    // wrong password
+    let request =
+        StacksHttpRequest::new_post_admin_log_levels(addr.into(), levels, "wrong password");
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    match http.handle_try_parse_request(
+        &mut handler,
+        &parsed_preamble.expect_request(),
+        &bytes[offset..],
+    ) {
+        Err(NetError::Http(HttpError::Http(err_code, message))) => {
+            assert_eq!(err_code, 401);
+            assert_eq!(message, "Unauthorized");
+        }
+        x => {
+            error!("Expected HTTP 401, got {:?}", &x);
+            panic!("expected error");
+        }
+    }
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+
+    // log levels are process-wide, so use a module that no other test touches
+    let module = "blockstack_lib::net::api::tests::postadminloglevels::test_try_make_response";
+
+    let mut requests = vec![];
+
+    let mut levels = BTreeMap::new();
+    levels.insert(module.to_string(), Some("trace".to_string()));
+    let request = StacksHttpRequest::new_post_admin_log_levels(addr.into(), levels, "password");
+    requests.push(request);
+
+    // read back without changing anything
+    let request =
+        StacksHttpRequest::new_post_admin_log_levels(addr.into(), BTreeMap::new(), "password");
+    requests.push(request);
+
+    let mut levels = BTreeMap::new();
+    levels.insert(module.to_string(), None);
+    let request = StacksHttpRequest::new_post_admin_log_levels(addr.into(), levels, "password");
+    requests.push(request);
+
+    let mut responses = test_rpc(function_name!(), requests);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    let log_levels = response.decode_admin_log_levels().unwrap();
+    assert_eq!(log_levels.modules.get(module), Some(&"trace".to_string()));
+
+    let log_levels = responses.remove(0).decode_admin_log_levels().unwrap();
+    assert_eq!(log_levels.modules.get(module), Some(&"trace".to_string()));
+
+    let log_levels = responses.remove(0).decode_admin_log_levels().unwrap();
+    assert!(log_levels.modules.get(module).is_none());
+    assert_eq!(
+        get_module_loglevel(&format!("{module}::inner")),
+        parse_log_level(&log_levels.default).unwrap()
+    );
+}
@@ -0,0 +1,162 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use super::TestRPC;
+use crate::burnchains::Txid;
+use crate::net::api::postadminmempool::AdminMempoolRequest;
+use crate::net::api::*;
+use crate::net::connection::ConnectionOptions;
+use crate::net::http::Error as HttpError;
+use crate::net::httpcore::{RPCRequestHandler, StacksHttp, StacksHttpRequest};
+use crate::net::{Error as NetError, ProtocolFamily};
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let mempool_request = AdminMempoolRequest::Blacklist {
+        txids: vec![Txid([0x11; 32]), Txid([0x22; 32])],
+    };
+    let request =
+        StacksHttpRequest::new_post_admin_mempool(addr.into(), &mempool_request, "password");
+    let bytes = request.try_serialize().unwrap();
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler =
+        postadminmempool::RPCPostAdminMempoolRequestHandler::new(Some("password".to_string()));
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    assert_eq!(handler.request, Some(mempool_request));
+
+    // parsed request consumes headers that would not be in a constructed reqeuest
+    parsed_request.clear_headers();
+    parsed_request.add_header("authorization".into(), "password".into());
+    let (preamble, _contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+
+    handler.restart();
+    assert!(handler.request.is_none());
+
+    // no txids
+    let request = StacksHttpRequest::new_post_admin_mempool(
+        addr.into(),
+        &AdminMempoolRequest::Drop { txids: vec![] },
+        "password",
+    );
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    match http.handle_try_parse_request(
+        &mut handler,
+        &parsed_preamble.expect_request(),
+        &bytes[offset..],
+    ) {
+        Err(NetError::Http(HttpError::DecodeError(..))) => {}
+        x => {
+            error!("Expected decode error, got {:?}", &x);
+            panic!("expected error");
+        }
+    }
+
+    // no admin password configured
+    let mut handler = postadminmempool::RPCPostAdminMempoolRequestHandler::new(None);
+    let request = StacksHttpRequest::new_post_admin_mempool(
+        addr.into(),
+        &AdminMempoolRequest::GarbageCollect,
+        "password",
+    );
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    match http.handle_try_parse_request(
+        &mut handler,
+        &parsed_preamble.expect_request(),
+        &bytes[offset..],
+    ) {
+        Err(NetError::Http(HttpError::Http(err_code, _))) => {
+            assert_eq!(err_code, 400);
+        }
+        x => {
+            error!("Expected HTTP 400, got {:?}", &x);
+            panic!("expected error");
+        }
+    }
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+
+    let test_rpc = TestRPC::setup(function_name!());
+    let mempool_txids = test_rpc.mempool_txids.clone();
+    let num_txs = mempool_txids.len() as u64;
+
+    let mut requests = vec![];
+
+    // drop one transaction, plus one that isn't there
+    let request = StacksHttpRequest::new_post_admin_mempool(
+        addr.into(),
+        &AdminMempoolRequest::Drop {
+            txids: vec![mempool_txids[0], Txid([0x11; 32])],
+        },
+        "password",
+    );
+    requests.push(request);
+
+    // blacklist two more
+    let request = StacksHttpRequest::new_post_admin_mempool(
+        addr.into(),
+        &AdminMempoolRequest::Blacklist {
+            txids: vec![mempool_txids[1], mempool_txids[2]],
+        },
+        "password",
+    );
+    requests.push(request);
+
+    // nothing is old enough to garbage-collect
+    let request = StacksHttpRequest::new_post_admin_mempool(
+        addr.into(),
+        &AdminMempoolRequest::GarbageCollect,
+        "password",
+    );
+    requests.push(request);
+
+    let mut responses = test_rpc.run(requests);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    let result = response.decode_admin_mempool_result().unwrap();
+    assert_eq!(result.removed, 1);
+    assert_eq!(result.mempool_size, num_txs - 1);
+
+    let result = responses.remove(0).decode_admin_mempool_result().unwrap();
+    assert_eq!(result.removed, 2);
+    assert_eq!(result.mempool_size, num_txs - 3);
+
+    let result = responses.remove(0).decode_admin_mempool_result().unwrap();
+    assert_eq!(result.removed, 0);
+    assert_eq!(result.mempool_size, num_txs - 3);
+}
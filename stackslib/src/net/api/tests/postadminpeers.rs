@@ -0,0 +1,204 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use stacks_common::types::net::PeerAddress;
+use stacks_common::util::get_epoch_time_secs;
+
+use super::test_rpc;
+use crate::net::api::postadminpeers::AdminPeerRequest;
+use crate::net::api::*;
+use crate::net::connection::ConnectionOptions;
+use crate::net::http::{Error as HttpError, HttpRequestContents};
+use crate::net::httpcore::{RPCRequestHandler, StacksHttp, StacksHttpRequest};
+use crate::net::{Error as NetError, ProtocolFamily};
+
+#[test]
+fn test_parse_admin_peer_request() {
+    let request: AdminPeerRequest =
+        serde_json::from_str(r#"{"action":"ban","ip":"1.2.3.4","port":20444}"#).unwrap();
+    assert_eq!(
+        request,
+        AdminPeerRequest::Ban {
+            ip: PeerAddress::from_ipv4(1, 2, 3, 4),
+            port: 20444,
+            duration: None
+        }
+    );
+
+    let request: AdminPeerRequest =
+        serde_json::from_str(r#"{"action":"disconnect","ip":"::1","port":20444}"#).unwrap();
+    assert_eq!(
+        request,
+        AdminPeerRequest::Disconnect {
+            ip: PeerAddress([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]),
+            port: 20444,
+        }
+    );
+
+    assert!(serde_json::from_str::<AdminPeerRequest>(
+        r#"{"action":"ban","ip":"not an ip","port":20444}"#
+    )
+    .is_err());
+    assert!(serde_json::from_str::<AdminPeerRequest>(
+        r#"{"action":"nuke","ip":"1.2.3.4","port":20444}"#
+    )
+    .is_err());
+}
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let peer_request = AdminPeerRequest::Ban {
+        ip: PeerAddress::from_ipv4(1, 2, 3, 4),
+        port: 20444,
+        duration: Some(60),
+    };
+    let request = StacksHttpRequest::new_post_admin_peer(addr.into(), &peer_request, "password");
+    let bytes = request.try_serialize().unwrap();
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler =
+        postadminpeers::RPCPostAdminPeersRequestHandler::new(Some("password".to_string()));
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    assert_eq!(handler.request, Some(peer_request.clone()));
+
+    // parsed request consumes headers that would not be in a constructed reqeuest
+    parsed_request.clear_headers();
+    parsed_request.add_header("authorization".into(), "password".into());
+    let (preamble, _contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+
+    handler.restart();
+    assert!(handler.request.is_none());
+
+    // missing authorization header
+    let request = StacksHttpRequest::new_for_peer(
+        addr.into(),
+        "POST".into(),
+        "/v3/admin/peers".into(),
+        HttpRequestContents::new().payload_json(serde_json::to_value(&peer_request).unwrap()),
+    )
+    .unwrap();
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    match http.handle_try_parse_request(
+        &mut handler,
+        &parsed_preamble.expect_request(),
+        &bytes[offset..],
+    ) {
+        Err(NetError::Http(HttpError::Http(err_code, message))) => {
+            assert_eq!(err_code, 401);
+            assert_eq!(message, "Unauthorized");
+        }
+        x => {
+            error!("Expected HTTP 401, got {:?}", &x);
+            panic!("expected error");
+        }
+    }
+    assert!(handler.request.is_none());
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let peer_ip = PeerAddress::from_ipv4(1, 2, 3, 4);
+
+    let mut requests = vec![];
+
+    // not connected, so nothing to do
+    let request = StacksHttpRequest::new_post_admin_peer(
+        addr.into(),
+        &AdminPeerRequest::Disconnect {
+            ip: peer_ip,
+            port: 20444,
+        },
+        "password",
+    );
+    requests.push(request);
+
+    let request = StacksHttpRequest::new_post_admin_peer(
+        addr.into(),
+        &AdminPeerRequest::Ban {
+            ip: peer_ip,
+            port: 20444,
+            duration: Some(3600),
+        },
+        "password",
+    );
+    requests.push(request);
+
+    let request = StacksHttpRequest::new_get_admin_peers(addr.into(), "password");
+    requests.push(request);
+
+    let request = StacksHttpRequest::new_post_admin_peer(
+        addr.into(),
+        &AdminPeerRequest::Unban {
+            ip: peer_ip,
+            port: 20444,
+        },
+        "password",
+    );
+    requests.push(request);
+
+    let request = StacksHttpRequest::new_get_admin_peers(addr.into(), "password");
+    requests.push(request);
+
+    let now = get_epoch_time_secs();
+    let mut responses = test_rpc(function_name!(), requests);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    let result = response.decode_admin_peer_result().unwrap();
+    assert_eq!(result.disconnected, 0);
+    assert_eq!(result.denied_until, None);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    let result = response.decode_admin_peer_result().unwrap();
+    assert_eq!(result.disconnected, 0);
+    let denied_until = result.denied_until.unwrap();
+    assert!(denied_until >= now + 3600);
+
+    let peers_info = responses.remove(0).decode_admin_peers().unwrap();
+    assert_eq!(peers_info.denied.len(), 1);
+    assert_eq!(peers_info.denied[0].ip, peer_ip);
+    assert_eq!(peers_info.denied[0].port, 20444);
+    assert_eq!(peers_info.denied[0].denied_until, denied_until);
+
+    let result = responses.remove(0).decode_admin_peer_result().unwrap();
+    assert_eq!(result.disconnected, 0);
+    assert_eq!(result.denied_until, None);
+
+    let peers_info = responses.remove(0).decode_admin_peers().unwrap();
+    assert!(peers_info.denied.is_empty());
+}
@@ -48,6 +48,7 @@ use crate::net::httpcore::{
 };
 use crate::net::{Error as NetError, ProtocolFamily, TipRequest};
 use crate::util_lib::db::DBConn;
+use crate::util_lib::sketch::SetSketch;
 
 #[test]
 fn test_try_parse_request() {
@@ -125,6 +126,50 @@ fn test_try_make_response() {
     assert!(page.is_none());
 }
 
+#[test]
+fn test_try_make_sketch_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+
+    let test_rpc = TestRPC::setup(function_name!());
+    let mempool_txids = test_rpc.mempool_txids.clone();
+    let mempool_txids: HashSet<_> = mempool_txids.iter().copied().collect();
+
+    let mut requests = vec![];
+
+    // empty sketch -- we get everything
+    let request = StacksHttpRequest::new_mempool_query(
+        addr.into(),
+        MemPoolSyncData::Sketch([0x00; 32], SetSketch::new(192)),
+        Some(Txid([0x00; 32])),
+    );
+    requests.push(request);
+
+    // sketch that's too small to decode -- the query fails
+    let mut sketch = SetSketch::new(3);
+    for key in 0..100 {
+        sketch.insert(key);
+    }
+    let request = StacksHttpRequest::new_mempool_query(
+        addr.into(),
+        MemPoolSyncData::Sketch([0x00; 32], sketch),
+        Some(Txid([0x00; 32])),
+    );
+    requests.push(request);
+
+    let mut responses = test_rpc.run(requests);
+
+    let response = responses.remove(0);
+    let (txs, page) = response.decode_mempool_txs_page().unwrap();
+    let received_txids: HashSet<_> = txs.iter().map(|tx| tx.txid()).collect();
+
+    assert_eq!(received_txids, mempool_txids);
+    assert!(page.is_none());
+
+    let response = responses.remove(0);
+    let (preamble, body) = response.destruct();
+    assert_eq!(preamble.status_code, 400);
+}
+
 #[test]
 fn test_stream_mempool_txs() {
     let mut chainstate = instantiate_chainstate(false, 0x80000000, function_name!());
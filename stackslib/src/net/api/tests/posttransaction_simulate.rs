@@ -0,0 +1,316 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use clarity::types::chainstate::StacksPrivateKey;
+use clarity::vm::types::PrincipalData;
+use clarity::vm::ContractName;
+use stacks_common::types::chainstate::{StacksAddress, StacksBlockId};
+use stacks_common::util::hash::Hash160;
+
+use super::TestRPC;
+use crate::chainstate::nakamoto::NakamotoChainState;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::{
+    FungibleConditionCode, PostConditionPrincipal, StacksTransaction, TokenTransferMemo,
+    TransactionAuth, TransactionPayload, TransactionPostCondition, TransactionPostConditionMode,
+    TransactionSmartContract, TransactionVersion,
+};
+use crate::net::api::*;
+use crate::net::connection::ConnectionOptions;
+use crate::net::httpcore::{
+    HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+};
+use crate::net::test::TestEventObserver;
+use crate::net::{ProtocolFamily, TipRequest};
+use crate::util_lib::strings::StacksString;
+
+fn make_transfer(privk: &StacksPrivateKey, nonce: u64, amount: u64) -> StacksTransaction {
+    let recipient = StacksAddress::new(1, Hash160([0xff; 20])).unwrap();
+    let payload =
+        TransactionPayload::TokenTransfer(recipient.into(), amount, TokenTransferMemo([0u8; 34]));
+    let auth = TransactionAuth::from_p2pkh(privk).unwrap();
+    let mut tx = StacksTransaction::new(TransactionVersion::Testnet, auth, payload);
+    tx.chain_id = 0x80000000;
+    tx.set_origin_nonce(nonce);
+    tx.set_post_condition_mode(TransactionPostConditionMode::Allow);
+    tx.set_tx_fee(300);
+    tx
+}
+
+fn make_deploy(privk: &StacksPrivateKey, nonce: u64, name: &str, code: &str) -> StacksTransaction {
+    let payload = TransactionPayload::SmartContract(
+        TransactionSmartContract {
+            name: ContractName::try_from(name).unwrap(),
+            code_body: StacksString::from_str(code).unwrap(),
+        },
+        None,
+    );
+    let auth = TransactionAuth::from_p2pkh(privk).unwrap();
+    let mut tx = StacksTransaction::new(TransactionVersion::Testnet, auth, payload);
+    tx.chain_id = 0x80000000;
+    tx.set_origin_nonce(nonce);
+    tx.set_post_condition_mode(TransactionPostConditionMode::Allow);
+    tx.set_tx_fee(300);
+    tx
+}
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let privk = StacksPrivateKey::random();
+    let txs = vec![make_transfer(&privk, 0, 123), make_transfer(&privk, 1, 456)];
+
+    let mut request = StacksHttpRequest::new_post_transaction_simulate(
+        addr.into(),
+        &txs,
+        TipRequest::SpecificTip(StacksBlockId([0x22; 32])),
+    );
+    let bytes = request.try_serialize().unwrap();
+
+    debug!("Request:\n{}\n", std::str::from_utf8(&bytes).unwrap());
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler = posttransaction_simulate::RPCPostTransactionSimulateRequestHandler::new(
+        Some("password".into()),
+    );
+
+    // missing authorization header
+    let bad_request = http.handle_try_parse_request(
+        &mut handler,
+        &parsed_preamble.expect_request(),
+        &bytes[offset..],
+    );
+    match bad_request {
+        Err(crate::net::Error::Http(crate::net::http::Error::Http(err_code, message))) => {
+            assert_eq!(err_code, 401);
+            assert_eq!(message, "Unauthorized");
+        }
+        _ => panic!("expected error"),
+    }
+
+    // add the authorization header
+    request.add_header("authorization".into(), "password".into());
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.clone().expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    assert_eq!(handler.transactions, Some(txs));
+    assert_eq!(
+        parsed_request.contents().tip_request(),
+        TipRequest::SpecificTip(StacksBlockId([0x22; 32]))
+    );
+
+    // parsed request consumes headers that would not be in a constructed reqeuest
+    parsed_request.clear_headers();
+    // but the authorization header should still be there
+    parsed_request.add_header("authorization".into(), "password".into());
+    let (preamble, contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+
+    handler.restart();
+    assert!(handler.transactions.is_none());
+
+    // simulation is disabled without an auth token
+    let mut handler = posttransaction_simulate::RPCPostTransactionSimulateRequestHandler::new(None);
+    let bad_request = http.handle_try_parse_request(
+        &mut handler,
+        &parsed_preamble.expect_request(),
+        &bytes[offset..],
+    );
+    match bad_request {
+        Err(crate::net::Error::Http(crate::net::http::Error::Http(err_code, _))) => {
+            assert_eq!(err_code, 400);
+        }
+        _ => panic!("expected error"),
+    }
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let test_observer = TestEventObserver::new();
+    let mut rpc_test = TestRPC::setup_nakamoto(function_name!(), &test_observer);
+    let mut requests = vec![];
+
+    let miner_privk = rpc_test.peer_1.miner.nakamoto_miner_key();
+    let miner_principal: PrincipalData = TransactionAuth::from_p2pkh(&miner_privk)
+        .unwrap()
+        .origin()
+        .address_testnet()
+        .into();
+
+    let canonical_tip = rpc_test.canonical_tip.clone();
+    let nonce = {
+        let sortdb = rpc_test.peer_1.sortdb.take().unwrap();
+        let account = rpc_test
+            .peer_1
+            .chainstate()
+            .with_read_only_clarity_tx(&sortdb.index_handle_at_tip(), &canonical_tip, |conn| {
+                StacksChainState::get_account(conn, &miner_principal)
+            })
+            .unwrap();
+        rpc_test.peer_1.sortdb = Some(sortdb);
+        account.nonce
+    };
+
+    // the second transaction depends on the first; the third has a stale nonce.  Token
+    // transfers can't have post-conditions, so the first one sends its STX from a contract.
+    let mut first_tx = make_deploy(
+        &miner_privk,
+        nonce,
+        "send-stx",
+        "(stx-transfer? u123 tx-sender 'ST000000000000000000002AMW42H)",
+    );
+    first_tx.add_post_condition(TransactionPostCondition::STX(
+        PostConditionPrincipal::Origin,
+        FungibleConditionCode::SentEq,
+        123,
+    ));
+    first_tx.add_post_condition(TransactionPostCondition::STX(
+        PostConditionPrincipal::Origin,
+        FungibleConditionCode::SentLe,
+        200,
+    ));
+    let txs = vec![
+        first_tx,
+        make_transfer(&miner_privk, nonce + 1, 456),
+        make_transfer(&miner_privk, nonce, 789),
+    ];
+
+    let mut request = StacksHttpRequest::new_post_transaction_simulate(
+        addr.into(),
+        &txs,
+        TipRequest::UseLatestAnchoredTip,
+    );
+    request.add_header("authorization".into(), "password".into());
+    requests.push(request);
+
+    // no such tip
+    let mut request = StacksHttpRequest::new_post_transaction_simulate(
+        addr.into(),
+        &txs,
+        TipRequest::SpecificTip(StacksBlockId([0x11; 32])),
+    );
+    request.add_header("authorization".into(), "password".into());
+    requests.push(request);
+
+    let mut responses = rpc_test.run(requests);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+
+    let simulated = response.decode_transaction_simulation().unwrap();
+    assert_eq!(simulated.len(), 3);
+
+    assert_eq!(simulated[0].txid, txs[0].txid());
+    assert!(simulated[0].mineable);
+    assert_eq!(simulated[0].fee, 300);
+    assert_eq!(simulated[0].result.as_deref(), Some("0x0703"));
+    assert!(!simulated[0].post_condition_aborted);
+    assert_eq!(simulated[0].post_conditions, vec![true, true]);
+    assert_eq!(simulated[0].events.len(), 1);
+    assert!(simulated[0].asset_map.is_some());
+
+    assert_eq!(simulated[1].txid, txs[1].txid());
+    assert!(simulated[1].mineable);
+    assert!(simulated[1].post_conditions.is_empty());
+
+    assert_eq!(simulated[2].txid, txs[2].txid());
+    assert!(!simulated[2].mineable);
+    assert!(simulated[2].abort_reason.is_some());
+    assert!(simulated[2].result.is_none());
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    let (preamble, body) = response.destruct();
+    assert_eq!(preamble.status_code, 404);
+}
+
+#[test]
+fn test_simulation_is_not_persisted() {
+    let test_observer = TestEventObserver::new();
+    let mut rpc_test = TestRPC::setup_nakamoto(function_name!(), &test_observer);
+
+    let miner_privk = rpc_test.peer_1.miner.nakamoto_miner_key();
+    let miner_principal: PrincipalData = TransactionAuth::from_p2pkh(&miner_privk)
+        .unwrap()
+        .origin()
+        .address_testnet()
+        .into();
+
+    let canonical_tip = rpc_test.canonical_tip.clone();
+    let sortdb = rpc_test.peer_1.sortdb.take().unwrap();
+    let chainstate = rpc_test.peer_1.chainstate();
+
+    let read_state = |chainstate: &mut StacksChainState| {
+        let account = chainstate
+            .with_read_only_clarity_tx(&sortdb.index_handle_at_tip(), &canonical_tip, |conn| {
+                StacksChainState::get_account(conn, &miner_principal)
+            })
+            .unwrap();
+        let root_hash = chainstate.clarity_state.with_marf(|marf| {
+            assert!(marf.get_open_chain_tip().is_none());
+            marf.get_root_hash_at(&canonical_tip).unwrap()
+        });
+        let canonical_header =
+            NakamotoChainState::get_canonical_block_header(chainstate.db(), &sortdb)
+                .unwrap()
+                .unwrap();
+        (account, root_hash, canonical_header.index_block_hash())
+    };
+
+    let (account_before, root_before, tip_before) = read_state(chainstate);
+
+    let txs = vec![
+        make_transfer(&miner_privk, account_before.nonce, 123),
+        make_transfer(&miner_privk, account_before.nonce + 1, 456),
+    ];
+    let simulated =
+        posttransaction_simulate::simulate_transactions(&sortdb, chainstate, &canonical_tip, &txs)
+            .unwrap();
+    assert!(simulated.iter().all(|simulated_tx| simulated_tx.mineable));
+
+    // the nonce, balance, and state root are as they were, and no new block was started
+    let (account_after, root_after, tip_after) = read_state(chainstate);
+    assert_eq!(account_before, account_after);
+    assert_eq!(root_before, root_after);
+    assert_eq!(tip_before, tip_after);
+
+    // and the same transactions simulate identically a second time
+    let resimulated =
+        posttransaction_simulate::simulate_transactions(&sortdb, chainstate, &canonical_tip, &txs)
+            .unwrap();
+    assert_eq!(simulated, resimulated);
+
+    rpc_test.peer_1.sortdb = Some(sortdb);
+}
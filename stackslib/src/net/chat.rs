@@ -23,7 +23,7 @@ use clarity::vm::types::QualifiedContractIdentifier;
 use rand;
 use rand::{thread_rng, Rng};
 use stacks_common::types::chainstate::PoxId;
-use stacks_common::types::net::PeerAddress;
+use stacks_common::types::net::{OnionAddress, PeerAddress};
 use stacks_common::types::StacksPublicKeyBuffer;
 use stacks_common::util::hash::to_hex;
 use stacks_common::util::secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey};
@@ -45,6 +45,7 @@ use crate::net::neighbors::MAX_NEIGHBOR_BLOCK_DELAY;
 use crate::net::p2p::PeerNetwork;
 use crate::net::relay::*;
 use crate::net::stackerdb::StackerDBs;
+use crate::net::transport::TransportKeyExchange;
 use crate::net::{
     Error as net_error, GetBlocksInv, GetPoxInv, Neighbor, NeighborKey, StacksMessage, StacksP2P,
     GETPOXINV_MAX_BITLEN, *,
@@ -383,6 +384,12 @@ pub struct ConversationP2P {
     /// outbound replies
     pub reply_handles: VecDeque<ReplyHandleP2P>,
 
+    /// our half of the key exchange for encrypted transport, once we've sent an EncryptTransport
+    transport_key_exchange: Option<TransportKeyExchange>,
+
+    /// whether or not we've told this peer our onion address
+    sent_onion_address: bool,
+
     /// system epochs
     epochs: EpochList,
 }
@@ -587,6 +594,8 @@ impl ConversationP2P {
 
             stats: NeighborStats::new(outbound),
             reply_handles: VecDeque::new(),
+            transport_key_exchange: None,
+            sent_onion_address: false,
 
             db_smart_contracts: vec![],
 
@@ -697,6 +706,31 @@ impl ConversationP2P {
         (peer_services & (ServiceFlags::STACKERDB as u16)) != 0
     }
 
+    /// Does the given services bitfield support compact Nakamoto block relay?  It will if it has
+    /// the COMPACT_NAKAMOTO_BLOCKS bit set
+    pub fn supports_compact_nakamoto_blocks(peer_services: u16) -> bool {
+        (peer_services & (ServiceFlags::COMPACT_NAKAMOTO_BLOCKS as u16)) != 0
+    }
+
+    /// Does the given services bitfield support mempool sync by set reconciliation?  It will if
+    /// it supports the mempool query protocol and has the MEMPOOL_SKETCH bit set
+    pub fn supports_mempool_sketch(peer_services: u16) -> bool {
+        Self::supports_mempool_query(peer_services)
+            && (peer_services & (ServiceFlags::MEMPOOL_SKETCH as u16)) != 0
+    }
+
+    /// Does the given services bitfield support encrypted transport?  It will if it has the
+    /// ENCRYPTED_TRANSPORT bit set
+    pub fn supports_encrypted_transport(peer_services: u16) -> bool {
+        (peer_services & (ServiceFlags::ENCRYPTED_TRANSPORT as u16)) != 0
+    }
+
+    /// Does the given services bitfield support onion addresses?  It will if it has the
+    /// ONION_ADDRESSES bit set
+    pub fn supports_onion_addresses(peer_services: u16) -> bool {
+        (peer_services & (ServiceFlags::ONION_ADDRESSES as u16)) != 0
+    }
+
     /// Does this remote neighbor support a particular StackerDB?
     pub fn replicates_stackerdb(&self, db: &QualifiedContractIdentifier) -> bool {
         for cid in self.db_smart_contracts.iter() {
@@ -1381,6 +1415,157 @@ impl ConversationP2P {
         Ok(())
     }
 
+    /// Should we upgrade this conversation to encrypted transport?  Only the outbound peer starts
+    /// the upgrade, once it has handshaked with a peer that supports it.
+    fn should_begin_transport_encryption(&self, local_services: u16) -> bool {
+        self.is_outbound()
+            && self.is_authenticated()
+            && self.transport_key_exchange.is_none()
+            && Self::supports_encrypted_transport(local_services)
+            && Self::supports_encrypted_transport(self.peer_services)
+    }
+
+    /// Send an EncryptTransport message with a new ephemeral key.  Everything we send after it
+    /// will be encrypted once the remote peer replies with its own key.
+    fn begin_transport_encryption(&mut self, network: &PeerNetwork) -> Result<(), net_error> {
+        let key_exchange = TransportKeyExchange::new();
+        let payload = StacksMessageType::EncryptTransport(EncryptTransportData {
+            ephemeral_public_key: StacksPublicKeyBuffer::from_public_key(
+                key_exchange.ephemeral_public_key(),
+            ),
+        });
+        let msg = self.sign_message(
+            network.get_chain_view(),
+            &network.get_local_peer().private_key,
+            payload,
+        )?;
+
+        let mut handle = self
+            .connection
+            .make_transport_upgrade_handle(self.conn_id)?;
+        handle
+            .write_all(&msg.serialize_to_vec())
+            .map_err(net_error::WriteError)?;
+
+        self.stats.msgs_tx += 1;
+        self.reply_handles.push_back(handle);
+        self.transport_key_exchange = Some(key_exchange);
+
+        debug!("{:?}: Begin encrypted transport", &self);
+        Ok(())
+    }
+
+    /// Handle an EncryptTransport message from the remote peer.  If we haven't sent one of our own
+    /// yet (i.e. we're the inbound peer), then reply with one.  Then, install the transport keys.
+    fn handle_encrypt_transport(
+        &mut self,
+        network: &PeerNetwork,
+        data: &EncryptTransportData,
+    ) -> Result<(), net_error> {
+        if !Self::supports_encrypted_transport(network.get_local_peer().services)
+            || !Self::supports_encrypted_transport(self.peer_services)
+        {
+            debug!("{:?}: Encrypted transport was not negotiated", &self);
+            return Err(net_error::InvalidMessage);
+        }
+        if self.connection.is_transport_encrypted() {
+            debug!("{:?}: Transport is already encrypted", &self);
+            return Err(net_error::InvalidMessage);
+        }
+        let remote_ephemeral_key = data.ephemeral_public_key.to_public_key().map_err(|e| {
+            debug!("{:?}: Invalid ephemeral transport key: {}", &self, e);
+            net_error::InvalidMessage
+        })?;
+        let Some(remote_public_key) = self.connection.get_public_key() else {
+            return Err(net_error::InvalidMessage);
+        };
+        if self.transport_key_exchange.is_none() {
+            self.begin_transport_encryption(network)?;
+        }
+        let key_exchange = self
+            .transport_key_exchange
+            .as_ref()
+            .ok_or(net_error::InvalidState)?;
+
+        let (send_cipher, recv_cipher) = key_exchange.finish(
+            &network.get_local_peer().private_key,
+            &remote_public_key,
+            &remote_ephemeral_key,
+        )?;
+        self.connection
+            .set_transport_ciphers(send_cipher, recv_cipher)?;
+
+        debug!("{:?}: Transport is now encrypted", &self);
+        Ok(())
+    }
+
+    /// Should we tell this peer our onion address?  We do so once, after handshaking with a peer
+    /// that supports onion addresses.
+    fn should_advertise_onion_address(&self, connection_opts: &ConnectionOptions) -> bool {
+        connection_opts.onion_address.is_some()
+            && self.is_authenticated()
+            && !self.sent_onion_address
+            && Self::supports_onion_addresses(self.peer_services)
+    }
+
+    /// Send our onion address to the remote peer, so that it (and the peers it tells about us)
+    /// can reach us through Tor.
+    fn advertise_onion_address(
+        &mut self,
+        network: &PeerNetwork,
+        onion_address: OnionAddress,
+        port: u16,
+    ) -> Result<(), net_error> {
+        let payload = StacksMessageType::OnionAddress(OnionAddressData {
+            onion_address,
+            port,
+        });
+        let msg = self.sign_message(
+            network.get_chain_view(),
+            &network.get_local_peer().private_key,
+            payload,
+        )?;
+        let handle = self.relay_signed_message(msg)?;
+        self.reply_handles.push_back(handle);
+        self.sent_onion_address = true;
+
+        debug!("{:?}: Advertised onion address {}", &self, &onion_address);
+        Ok(())
+    }
+
+    /// Handle an OnionAddress message from the remote peer.  If it's the onion service behind the
+    /// address the peer handshaked with, then remember it so we can connect to the peer through our
+    /// SOCKS5 proxy.
+    fn handle_onion_address(
+        &mut self,
+        network: &mut PeerNetwork,
+        data: &OnionAddressData,
+    ) -> Result<(), net_error> {
+        if data.onion_address.to_peer_address() != self.handshake_addrbytes
+            || data.port != self.handshake_port
+        {
+            debug!(
+                "{:?}: Ignoring onion address {}:{}, which does not match handshake address {}:{}",
+                &self,
+                &data.onion_address,
+                data.port,
+                &self.handshake_addrbytes.pretty_print(),
+                self.handshake_port
+            );
+            return Ok(());
+        }
+        let tx = network.peerdb_tx_begin().map_err(net_error::DBError)?;
+        PeerDB::set_onion_address(&tx, &data.onion_address)?;
+        tx.commit()
+            .map_err(|e| net_error::DBError(db_error::SqliteError(e)))?;
+
+        debug!(
+            "{:?}: Peer is reachable at {}:{}",
+            &self, &data.onion_address, data.port
+        );
+        Ok(())
+    }
+
     /// Reply to a ping with a pong.
     /// Called from the p2p network thread.
     fn handle_ping(
@@ -1783,6 +1968,82 @@ impl ConversationP2P {
         )
     }
 
+    /// Create a response to an inbound GetNakamotoBlockTxs request, but unsigned.
+    /// Replies with the requested transactions, or with the whole block if no transactions were
+    /// requested.  Replies with a NACK if we don't have the block.
+    pub fn make_getnakamotoblocktxs_response(
+        chainstate: &StacksChainState,
+        get_block_txs: &GetNakamotoBlockTxsData,
+    ) -> Result<StacksMessageType, net_error> {
+        let Some((block, _)) = chainstate
+            .nakamoto_blocks_db()
+            .get_nakamoto_block(&get_block_txs.block_id)?
+        else {
+            debug!(
+                "Reply Nack to GetNakamotoBlockTxs: no such block {}",
+                &get_block_txs.block_id
+            );
+            return Ok(StacksMessageType::Nack(NackData::new(
+                NackErrorCodes::NoSuchBlock,
+            )));
+        };
+
+        if get_block_txs.indexes.is_empty() {
+            debug!(
+                "Reply NakamotoBlocks for {} to GetNakamotoBlockTxs",
+                &get_block_txs.block_id
+            );
+            return Ok(StacksMessageType::NakamotoBlocks(NakamotoBlocksData {
+                blocks: vec![block],
+            }));
+        }
+
+        let mut txs = Vec::with_capacity(get_block_txs.indexes.len());
+        for index in get_block_txs.indexes.iter() {
+            let Some(tx) = block.txs.get(usize::from(*index)) else {
+                debug!(
+                    "Reply Nack to GetNakamotoBlockTxs: block {} has no tx {}",
+                    &get_block_txs.block_id, index
+                );
+                return Ok(StacksMessageType::Nack(NackData::new(
+                    NackErrorCodes::InvalidMessage,
+                )));
+            };
+            txs.push(tx.clone());
+        }
+
+        debug!(
+            "Reply NakamotoBlockTxs for {} with {} txs",
+            &get_block_txs.block_id,
+            txs.len()
+        );
+        Ok(StacksMessageType::NakamotoBlockTxs(NakamotoBlockTxsData {
+            block_id: get_block_txs.block_id.clone(),
+            txs,
+        }))
+    }
+
+    /// Handle an inbound GetNakamotoBlockTxs request.
+    /// Returns a reply handle to the generated message (possibly a nack)
+    fn handle_getnakamotoblocktxs(
+        &mut self,
+        network: &PeerNetwork,
+        chainstate: &StacksChainState,
+        preamble: &Preamble,
+        get_block_txs: &GetNakamotoBlockTxsData,
+    ) -> Result<ReplyHandleP2P, net_error> {
+        monitoring::increment_msg_counter("p2p_get_nakamoto_block_txs".to_string());
+
+        let response =
+            ConversationP2P::make_getnakamotoblocktxs_response(chainstate, get_block_txs)?;
+        self.sign_and_reply(
+            network.get_local_peer(),
+            network.get_chain_view(),
+            preamble,
+            response,
+        )
+    }
+
     /// Create a response an inbound GetPoxInv request, but unsigned.
     /// Returns a reply handle to the generated message (possibly a nack)
     pub fn make_getpoxinv_response(
@@ -2295,6 +2556,9 @@ impl ConversationP2P {
                 &msg.preamble,
                 get_nakamoto_inv,
             ),
+            StacksMessageType::GetNakamotoBlockTxs(ref get_block_txs) => {
+                self.handle_getnakamotoblocktxs(network, chainstate, &msg.preamble, get_block_txs)
+            }
             StacksMessageType::Blocks(_) => {
                 monitoring::increment_stx_blocks_received_counter();
 
@@ -2359,7 +2623,9 @@ impl ConversationP2P {
                     }
                 }
             }
-            StacksMessageType::NakamotoBlocks(_) => {
+            StacksMessageType::NakamotoBlocks(_)
+            | StacksMessageType::CompactNakamotoBlock(_)
+            | StacksMessageType::NakamotoBlockTxs(_) => {
                 // not handled here, but do some accounting -- we can't receive too many
                 // Nakamoto blocks per second
                 match self.validate_nakamoto_block_push(
@@ -2583,6 +2849,16 @@ impl ConversationP2P {
                 debug!("{:?}: Got NatPunchReply({})", &self, _m.nonce);
                 Ok(None)
             }
+            StacksMessageType::EncryptTransport(ref data) => {
+                debug!("{:?}: Got EncryptTransport", &self);
+                consume = true;
+                self.handle_encrypt_transport(network, data).map(|_| None)
+            }
+            StacksMessageType::OnionAddress(ref data) => {
+                debug!("{:?}: Got OnionAddress", &self);
+                consume = true;
+                self.handle_onion_address(network, data).map(|_| None)
+            }
             _ => {
                 debug!(
                     "{:?}: Got a data-plane message (type {})",
@@ -2695,6 +2971,11 @@ impl ConversationP2P {
                 // it's okay to forward this back (i.e. don't consume)
                 Ok(None)
             }
+            StacksMessageType::EncryptTransport(_) => {
+                // the rest of the stream is encrypted, but we can't have agreed on a key
+                debug!("{:?}: Got unauthenticated EncryptTransport", &self);
+                return Err(net_error::InvalidMessage);
+            }
             _ => {
                 debug!(
                     "{:?}: Got unauthenticated message (type {}), will NACK",
@@ -2926,14 +3207,10 @@ impl ConversationP2P {
         debug!("{:?}: {} messages pending", &self, num_inbound);
 
         let mut unsolicited = vec![];
-        for _ in 0..num_inbound {
+
+        // NOTE: handling an EncryptTransport message may decrypt more messages into the inbox
+        while let Some(mut msg) = self.connection.next_inbox_message() {
             let update_stats; // whether or not this message can count towards this peer's liveness stats
-            let mut msg = match self.connection.next_inbox_message() {
-                None => {
-                    continue;
-                }
-                Some(m) => m,
-            };
 
             if !self.validate_inbound_message(&msg, network.get_chain_view())? {
                 continue;
@@ -3011,6 +3288,16 @@ impl ConversationP2P {
             }
         }
 
+        if self.should_begin_transport_encryption(network.get_local_peer().services) {
+            self.begin_transport_encryption(network)?;
+        }
+
+        if self.should_advertise_onion_address(network.get_connection_opts()) {
+            if let Some((onion_address, port)) = network.get_connection_opts().onion_address {
+                self.advertise_onion_address(network, onion_address, port)?;
+            }
+        }
+
         // while we're at it, update our IP address if we have a pending DNS resolution (or start
         // the process if we need it)
         self.try_resolve_data_url_host(dns_client_opt, network.get_connection_opts().dns_timeout);
@@ -4770,6 +5057,166 @@ mod test {
         }
     }
 
+    #[test]
+    fn convo_encrypt_transport_ping() {
+        let conn_opts = ConnectionOptions::default();
+        let socketaddr_1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+        let socketaddr_2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8081);
+
+        let mut chain_view = BurnchainView {
+            burn_block_height: 12348,
+            burn_block_hash: BurnchainHeaderHash([0x11; 32]),
+            burn_stable_block_height: 12341,
+            burn_stable_block_hash: BurnchainHeaderHash([0x22; 32]),
+            last_burn_block_hashes: HashMap::new(),
+            rc_consensus_hash: ConsensusHash([0x33; 20]),
+        };
+        chain_view.make_test_data();
+
+        let test_name_1 = "convo_encrypt_transport_ping_1";
+        let test_name_2 = "convo_encrypt_transport_ping_2";
+
+        let burnchain_1 = testing_burnchain_config(test_name_1);
+        let burnchain_2 = testing_burnchain_config(test_name_2);
+
+        let services = DEFAULT_SERVICES | (ServiceFlags::ENCRYPTED_TRANSPORT as u16);
+        let (mut peerdb_1, mut sortdb_1, _stackerdbs_1, _pox_id_1, mut chainstate_1) =
+            make_test_chain_dbs(
+                test_name_1,
+                &burnchain_1,
+                0x9abcdef0,
+                12350,
+                "http://peer1.com".into(),
+                &[],
+                &[],
+                services,
+            );
+        let (mut peerdb_2, mut sortdb_2, _stackerdbs_2, _pox_id_2, mut chainstate_2) =
+            make_test_chain_dbs(
+                test_name_2,
+                &burnchain_2,
+                0x9abcdef0,
+                12351,
+                "http://peer2.com".into(),
+                &[],
+                &[],
+                services,
+            );
+
+        let mut net_1 = db_setup(
+            test_name_1,
+            &burnchain_1,
+            0x9abcdef0,
+            &mut peerdb_1,
+            &mut sortdb_1,
+            &socketaddr_1,
+            &chain_view,
+        );
+        let mut net_2 = db_setup(
+            test_name_2,
+            &burnchain_2,
+            0x9abcdef0,
+            &mut peerdb_2,
+            &mut sortdb_2,
+            &socketaddr_2,
+            &chain_view,
+        );
+
+        let local_peer_1 = PeerDB::get_local_peer(peerdb_1.conn()).unwrap();
+
+        // convo_1 is outbound, so it starts the upgrade
+        let mut convo_1 = ConversationP2P::new(
+            123,
+            456,
+            &burnchain_1,
+            &socketaddr_2,
+            &conn_opts,
+            true,
+            0,
+            StacksEpoch::unit_test_pre_2_05(0),
+        );
+        let mut convo_2 = ConversationP2P::new(
+            123,
+            456,
+            &burnchain_2,
+            &socketaddr_1,
+            &conn_opts,
+            false,
+            0,
+            StacksEpoch::unit_test_pre_2_05(0),
+        );
+
+        // convo_1 handshakes with convo_2
+        let handshake_data_1 = HandshakeData::from_local_peer(&local_peer_1);
+        let handshake_1 = convo_1
+            .sign_message(
+                &chain_view,
+                &local_peer_1.private_key,
+                StacksMessageType::Handshake(handshake_data_1),
+            )
+            .unwrap();
+        let mut rh_handshake_1 = convo_1.send_signed_request(handshake_1, 1000000).unwrap();
+
+        convo_send_recv(&mut convo_1, vec![&mut rh_handshake_1], &mut convo_2);
+        convo_2
+            .chat(&mut net_2, &sortdb_2, &mut chainstate_2, &mut None, false)
+            .unwrap();
+
+        // convo_1 gets the handshake-accept, and sends its EncryptTransport
+        convo_send_recv(&mut convo_2, vec![&mut rh_handshake_1], &mut convo_1);
+        convo_1
+            .chat(&mut net_1, &sortdb_1, &mut chainstate_1, &mut None, false)
+            .unwrap();
+        match rh_handshake_1.recv(0).unwrap().payload {
+            StacksMessageType::HandshakeAccept(..) => {}
+            x => panic!("Unexpected payload message type {:?}", &x),
+        }
+        assert!(!convo_1.connection.is_transport_encrypted());
+
+        // convo_2 replies with its own EncryptTransport, which must go out in plaintext
+        convo_send_recv(&mut convo_1, vec![], &mut convo_2);
+        convo_2
+            .chat(&mut net_2, &sortdb_2, &mut chainstate_2, &mut None, false)
+            .unwrap();
+        assert!(convo_2.connection.is_transport_encrypted());
+
+        convo_send_recv(&mut convo_2, vec![], &mut convo_1);
+        convo_1
+            .chat(&mut net_1, &sortdb_1, &mut chainstate_1, &mut None, false)
+            .unwrap();
+        assert!(convo_1.connection.is_transport_encrypted());
+
+        // the peers keep talking over the encrypted transport
+        for _ in 0..3 {
+            let ping_data_1 = PingData::new();
+            let ping_1 = convo_1
+                .sign_message(
+                    &chain_view,
+                    &local_peer_1.private_key,
+                    StacksMessageType::Ping(ping_data_1.clone()),
+                )
+                .unwrap();
+            let mut rh_ping_1 = convo_1.send_signed_request(ping_1, 1000000).unwrap();
+
+            convo_send_recv(&mut convo_1, vec![&mut rh_ping_1], &mut convo_2);
+            let unhandled_2 = convo_2
+                .chat(&mut net_2, &sortdb_2, &mut chainstate_2, &mut None, false)
+                .unwrap();
+            assert!(unhandled_2.is_empty());
+
+            convo_send_recv(&mut convo_2, vec![&mut rh_ping_1], &mut convo_1);
+            let unhandled_1 = convo_1
+                .chat(&mut net_1, &sortdb_1, &mut chainstate_1, &mut None, false)
+                .unwrap();
+            assert!(unhandled_1.is_empty());
+
+            match rh_ping_1.recv(0).unwrap().payload {
+                StacksMessageType::Pong(ref data) => assert_eq!(data.nonce, ping_data_1.nonce),
+                x => panic!("Unexpected payload message type {:?}", &x),
+            }
+        }
+    }
+
     #[test]
     fn convo_handshake_ping_loop() {
         let conn_opts = ConnectionOptions::default();
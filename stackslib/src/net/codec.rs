@@ -29,8 +29,8 @@ use stacks_common::codec::{
     read_next, read_next_at_most, read_next_exact, write_next, Error as codec_error,
     StacksMessageCodec, MAX_MESSAGE_LEN, MAX_RELAYERS_LEN, PREAMBLE_ENCODED_SIZE,
 };
-use stacks_common::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash};
-use stacks_common::types::net::PeerAddress;
+use stacks_common::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash, StacksBlockId};
+use stacks_common::types::net::{OnionAddress, PeerAddress};
 use stacks_common::types::StacksPublicKeyBuffer;
 use stacks_common::util::hash::{to_hex, DoubleSha256, Hash160, MerkleHashFunc};
 use stacks_common::util::log;
@@ -41,10 +41,11 @@ use stacks_common::util::secp256k1::{
 
 use crate::burnchains::{BurnchainView, PrivateKey, PublicKey};
 use crate::chainstate::burn::ConsensusHash;
-use crate::chainstate::nakamoto::NakamotoBlock;
+use crate::chainstate::nakamoto::{NakamotoBlock, NakamotoBlockHeader};
 use crate::chainstate::stacks::{
     StacksBlock, StacksMicroblock, StacksPublicKey, StacksTransaction, MAX_BLOCK_LEN,
 };
+use crate::core::mempool::TxTag;
 use crate::core::PEER_VERSION_TESTNET;
 use crate::net::db::LocalPeer;
 use crate::net::{Error as net_error, *};
@@ -385,6 +386,110 @@ impl StacksMessageCodec for NakamotoBlocksData {
     }
 }
 
+impl StacksMessageCodec for CompactNakamotoBlockData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.header)?;
+        write_next(fd, &self.nonce)?;
+        write_next(fd, &self.tx_tags)?;
+        write_next(fd, &self.prefilled_txs)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<Self, codec_error> {
+        let header: NakamotoBlockHeader = read_next(fd)?;
+        let nonce: u64 = read_next(fd)?;
+        let tx_tags: Vec<TxTag> = read_next_at_most(fd, COMPACT_NAKAMOTO_BLOCK_TXS_MAX)?;
+        let prefilled_txs: Vec<StacksTransaction> = {
+            let mut bound_read = BoundReader::from_reader(fd, u64::from(MAX_BLOCK_LEN));
+            read_next_at_most(&mut bound_read, COMPACT_NAKAMOTO_BLOCK_TXS_MAX)
+        }?;
+
+        // every prefilled transaction takes up a slot in the block
+        if prefilled_txs.len() > tx_tags.len() {
+            return Err(codec_error::DeserializeError(
+                "Invalid CompactNakamotoBlockData: more prefilled txs than tx tags".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            header,
+            nonce,
+            tx_tags,
+            prefilled_txs,
+        })
+    }
+}
+
+impl StacksMessageCodec for GetNakamotoBlockTxsData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.block_id)?;
+        write_next(fd, &self.indexes)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<Self, codec_error> {
+        let block_id: StacksBlockId = read_next(fd)?;
+        let indexes: Vec<u16> = read_next_at_most(fd, COMPACT_NAKAMOTO_BLOCK_TXS_MAX)?;
+
+        // only valid if sorted and without dups
+        if !indexes.windows(2).all(|pair| pair[0] < pair[1]) {
+            return Err(codec_error::DeserializeError(
+                "Invalid GetNakamotoBlockTxsData: indexes are not strictly increasing".to_string(),
+            ));
+        }
+
+        Ok(Self { block_id, indexes })
+    }
+}
+
+impl StacksMessageCodec for NakamotoBlockTxsData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.block_id)?;
+        write_next(fd, &self.txs)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<Self, codec_error> {
+        let block_id: StacksBlockId = read_next(fd)?;
+        let txs: Vec<StacksTransaction> = {
+            let mut bound_read = BoundReader::from_reader(fd, u64::from(MAX_BLOCK_LEN));
+            read_next_at_most(&mut bound_read, COMPACT_NAKAMOTO_BLOCK_TXS_MAX)
+        }?;
+        Ok(Self { block_id, txs })
+    }
+}
+
+impl StacksMessageCodec for EncryptTransportData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.ephemeral_public_key)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<Self, codec_error> {
+        let ephemeral_public_key: StacksPublicKeyBuffer = read_next(fd)?;
+        Ok(Self {
+            ephemeral_public_key,
+        })
+    }
+}
+
+impl StacksMessageCodec for OnionAddressData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.onion_address)?;
+        write_next(fd, &self.port)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<Self, codec_error> {
+        let onion_address: OnionAddress = read_next(fd)?;
+        let port: u16 = read_next(fd)?;
+        Ok(Self {
+            onion_address,
+            port,
+        })
+    }
+}
+
 impl StacksMessageCodec for GetPoxInv {
     fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
         write_next(fd, &self.consensus_hash)?;
@@ -960,6 +1065,13 @@ impl StacksMessageType {
             StacksMessageType::GetNakamotoInv(ref _m) => StacksMessageID::GetNakamotoInv,
             StacksMessageType::NakamotoInv(ref _m) => StacksMessageID::NakamotoInv,
             StacksMessageType::NakamotoBlocks(ref _m) => StacksMessageID::NakamotoBlocks,
+            StacksMessageType::CompactNakamotoBlock(ref _m) => {
+                StacksMessageID::CompactNakamotoBlock
+            }
+            StacksMessageType::GetNakamotoBlockTxs(ref _m) => StacksMessageID::GetNakamotoBlockTxs,
+            StacksMessageType::NakamotoBlockTxs(ref _m) => StacksMessageID::NakamotoBlockTxs,
+            StacksMessageType::EncryptTransport(ref _m) => StacksMessageID::EncryptTransport,
+            StacksMessageType::OnionAddress(ref _m) => StacksMessageID::OnionAddress,
         }
     }
 
@@ -995,6 +1107,11 @@ impl StacksMessageType {
             StacksMessageType::GetNakamotoInv(ref _m) => "GetNakamotoInv",
             StacksMessageType::NakamotoInv(ref _m) => "NakamotoInv",
             StacksMessageType::NakamotoBlocks(ref _m) => "NakamotoBlocks",
+            StacksMessageType::CompactNakamotoBlock(ref _m) => "CompactNakamotoBlock",
+            StacksMessageType::GetNakamotoBlockTxs(ref _m) => "GetNakamotoBlockTxs",
+            StacksMessageType::NakamotoBlockTxs(ref _m) => "NakamotoBlockTxs",
+            StacksMessageType::EncryptTransport(ref _m) => "EncryptTransport",
+            StacksMessageType::OnionAddress(ref _m) => "OnionAddress",
         }
     }
 
@@ -1111,6 +1228,24 @@ impl StacksMessageType {
                         .collect::<Vec<_>>()
                 )
             }
+            StacksMessageType::CompactNakamotoBlock(ref m) => {
+                format!(
+                    "CompactNakamotoBlock({},txs={},prefilled={})",
+                    &m.header.block_id(),
+                    m.tx_tags.len(),
+                    m.prefilled_txs.len()
+                )
+            }
+            StacksMessageType::GetNakamotoBlockTxs(ref m) => {
+                format!("GetNakamotoBlockTxs({},{:?})", &m.block_id, &m.indexes)
+            }
+            StacksMessageType::NakamotoBlockTxs(ref m) => {
+                format!("NakamotoBlockTxs({},txs={})", &m.block_id, m.txs.len())
+            }
+            StacksMessageType::EncryptTransport(ref _m) => "EncryptTransport".to_string(),
+            StacksMessageType::OnionAddress(ref m) => {
+                format!("OnionAddress({}:{})", &m.onion_address, m.port)
+            }
         }
     }
 }
@@ -1163,6 +1298,15 @@ impl StacksMessageCodec for StacksMessageID {
             x if x == StacksMessageID::GetNakamotoInv as u8 => StacksMessageID::GetNakamotoInv,
             x if x == StacksMessageID::NakamotoInv as u8 => StacksMessageID::NakamotoInv,
             x if x == StacksMessageID::NakamotoBlocks as u8 => StacksMessageID::NakamotoBlocks,
+            x if x == StacksMessageID::CompactNakamotoBlock as u8 => {
+                StacksMessageID::CompactNakamotoBlock
+            }
+            x if x == StacksMessageID::GetNakamotoBlockTxs as u8 => {
+                StacksMessageID::GetNakamotoBlockTxs
+            }
+            x if x == StacksMessageID::NakamotoBlockTxs as u8 => StacksMessageID::NakamotoBlockTxs,
+            x if x == StacksMessageID::EncryptTransport as u8 => StacksMessageID::EncryptTransport,
+            x if x == StacksMessageID::OnionAddress as u8 => StacksMessageID::OnionAddress,
             _ => {
                 return Err(codec_error::DeserializeError(
                     "Unknown message ID".to_string(),
@@ -1208,6 +1352,11 @@ impl StacksMessageCodec for StacksMessageType {
             StacksMessageType::GetNakamotoInv(ref m) => write_next(fd, m)?,
             StacksMessageType::NakamotoInv(ref m) => write_next(fd, m)?,
             StacksMessageType::NakamotoBlocks(ref m) => write_next(fd, m)?,
+            StacksMessageType::CompactNakamotoBlock(ref m) => write_next(fd, m)?,
+            StacksMessageType::GetNakamotoBlockTxs(ref m) => write_next(fd, m)?,
+            StacksMessageType::NakamotoBlockTxs(ref m) => write_next(fd, m)?,
+            StacksMessageType::EncryptTransport(ref m) => write_next(fd, m)?,
+            StacksMessageType::OnionAddress(ref m) => write_next(fd, m)?,
         }
         Ok(())
     }
@@ -1322,6 +1471,26 @@ impl StacksMessageCodec for StacksMessageType {
                 let m: NakamotoBlocksData = read_next(fd)?;
                 StacksMessageType::NakamotoBlocks(m)
             }
+            StacksMessageID::CompactNakamotoBlock => {
+                let m: CompactNakamotoBlockData = read_next(fd)?;
+                StacksMessageType::CompactNakamotoBlock(m)
+            }
+            StacksMessageID::GetNakamotoBlockTxs => {
+                let m: GetNakamotoBlockTxsData = read_next(fd)?;
+                StacksMessageType::GetNakamotoBlockTxs(m)
+            }
+            StacksMessageID::NakamotoBlockTxs => {
+                let m: NakamotoBlockTxsData = read_next(fd)?;
+                StacksMessageType::NakamotoBlockTxs(m)
+            }
+            StacksMessageID::EncryptTransport => {
+                let m: EncryptTransportData = read_next(fd)?;
+                StacksMessageType::EncryptTransport(m)
+            }
+            StacksMessageID::OnionAddress => {
+                let m: OnionAddressData = read_next(fd)?;
+                StacksMessageType::OnionAddress(m)
+            }
             StacksMessageID::Reserved => {
                 return Err(codec_error::DeserializeError(
                     "Unsupported message ID 'reserved'".to_string(),
@@ -1507,6 +1676,10 @@ impl MessageSequence for StacksMessage {
     fn get_message_name(&self) -> &'static str {
         self.payload.get_message_name()
     }
+
+    fn is_transport_upgrade(&self) -> bool {
+        matches!(self.payload, StacksMessageType::EncryptTransport(..))
+    }
 }
 
 impl StacksP2P {
@@ -1600,10 +1773,15 @@ impl ProtocolFamily for StacksP2P {
 pub mod test {
     use stacks_common::bitvec::BitVec;
     use stacks_common::codec::NEIGHBOR_ADDRESS_ENCODED_SIZE;
+    use stacks_common::types::StacksEpochId;
     use stacks_common::util::hash::hex_bytes;
     use stacks_common::util::secp256k1::*;
 
     use super::*;
+    use crate::chainstate::stacks::test::codec_all_transactions;
+    use crate::chainstate::stacks::{
+        TransactionAnchorMode, TransactionPostConditionMode, TransactionVersion,
+    };
     use crate::net::{GetNakamotoInvData, NakamotoInvData};
 
     fn check_overflow<T>(r: Result<T, net_error>) -> bool {
@@ -2469,6 +2647,140 @@ pub mod test {
         let _ = NakamotoInvData::consensus_deserialize(&mut &nakamoto_inv_bytes[..]).unwrap_err();
     }
 
+    #[test]
+    fn codec_CompactNakamotoBlock() {
+        let header = NakamotoBlockHeader::empty();
+        let compact_block = CompactNakamotoBlockData {
+            header: header.clone(),
+            nonce: 0x0102030405060708,
+            tx_tags: vec![TxTag([0x11; 8]), TxTag([0x22; 8])],
+            prefilled_txs: vec![],
+        };
+
+        let mut compact_block_bytes = header.serialize_to_vec();
+        compact_block_bytes.extend_from_slice(&[
+            // nonce
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, // tx tags length
+            0x00, 0x00, 0x00, 0x02, // tx tags
+            0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+            0x22, 0x22, // prefilled txs length
+            0x00, 0x00, 0x00, 0x00,
+        ]);
+
+        check_codec_and_corruption::<CompactNakamotoBlockData>(
+            &compact_block,
+            &compact_block_bytes,
+        );
+
+        // can't have more prefilled txs than tx tags
+        let mut too_many_prefilled = compact_block.clone();
+        too_many_prefilled.tx_tags.clear();
+        too_many_prefilled.prefilled_txs = codec_all_transactions(
+            &TransactionVersion::Testnet,
+            0x80000000,
+            &TransactionAnchorMode::Any,
+            &TransactionPostConditionMode::Allow,
+            StacksEpochId::latest(),
+        )
+        .into_iter()
+        .take(1)
+        .collect();
+        assert!(check_deserialize_failure(&too_many_prefilled));
+    }
+
+    #[test]
+    fn codec_GetNakamotoBlockTxs() {
+        let get_block_txs = GetNakamotoBlockTxsData {
+            block_id: StacksBlockId([0x55; 32]),
+            indexes: vec![1, 0x0203],
+        };
+
+        let get_block_txs_bytes: Vec<u8> = vec![
+            // block id
+            0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+            0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+            0x55, 0x55, 0x55, 0x55, // indexes length
+            0x00, 0x00, 0x00, 0x02, // indexes
+            0x00, 0x01, 0x02, 0x03,
+        ];
+
+        check_codec_and_corruption::<GetNakamotoBlockTxsData>(&get_block_txs, &get_block_txs_bytes);
+
+        // indexes must be strictly increasing
+        let unsorted = GetNakamotoBlockTxsData {
+            block_id: StacksBlockId([0x55; 32]),
+            indexes: vec![0x0203, 1],
+        };
+        assert!(check_deserialize_failure(&unsorted));
+
+        let duplicated = GetNakamotoBlockTxsData {
+            block_id: StacksBlockId([0x55; 32]),
+            indexes: vec![1, 1],
+        };
+        assert!(check_deserialize_failure(&duplicated));
+    }
+
+    #[test]
+    fn codec_NakamotoBlockTxs() {
+        let block_txs = NakamotoBlockTxsData {
+            block_id: StacksBlockId([0x66; 32]),
+            txs: vec![],
+        };
+
+        let block_txs_bytes: Vec<u8> = vec![
+            // block id
+            0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+            0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+            0x66, 0x66, 0x66, 0x66, // txs length
+            0x00, 0x00, 0x00, 0x00,
+        ];
+
+        check_codec_and_corruption::<NakamotoBlockTxsData>(&block_txs, &block_txs_bytes);
+    }
+
+    #[test]
+    fn codec_EncryptTransport() {
+        let encrypt_transport = EncryptTransportData {
+            ephemeral_public_key: StacksPublicKeyBuffer::from_bytes(
+                &hex_bytes("034e316be04870cef1795fba64d581cf64bad0c894b01a068fb9edf85321dcd9bb")
+                    .unwrap(),
+            )
+            .unwrap(),
+        };
+
+        let encrypt_transport_bytes: Vec<u8> = vec![
+            // ephemeral public key
+            0x03, 0x4e, 0x31, 0x6b, 0xe0, 0x48, 0x70, 0xce, 0xf1, 0x79, 0x5f, 0xba, 0x64, 0xd5,
+            0x81, 0xcf, 0x64, 0xba, 0xd0, 0xc8, 0x94, 0xb0, 0x1a, 0x06, 0x8f, 0xb9, 0xed, 0xf8,
+            0x53, 0x21, 0xdc, 0xd9, 0xbb,
+        ];
+
+        check_codec_and_corruption::<EncryptTransportData>(
+            &encrypt_transport,
+            &encrypt_transport_bytes,
+        );
+    }
+
+    #[test]
+    fn codec_OnionAddress() {
+        let onion_address = OnionAddressData {
+            onion_address: "duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczad.onion"
+                .parse()
+                .unwrap(),
+            port: 20444,
+        };
+
+        let onion_address_bytes: Vec<u8> = vec![
+            // onion service public key
+            0x1d, 0x04, 0xa1, 0xd0, 0x4a, 0x33, 0x8c, 0x6e, 0x6a, 0xe9, 0x70, 0xbf, 0xab, 0xee,
+            0x49, 0x04, 0x9d, 0x67, 0x02, 0x25, 0x09, 0x84, 0xca, 0x95, 0x0c, 0x01, 0x67, 0x3f,
+            0x4e, 0xc0, 0x34, 0xad, // port
+            0x4f, 0xdc,
+        ];
+
+        check_codec_and_corruption::<OnionAddressData>(&onion_address, &onion_address_bytes);
+    }
+
     #[test]
     fn codec_StacksMessage() {
         let payloads: Vec<StacksMessageType> = vec![
@@ -2665,6 +2977,20 @@ pub mod test {
                     true, true, true, true, true, true, true, true].as_slice()
                 ).unwrap()
             }),
+            StacksMessageType::CompactNakamotoBlock(CompactNakamotoBlockData {
+                header: NakamotoBlockHeader::empty(),
+                nonce: 0x0102030405060708,
+                tx_tags: vec![TxTag([0x11; 8]), TxTag([0x22; 8])],
+                prefilled_txs: vec![],
+            }),
+            StacksMessageType::GetNakamotoBlockTxs(GetNakamotoBlockTxsData {
+                block_id: StacksBlockId([0x33; 32]),
+                indexes: vec![1, 2, 0x1234],
+            }),
+            StacksMessageType::NakamotoBlockTxs(NakamotoBlockTxsData {
+                block_id: StacksBlockId([0x44; 32]),
+                txs: vec![],
+            }),
         ];
 
         let mut maximal_relayers: Vec<RelayData> = vec![];
@@ -0,0 +1,347 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compact Nakamoto block relay.
+//!
+//! Most of a Nakamoto block's transactions were already gossiped before the block was mined, so
+//! they are likely to be in the recipient's mempool.  A compact block carries the block header
+//! and a short tag for each transaction, and the recipient rebuilds the block from its mempool.
+//! Transactions it cannot find are fetched from the sender with a `GetNakamotoBlockTxs`
+//! request.  If the rebuilt block does not match the header's transaction Merkle root (e.g. due
+//! to a tag collision), the recipient asks for the full block instead.
+
+use std::collections::{HashMap, HashSet};
+
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::util::hash::{MerkleTree, Sha512Trunc256Sum};
+
+use crate::chainstate::nakamoto::{NakamotoBlock, NakamotoBlockHeader};
+use crate::chainstate::stacks::{StacksTransaction, TransactionPayload};
+use crate::core::mempool::TxTag;
+use crate::net::{CompactNakamotoBlockData, Error as net_error};
+
+impl CompactNakamotoBlockData {
+    /// Make a compact block out of a full block.
+    /// The coinbase and tenure-change transactions never pass through the mempool, so they are
+    /// sent along in full.
+    pub fn from_block(block: &NakamotoBlock, nonce: u64) -> Self {
+        let mut compact = Self {
+            header: block.header.clone(),
+            nonce,
+            tx_tags: vec![],
+            prefilled_txs: vec![],
+        };
+        let seed = compact.tx_tag_seed();
+        for tx in block.txs.iter() {
+            compact.tx_tags.push(TxTag::from(&seed, &tx.txid()));
+            if matches!(
+                tx.payload,
+                TransactionPayload::Coinbase(..) | TransactionPayload::TenureChange(..)
+            ) {
+                compact.prefilled_txs.push(tx.clone());
+            }
+        }
+        compact
+    }
+
+    /// The seed for this block's transaction tags: the block ID, followed by the nonce.
+    pub fn tx_tag_seed(&self) -> Vec<u8> {
+        let mut seed = self.header.block_id().0.to_vec();
+        seed.extend_from_slice(&self.nonce.to_be_bytes());
+        seed
+    }
+
+    pub fn block_id(&self) -> StacksBlockId {
+        self.header.block_id()
+    }
+}
+
+/// A Nakamoto block being rebuilt from a compact block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialNakamotoBlock {
+    /// The block header, including the signer signatures
+    pub header: NakamotoBlockHeader,
+    /// Seed for the transaction tags
+    tx_tag_seed: Vec<u8>,
+    /// The tag of each transaction, in block order
+    tx_tags: Vec<TxTag>,
+    /// The transactions found so far, in block order
+    txs: Vec<Option<StacksTransaction>>,
+}
+
+impl PartialNakamotoBlock {
+    /// Start rebuilding a block from a compact block.  Only the prefilled transactions will be
+    /// filled in.
+    pub fn from_compact(compact: &CompactNakamotoBlockData) -> Self {
+        let mut partial = Self {
+            header: compact.header.clone(),
+            tx_tag_seed: compact.tx_tag_seed(),
+            tx_tags: compact.tx_tags.clone(),
+            txs: vec![None; compact.tx_tags.len()],
+        };
+        partial.fill_from_candidates(compact.prefilled_txs.iter());
+        partial
+    }
+
+    pub fn block_id(&self) -> StacksBlockId {
+        self.header.block_id()
+    }
+
+    /// The tags of the transactions which have not been found yet
+    pub fn missing_tags(&self) -> HashSet<TxTag> {
+        self.tx_tags
+            .iter()
+            .zip(self.txs.iter())
+            .filter(|(_, tx_opt)| tx_opt.is_none())
+            .map(|(tag, _)| tag.clone())
+            .collect()
+    }
+
+    /// The indexes of the transactions which have not been found yet, in increasing order
+    pub fn missing_indexes(&self) -> Vec<u16> {
+        self.txs
+            .iter()
+            .enumerate()
+            .filter(|(_, tx_opt)| tx_opt.is_none())
+            .filter_map(|(i, _)| u16::try_from(i).ok())
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.txs.iter().all(|tx_opt| tx_opt.is_some())
+    }
+
+    pub fn tx_tag_seed(&self) -> &[u8] {
+        &self.tx_tag_seed
+    }
+
+    /// Fill in missing transactions from a set of candidates (e.g. from the mempool).
+    /// A tag which matches more than one candidate is ambiguous, so it is left missing and the
+    /// transaction will be requested from the sender instead.
+    /// Returns the number of transactions filled in.
+    pub fn fill_from_candidates<'a>(
+        &mut self,
+        candidates: impl Iterator<Item = &'a StacksTransaction>,
+    ) -> usize {
+        let mut by_tag: HashMap<TxTag, Option<&StacksTransaction>> = HashMap::new();
+        for tx in candidates {
+            let tag = TxTag::from(&self.tx_tag_seed, &tx.txid());
+            let entry = by_tag.entry(tag).or_insert(Some(tx));
+            if entry.is_some_and(|other_tx| other_tx.txid() != tx.txid()) {
+                // collision
+                *entry = None;
+            }
+        }
+
+        let mut num_filled = 0;
+        for (tag, tx_opt) in self.tx_tags.iter().zip(self.txs.iter_mut()) {
+            if tx_opt.is_some() {
+                continue;
+            }
+            if let Some(Some(tx)) = by_tag.get(tag) {
+                *tx_opt = Some((*tx).clone());
+                num_filled += 1;
+            }
+        }
+        num_filled
+    }
+
+    /// Fill in the transactions sent in reply to a `GetNakamotoBlockTxs` request for `indexes`.
+    /// Each transaction must match the tag at its index.
+    pub fn fill_from_response(
+        &mut self,
+        indexes: &[u16],
+        txs: Vec<StacksTransaction>,
+    ) -> Result<(), net_error> {
+        if indexes.len() != txs.len() {
+            return Err(net_error::InvalidMessage);
+        }
+        for (index, tx) in indexes.iter().zip(txs.into_iter()) {
+            let index = usize::from(*index);
+            let Some(tag) = self.tx_tags.get(index) else {
+                return Err(net_error::InvalidMessage);
+            };
+            if *tag != TxTag::from(&self.tx_tag_seed, &tx.txid()) {
+                return Err(net_error::InvalidMessage);
+            }
+            self.txs[index] = Some(tx);
+        }
+        Ok(())
+    }
+
+    /// Finish rebuilding the block.
+    /// Fails if any transaction is still missing, or if the transactions do not match the header's
+    /// transaction Merkle root.
+    pub fn try_into_block(self) -> Result<NakamotoBlock, net_error> {
+        let Some(txs) = self.txs.into_iter().collect::<Option<Vec<_>>>() else {
+            return Err(net_error::InvalidMessage);
+        };
+        let txid_vecs: Vec<_> = txs.iter().map(|tx| tx.txid().as_bytes().to_vec()).collect();
+        let tx_merkle_root: Sha512Trunc256Sum = MerkleTree::new(&txid_vecs).root();
+        if tx_merkle_root != self.header.tx_merkle_root {
+            return Err(net_error::InvalidMessage);
+        }
+        Ok(NakamotoBlock {
+            header: self.header,
+            txs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use stacks_common::types::StacksEpochId;
+    use stacks_common::util::hash::{MerkleTree, Sha512Trunc256Sum};
+
+    use super::*;
+    use crate::chainstate::stacks::test::codec_all_transactions;
+    use crate::chainstate::stacks::{
+        TransactionAnchorMode, TransactionPostConditionMode, TransactionVersion,
+    };
+
+    /// Make a Nakamoto block with lots of transactions, including coinbases and tenure-changes
+    fn make_test_block() -> NakamotoBlock {
+        let mut txids = HashSet::new();
+        let txs: Vec<_> = codec_all_transactions(
+            &TransactionVersion::Testnet,
+            0x80000000,
+            &TransactionAnchorMode::OnChainOnly,
+            &TransactionPostConditionMode::Allow,
+            StacksEpochId::latest(),
+        )
+        .into_iter()
+        .filter(|tx| txids.insert(tx.txid()))
+        .collect();
+
+        let txid_vecs: Vec<_> = txs.iter().map(|tx| tx.txid().as_bytes().to_vec()).collect();
+        let mut header = NakamotoBlockHeader::empty();
+        header.tx_merkle_root = MerkleTree::<Sha512Trunc256Sum>::new(&txid_vecs).root();
+        NakamotoBlock { header, txs }
+    }
+
+    fn is_prefilled(tx: &StacksTransaction) -> bool {
+        matches!(
+            tx.payload,
+            TransactionPayload::Coinbase(..) | TransactionPayload::TenureChange(..)
+        )
+    }
+
+    #[test]
+    fn rebuild_compact_block_from_candidates_and_response() {
+        let block = make_test_block();
+        let compact = CompactNakamotoBlockData::from_block(&block, 0x0102030405060708);
+        assert_eq!(compact.block_id(), block.block_id());
+        assert_eq!(compact.tx_tags.len(), block.txs.len());
+
+        let expected_prefilled: Vec<_> = block
+            .txs
+            .iter()
+            .filter(|tx| is_prefilled(tx))
+            .cloned()
+            .collect();
+        assert!(!expected_prefilled.is_empty());
+        assert_eq!(compact.prefilled_txs, expected_prefilled);
+
+        // only the prefilled txs are known at first
+        let mut partial = PartialNakamotoBlock::from_compact(&compact);
+        assert_eq!(
+            partial.missing_indexes().len(),
+            block.txs.len() - expected_prefilled.len()
+        );
+
+        // the first half of the txs are in the "mempool", plus an unrelated tx
+        let half = block.txs.len() / 2;
+        let mut unrelated_tx = block.txs[0].clone();
+        unrelated_tx.chain_id = 0x80000001;
+        let candidates: Vec<_> = block.txs[..half]
+            .iter()
+            .chain([unrelated_tx].iter())
+            .cloned()
+            .collect();
+        partial.fill_from_candidates(candidates.iter());
+
+        let expected_missing: Vec<u16> = block
+            .txs
+            .iter()
+            .enumerate()
+            .filter(|(i, tx)| *i >= half && !is_prefilled(tx))
+            .map(|(i, _)| u16::try_from(i).unwrap())
+            .collect();
+        assert_eq!(partial.missing_indexes(), expected_missing);
+        assert!(!partial.is_complete());
+        assert!(partial.clone().try_into_block().is_err());
+
+        // the sender fills in the rest
+        let response_txs: Vec<_> = expected_missing
+            .iter()
+            .map(|i| block.txs[usize::from(*i)].clone())
+            .collect();
+        partial
+            .fill_from_response(&expected_missing, response_txs)
+            .unwrap();
+        assert!(partial.is_complete());
+        assert_eq!(partial.try_into_block().unwrap(), block);
+
+        // a different nonce gives different tags
+        let other_compact = CompactNakamotoBlockData::from_block(&block, 0x0807060504030201);
+        assert_ne!(other_compact.tx_tags, compact.tx_tags);
+    }
+
+    #[test]
+    fn rebuild_compact_block_rejects_bad_data() {
+        let block = make_test_block();
+        let compact = CompactNakamotoBlockData::from_block(&block, 0x0102030405060708);
+        let partial = PartialNakamotoBlock::from_compact(&compact);
+        let missing = partial.missing_indexes();
+        let response_txs: Vec<_> = missing
+            .iter()
+            .map(|i| block.txs[usize::from(*i)].clone())
+            .collect();
+
+        // wrong number of txs
+        let mut bad_partial = partial.clone();
+        assert!(bad_partial
+            .fill_from_response(&missing, response_txs[1..].to_vec())
+            .is_err());
+
+        // tx doesn't match its tag
+        let mut bad_partial = partial.clone();
+        let mut swapped_txs = response_txs.clone();
+        swapped_txs.swap(0, 1);
+        assert!(bad_partial
+            .fill_from_response(&missing, swapped_txs)
+            .is_err());
+
+        // index out of range
+        let mut bad_partial = partial.clone();
+        assert!(bad_partial
+            .fill_from_response(
+                &[u16::try_from(block.txs.len()).unwrap()],
+                vec![block.txs[0].clone()]
+            )
+            .is_err());
+
+        // txs don't match the header's tx merkle root
+        let mut bad_block = block.clone();
+        bad_block.header.tx_merkle_root = Sha512Trunc256Sum([0x11; 32]);
+        let bad_compact = CompactNakamotoBlockData::from_block(&bad_block, 0x0102030405060708);
+        let mut bad_partial = PartialNakamotoBlock::from_compact(&bad_compact);
+        bad_partial.fill_from_candidates(block.txs.iter());
+        assert!(bad_partial.is_complete());
+        assert!(bad_partial.try_into_block().is_err());
+    }
+}
@@ -26,7 +26,7 @@ use std::{io, net};
 use clarity::vm::costs::ExecutionCost;
 use clarity::vm::types::{QualifiedContractIdentifier, BOUND_VALUE_SERIALIZATION_HEX};
 use stacks_common::codec::{StacksMessageCodec, MAX_MESSAGE_LEN};
-use stacks_common::types::net::PeerAddress;
+use stacks_common::types::net::{OnionAddress, PeerAddress};
 use stacks_common::util::hash::to_hex;
 use stacks_common::util::pipe::*;
 use stacks_common::util::secp256k1::Secp256k1PublicKey;
@@ -43,6 +43,8 @@ use crate::net::neighbors::{
     WALK_MAX_DURATION, WALK_MIN_DURATION, WALK_RESET_INTERVAL, WALK_RESET_PROB, WALK_RETRY_COUNT,
     WALK_SEED_PROBABILITY, WALK_STATE_TIMEOUT,
 };
+use crate::net::socks::Socks5Proxy;
+use crate::net::transport::TransportCipher;
 use crate::net::{
     Error as net_error, MessageSequence, NeighborAddress, Preamble, ProtocolFamily, RelayData,
     StacksHttp, StacksP2P,
@@ -293,6 +295,8 @@ impl<P: ProtocolFamily> Write for NetworkReplyHandle<P> {
 struct InflightMessage<P: ProtocolFamily> {
     pipe_read: Option<PipeRead>,
     notify: Option<ReceiverNotify<P>>,
+    /// if true, then all bytes sent after this message are encrypted
+    encrypt_after: bool,
 }
 
 #[derive(Debug)]
@@ -308,6 +312,12 @@ struct ConnectionInbox<P: ProtocolFamily> {
     buf: Vec<u8>,
     message_ptr: usize, // index into buf where the message begins
     payload_ptr: usize, // for payloads of unknown length, this points to where to read next
+
+    // encrypted transport.  Once the remote peer sends a transport upgrade message, all subsequent
+    // bytes are buffered as ciphertext until we have the key to decrypt them.
+    decrypting: bool,
+    recv_cipher: Option<TransportCipher>,
+    ciphertext_buf: Vec<u8>,
 }
 
 #[derive(Debug)]
@@ -322,6 +332,14 @@ struct ConnectionOutbox<P: ProtocolFamily> {
 
     // in-flight messages
     inflight: VecDeque<ReceiverNotify<P>>,
+
+    // encrypted transport.  Once we send a transport upgrade message, all subsequent messages are
+    // held back until we have the key to encrypt them.  A key that is installed before our
+    // upgrade message has been buffered is staged until then, so that the upgrade message (and
+    // everything queued ahead of it) still goes out in plaintext.
+    encrypting: bool,
+    send_cipher: Option<TransportCipher>,
+    staged_send_cipher: Option<TransportCipher>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -427,6 +445,17 @@ pub struct ConnectionOptions {
     pub socket_send_buffer_size: u32,
     /// whether or not to announce or accept neighbors that are behind private networks
     pub private_neighbors: bool,
+    /// whether or not to encrypt p2p connections with peers that also support it
+    pub encrypt_transport: bool,
+    /// SOCKS5 proxy to make outbound p2p connections through, if any.  Onion peers can only be
+    /// reached through one.
+    pub socks5_proxy: Option<Socks5Proxy>,
+    /// hostnames for the proxy to resolve, keyed by the synthetic addresses that stand in for them
+    /// (see `PeerAddress::from_proxied_hostname()`)
+    pub socks5_hostnames: HashMap<PeerAddress, String>,
+    /// our onion service address and port, if we can be reached through Tor.  It's advertised to
+    /// peers in lieu of a public IP address.
+    pub onion_address: Option<(OnionAddress, u16)>,
     /// maximum number of confirmations for a nakamoto block's sortition for which it will be
     /// pushed
     pub max_nakamoto_block_relay_age: u64,
@@ -569,6 +598,10 @@ impl std::default::Default for ConnectionOptions {
             socket_recv_buffer_size: 131072, // Linux default
             socket_send_buffer_size: 16384, // Linux default
             private_neighbors: true,
+            encrypt_transport: false,
+            socks5_proxy: None,
+            socks5_hostnames: HashMap::new(),
+            onion_address: None,
             max_nakamoto_block_relay_age: 6,
             nakamoto_push_interval_ms: 30_000, // re-send a block no more than once every 30 seconds
             nakamoto_inv_sync_burst_interval_ms: 1_000, // wait 1 second after a sortition before running inventory sync
@@ -625,6 +658,9 @@ impl<P: ProtocolFamily> ConnectionInbox<P> {
             buf: vec![],
             message_ptr: 0,
             payload_ptr: 0,
+            decrypting: false,
+            recv_cipher: None,
+            ciphertext_buf: vec![],
         }
     }
 
@@ -935,8 +971,15 @@ impl<P: ProtocolFamily> ConnectionInbox<P> {
                             message.request_id(),
                             bytes_consumed
                         );
+                        let upgrade = message.is_transport_upgrade();
                         self.inbox.push_back(message);
                         consumed_message = true;
+                        if upgrade {
+                            // the rest of the input is ciphertext
+                            self.preamble = None;
+                            self.begin_decrypting(&buf[(offset + bytes_consumed)..])?;
+                            return Ok(());
+                        }
                     };
 
                     bytes_consumed
@@ -983,8 +1026,15 @@ impl<P: ProtocolFamily> ConnectionInbox<P> {
                         if let Some(message) = message_opt {
                             // queue up
                             test_debug!("Consumed buffered message '{}' (request {}) from {} input buffer bytes", message.get_message_name(), message.request_id(), _bytes_consumed);
+                            let upgrade = message.is_transport_upgrade();
                             self.inbox.push_back(message);
                             consumed_message = true;
+                            if upgrade {
+                                // the rest of the buffer is ciphertext
+                                self.preamble = None;
+                                self.begin_decrypting(&[])?;
+                                return Ok(());
+                            }
                         }
                     }
                     self.preamble = preamble_opt;
@@ -1005,6 +1055,53 @@ impl<P: ProtocolFamily> ConnectionInbox<P> {
         Ok(())
     }
 
+    /// Switch to decrypting the input stream, once the remote peer has sent a transport upgrade
+    /// message.  Any bytes already buffered after the upgrade message, as well as the given
+    /// `remaining` bytes that were read after it, are encrypted records.
+    /// Returns net_error::InvalidMessage if the input stream is already encrypted.
+    fn begin_decrypting(&mut self, remaining: &[u8]) -> Result<(), net_error> {
+        if self.decrypting {
+            debug!("Remote peer tried to upgrade an already-encrypted transport");
+            return Err(net_error::InvalidMessage);
+        }
+        self.decrypting = true;
+        self.ciphertext_buf = std::mem::take(&mut self.buf);
+        self.ciphertext_buf.extend_from_slice(remaining);
+        self.message_ptr = 0;
+        self.payload_ptr = 0;
+        Ok(())
+    }
+
+    /// Buffer up encrypted records, and decrypt any complete ones into the message stream.
+    fn consume_ciphertext(&mut self, protocol: &mut P, bytes: &[u8]) -> Result<(), net_error> {
+        self.ciphertext_buf.extend_from_slice(bytes);
+        if self.recv_cipher.is_none() && self.ciphertext_buf.len() > (MAX_MESSAGE_LEN as usize) {
+            // remote peer isn't waiting for us to finish the key exchange
+            return Err(net_error::InboxOverflow);
+        }
+        self.decrypt_records(protocol)
+    }
+
+    /// Decrypt all complete records in the ciphertext buffer, and parse the plaintext into
+    /// messages.  Does nothing if we don't have the key yet.
+    fn decrypt_records(&mut self, protocol: &mut P) -> Result<(), net_error> {
+        let Some(cipher) = self.recv_cipher.as_mut() else {
+            return Ok(());
+        };
+        let mut plaintext = vec![];
+        let mut ptr = 0;
+        while let Some((record, record_len)) = cipher.open(&self.ciphertext_buf[ptr..])? {
+            plaintext.extend_from_slice(&record);
+            ptr += record_len;
+        }
+        self.ciphertext_buf.drain(0..ptr);
+
+        if plaintext.is_empty() {
+            return Ok(());
+        }
+        self.consume_messages(protocol, &plaintext)
+    }
+
     /// Read bytes from an input stream, buffer them up, try to parse the buffer
     /// into messages, and enqueue the messages into the inbox.
     /// Returns net_error::RecvError if we couldn't read from the fd
@@ -1059,7 +1156,11 @@ impl<P: ProtocolFamily> ConnectionInbox<P> {
 
             if num_read > 0 {
                 // decode into message stream
-                self.consume_messages(protocol, &buf[0..num_read])?;
+                if self.decrypting {
+                    self.consume_ciphertext(protocol, &buf[0..num_read])?;
+                } else {
+                    self.consume_messages(protocol, &buf[0..num_read])?;
+                }
             }
         }
 
@@ -1090,6 +1191,9 @@ impl<P: ProtocolFamily> ConnectionOutbox<P> {
             socket_out_buf: vec![],
             socket_out_ptr: 0,
             inflight: VecDeque::new(),
+            encrypting: false,
+            send_cipher: None,
+            staged_send_cipher: None,
         }
     }
 
@@ -1120,6 +1224,13 @@ impl<P: ProtocolFamily> ConnectionOutbox<P> {
         match receiver_notify_opt {
             None => {}
             Some(receiver_notify) => {
+                if receiver_notify.encrypt_after {
+                    test_debug!("Sent transport upgrade message; encrypting from now on");
+                    self.encrypting = true;
+                    if let Some(cipher) = self.staged_send_cipher.take() {
+                        self.send_cipher = Some(cipher);
+                    }
+                }
                 if receiver_notify.notify.is_some() {
                     self.inflight.push_back(receiver_notify.notify.unwrap());
                 }
@@ -1131,6 +1242,7 @@ impl<P: ProtocolFamily> ConnectionOutbox<P> {
         &mut self,
         pipe_read: PipeRead,
         recv_notify: Option<ReceiverNotify<P>>,
+        encrypt_after: bool,
     ) -> Result<(), net_error> {
         if self.outbox.len() > self.outbox_maxlen {
             test_debug!(
@@ -1144,11 +1256,27 @@ impl<P: ProtocolFamily> ConnectionOutbox<P> {
         let inflight = InflightMessage {
             pipe_read: Some(pipe_read),
             notify: recv_notify,
+            encrypt_after,
         };
         self.outbox.push_back(inflight);
         Ok(())
     }
 
+    /// Install the key for encrypting outbound records.  It takes effect right away if our
+    /// transport upgrade message has already been buffered, and once it has been otherwise.
+    fn set_send_cipher(&mut self, cipher: TransportCipher) {
+        if self.encrypting {
+            self.send_cipher = Some(cipher);
+        } else {
+            self.staged_send_cipher = Some(cipher);
+        }
+    }
+
+    /// Do we have a key for encrypting outbound records, whether or not it is in use yet?
+    fn has_send_cipher(&self) -> bool {
+        self.send_cipher.is_some() || self.staged_send_cipher.is_some()
+    }
+
     /// Write queued messages to the given W
     /// Returns number of bytes sent out to fd.
     fn send_bytes<W: Write>(&mut self, fd: &mut W) -> Result<usize, net_error> {
@@ -1158,6 +1286,11 @@ impl<P: ProtocolFamily> ConnectionOutbox<P> {
         let mut message_eof = false;
         while !blocked && !disconnected && !message_eof {
             if self.pending_message_fd.is_none() {
+                if self.encrypting && self.send_cipher.is_none() {
+                    // can't send anything until the key exchange finishes
+                    test_debug!("Connection is waiting for transport encryption keys");
+                    break;
+                }
                 self.pending_message_fd = self.begin_next_message();
             }
 
@@ -1204,7 +1337,14 @@ impl<P: ProtocolFamily> ConnectionOutbox<P> {
                         },
                     };
 
-                    self.socket_out_buf.extend_from_slice(&buf[0..nr_input]);
+                    if nr_input > 0 {
+                        if let Some(cipher) = self.send_cipher.as_mut() {
+                            let record = cipher.seal(&buf[0..nr_input])?;
+                            self.socket_out_buf.extend_from_slice(&record);
+                        } else {
+                            self.socket_out_buf.extend_from_slice(&buf[0..nr_input]);
+                        }
+                    }
 
                     test_debug!(
                         "Connection buffered {} bytes from pipe ({} total, ptr = {}, blocked = {})",
@@ -1425,7 +1565,8 @@ impl<P: ProtocolFamily + Clone> NetworkConnection<P> {
         let mut recv_handle = NetworkReplyHandle::new(recv_ch, pipe_write, socket_event_id);
         recv_handle.set_deadline(timeout + get_epoch_time_secs());
 
-        self.outbox.queue_message(pipe_read, Some(recv_notify))?;
+        self.outbox
+            .queue_message(pipe_read, Some(recv_notify), false)?;
         Ok(recv_handle)
     }
 
@@ -1436,12 +1577,50 @@ impl<P: ProtocolFamily + Clone> NetworkConnection<P> {
         socket_event_id: usize,
     ) -> Result<NetworkReplyHandle<P>, net_error> {
         let (pipe_read, pipe_write) = Pipe::new();
-        self.outbox.queue_message(pipe_read, None)?;
+        self.outbox.queue_message(pipe_read, None, false)?;
 
         let send_handle = NetworkReplyHandle::new_relay(pipe_write, socket_event_id);
         Ok(send_handle)
     }
 
+    /// Send a transport upgrade message and expect no reply.  Every byte sent after it will be
+    /// encrypted, so no further messages will be sent until set_transport_ciphers() is called.
+    /// Returns a Write-able handle into which the message should be written, and flushed.
+    pub fn make_transport_upgrade_handle(
+        &mut self,
+        socket_event_id: usize,
+    ) -> Result<NetworkReplyHandle<P>, net_error> {
+        if self.outbox.encrypting || self.outbox.outbox.iter().any(|msg| msg.encrypt_after) {
+            return Err(net_error::InvalidState);
+        }
+        let (pipe_read, pipe_write) = Pipe::new();
+        self.outbox.queue_message(pipe_read, None, true)?;
+
+        let send_handle = NetworkReplyHandle::new_relay(pipe_write, socket_event_id);
+        Ok(send_handle)
+    }
+
+    /// Install the ciphers for encrypted transport, once both peers' transport upgrade messages
+    /// have been exchanged.  Any records received in the meantime are decrypted.
+    pub fn set_transport_ciphers(
+        &mut self,
+        send_cipher: TransportCipher,
+        recv_cipher: TransportCipher,
+    ) -> Result<(), net_error> {
+        if self.outbox.has_send_cipher() || self.inbox.recv_cipher.is_some() {
+            return Err(net_error::InvalidState);
+        }
+        self.outbox.set_send_cipher(send_cipher);
+        self.inbox.recv_cipher = Some(recv_cipher);
+        self.inbox.decrypt_records(&mut self.protocol)
+    }
+
+    /// Is this connection's transport encrypted?  This is true once the keys are installed, even
+    /// if our own transport upgrade message has yet to be sent.
+    pub fn is_transport_encrypted(&self) -> bool {
+        self.outbox.has_send_cipher() && self.inbox.recv_cipher.is_some()
+    }
+
     /// Send data
     pub fn send_data<W: Write>(&mut self, fd: &mut W) -> Result<usize, net_error> {
         self.outbox.send_bytes(fd)
@@ -24,7 +24,7 @@ use rand::seq::SliceRandom;
 use rand::{thread_rng, Rng, RngCore};
 use rusqlite::types::ToSql;
 use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row, Transaction};
-use stacks_common::types::net::{PeerAddress, PeerHost};
+use stacks_common::types::net::{OnionAddress, PeerAddress, PeerHost};
 use stacks_common::types::sqlite::NO_PARAMS;
 use stacks_common::util;
 use stacks_common::util::hash::{
@@ -45,7 +45,7 @@ use crate::util_lib::db::{
 };
 use crate::util_lib::strings::UrlString;
 
-pub const PEERDB_VERSION: &str = "3";
+pub const PEERDB_VERSION: &str = "4";
 
 const NUM_SLOTS: usize = 8;
 
@@ -152,7 +152,10 @@ impl LocalPeer {
         let port = port;
         let services = (ServiceFlags::RELAY as u16)
             | (ServiceFlags::RPC as u16)
-            | (ServiceFlags::STACKERDB as u16);
+            | (ServiceFlags::STACKERDB as u16)
+            | (ServiceFlags::COMPACT_NAKAMOTO_BLOCKS as u16)
+            | (ServiceFlags::MEMPOOL_SKETCH as u16)
+            | (ServiceFlags::ONION_ADDRESSES as u16);
 
         info!(
             "Will be authenticating p2p messages with the following";
@@ -408,6 +411,16 @@ const PEERDB_SCHEMA_3: &[&str] = &[
     "UPDATE db_config SET version = 3;",
 ];
 
+const PEERDB_SCHEMA_4: &[&str] = &[
+    r#"
+    CREATE TABLE onion_addresses(
+        addrbytes TEXT PRIMARY KEY NOT NULL,
+        onion_address TEXT NOT NULL
+    );
+    "#,
+    "UPDATE db_config SET version = 4;",
+];
+
 #[derive(Debug)]
 pub struct PeerDB {
     pub conn: Connection,
@@ -554,6 +567,15 @@ impl PeerDB {
         Ok(())
     }
 
+    #[cfg_attr(test, mutants::skip)]
+    fn apply_schema_4(tx: &Transaction) -> Result<(), db_error> {
+        test_debug!("Apply schema 4 to peer DB");
+        for row_text in PEERDB_SCHEMA_4 {
+            tx.execute_batch(row_text).map_err(db_error::SqliteError)?;
+        }
+        Ok(())
+    }
+
     fn apply_schema_migrations(tx: &Transaction) -> Result<String, db_error> {
         test_debug!("Apply any schema migrations");
         let expected_version = PEERDB_VERSION.to_string();
@@ -568,6 +590,8 @@ impl PeerDB {
                         PeerDB::apply_schema_2(tx)?;
                     } else if version == "2" {
                         PeerDB::apply_schema_3(tx)?;
+                    } else if version == "3" {
+                        PeerDB::apply_schema_4(tx)?;
                     } else if version == expected_version {
                         return Ok(ret.expect("unreachable"));
                     } else {
@@ -1083,6 +1107,17 @@ impl PeerDB {
             .collect())
     }
 
+    /// Get the peers that are denied as of `now`
+    pub fn get_denied_peers(
+        conn: &DBConn,
+        network_id: u32,
+        now: u64,
+    ) -> Result<Vec<Neighbor>, db_error> {
+        let sql = "SELECT * FROM frontier WHERE denied > ?1 AND network_id = ?2";
+        let args = params![u64_to_sql(now)?, network_id];
+        Self::query_peers(conn, sql, args)
+    }
+
     /// Get the bootstrap peers
     pub fn get_bootstrap_peers(conn: &DBConn, network_id: u32) -> Result<Vec<Neighbor>, db_error> {
         let sql = "SELECT * FROM frontier WHERE initial = 1 AND network_id = ?1 ORDER BY RANDOM()";
@@ -1568,6 +1603,36 @@ impl PeerDB {
         Ok(ret)
     }
 
+    /// Record the onion service that stands in for its synthetic peer address, so we can connect
+    /// to it later.
+    pub fn set_onion_address(tx: &Transaction, onion: &OnionAddress) -> Result<(), db_error> {
+        let args = params![onion.to_peer_address().to_bin(), onion.hostname()];
+        tx.execute(
+            "INSERT OR REPLACE INTO onion_addresses (addrbytes, onion_address) VALUES (?1, ?2)",
+            args,
+        )
+        .map_err(db_error::SqliteError)?;
+        Ok(())
+    }
+
+    /// Get the onion service behind a synthetic peer address, if we know it
+    pub fn get_onion_address(
+        conn: &DBConn,
+        addrbytes: &PeerAddress,
+    ) -> Result<Option<OnionAddress>, db_error> {
+        let qry = "SELECT onion_address FROM onion_addresses WHERE addrbytes = ?1";
+        let args = params![addrbytes.to_bin()];
+        let hostname_opt: Option<String> = query_row(conn, qry, args)?;
+        hostname_opt
+            .map(|hostname| {
+                hostname.parse::<OnionAddress>().map_err(|e| {
+                    error!("Unparseable onion address {}: {:?}", &hostname, &e);
+                    db_error::ParseError
+                })
+            })
+            .transpose()
+    }
+
     /// Get all deny CIDR prefixes
     pub fn get_denied_cidrs(conn: &DBConn) -> Result<Vec<(PeerAddress, u32)>, db_error> {
         PeerDB::get_cidr_prefixes(conn, "denied_prefixes")
@@ -1655,6 +1720,42 @@ impl PeerDB {
         Ok(())
     }
 
+    /// Remove an allowed CIDR prefix.  Peers it covered stay allowed only if another allowed
+    /// prefix covers them too.
+    pub fn remove_allow_cidr(
+        tx: &Transaction,
+        prefix: &PeerAddress,
+        mask: u32,
+    ) -> Result<(), db_error> {
+        assert!(mask > 0 && mask <= 128);
+        PeerDB::remove_cidr_prefix(tx, "allowed_prefixes", prefix, mask)?;
+
+        debug!("Remove allow {}/{}", &prefix, mask);
+        PeerDB::apply_cidr_filter(tx, prefix, mask, "allowed", 0)?;
+        for (prefix, mask) in PeerDB::get_allowed_cidrs(tx)?.into_iter() {
+            PeerDB::apply_cidr_filter(tx, &prefix, mask, "allowed", -1)?;
+        }
+        Ok(())
+    }
+
+    /// Remove a denied CIDR prefix.  Peers it covered stay denied only if another denied prefix
+    /// covers them too.
+    pub fn remove_deny_cidr(
+        tx: &Transaction,
+        prefix: &PeerAddress,
+        mask: u32,
+    ) -> Result<(), db_error> {
+        assert!(mask > 0 && mask <= 128);
+        PeerDB::remove_cidr_prefix(tx, "denied_prefixes", prefix, mask)?;
+
+        debug!("Remove deny {}/{}", &prefix, mask);
+        PeerDB::apply_cidr_filter(tx, prefix, mask, "denied", 0)?;
+        for (prefix, mask) in PeerDB::get_denied_cidrs(tx)?.into_iter() {
+            PeerDB::apply_cidr_filter(tx, &prefix, mask, "denied", i64::MAX)?;
+        }
+        Ok(())
+    }
+
     /// Get random neighbors, optionally always including allowed neighbors.
     /// Private IPs may be returned, if known.
     pub fn get_random_neighbors(
@@ -1918,6 +2019,9 @@ mod test {
             (ServiceFlags::RELAY as u16)
                 | (ServiceFlags::RPC as u16)
                 | (ServiceFlags::STACKERDB as u16)
+                | (ServiceFlags::COMPACT_NAKAMOTO_BLOCKS as u16)
+                | (ServiceFlags::MEMPOOL_SKETCH as u16)
+                | (ServiceFlags::ONION_ADDRESSES as u16)
         );
         assert_eq!(local_peer.stacker_dbs, vec![]);
 
@@ -3122,6 +3226,39 @@ mod test {
         assert_eq!(peer_allowed.allowed, 20000000);
     }
 
+    /// Verifies that PeerDB::set_onion_address() and PeerDB::get_onion_address() map synthetic
+    /// onion peer addresses back to their onion services
+    #[test]
+    fn test_peer_onion_addresses() {
+        let mut db =
+            PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &[], &[])
+                .unwrap();
+        let onion: OnionAddress = "duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczad.onion"
+            .parse()
+            .unwrap();
+        let addrbytes = onion.to_peer_address();
+
+        assert_eq!(
+            PeerDB::get_onion_address(db.conn(), &addrbytes).unwrap(),
+            None
+        );
+        {
+            let tx = db.tx_begin().unwrap();
+            PeerDB::set_onion_address(&tx, &onion).unwrap();
+            // idempotent
+            PeerDB::set_onion_address(&tx, &onion).unwrap();
+            tx.commit().unwrap();
+        }
+        assert_eq!(
+            PeerDB::get_onion_address(db.conn(), &addrbytes).unwrap(),
+            Some(onion)
+        );
+        assert_eq!(
+            PeerDB::get_onion_address(db.conn(), &PeerAddress::from_ipv4(127, 0, 0, 1)).unwrap(),
+            None
+        );
+    }
+
     /// Verifies that PeerDB::add_cidr_prefix(), PeerDB::get_denied_cidrs(), and
     /// PeerDB::get_allowed_cidrs() correctly store and load CIDR prefixes
     #[test]
@@ -3371,6 +3508,110 @@ mod test {
         assert_eq!(n2.denied, 67890);
     }
 
+    /// Verifies that PeerDB::remove_deny_cidr() and PeerDB::remove_allow_cidr() lift a CIDR
+    /// rule from the peers it covers, unless another rule still covers them, and that
+    /// PeerDB::get_denied_peers() finds the denied peers.
+    #[test]
+    fn test_peer_remove_cidr() {
+        let neighbor_1 = Neighbor {
+            addr: NeighborKey {
+                peer_version: 0x12345678,
+                network_id: 0x9abcdef0,
+                addrbytes: PeerAddress([
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
+                    0x0d, 0x0e, 0x0f,
+                ]),
+                port: 12345,
+            },
+            public_key: Secp256k1PublicKey::from_hex(
+                "02fa66b66f8971a8cd4d20ffded09674e030f0f33883f337f34b95ad4935bac0e3",
+            )
+            .unwrap(),
+            expire_block: 23456,
+            last_contact_time: 1552509642,
+            allowed: 0,
+            denied: 0,
+            asn: 34567,
+            org: 45678,
+            in_degree: 1,
+            out_degree: 1,
+        };
+
+        let mut db = PeerDB::connect_memory(
+            0x9abcdef0,
+            12345,
+            0,
+            "http://foo.com".into(),
+            &[],
+            &[neighbor_1.clone()],
+        )
+        .unwrap();
+
+        let get_neighbor_1 = |db: &PeerDB| {
+            PeerDB::get_peer(
+                db.conn(),
+                neighbor_1.addr.network_id,
+                &neighbor_1.addr.addrbytes,
+                neighbor_1.addr.port,
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        let prefix_48 = PeerAddress([
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ]);
+        let prefix_64 = PeerAddress([
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ]);
+
+        {
+            let tx = db.tx_begin().unwrap();
+            PeerDB::add_deny_cidr(&tx, &prefix_48, 48).unwrap();
+            PeerDB::add_deny_cidr(&tx, &prefix_64, 64).unwrap();
+            PeerDB::add_allow_cidr(&tx, &prefix_64, 64).unwrap();
+            tx.commit().unwrap();
+        }
+
+        assert_eq!(get_neighbor_1(&db).denied, i64::MAX);
+        assert_eq!(get_neighbor_1(&db).allowed, -1);
+
+        let denied =
+            PeerDB::get_denied_peers(db.conn(), 0x9abcdef0, get_epoch_time_secs()).unwrap();
+        assert_eq!(denied.len(), 1);
+        assert_eq!(denied[0].addr, neighbor_1.addr);
+
+        {
+            // still covered by the /48
+            let tx = db.tx_begin().unwrap();
+            PeerDB::remove_deny_cidr(&tx, &prefix_64, 64).unwrap();
+            tx.commit().unwrap();
+        }
+        assert_eq!(get_neighbor_1(&db).denied, i64::MAX);
+        assert_eq!(
+            PeerDB::get_denied_cidrs(db.conn()).unwrap(),
+            vec![(prefix_48.clone(), 48)]
+        );
+
+        {
+            let tx = db.tx_begin().unwrap();
+            PeerDB::remove_deny_cidr(&tx, &prefix_48, 48).unwrap();
+            PeerDB::remove_allow_cidr(&tx, &prefix_64, 64).unwrap();
+            tx.commit().unwrap();
+        }
+        assert_eq!(get_neighbor_1(&db).denied, 0);
+        assert_eq!(get_neighbor_1(&db).allowed, 0);
+        assert!(PeerDB::get_denied_cidrs(db.conn()).unwrap().is_empty());
+        assert!(PeerDB::get_allowed_cidrs(db.conn()).unwrap().is_empty());
+        assert!(
+            PeerDB::get_denied_peers(db.conn(), 0x9abcdef0, get_epoch_time_secs())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
     /// Tests that PeerDB::refresh_allowed() and PeerDB::refresh_denied() re-apply CIDR allow/deny
     /// rules to the DB.  Peers that match an allowed CIDR prefix remain allowed (or, if not
     /// allowed, are marked as allowed), and peers that match a denied CIDR prefix remain denied
@@ -188,18 +188,18 @@ pub enum BlockDownloaderState {
 
 #[derive(Debug)]
 pub struct BlockDownloader {
-    state: BlockDownloaderState,
+    pub(crate) state: BlockDownloaderState,
     pox_id: PoxId,
 
     /// Sortition height at which to attempt to fetch blocks
-    block_sortition_height: u64,
-    microblock_sortition_height: u64,
+    pub(crate) block_sortition_height: u64,
+    pub(crate) microblock_sortition_height: u64,
     next_block_sortition_height: u64,
     next_microblock_sortition_height: u64,
 
     /// How many blocks downloaded since we re-scanned the chain?
-    num_blocks_downloaded: u64,
-    num_microblocks_downloaded: u64,
+    pub(crate) num_blocks_downloaded: u64,
+    pub(crate) num_microblocks_downloaded: u64,
 
     /// How many times have we tried to download blocks, only to find nothing?
     empty_block_download_passes: u64,
@@ -226,8 +226,8 @@ pub struct BlockDownloader {
 
     /// In-flight requests for blocks and confirmed microblocks
     /// The key for each of these is the sortition height and _index_ block hash.
-    getblock_requests: HashMap<BlockRequestKey, usize>,
-    getmicroblocks_requests: HashMap<BlockRequestKey, usize>,
+    pub(crate) getblock_requests: HashMap<BlockRequestKey, usize>,
+    pub(crate) getmicroblocks_requests: HashMap<BlockRequestKey, usize>,
     blocks: HashMap<BlockRequestKey, StacksBlock>,
     microblocks: HashMap<BlockRequestKey, Vec<StacksMicroblock>>,
 
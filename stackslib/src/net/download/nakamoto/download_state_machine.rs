@@ -104,7 +104,7 @@ pub struct NakamotoDownloadStateMachine {
     /// Last burnchain tip we've seen
     last_sort_tip: Option<BlockSnapshot>,
     /// Download behavior we're in
-    state: NakamotoDownloadState,
+    pub(crate) state: NakamotoDownloadState,
     /// Map a tenure ID to its tenure start-block and end-block for each of our neighbors' invs
     tenure_block_ids: HashMap<NeighborAddress, AvailableTenures>,
     /// Who can serve a given tenure
@@ -114,13 +114,14 @@ pub struct NakamotoDownloadStateMachine {
     /// Unconfirmed tenure download schedule
     unconfirmed_tenure_download_schedule: VecDeque<NeighborAddress>,
     /// Ongoing unconfirmed tenure downloads, prioritized in who announces the latest block
-    unconfirmed_tenure_downloads: HashMap<NeighborAddress, NakamotoUnconfirmedTenureDownloader>,
+    pub(crate) unconfirmed_tenure_downloads:
+        HashMap<NeighborAddress, NakamotoUnconfirmedTenureDownloader>,
     /// Ongoing confirmed tenure downloads for when we know the start and end block hashes.
-    tenure_downloads: NakamotoTenureDownloaderSet,
+    pub(crate) tenure_downloads: NakamotoTenureDownloaderSet,
     /// comms to remote neighbors
     pub(super) neighbor_rpc: NeighborRPC,
     /// Nakamoto chain tip
-    nakamoto_tip: StacksBlockId,
+    pub(crate) nakamoto_tip: StacksBlockId,
     /// do we need to fetch unconfirmed tenures?
     fetch_unconfirmed_tenures: bool,
     /// last time an unconfirmed tenures was checked
@@ -54,6 +54,7 @@ use crate::net::http::{
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::server::HttpPeer;
+use crate::net::socks::{self, Socks5Proxy};
 use crate::net::{Error as NetError, MessageSequence, ProtocolFamily, StacksNodeState, UrlString};
 
 const CHUNK_BUF_LEN: usize = 32768;
@@ -772,6 +773,10 @@ impl MessageSequence for StacksHttpMessage {
     fn get_message_name(&self) -> &'static str {
         "StacksHttpMessage"
     }
+
+    fn is_transport_upgrade(&self) -> bool {
+        false
+    }
 }
 
 /// A partially-decoded, streamed HTTP message (response) being received.
@@ -1792,30 +1797,49 @@ pub fn send_http_request(
     request: StacksHttpRequest,
     timeout: Duration,
 ) -> Result<StacksHttpResponse, io::Error> {
-    // Find the host:port that works.
-    // This is sometimes necessary because `localhost` can resolve to both its ipv4 and ipv6
-    // addresses, but usually, Stacks services like event observers are only bound to ipv4
-    // addresses.  So, be sure to use an address that will lead to a socket connection!
-    let mut stream_and_addr = None;
-    let mut last_err = None;
-    for addr in format!("{host}:{port}").to_socket_addrs()? {
-        debug!("send_request: connect to {}", &addr);
-        match TcpStream::connect_timeout(&addr, timeout) {
-            Ok(sock) => {
-                stream_and_addr = Some((sock, addr));
-                break;
-            }
-            Err(e) => {
-                last_err = Some(e);
+    send_http_request_with_proxy(host, port, None, request, timeout)
+}
+
+/// Send an HTTP request to the given host:port, optionally through a SOCKS5 proxy.  If a proxy
+/// is given, then it resolves `host` for us.  Otherwise, this behaves like `send_http_request()`.
+pub fn send_http_request_with_proxy(
+    host: &str,
+    port: u16,
+    socks5_proxy: Option<&Socks5Proxy>,
+    request: StacksHttpRequest,
+    timeout: Duration,
+) -> Result<StacksHttpResponse, io::Error> {
+    let (mut stream, addr) = if let Some(proxy) = socks5_proxy {
+        debug!("send_request: connect to {host}:{port} via {proxy:?}");
+        let target = PeerHost::from_host_port(host.to_string(), port);
+        (socks::connect(proxy, &target, timeout)?, proxy.addr)
+    } else {
+        // Find the host:port that works.
+        // This is sometimes necessary because `localhost` can resolve to both its ipv4 and ipv6
+        // addresses, but usually, Stacks services like event observers are only bound to ipv4
+        // addresses.  So, be sure to use an address that will lead to a socket connection!
+        let mut stream_and_addr = None;
+        let mut last_err = None;
+        for addr in format!("{host}:{port}").to_socket_addrs()? {
+            debug!("send_request: connect to {}", &addr);
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(sock) => {
+                    stream_and_addr = Some((sock, addr));
+                    break;
+                }
+                Err(e) => {
+                    last_err = Some(e);
+                }
             }
         }
-    }
 
-    let Some((mut stream, addr)) = stream_and_addr else {
-        return Err(last_err.unwrap_or(io::Error::new(
-            io::ErrorKind::Other,
-            "Unable to connect to {host}:{port}",
-        )));
+        let Some(stream_and_addr) = stream_and_addr else {
+            return Err(last_err.unwrap_or(io::Error::new(
+                io::ErrorKind::Other,
+                "Unable to connect to {host}:{port}",
+            )));
+        };
+        stream_and_addr
     };
 
     stream.set_read_timeout(Some(timeout))?;
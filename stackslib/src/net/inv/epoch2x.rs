@@ -970,7 +970,7 @@ pub struct InvState {
     last_rescanned_at: u64,
 
     /// How many passes -- short and full -- have we done?
-    num_inv_syncs: u64,
+    pub(crate) num_inv_syncs: u64,
 
     /// What's the last reward cycle we _started_ the inv scan at?
     pub block_sortition_start: u64,
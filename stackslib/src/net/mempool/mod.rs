@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashSet;
 use std::net::SocketAddr;
 
 use rand::prelude::*;
@@ -33,6 +34,9 @@ use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, HttpRequestContents};
 use crate::util_lib::strings::UrlString;
 
+/// Maximum number of peers whose mempool sketch failures we remember
+const MAX_MEMPOOL_SKETCH_FAILURES: usize = 1024;
+
 /// The four states the mempool sync state machine can be in
 #[derive(Debug, Clone, PartialEq)]
 pub enum MempoolSyncState {
@@ -63,6 +67,11 @@ pub struct MempoolSync {
     pub(crate) mempool_sync_txs: u64,
     /// what's the API endpoint?
     api_endpoint: String,
+    /// should we query the current peer with a mempool sketch?
+    mempool_sync_use_sketch: bool,
+    /// data URLs of peers that could not reconcile one of our mempool sketches.  We don't send
+    /// them sketches again.
+    mempool_sketch_failures: HashSet<UrlString>,
 }
 
 impl MempoolSync {
@@ -74,6 +83,8 @@ impl MempoolSync {
             mempool_sync_completions: 0,
             mempool_sync_txs: 0,
             api_endpoint: "/v2/mempool/query".to_string(),
+            mempool_sync_use_sketch: false,
+            mempool_sketch_failures: HashSet::new(),
         }
     }
 
@@ -131,9 +142,11 @@ impl MempoolSync {
     fn mempool_sync_reset(&mut self) {
         self.mempool_state = MempoolSyncState::PickOutboundPeer;
         self.mempool_sync_timeout = 0;
+        self.mempool_sync_use_sketch = false;
     }
 
     /// Pick a peer to mempool sync with.
+    /// If both we and the peer support set reconciliation, then the sync will use a mempool sketch.
     /// Returns Ok(None) if we're done syncing the mempool.
     /// Returns Ok(Some(..)) if we're not done, and can proceed
     /// Returns the new sync state -- either ResolveURL if we need to resolve a data URL,
@@ -151,6 +164,8 @@ impl MempoolSync {
             return Ok(None);
         }
 
+        let local_supports_sketch =
+            ConversationP2P::supports_mempool_sketch(network.get_local_peer().services);
         let mut idx = thread_rng().gen::<usize>() % num_peers;
         let mut mempool_sync_data_url = None;
         let mut mempool_sync_data_url_and_sockaddr = None;
@@ -173,6 +188,9 @@ impl MempoolSync {
             if convo.data_url.is_empty() {
                 continue;
             }
+            self.mempool_sync_use_sketch = local_supports_sketch
+                && ConversationP2P::supports_mempool_sketch(convo.peer_services)
+                && !self.mempool_sketch_failures.contains(&convo.data_url);
             // already resolved?
             if let Some(sockaddr) = convo.data_ip.as_ref() {
                 mempool_sync_data_url_and_sockaddr =
@@ -329,7 +347,11 @@ impl MempoolSync {
         mempool: &MemPoolDB,
         page_id: Txid,
     ) -> Result<(bool, Option<usize>), NetError> {
-        let sync_data = mempool.make_mempool_sync_data()?;
+        let sync_data = if self.mempool_sync_use_sketch {
+            mempool.make_mempool_sketch_sync_data()?
+        } else {
+            mempool.make_mempool_sync_data()?
+        };
         let request = StacksHttpRequest::new_for_peer(
             PeerHost::from_socketaddr(addr),
             "POST".into(),
@@ -347,6 +369,8 @@ impl MempoolSync {
     /// Return Ok(true, ..) if we're done with the mempool sync.
     /// Return Ok(false, ..) if we have more work to do.
     /// Returns the page ID of the next request to make, and the list of transactions we got
+    /// Returns Ok((true, Some(page_id), None)) if the peer could not reconcile our mempool sketch,
+    /// in which case the query should be retried from page_id without one.
     #[cfg_attr(test, mutants::skip)]
     fn mempool_sync_recv_response(
         &mut self,
@@ -385,6 +409,15 @@ impl MempoolSync {
                                 return Ok((true, page_id_opt, Some(txs)));
                             }
                             Err(e) => {
+                                if self.mempool_sync_use_sketch {
+                                    // fall back to a bloom filter or tag list
+                                    debug!(
+                                        "{:?}: Mempool sync peer could not reconcile our mempool sketch: {:?}",
+                                        &network.local_peer, &e
+                                    );
+                                    self.mempool_sync_use_sketch = false;
+                                    return Ok((true, Some(Txid([0u8; 32])), None));
+                                }
                                 warn!(
                                     "{:?}: Mempool sync request did not receive a txs page: {:?}",
                                     &network.local_peer, &e
@@ -567,7 +600,16 @@ impl MempoolSync {
                             };
                             return (ret, Some(txs));
                         }
-                        Ok((true, _, None)) => {
+                        Ok((true, Some(page_id), None)) => {
+                            // retry without a mempool sketch, and don't send this peer one again
+                            if self.mempool_sketch_failures.len() >= MAX_MEMPOOL_SKETCH_FAILURES {
+                                self.mempool_sketch_failures.clear();
+                            }
+                            self.mempool_sketch_failures.insert(url.clone());
+                            self.mempool_state =
+                                MempoolSyncState::SendQuery(url.clone(), addr.clone(), page_id);
+                        }
+                        Ok((true, None, None)) => {
                             // done! did not get data
                             self.mempool_sync_reset();
                             return (true, None);
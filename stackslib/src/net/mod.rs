@@ -50,7 +50,7 @@ use stacks_common::codec::{
 use stacks_common::types::chainstate::{
     BlockHeaderHash, BurnchainHeaderHash, PoxId, StacksAddress, StacksBlockId,
 };
-use stacks_common::types::net::{Error as AddrError, PeerAddress, PeerHost};
+use stacks_common::types::net::{Error as AddrError, OnionAddress, PeerAddress, PeerHost};
 use stacks_common::types::StacksPublicKeyBuffer;
 use stacks_common::util::hash::{
     hex_bytes, to_hex, Hash160, Sha256Sum, DOUBLE_SHA256_ENCODED_SIZE, HASH160_ENCODED_SIZE,
@@ -68,7 +68,7 @@ use crate::chainstate::burn::db::sortdb::SortitionDB;
 use crate::chainstate::burn::{ConsensusHash, Opcodes};
 use crate::chainstate::coordinator::comm::CoordinatorChannels;
 use crate::chainstate::coordinator::Error as coordinator_error;
-use crate::chainstate::nakamoto::{NakamotoBlock, NakamotoChainState};
+use crate::chainstate::nakamoto::{NakamotoBlock, NakamotoBlockHeader, NakamotoChainState};
 use crate::chainstate::stacks::boot::{
     BOOT_TEST_POX_4_AGG_KEY_CONTRACT, BOOT_TEST_POX_4_AGG_KEY_FNAME,
 };
@@ -117,6 +117,7 @@ pub mod chat;
 /// Implements serialization and deserialization for `StacksMessage` types.
 /// Also has functionality to sign, verify, and ensure well-formedness of messages.
 pub mod codec;
+pub mod compact;
 pub mod connection;
 pub mod db;
 /// Implements `DNSResolver`, a simple DNS resolver state machine. Also implements `DNSClient`,
@@ -139,7 +140,9 @@ pub mod prune;
 pub mod relay;
 pub mod rpc;
 pub mod server;
+pub mod socks;
 pub mod stackerdb;
+pub mod transport;
 pub mod unsolicited;
 
 pub use crate::net::neighbors::{NeighborComms, PeerNetworkComms};
@@ -938,6 +941,61 @@ pub struct NakamotoBlocksData {
     pub blocks: Vec<NakamotoBlock>,
 }
 
+/// Compact Nakamoto epoch 3.x block pushed.
+/// Instead of the block's transactions, this carries a short tag for each of them, so the
+/// recipient can rebuild the block from the transactions already in its mempool.  The header
+/// carries the signer signatures, so the reconstructed block can be validated as usual.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactNakamotoBlockData {
+    /// The block header, including the signer signatures
+    pub header: NakamotoBlockHeader,
+    /// Sender-chosen salt for the transaction tags, so tag collisions can't be precomputed
+    pub nonce: u64,
+    /// The tag of each transaction in the block, in block order.
+    /// Tags are computed with `TxTag::from(&self.tx_tag_seed(), &txid)`.
+    pub tx_tags: Vec<TxTag>,
+    /// Transactions that the recipient is unlikely to have in its mempool (i.e. the coinbase and
+    /// tenure-change transactions).  These must also be represented in `tx_tags`.
+    pub prefilled_txs: Vec<StacksTransaction>,
+}
+
+/// Request for the transactions of a compact Nakamoto block that could not be found in the
+/// mempool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetNakamotoBlockTxsData {
+    /// The block being reconstructed
+    pub block_id: StacksBlockId,
+    /// Indexes of the missing transactions, in increasing order.  If empty, then the requester
+    /// asks for the entire block instead (i.e. as a `NakamotoBlocks` message).
+    pub indexes: Vec<u16>,
+}
+
+/// Response to a GetNakamotoBlockTxs request
+#[derive(Debug, Clone, PartialEq)]
+pub struct NakamotoBlockTxsData {
+    /// The block being reconstructed
+    pub block_id: StacksBlockId,
+    /// The requested transactions, in the order of the request's indexes
+    pub txs: Vec<StacksTransaction>,
+}
+
+/// Request to upgrade the connection to encrypted transport.  All bytes the sender writes after
+/// this message are encrypted records (see `net::transport`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncryptTransportData {
+    /// The sender's ephemeral key for this connection's key exchange
+    pub ephemeral_public_key: StacksPublicKeyBuffer,
+}
+
+/// The sender's onion service address.  It's sent after the handshake by peers which are reachable
+/// through Tor, since the handshake itself only carries the synthetic peer address that stands in
+/// for the onion service.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnionAddressData {
+    pub onion_address: OnionAddress,
+    pub port: u16,
+}
+
 /// Microblocks pushed
 #[derive(Debug, Clone, PartialEq)]
 pub struct MicroblocksData {
@@ -1028,6 +1086,10 @@ pub enum ServiceFlags {
     RELAY = 0x01,
     RPC = 0x02,
     STACKERDB = 0x04,
+    COMPACT_NAKAMOTO_BLOCKS = 0x08,
+    MEMPOOL_SKETCH = 0x10,
+    ENCRYPTED_TRANSPORT = 0x20,
+    ONION_ADDRESSES = 0x40,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -1062,6 +1124,8 @@ pub mod NackErrorCodes {
     pub const FutureVersion: u32 = 9;
     /// The referenced StackerDB state view is stale locally relative to the requested version
     pub const FutureView: u32 = 10;
+    /// The requested Nakamoto block is not known to this node
+    pub const NoSuchBlock: u32 = 11;
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -1175,6 +1239,12 @@ pub enum StacksMessageType {
     GetNakamotoInv(GetNakamotoInvData),
     NakamotoInv(NakamotoInvData),
     NakamotoBlocks(NakamotoBlocksData),
+    CompactNakamotoBlock(CompactNakamotoBlockData),
+    GetNakamotoBlockTxs(GetNakamotoBlockTxsData),
+    NakamotoBlockTxs(NakamotoBlockTxsData),
+    // transport
+    EncryptTransport(EncryptTransportData),
+    OnionAddress(OnionAddressData),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -1210,6 +1280,12 @@ pub enum StacksMessageID {
     GetNakamotoInv = 26,
     NakamotoInv = 27,
     NakamotoBlocks = 28,
+    CompactNakamotoBlock = 29,
+    GetNakamotoBlockTxs = 30,
+    NakamotoBlockTxs = 31,
+    // transport
+    EncryptTransport = 32,
+    OnionAddress = 33,
     // reserved
     Reserved = 255,
 }
@@ -1226,6 +1302,9 @@ pub struct StacksMessage {
 pub trait MessageSequence {
     fn request_id(&self) -> u32;
     fn get_message_name(&self) -> &'static str;
+    /// Does this message switch the sender's side of the connection to encrypted transport once
+    /// it has been sent?
+    fn is_transport_upgrade(&self) -> bool;
 }
 
 pub trait ProtocolFamily {
@@ -1311,6 +1390,10 @@ pub const BLOCKS_PUSHED_MAX: u32 = 32;
 // message.
 pub const NAKAMOTO_BLOCKS_PUSHED_MAX: u32 = 32;
 
+// maximum number of transactions that can be tagged in a compact Nakamoto block, or requested
+// from one.  Transactions are identified by a u16 index.
+pub const COMPACT_NAKAMOTO_BLOCK_TXS_MAX: u32 = u16::MAX as u32;
+
 /// Neighbor to drop
 #[derive(Clone, Eq, PartialOrd, Ord, Debug)]
 pub struct DropNeighbor {
@@ -1512,6 +1595,11 @@ pub struct NetworkResult {
     pub pushed_microblocks: HashMap<NeighborKey, Vec<(Vec<RelayData>, MicroblocksData)>>,
     /// all Stacks 3.x blocks pushed to us
     pub pushed_nakamoto_blocks: HashMap<NeighborKey, Vec<(Vec<RelayData>, NakamotoBlocksData)>>,
+    /// all compact Nakamoto blocks pushed to us and their message relay hints
+    pub pushed_compact_nakamoto_blocks:
+        HashMap<NeighborKey, Vec<(Vec<RelayData>, CompactNakamotoBlockData)>>,
+    /// all transactions sent to us to complete compact Nakamoto blocks
+    pub pushed_nakamoto_block_txs: HashMap<NeighborKey, Vec<NakamotoBlockTxsData>>,
     /// transactions sent to us by the http server
     pub uploaded_transactions: Vec<StacksTransaction>,
     /// blocks sent to us via the http server
@@ -1574,6 +1662,8 @@ impl NetworkResult {
             pushed_blocks: HashMap::new(),
             pushed_microblocks: HashMap::new(),
             pushed_nakamoto_blocks: HashMap::new(),
+            pushed_compact_nakamoto_blocks: HashMap::new(),
+            pushed_nakamoto_block_txs: HashMap::new(),
             uploaded_transactions: vec![],
             uploaded_nakamoto_blocks: vec![],
             uploaded_blocks: vec![],
@@ -1921,6 +2011,40 @@ impl NetworkResult {
             }
         }
 
+        // merge pushed compact nakamoto blocks, but drop the ones we already have in full
+        for (nk, mut compact_block_data) in self.pushed_compact_nakamoto_blocks.drain() {
+            compact_block_data.retain(|(_, compact_block)| {
+                let retain = !newer_naka_blocks.contains(&compact_block.block_id());
+                if !retain {
+                    debug!(
+                        "Drop duplicate pushed compact nakamoto block {}",
+                        &compact_block.block_id()
+                    );
+                }
+                retain
+            });
+            if compact_block_data.is_empty() {
+                continue;
+            }
+
+            if let Some(newer_compact_data) = newer.pushed_compact_nakamoto_blocks.get_mut(&nk) {
+                newer_compact_data.append(&mut compact_block_data);
+            } else {
+                newer
+                    .pushed_compact_nakamoto_blocks
+                    .insert(nk, compact_block_data);
+            }
+        }
+
+        // no dedup here, but do merge
+        for (nk, mut block_txs_data) in self.pushed_nakamoto_block_txs.drain() {
+            if let Some(newer_block_txs_data) = newer.pushed_nakamoto_block_txs.get_mut(&nk) {
+                newer_block_txs_data.append(&mut block_txs_data);
+            } else {
+                newer.pushed_nakamoto_block_txs.insert(nk, block_txs_data);
+            }
+        }
+
         // merge uploaded data, but deduplicate
         self.uploaded_transactions.retain(|tx| {
             let retain = !newer_txids.contains(&tx.txid());
@@ -2092,6 +2216,8 @@ impl NetworkResult {
         !self.nakamoto_blocks.is_empty()
             || !self.pushed_nakamoto_blocks.is_empty()
             || !self.uploaded_nakamoto_blocks.is_empty()
+            || !self.pushed_compact_nakamoto_blocks.is_empty()
+            || !self.pushed_nakamoto_block_txs.is_empty()
     }
 
     pub fn has_transactions(&self) -> bool {
@@ -2174,6 +2300,28 @@ impl NetworkResult {
                                 .insert(neighbor_key.clone(), vec![(message.relayers, block_data)]);
                         }
                     }
+                    StacksMessageType::CompactNakamotoBlock(compact_block_data) => {
+                        if let Some(compact_blocks_msgs) =
+                            self.pushed_compact_nakamoto_blocks.get_mut(&neighbor_key)
+                        {
+                            compact_blocks_msgs.push((message.relayers, compact_block_data));
+                        } else {
+                            self.pushed_compact_nakamoto_blocks.insert(
+                                neighbor_key.clone(),
+                                vec![(message.relayers, compact_block_data)],
+                            );
+                        }
+                    }
+                    StacksMessageType::NakamotoBlockTxs(block_txs_data) => {
+                        if let Some(block_txs_msgs) =
+                            self.pushed_nakamoto_block_txs.get_mut(&neighbor_key)
+                        {
+                            block_txs_msgs.push(block_txs_data);
+                        } else {
+                            self.pushed_nakamoto_block_txs
+                                .insert(neighbor_key.clone(), vec![block_txs_data]);
+                        }
+                    }
                     StacksMessageType::StackerDBPushChunk(chunk_data) => {
                         self.pushed_stackerdb_chunks.push(chunk_data)
                     }
@@ -67,8 +67,9 @@ use crate::net::mempool::MempoolSync;
 use crate::net::neighbors::*;
 use crate::net::poll::{NetworkPollState, NetworkState};
 use crate::net::prune::*;
-use crate::net::relay::{RelayerStats, *, *};
+use crate::net::relay::{RelayerStats, *};
 use crate::net::server::*;
+use crate::net::socks::Socks5Handshake;
 use crate::net::stackerdb::{StackerDBConfig, StackerDBSync, StackerDBTx, StackerDBs};
 use crate::net::{Error as net_error, Neighbor, NeighborKey, *};
 use crate::util_lib::boot::boot_code_id;
@@ -85,6 +86,7 @@ pub enum NetworkRequest {
     ), // announce to all wanting neighbors that we have these confirmed microblock streams
     Relay(NeighborKey, StacksMessage),
     Broadcast(Vec<RelayData>, StacksMessageType),
+    Send(NeighborKey, StacksMessageType), // sign and send a message to a single neighbor, expecting no reply
 }
 
 /// Handle for other threads to use to issue p2p network requests.
@@ -161,6 +163,17 @@ impl NetworkHandle {
         self.send_request(req)
     }
 
+    /// Sign and send a message to a single peer via the p2p network thread, expecting no reply.
+    /// Called from outside the p2p thread by other threads.
+    pub fn send_message(
+        &mut self,
+        neighbor_key: NeighborKey,
+        msg: StacksMessageType,
+    ) -> Result<(), net_error> {
+        let req = NetworkRequest::Send(neighbor_key, msg);
+        self.send_request(req)
+    }
+
     /// Broadcast a message to our neighbors via the p2p network thread.
     /// Add relay information for each one.
     pub fn broadcast_message(
@@ -203,6 +216,9 @@ pub struct ConnectingPeer {
     outbound: bool,
     timestamp: u64,
     nk: NeighborKey,
+    /// If this socket is connected to a SOCKS5 proxy, then this is the handshake which gets the
+    /// proxy to connect it to the peer.
+    socks5: Option<Socks5Handshake>,
 }
 
 impl ConnectingPeer {
@@ -217,6 +233,23 @@ impl ConnectingPeer {
             outbound,
             timestamp,
             nk,
+            socks5: None,
+        }
+    }
+
+    /// Instantiate an outbound connecting peer whose socket goes to a SOCKS5 proxy
+    pub fn new_proxied(
+        socket: mio_net::TcpStream,
+        timestamp: u64,
+        nk: NeighborKey,
+        socks5: Socks5Handshake,
+    ) -> Self {
+        Self {
+            socket,
+            outbound: true,
+            timestamp,
+            nk,
+            socks5: Some(socks5),
         }
     }
 }
@@ -325,6 +358,8 @@ pub enum DropReason {
     OrgDominatesPeerTable,
     /// There was a request to drop the peer due to a testing directive
     FaultInjection,
+    /// The node operator asked to drop the peer
+    AdminRequest,
 }
 
 impl std::fmt::Display for DropReason {
@@ -352,6 +387,9 @@ impl std::fmt::Display for DropReason {
             DropReason::FaultInjection => {
                 write!(f, "The peer was dropped due to a testing directive")
             }
+            DropReason::AdminRequest => {
+                write!(f, "The peer was dropped at the node operator's request")
+            }
         }
     }
 }
@@ -405,6 +443,8 @@ pub enum DropSource {
     BlockDownloaderGetBlocks,
     /// From a getmicroblocks attempt in the block downloader
     BlockDownloaderGetMicroblocks,
+    /// From the admin RPC
+    AdminRPC,
 }
 
 impl std::fmt::Display for DropSource {
@@ -439,6 +479,7 @@ impl std::fmt::Display for DropSource {
             DropSource::NetworkBlockDownload => write!(f, "NetworkBlockDownload"),
             DropSource::BlockDownloaderGetBlocks => write!(f, "BlockDownloaderGetBlocks"),
             DropSource::BlockDownloaderGetMicroblocks => write!(f, "BlockDownloaderGetMicroblocks"),
+            DropSource::AdminRPC => write!(f, "AdminRPC"),
         }
     }
 }
@@ -1238,6 +1279,51 @@ impl PeerNetwork {
         })?
     }
 
+    /// Broadcast Nakamoto blocks to a list of neighbors.
+    /// If both we and a neighbor support compact Nakamoto block relay, then the neighbor is sent a
+    /// compact block for each block, which it will rebuild from its mempool.  The other neighbors
+    /// are sent the full blocks.
+    fn broadcast_nakamoto_blocks(
+        &mut self,
+        neighbor_keys: Vec<NeighborKey>,
+        relay_hints: Vec<RelayData>,
+        blocks_data: NakamotoBlocksData,
+    ) {
+        let local_supports_compact =
+            ConversationP2P::supports_compact_nakamoto_blocks(self.local_peer.services);
+        let (compact_neighbor_keys, full_neighbor_keys): (Vec<_>, Vec<_>) =
+            neighbor_keys.into_iter().partition(|nk| {
+                local_supports_compact
+                    && self
+                        .events
+                        .get(nk)
+                        .and_then(|event_id| self.peers.get(event_id))
+                        .map(|convo| {
+                            ConversationP2P::supports_compact_nakamoto_blocks(convo.peer_services)
+                        })
+                        .unwrap_or(false)
+            });
+
+        if !compact_neighbor_keys.is_empty() {
+            for block in blocks_data.blocks.iter() {
+                let compact_block =
+                    CompactNakamotoBlockData::from_block(block, thread_rng().gen::<u64>());
+                self.broadcast_message(
+                    compact_neighbor_keys.clone(),
+                    relay_hints.clone(),
+                    StacksMessageType::CompactNakamotoBlock(compact_block),
+                );
+            }
+        }
+        if !full_neighbor_keys.is_empty() {
+            self.broadcast_message(
+                full_neighbor_keys,
+                relay_hints,
+                StacksMessageType::NakamotoBlocks(blocks_data),
+            );
+        }
+    }
+
     /// Broadcast a message to a list of neighbors.
     /// Neighbors in the `relay_hints` vec will *not* receive data, since they were the one(s) that
     /// sent this peer the message in the first place.
@@ -1422,14 +1508,19 @@ impl PeerNetwork {
             return Ok(event_id);
         }
 
+        let socks5_handshake = self.make_socks5_handshake(neighbor)?;
         let next_event_id = match self.network {
             None => {
                 debug!("{:?}: network not connected", &self.local_peer);
                 return Err(net_error::NotConnected);
             }
             Some(ref mut network) => {
+                let connect_addr = match socks5_handshake {
+                    Some(ref handshake) => handshake.proxy().addr,
+                    None => neighbor.addrbytes.to_socketaddr(neighbor.port),
+                };
                 let sock = NetworkState::connect(
-                    &neighbor.addrbytes.to_socketaddr(neighbor.port),
+                    &connect_addr,
                     self.connection_opts.socket_send_buffer_size,
                     self.connection_opts.socket_recv_buffer_size,
                 )?;
@@ -1437,10 +1528,18 @@ impl PeerNetwork {
                 let registered_event_id =
                     network.register(self.p2p_network_handle, hint_event_id, &sock)?;
 
-                self.connecting.insert(
-                    registered_event_id,
-                    ConnectingPeer::new(sock, true, get_epoch_time_secs(), neighbor.clone()),
-                );
+                let connecting_peer = match socks5_handshake {
+                    Some(handshake) => ConnectingPeer::new_proxied(
+                        sock,
+                        get_epoch_time_secs(),
+                        neighbor.clone(),
+                        handshake,
+                    ),
+                    None => {
+                        ConnectingPeer::new(sock, true, get_epoch_time_secs(), neighbor.clone())
+                    }
+                };
+                self.connecting.insert(registered_event_id, connecting_peer);
                 registered_event_id
             }
         };
@@ -1448,6 +1547,53 @@ impl PeerNetwork {
         Ok(next_event_id)
     }
 
+    /// If we make outbound connections through a SOCKS5 proxy, then start the handshake which
+    /// will get the proxy to connect us to this neighbor.  Onion peers can only be reached this
+    /// way, and only once we have learned their onion addresses.  The same goes for peers known
+    /// by hostnames that the proxy resolves for us.
+    fn make_socks5_handshake(
+        &self,
+        neighbor: &NeighborKey,
+    ) -> Result<Option<Socks5Handshake>, net_error> {
+        let Some(ref proxy) = self.connection_opts.socks5_proxy else {
+            if neighbor.addrbytes.is_onion() || neighbor.addrbytes.is_proxied_hostname() {
+                debug!(
+                    "{:?}: cannot connect to synthetic peer {:?} without a SOCKS5 proxy",
+                    &self.local_peer, neighbor
+                );
+                return Err(net_error::ConnectionError);
+            }
+            return Ok(None);
+        };
+        let target = if neighbor.addrbytes.is_onion() {
+            let Some(onion) = PeerDB::get_onion_address(self.peerdb.conn(), &neighbor.addrbytes)?
+            else {
+                debug!(
+                    "{:?}: do not know the onion address of {:?}",
+                    &self.local_peer, neighbor
+                );
+                return Err(net_error::ConnectionError);
+            };
+            PeerHost::DNS(onion.hostname(), neighbor.port)
+        } else if neighbor.addrbytes.is_proxied_hostname() {
+            let Some(hostname) = self
+                .connection_opts
+                .socks5_hostnames
+                .get(&neighbor.addrbytes)
+            else {
+                debug!(
+                    "{:?}: do not know the hostname of {:?}",
+                    &self.local_peer, neighbor
+                );
+                return Err(net_error::ConnectionError);
+            };
+            PeerHost::DNS(hostname.clone(), neighbor.port)
+        } else {
+            PeerHost::IP(neighbor.addrbytes, neighbor.port)
+        };
+        Ok(Some(Socks5Handshake::new(proxy, target)))
+    }
+
     /// Given a list of neighbors keys, find the _set_ of neighbor keys that represent unique
     /// connections.  This is used by the broadcast logic to ensure that we only send a message to
     /// a peer once, even if we have both an inbound and outbound connection to it.
@@ -1645,7 +1791,15 @@ impl PeerNetwork {
                         return Err(net_error::InvalidMessage);
                     }
                 }?;
-                self.broadcast_message(neighbor_keys, relay_hints, msg);
+                if let StacksMessageType::NakamotoBlocks(data) = msg {
+                    self.broadcast_nakamoto_blocks(neighbor_keys, relay_hints, data);
+                } else {
+                    self.broadcast_message(neighbor_keys, relay_hints, msg);
+                }
+                Ok(())
+            }
+            NetworkRequest::Send(neighbor_key, msg) => {
+                self.broadcast_message(vec![neighbor_key], vec![], msg);
                 Ok(())
             }
         }
@@ -1969,8 +2123,10 @@ impl PeerNetwork {
         event_id: usize,
         socket: mio_net::TcpStream,
         outbound: bool,
+        proxied_addr: Option<SocketAddr>,
     ) -> Result<(), net_error> {
-        let client_addr = match socket.peer_addr() {
+        // if the socket goes through a proxy, then its peer address is the proxy's
+        let client_addr = match proxied_addr.map_or_else(|| socket.peer_addr(), Ok) {
             Ok(addr) => addr,
             Err(e) => {
                 debug!(
@@ -2175,6 +2331,34 @@ impl PeerNetwork {
         });
     }
 
+    /// Deregister every connection to the peer at `addrbytes:port`, whether that is the address
+    /// of the connection itself or the address the peer gave in its handshake.
+    /// Returns the neighbor keys of the connections that were dropped.
+    pub fn deregister_peer_address(
+        &mut self,
+        addrbytes: &PeerAddress,
+        port: u16,
+        reason: DropReason,
+        source: DropSource,
+    ) -> Vec<NeighborKey> {
+        let to_drop: Vec<_> = self
+            .peers
+            .values()
+            .filter(|convo| {
+                let nk = convo.to_neighbor_key();
+                let handshake_nk = convo.to_handshake_neighbor_key();
+                (nk.addrbytes == *addrbytes && nk.port == port)
+                    || (handshake_nk.addrbytes == *addrbytes && handshake_nk.port == port)
+            })
+            .map(|convo| convo.to_neighbor_key())
+            .collect();
+
+        for nk in to_drop.iter() {
+            self.deregister_neighbor(nk, reason.clone(), source);
+        }
+        to_drop
+    }
+
     /// Deregister and ban a neighbor
     pub fn deregister_and_ban_neighbor(
         &mut self,
@@ -2299,7 +2483,7 @@ impl PeerNetwork {
             };
 
             // start tracking it
-            if let Err(_e) = self.register_peer(event_id, client_sock, false) {
+            if let Err(_e) = self.register_peer(event_id, client_sock, false, None) {
                 // NOTE: register_peer will deregister the socket for us
                 continue;
             }
@@ -2420,12 +2604,52 @@ impl PeerNetwork {
     /// Process any newly-connecting sockets
     fn process_connecting_sockets(&mut self, poll_state: &mut NetworkPollState) {
         for event_id in poll_state.ready.iter() {
-            if self.connecting.contains_key(event_id) {
+            if let Some(connecting) = self.connecting.get_mut(event_id) {
+                // if we're connecting through a proxy, then the proxy must connect us to the peer
+                // before we can register it
+                if let Some(handshake) = connecting.socks5.as_mut() {
+                    match handshake.step(&mut connecting.socket) {
+                        Ok(true) => {
+                            debug!(
+                                "{:?}: SOCKS5 proxy {:?} connected event {} to {}",
+                                &self.local_peer,
+                                handshake.proxy(),
+                                event_id,
+                                handshake.target()
+                            );
+                        }
+                        Ok(false) => {
+                            continue;
+                        }
+                        Err(_e) => {
+                            debug!(
+                                "{:?}: SOCKS5 proxy {:?} failed to connect event {} to {}: {:?}",
+                                &self.local_peer,
+                                handshake.proxy(),
+                                event_id,
+                                handshake.target(),
+                                &_e
+                            );
+                            if let Some(ConnectingPeer { socket, .. }) =
+                                self.connecting.remove(event_id)
+                            {
+                                self.deregister_socket(*event_id, socket);
+                            }
+                            continue;
+                        }
+                    }
+                }
+
                 let ConnectingPeer {
-                    socket, outbound, ..
+                    socket,
+                    outbound,
+                    nk,
+                    socks5,
+                    ..
                 } = self.connecting.remove(event_id).unwrap();
+                let proxied_addr = socks5.map(|_| nk.addrbytes.to_socketaddr(nk.port));
                 let sock_str = format!("{:?}", &socket);
-                if let Err(_e) = self.register_peer(*event_id, socket, outbound) {
+                if let Err(_e) = self.register_peer(*event_id, socket, outbound, proxied_addr) {
                     debug!(
                         "{:?}: Failed to register connecting socket on event {} ({}): {:?}",
                         &self.local_peer, event_id, sock_str, &_e
@@ -5611,8 +5835,7 @@ mod test {
     use clarity::vm::ast::stack_depth_checker::AST_CALL_STACK_DEPTH_BUFFER;
     use clarity::vm::types::StacksAddressExtensions;
     use clarity::vm::MAX_CALL_STACK_DEPTH;
-    use rand;
-    use rand::RngCore;
+    use rand::{self, RngCore};
     use stacks_common::types::chainstate::BurnchainHeaderHash;
     use stacks_common::util::secp256k1::Secp256k1PrivateKey;
     use stacks_common::util::{log, sleep_ms};
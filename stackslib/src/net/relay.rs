@@ -15,7 +15,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
-use std::{cmp, mem};
+use std::{cmp, iter, mem};
 
 use clarity::vm::ast::errors::{ParseError, ParseErrors};
 use clarity::vm::ast::{ast_check_size, ASTRules};
@@ -52,6 +52,7 @@ use crate::clarity_vm::clarity::Error as clarity_error;
 use crate::core::mempool::{MemPoolDB, *};
 use crate::monitoring::update_stacks_tip_height;
 use crate::net::chat::*;
+use crate::net::compact::PartialNakamotoBlock;
 use crate::net::connection::*;
 use crate::net::db::*;
 use crate::net::httpcore::*;
@@ -69,6 +70,9 @@ pub const MAX_RELAYER_STATS: usize = 4096;
 pub const MAX_RECENT_MESSAGES: usize = 256;
 pub const MAX_RECENT_MESSAGE_AGE: usize = 600; // seconds; equal to the expected epoch length
 pub const RELAY_DUPLICATE_INFERENCE_WARMUP: usize = 128;
+pub const COMPACT_NAKAMOTO_BLOCK_TIMEOUT_MS: u128 = 30_000; // how long to wait for a compact block's missing txs
+pub const MAX_PENDING_COMPACT_NAKAMOTO_BLOCKS: usize = 64; // compact blocks awaiting missing txs, from all neighbors
+pub const MAX_PENDING_COMPACT_NAKAMOTO_BLOCKS_PER_NEIGHBOR: usize = 4; // compact blocks awaiting missing txs, from one neighbor
 
 #[cfg(any(test, feature = "testing"))]
 pub mod fault_injection {
@@ -126,6 +130,25 @@ pub struct Relayer {
     /// Maps to tenure ID and timestamp, so we can garbage-collect.
     /// Timestamp is in milliseconds
     recently_sent_nakamoto_blocks: HashMap<StacksBlockId, (ConsensusHash, u128)>,
+    /// Compact Nakamoto blocks we're still rebuilding, because we asked their senders for the
+    /// transactions we couldn't find in our mempool.  Bounded by
+    /// MAX_PENDING_COMPACT_NAKAMOTO_BLOCKS in total and by
+    /// MAX_PENDING_COMPACT_NAKAMOTO_BLOCKS_PER_NEIGHBOR for each sender.
+    pending_compact_nakamoto_blocks: HashMap<StacksBlockId, PendingCompactNakamotoBlock>,
+}
+
+/// A compact Nakamoto block whose missing transactions we have requested from its sender
+struct PendingCompactNakamotoBlock {
+    /// The peer that sent us the compact block, and which we asked for its missing transactions
+    neighbor_key: NeighborKey,
+    /// Relay hints of the compact block
+    relayers: Vec<RelayData>,
+    /// The block, minus the missing transactions
+    partial_block: PartialNakamotoBlock,
+    /// The indexes of the missing transactions
+    requested_indexes: Vec<u16>,
+    /// When we asked, in milliseconds, so we can garbage-collect
+    requested_at_ms: u128,
 }
 
 #[derive(Debug)]
@@ -591,6 +614,7 @@ impl Relayer {
             connection_opts,
             stacker_dbs,
             recently_sent_nakamoto_blocks: HashMap::new(),
+            pending_compact_nakamoto_blocks: HashMap::new(),
         }
     }
 
@@ -647,17 +671,36 @@ impl Relayer {
         chainstate: &mut StacksChainState,
         stacks_tip: &StacksBlockId,
         nakamoto_blocks_data: &NakamotoBlocksData,
+    ) -> Result<(), net_error> {
+        Relayer::validate_nakamoto_block_headers_push(
+            burnchain,
+            sortdb,
+            chainstate,
+            stacks_tip,
+            nakamoto_blocks_data
+                .blocks
+                .iter()
+                .map(|block| &block.header),
+        )
+    }
+
+    /// Given the headers of Nakamoto blocks pushed to us (in full or compact form), verify that
+    /// they correspond to expected block data and are signed by the active reward set.
+    pub fn validate_nakamoto_block_headers_push<'a>(
+        burnchain: &Burnchain,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        stacks_tip: &StacksBlockId,
+        headers: impl Iterator<Item = &'a NakamotoBlockHeader>,
     ) -> Result<(), net_error> {
         let conn = sortdb.index_conn();
         let mut loaded_reward_sets = HashMap::new();
         let tip_sn = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
 
-        for nakamoto_block in nakamoto_blocks_data.blocks.iter() {
+        for header in headers {
             // is this the right Stacks block for this sortition?
-            let Some(sn) = SortitionDB::get_block_snapshot_consensus(
-                conn.conn(),
-                &nakamoto_block.header.consensus_hash,
-            )?
+            let Some(sn) =
+                SortitionDB::get_block_snapshot_consensus(conn.conn(), &header.consensus_hash)?
             else {
                 // don't know this sortition yet
                 continue;
@@ -666,7 +709,7 @@ impl Relayer {
             if !sn.pox_valid {
                 info!(
                     "Pushed block from consensus hash {} corresponds to invalid PoX state",
-                    nakamoto_block.header.consensus_hash
+                    header.consensus_hash
                 );
                 continue;
             }
@@ -674,7 +717,7 @@ impl Relayer {
             if !sn.sortition {
                 info!(
                     "No such sortition in block with consensus hash {}",
-                    &nakamoto_block.header.consensus_hash
+                    &header.consensus_hash
                 );
                 return Err(net_error::InvalidMessage);
             }
@@ -738,11 +781,11 @@ impl Relayer {
                 return Err(net_error::NoPoXRewardSet(sn_rc));
             };
 
-            if let Err(e) = nakamoto_block.header.verify_signer_signatures(reward_set) {
+            if let Err(e) = header.verify_signer_signatures(reward_set) {
                 warn!(
                     "Signature verification failure for Nakamoto block";
-                    "consensus_hash" => %nakamoto_block.header.consensus_hash,
-                    "block_hash" => %nakamoto_block.header.block_hash(),
+                    "consensus_hash" => %header.consensus_hash,
+                    "block_hash" => %header.block_hash(),
                     "reward_cycle" => sn_rc,
                     "error" => %e.to_string()
                 );
@@ -2838,6 +2881,262 @@ impl Relayer {
             .retain(|_blk_id, (ch, _ts)| relay_tenures.contains(ch));
     }
 
+    #[cfg_attr(test, mutants::skip)]
+    /// Rebuild the compact Nakamoto blocks pushed to us, using the transactions in our mempool and
+    /// the transactions pushed to us in this network result.
+    /// Rebuilt blocks are added to `network_result.pushed_nakamoto_blocks`, so they get validated
+    /// and stored like any other pushed block.
+    /// If transactions are missing, then ask the sender for them, and finish rebuilding the block
+    /// once they arrive.  If a rebuilt block does not match its header's transaction Merkle root
+    /// (e.g. due to a tag collision), or the sender's reply doesn't complete it, then ask the
+    /// sender for the full block instead.  The same goes for compact blocks that would exceed the
+    /// limits on how many blocks can await transactions.
+    /// Compact blocks whose headers fail validation (e.g. their signer signatures don't verify)
+    /// are dropped.
+    pub fn process_compact_nakamoto_blocks(
+        &mut self,
+        network_result: &mut NetworkResult,
+        burnchain: &Burnchain,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        mempool: &MemPoolDB,
+    ) {
+        // garbage-collect requests that were never answered
+        let now = get_epoch_time_ms();
+        self.pending_compact_nakamoto_blocks
+            .retain(|block_id, pending| {
+                let retain = pending.requested_at_ms + COMPACT_NAKAMOTO_BLOCK_TIMEOUT_MS >= now;
+                if !retain {
+                    debug!(
+                        "Timed out waiting for txs of compact Nakamoto block {} from {:?}",
+                        block_id, &pending.neighbor_key
+                    );
+                }
+                retain
+            });
+
+        let compact_blocks = mem::take(&mut network_result.pushed_compact_nakamoto_blocks);
+        let block_txs = mem::take(&mut network_result.pushed_nakamoto_block_txs);
+        let mut rebuilt_blocks = vec![];
+
+        let pushed_txs: Vec<_> = network_result
+            .pushed_transactions
+            .values()
+            .flatten()
+            .map(|(_, tx)| tx)
+            .collect();
+
+        for (neighbor_key, compact_blocks_data) in compact_blocks.into_iter() {
+            for (relayers, compact_block) in compact_blocks_data.into_iter() {
+                let block_id = compact_block.block_id();
+                if self.pending_compact_nakamoto_blocks.contains_key(&block_id) {
+                    debug!("Already rebuilding compact Nakamoto block {}", &block_id);
+                    continue;
+                }
+                let have_block = chainstate
+                    .nakamoto_blocks_db()
+                    .has_nakamoto_block_with_index_hash(&block_id)
+                    .unwrap_or_else(|e| {
+                        warn!(
+                            "Failed to determine if we have Nakamoto block";
+                            "stacks_block_id" => %block_id,
+                            "err" => ?e
+                        );
+                        false
+                    });
+                if have_block {
+                    debug!("Already have compact Nakamoto block {}", &block_id);
+                    continue;
+                }
+                if let Err(e) = Relayer::validate_nakamoto_block_headers_push(
+                    burnchain,
+                    sortdb,
+                    chainstate,
+                    &network_result.stacks_tip,
+                    iter::once(&compact_block.header),
+                ) {
+                    info!(
+                        "Failed to validate compact Nakamoto block {} pushed from {:?}: {:?}",
+                        &block_id, &neighbor_key, &e
+                    );
+                    continue;
+                }
+
+                let mut partial_block = PartialNakamotoBlock::from_compact(&compact_block);
+                partial_block.fill_from_candidates(pushed_txs.iter().copied());
+                if !partial_block.is_complete() {
+                    match mempool.find_txs_by_tags(
+                        partial_block.tx_tag_seed(),
+                        &partial_block.missing_tags(),
+                    ) {
+                        Ok(mempool_txs) => {
+                            partial_block.fill_from_candidates(mempool_txs.iter());
+                        }
+                        Err(e) => {
+                            warn!(
+                                "Failed to query mempool for compact Nakamoto block txs";
+                                "stacks_block_id" => %block_id,
+                                "err" => ?e
+                            );
+                        }
+                    }
+                }
+                rebuilt_blocks.extend(self.finish_compact_nakamoto_block(
+                    neighbor_key.clone(),
+                    relayers,
+                    partial_block,
+                    true,
+                ));
+            }
+        }
+
+        for (neighbor_key, block_txs_list) in block_txs.into_iter() {
+            for block_txs_data in block_txs_list.into_iter() {
+                let block_id = block_txs_data.block_id;
+                let Some(mut pending) = self.pending_compact_nakamoto_blocks.remove(&block_id)
+                else {
+                    debug!(
+                        "Unexpected NakamotoBlockTxs for {} from {:?}",
+                        &block_id, &neighbor_key
+                    );
+                    continue;
+                };
+                if pending.neighbor_key != neighbor_key {
+                    debug!(
+                        "Unexpected NakamotoBlockTxs for {} from {:?}; expected {:?}",
+                        &block_id, &neighbor_key, &pending.neighbor_key
+                    );
+                    self.pending_compact_nakamoto_blocks
+                        .insert(block_id, pending);
+                    continue;
+                }
+                if let Err(e) = pending
+                    .partial_block
+                    .fill_from_response(&pending.requested_indexes, block_txs_data.txs)
+                {
+                    info!(
+                        "Invalid NakamotoBlockTxs for {} from {:?}: {:?}",
+                        &block_id, &neighbor_key, &e
+                    );
+                }
+                rebuilt_blocks.extend(self.finish_compact_nakamoto_block(
+                    pending.neighbor_key,
+                    pending.relayers,
+                    pending.partial_block,
+                    false,
+                ));
+            }
+        }
+
+        for (neighbor_key, relayers, block) in rebuilt_blocks.into_iter() {
+            let blocks_data = NakamotoBlocksData {
+                blocks: vec![block],
+            };
+            if let Some(nakamoto_blocks_msgs) =
+                network_result.pushed_nakamoto_blocks.get_mut(&neighbor_key)
+            {
+                nakamoto_blocks_msgs.push((relayers, blocks_data));
+            } else {
+                network_result
+                    .pushed_nakamoto_blocks
+                    .insert(neighbor_key, vec![(relayers, blocks_data)]);
+            }
+        }
+    }
+
+    /// Can we wait on another compact Nakamoto block from this neighbor?
+    fn can_add_pending_compact_nakamoto_block(&self, neighbor_key: &NeighborKey) -> bool {
+        if self.pending_compact_nakamoto_blocks.len() >= MAX_PENDING_COMPACT_NAKAMOTO_BLOCKS {
+            debug!(
+                "Already waiting on {} compact Nakamoto blocks",
+                self.pending_compact_nakamoto_blocks.len()
+            );
+            return false;
+        }
+        let num_from_neighbor = self
+            .pending_compact_nakamoto_blocks
+            .values()
+            .filter(|pending| &pending.neighbor_key == neighbor_key)
+            .count();
+        if num_from_neighbor >= MAX_PENDING_COMPACT_NAKAMOTO_BLOCKS_PER_NEIGHBOR {
+            debug!(
+                "Already waiting on {} compact Nakamoto blocks from {:?}",
+                num_from_neighbor, neighbor_key
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Finish rebuilding a compact Nakamoto block.
+    /// If transactions are still missing and `can_request_txs` is true, then ask the sender for
+    /// them, unless we're already waiting on too many compact blocks.  Otherwise, if the block
+    /// can't be rebuilt, ask the sender for the full block.
+    /// Returns the sender, relay hints, and block if the block was rebuilt.
+    fn finish_compact_nakamoto_block(
+        &mut self,
+        neighbor_key: NeighborKey,
+        relayers: Vec<RelayData>,
+        partial_block: PartialNakamotoBlock,
+        can_request_txs: bool,
+    ) -> Option<(NeighborKey, Vec<RelayData>, NakamotoBlock)> {
+        let block_id = partial_block.block_id();
+        if partial_block.is_complete() {
+            match partial_block.try_into_block() {
+                Ok(block) => {
+                    debug!("Rebuilt compact Nakamoto block {}", &block_id);
+                    return Some((neighbor_key, relayers, block));
+                }
+                Err(_) => {
+                    info!(
+                        "Rebuilt compact Nakamoto block {} does not match its header",
+                        &block_id
+                    );
+                }
+            }
+        } else if can_request_txs && self.can_add_pending_compact_nakamoto_block(&neighbor_key) {
+            let indexes = partial_block.missing_indexes();
+            debug!(
+                "Request {} missing txs of compact Nakamoto block {} from {:?}",
+                indexes.len(),
+                &block_id,
+                &neighbor_key
+            );
+            let msg = StacksMessageType::GetNakamotoBlockTxs(GetNakamotoBlockTxsData {
+                block_id: block_id.clone(),
+                indexes: indexes.clone(),
+            });
+            if let Err(e) = self.p2p.send_message(neighbor_key.clone(), msg) {
+                warn!("Failed to request compact Nakamoto block txs: {:?}", &e);
+                return None;
+            }
+            self.pending_compact_nakamoto_blocks.insert(
+                block_id,
+                PendingCompactNakamotoBlock {
+                    neighbor_key,
+                    relayers,
+                    partial_block,
+                    requested_indexes: indexes,
+                    requested_at_ms: get_epoch_time_ms(),
+                },
+            );
+            return None;
+        }
+
+        debug!(
+            "Request full Nakamoto block {} from {:?}",
+            &block_id, &neighbor_key
+        );
+        let msg = StacksMessageType::GetNakamotoBlockTxs(GetNakamotoBlockTxsData {
+            block_id,
+            indexes: vec![],
+        });
+        if let Err(e) = self.p2p.send_message(neighbor_key, msg) {
+            warn!("Failed to request full Nakamoto block: {:?}", &e);
+        }
+        None
+    }
+
     #[cfg_attr(test, mutants::skip)]
     /// Process epoch3 data
     /// Relay new nakamoto blocks if not in ibd
@@ -2975,6 +3274,15 @@ impl Relayer {
                 coord_comms,
             );
 
+        // rebuild compact epoch3 blocks, so they can be processed with the rest of the epoch3 data
+        self.process_compact_nakamoto_blocks(
+            network_result,
+            burnchain,
+            sortdb,
+            chainstate,
+            mempool,
+        );
+
         // process epoch3 data
         let num_new_nakamoto_blocks = self.process_new_epoch3_blocks(
             local_peer,
@@ -0,0 +1,606 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Outbound connections through a SOCKS5 proxy (RFC 1928), such as Tor.
+//!
+//! The p2p network drives a `Socks5Handshake` over a non-blocking socket as it becomes ready, and
+//! only registers the peer once the proxy has connected it to its target.  The burnchain clients
+//! use the blocking `connect()` helper instead.  Targets are given as a `PeerHost`, so that
+//! `.onion` hostnames can be resolved by the proxy itself.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use std::{fmt, io};
+
+use serde::de::{Deserialize, Error as de_Error};
+use stacks_common::types::net::PeerHost;
+
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_AUTH_VERSION: u8 = 0x01;
+
+const SOCKS5_METHOD_NO_AUTH: u8 = 0x00;
+const SOCKS5_METHOD_USERNAME_PASSWORD: u8 = 0x02;
+
+const SOCKS5_CMD_CONNECT: u8 = 0x01;
+
+const SOCKS5_ATYP_IPV4: u8 = 0x01;
+const SOCKS5_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS5_ATYP_IPV6: u8 = 0x04;
+
+/// A SOCKS5 proxy endpoint, with optional username/password authentication (RFC 1929).
+/// Tor uses the credentials to isolate circuits from one another.
+#[derive(Clone, PartialEq)]
+pub struct Socks5Proxy {
+    pub addr: SocketAddr,
+    pub credentials: Option<(String, String)>,
+}
+
+impl fmt::Debug for Socks5Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // don't log the password
+        match self.credentials {
+            Some((ref username, _)) => write!(f, "socks5://{}@{}", username, &self.addr),
+            None => write!(f, "socks5://{}", &self.addr),
+        }
+    }
+}
+
+impl FromStr for Socks5Proxy {
+    type Err = String;
+
+    /// Parse a proxy from `[username:password@]host:port`.  The host is resolved here.
+    fn from_str(s: &str) -> Result<Socks5Proxy, String> {
+        let (credentials, hostport) = match s.rsplit_once('@') {
+            Some((userpass, hostport)) => {
+                let Some((username, password)) = userpass.split_once(':') else {
+                    return Err("SOCKS5 proxy credentials must be `username:password`".into());
+                };
+                if username.len() > 255 || password.len() > 255 {
+                    return Err(
+                        "SOCKS5 proxy username and password are limited to 255 bytes".into(),
+                    );
+                }
+                (Some((username.to_string(), password.to_string())), hostport)
+            }
+            None => (None, s),
+        };
+        let addr = hostport
+            .to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve SOCKS5 proxy `{hostport}`: {e:?}"))?
+            .next()
+            .ok_or_else(|| format!("SOCKS5 proxy `{hostport}` has no addresses"))?;
+        Ok(Socks5Proxy { addr, credentials })
+    }
+}
+
+impl<'de> Deserialize<'de> for Socks5Proxy {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Socks5Proxy, D::Error> {
+        let inst = String::deserialize(d)?;
+        Socks5Proxy::from_str(&inst).map_err(de_Error::custom)
+    }
+}
+
+/// Where we are in the SOCKS5 handshake
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Socks5State {
+    /// Sent our authentication methods; waiting for the proxy to pick one
+    Greeting,
+    /// Sent our username and password; waiting for the proxy to accept them
+    Auth,
+    /// Sent the CONNECT request; waiting for the head of the reply
+    Connect,
+    /// Waiting for the rest of the reply, which carries the proxy's bound address
+    ConnectAddress,
+    /// The proxy has connected us to the target
+    Done,
+}
+
+/// A client-side SOCKS5 CONNECT handshake.  It can be driven over a non-blocking socket by
+/// calling `step()` whenever the socket is ready, until it returns true.  Once it does, the
+/// socket is connected to the target and carries its traffic.
+#[derive(Debug)]
+pub struct Socks5Handshake {
+    proxy: Socks5Proxy,
+    target: PeerHost,
+    state: Socks5State,
+    /// bytes of the current request that have not been written yet
+    sendbuf: Vec<u8>,
+    /// bytes of the current reply read so far
+    recvbuf: Vec<u8>,
+    /// how many bytes of the current reply we need before we can act on it
+    recv_len: usize,
+}
+
+impl Socks5Handshake {
+    pub fn new(proxy: &Socks5Proxy, target: PeerHost) -> Socks5Handshake {
+        let sendbuf = if proxy.credentials.is_some() {
+            vec![
+                SOCKS5_VERSION,
+                2,
+                SOCKS5_METHOD_NO_AUTH,
+                SOCKS5_METHOD_USERNAME_PASSWORD,
+            ]
+        } else {
+            vec![SOCKS5_VERSION, 1, SOCKS5_METHOD_NO_AUTH]
+        };
+        Socks5Handshake {
+            proxy: proxy.clone(),
+            target,
+            state: Socks5State::Greeting,
+            sendbuf,
+            recvbuf: vec![],
+            recv_len: 2,
+        }
+    }
+
+    pub fn proxy(&self) -> &Socks5Proxy {
+        &self.proxy
+    }
+
+    pub fn target(&self) -> &PeerHost {
+        &self.target
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state == Socks5State::Done
+    }
+
+    /// Make the CONNECT request for our target
+    fn connect_request(&self) -> io::Result<Vec<u8>> {
+        let mut request = vec![SOCKS5_VERSION, SOCKS5_CMD_CONNECT, 0x00];
+        match self.target {
+            PeerHost::IP(ref addrbytes, _) => {
+                if let Some(octets) = addrbytes.ipv4_octets() {
+                    request.push(SOCKS5_ATYP_IPV4);
+                    request.extend_from_slice(&octets);
+                } else {
+                    request.push(SOCKS5_ATYP_IPV6);
+                    request.extend_from_slice(addrbytes.as_bytes());
+                }
+            }
+            PeerHost::DNS(ref hostname, _) => {
+                let Ok(len) = u8::try_from(hostname.len()) else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Hostname is too long for SOCKS5: {hostname}"),
+                    ));
+                };
+                request.push(SOCKS5_ATYP_DOMAIN);
+                request.push(len);
+                request.extend_from_slice(hostname.as_bytes());
+            }
+        }
+        request.extend_from_slice(&self.target.port().to_be_bytes());
+        Ok(request)
+    }
+
+    /// Move on to the next request
+    fn next_request(&mut self, state: Socks5State, request: Vec<u8>, recv_len: usize) {
+        self.state = state;
+        self.sendbuf = request;
+        self.recvbuf.clear();
+        self.recv_len = recv_len;
+    }
+
+    /// Act on a complete reply from the proxy
+    fn advance(&mut self) -> io::Result<()> {
+        if self.recvbuf[0] != SOCKS5_VERSION && self.state != Socks5State::Auth {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Proxy replied with SOCKS version {}", self.recvbuf[0]),
+            ));
+        }
+        match self.state {
+            Socks5State::Greeting => match (self.recvbuf[1], self.proxy.credentials.as_ref()) {
+                (SOCKS5_METHOD_NO_AUTH, _) => {
+                    let request = self.connect_request()?;
+                    self.next_request(Socks5State::Connect, request, 5);
+                }
+                (SOCKS5_METHOD_USERNAME_PASSWORD, Some((username, password))) => {
+                    let mut request = vec![SOCKS5_AUTH_VERSION, username.len() as u8];
+                    request.extend_from_slice(username.as_bytes());
+                    request.push(password.len() as u8);
+                    request.extend_from_slice(password.as_bytes());
+                    self.next_request(Socks5State::Auth, request, 2);
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        "Proxy did not accept any of our authentication methods",
+                    ));
+                }
+            },
+            Socks5State::Auth => {
+                if self.recvbuf[1] != 0x00 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        "Proxy rejected our username and password",
+                    ));
+                }
+                let request = self.connect_request()?;
+                self.next_request(Socks5State::Connect, request, 5);
+            }
+            Socks5State::Connect => {
+                if self.recvbuf[1] != 0x00 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionRefused,
+                        format!(
+                            "Proxy failed to connect to {}: {}",
+                            &self.target,
+                            reply_description(self.recvbuf[1])
+                        ),
+                    ));
+                }
+                // the head of the reply includes the first byte of the bound address
+                let remaining = match self.recvbuf[3] {
+                    SOCKS5_ATYP_IPV4 => 4 - 1,
+                    SOCKS5_ATYP_IPV6 => 16 - 1,
+                    SOCKS5_ATYP_DOMAIN => usize::from(self.recvbuf[4]),
+                    atyp => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("Proxy replied with unknown address type {atyp}"),
+                        ));
+                    }
+                };
+                self.state = Socks5State::ConnectAddress;
+                self.recv_len += remaining + 2;
+            }
+            Socks5State::ConnectAddress => {
+                self.state = Socks5State::Done;
+                self.recvbuf.clear();
+                self.recv_len = 0;
+            }
+            Socks5State::Done => {}
+        }
+        Ok(())
+    }
+
+    /// Make as much progress on the handshake as the socket allows.
+    /// Returns Ok(true) once the proxy has connected us to the target, and Ok(false) if we need to
+    /// wait for the socket to become ready again.
+    pub fn step<S: Read + Write>(&mut self, sock: &mut S) -> io::Result<bool> {
+        loop {
+            if self.state == Socks5State::Done {
+                return Ok(true);
+            }
+
+            while !self.sendbuf.is_empty() {
+                match sock.write(&self.sendbuf) {
+                    Ok(0) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::WriteZero,
+                            "Proxy connection closed",
+                        ));
+                    }
+                    Ok(nw) => {
+                        self.sendbuf.drain(0..nw);
+                    }
+                    Err(e) => match e.kind() {
+                        // a non-blocking connect(2) may not have finished yet
+                        io::ErrorKind::WouldBlock | io::ErrorKind::NotConnected => {
+                            return Ok(false);
+                        }
+                        io::ErrorKind::Interrupted => {}
+                        _ => {
+                            return Err(e);
+                        }
+                    },
+                }
+            }
+
+            // only read as much as this reply needs, so we never consume the target's bytes
+            while self.recvbuf.len() < self.recv_len {
+                let mut buf = vec![0u8; self.recv_len - self.recvbuf.len()];
+                match sock.read(&mut buf) {
+                    Ok(0) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "Proxy connection closed",
+                        ));
+                    }
+                    Ok(nr) => {
+                        self.recvbuf.extend_from_slice(&buf[0..nr]);
+                    }
+                    Err(e) => match e.kind() {
+                        io::ErrorKind::WouldBlock | io::ErrorKind::NotConnected => {
+                            return Ok(false);
+                        }
+                        io::ErrorKind::Interrupted => {}
+                        _ => {
+                            return Err(e);
+                        }
+                    },
+                }
+            }
+
+            self.advance()?;
+        }
+    }
+}
+
+/// Describe a SOCKS5 reply code
+fn reply_description(rep: u8) -> &'static str {
+    match rep {
+        0x01 => "general SOCKS server failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+/// Open a blocking connection to `target` through `proxy`.  `timeout` bounds the connection to
+/// the proxy, as well as the whole SOCKS5 handshake.  On success, the returned stream's read and
+/// write timeouts are set to `timeout`.
+pub fn connect(proxy: &Socks5Proxy, target: &PeerHost, timeout: Duration) -> io::Result<TcpStream> {
+    let deadline = Instant::now() + timeout;
+    let mut sock = TcpStream::connect_timeout(&proxy.addr, timeout)?;
+    sock.set_read_timeout(Some(timeout))?;
+    sock.set_write_timeout(Some(timeout))?;
+
+    let mut handshake = Socks5Handshake::new(proxy, target.clone());
+    while !handshake.step(&mut sock)? {
+        if Instant::now() > deadline {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("Timed out connecting to {} via {:?}", target, proxy),
+            ));
+        }
+    }
+    Ok(sock)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use std::net::TcpListener;
+    use std::thread;
+
+    use stacks_common::types::net::PeerAddress;
+
+    use super::*;
+
+    /// A socket whose reads come from a script, one chunk at a time, and which would block
+    /// between chunks
+    struct ScriptedSocket {
+        reads: VecDeque<Vec<u8>>,
+        written: Vec<u8>,
+        blocked: bool,
+    }
+
+    impl ScriptedSocket {
+        fn new(reads: Vec<Vec<u8>>) -> Self {
+            Self {
+                reads: reads.into(),
+                written: vec![],
+                blocked: false,
+            }
+        }
+    }
+
+    impl Read for ScriptedSocket {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.blocked {
+                self.blocked = false;
+                return Err(io::Error::from(io::ErrorKind::WouldBlock));
+            }
+            let Some(mut chunk) = self.reads.pop_front() else {
+                return Err(io::Error::from(io::ErrorKind::WouldBlock));
+            };
+            let nr = buf.len().min(chunk.len());
+            buf[0..nr].copy_from_slice(&chunk[0..nr]);
+            if nr < chunk.len() {
+                self.reads.push_front(chunk.split_off(nr));
+            } else {
+                self.blocked = true;
+            }
+            Ok(nr)
+        }
+    }
+
+    impl Write for ScriptedSocket {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_socks5_proxy_parse() {
+        let proxy = Socks5Proxy::from_str("127.0.0.1:9050").unwrap();
+        assert_eq!(proxy.addr, "127.0.0.1:9050".parse().unwrap());
+        assert_eq!(proxy.credentials, None);
+
+        let proxy = Socks5Proxy::from_str("alice:hunter2@127.0.0.1:9050").unwrap();
+        assert_eq!(proxy.addr, "127.0.0.1:9050".parse().unwrap());
+        assert_eq!(
+            proxy.credentials,
+            Some(("alice".to_string(), "hunter2".to_string()))
+        );
+        assert_eq!(format!("{:?}", &proxy), "socks5://alice@127.0.0.1:9050");
+
+        assert!(Socks5Proxy::from_str("alice@127.0.0.1:9050").is_err());
+        assert!(Socks5Proxy::from_str("127.0.0.1").is_err());
+    }
+
+    #[test]
+    fn test_socks5_handshake_no_auth() {
+        let proxy = Socks5Proxy::from_str("127.0.0.1:9050").unwrap();
+        let target = PeerHost::IP(PeerAddress::from_ipv4(1, 2, 3, 4), 20444);
+        let mut handshake = Socks5Handshake::new(&proxy, target);
+
+        // the proxy trickles its replies in one byte at a time
+        let mut reads = vec![vec![0x05], vec![0x00]];
+        for byte in [0x05, 0x00, 0x00, 0x01, 127, 0, 0, 1, 0x01, 0x02] {
+            reads.push(vec![byte]);
+        }
+        let mut sock = ScriptedSocket::new(reads);
+
+        let mut steps = 0;
+        while !handshake.step(&mut sock).unwrap() {
+            steps += 1;
+            assert!(steps < 100);
+        }
+        assert!(handshake.is_done());
+        assert_eq!(
+            sock.written,
+            vec![
+                0x05, 0x01, 0x00, // greeting
+                0x05, 0x01, 0x00, 0x01, 1, 2, 3, 4, 0x4f, 0xdc // connect
+            ]
+        );
+        assert!(sock.reads.is_empty());
+    }
+
+    #[test]
+    fn test_socks5_handshake_auth_and_domain() {
+        let proxy = Socks5Proxy::from_str("alice:hunter2@127.0.0.1:9050").unwrap();
+        let onion = "duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczad.onion";
+        let target = PeerHost::DNS(onion.to_string(), 20444);
+        let mut handshake = Socks5Handshake::new(&proxy, target);
+
+        // the proxy replies with a domain-name bound address, and the target's first bytes follow
+        // the reply
+        let mut connect_reply = vec![0x05, 0x00, 0x00, 0x03, 0x04];
+        connect_reply.extend_from_slice(b"tor!");
+        connect_reply.extend_from_slice(&[0x00, 0x00]);
+        connect_reply.extend_from_slice(b"peer data");
+        let mut sock = ScriptedSocket::new(vec![vec![0x05, 0x02], vec![0x01, 0x00], connect_reply]);
+
+        let mut steps = 0;
+        while !handshake.step(&mut sock).unwrap() {
+            steps += 1;
+            assert!(steps < 100);
+        }
+
+        let mut expected = vec![0x05, 0x02, 0x00, 0x02];
+        expected.extend_from_slice(&[0x01, 5]);
+        expected.extend_from_slice(b"alice");
+        expected.push(7);
+        expected.extend_from_slice(b"hunter2");
+        expected.extend_from_slice(&[0x05, 0x01, 0x00, 0x03, onion.len() as u8]);
+        expected.extend_from_slice(onion.as_bytes());
+        expected.extend_from_slice(&[0x4f, 0xdc]);
+        assert_eq!(sock.written, expected);
+
+        // the target's bytes were left on the socket
+        assert_eq!(sock.reads.pop_front().unwrap(), b"peer data".to_vec());
+    }
+
+    /// Step `handshake` until it fails, as the socket becomes ready again
+    fn handshake_error(handshake: &mut Socks5Handshake, sock: &mut ScriptedSocket) -> io::Error {
+        for _ in 0..100 {
+            match handshake.step(sock) {
+                Ok(false) => {}
+                Ok(true) => panic!("handshake succeeded"),
+                Err(e) => return e,
+            }
+        }
+        panic!("handshake did not finish");
+    }
+
+    #[test]
+    fn test_socks5_handshake_failures() {
+        let target = PeerHost::IP(PeerAddress::from_ipv4(1, 2, 3, 4), 20444);
+
+        // proxy wants credentials we don't have
+        let proxy = Socks5Proxy::from_str("127.0.0.1:9050").unwrap();
+        let mut handshake = Socks5Handshake::new(&proxy, target.clone());
+        let mut sock = ScriptedSocket::new(vec![vec![0x05, 0xff]]);
+        assert_eq!(
+            handshake_error(&mut handshake, &mut sock).kind(),
+            io::ErrorKind::PermissionDenied
+        );
+
+        // proxy rejects our credentials
+        let proxy = Socks5Proxy::from_str("alice:hunter2@127.0.0.1:9050").unwrap();
+        let mut handshake = Socks5Handshake::new(&proxy, target.clone());
+        let mut sock = ScriptedSocket::new(vec![vec![0x05, 0x02, 0x01, 0x01]]);
+        assert_eq!(
+            handshake_error(&mut handshake, &mut sock).kind(),
+            io::ErrorKind::PermissionDenied
+        );
+
+        // proxy can't reach the target
+        let proxy = Socks5Proxy::from_str("127.0.0.1:9050").unwrap();
+        let mut handshake = Socks5Handshake::new(&proxy, target.clone());
+        let mut sock = ScriptedSocket::new(vec![
+            vec![0x05, 0x00],
+            vec![0x05, 0x04, 0x00, 0x01, 0, 0, 0, 0, 0, 0],
+        ]);
+        assert_eq!(
+            handshake_error(&mut handshake, &mut sock).kind(),
+            io::ErrorKind::ConnectionRefused
+        );
+
+        // not a SOCKS5 proxy
+        let mut handshake = Socks5Handshake::new(&proxy, target);
+        let mut sock = ScriptedSocket::new(vec![b"HTTP/1.1 400 Bad Request\r\n".to_vec()]);
+        assert_eq!(
+            handshake_error(&mut handshake, &mut sock).kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_socks5_connect_blocking() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_addr = listener.local_addr().unwrap();
+
+        // a minimal SOCKS5 proxy which echoes a line back instead of connecting anywhere
+        let server = thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut greeting = [0u8; 3];
+            sock.read_exact(&mut greeting).unwrap();
+            assert_eq!(greeting, [0x05, 0x01, 0x00]);
+            sock.write_all(&[0x05, 0x00]).unwrap();
+
+            let mut request = [0u8; 10];
+            sock.read_exact(&mut request).unwrap();
+            assert_eq!(request, [0x05, 0x01, 0x00, 0x01, 10, 0, 0, 1, 0x4f, 0xdc]);
+            sock.write_all(&[0x05, 0x00, 0x00, 0x01, 127, 0, 0, 1, 0x12, 0x34])
+                .unwrap();
+
+            let mut line = [0u8; 5];
+            sock.read_exact(&mut line).unwrap();
+            sock.write_all(&line).unwrap();
+        });
+
+        let proxy = Socks5Proxy {
+            addr: proxy_addr,
+            credentials: None,
+        };
+        let target = PeerHost::IP(PeerAddress::from_ipv4(10, 0, 0, 1), 20444);
+        let mut sock = connect(&proxy, &target, Duration::from_secs(5)).unwrap();
+
+        sock.write_all(b"hello").unwrap();
+        let mut echoed = [0u8; 5];
+        sock.read_exact(&mut echoed).unwrap();
+        assert_eq!(&echoed, b"hello");
+
+        server.join().unwrap();
+    }
+}
@@ -391,7 +391,7 @@ pub enum StackerDBSyncState {
 /// Set of peers for a stacker DB
 pub struct StackerDBSync<NC: NeighborComms> {
     /// what state are we in?
-    state: StackerDBSyncState,
+    pub(crate) state: StackerDBSyncState,
     /// What was the rc consensus hash at the start of sync?
     pub rc_consensus_hash: Option<ConsensusHash>,
     /// which contract this is a replica for
@@ -433,7 +433,7 @@ pub struct StackerDBSync<NC: NeighborComms> {
     /// total chunks pushed
     pub total_pushed: u64,
     /// last time the state-transition function ran to completion
-    last_run_ts: u64,
+    pub(crate) last_run_ts: u64,
     /// whether or not we should immediately re-fetch chunks because we learned about new chunks
     /// from our peers when they replied to our chunk-pushes with new inventory state
     need_resync: bool,
@@ -446,7 +446,7 @@ pub struct StackerDBSync<NC: NeighborComms> {
     /// How many connections have been made in the last pass (gets reset)
     num_connections: u64,
     /// Number of state machine passes
-    rounds: u128,
+    pub(crate) rounds: u128,
     /// Round when we last pushed
     push_round: u128,
     /// time we last deliberately evicted a peer
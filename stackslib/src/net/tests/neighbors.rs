@@ -14,6 +14,12 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
 use clarity::vm::types::{StacksAddressExtensions, StandardPrincipalData};
 use rand::prelude::*;
 use rand::thread_rng;
@@ -28,6 +34,7 @@ use crate::net::asn::*;
 use crate::net::chat::*;
 use crate::net::db::*;
 use crate::net::neighbors::*;
+use crate::net::socks::Socks5Proxy;
 use crate::net::test::*;
 use crate::net::{Error as net_error, *};
 use crate::util_lib::test::*;
@@ -238,6 +245,353 @@ fn test_step_walk_1_neighbor_plain_no_natpunch() {
     })
 }
 
+/// Does this peer have a conversation whose transport is encrypted?
+fn has_encrypted_convo(peer: &TestPeer, outbound: bool) -> bool {
+    peer.network.iter_peer_convos().any(|(_, convo)| {
+        convo.is_outbound() == outbound && convo.connection.is_transport_encrypted()
+    })
+}
+
+#[test]
+fn test_step_walk_1_neighbor_encrypted_transport() {
+    with_timeout(600, || {
+        let mut peer_1_config = TestPeerConfig::new(function_name!(), 0, 0);
+        let mut peer_2_config = TestPeerConfig::new(function_name!(), 0, 0);
+
+        peer_1_config.services |= ServiceFlags::ENCRYPTED_TRANSPORT as u16;
+        peer_2_config.services |= ServiceFlags::ENCRYPTED_TRANSPORT as u16;
+
+        let mut peer_1 = TestPeer::new(peer_1_config);
+        let mut peer_2 = TestPeer::new(peer_2_config);
+
+        peer_1.add_neighbor(&mut peer_2.to_neighbor(), None, true);
+
+        let mut i = 0;
+        let mut walk_1_count = 0;
+        let mut walk_2_count = 0;
+        let mut peer_1_encrypted = false;
+        let mut peer_2_encrypted = false;
+
+        while (walk_1_count < 20 || walk_2_count < 20)
+            || !peer_1_encrypted
+            || !peer_2_encrypted
+            || peer_1
+                .network
+                .get_neighbor_stats(&peer_2.to_neighbor().addr)
+                .is_none()
+        {
+            let _ = peer_1.step();
+            let _ = peer_2.step();
+
+            walk_1_count = peer_1.network.walk_total_step_count;
+            walk_2_count = peer_2.network.walk_total_step_count;
+
+            // peer 1 connects to peer 2, so peer 1 starts the upgrade
+            peer_1_encrypted |= has_encrypted_convo(&peer_1, true);
+            peer_2_encrypted |= has_encrypted_convo(&peer_2, false);
+
+            test_debug!(
+                "peer 1 took {} walk steps; peer 2 took {} walk steps",
+                walk_1_count,
+                walk_2_count
+            );
+
+            if let Some(ref w) = peer_1.network.walk {
+                assert!(w.result.broken_connections.is_empty());
+                assert!(w.result.replaced_neighbors.is_empty());
+            };
+
+            if let Some(ref w) = peer_2.network.walk {
+                assert!(w.result.broken_connections.is_empty());
+                assert!(w.result.replaced_neighbors.is_empty());
+            };
+
+            i += 1;
+        }
+
+        debug!("Completed walk round {} step(s)", i);
+
+        // peer 1 kept talking to peer 2 over the encrypted transport
+        let stats_1 = peer_1
+            .network
+            .get_neighbor_stats(&peer_2.to_neighbor().addr)
+            .unwrap();
+        assert!(stats_1.last_contact_time > 0);
+        assert!(stats_1.last_handshake_time > 0);
+        assert!(stats_1.bytes_rx > 0);
+        assert!(stats_1.bytes_tx > 0);
+
+        // peer 2 is in peer 1's frontier DB
+        let neighbor_2 = peer_2.to_neighbor();
+        let p = PeerDB::get_peer(
+            peer_1.get_peerdb_conn(),
+            neighbor_2.addr.network_id,
+            &neighbor_2.addr.addrbytes,
+            neighbor_2.addr.port,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(p.public_key, neighbor_2.public_key);
+    })
+}
+
+#[test]
+fn test_step_walk_1_neighbor_encrypted_transport_unsupported() {
+    with_timeout(600, || {
+        // only peer 1 wants encrypted transport, so the peers talk in plaintext
+        let mut peer_1_config = TestPeerConfig::new(function_name!(), 0, 0);
+        let peer_2_config = TestPeerConfig::new(function_name!(), 0, 0);
+
+        peer_1_config.services |= ServiceFlags::ENCRYPTED_TRANSPORT as u16;
+
+        let mut peer_1 = TestPeer::new(peer_1_config);
+        let mut peer_2 = TestPeer::new(peer_2_config);
+
+        peer_1.add_neighbor(&mut peer_2.to_neighbor(), None, true);
+
+        let mut walk_1_count = 0;
+        let mut walk_2_count = 0;
+
+        while (walk_1_count < 20 || walk_2_count < 20)
+            || peer_1
+                .network
+                .get_neighbor_stats(&peer_2.to_neighbor().addr)
+                .is_none()
+        {
+            let _ = peer_1.step();
+            let _ = peer_2.step();
+
+            walk_1_count = peer_1.network.walk_total_step_count;
+            walk_2_count = peer_2.network.walk_total_step_count;
+
+            assert!(!has_encrypted_convo(&peer_1, true));
+            assert!(!has_encrypted_convo(&peer_2, false));
+
+            if let Some(ref w) = peer_1.network.walk {
+                assert!(w.result.broken_connections.is_empty());
+                assert!(w.result.replaced_neighbors.is_empty());
+            };
+
+            if let Some(ref w) = peer_2.network.walk {
+                assert!(w.result.broken_connections.is_empty());
+                assert!(w.result.replaced_neighbors.is_empty());
+            };
+        }
+
+        let stats_1 = peer_1
+            .network
+            .get_neighbor_stats(&peer_2.to_neighbor().addr)
+            .unwrap();
+        assert!(stats_1.last_handshake_time > 0);
+        assert!(stats_1.bytes_rx > 0);
+        assert!(stats_1.bytes_tx > 0);
+    })
+}
+
+/// Run a minimal no-auth SOCKS5 proxy on localhost that relays each CONNECT to its target.
+/// Returns the proxy's address, a counter of the connections it has relayed, and the domain names
+/// it has resolved.
+fn spawn_socks5_relay() -> (SocketAddr, Arc<AtomicUsize>, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy_addr = listener.local_addr().unwrap();
+    let relayed = Arc::new(AtomicUsize::new(0));
+    let relayed_counter = relayed.clone();
+    let domains = Arc::new(Mutex::new(vec![]));
+    let resolved_domains = domains.clone();
+
+    thread::spawn(move || {
+        for client in listener.incoming() {
+            let Ok(mut client) = client else {
+                continue;
+            };
+            let relayed_counter = relayed_counter.clone();
+            let resolved_domains = resolved_domains.clone();
+            thread::spawn(move || {
+                let mut greeting = [0u8; 2];
+                client.read_exact(&mut greeting).unwrap();
+                let mut methods = vec![0u8; greeting[1] as usize];
+                client.read_exact(&mut methods).unwrap();
+                client.write_all(&[0x05, 0x00]).unwrap();
+
+                let mut request = [0u8; 4];
+                client.read_exact(&mut request).unwrap();
+                let host = match request[3] {
+                    0x01 => {
+                        let mut octets = [0u8; 4];
+                        client.read_exact(&mut octets).unwrap();
+                        IpAddr::from(octets).to_string()
+                    }
+                    0x03 => {
+                        let mut len = [0u8; 1];
+                        client.read_exact(&mut len).unwrap();
+                        let mut domain = vec![0u8; len[0] as usize];
+                        client.read_exact(&mut domain).unwrap();
+                        let domain = String::from_utf8(domain).unwrap();
+                        resolved_domains.lock().unwrap().push(domain.clone());
+                        domain
+                    }
+                    0x04 => {
+                        let mut octets = [0u8; 16];
+                        client.read_exact(&mut octets).unwrap();
+                        IpAddr::from(octets).to_string()
+                    }
+                    atyp => panic!("Unsupported address type {}", atyp),
+                };
+                let mut port = [0u8; 2];
+                client.read_exact(&mut port).unwrap();
+
+                let server = (host.as_str(), u16::from_be_bytes(port))
+                    .to_socket_addrs()
+                    .unwrap()
+                    .find_map(|addr| TcpStream::connect(addr).ok())
+                    .unwrap();
+                client
+                    .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                    .unwrap();
+                relayed_counter.fetch_add(1, Ordering::SeqCst);
+
+                let mut client_rx = client.try_clone().unwrap();
+                let mut server_tx = server.try_clone().unwrap();
+                let mut server_rx = server;
+                thread::spawn(move || {
+                    let _ = io::copy(&mut client_rx, &mut server_tx);
+                    let _ = server_tx.shutdown(Shutdown::Both);
+                });
+                let _ = io::copy(&mut server_rx, &mut client);
+                let _ = client.shutdown(Shutdown::Both);
+            });
+        }
+    });
+
+    (proxy_addr, relayed, domains)
+}
+
+#[test]
+fn test_step_walk_1_neighbor_socks5_proxy() {
+    with_timeout(600, || {
+        let (proxy_addr, relayed, _) = spawn_socks5_relay();
+
+        let mut peer_1_config = TestPeerConfig::new(function_name!(), 0, 0);
+        let peer_2_config = TestPeerConfig::new(function_name!(), 0, 0);
+
+        // peer 1 makes all of its outbound connections through the proxy
+        peer_1_config.connection_opts.socks5_proxy = Some(Socks5Proxy {
+            addr: proxy_addr,
+            credentials: None,
+        });
+
+        let mut peer_1 = TestPeer::new(peer_1_config);
+        let mut peer_2 = TestPeer::new(peer_2_config);
+
+        peer_1.add_neighbor(&mut peer_2.to_neighbor(), None, true);
+
+        let mut walk_1_count = 0;
+        let mut walk_2_count = 0;
+
+        while (walk_1_count < 20 || walk_2_count < 20)
+            || peer_1
+                .network
+                .get_neighbor_stats(&peer_2.to_neighbor().addr)
+                .is_none()
+        {
+            let _ = peer_1.step();
+            let _ = peer_2.step();
+
+            walk_1_count = peer_1.network.walk_total_step_count;
+            walk_2_count = peer_2.network.walk_total_step_count;
+
+            if let Some(ref w) = peer_1.network.walk {
+                assert!(w.result.broken_connections.is_empty());
+                assert!(w.result.replaced_neighbors.is_empty());
+            };
+
+            if let Some(ref w) = peer_2.network.walk {
+                assert!(w.result.broken_connections.is_empty());
+                assert!(w.result.replaced_neighbors.is_empty());
+            };
+        }
+
+        // peer 1 reached peer 2 through the proxy
+        assert!(relayed.load(Ordering::SeqCst) > 0);
+
+        let stats_1 = peer_1
+            .network
+            .get_neighbor_stats(&peer_2.to_neighbor().addr)
+            .unwrap();
+        assert!(stats_1.last_handshake_time > 0);
+        assert!(stats_1.bytes_rx > 0);
+        assert!(stats_1.bytes_tx > 0);
+
+        // peer 2 is in peer 1's frontier DB under its own address, not the proxy's
+        let neighbor_2 = peer_2.to_neighbor();
+        let p = PeerDB::get_peer(
+            peer_1.get_peerdb_conn(),
+            neighbor_2.addr.network_id,
+            &neighbor_2.addr.addrbytes,
+            neighbor_2.addr.port,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(p.public_key, neighbor_2.public_key);
+    })
+}
+
+#[test]
+fn test_step_walk_1_neighbor_socks5_proxy_hostname() {
+    with_timeout(600, || {
+        let (proxy_addr, relayed, domains) = spawn_socks5_relay();
+
+        let mut peer_1_config = TestPeerConfig::new(function_name!(), 0, 0);
+        let peer_2_config = TestPeerConfig::new(function_name!(), 0, 0);
+
+        // peer 1 knows peer 2 only by a hostname, which the proxy resolves
+        let hostname_addr = PeerAddress::from_proxied_hostname("localhost");
+        peer_1_config.connection_opts.socks5_proxy = Some(Socks5Proxy {
+            addr: proxy_addr,
+            credentials: None,
+        });
+        peer_1_config
+            .connection_opts
+            .socks5_hostnames
+            .insert(hostname_addr, "localhost".to_string());
+
+        let mut peer_1 = TestPeer::new(peer_1_config);
+        let mut peer_2 = TestPeer::new(peer_2_config);
+
+        let mut neighbor_2 = peer_2.to_neighbor();
+        neighbor_2.addr.addrbytes = hostname_addr;
+        peer_1.add_neighbor(&mut neighbor_2, None, true);
+
+        let mut walk_1_count = 0;
+        let mut walk_2_count = 0;
+
+        while (walk_1_count < 20 || walk_2_count < 20)
+            || peer_1
+                .network
+                .get_neighbor_stats(&neighbor_2.addr)
+                .is_none()
+        {
+            let _ = peer_1.step();
+            let _ = peer_2.step();
+
+            walk_1_count = peer_1.network.walk_total_step_count;
+            walk_2_count = peer_2.network.walk_total_step_count;
+        }
+
+        // peer 1 reached peer 2 through the proxy, which resolved peer 2's hostname
+        assert!(relayed.load(Ordering::SeqCst) > 0);
+        assert!(domains
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|domain| domain == "localhost"));
+        assert!(!domains.lock().unwrap().is_empty());
+
+        let stats_1 = peer_1.network.get_neighbor_stats(&neighbor_2.addr).unwrap();
+        assert!(stats_1.last_handshake_time > 0);
+    })
+}
+
 #[test]
 fn test_step_walk_1_neighbor_denied() {
     with_timeout(600, || {
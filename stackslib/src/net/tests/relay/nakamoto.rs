@@ -53,6 +53,7 @@ use crate::chainstate::stacks::tests::{
 };
 use crate::chainstate::stacks::{Error as ChainstateError, *};
 use crate::clarity_vm::clarity::ClarityConnection;
+use crate::core::mempool::MemPoolDB;
 use crate::core::*;
 use crate::net::asn::*;
 use crate::net::chat::*;
@@ -61,6 +62,7 @@ use crate::net::download::*;
 use crate::net::http::{HttpRequestContents, HttpRequestPreamble};
 use crate::net::httpcore::StacksHttpMessage;
 use crate::net::inv::inv2x::*;
+use crate::net::p2p::{NetworkHandle, NetworkRequest};
 use crate::net::relay::{AcceptedNakamotoBlocks, ProcessedNetReceipts, Relayer};
 use crate::net::test::*;
 use crate::net::tests::download::epoch2x::run_get_blocks_and_microblocks;
@@ -621,7 +623,7 @@ fn test_no_buffer_ready_nakamoto_blocks() {
                         assert!(!follower.network.is_nakamoto_block_bufferable(
                             &sortdb,
                             &node.chainstate,
-                            block
+                            &block.header
                         ));
 
                         // suppose these blocks were invalid -- they would not be bufferable.
@@ -640,7 +642,7 @@ fn test_no_buffer_ready_nakamoto_blocks() {
                         assert_eq!(
                             follower
                                 .network
-                                .find_nakamoto_block_reward_cycle(&sortdb, &bad_block),
+                                .find_nakamoto_block_reward_cycle(&sortdb, &bad_block.header),
                             (
                                 Some(
                                     follower
@@ -655,7 +657,7 @@ fn test_no_buffer_ready_nakamoto_blocks() {
                         assert!(!follower.network.is_nakamoto_block_bufferable(
                             &sortdb,
                             &node.chainstate,
-                            &bad_block
+                            &bad_block.header
                         ));
 
                         // unrecognized consensus hash
@@ -664,7 +666,7 @@ fn test_no_buffer_ready_nakamoto_blocks() {
                         assert_eq!(
                             follower
                                 .network
-                                .find_nakamoto_block_reward_cycle(&sortdb, &bad_block),
+                                .find_nakamoto_block_reward_cycle(&sortdb, &bad_block.header),
                             (
                                 Some(
                                     follower
@@ -692,7 +694,7 @@ fn test_no_buffer_ready_nakamoto_blocks() {
                         assert_eq!(
                             follower
                                 .network
-                                .find_nakamoto_block_reward_cycle(&sortdb, &bad_block),
+                                .find_nakamoto_block_reward_cycle(&sortdb, &bad_block.header),
                             (
                                 Some(
                                     follower
@@ -752,7 +754,7 @@ fn test_no_buffer_ready_nakamoto_blocks() {
                         assert!(!follower.network.is_nakamoto_block_bufferable(
                             &sortdb,
                             &node.chainstate,
-                            block
+                            &block.header
                         ));
                     }
 
@@ -798,6 +800,352 @@ fn test_no_buffer_ready_nakamoto_blocks() {
     });
 }
 
+/// Make a copy of `network_result` which carries only the given compact block and the given
+/// reply to a `GetNakamotoBlockTxs` request, both from `neighbor_key`.
+fn make_compact_block_network_result(
+    network_result: &NetworkResult,
+    neighbor_key: &NeighborKey,
+    compact_block_opt: Option<CompactNakamotoBlockData>,
+    block_txs_opt: Option<NakamotoBlockTxsData>,
+) -> NetworkResult {
+    let mut compact_network_result = network_result.clone();
+    compact_network_result.pushed_transactions.clear();
+    compact_network_result.pushed_nakamoto_blocks.clear();
+    compact_network_result
+        .pushed_compact_nakamoto_blocks
+        .clear();
+    compact_network_result.pushed_nakamoto_block_txs.clear();
+    if let Some(compact_block) = compact_block_opt {
+        compact_network_result
+            .pushed_compact_nakamoto_blocks
+            .insert(neighbor_key.clone(), vec![(vec![], compact_block)]);
+    }
+    if let Some(block_txs) = block_txs_opt {
+        compact_network_result
+            .pushed_nakamoto_block_txs
+            .insert(neighbor_key.clone(), vec![block_txs]);
+    }
+    compact_network_result
+}
+
+/// Get the blocks that were rebuilt from compact blocks
+fn get_rebuilt_blocks(network_result: &NetworkResult) -> Vec<NakamotoBlock> {
+    network_result
+        .pushed_nakamoto_blocks
+        .values()
+        .flatten()
+        .flat_map(|(_, blocks_data)| blocks_data.blocks.clone())
+        .collect()
+}
+
+/// Get the GetNakamotoBlockTxs requests the relayer sent to the p2p thread
+fn get_block_txs_requests(
+    p2p_receiver: &Receiver<NetworkRequest>,
+) -> Vec<(NeighborKey, GetNakamotoBlockTxsData)> {
+    p2p_receiver
+        .try_iter()
+        .filter_map(|request| match request {
+            NetworkRequest::Send(neighbor_key, StacksMessageType::GetNakamotoBlockTxs(data)) => {
+                Some((neighbor_key, data))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Verify that compact Nakamoto blocks are rebuilt from the mempool, that their missing
+/// transactions are fetched from the sender, and that the full block is requested if the sender's
+/// reply doesn't complete the block.  Compact blocks whose signer signatures don't verify are
+/// dropped.
+#[test]
+fn test_process_compact_nakamoto_blocks() {
+    let observer = TestEventObserver::new();
+    let bitvecs = vec![vec![
+        true, true, true, true, true, true, true, true, true, true,
+    ]];
+
+    let rc_len = 10u64;
+    let (peer, mut followers) =
+        make_nakamoto_peers_from_invs(function_name!(), &observer, rc_len as u32, 5, bitvecs, 1);
+    let peer_nk = peer.to_neighbor().addr;
+    let mut follower = followers.pop().unwrap();
+
+    // capture the follower relayer's requests to the sender
+    let test_path = TestPeer::make_test_path(&follower.config);
+    let stackerdb_path = format!("{}/stacker_db.sqlite", &test_path);
+    let follower_stacker_dbs = StackerDBs::connect(&stackerdb_path, true).unwrap();
+    let (p2p_sender, p2p_receiver) = sync_channel(1024);
+    let mut follower_relayer = Relayer::new(
+        NetworkHandle::new(p2p_sender),
+        follower.network.connection_opts.clone(),
+        follower_stacker_dbs,
+    );
+
+    // disable the follower's ability to download blocks from the seed peer
+    follower.network.connection_opts.disable_block_download = true;
+    follower.config.connection_opts.disable_block_download = true;
+
+    let (seed_comms, mut follower_comms) = SeedNode::comms();
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            SeedNode::main(peer, rc_len, seed_comms);
+        });
+
+        let mut seed_exited = false;
+        let (mut follower_dns_client, follower_dns_thread_handle) = dns_thread_start(100);
+
+        while !seed_exited {
+            let mut network_result = follower
+                .step_with_ibd_and_dns(true, Some(&mut follower_dns_client))
+                .ok();
+
+            match follower_comms.try_recv() {
+                None => {}
+                Some(SeedData::BurnOps(burn_ops, consensus_hash)) => {
+                    debug!("Follower got {}: {:?}", &consensus_hash, &burn_ops);
+                    let (_, _, follower_consensus_hash) =
+                        follower.next_burnchain_block(burn_ops.clone());
+                    assert_eq!(follower_consensus_hash, consensus_hash);
+                }
+                Some(SeedData::Blocks(blocks)) => {
+                    debug!("Follower got Nakamoto blocks {:?}", &blocks);
+
+                    let mut sortdb = follower.sortdb.take().unwrap();
+                    let mut node = follower.stacks_node.take().unwrap();
+                    let mut mempool = follower.mempool.take().unwrap();
+
+                    let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).unwrap();
+
+                    if let Some(mut network_result) = network_result.take() {
+                        for (i, block) in blocks.iter().enumerate() {
+                            let block_id = block.block_id();
+                            let mut compact_block = CompactNakamotoBlockData::from_block(
+                                block,
+                                thread_rng().gen::<u64>(),
+                            );
+
+                            // the txs which were not prefilled, and their indexes
+                            let (indexes, txs): (Vec<_>, Vec<_>) = block
+                                .txs
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, tx)| !compact_block.prefilled_txs.contains(tx))
+                                .map(|(i, tx)| (u16::try_from(i).unwrap(), tx.clone()))
+                                .unzip();
+                            assert!(!txs.is_empty());
+
+                            match i % 4 {
+                                0 => {
+                                    // rebuild from the mempool
+                                    let mut mempool_tx = mempool.tx_begin().unwrap();
+                                    for tx in txs.iter() {
+                                        let origin_address = tx.origin_address();
+                                        let sponsor_address =
+                                            tx.sponsor_address().unwrap_or(origin_address.clone());
+                                        MemPoolDB::try_add_tx(
+                                            &mut mempool_tx,
+                                            &mut node.chainstate,
+                                            &block.header.consensus_hash,
+                                            &block.header.block_hash(),
+                                            false,
+                                            tx.txid(),
+                                            tx.serialize_to_vec(),
+                                            tx.get_tx_fee(),
+                                            tip.block_height,
+                                            &origin_address,
+                                            tx.get_origin_nonce(),
+                                            &sponsor_address,
+                                            tx.get_sponsor_nonce().unwrap_or(tx.get_origin_nonce()),
+                                            None,
+                                        )
+                                        .unwrap();
+                                    }
+                                    mempool_tx.commit().unwrap();
+
+                                    let mut compact_network_result =
+                                        make_compact_block_network_result(
+                                            &network_result,
+                                            &peer_nk,
+                                            Some(compact_block),
+                                            None,
+                                        );
+                                    follower_relayer.process_compact_nakamoto_blocks(
+                                        &mut compact_network_result,
+                                        &follower.network.burnchain,
+                                        &sortdb,
+                                        &mut node.chainstate,
+                                        &mempool,
+                                    );
+                                    assert_eq!(
+                                        get_rebuilt_blocks(&compact_network_result),
+                                        vec![block.clone()]
+                                    );
+                                    assert!(get_block_txs_requests(&p2p_receiver).is_empty());
+                                }
+                                1 => {
+                                    // fetch the missing txs from the sender
+                                    let mut compact_network_result =
+                                        make_compact_block_network_result(
+                                            &network_result,
+                                            &peer_nk,
+                                            Some(compact_block),
+                                            None,
+                                        );
+                                    follower_relayer.process_compact_nakamoto_blocks(
+                                        &mut compact_network_result,
+                                        &follower.network.burnchain,
+                                        &sortdb,
+                                        &mut node.chainstate,
+                                        &mempool,
+                                    );
+                                    assert!(get_rebuilt_blocks(&compact_network_result).is_empty());
+                                    assert_eq!(
+                                        get_block_txs_requests(&p2p_receiver),
+                                        vec![(
+                                            peer_nk.clone(),
+                                            GetNakamotoBlockTxsData {
+                                                block_id: block_id.clone(),
+                                                indexes,
+                                            }
+                                        )]
+                                    );
+
+                                    let mut reply_network_result =
+                                        make_compact_block_network_result(
+                                            &network_result,
+                                            &peer_nk,
+                                            None,
+                                            Some(NakamotoBlockTxsData { block_id, txs }),
+                                        );
+                                    follower_relayer.process_compact_nakamoto_blocks(
+                                        &mut reply_network_result,
+                                        &follower.network.burnchain,
+                                        &sortdb,
+                                        &mut node.chainstate,
+                                        &mempool,
+                                    );
+                                    assert_eq!(
+                                        get_rebuilt_blocks(&reply_network_result),
+                                        vec![block.clone()]
+                                    );
+                                    assert!(get_block_txs_requests(&p2p_receiver).is_empty());
+                                }
+                                2 => {
+                                    // the sender's reply doesn't complete the block, so fall back
+                                    // to requesting the full block
+                                    let mut compact_network_result =
+                                        make_compact_block_network_result(
+                                            &network_result,
+                                            &peer_nk,
+                                            Some(compact_block),
+                                            None,
+                                        );
+                                    follower_relayer.process_compact_nakamoto_blocks(
+                                        &mut compact_network_result,
+                                        &follower.network.burnchain,
+                                        &sortdb,
+                                        &mut node.chainstate,
+                                        &mempool,
+                                    );
+                                    assert_eq!(get_block_txs_requests(&p2p_receiver).len(), 1);
+
+                                    let mut reply_network_result =
+                                        make_compact_block_network_result(
+                                            &network_result,
+                                            &peer_nk,
+                                            None,
+                                            Some(NakamotoBlockTxsData {
+                                                block_id: block_id.clone(),
+                                                txs: vec![],
+                                            }),
+                                        );
+                                    follower_relayer.process_compact_nakamoto_blocks(
+                                        &mut reply_network_result,
+                                        &follower.network.burnchain,
+                                        &sortdb,
+                                        &mut node.chainstate,
+                                        &mempool,
+                                    );
+                                    assert!(get_rebuilt_blocks(&reply_network_result).is_empty());
+                                    assert_eq!(
+                                        get_block_txs_requests(&p2p_receiver),
+                                        vec![(
+                                            peer_nk.clone(),
+                                            GetNakamotoBlockTxsData {
+                                                block_id,
+                                                indexes: vec![],
+                                            }
+                                        )]
+                                    );
+                                }
+                                _ => {
+                                    // a compact block whose signer signatures don't verify is
+                                    // dropped
+                                    compact_block.header.signer_signature.clear();
+                                    let mut compact_network_result =
+                                        make_compact_block_network_result(
+                                            &network_result,
+                                            &peer_nk,
+                                            Some(compact_block),
+                                            None,
+                                        );
+                                    follower_relayer.process_compact_nakamoto_blocks(
+                                        &mut compact_network_result,
+                                        &follower.network.burnchain,
+                                        &sortdb,
+                                        &mut node.chainstate,
+                                        &mempool,
+                                    );
+                                    assert!(get_rebuilt_blocks(&compact_network_result).is_empty());
+                                    assert!(get_block_txs_requests(&p2p_receiver).is_empty());
+                                }
+                            }
+                        }
+
+                        // go process the full blocks, so the follower keeps up with the seed
+                        let mut unsolicited = HashMap::new();
+                        let msg = StacksMessage::from_chain_view(
+                            follower.network.bound_neighbor_key().peer_version,
+                            follower.network.bound_neighbor_key().network_id,
+                            follower.network.get_chain_view(),
+                            StacksMessageType::NakamotoBlocks(NakamotoBlocksData {
+                                blocks: blocks.clone(),
+                            }),
+                        );
+                        unsolicited.insert((1, peer_nk.clone()), vec![msg]);
+
+                        network_result.consume_unsolicited(unsolicited);
+                        let num_processed = follower_relayer.process_new_epoch3_blocks(
+                            follower.network.get_local_peer(),
+                            &mut network_result,
+                            &follower.network.burnchain,
+                            &mut sortdb,
+                            &mut node.chainstate,
+                            true,
+                            None,
+                        );
+                        assert_eq!(num_processed, blocks.len() as u64);
+                    }
+
+                    follower.stacks_node = Some(node);
+                    follower.sortdb = Some(sortdb);
+                    follower.mempool = Some(mempool);
+                }
+                Some(SeedData::Exit(_exited)) => {
+                    debug!("Follower got seed exit");
+                    seed_exited = true;
+                    follower_comms.send_exit();
+                }
+            }
+
+            follower.coord.handle_new_burnchain_block().unwrap();
+            follower.coord.handle_new_stacks_block().unwrap();
+            follower.coord.handle_new_nakamoto_stacks_block().unwrap();
+        }
+    });
+}
+
 /// Verify that Nakamoto blocks whose sortitions are not yet known will be buffered, and sent to
 /// the relayer once the burnchain advances.
 #[test]
@@ -900,7 +1248,7 @@ fn test_buffer_nonready_nakamoto_blocks() {
                         assert!(follower.network.is_nakamoto_block_bufferable(
                             &sortdb,
                             &node.chainstate,
-                            block
+                            &block.header
                         ));
                     }
 
@@ -0,0 +1,302 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Authenticated encryption for the p2p transport.
+//!
+//! Peers that both advertise the ENCRYPTED_TRANSPORT service bit upgrade their connection once
+//! they have handshaked.  The outbound peer sends an `EncryptTransport` message with a fresh
+//! ephemeral public key, and the inbound peer replies with one of its own.  Both messages are
+//! signed with the peers' node keys like any other message, and every byte a peer sends after its
+//! `EncryptTransport` message is part of an encrypted record.
+//!
+//! The keys are derived Noise-style from three Diffie-Hellman results -- ephemeral-ephemeral, and
+//! each peer's static node key with the other's ephemeral key -- so that they are bound to both
+//! peers' identities and have forward secrecy.  Each direction gets its own AES-256-GCM key,
+//! expanded from the DH results with HKDF-SHA256, and
+//! uses a counter for its nonces.
+
+use std::fmt;
+
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::Aes256Gcm;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use stacks_common::util::secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey};
+
+use crate::net::Error as net_error;
+
+/// HKDF salt for transport key derivation
+const TRANSPORT_KEY_SALT: &[u8] = b"stacks-p2p-encrypted-transport-v1";
+
+/// Length of the AES-GCM authentication tag on each record
+pub const TRANSPORT_RECORD_TAG_LEN: usize = 16;
+
+/// Maximum length of the ciphertext in a record.  Records are made from the chunks that the
+/// connection outbox reads from its message pipes, which are much smaller than this.
+pub const TRANSPORT_RECORD_MAX_LEN: usize = 65536 + TRANSPORT_RECORD_TAG_LEN;
+
+/// Compute the ECDH shared secret between a private key and a public key
+fn ecdh(
+    private_key: &Secp256k1PrivateKey,
+    public_key: &Secp256k1PublicKey,
+) -> Result<[u8; 32], net_error> {
+    let secret_key = secp256k1::SecretKey::from_slice(private_key.as_slice())
+        .map_err(|e| net_error::SigningError(format!("Invalid private key: {:?}", &e)))?;
+    let public_key = secp256k1::PublicKey::from_slice(&public_key.to_bytes_compressed())
+        .map_err(|e| net_error::SigningError(format!("Invalid public key: {:?}", &e)))?;
+    Ok(secp256k1::ecdh::SharedSecret::new(&public_key, &secret_key).secret_bytes())
+}
+
+/// One direction of an encrypted transport.
+/// Plaintext is sealed into records, each of which is a 4-byte big-endian ciphertext length
+/// followed by the ciphertext (which includes the authentication tag).
+pub struct TransportCipher {
+    cipher: Aes256Gcm,
+    /// number of records sealed or opened so far; used as the nonce
+    counter: u64,
+}
+
+impl fmt::Debug for TransportCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TransportCipher(counter={})", self.counter)
+    }
+}
+
+impl TransportCipher {
+    fn new(key: &[u8; 32]) -> TransportCipher {
+        TransportCipher {
+            cipher: Aes256Gcm::new(GenericArray::from_slice(key)),
+            counter: 0,
+        }
+    }
+
+    /// Get the nonce for the next record, and advance the counter
+    fn next_nonce(&mut self) -> Result<[u8; 12], net_error> {
+        let mut nonce = [0u8; 12];
+        nonce[4..].copy_from_slice(&self.counter.to_be_bytes());
+        self.counter = self.counter.checked_add(1).ok_or(net_error::OverflowError(
+            "Transport record counter overflow".to_string(),
+        ))?;
+        Ok(nonce)
+    }
+
+    /// Encrypt `plaintext` into a record
+    pub fn seal(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, net_error> {
+        if plaintext.len() + TRANSPORT_RECORD_TAG_LEN > TRANSPORT_RECORD_MAX_LEN {
+            return Err(net_error::OverflowError(format!(
+                "Transport record plaintext is too long ({} bytes)",
+                plaintext.len()
+            )));
+        }
+        let nonce = self.next_nonce()?;
+        let ciphertext = self
+            .cipher
+            .encrypt(GenericArray::from_slice(&nonce), plaintext)
+            .map_err(|_| net_error::SigningError("Failed to encrypt record".to_string()))?;
+
+        let mut record = Vec::with_capacity(4 + ciphertext.len());
+        record.extend_from_slice(&(ciphertext.len() as u32).to_be_bytes());
+        record.extend_from_slice(&ciphertext);
+        Ok(record)
+    }
+
+    /// Decrypt the record at the start of `buf`.
+    /// Returns Ok(Some((plaintext, number of bytes consumed))) on success.
+    /// Returns Ok(None) if `buf` does not yet hold a whole record.
+    /// Returns Err(..) if the record is malformed or fails authentication.
+    pub fn open(&mut self, buf: &[u8]) -> Result<Option<(Vec<u8>, usize)>, net_error> {
+        if buf.len() < 4 {
+            return Ok(None);
+        }
+        let mut len_bytes = [0u8; 4];
+        len_bytes.copy_from_slice(&buf[0..4]);
+        let len = u32::from_be_bytes(len_bytes) as usize;
+        if !(TRANSPORT_RECORD_TAG_LEN..=TRANSPORT_RECORD_MAX_LEN).contains(&len) {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid transport record length {}",
+                len
+            )));
+        }
+        if buf.len() < 4 + len {
+            return Ok(None);
+        }
+
+        let nonce = self.next_nonce()?;
+        let plaintext = self
+            .cipher
+            .decrypt(GenericArray::from_slice(&nonce), &buf[4..(4 + len)])
+            .map_err(|_| net_error::VerifyingError("Failed to decrypt record".to_string()))?;
+        Ok(Some((plaintext, 4 + len)))
+    }
+}
+
+/// Our half of the key exchange that upgrades a connection to encrypted transport
+#[derive(Debug)]
+pub struct TransportKeyExchange {
+    ephemeral_private_key: Secp256k1PrivateKey,
+    ephemeral_public_key: Secp256k1PublicKey,
+}
+
+impl TransportKeyExchange {
+    /// Start a key exchange with a fresh ephemeral key
+    pub fn new() -> TransportKeyExchange {
+        let mut ephemeral_private_key = Secp256k1PrivateKey::random();
+        ephemeral_private_key.set_compress_public(true);
+        let ephemeral_public_key = Secp256k1PublicKey::from_private(&ephemeral_private_key);
+        TransportKeyExchange {
+            ephemeral_private_key,
+            ephemeral_public_key,
+        }
+    }
+
+    pub fn ephemeral_public_key(&self) -> &Secp256k1PublicKey {
+        &self.ephemeral_public_key
+    }
+
+    /// Derive the key for the records sent by the owner of `sender_ephemeral_key`, with
+    /// HKDF-SHA256 over the shared secret.  The expansion info binds the key to its direction.
+    fn derive_key(
+        shared_secret: &[u8],
+        sender_ephemeral_key: &Secp256k1PublicKey,
+        receiver_ephemeral_key: &Secp256k1PublicKey,
+    ) -> [u8; 32] {
+        let mut info = vec![];
+        info.extend_from_slice(&sender_ephemeral_key.to_bytes_compressed());
+        info.extend_from_slice(&receiver_ephemeral_key.to_bytes_compressed());
+
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(TRANSPORT_KEY_SALT), shared_secret)
+            .expand(&info, &mut key)
+            .expect("FATAL: 32 bytes is a valid HKDF-SHA256 output length");
+        key
+    }
+
+    /// Finish the key exchange, given our node key, and the remote peer's node key and ephemeral
+    /// key.  Returns the ciphers for (sending, receiving) records.
+    pub fn finish(
+        &self,
+        local_private_key: &Secp256k1PrivateKey,
+        remote_public_key: &Secp256k1PublicKey,
+        remote_ephemeral_key: &Secp256k1PublicKey,
+    ) -> Result<(TransportCipher, TransportCipher), net_error> {
+        let local_ephemeral_bytes = self.ephemeral_public_key.to_bytes_compressed();
+        let remote_ephemeral_bytes = remote_ephemeral_key.to_bytes_compressed();
+        if local_ephemeral_bytes == remote_ephemeral_bytes {
+            return Err(net_error::InvalidMessage);
+        }
+
+        // both peers must combine the DH results in the same order, so put the static-ephemeral
+        // result for the peer with the lesser ephemeral key first.
+        let ee = ecdh(&self.ephemeral_private_key, remote_ephemeral_key)?;
+        let local_se = ecdh(local_private_key, remote_ephemeral_key)?;
+        let remote_se = ecdh(&self.ephemeral_private_key, remote_public_key)?;
+
+        let mut shared_secret = vec![];
+        shared_secret.extend_from_slice(&ee);
+        if local_ephemeral_bytes < remote_ephemeral_bytes {
+            shared_secret.extend_from_slice(&local_se);
+            shared_secret.extend_from_slice(&remote_se);
+        } else {
+            shared_secret.extend_from_slice(&remote_se);
+            shared_secret.extend_from_slice(&local_se);
+        }
+
+        let send_key = Self::derive_key(
+            &shared_secret,
+            &self.ephemeral_public_key,
+            remote_ephemeral_key,
+        );
+        let recv_key = Self::derive_key(
+            &shared_secret,
+            remote_ephemeral_key,
+            &self.ephemeral_public_key,
+        );
+        Ok((
+            TransportCipher::new(&send_key),
+            TransportCipher::new(&recv_key),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_transport_key_exchange_and_records() {
+        let node_key_1 = Secp256k1PrivateKey::random();
+        let node_key_2 = Secp256k1PrivateKey::random();
+        let node_pubkey_1 = Secp256k1PublicKey::from_private(&node_key_1);
+        let node_pubkey_2 = Secp256k1PublicKey::from_private(&node_key_2);
+
+        let kx_1 = TransportKeyExchange::new();
+        let kx_2 = TransportKeyExchange::new();
+
+        let (mut send_1, mut recv_1) = kx_1
+            .finish(&node_key_1, &node_pubkey_2, kx_2.ephemeral_public_key())
+            .unwrap();
+        let (mut send_2, mut recv_2) = kx_2
+            .finish(&node_key_2, &node_pubkey_1, kx_1.ephemeral_public_key())
+            .unwrap();
+
+        // records go both ways, and can be split across reads
+        let mut buf = vec![];
+        buf.extend_from_slice(&send_1.seal(b"hello").unwrap());
+        buf.extend_from_slice(&send_1.seal(b"world").unwrap());
+
+        assert!(recv_2.open(&buf[0..3]).unwrap().is_none());
+        assert!(recv_2.open(&buf[0..10]).unwrap().is_none());
+
+        let (plaintext, consumed) = recv_2.open(&buf).unwrap().unwrap();
+        assert_eq!(plaintext, b"hello".to_vec());
+        let (plaintext, _) = recv_2.open(&buf[consumed..]).unwrap().unwrap();
+        assert_eq!(plaintext, b"world".to_vec());
+
+        let record = send_2.seal(b"hi there").unwrap();
+        let (plaintext, consumed) = recv_1.open(&record).unwrap().unwrap();
+        assert_eq!(plaintext, b"hi there".to_vec());
+        assert_eq!(consumed, record.len());
+
+        // tampered records are rejected
+        let mut record = send_2.seal(b"tampered").unwrap();
+        let last = record.len() - 1;
+        record[last] ^= 0x01;
+        assert!(recv_1.open(&record).is_err());
+
+        // replayed records are rejected, since the nonce has moved on
+        let record = send_1.seal(b"once").unwrap();
+        recv_2.open(&record).unwrap().unwrap();
+        assert!(recv_2.open(&record).is_err());
+
+        // a peer that doesn't know the right node key can't derive the same keys
+        let impostor_key = Secp256k1PrivateKey::random();
+        let (mut impostor_send, _) = kx_2
+            .finish(&impostor_key, &node_pubkey_1, kx_1.ephemeral_public_key())
+            .unwrap();
+        let (_, mut recv_1) = kx_1
+            .finish(&node_key_1, &node_pubkey_2, kx_2.ephemeral_public_key())
+            .unwrap();
+        let record = impostor_send.seal(b"impostor").unwrap();
+        assert!(recv_1.open(&record).is_err());
+
+        // oversized records are rejected
+        let mut bad_record = ((TRANSPORT_RECORD_MAX_LEN + 1) as u32)
+            .to_be_bytes()
+            .to_vec();
+        bad_record.extend_from_slice(&[0u8; 32]);
+        assert!(recv_1.open(&bad_record).is_err());
+    }
+}
@@ -19,13 +19,13 @@ use std::collections::HashMap;
 use stacks_common::types::chainstate::{BlockHeaderHash, ConsensusHash};
 
 use crate::chainstate::burn::db::sortdb::SortitionDB;
-use crate::chainstate::nakamoto::NakamotoBlock;
+use crate::chainstate::nakamoto::NakamotoBlockHeader;
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::{Error as ChainstateError, StacksBlockHeader};
 use crate::net::p2p::{PeerNetwork, PeerNetworkWorkState, PendingMessages};
 use crate::net::{
-    BlocksAvailableData, BlocksData, BlocksDatum, Error as NetError, MicroblocksData,
-    NakamotoBlocksData, NeighborKey, Preamble, StacksMessage, StacksMessageType,
+    BlocksAvailableData, BlocksData, BlocksDatum, CompactNakamotoBlockData, Error as NetError,
+    MicroblocksData, NakamotoBlocksData, NeighborKey, Preamble, StacksMessage, StacksMessageType,
 };
 
 /// This module contains all of the code needed to handle unsolicited messages -- that is, messages
@@ -35,6 +35,7 @@ use crate::net::{
 /// * MicroblocksAvailable (epoch 2.x)
 /// * BlocksData (epoch 2.x)
 /// * NakamotoBlocksData (epoch 3.x)
+/// * CompactNakamotoBlockData (epoch 3.x)
 ///
 /// Normally, the PeerNetwork will attempt to validate each message and pass it to the Relayer via
 /// a NetworkResult.  However, some kinds of messages (such as these) cannot be always be
@@ -232,10 +233,16 @@ impl PeerNetwork {
                         return false;
                     }
                 }
-                StacksMessageType::NakamotoBlocks(_) => {
+                StacksMessageType::NakamotoBlocks(_)
+                | StacksMessageType::CompactNakamotoBlock(_) => {
+                    // compact blocks count against the same quota as full blocks
                     nakamoto_blocks_data += 1;
-                    if matches!(&msg.payload, StacksMessageType::NakamotoBlocks(..))
-                        && nakamoto_blocks_data >= self.connection_opts.max_buffered_nakamoto_blocks
+                    if matches!(
+                        &msg.payload,
+                        StacksMessageType::NakamotoBlocks(..)
+                            | StacksMessageType::CompactNakamotoBlock(..)
+                    ) && nakamoto_blocks_data
+                        >= self.connection_opts.max_buffered_nakamoto_blocks
                     {
                         debug!(
                             "{:?}: Cannot buffer NakamotoBlocksData from event {} -- already have {} buffered",
@@ -795,19 +802,19 @@ impl PeerNetwork {
     }
 
     #[cfg_attr(test, mutants::skip)]
-    /// Check the signature of a NakamotoBlock against its sortition's reward cycle.
+    /// Check the signature of a NakamotoBlock's header against its sortition's reward cycle.
     /// The reward cycle must be recent.
     pub(crate) fn check_nakamoto_block_signer_signature(
         &mut self,
         reward_cycle: u64,
-        nakamoto_block: &NakamotoBlock,
+        header: &NakamotoBlockHeader,
     ) -> bool {
         let Some(rc_data) = self.current_reward_sets.get(&reward_cycle) else {
             info!(
                 "{:?}: Failed to validate Nakamoto block {}/{}: no reward set for cycle {}",
                 self.get_local_peer(),
-                &nakamoto_block.header.consensus_hash,
-                &nakamoto_block.header.block_hash(),
+                &header.consensus_hash,
+                &header.block_hash(),
                 reward_cycle,
             );
             return false;
@@ -821,9 +828,9 @@ impl PeerNetwork {
             return false;
         };
 
-        if let Err(e) = nakamoto_block.header.verify_signer_signatures(reward_set) {
+        if let Err(e) = header.verify_signer_signatures(reward_set) {
             info!(
-                "{:?}: signature verification failure for Nakamoto block {}/{} in reward cycle {}: {:?}", self.get_local_peer(), &nakamoto_block.header.consensus_hash, &nakamoto_block.header.block_hash(), reward_cycle, &e
+                "{:?}: signature verification failure for Nakamoto block {}/{} in reward cycle {}: {:?}", self.get_local_peer(), &header.consensus_hash, &header.block_hash(), reward_cycle, &e
             );
             return false;
         }
@@ -838,18 +845,18 @@ impl PeerNetwork {
     pub(crate) fn find_nakamoto_block_reward_cycle(
         &self,
         sortdb: &SortitionDB,
-        nakamoto_block: &NakamotoBlock,
+        header: &NakamotoBlockHeader,
     ) -> (Option<u64>, bool) {
         let (reward_set_sn, can_process) = match SortitionDB::get_block_snapshot_consensus(
             sortdb.conn(),
-            &nakamoto_block.header.consensus_hash,
+            &header.consensus_hash,
         ) {
             Ok(Some(sn)) => (sn, true),
             Ok(None) => {
                 debug!(
                     "No sortition {} for block {}",
-                    &nakamoto_block.header.consensus_hash,
-                    &nakamoto_block.block_id()
+                    &header.consensus_hash,
+                    &header.block_id()
                 );
                 // we don't have the sortition for this, so we can't process it yet (i.e. we need
                 // to buffer)
@@ -860,7 +867,7 @@ impl PeerNetwork {
                 info!(
                     "{:?}: Failed to query block snapshot for {}: {:?}",
                     self.get_local_peer(),
-                    &nakamoto_block.header.consensus_hash,
+                    &header.consensus_hash,
                     &e
                 );
                 return (None, false);
@@ -871,7 +878,7 @@ impl PeerNetwork {
             info!(
                 "{:?}: Failed to query snapshot for {}: not on the valid PoX fork",
                 self.get_local_peer(),
-                &nakamoto_block.header.consensus_hash
+                &header.consensus_hash
             );
             return (None, false);
         }
@@ -885,34 +892,34 @@ impl PeerNetwork {
     }
 
     #[cfg_attr(test, mutants::skip)]
-    /// Determine if an unsolicited NakamotoBlockData message contains data we can potentially
-    /// buffer.  Returns whether or not the block can be buffered.
+    /// Determine if an unsolicited NakamotoBlockData or CompactNakamotoBlockData message contains
+    /// data we can potentially buffer.  Only the block header is needed to decide.
+    /// Returns whether or not the block can be buffered.
     pub(crate) fn is_nakamoto_block_bufferable(
         &mut self,
         sortdb: &SortitionDB,
         chainstate: &StacksChainState,
-        nakamoto_block: &NakamotoBlock,
+        header: &NakamotoBlockHeader,
     ) -> bool {
         if chainstate
             .nakamoto_blocks_db()
-            .has_nakamoto_block_with_index_hash(&nakamoto_block.block_id())
+            .has_nakamoto_block_with_index_hash(&header.block_id())
             .unwrap_or(false)
         {
             debug!(
                 "{:?}: Aleady have Nakamoto block {}",
                 &self.get_local_peer(),
-                &nakamoto_block.block_id()
+                &header.block_id()
             );
             return false;
         }
 
-        let (sn_rc_opt, can_process) =
-            self.find_nakamoto_block_reward_cycle(sortdb, nakamoto_block);
+        let (sn_rc_opt, can_process) = self.find_nakamoto_block_reward_cycle(sortdb, header);
         let Some(sn_rc) = sn_rc_opt else {
             return false;
         };
 
-        if !self.check_nakamoto_block_signer_signature(sn_rc, nakamoto_block) {
+        if !self.check_nakamoto_block_signer_signature(sn_rc, header) {
             return false;
         }
 
@@ -946,7 +953,7 @@ impl PeerNetwork {
 
         let mut to_buffer = false;
         for nakamoto_block in nakamoto_blocks.blocks.iter() {
-            if self.is_nakamoto_block_bufferable(sortdb, chainstate, nakamoto_block) {
+            if self.is_nakamoto_block_bufferable(sortdb, chainstate, &nakamoto_block.header) {
                 debug!(
                     "{:?}: Will buffer unsolicited NakamotoBlocksData({}) ({})",
                     &self.get_local_peer(),
@@ -990,6 +997,39 @@ impl PeerNetwork {
         )
     }
 
+    #[cfg_attr(test, mutants::skip)]
+    /// Handle an unsolicited CompactNakamotoBlockData message.
+    ///
+    /// The compact block is validated and buffered just like a NakamotoBlocksData message with
+    /// one block, since its header carries the signer signatures.  The relayer rebuilds it once
+    /// it can be processed.
+    ///
+    /// Returns true if this message should be buffered and re-processed
+    fn handle_unsolicited_CompactNakamotoBlockData(
+        &mut self,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        event_id: usize,
+        compact_block: &CompactNakamotoBlockData,
+    ) -> bool {
+        debug!(
+            "{:?}: Process CompactNakamotoBlockData({}) from event {}",
+            &self.get_local_peer(),
+            &compact_block.block_id(),
+            event_id
+        );
+        if self.is_nakamoto_block_bufferable(sortdb, chainstate, &compact_block.header) {
+            debug!(
+                "{:?}: Will buffer unsolicited CompactNakamotoBlockData({}) ({})",
+                &self.get_local_peer(),
+                &compact_block.block_id(),
+                &compact_block.header.consensus_hash,
+            );
+            return true;
+        }
+        false
+    }
+
     #[cfg_attr(test, mutants::skip)]
     /// Handle an unsolicited message, with either the intention of just processing it (in which
     /// case, `buffer` will be `false`), or with the intention of not only processing it, but also
@@ -1077,6 +1117,21 @@ impl PeerNetwork {
 
                 (to_buffer, true)
             }
+            StacksMessageType::CompactNakamotoBlock(ref compact_block) => {
+                let to_buffer = if buffer {
+                    self.handle_unsolicited_CompactNakamotoBlockData(
+                        sortdb,
+                        chainstate,
+                        event_id,
+                        compact_block,
+                    )
+                } else {
+                    // nothing to do if we're not querying about whether we can buffer this.
+                    false
+                };
+
+                (to_buffer, true)
+            }
             _ => (false, true),
         }
     }
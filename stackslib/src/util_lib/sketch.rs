@@ -0,0 +1,326 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Set sketches for set reconciliation.
+//!
+//! A `SetSketch` is an invertible Bloom lookup table (IBLT) over 64-bit keys.  Two nodes that
+//! each insert their set into a sketch with the same number of cells can subtract one sketch from
+//! the other to cancel out the keys they have in common.  If what remains -- the symmetric
+//! difference of the two sets -- is small relative to the number of cells, then it can be
+//! recovered exactly by repeatedly "peeling" cells that hold a single key.  The size of a sketch
+//! depends only on the size of the difference it can recover, not on the size of the sets.
+
+use std::collections::HashSet;
+use std::hash::Hasher;
+use std::io::{Read, Write};
+
+use siphasher::sip::SipHasher; // this is SipHash-2-4
+use stacks_common::codec::{
+    read_next, read_next_at_most, write_next, Error as codec_error, StacksMessageCodec,
+};
+
+/// Number of cells each key is stored in.  A sketch is split into this many equal-sized
+/// partitions, and each key is stored in one cell of each partition.
+pub const SKETCH_NUM_HASHES: u32 = 3;
+
+/// Maximum number of cells a sketch may have.  This bounds both the size of a sketch on the wire
+/// (18 bytes per cell) and the work a peer does to decode one: enough to recover a difference of
+/// about 2,000 keys.
+pub const SKETCH_MAX_CELLS: u32 = SKETCH_NUM_HASHES * 1024;
+
+/// One cell of a sketch
+#[derive(Debug, Clone, PartialEq, Default)]
+struct SketchCell {
+    /// Number of keys stored in this cell, modulo 2**16.  Once one sketch is subtracted from
+    /// another, a cell holding a single key that only the first (second) set has will have a
+    /// count of 1 (u16::MAX).
+    count: u16,
+    /// XOR of the keys stored in this cell
+    key_sum: u64,
+    /// XOR of the checksums of the keys stored in this cell
+    hash_sum: u64,
+}
+
+impl SketchCell {
+    fn is_empty(&self) -> bool {
+        self.count == 0 && self.key_sum == 0 && self.hash_sum == 0
+    }
+}
+
+impl StacksMessageCodec for SketchCell {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.count)?;
+        write_next(fd, &self.key_sum)?;
+        write_next(fd, &self.hash_sum)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<SketchCell, codec_error> {
+        let count: u16 = read_next(fd)?;
+        let key_sum: u64 = read_next(fd)?;
+        let hash_sum: u64 = read_next(fd)?;
+        Ok(SketchCell {
+            count,
+            key_sum,
+            hash_sum,
+        })
+    }
+}
+
+/// Invertible Bloom lookup table over 64-bit keys.  The keys are expected to be uniformly
+/// distributed already (e.g. they are truncated hashes), so they are not re-hashed with a secret
+/// seed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetSketch {
+    cells: Vec<SketchCell>,
+}
+
+impl SetSketch {
+    /// Make a new, empty sketch with at least `num_cells` cells.  The number of cells is rounded
+    /// up to a multiple of SKETCH_NUM_HASHES, and capped at SKETCH_MAX_CELLS.
+    pub fn new(num_cells: u32) -> SetSketch {
+        let num_cells = num_cells
+            .max(1)
+            .div_ceil(SKETCH_NUM_HASHES)
+            .saturating_mul(SKETCH_NUM_HASHES)
+            .min(SKETCH_MAX_CELLS);
+        SetSketch {
+            cells: vec![SketchCell::default(); num_cells as usize],
+        }
+    }
+
+    pub fn num_cells(&self) -> u32 {
+        self.cells.len() as u32
+    }
+
+    /// Which cell in partition `i` does `key` go into?
+    fn cell_index(&self, i: u32, key: u64) -> usize {
+        let partition_len = self.cells.len() / (SKETCH_NUM_HASHES as usize);
+        let mut hasher = SipHasher::new_with_keys(u64::from(i) + 1, 0);
+        hasher.write(&key.to_be_bytes());
+        (i as usize) * partition_len + ((hasher.finish() % (partition_len as u64)) as usize)
+    }
+
+    /// Checksum of a key, used to tell whether or not a cell holds a single key
+    fn key_checksum(key: u64) -> u64 {
+        let mut hasher = SipHasher::new_with_keys(0, 0);
+        hasher.write(&key.to_be_bytes());
+        hasher.finish()
+    }
+
+    /// Add (or remove) a key to (or from) each of its cells
+    fn update(&mut self, key: u64, add: bool) {
+        let checksum = Self::key_checksum(key);
+        for i in 0..SKETCH_NUM_HASHES {
+            let idx = self.cell_index(i, key);
+            let cell = &mut self.cells[idx];
+            cell.count = if add {
+                cell.count.wrapping_add(1)
+            } else {
+                cell.count.wrapping_sub(1)
+            };
+            cell.key_sum ^= key;
+            cell.hash_sum ^= checksum;
+        }
+    }
+
+    /// Add a key to the sketch
+    pub fn insert(&mut self, key: u64) {
+        self.update(key, true);
+    }
+
+    /// Subtract `other` from this sketch, cancelling out the keys the two sketches have in common.
+    /// Returns None if the sketches have different numbers of cells.
+    pub fn subtract(&self, other: &SetSketch) -> Option<SetSketch> {
+        if self.cells.len() != other.cells.len() {
+            return None;
+        }
+        let cells = self
+            .cells
+            .iter()
+            .zip(other.cells.iter())
+            .map(|(ours, theirs)| SketchCell {
+                count: ours.count.wrapping_sub(theirs.count),
+                key_sum: ours.key_sum ^ theirs.key_sum,
+                hash_sum: ours.hash_sum ^ theirs.hash_sum,
+            })
+            .collect();
+        Some(SetSketch { cells })
+    }
+
+    /// Recover the keys in a sketch produced by `subtract()`.
+    /// Returns (keys only in the first set, keys only in the second set) on success.
+    /// Returns None if the difference is too big for this sketch to recover.
+    ///
+    /// Each cell is visited once, plus once more each time a key is peeled out of it, and no more
+    /// keys are peeled than the sketch has cells.  So the work done is linear in the size of the
+    /// sketch, even if it was crafted by a malicious peer.
+    pub fn decode(mut self) -> Option<(Vec<u64>, Vec<u64>)> {
+        let mut ours = vec![];
+        let mut theirs = vec![];
+        let mut peeled = HashSet::new();
+        let mut to_visit: Vec<usize> = (0..self.cells.len()).collect();
+        while let Some(idx) = to_visit.pop() {
+            let cell = &self.cells[idx];
+            let is_ours = match cell.count {
+                1 => true,
+                u16::MAX => false,
+                _ => continue,
+            };
+            let key = cell.key_sum;
+            if cell.hash_sum != Self::key_checksum(key) {
+                // more than one key in this cell
+                continue;
+            }
+            if peeled.len() >= self.cells.len() || !peeled.insert(key) {
+                // a well-formed sketch never yields the same key twice, or more keys than cells
+                return None;
+            }
+            if is_ours {
+                ours.push(key);
+            } else {
+                theirs.push(key);
+            }
+            self.update(key, !is_ours);
+            // the key's other cells may now hold a single key
+            to_visit.extend((0..SKETCH_NUM_HASHES).map(|i| self.cell_index(i, key)));
+        }
+
+        if !self.cells.iter().all(|cell| cell.is_empty()) {
+            // could not peel everything
+            return None;
+        }
+        Some((ours, theirs))
+    }
+}
+
+impl StacksMessageCodec for SetSketch {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.cells)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<SetSketch, codec_error> {
+        let cells: Vec<SketchCell> = read_next_at_most(fd, SKETCH_MAX_CELLS)?;
+        if cells.is_empty() || cells.len() % (SKETCH_NUM_HASHES as usize) != 0 {
+            return Err(codec_error::DeserializeError(format!(
+                "Invalid set sketch size {}",
+                cells.len()
+            )));
+        }
+        Ok(SetSketch { cells })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::prelude::*;
+    use rand::thread_rng;
+
+    use super::*;
+
+    #[test]
+    fn test_set_sketch_recovers_difference() {
+        let common: Vec<u64> = (0..5000).map(|_| thread_rng().gen()).collect();
+        let ours_only: Vec<u64> = (0..40).map(|_| thread_rng().gen()).collect();
+        let theirs_only: Vec<u64> = (0..30).map(|_| thread_rng().gen()).collect();
+
+        let mut our_sketch = SetSketch::new(300);
+        let mut their_sketch = SetSketch::new(300);
+        for key in common.iter() {
+            our_sketch.insert(*key);
+            their_sketch.insert(*key);
+        }
+        for key in ours_only.iter() {
+            our_sketch.insert(*key);
+        }
+        for key in theirs_only.iter() {
+            their_sketch.insert(*key);
+        }
+
+        let (mut ours, mut theirs) = our_sketch
+            .subtract(&their_sketch)
+            .unwrap()
+            .decode()
+            .unwrap();
+        ours.sort();
+        theirs.sort();
+
+        let mut expected_ours = ours_only.clone();
+        let mut expected_theirs = theirs_only.clone();
+        expected_ours.sort();
+        expected_theirs.sort();
+
+        assert_eq!(ours, expected_ours);
+        assert_eq!(theirs, expected_theirs);
+
+        // identical sets have an empty difference
+        let (ours, theirs) = our_sketch.subtract(&our_sketch).unwrap().decode().unwrap();
+        assert!(ours.is_empty());
+        assert!(theirs.is_empty());
+    }
+
+    #[test]
+    fn test_set_sketch_fails_on_large_difference() {
+        let mut our_sketch = SetSketch::new(30);
+        let their_sketch = SetSketch::new(30);
+        for _ in 0..1000 {
+            our_sketch.insert(thread_rng().gen());
+        }
+        assert!(our_sketch
+            .subtract(&their_sketch)
+            .unwrap()
+            .decode()
+            .is_none());
+
+        // sketches must be the same size
+        assert!(our_sketch.subtract(&SetSketch::new(60)).is_none());
+    }
+
+    #[test]
+    fn test_set_sketch_codec() {
+        let mut sketch = SetSketch::new(10);
+        assert_eq!(sketch.num_cells(), 12);
+        for key in 0..20u64 {
+            sketch.insert(key);
+        }
+
+        let bytes = sketch.serialize_to_vec();
+        let decoded = SetSketch::consensus_deserialize(&mut &bytes[..]).unwrap();
+        assert_eq!(decoded, sketch);
+
+        // cell count must be a nonzero multiple of the number of hashes
+        let bad_sketch = SetSketch {
+            cells: vec![SketchCell::default(); 4],
+        };
+        let bytes = bad_sketch.serialize_to_vec();
+        assert!(SetSketch::consensus_deserialize(&mut &bytes[..]).is_err());
+
+        let empty_sketch = SetSketch { cells: vec![] };
+        let bytes = empty_sketch.serialize_to_vec();
+        assert!(SetSketch::consensus_deserialize(&mut &bytes[..]).is_err());
+
+        // sketches can't be bigger than SKETCH_MAX_CELLS
+        assert_eq!(
+            SetSketch::new(SKETCH_MAX_CELLS + 1).num_cells(),
+            SKETCH_MAX_CELLS
+        );
+        let big_sketch = SetSketch {
+            cells: vec![SketchCell::default(); (SKETCH_MAX_CELLS + SKETCH_NUM_HASHES) as usize],
+        };
+        let bytes = big_sketch.serialize_to_vec();
+        assert!(SetSketch::consensus_deserialize(&mut &bytes[..]).is_err());
+    }
+}
@@ -0,0 +1,531 @@
+//! Client bindings for the `ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.market` contract.
+//!
+//! Generated by `clarity-cli generate_bindings`. Do not edit.
+
+#![allow(
+    dead_code,
+    clippy::needless_question_mark,
+    clippy::result_large_err,
+    clippy::too_many_arguments
+)]
+
+use std::fmt;
+
+use blockstack_lib::chainstate::stacks::{TransactionContractCall, TransactionPayload};
+use blockstack_lib::net::api::callreadonly::{CallReadOnlyRequestBody, CallReadOnlyResponse};
+use clarity::vm::types::{
+    ASCIIData, BuffData, CharType, ListData, OptionalData, PrincipalData,
+    QualifiedContractIdentifier, ResponseData, SequenceData, TupleData, UTF8Data, Value,
+};
+use clarity::vm::ClarityName;
+use stacks_common::types::chainstate::StacksAddress;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindingError {
+    /// A value does not fit its Clarity type
+    Encode(String),
+    /// A Clarity value does not have the expected type
+    Decode(String),
+    /// The node could not evaluate a read-only call
+    Call(String),
+}
+
+impl fmt::Display for BindingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BindingError::Encode(msg) => write!(f, "failed to encode value: {msg}"),
+            BindingError::Decode(msg) => write!(f, "failed to decode value: {msg}"),
+            BindingError::Call(msg) => write!(f, "read-only call failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for BindingError {}
+
+fn unexpected(expected: &str, value: &Value) -> BindingError {
+    BindingError::Decode(format!("expected {expected}, got {value}"))
+}
+
+fn encode_none(_value: ()) -> Value {
+    Value::none()
+}
+
+fn encode_buff(data: Vec<u8>, max_len: usize) -> Result<Value, BindingError> {
+    if data.len() > max_len {
+        return Err(BindingError::Encode(format!(
+            "buffer of length {} exceeds {max_len}",
+            data.len()
+        )));
+    }
+    Value::buff_from(data).map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn encode_ascii(data: String, max_len: usize) -> Result<Value, BindingError> {
+    if data.len() > max_len {
+        return Err(BindingError::Encode(format!(
+            "string of length {} exceeds {max_len}",
+            data.len()
+        )));
+    }
+    Value::string_ascii_from_bytes(data.into_bytes()).map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn encode_utf8(data: String, max_len: usize) -> Result<Value, BindingError> {
+    if data.chars().count() > max_len {
+        return Err(BindingError::Encode(format!(
+            "string of length {} exceeds {max_len}",
+            data.chars().count()
+        )));
+    }
+    Value::string_utf8_from_bytes(data.into_bytes()).map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn encode_optional(data: Option<Value>) -> Result<Value, BindingError> {
+    match data {
+        Some(value) => Value::some(value).map_err(|e| BindingError::Encode(e.to_string())),
+        None => Ok(Value::none()),
+    }
+}
+
+fn encode_response(data: Result<Value, Value>) -> Result<Value, BindingError> {
+    match data {
+        Ok(value) => Value::okay(value),
+        Err(value) => Value::error(value),
+    }
+    .map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn encode_list(items: Vec<Value>, max_len: usize) -> Result<Value, BindingError> {
+    if items.len() > max_len {
+        return Err(BindingError::Encode(format!(
+            "list of length {} exceeds {max_len}",
+            items.len()
+        )));
+    }
+    Value::cons_list_unsanitized(items).map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn encode_tuple(fields: Vec<(&str, Value)>) -> Result<Value, BindingError> {
+    let fields = fields
+        .into_iter()
+        .map(|(name, value)| {
+            ClarityName::try_from(name.to_string())
+                .map(|name| (name, value))
+                .map_err(|e| BindingError::Encode(e.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    TupleData::from_data(fields)
+        .map(Value::Tuple)
+        .map_err(|e| BindingError::Encode(e.to_string()))
+}
+
+fn decode_none(value: Value) -> Result<(), BindingError> {
+    match value {
+        Value::Optional(OptionalData { data: None }) => Ok(()),
+        value => Err(unexpected("none", &value)),
+    }
+}
+
+fn decode_int(value: Value) -> Result<i128, BindingError> {
+    match value {
+        Value::Int(x) => Ok(x),
+        value => Err(unexpected("int", &value)),
+    }
+}
+
+fn decode_uint(value: Value) -> Result<u128, BindingError> {
+    match value {
+        Value::UInt(x) => Ok(x),
+        value => Err(unexpected("uint", &value)),
+    }
+}
+
+fn decode_bool(value: Value) -> Result<bool, BindingError> {
+    match value {
+        Value::Bool(x) => Ok(x),
+        value => Err(unexpected("bool", &value)),
+    }
+}
+
+fn decode_principal(value: Value) -> Result<PrincipalData, BindingError> {
+    match value {
+        Value::Principal(x) => Ok(x),
+        Value::CallableContract(x) => Ok(PrincipalData::Contract(x.contract_identifier)),
+        value => Err(unexpected("principal", &value)),
+    }
+}
+
+fn decode_contract(value: Value) -> Result<QualifiedContractIdentifier, BindingError> {
+    match value {
+        Value::Principal(PrincipalData::Contract(x)) => Ok(x),
+        Value::CallableContract(x) => Ok(x.contract_identifier),
+        value => Err(unexpected("contract principal", &value)),
+    }
+}
+
+fn decode_buff(value: Value, max_len: usize) -> Result<Vec<u8>, BindingError> {
+    match value {
+        Value::Sequence(SequenceData::Buffer(BuffData { data })) if data.len() <= max_len => {
+            Ok(data)
+        }
+        value => Err(unexpected(&format!("(buff {max_len})"), &value)),
+    }
+}
+
+fn decode_ascii(value: Value, max_len: usize) -> Result<String, BindingError> {
+    match value {
+        Value::Sequence(SequenceData::String(CharType::ASCII(ASCIIData { data })))
+            if data.len() <= max_len =>
+        {
+            String::from_utf8(data).map_err(|e| BindingError::Decode(e.to_string()))
+        }
+        value => Err(unexpected(&format!("(string-ascii {max_len})"), &value)),
+    }
+}
+
+fn decode_utf8(value: Value, max_len: usize) -> Result<String, BindingError> {
+    match value {
+        Value::Sequence(SequenceData::String(CharType::UTF8(UTF8Data { data })))
+            if data.len() <= max_len =>
+        {
+            String::from_utf8(data.concat()).map_err(|e| BindingError::Decode(e.to_string()))
+        }
+        value => Err(unexpected(&format!("(string-utf8 {max_len})"), &value)),
+    }
+}
+
+fn decode_optional(value: Value) -> Result<Option<Value>, BindingError> {
+    match value {
+        Value::Optional(OptionalData { data }) => Ok(data.map(|x| *x)),
+        value => Err(unexpected("optional", &value)),
+    }
+}
+
+fn decode_response(value: Value) -> Result<Result<Value, Value>, BindingError> {
+    match value {
+        Value::Response(ResponseData { committed: true, data }) => Ok(Ok(*data)),
+        Value::Response(ResponseData { committed: false, data }) => Ok(Err(*data)),
+        value => Err(unexpected("response", &value)),
+    }
+}
+
+fn decode_list(value: Value, max_len: usize) -> Result<Vec<Value>, BindingError> {
+    match value {
+        Value::Sequence(SequenceData::List(ListData { data, .. })) if data.len() <= max_len => {
+            Ok(data)
+        }
+        value => Err(unexpected(&format!("list of at most {max_len} items"), &value)),
+    }
+}
+
+fn decode_tuple(value: Value, num_fields: usize) -> Result<TupleData, BindingError> {
+    match value {
+        Value::Tuple(tuple) if tuple.data_map.len() == num_fields => Ok(tuple),
+        value => Err(unexpected(&format!("tuple of {num_fields} fields"), &value)),
+    }
+}
+
+fn take_field(tuple: &mut TupleData, name: &str) -> Result<Value, BindingError> {
+    tuple
+        .data_map
+        .remove(name)
+        .ok_or_else(|| BindingError::Decode(format!("missing tuple field `{name}`")))
+}
+
+fn has_tag(tuple: &TupleData, field: &str, tag: &str) -> bool {
+    match tuple.data_map.get(field) {
+        Some(Value::Sequence(SequenceData::String(CharType::ASCII(ASCIIData { data })))) => {
+            data.as_slice() == tag.as_bytes()
+        }
+        _ => false,
+    }
+}
+
+fn contract_call(
+    function_name: &str,
+    function_args: Vec<Value>,
+) -> Result<TransactionPayload, BindingError> {
+    let contract_id = contract_id();
+    Ok(TransactionPayload::ContractCall(TransactionContractCall {
+        address: StacksAddress::from(contract_id.issuer),
+        contract_name: contract_id.name,
+        function_name: ClarityName::try_from(function_name.to_string())
+            .map_err(|e| BindingError::Encode(e.to_string()))?,
+        function_args,
+    }))
+}
+
+fn read_only_request(
+    sender: &PrincipalData,
+    arguments: Vec<Value>,
+) -> Result<CallReadOnlyRequestBody, BindingError> {
+    Ok(CallReadOnlyRequestBody {
+        sender: sender.to_string(),
+        sponsor: None,
+        arguments: arguments
+            .iter()
+            .map(|value| value.serialize_to_hex())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| BindingError::Encode(format!("{e:?}")))?,
+    })
+}
+
+fn read_only_path(function_name: &str) -> String {
+    let contract_id = contract_id();
+    format!(
+        "/v2/contracts/call-read/{}/{}/{function_name}",
+        contract_id.issuer, contract_id.name
+    )
+}
+
+fn read_only_result(response: &CallReadOnlyResponse) -> Result<Value, BindingError> {
+    if !response.okay {
+        return Err(BindingError::Call(
+            response.cause.clone().unwrap_or_default(),
+        ));
+    }
+    let result = response
+        .result
+        .as_deref()
+        .ok_or_else(|| BindingError::Call("no result".into()))?;
+    Value::try_deserialize_hex_untyped(result).map_err(|e| BindingError::Decode(e.to_string()))
+}
+
+pub const CONTRACT_ID: &str = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.market";
+
+pub fn contract_id() -> QualifiedContractIdentifier {
+    QualifiedContractIdentifier::parse(CONTRACT_ID).expect("valid contract identifier")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetListingOutput {
+    pub memo: Option<Vec<u8>>,
+    pub price: u128,
+    pub seller: PrincipalData,
+}
+
+impl GetListingOutput {
+    pub fn to_value(&self) -> Result<Value, BindingError> {
+        let this = self.clone();
+        encode_tuple(vec![
+            ("memo", encode_optional(this.memo.map(|x0| -> Result<Value, BindingError> { Ok(encode_buff(x0, 34)?) }).transpose()?)?),
+            ("price", Value::UInt(this.price)),
+            ("seller", Value::Principal(this.seller)),
+        ])
+    }
+
+    pub fn from_value(value: Value) -> Result<Self, BindingError> {
+        let mut tuple = decode_tuple(value, 3)?;
+        Ok(Self {
+            memo: decode_optional(take_field(&mut tuple, "memo")?)?.map(|x0| -> Result<_, BindingError> { Ok(decode_buff(x0, 34)?) }).transpose()?,
+            price: decode_uint(take_field(&mut tuple, "price")?)?,
+            seller: decode_principal(take_field(&mut tuple, "seller")?)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetStatsOutput {
+    pub fee: i128,
+    pub listed: u128,
+    pub tags: Vec<String>,
+}
+
+impl GetStatsOutput {
+    pub fn to_value(&self) -> Result<Value, BindingError> {
+        let this = self.clone();
+        encode_tuple(vec![
+            ("fee", Value::Int(this.fee)),
+            ("listed", Value::UInt(this.listed)),
+            ("tags", encode_list(this.tags.into_iter().map(|x0| -> Result<Value, BindingError> { Ok(encode_ascii(x0, 3)?) }).collect::<Result<Vec<_>, _>>()?, 2)?),
+        ])
+    }
+
+    pub fn from_value(value: Value) -> Result<Self, BindingError> {
+        let mut tuple = decode_tuple(value, 3)?;
+        Ok(Self {
+            fee: decode_int(take_field(&mut tuple, "fee")?)?,
+            listed: decode_uint(take_field(&mut tuple, "listed")?)?,
+            tags: decode_list(take_field(&mut tuple, "tags")?, 2)?.into_iter().map(|x0| -> Result<_, BindingError> { Ok(decode_ascii(x0, 3)?) }).collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedEvent {
+    pub event: String,
+    pub id: u128,
+    pub price: u128,
+    pub seller: PrincipalData,
+}
+
+impl ListedEvent {
+    pub fn to_value(&self) -> Result<Value, BindingError> {
+        let this = self.clone();
+        encode_tuple(vec![
+            ("event", encode_ascii(this.event, 6)?),
+            ("id", Value::UInt(this.id)),
+            ("price", Value::UInt(this.price)),
+            ("seller", Value::Principal(this.seller)),
+        ])
+    }
+
+    pub fn from_value(value: Value) -> Result<Self, BindingError> {
+        let mut tuple = decode_tuple(value, 4)?;
+        Ok(Self {
+            event: decode_ascii(take_field(&mut tuple, "event")?, 6)?,
+            id: decode_uint(take_field(&mut tuple, "id")?)?,
+            price: decode_uint(take_field(&mut tuple, "price")?)?,
+            seller: decode_principal(take_field(&mut tuple, "seller")?)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoldEvent {
+    pub buyer: PrincipalData,
+    pub event: String,
+    pub id: u128,
+    pub price: u128,
+}
+
+impl SoldEvent {
+    pub fn to_value(&self) -> Result<Value, BindingError> {
+        let this = self.clone();
+        encode_tuple(vec![
+            ("buyer", Value::Principal(this.buyer)),
+            ("event", encode_ascii(this.event, 4)?),
+            ("id", Value::UInt(this.id)),
+            ("price", Value::UInt(this.price)),
+        ])
+    }
+
+    pub fn from_value(value: Value) -> Result<Self, BindingError> {
+        let mut tuple = decode_tuple(value, 4)?;
+        Ok(Self {
+            buyer: decode_principal(take_field(&mut tuple, "buyer")?)?,
+            event: decode_ascii(take_field(&mut tuple, "event")?, 4)?,
+            id: decode_uint(take_field(&mut tuple, "id")?)?,
+            price: decode_uint(take_field(&mut tuple, "price")?)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventEvent {
+    pub name: String,
+    pub verified: bool,
+}
+
+impl EventEvent {
+    pub fn to_value(&self) -> Result<Value, BindingError> {
+        let this = self.clone();
+        encode_tuple(vec![
+            ("name", encode_ascii(this.name, 32)?),
+            ("verified", Value::Bool(this.verified)),
+        ])
+    }
+
+    pub fn from_value(value: Value) -> Result<Self, BindingError> {
+        let mut tuple = decode_tuple(value, 2)?;
+        Ok(Self {
+            name: decode_ascii(take_field(&mut tuple, "name")?, 32)?,
+            verified: decode_bool(take_field(&mut tuple, "verified")?)?,
+        })
+    }
+}
+
+/// Build the payload of a contract-call to `buy`
+pub fn buy_payload(id: u128, pricer: QualifiedContractIdentifier) -> Result<TransactionPayload, BindingError> {
+    contract_call("buy", vec![Value::UInt(id), Value::Principal(PrincipalData::Contract(pricer))])
+}
+
+/// Decode the result of a contract-call to `buy`
+pub fn decode_buy_result(value: Value) -> Result<Result<u128, u128>, BindingError> {
+    Ok(match decode_response(value)? { Ok(x0) => Ok(decode_uint(x0)?), Err(x0) => Err(decode_uint(x0)?) })
+}
+
+/// Build the payload of a contract-call to `list-item`
+pub fn list_item_payload(price: u128, memo: Option<Vec<u8>>) -> Result<TransactionPayload, BindingError> {
+    contract_call("list-item", vec![Value::UInt(price), encode_optional(memo.map(|x0| -> Result<Value, BindingError> { Ok(encode_buff(x0, 34)?) }).transpose()?)?])
+}
+
+/// Decode the result of a contract-call to `list-item`
+pub fn decode_list_item_result(value: Value) -> Result<Result<u128, ()>, BindingError> {
+    Ok(match decode_response(value)? { Ok(x0) => Ok(decode_uint(x0)?), Err(x0) => Err(decode_none(x0)?) })
+}
+
+/// Build the payload of a contract-call to `set-profile`
+pub fn set_profile_payload(name: String, bio: String, scores: Vec<i128>, verified: bool) -> Result<TransactionPayload, BindingError> {
+    contract_call("set-profile", vec![encode_ascii(name, 32)?, encode_utf8(bio, 64)?, encode_list(scores.into_iter().map(|x0| -> Result<Value, BindingError> { Ok(Value::Int(x0)) }).collect::<Result<Vec<_>, _>>()?, 5)?, Value::Bool(verified)])
+}
+
+/// Decode the result of a contract-call to `set-profile`
+pub fn decode_set_profile_result(value: Value) -> Result<Result<Option<()>, ()>, BindingError> {
+    Ok(match decode_response(value)? { Ok(x0) => Ok(decode_optional(x0)?.map(|x1| -> Result<_, BindingError> { Ok(decode_none(x1)?) }).transpose()?), Err(x0) => Err(decode_none(x0)?) })
+}
+
+/// The RPC path of a read-only call to `get-listing`
+pub fn get_listing_path() -> String {
+    read_only_path("get-listing")
+}
+
+/// Build the body of a read-only call to `get-listing`
+pub fn get_listing_request(sender: &PrincipalData, id: u128) -> Result<CallReadOnlyRequestBody, BindingError> {
+    read_only_request(sender, vec![Value::UInt(id)])
+}
+
+/// Decode the response to a read-only call to `get-listing`
+pub fn decode_get_listing_response(response: &CallReadOnlyResponse) -> Result<Option<GetListingOutput>, BindingError> {
+    let value = read_only_result(response)?;
+    Ok(decode_optional(value)?.map(|x0| -> Result<_, BindingError> { Ok(GetListingOutput::from_value(x0)?) }).transpose()?)
+}
+
+/// The RPC path of a read-only call to `get-stats`
+pub fn get_stats_path() -> String {
+    read_only_path("get-stats")
+}
+
+/// Build the body of a read-only call to `get-stats`
+pub fn get_stats_request(sender: &PrincipalData) -> Result<CallReadOnlyRequestBody, BindingError> {
+    read_only_request(sender, vec![])
+}
+
+/// Decode the response to a read-only call to `get-stats`
+pub fn decode_get_stats_response(response: &CallReadOnlyResponse) -> Result<Result<GetStatsOutput, ()>, BindingError> {
+    let value = read_only_result(response)?;
+    Ok(match decode_response(value)? { Ok(x0) => Ok(GetStatsOutput::from_value(x0)?), Err(x0) => Err(decode_none(x0)?) })
+}
+
+/// The contract's `print` events
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrintEvent {
+    Listed(ListedEvent),
+    Sold(SoldEvent),
+    Event(EventEvent),
+}
+
+/// Decode a value printed by the contract.  Returns `None` if it is not one of its events.
+pub fn decode_print_event(value: Value) -> Result<Option<PrintEvent>, BindingError> {
+    let Value::Tuple(ref tuple) = value else {
+        return Ok(None);
+    };
+    if has_tag(tuple, "event", "listed") {
+        return Ok(Some(PrintEvent::Listed(ListedEvent::from_value(value)?)));
+    }
+    if has_tag(tuple, "event", "sold") {
+        return Ok(Some(PrintEvent::Sold(SoldEvent::from_value(value)?)));
+    }
+    if let Ok(event) = EventEvent::from_value(value.clone()) {
+        return Ok(Some(PrintEvent::Event(event)));
+    }
+    Ok(None)
+}
+
+/// Decode the hex-encoded `raw_value` of a contract event
+pub fn decode_print_event_hex(raw_value: &str) -> Result<Option<PrintEvent>, BindingError> {
+    decode_print_event(
+        Value::try_deserialize_hex_untyped(raw_value)
+            .map_err(|e| BindingError::Decode(e.to_string()))?,
+    )
+}
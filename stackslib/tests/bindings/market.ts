@@ -0,0 +1,335 @@
+// Client bindings for the `ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.market` contract.
+//
+// Generated by `clarity-cli generate_bindings`. Do not edit.
+
+import {
+  Cl,
+  ClarityType,
+  ClarityValue,
+  ContractCallPayload,
+  createContractCallPayload,
+  cvToHex,
+  hexToCV,
+} from '@stacks/transactions';
+
+export type Response<T, E> = { ok: true; value: T } | { ok: false; error: E };
+
+function unexpected(expected: string, cv: ClarityValue): Error {
+  return new Error(`expected ${expected}, got ${cv.type}`);
+}
+
+function hexToBytes(hex: string): Uint8Array {
+  const digits = hex.startsWith('0x') ? hex.slice(2) : hex;
+  const bytes = new Uint8Array(digits.length / 2);
+  for (let i = 0; i < bytes.length; i++) {
+    bytes[i] = parseInt(digits.slice(2 * i, 2 * i + 2), 16);
+  }
+  return bytes;
+}
+
+function encodeOptional<T>(value: T | null, encode: (x: T) => ClarityValue): ClarityValue {
+  return value === null ? Cl.none() : Cl.some(encode(value));
+}
+
+function encodeResponse<T, E>(
+  value: Response<T, E>,
+  encodeOk: (x: T) => ClarityValue,
+  encodeErr: (x: E) => ClarityValue,
+): ClarityValue {
+  return value.ok ? Cl.ok(encodeOk(value.value)) : Cl.error(encodeErr(value.error));
+}
+
+function decodeNone(cv: ClarityValue): null {
+  if (cv.type !== ClarityType.OptionalNone) throw unexpected('none', cv);
+  return null;
+}
+
+function decodeInt(cv: ClarityValue): bigint {
+  if (cv.type !== ClarityType.Int) throw unexpected('int', cv);
+  return BigInt(cv.value);
+}
+
+function decodeUint(cv: ClarityValue): bigint {
+  if (cv.type !== ClarityType.UInt) throw unexpected('uint', cv);
+  return BigInt(cv.value);
+}
+
+function decodeBool(cv: ClarityValue): boolean {
+  if (cv.type === ClarityType.BoolTrue) return true;
+  if (cv.type === ClarityType.BoolFalse) return false;
+  throw unexpected('bool', cv);
+}
+
+function decodePrincipal(cv: ClarityValue): string {
+  if (cv.type !== ClarityType.PrincipalStandard && cv.type !== ClarityType.PrincipalContract) {
+    throw unexpected('principal', cv);
+  }
+  return cv.value;
+}
+
+function decodeContract(cv: ClarityValue): string {
+  if (cv.type !== ClarityType.PrincipalContract) throw unexpected('contract principal', cv);
+  return cv.value;
+}
+
+function decodeBuffer(cv: ClarityValue): Uint8Array {
+  if (cv.type !== ClarityType.Buffer) throw unexpected('buffer', cv);
+  return hexToBytes(cv.value);
+}
+
+function decodeAscii(cv: ClarityValue): string {
+  if (cv.type !== ClarityType.StringASCII) throw unexpected('string-ascii', cv);
+  return cv.value;
+}
+
+function decodeUtf8(cv: ClarityValue): string {
+  if (cv.type !== ClarityType.StringUTF8) throw unexpected('string-utf8', cv);
+  return cv.value;
+}
+
+function decodeOptional<T>(cv: ClarityValue, decode: (x: ClarityValue) => T): T | null {
+  if (cv.type === ClarityType.OptionalNone) return null;
+  if (cv.type === ClarityType.OptionalSome) return decode(cv.value);
+  throw unexpected('optional', cv);
+}
+
+function decodeResponse<T, E>(
+  cv: ClarityValue,
+  decodeOk: (x: ClarityValue) => T,
+  decodeErr: (x: ClarityValue) => E,
+): Response<T, E> {
+  if (cv.type === ClarityType.ResponseOk) return { ok: true, value: decodeOk(cv.value) };
+  if (cv.type === ClarityType.ResponseErr) return { ok: false, error: decodeErr(cv.value) };
+  throw unexpected('response', cv);
+}
+
+function decodeList<T>(cv: ClarityValue, decode: (x: ClarityValue) => T): T[] {
+  if (cv.type !== ClarityType.List) throw unexpected('list', cv);
+  return cv.value.map(decode);
+}
+
+function decodeTuple(cv: ClarityValue, numFields: number): { [key: string]: ClarityValue } {
+  if (cv.type !== ClarityType.Tuple || Object.keys(cv.value).length !== numFields) {
+    throw unexpected(`tuple of ${numFields} fields`, cv);
+  }
+  return cv.value;
+}
+
+function field(tuple: { [key: string]: ClarityValue }, name: string): ClarityValue {
+  const value = tuple[name];
+  if (value === undefined) throw new Error(`missing tuple field \`${name}\``);
+  return value;
+}
+
+function hasTag(cv: ClarityValue, name: string, tag: string): boolean {
+  if (cv.type !== ClarityType.Tuple) return false;
+  const value = cv.value[name];
+  return value !== undefined && value.type === ClarityType.StringASCII && value.value === tag;
+}
+
+async function callReadOnly(
+  node: string,
+  functionName: string,
+  sender: string,
+  args: ClarityValue[],
+): Promise<ClarityValue> {
+  const response = await fetch(
+    `${node}/v2/contracts/call-read/${CONTRACT_ADDRESS}/${CONTRACT_NAME}/${functionName}`,
+    {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ sender, arguments: args.map((arg) => cvToHex(arg)) }),
+    },
+  );
+  const body = await response.json();
+  if (!body.okay) throw new Error(`read-only call failed: ${body.cause}`);
+  return hexToCV(body.result);
+}
+
+export const CONTRACT_ADDRESS = 'ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM';
+export const CONTRACT_NAME = 'market';
+
+export interface GetListingOutput {
+  memo: Uint8Array | null;
+  price: bigint;
+  seller: string;
+}
+
+export function encodeGetListingOutput(value: GetListingOutput): ClarityValue {
+  return Cl.tuple({
+    'memo': encodeOptional(value.memo, (x0) => Cl.buffer(x0)),
+    'price': Cl.uint(value.price),
+    'seller': Cl.principal(value.seller),
+  });
+}
+
+export function decodeGetListingOutput(cv: ClarityValue): GetListingOutput {
+  const tuple = decodeTuple(cv, 3);
+  return {
+    memo: decodeOptional(field(tuple, 'memo'), (x0) => decodeBuffer(x0)),
+    price: decodeUint(field(tuple, 'price')),
+    seller: decodePrincipal(field(tuple, 'seller')),
+  };
+}
+
+export interface GetStatsOutput {
+  fee: bigint;
+  listed: bigint;
+  tags: string[];
+}
+
+export function encodeGetStatsOutput(value: GetStatsOutput): ClarityValue {
+  return Cl.tuple({
+    'fee': Cl.int(value.fee),
+    'listed': Cl.uint(value.listed),
+    'tags': Cl.list(value.tags.map((x0) => Cl.stringAscii(x0))),
+  });
+}
+
+export function decodeGetStatsOutput(cv: ClarityValue): GetStatsOutput {
+  const tuple = decodeTuple(cv, 3);
+  return {
+    fee: decodeInt(field(tuple, 'fee')),
+    listed: decodeUint(field(tuple, 'listed')),
+    tags: decodeList(field(tuple, 'tags'), (x0) => decodeAscii(x0)),
+  };
+}
+
+export interface ListedEvent {
+  event: string;
+  id: bigint;
+  price: bigint;
+  seller: string;
+}
+
+export function encodeListedEvent(value: ListedEvent): ClarityValue {
+  return Cl.tuple({
+    'event': Cl.stringAscii(value.event),
+    'id': Cl.uint(value.id),
+    'price': Cl.uint(value.price),
+    'seller': Cl.principal(value.seller),
+  });
+}
+
+export function decodeListedEvent(cv: ClarityValue): ListedEvent {
+  const tuple = decodeTuple(cv, 4);
+  return {
+    event: decodeAscii(field(tuple, 'event')),
+    id: decodeUint(field(tuple, 'id')),
+    price: decodeUint(field(tuple, 'price')),
+    seller: decodePrincipal(field(tuple, 'seller')),
+  };
+}
+
+export interface SoldEvent {
+  buyer: string;
+  event: string;
+  id: bigint;
+  price: bigint;
+}
+
+export function encodeSoldEvent(value: SoldEvent): ClarityValue {
+  return Cl.tuple({
+    'buyer': Cl.principal(value.buyer),
+    'event': Cl.stringAscii(value.event),
+    'id': Cl.uint(value.id),
+    'price': Cl.uint(value.price),
+  });
+}
+
+export function decodeSoldEvent(cv: ClarityValue): SoldEvent {
+  const tuple = decodeTuple(cv, 4);
+  return {
+    buyer: decodePrincipal(field(tuple, 'buyer')),
+    event: decodeAscii(field(tuple, 'event')),
+    id: decodeUint(field(tuple, 'id')),
+    price: decodeUint(field(tuple, 'price')),
+  };
+}
+
+export interface EventEvent {
+  name: string;
+  verified: boolean;
+}
+
+export function encodeEventEvent(value: EventEvent): ClarityValue {
+  return Cl.tuple({
+    'name': Cl.stringAscii(value.name),
+    'verified': Cl.bool(value.verified),
+  });
+}
+
+export function decodeEventEvent(cv: ClarityValue): EventEvent {
+  const tuple = decodeTuple(cv, 2);
+  return {
+    name: decodeAscii(field(tuple, 'name')),
+    verified: decodeBool(field(tuple, 'verified')),
+  };
+}
+
+/** Build the payload of a contract-call to `buy` */
+export function buyPayload(id: bigint, pricer: string): ContractCallPayload {
+  return createContractCallPayload(CONTRACT_ADDRESS, CONTRACT_NAME, 'buy', [Cl.uint(id), Cl.principal(pricer)]);
+}
+
+/** Decode the result of a contract-call to `buy` */
+export function decodeBuyResult(cv: ClarityValue): Response<bigint, bigint> {
+  return decodeResponse(cv, (x0) => decodeUint(x0), (x0) => decodeUint(x0));
+}
+
+/** Build the payload of a contract-call to `list-item` */
+export function listItemPayload(price: bigint, memo: Uint8Array | null): ContractCallPayload {
+  return createContractCallPayload(CONTRACT_ADDRESS, CONTRACT_NAME, 'list-item', [Cl.uint(price), encodeOptional(memo, (x0) => Cl.buffer(x0))]);
+}
+
+/** Decode the result of a contract-call to `list-item` */
+export function decodeListItemResult(cv: ClarityValue): Response<bigint, null> {
+  return decodeResponse(cv, (x0) => decodeUint(x0), (x0) => decodeNone(x0));
+}
+
+/** Build the payload of a contract-call to `set-profile` */
+export function setProfilePayload(name: string, bio: string, scores: bigint[], verified: boolean): ContractCallPayload {
+  return createContractCallPayload(CONTRACT_ADDRESS, CONTRACT_NAME, 'set-profile', [Cl.stringAscii(name), Cl.stringUtf8(bio), Cl.list(scores.map((x0) => Cl.int(x0))), Cl.bool(verified)]);
+}
+
+/** Decode the result of a contract-call to `set-profile` */
+export function decodeSetProfileResult(cv: ClarityValue): Response<null | null, null> {
+  return decodeResponse(cv, (x0) => decodeOptional(x0, (x1) => decodeNone(x1)), (x0) => decodeNone(x0));
+}
+
+/** Call the read-only function `get-listing` through the node at `node` */
+export async function getListing(node: string, sender: string, id: bigint): Promise<GetListingOutput | null> {
+  const cv = await callReadOnly(node, 'get-listing', sender, [Cl.uint(id)]);
+  return decodeOptional(cv, (x0) => decodeGetListingOutput(x0));
+}
+
+/** Call the read-only function `get-stats` through the node at `node` */
+export async function getStats(node: string, sender: string): Promise<Response<GetStatsOutput, null>> {
+  const cv = await callReadOnly(node, 'get-stats', sender, []);
+  return decodeResponse(cv, (x0) => decodeGetStatsOutput(x0), (x0) => decodeNone(x0));
+}
+
+/** The contract's `print` events */
+export type PrintEvent =
+  | { event: 'Listed'; data: ListedEvent }
+  | { event: 'Sold'; data: SoldEvent }
+  | { event: 'Event'; data: EventEvent };
+
+/** Decode a value printed by the contract.  Returns `null` if it is not one of its events. */
+export function decodePrintEvent(cv: ClarityValue): PrintEvent | null {
+  if (hasTag(cv, 'event', 'listed')) {
+    return { event: 'Listed', data: decodeListedEvent(cv) };
+  }
+  if (hasTag(cv, 'event', 'sold')) {
+    return { event: 'Sold', data: decodeSoldEvent(cv) };
+  }
+  try {
+    return { event: 'Event', data: decodeEventEvent(cv) };
+  } catch {}
+  return null;
+}
+
+/** Decode the hex-encoded `raw_value` of a contract event */
+export function decodePrintEventHex(rawValue: string): PrintEvent | null {
+  return decodePrintEvent(hexToCV(rawValue));
+}
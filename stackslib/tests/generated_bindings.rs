@@ -0,0 +1,176 @@
+// Copyright (C) 2026 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checks the client bindings that `clarity-cli generate_bindings` generates for the fixture
+//! contract in `bindings/market.clar`.  The checked-in Rust bindings are compiled as the
+//! `market` module, and both they and the TypeScript bindings must be exactly what the
+//! generator produces.  To update the fixtures after changing the generator, run
+//!
+//!   clarity-cli generate_bindings --rust tests/bindings/market.rs \
+//!     --typescript tests/bindings/market.ts \
+//!     ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.market tests/bindings/market.clar
+
+#[rustfmt::skip]
+#[path = "bindings/market.rs"]
+mod market;
+
+use std::fs;
+use std::path::PathBuf;
+
+use blockstack_lib::chainstate::stacks::TransactionPayload;
+use blockstack_lib::clarity_cli::invoke_command;
+use blockstack_lib::net::api::callreadonly::CallReadOnlyResponse;
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, Value};
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("bindings")
+        .join(name)
+}
+
+#[test]
+fn generated_bindings_match_fixtures() {
+    let (exit, result) = invoke_command(
+        "test",
+        &[
+            "generate_bindings".to_string(),
+            market::CONTRACT_ID.to_string(),
+            fixture_path("market.clar").display().to_string(),
+        ],
+    );
+    assert_eq!(exit, 0);
+    let result = result.unwrap();
+    assert_eq!(result["print_events"], 3);
+    assert_eq!(
+        result["rust"].as_str().unwrap(),
+        fs::read_to_string(fixture_path("market.rs")).unwrap()
+    );
+    assert_eq!(
+        result["typescript"].as_str().unwrap(),
+        fs::read_to_string(fixture_path("market.ts")).unwrap()
+    );
+}
+
+#[test]
+fn generated_rust_bindings_encode_and_decode() {
+    let contract_id =
+        QualifiedContractIdentifier::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.market")
+            .unwrap();
+    let sender = PrincipalData::parse("ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG").unwrap();
+    assert_eq!(market::contract_id(), contract_id);
+
+    // public functions
+    let TransactionPayload::ContractCall(call) =
+        market::list_item_payload(100, Some(vec![1, 2])).unwrap()
+    else {
+        panic!("not a contract call");
+    };
+    assert_eq!(call.contract_identifier(), contract_id);
+    assert_eq!(call.function_name.as_str(), "list-item");
+    assert_eq!(
+        call.function_args,
+        vec![
+            Value::UInt(100),
+            Value::some(Value::buff_from(vec![1, 2]).unwrap()).unwrap()
+        ]
+    );
+    assert!(matches!(
+        market::list_item_payload(100, Some(vec![0; 35])),
+        Err(market::BindingError::Encode(_))
+    ));
+    let TransactionPayload::ContractCall(call) =
+        market::buy_payload(1, contract_id.clone()).unwrap()
+    else {
+        panic!("not a contract call");
+    };
+    assert_eq!(
+        call.function_args[1],
+        Value::Principal(PrincipalData::Contract(contract_id.clone()))
+    );
+    assert_eq!(
+        market::decode_buy_result(Value::error(Value::UInt(404)).unwrap()).unwrap(),
+        Err(404)
+    );
+    assert!(matches!(
+        market::decode_buy_result(Value::UInt(404)),
+        Err(market::BindingError::Decode(_))
+    ));
+
+    // read-only functions
+    assert_eq!(
+        market::get_stats_path(),
+        "/v2/contracts/call-read/ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM/market/get-stats"
+    );
+    let request = market::get_listing_request(&sender, 7).unwrap();
+    assert_eq!(request.sender, sender.to_string());
+    assert_eq!(
+        request.arguments,
+        vec![Value::UInt(7).serialize_to_hex().unwrap()]
+    );
+    let stats = market::GetStatsOutput {
+        fee: -1,
+        listed: 2,
+        tags: vec!["new".into(), "hot".into()],
+    };
+    let response = CallReadOnlyResponse {
+        okay: true,
+        result: Some(format!(
+            "0x{}",
+            Value::okay(stats.to_value().unwrap())
+                .unwrap()
+                .serialize_to_hex()
+                .unwrap()
+        )),
+        cause: None,
+    };
+    assert_eq!(
+        market::decode_get_stats_response(&response).unwrap(),
+        Ok(stats)
+    );
+    let response = CallReadOnlyResponse {
+        okay: false,
+        result: None,
+        cause: Some("Unchecked(NoSuchContract)".into()),
+    };
+    assert_eq!(
+        market::decode_get_stats_response(&response),
+        Err(market::BindingError::Call(
+            "Unchecked(NoSuchContract)".into()
+        ))
+    );
+
+    // print events
+    let listed = market::ListedEvent {
+        event: "listed".into(),
+        id: 0,
+        price: 100,
+        seller: sender.clone(),
+    };
+    assert_eq!(
+        market::decode_print_event(listed.to_value().unwrap()).unwrap(),
+        Some(market::PrintEvent::Listed(listed))
+    );
+    let profile = market::EventEvent {
+        name: "alice".into(),
+        verified: true,
+    };
+    assert_eq!(
+        market::decode_print_event_hex(&profile.to_value().unwrap().serialize_to_hex().unwrap())
+            .unwrap(),
+        Some(market::PrintEvent::Event(profile))
+    );
+    assert_eq!(market::decode_print_event(Value::UInt(1)).unwrap(), None);
+}
@@ -57,7 +57,7 @@ use stacks::config::{
 use stacks::core::{EpochList, StacksEpochId};
 use stacks::monitoring::{increment_btc_blocks_received_counter, increment_btc_ops_sent_counter};
 use stacks::net::http::{HttpRequestContents, HttpResponsePayload};
-use stacks::net::httpcore::{send_http_request, StacksHttpRequest};
+use stacks::net::httpcore::{send_http_request_with_proxy, StacksHttpRequest};
 use stacks::net::Error as NetError;
 use stacks_common::codec::StacksMessageCodec;
 use stacks_common::deps_common::bitcoin::blockdata::opcodes;
@@ -181,6 +181,7 @@ pub fn make_bitcoin_indexer(
             first_block: burnchain_params.first_block_height,
             magic_bytes: burnchain_config.magic_bytes,
             epochs: burnchain_config.epochs,
+            socks5_proxy: burnchain_config.socks5_proxy,
         }
     };
 
@@ -342,6 +343,7 @@ impl BitcoinRegtestController {
                 first_block: burnchain_params.first_block_height,
                 magic_bytes: burnchain_config.magic_bytes,
                 epochs: burnchain_config.epochs,
+                socks5_proxy: burnchain_config.socks5_proxy,
             }
         };
 
@@ -388,6 +390,7 @@ impl BitcoinRegtestController {
                 first_block: burnchain_params.first_block_height,
                 magic_bytes: burnchain_config.magic_bytes,
                 epochs: burnchain_config.epochs,
+                socks5_proxy: burnchain_config.socks5_proxy,
             }
         };
 
@@ -2829,7 +2832,13 @@ impl BitcoinRPCRequest {
         let host = request.preamble().host.hostname();
         let port = request.preamble().host.port();
 
-        let response = send_http_request(&host, port, request, timeout)?;
+        let response = send_http_request_with_proxy(
+            &host,
+            port,
+            config.burnchain.socks5_proxy.as_ref(),
+            request,
+            timeout,
+        )?;
         if let HttpResponsePayload::JSON(js) = response.destruct().1 {
             Ok(js)
         } else {
@@ -75,6 +75,8 @@ use stacks_common::util::hash::{bytes_to_hex, Sha512Trunc256Sum};
 use stacks_common::util::secp256k1::MessageSignature;
 use url::Url;
 
+use crate::event_stream::{start_event_stream_server, EventStream};
+
 #[cfg(any(test, feature = "testing"))]
 lazy_static! {
     /// Do not announce a signed/mined block to the network when set to true.
@@ -93,6 +95,8 @@ pub struct EventObserver {
     /// If true, the stacks-node will not retry if event delivery fails for any reason.
     /// WARNING: This should not be set on observers that require successful delivery of all events.
     pub disable_retries: bool,
+    /// If set, payloads are appended to this event stream instead of being POSTed to `endpoint`
+    pub stream: Option<Arc<EventStream>>,
 }
 
 struct ReceiptPayloadInfo<'a> {
@@ -560,12 +564,18 @@ impl EventObserver {
             endpoint,
             timeout,
             disable_retries,
+            stream: None,
         }
     }
 
     /// Send the payload to the given URL.
     /// Before sending this payload, any pending payloads in the database will be sent first.
     pub fn send_payload(&self, payload: &serde_json::Value, path: &str) {
+        if let Some(stream) = &self.stream {
+            stream.publish(path, payload);
+            return;
+        }
+
         // Construct the full URL
         let url_str = if path.starts_with('/') {
             format!("{}{path}", &self.endpoint)
@@ -1683,9 +1693,20 @@ impl EventDispatcher {
     }
 
     pub fn register_observer(&mut self, conf: &EventObserverConfig, working_dir: PathBuf) {
+        self.register_observer_with_db(conf, Some(working_dir))
+    }
+
+    /// Register an observer.  If `working_dir` is `None`, then undelivered payloads are not
+    /// stored, and are instead retried until they are delivered (or dropped, if `disable_retries`
+    /// is set).
+    pub fn register_observer_with_db(
+        &mut self,
+        conf: &EventObserverConfig,
+        working_dir: Option<PathBuf>,
+    ) {
         info!("Registering event observer at: {}", conf.endpoint);
         let event_observer = EventObserver::new(
-            Some(working_dir),
+            working_dir,
             conf.endpoint.clone(),
             Duration::from_millis(conf.timeout_ms),
             conf.disable_retries,
@@ -1759,6 +1780,51 @@ impl EventDispatcher {
 
         self.registered_observers.push(event_observer);
     }
+
+    /// Serve all events to streaming clients on `bind`.  The stream is registered as an
+    /// observer of every event type; clients apply their own filters.
+    pub fn register_event_stream(&mut self, bind: &str, retention: u64, working_dir: PathBuf) {
+        info!("Registering event stream at: {bind}");
+        let event_stream = Arc::new(
+            EventStream::new(&working_dir, retention)
+                .expect("FATAL: failed to initialize database for event stream"),
+        );
+        start_event_stream_server(event_stream.clone(), bind)
+            .unwrap_or_else(|e| panic!("FATAL: failed to serve event stream on {bind}: {e:?}"));
+        self.attach_event_stream(bind, event_stream, working_dir);
+    }
+
+    /// Publish all events to an event stream that is already being served on `bind`, such as
+    /// the one registered by the epoch 2.x run loop before the epoch 3.0 transition.
+    pub fn attach_event_stream(
+        &mut self,
+        bind: &str,
+        event_stream: Arc<EventStream>,
+        working_dir: PathBuf,
+    ) {
+        let conf = EventObserverConfig {
+            endpoint: bind.to_string(),
+            events_keys: vec![
+                EventKeyType::AnyEvent,
+                EventKeyType::MinedBlocks,
+                EventKeyType::MinedMicroblocks,
+                EventKeyType::StackerDBChunks,
+                EventKeyType::BlockProposal,
+            ],
+            timeout_ms: 0,
+            disable_retries: false,
+        };
+        let observer_index = self.registered_observers.len();
+        self.register_observer(&conf, working_dir);
+        self.registered_observers[observer_index].stream = Some(event_stream);
+    }
+
+    /// The event stream this dispatcher publishes to, if there is one
+    pub fn get_event_stream(&self) -> Option<Arc<EventStream>> {
+        self.registered_observers
+            .iter()
+            .find_map(|observer| observer.stream.clone())
+    }
 }
 
 #[cfg(any(test, feature = "testing"))]
@@ -2526,4 +2592,32 @@ mod test {
 
         assert_eq!(event_dispatcher.registered_observers.len(), 1);
     }
+
+    #[test]
+    /// The epoch 3.0 run loop takes over the epoch 2.x run loop's event stream instead of
+    /// binding its own listener on the same address.
+    fn test_attach_event_stream() {
+        let dir = tempdir().unwrap();
+        let working_dir = dir.path().to_path_buf();
+        let bind = format!("127.0.0.1:{}", get_random_port());
+
+        let mut neon_dispatcher = EventDispatcher::new();
+        assert!(neon_dispatcher.get_event_stream().is_none());
+        neon_dispatcher.register_event_stream(&bind, 10, working_dir.clone());
+        let event_stream = neon_dispatcher.get_event_stream().unwrap();
+
+        // registering it again would fail to bind, but attaching does not bind at all
+        assert!(TcpListener::bind(&bind).is_err());
+        let mut naka_dispatcher = EventDispatcher::new();
+        naka_dispatcher.attach_event_stream(&bind, event_stream.clone(), working_dir);
+        assert!(Arc::ptr_eq(
+            &naka_dispatcher.get_event_stream().unwrap(),
+            &event_stream
+        ));
+        assert_eq!(naka_dispatcher.registered_observers.len(), 1);
+        assert_eq!(
+            naka_dispatcher.registered_observers[0].endpoint,
+            neon_dispatcher.registered_observers[0].endpoint
+        );
+    }
 }
@@ -0,0 +1,749 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Streaming event observer transport.
+//!
+//! Instead of POSTing each payload to a remote observer, the node can serve the same payloads
+//! to any number of local clients as server-sent events.  Every payload is appended to a
+//! SQLite-backed log and assigned a monotonically increasing sequence number, which is sent
+//! as the SSE event `id`.  A client that reconnects with the last sequence number it saw
+//! (via the `Last-Event-ID` header or the `after` query parameter) resumes exactly where it
+//! left off, as long as that payload is still retained.
+//!
+//! Publishing a payload never blocks on a client, so a slow consumer cannot stall block
+//! processing.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use clarity::vm::types::QualifiedContractIdentifier;
+use rusqlite::{params, Connection, OptionalExtension};
+use stacks::config::EventKeyType;
+use stacks::util_lib::db::Error as db_error;
+use url::Url;
+
+use crate::event_dispatcher::{
+    PATH_BLOCK_PROCESSED, PATH_BURN_BLOCK_SUBMIT, PATH_MEMPOOL_TX_DROP, PATH_MEMPOOL_TX_SUBMIT,
+    PATH_MICROBLOCK_SUBMIT, PATH_MINED_BLOCK, PATH_MINED_MICROBLOCK, PATH_MINED_NAKAMOTO_BLOCK,
+    PATH_PROPOSAL_RESPONSE, PATH_STACKERDB_CHUNKS,
+};
+
+/// HTTP path on which the event stream is served
+pub const EVENT_STREAM_PATH: &str = "/v1/events";
+/// Maximum number of concurrently-connected stream clients
+const MAX_CLIENTS: usize = 64;
+/// Maximum size of a client's HTTP request preamble
+const MAX_REQUEST_LEN: usize = 8192;
+/// Number of payloads to load from the log at once
+const PAYLOAD_BATCH_SIZE: u32 = 64;
+/// How long a client waits for a new payload before sending a keep-alive
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+/// How long a client's socket may block on a read or write before the client is dropped.
+/// Each client holds a thread and a slot, so a stalled one must not hold them forever.
+const CLIENT_IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A payload stored in the event stream log
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamPayload {
+    pub seq: u64,
+    /// Event observer path this payload would have been POSTed to (e.g. `new_block`)
+    pub path: String,
+    pub payload: serde_json::Value,
+}
+
+/// The publisher's side of the payload log
+#[derive(Debug)]
+struct PublishState {
+    /// Connection used to append payloads, opened once for the life of the stream
+    conn: Connection,
+    /// Sequence number of the most recently published payload
+    latest_seq: u64,
+}
+
+/// The event stream's payload log
+#[derive(Debug)]
+pub struct EventStream {
+    /// Path to the database which holds the retained payloads
+    db_path: PathBuf,
+    /// Number of most-recent payloads to retain
+    retention: u64,
+    state: Mutex<PublishState>,
+    /// Signaled whenever a payload is published
+    new_payload: Condvar,
+}
+
+impl EventStream {
+    fn init_db(db_path: &Path) -> Result<Connection, db_error> {
+        let conn = Connection::open(db_path)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS stream_payloads (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT NOT NULL,
+                payload TEXT NOT NULL
+            )",
+            [],
+        )?;
+        Ok(conn)
+    }
+
+    /// Open (or create) the payload log in `working_dir`
+    pub fn new(working_dir: &Path, retention: u64) -> Result<Self, db_error> {
+        let db_path = working_dir.join("event_stream.sqlite");
+        let conn = Self::init_db(&db_path)?;
+        let latest_seq = Self::get_latest_seq(&conn)?;
+        Ok(EventStream {
+            db_path,
+            retention: retention.max(1),
+            state: Mutex::new(PublishState { conn, latest_seq }),
+            new_payload: Condvar::new(),
+        })
+    }
+
+    fn connect(&self) -> Result<Connection, db_error> {
+        Ok(Connection::open(&self.db_path)?)
+    }
+
+    /// Sequence number of the most recently published payload, or 0 if there are none.
+    /// Sequence numbers are never reused, even once their payloads are pruned.
+    fn get_latest_seq(conn: &Connection) -> Result<u64, db_error> {
+        let seq: Option<i64> = conn
+            .query_row(
+                "SELECT seq FROM sqlite_sequence WHERE name = 'stream_payloads'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(seq.map(|seq| u64::try_from(seq).unwrap_or(0)).unwrap_or(0))
+    }
+
+    /// Sequence number of the oldest retained payload, if there are any
+    fn get_oldest_seq(conn: &Connection) -> Result<Option<u64>, db_error> {
+        let seq: Option<i64> =
+            conn.query_row("SELECT MIN(seq) FROM stream_payloads", [], |row| row.get(0))?;
+        Ok(seq.map(|seq| u64::try_from(seq).unwrap_or(0)))
+    }
+
+    fn insert_payload(
+        conn: &Connection,
+        path: &str,
+        payload: &serde_json::Value,
+        retention: u64,
+    ) -> Result<u64, db_error> {
+        conn.execute(
+            "INSERT INTO stream_payloads (path, payload) VALUES (?1, ?2)",
+            params![path, payload.to_string()],
+        )?;
+        let seq = u64::try_from(conn.last_insert_rowid()).map_err(|_| db_error::Overflow)?;
+        conn.execute(
+            "DELETE FROM stream_payloads WHERE seq <= ?1",
+            params![i64::try_from(seq.saturating_sub(retention)).map_err(|_| db_error::Overflow)?],
+        )?;
+        Ok(seq)
+    }
+
+    /// Load up to `limit` payloads with sequence numbers greater than `after`, in order
+    fn get_payloads_after(
+        conn: &Connection,
+        after: u64,
+        limit: u32,
+    ) -> Result<Vec<StreamPayload>, db_error> {
+        let after = i64::try_from(after).map_err(|_| db_error::Overflow)?;
+        let mut stmt = conn.prepare(
+            "SELECT seq, path, payload FROM stream_payloads WHERE seq > ?1 ORDER BY seq LIMIT ?2",
+        )?;
+        let payload_iter = stmt.query_and_then(
+            params![after, limit],
+            |row| -> Result<StreamPayload, db_error> {
+                let seq: i64 = row.get(0)?;
+                let path: String = row.get(1)?;
+                let payload_text: String = row.get(2)?;
+                let payload: serde_json::Value =
+                    serde_json::from_str(&payload_text).map_err(db_error::SerializationError)?;
+                Ok(StreamPayload {
+                    seq: u64::try_from(seq).map_err(|_| db_error::Overflow)?,
+                    path,
+                    payload,
+                })
+            },
+        )?;
+        payload_iter.collect()
+    }
+
+    /// Append a payload to the log and wake up all waiting clients
+    pub fn publish(&self, path: &str, payload: &serde_json::Value) {
+        let path = path.trim_start_matches('/');
+        let mut state = self
+            .state
+            .lock()
+            .expect("FATAL: event stream lock poisoned");
+        match Self::insert_payload(&state.conn, path, payload, self.retention) {
+            Ok(seq) => {
+                state.latest_seq = seq;
+                self.new_payload.notify_all();
+            }
+            Err(e) => {
+                error!("Event stream: failed to store payload"; "path" => path, "error" => ?e);
+            }
+        }
+    }
+
+    /// Block until a payload with a sequence number greater than `after` has been published,
+    /// or until `timeout` passes.  Returns the latest sequence number.
+    fn wait_for_payload(&self, after: u64, timeout: Duration) -> u64 {
+        let state = self
+            .state
+            .lock()
+            .expect("FATAL: event stream lock poisoned");
+        let (state, _) = self
+            .new_payload
+            .wait_timeout_while(state, timeout, |state| state.latest_seq <= after)
+            .expect("FATAL: event stream lock poisoned");
+        state.latest_seq
+    }
+
+    fn latest_seq(&self) -> u64 {
+        self.state
+            .lock()
+            .expect("FATAL: event stream lock poisoned")
+            .latest_seq
+    }
+}
+
+/// Which payloads (and which transaction events within them) a client wants
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventStreamFilter {
+    keys: Vec<EventKeyType>,
+}
+
+impl EventStreamFilter {
+    /// Parse a comma-separated list of event keys, in the same format as an event observer's
+    /// `events_keys`.  An empty list subscribes to everything.
+    pub fn from_keys(keys: &str) -> Result<Self, String> {
+        let keys = keys
+            .split(',')
+            .map(|key| key.trim())
+            .filter(|key| !key.is_empty())
+            .map(|key| {
+                EventKeyType::from_string(key).ok_or_else(|| format!("Invalid event key: {key}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Ok(Self::any());
+        }
+        Ok(Self { keys })
+    }
+
+    pub fn any() -> Self {
+        Self {
+            keys: vec![EventKeyType::AnyEvent],
+        }
+    }
+
+    fn has_key(&self, key: &EventKeyType) -> bool {
+        self.keys.contains(&EventKeyType::AnyEvent) || self.keys.contains(key)
+    }
+
+    /// Does a serialized transaction event match one of the STX, asset or contract event keys?
+    /// This mirrors the event dispatcher's dispatch matrix.
+    fn matches_tx_event(&self, event: &serde_json::Value) -> bool {
+        if self.keys.contains(&EventKeyType::AnyEvent) {
+            return true;
+        }
+        let Some(event_type) = event.get("type").and_then(|t| t.as_str()) else {
+            return false;
+        };
+        let event_data = event.get(event_type);
+        match event_type {
+            "stx_transfer_event" | "stx_mint_event" | "stx_burn_event" | "stx_lock_event" => {
+                self.keys.contains(&EventKeyType::STXEvent)
+            }
+            "contract_event" => {
+                let contract_id = event_data
+                    .and_then(|data| data.get("contract_identifier"))
+                    .and_then(|id| id.as_str())
+                    .and_then(|id| QualifiedContractIdentifier::parse(id).ok());
+                let topic = event_data
+                    .and_then(|data| data.get("topic"))
+                    .and_then(|topic| topic.as_str());
+                let (Some(contract_id), Some(topic)) = (contract_id, topic) else {
+                    return false;
+                };
+                self.keys.iter().any(|key| {
+                    matches!(key, EventKeyType::SmartContractEvent((id, name)) if *id == contract_id && name == topic)
+                })
+            }
+            _ => {
+                let Some(asset_id) = event_data
+                    .and_then(|data| data.get("asset_identifier"))
+                    .and_then(|id| id.as_str())
+                else {
+                    return false;
+                };
+                self.keys.iter().any(
+                    |key| matches!(key, EventKeyType::AssetEvent(id) if id.to_string() == asset_id),
+                )
+            }
+        }
+    }
+
+    /// Decide whether or not a client receives this payload, and if so, what it looks like.
+    /// Like HTTP observers, every client receives block and microblock payloads, with their
+    /// `events` filtered to the ones the client subscribed to.
+    pub fn apply(&self, path: &str, payload: &serde_json::Value) -> Option<serde_json::Value> {
+        let wanted = match path {
+            PATH_BURN_BLOCK_SUBMIT => self.has_key(&EventKeyType::BurnchainBlocks),
+            PATH_MEMPOOL_TX_SUBMIT | PATH_MEMPOOL_TX_DROP => {
+                self.has_key(&EventKeyType::MemPoolTransactions)
+            }
+            PATH_STACKERDB_CHUNKS => self.keys.contains(&EventKeyType::StackerDBChunks),
+            PATH_PROPOSAL_RESPONSE => self.keys.contains(&EventKeyType::BlockProposal),
+            PATH_MINED_BLOCK | PATH_MINED_NAKAMOTO_BLOCK => {
+                self.keys.contains(&EventKeyType::MinedBlocks)
+            }
+            PATH_MINED_MICROBLOCK => self.keys.contains(&EventKeyType::MinedMicroblocks),
+            PATH_MICROBLOCK_SUBMIT if !self.has_key(&EventKeyType::Microblocks) => false,
+            PATH_BLOCK_PROCESSED | PATH_MICROBLOCK_SUBMIT => {
+                let mut payload = payload.clone();
+                if let Some(events) = payload.get_mut("events").and_then(|e| e.as_array_mut()) {
+                    events.retain(|event| self.matches_tx_event(event));
+                }
+                return Some(payload);
+            }
+            _ => true,
+        };
+        wanted.then(|| payload.clone())
+    }
+}
+
+/// Format a payload as a server-sent event
+fn format_sse_event(seq: u64, path: &str, payload: &serde_json::Value) -> String {
+    format!("id: {seq}\nevent: {path}\ndata: {payload}\n\n")
+}
+
+/// A parsed stream request
+#[derive(Debug, Clone, PartialEq)]
+struct EventStreamRequest {
+    filter: EventStreamFilter,
+    /// Stream payloads after this sequence number.
+    /// If not given, only payloads published from now on are streamed.
+    after: Option<u64>,
+}
+
+/// Read and parse the client's HTTP request.  Errors are (status code, message) pairs.
+fn read_request(stream: &mut TcpStream) -> Result<EventStreamRequest, (u16, String)> {
+    let mut buf = vec![];
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() > MAX_REQUEST_LEN {
+            return Err((400, "Request too large".into()));
+        }
+        let nread = stream
+            .read(&mut chunk)
+            .map_err(|e| (400, format!("Failed to read request: {e}")))?;
+        if nread == 0 {
+            return Err((400, "Connection closed".into()));
+        }
+        buf.extend_from_slice(&chunk[..nread]);
+    }
+    let request = String::from_utf8_lossy(&buf);
+    parse_request(&request)
+}
+
+fn parse_request(request: &str) -> Result<EventStreamRequest, (u16, String)> {
+    let mut lines = request.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let (Some(verb), Some(target)) = (parts.next(), parts.next()) else {
+        return Err((400, "Malformed request line".into()));
+    };
+    if verb != "GET" {
+        return Err((405, "Method not allowed".into()));
+    }
+    let url = Url::parse(&format!("http://localhost{target}"))
+        .map_err(|e| (400, format!("Malformed request target: {e}")))?;
+    if url.path() != EVENT_STREAM_PATH {
+        return Err((404, "Not found".into()));
+    }
+
+    let mut last_event_id = None;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("last-event-id") {
+                last_event_id = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let mut filter = EventStreamFilter::any();
+    let mut after = last_event_id;
+    for (name, value) in url.query_pairs() {
+        match name.as_ref() {
+            "keys" => filter = EventStreamFilter::from_keys(&value).map_err(|e| (400, e))?,
+            "after" => after = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    let after = after
+        .map(|after| {
+            after
+                .parse::<u64>()
+                .map_err(|_| (400, format!("Invalid sequence number: {after}")))
+        })
+        .transpose()?;
+
+    Ok(EventStreamRequest { filter, after })
+}
+
+fn write_error(stream: &mut TcpStream, status: u16, message: &str) {
+    let reason = match status {
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        410 => "Gone",
+        503 => "Service Unavailable",
+        _ => "Bad Request",
+    };
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{message}",
+        message.len()
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// Serve one client until it disconnects, or until it stops reading or writing for longer
+/// than `CLIENT_IO_TIMEOUT`
+fn handle_client(event_stream: &EventStream, mut stream: TcpStream) -> Result<(), String> {
+    stream
+        .set_read_timeout(Some(CLIENT_IO_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(CLIENT_IO_TIMEOUT)))
+        .map_err(|e| e.to_string())?;
+    let request = match read_request(&mut stream) {
+        Ok(request) => request,
+        Err((status, message)) => {
+            write_error(&mut stream, status, &message);
+            return Err(message);
+        }
+    };
+    let conn = event_stream.connect().map_err(|e| format!("{e:?}"))?;
+    let mut last_seq = match request.after {
+        Some(after) => after,
+        None => event_stream.latest_seq(),
+    };
+
+    let check_retained = |conn: &Connection, last_seq: u64| -> Result<(), String> {
+        let oldest_seq = EventStream::get_oldest_seq(conn).map_err(|e| format!("{e:?}"))?;
+        match oldest_seq {
+            Some(oldest_seq) if oldest_seq > last_seq.saturating_add(1) => Err(format!(
+                "Payloads after {last_seq} are no longer retained; oldest is {oldest_seq}"
+            )),
+            _ => Ok(()),
+        }
+    };
+
+    if let Err(message) = check_retained(&conn, last_seq) {
+        write_error(&mut stream, 410, &message);
+        return Err(message);
+    }
+
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+        )
+        .map_err(|e| e.to_string())?;
+
+    loop {
+        let payloads = EventStream::get_payloads_after(&conn, last_seq, PAYLOAD_BATCH_SIZE)
+            .map_err(|e| format!("{e:?}"))?;
+        if payloads.is_empty() {
+            if event_stream.wait_for_payload(last_seq, KEEPALIVE_INTERVAL) <= last_seq {
+                stream
+                    .write_all(b": keepalive\n\n")
+                    .map_err(|e| e.to_string())?;
+            }
+            continue;
+        }
+        if payloads[0].seq != last_seq.saturating_add(1) {
+            // this client fell behind, and the payloads it needs were pruned
+            check_retained(&conn, last_seq)?;
+        }
+
+        let mut out = String::new();
+        let mut skipped = false;
+        for payload in payloads.iter() {
+            last_seq = payload.seq;
+            match request.filter.apply(&payload.path, &payload.payload) {
+                Some(filtered) => {
+                    out.push_str(&format_sse_event(payload.seq, &payload.path, &filtered));
+                    skipped = false;
+                }
+                None => skipped = true,
+            }
+        }
+        if skipped {
+            // advance the client's last event ID past the filtered payloads, so it does not
+            // re-scan them when it reconnects
+            out.push_str(&format!("id: {last_seq}\n\n"));
+        }
+        stream
+            .write_all(out.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+}
+
+/// Start serving the event stream on `bind`.  Each client gets its own thread.
+pub fn start_event_stream_server(
+    event_stream: Arc<EventStream>,
+    bind: &str,
+) -> Result<thread::JoinHandle<()>, std::io::Error> {
+    let listener = TcpListener::bind(bind)?;
+    info!("Serving event stream"; "addr" => ?listener.local_addr());
+    thread::Builder::new()
+        .name("event-stream".into())
+        .spawn(move || {
+            let num_clients = Arc::new(AtomicUsize::new(0));
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        warn!("Event stream: failed to accept connection: {e:?}");
+                        continue;
+                    }
+                };
+                if num_clients.load(Ordering::SeqCst) >= MAX_CLIENTS {
+                    write_error(&mut stream, 503, "Too many clients");
+                    continue;
+                }
+                num_clients.fetch_add(1, Ordering::SeqCst);
+                let event_stream = event_stream.clone();
+                let num_clients = num_clients.clone();
+                let peer_addr = stream.peer_addr().ok();
+                let res = thread::Builder::new()
+                    .name("event-stream-client".into())
+                    .spawn(move || {
+                        debug!("Event stream: client connected"; "peer" => ?peer_addr);
+                        if let Err(e) = handle_client(&event_stream, stream) {
+                            debug!("Event stream: client disconnected"; "peer" => ?peer_addr, "reason" => %e);
+                        }
+                        num_clients.fetch_sub(1, Ordering::SeqCst);
+                    });
+                if let Err(e) = res {
+                    warn!("Event stream: failed to spawn client thread: {e:?}");
+                }
+            }
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader};
+
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_publish_and_prune() {
+        let dir = tempdir().unwrap();
+        let event_stream = EventStream::new(dir.path(), 3).unwrap();
+        for i in 0..5 {
+            event_stream.publish(PATH_BURN_BLOCK_SUBMIT, &json!({ "burn_block_height": i }));
+        }
+        assert_eq!(event_stream.latest_seq(), 5);
+
+        let conn = event_stream.connect().unwrap();
+        assert_eq!(EventStream::get_oldest_seq(&conn).unwrap(), Some(3));
+        let payloads = EventStream::get_payloads_after(&conn, 0, 10).unwrap();
+        let seqs: Vec<_> = payloads.iter().map(|p| p.seq).collect();
+        assert_eq!(seqs, vec![3, 4, 5]);
+        assert_eq!(payloads[0].path, "new_burn_block");
+        assert_eq!(payloads[0].payload, json!({ "burn_block_height": 2 }));
+
+        let payloads = EventStream::get_payloads_after(&conn, 4, 10).unwrap();
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].seq, 5);
+
+        // sequence numbers survive a restart, even if everything was pruned
+        conn.execute("DELETE FROM stream_payloads", []).unwrap();
+        drop(event_stream);
+        let event_stream = EventStream::new(dir.path(), 3).unwrap();
+        assert_eq!(event_stream.latest_seq(), 5);
+        event_stream.publish(PATH_BURN_BLOCK_SUBMIT, &json!({}));
+        assert_eq!(event_stream.latest_seq(), 6);
+    }
+
+    #[test]
+    fn test_filter() {
+        let stx_event = json!({ "type": "stx_transfer_event", "stx_transfer_event": {} });
+        let contract_event = json!({
+            "type": "contract_event",
+            "contract_event": {
+                "contract_identifier": "ST000000000000000000002AMW42H.pox-4",
+                "topic": "print",
+            }
+        });
+        let ft_event = json!({
+            "type": "ft_transfer_event",
+            "ft_transfer_event": {
+                "asset_identifier": "ST000000000000000000002AMW42H.token::tok",
+            }
+        });
+        let block = json!({ "events": [stx_event, contract_event, ft_event] });
+
+        let any = EventStreamFilter::from_keys("").unwrap();
+        assert_eq!(any, EventStreamFilter::any());
+        assert_eq!(any.apply(PATH_BLOCK_PROCESSED, &block), Some(block.clone()));
+        assert!(any.apply(PATH_BURN_BLOCK_SUBMIT, &json!({})).is_some());
+
+        let filter =
+            EventStreamFilter::from_keys("burn_blocks,ST000000000000000000002AMW42H.pox-4::print")
+                .unwrap();
+        assert!(filter.apply(PATH_BURN_BLOCK_SUBMIT, &json!({})).is_some());
+        assert!(filter.apply(PATH_MEMPOOL_TX_SUBMIT, &json!([])).is_none());
+        assert_eq!(
+            filter.apply(PATH_BLOCK_PROCESSED, &block),
+            Some(json!({ "events": [contract_event] }))
+        );
+
+        let filter =
+            EventStreamFilter::from_keys("stx,ST000000000000000000002AMW42H.token.tok").unwrap();
+        assert_eq!(
+            filter.apply(PATH_BLOCK_PROCESSED, &block),
+            Some(json!({ "events": [stx_event, ft_event] }))
+        );
+        assert!(filter.apply(PATH_BURN_BLOCK_SUBMIT, &json!({})).is_none());
+
+        assert!(EventStreamFilter::from_keys("not-a-key").is_err());
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let request = parse_request(
+            "GET /v1/events?keys=burn_blocks%2Cmemtx&after=12 HTTP/1.1\r\nHost: localhost\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(request.after, Some(12));
+        assert_eq!(
+            request.filter,
+            EventStreamFilter::from_keys("burn_blocks,memtx").unwrap()
+        );
+
+        let request =
+            parse_request("GET /v1/events HTTP/1.1\r\nLast-Event-ID: 34\r\n\r\n").unwrap();
+        assert_eq!(request.after, Some(34));
+        assert_eq!(request.filter, EventStreamFilter::any());
+
+        assert_eq!(
+            parse_request("GET /v1/other HTTP/1.1\r\n\r\n")
+                .unwrap_err()
+                .0,
+            404
+        );
+        assert_eq!(
+            parse_request("POST /v1/events HTTP/1.1\r\n\r\n")
+                .unwrap_err()
+                .0,
+            405
+        );
+        assert_eq!(
+            parse_request("GET /v1/events?after=abc HTTP/1.1\r\n\r\n")
+                .unwrap_err()
+                .0,
+            400
+        );
+    }
+
+    /// Read one server-sent event (without its trailing blank line)
+    fn read_sse_event(reader: &mut impl BufRead) -> Vec<String> {
+        let mut lines = vec![];
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end().to_string();
+            if line.is_empty() {
+                return lines;
+            }
+            lines.push(line);
+        }
+    }
+
+    #[test]
+    fn test_stream_resume() {
+        let dir = tempdir().unwrap();
+        let event_stream = Arc::new(EventStream::new(dir.path(), 100).unwrap());
+        for i in 0..3 {
+            event_stream.publish(PATH_BURN_BLOCK_SUBMIT, &json!({ "burn_block_height": i }));
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        start_event_stream_server(event_stream.clone(), &addr.to_string()).unwrap();
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET /v1/events?keys=burn_blocks HTTP/1.1\r\nLast-Event-ID: 1\r\n\r\n")
+            .unwrap();
+        let mut reader = BufReader::new(client);
+        let preamble = read_sse_event(&mut reader);
+        assert_eq!(preamble[0], "HTTP/1.1 200 OK");
+
+        // resumes after sequence number 1
+        assert_eq!(
+            read_sse_event(&mut reader),
+            vec![
+                "id: 2".to_string(),
+                "event: new_burn_block".to_string(),
+                "data: {\"burn_block_height\":1}".to_string()
+            ]
+        );
+        assert_eq!(read_sse_event(&mut reader)[0], "id: 3");
+
+        // filtered payloads only advance the sequence number
+        event_stream.publish(PATH_MEMPOOL_TX_SUBMIT, &json!([]));
+        assert_eq!(read_sse_event(&mut reader), vec!["id: 4".to_string()]);
+
+        // new payloads are pushed as they are published
+        event_stream.publish(PATH_BURN_BLOCK_SUBMIT, &json!({ "burn_block_height": 3 }));
+        assert_eq!(read_sse_event(&mut reader)[0], "id: 5");
+    }
+
+    #[test]
+    fn test_stream_pruned() {
+        let dir = tempdir().unwrap();
+        let event_stream = Arc::new(EventStream::new(dir.path(), 2).unwrap());
+        for i in 0..5 {
+            event_stream.publish(PATH_BURN_BLOCK_SUBMIT, &json!({ "burn_block_height": i }));
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        start_event_stream_server(event_stream, &addr.to_string()).unwrap();
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET /v1/events?after=1 HTTP/1.1\r\n\r\n")
+            .unwrap();
+        let mut reader = BufReader::new(client);
+        let preamble = read_sse_event(&mut reader);
+        assert_eq!(preamble[0], "HTTP/1.1 410 Gone");
+    }
+}
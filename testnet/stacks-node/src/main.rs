@@ -17,6 +17,7 @@ pub mod monitoring;
 
 pub mod burnchains;
 pub mod event_dispatcher;
+pub mod event_stream;
 pub mod genesis_data;
 pub mod globals;
 pub mod keychain;
@@ -33,6 +34,7 @@ use std::{env, panic, process};
 
 use backtrace::Backtrace;
 use pico_args::Arguments;
+use stacks::burnchains::db::BurnchainDB;
 use stacks::chainstate::burn::db::sortdb::SortitionDB;
 use stacks::chainstate::burn::operations::leader_block_commit::RewardSetInfo;
 use stacks::chainstate::coordinator::{get_next_recipients, OnChainRewardSetProvider};
@@ -41,6 +43,7 @@ use stacks::chainstate::stacks::db::blocks::DummyEventDispatcher;
 use stacks::chainstate::stacks::db::StacksChainState;
 use stacks::config::chain_data::MinerStats;
 pub use stacks::config::{Config, ConfigFile};
+use stacks::config::{EventKeyType, EventObserverConfig};
 #[cfg(not(any(target_os = "macos", target_os = "windows", target_arch = "arm")))]
 use tikv_jemallocator::Jemalloc;
 
@@ -94,6 +97,75 @@ fn cli_pick_best_tip(config_path: &str, at_stacks_height: Option<u64>) -> TipCan
     BlockMinerThread::inner_pick_best_tip(stacks_tips, HashMap::new()).unwrap()
 }
 
+/// Implementation of `replay-events` CLI option
+fn cli_replay_events(
+    config_path: &str,
+    observer: &str,
+    events_keys: &str,
+    start_height: u64,
+    end_height: u64,
+) {
+    info!("Loading config at path {config_path}");
+    let config = match ConfigFile::from_path(config_path) {
+        Ok(config_file) => Config::from_config_file(config_file, true).unwrap(),
+        Err(e) => {
+            warn!("Invalid config file: {e}");
+            process::exit(1);
+        }
+    };
+    let events_keys = events_keys
+        .split(',')
+        .map(|key| {
+            EventKeyType::from_string(key.trim()).unwrap_or_else(|| {
+                warn!("Invalid event key: {key}");
+                process::exit(1);
+            })
+        })
+        .collect();
+
+    // don't share the node's pending payloads database, and don't give up on undelivered
+    // payloads
+    let mut event_dispatcher = EventDispatcher::new();
+    event_dispatcher.register_observer_with_db(
+        &EventObserverConfig {
+            endpoint: observer.to_string(),
+            events_keys,
+            timeout_ms: 10_000,
+            disable_retries: false,
+        },
+        None,
+    );
+
+    let burnchain = config.get_burnchain();
+    let (mut chainstate, _) = StacksChainState::open(
+        config.is_mainnet(),
+        config.burnchain.chain_id,
+        &config.get_chainstate_path_str(),
+        Some(config.node.get_marf_opts()),
+    )
+    .unwrap();
+    let mut sortdb = SortitionDB::open(
+        &config.get_burn_db_file_path(),
+        true,
+        burnchain.pox_constants.clone(),
+    )
+    .unwrap();
+    let burnchain_db = BurnchainDB::open(&burnchain.get_burnchaindb_path(), false).unwrap();
+
+    if let Err(e) = stacks::cli::replay_events(
+        &mut sortdb,
+        &mut chainstate,
+        &burnchain_db,
+        &burnchain,
+        start_height,
+        end_height,
+        &event_dispatcher,
+    ) {
+        warn!("Failed to replay events: {e:?}");
+        process::exit(1);
+    }
+}
+
 /// Implementation of `get_miner_spend` CLI option
 #[allow(clippy::incompatible_msrv)]
 fn cli_get_miner_spend(
@@ -384,6 +456,23 @@ fn main() {
             println!("Best tip is {best_tip:?}");
             process::exit(0);
         }
+        "replay-events" => {
+            let config_path: String = args.value_from_str("--config").unwrap();
+            let observer: String = args.value_from_str("--observer").unwrap();
+            let events_keys: Option<String> = args.opt_value_from_str("--events-keys").unwrap();
+            let start_height: u64 = args.value_from_str("--start-height").unwrap();
+            let end_height: u64 = args.value_from_str("--end-height").unwrap();
+            args.finish();
+
+            cli_replay_events(
+                &config_path,
+                &observer,
+                events_keys.as_deref().unwrap_or("*"),
+                start_height,
+                end_height,
+            );
+            process::exit(0);
+        }
         "get-spend-amount" => {
             let config_path: String = args.value_from_str("--config").unwrap();
             let at_burnchain_height: Option<u64> =
@@ -478,6 +567,16 @@ key-for-seed\tOutput the associated secret key for a burnchain signer created wi
 \t\tCan be passed a config file for the seed via the `--config <file>` option *or* by supplying the hex seed on
 \t\tthe command line directly.
 
+replay-events\tRe-send `new_burn_block` and `new_block` events for a range of burnchain heights to an
+\t\tevent observer, e.g. to rebuild an indexer.
+\t\tThe node should not be running.
+\t\tArguments:
+\t\t  --config: path to the config file
+\t\t  --observer: event observer endpoint, e.g. localhost:3700
+\t\t  --start-height: first burnchain block height to replay
+\t\t  --end-height: last burnchain block height to replay
+\t\t  --events-keys: optional comma-separated event keys, as in `events_keys` (default: \"*\")
+
 replay-mock-mining\tReplay mock mined blocks from <dir>
 \t\tArguments:
 \t\t  --path: path to directory of mock mined blocks
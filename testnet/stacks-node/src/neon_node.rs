@@ -360,6 +360,7 @@ pub(crate) fn open_chainstate_with_faults(
     )?;
 
     chainstate.fault_injection.hide_blocks = config.node.fault_injection_hide_blocks;
+    chainstate.txindex = config.node.txindex;
     Ok(chainstate)
 }
 
@@ -589,7 +590,7 @@ impl MicroblockMinerThread {
             })
             .ok()?;
 
-        let mempool = MemPoolDB::open(
+        let mut mempool = MemPoolDB::open(
             config.is_mainnet(),
             config.burnchain.chain_id,
             &stacks_chainstate_path,
@@ -597,6 +598,7 @@ impl MicroblockMinerThread {
             metric,
         )
         .expect("Database failure opening mempool");
+        mempool.policy = config.make_mempool_policy();
 
         let MinerTip {
             consensus_hash: ch,
@@ -2459,6 +2461,7 @@ impl BlockMinerThread {
             metric,
         )
         .expect("Database failure opening mempool");
+        mem_pool.policy = self.config.make_mempool_policy();
 
         let tenure_begin = get_epoch_time_ms();
 
@@ -2799,7 +2802,7 @@ impl RelayerThread {
             .make_cost_metric()
             .unwrap_or_else(|| Box::new(UnitMetric));
 
-        let mempool = MemPoolDB::open(
+        let mut mempool = MemPoolDB::open(
             is_mainnet,
             chain_id,
             &stacks_chainstate_path,
@@ -2807,6 +2810,7 @@ impl RelayerThread {
             metric,
         )
         .expect("Database failure opening mempool");
+        mempool.policy = config.make_mempool_policy();
 
         let keychain = Keychain::default(config.node.seed.clone());
         let bitcoin_controller = BitcoinRegtestController::new_dummy(config.clone());
@@ -4333,14 +4337,16 @@ impl PeerThread {
             .make_cost_metric()
             .unwrap_or_else(|| Box::new(UnitMetric));
 
-        MemPoolDB::open(
+        let mut mempool = MemPoolDB::open(
             config.is_mainnet(),
             config.burnchain.chain_id,
             &config.get_chainstate_path_str(),
             cost_estimator,
             metric,
         )
-        .expect("Database failure opening mempool")
+        .expect("Database failure opening mempool");
+        mempool.policy = config.make_mempool_policy();
+        mempool
     }
 
     /// Instantiate the p2p thread.
@@ -4640,14 +4646,16 @@ impl StacksNode {
             .make_cost_metric()
             .unwrap_or_else(|| Box::new(UnitMetric));
 
-        MemPoolDB::open(
+        let mut mempool = MemPoolDB::open(
             config.is_mainnet(),
             config.burnchain.chain_id,
             &config.get_chainstate_path_str(),
             cost_estimator,
             metric,
         )
-        .expect("BUG: failed to instantiate mempool")
+        .expect("BUG: failed to instantiate mempool");
+        mempool.policy = config.make_mempool_policy();
+        mempool
     }
 
     /// Set up the Peer DB and update any soft state from the config file. This includes:
@@ -4721,9 +4729,20 @@ impl StacksNode {
                 )
                 .unwrap();
             }
+            for onion_address in config.node.bootstrap_onion_addresses.iter() {
+                PeerDB::set_onion_address(&tx, onion_address).unwrap();
+            }
             tx.commit().unwrap();
         }
 
+        if !config.node.bootstrap_onion_addresses.is_empty()
+            && config.connection_options.socks5_proxy.is_none()
+        {
+            warn!(
+                "Onion bootstrap nodes cannot be reached without connection_options.socks5_proxy"
+            );
+        }
+
         if !config.node.deny_nodes.is_empty() {
             warn!("Will ignore nodes {:?}", &config.node.deny_nodes);
         }
@@ -4744,16 +4763,21 @@ impl StacksNode {
             tx.commit().unwrap();
         }
 
-        // update services to indicate we can support mempool sync and stackerdb
+        // update services to indicate we can support mempool sync (including by set
+        // reconciliation), stackerdb, compact Nakamoto block relay, and onion addresses, as well
+        // as encrypted transport if it's enabled
         {
+            let mut services = (ServiceFlags::RPC as u16)
+                | (ServiceFlags::RELAY as u16)
+                | (ServiceFlags::STACKERDB as u16)
+                | (ServiceFlags::COMPACT_NAKAMOTO_BLOCKS as u16)
+                | (ServiceFlags::MEMPOOL_SKETCH as u16)
+                | (ServiceFlags::ONION_ADDRESSES as u16);
+            if config.connection_options.encrypt_transport {
+                services |= ServiceFlags::ENCRYPTED_TRANSPORT as u16;
+            }
             let tx = peerdb.tx_begin().unwrap();
-            PeerDB::set_local_services(
-                &tx,
-                (ServiceFlags::RPC as u16)
-                    | (ServiceFlags::RELAY as u16)
-                    | (ServiceFlags::STACKERDB as u16),
-            )
-            .unwrap();
+            PeerDB::set_local_services(&tx, services).unwrap();
             tx.commit().unwrap();
         }
 
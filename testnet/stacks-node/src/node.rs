@@ -227,6 +227,7 @@ fn spawn_peer(
                     continue;
                 }
             };
+            mem_pool.policy = config.make_mempool_policy();
 
             let indexer = make_bitcoin_indexer(&config, None);
 
@@ -342,6 +343,13 @@ impl Node {
         for observer in &config.events_observers {
             event_dispatcher.register_observer(observer, config.get_working_dir());
         }
+        if let Some(bind) = config.node.event_stream_bind.as_ref() {
+            event_dispatcher.register_event_stream(
+                bind,
+                config.node.event_stream_retention,
+                config.get_working_dir(),
+            );
+        }
 
         let burnchain_config = config.get_burnchain();
 
@@ -685,7 +693,7 @@ impl Node {
             .make_cost_metric()
             .unwrap_or_else(|| Box::new(UnitMetric));
 
-        let mem_pool = MemPoolDB::open(
+        let mut mem_pool = MemPoolDB::open(
             self.config.is_mainnet(),
             self.config.burnchain.chain_id,
             &self.chain_state.root_path,
@@ -693,6 +701,7 @@ impl Node {
             metric,
         )
         .expect("FATAL: failed to open mempool");
+        mem_pool.policy = self.config.make_mempool_policy();
 
         // Construct the coinbase transaction - 1st txn that should be handled and included in
         // the upcoming tenure.
@@ -83,7 +83,7 @@ impl BootRunLoop {
                 InnerLoops::Epoch2(neon),
             )
         } else {
-            let naka = NakaRunLoop::new(config.clone(), None, None, None);
+            let naka = NakaRunLoop::new(config.clone(), None, None, None, None);
             (
                 naka.get_coordinator_channel().unwrap(),
                 InnerLoops::Epoch3(naka),
@@ -164,6 +164,7 @@ impl BootRunLoop {
         let data_to_naka = neon_loop.start(burnchain_opt.clone(), mine_start);
 
         let monitoring_thread = neon_loop.take_monitoring_thread();
+        let event_stream = neon_loop.get_event_dispatcher().get_event_stream();
         // did we exit because of the epoch-3.0 transition, or some other reason?
         let exited_for_transition = boot_thread
             .join()
@@ -184,6 +185,7 @@ impl BootRunLoop {
             Some(termination_switch),
             Some(counters),
             monitoring_thread,
+            event_stream,
         );
         let new_coord_channels = naka
             .get_coordinator_channel()
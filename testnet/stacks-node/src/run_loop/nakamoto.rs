@@ -37,6 +37,7 @@ use stacks_common::util::{get_epoch_time_secs, sleep_ms};
 use stx_genesis::GenesisData;
 
 use crate::burnchains::make_bitcoin_indexer;
+use crate::event_stream::EventStream;
 use crate::globals::Globals as GenericGlobals;
 use crate::monitoring::{start_serving_monitoring_metrics, MonitoringError};
 use crate::nakamoto_node::{self, StacksNode, BLOCK_PROCESSOR_STACK_SIZE, RELAYER_MAX_BUFFER};
@@ -82,6 +83,7 @@ impl RunLoop {
         should_keep_running: Option<Arc<AtomicBool>>,
         counters: Option<Counters>,
         monitoring_thread: Option<JoinHandle<Result<(), MonitoringError>>>,
+        event_stream: Option<Arc<EventStream>>,
     ) -> Self {
         let channels = CoordinatorCommunication::instantiate();
         let should_keep_running =
@@ -95,6 +97,19 @@ impl RunLoop {
         for observer in config.events_observers.iter() {
             event_dispatcher.register_observer(observer, config.get_working_dir());
         }
+        if let Some(bind) = config.node.event_stream_bind.as_ref() {
+            // the event stream is only served once per process; if this run loop takes over
+            // from the epoch 2.x run loop, it keeps publishing to that run loop's stream.
+            if let Some(event_stream) = event_stream {
+                event_dispatcher.attach_event_stream(bind, event_stream, config.get_working_dir());
+            } else {
+                event_dispatcher.register_event_stream(
+                    bind,
+                    config.node.event_stream_retention,
+                    config.get_working_dir(),
+                );
+            }
+        }
 
         Self {
             config,
@@ -252,7 +267,7 @@ impl RunLoop {
             get_bulk_initial_names: Some(Box::new(move || get_names(use_test_genesis_data))),
         };
 
-        let (chain_state_db, receipts) = StacksChainState::open_and_exec(
+        let (mut chain_state_db, receipts) = StacksChainState::open_and_exec(
             self.config.is_mainnet(),
             self.config.burnchain.chain_id,
             &self.config.get_chainstate_path_str(),
@@ -260,6 +275,7 @@ impl RunLoop {
             Some(self.config.node.get_marf_opts()),
         )
         .unwrap();
+        chain_state_db.txindex = self.config.node.txindex;
         run_loop::announce_boot_receipts(
             &mut self.event_dispatcher,
             &chain_state_db,
@@ -278,6 +278,13 @@ impl RunLoop {
         for observer in config.events_observers.iter() {
             event_dispatcher.register_observer(observer, config.get_working_dir());
         }
+        if let Some(bind) = config.node.event_stream_bind.as_ref() {
+            event_dispatcher.register_event_stream(
+                bind,
+                config.node.event_stream_retention,
+                config.get_working_dir(),
+            );
+        }
 
         Self {
             config,
@@ -585,7 +592,7 @@ impl RunLoop {
         };
 
         info!("About to call open_and_exec");
-        let (chain_state_db, receipts) = StacksChainState::open_and_exec(
+        let (mut chain_state_db, receipts) = StacksChainState::open_and_exec(
             self.config.is_mainnet(),
             self.config.burnchain.chain_id,
             &self.config.get_chainstate_path_str(),
@@ -593,6 +600,7 @@ impl RunLoop {
             Some(self.config.node.get_marf_opts()),
         )
         .unwrap();
+        chain_state_db.txindex = self.config.node.txindex;
         run_loop::announce_boot_receipts(
             &mut self.event_dispatcher,
             &chain_state_db,
@@ -1553,6 +1553,7 @@ fn regr_use_block_header_pk() {
             db_path: None,
             timeout: Duration::from_secs(120),
             disable_retries: false,
+            stream: None,
         })
         .collect();
 
@@ -7604,7 +7605,7 @@ fn block_validation_pending_table() {
     let signer_keys = signer_test
         .signer_configs
         .iter()
-        .map(|c| StacksPublicKey::from_private(&c.stacks_private_key))
+        .map(|c| c.stacks_public_key)
         .collect::<Vec<_>>();
     wait_for_block_rejections_from_signers(30, &block.header.signer_signature_hash(), &signer_keys)
         .expect("Timed out waiting for block rejections");